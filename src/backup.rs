@@ -1,15 +1,122 @@
+use crate::delta;
+use crate::digest::DigestAlgorithm;
 use crate::error::{MutxError, Result};
+use crate::housekeep::{prune_backups, timestamped_backups};
+use crate::utils::path::resolve_best_effort;
+use crate::utils::xattr::{
+    copy_xattr, get_xattr, set_xattr, BACKUP_SHA256_XATTR, BACKUP_SOURCE_XATTR, COMPRESSION_XATTR,
+};
+use crate::utils::{apply_nofollow, ensure_within, to_nfc, unique_temp_path, verify_not_link};
+use crate::write::engine::is_out_of_space;
+use crate::write::StageDir;
 use chrono::Local;
-use std::fs;
-use std::path::PathBuf;
-use tracing::debug;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tracing::{debug, warn};
+
+/// Suffix used for backups unless overridden on the command line or in the
+/// configuration file
+pub const DEFAULT_BACKUP_SUFFIX: &str = ".mutx.backup";
+
+/// Suffix written by releases before backups were namespaced with `.mutx`.
+/// Housekeep still recognizes timestamped backups with it when cleaning with
+/// the default suffix.
+pub const LEGACY_BACKUP_SUFFIX: &str = ".backup";
+
+/// A backup filename suffix, validated when constructed (see
+/// [`validate_backup_suffix`])
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub struct BackupSuffix(String);
+
+impl BackupSuffix {
+    /// Validate `suffix`, rejecting an empty suffix or a single dot
+    pub fn new(suffix: impl Into<String>) -> Result<Self> {
+        let suffix = suffix.into();
+        validate_backup_suffix(&suffix)?;
+        Ok(BackupSuffix(suffix))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for BackupSuffix {
+    /// [`DEFAULT_BACKUP_SUFFIX`]
+    fn default() -> Self {
+        BackupSuffix(DEFAULT_BACKUP_SUFFIX.to_string())
+    }
+}
+
+impl AsRef<str> for BackupSuffix {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for BackupSuffix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for BackupSuffix {
+    type Err = MutxError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        BackupSuffix::new(s)
+    }
+}
+
+impl TryFrom<String> for BackupSuffix {
+    type Error = MutxError;
+
+    fn try_from(suffix: String) -> Result<Self> {
+        BackupSuffix::new(suffix)
+    }
+}
+
+/// What became of the backup a write asked for, as recorded in
+/// [`crate::WriteReport::backup`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum BackupOutcome {
+    /// The replaced content was backed up to `path`
+    Created { path: PathBuf },
+    /// The backup failed and the write went ahead without it (best effort)
+    Failed { error: String },
+}
+
+impl BackupOutcome {
+    /// The backup file, if one was created
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            BackupOutcome::Created { path } => Some(path),
+            BackupOutcome::Failed { .. } => None,
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct BackupConfig {
     pub source: PathBuf,
-    pub suffix: String,
+    pub suffix: BackupSuffix,
     pub directory: Option<PathBuf>,
     pub timestamp: bool,
+    /// When copying fails because the filesystem or quota is full, remove
+    /// the oldest timestamped backups of the same source, keeping this many
+    /// of the newest, and retry once. `None` fails straight away.
+    pub prune_on_enospc: Option<usize>,
+    /// Once the backup is made, rewrite the timestamped backup before it as
+    /// a delta against it (see [`crate::delta`]). Only useful with
+    /// `timestamp`, since the newest backup must keep its name.
+    pub delta: bool,
 }
 
 /// Validate that a backup suffix is safe to use
@@ -31,9 +138,6 @@ pub fn validate_backup_suffix(suffix: &str) -> Result<()> {
 
 /// Create a backup of the specified file using atomic operations
 pub fn create_backup(config: &BackupConfig) -> Result<PathBuf> {
-    // Validate suffix before creating backup
-    validate_backup_suffix(&config.suffix)?;
-
     let source = &config.source;
 
     // Verify source exists
@@ -46,7 +150,12 @@ pub fn create_backup(config: &BackupConfig) -> Result<PathBuf> {
     }
 
     // Generate backup filename
-    let backup_path = generate_backup_path(config)?;
+    let location = BackupLocation::of(source, config.directory.as_deref())?;
+    let backup_path = generate_backup_path(config, &location)?;
+
+    // The name is built from the source filename and suffix; make sure neither
+    // can steer the backup (or its temp stage) out of the backup directory
+    ensure_within(&backup_path, &location.dir)?;
 
     // Ensure backup directory exists
     if let Some(parent) = backup_path.parent() {
@@ -56,19 +165,59 @@ pub fn create_backup(config: &BackupConfig) -> Result<PathBuf> {
         })?;
     }
 
+    // The backup the new one supersedes, to store as a delta against it
+    let previous = if config.delta {
+        timestamped_backups(source, config.suffix.as_str(), config.directory.as_deref())?
+            .pop()
+            .map(|(_, path)| path)
+            .filter(|path| *path != backup_path)
+    } else {
+        None
+    };
+
     debug!(
         "Creating atomic backup: {} -> {}",
         source.display(),
         backup_path.display()
     );
 
-    // Atomic backup using copy-to-temp + rename strategy
-    let temp_backup = backup_path.with_extension("tmp");
+    // Atomic backup using copy-to-temp + rename strategy. The temp name is
+    // unique, so concurrent backups never share one; temps left by a crash
+    // are removed by `housekeep backups`.
+    let temp_backup = unique_temp_path(&backup_path);
 
-    // Copy to temporary file
-    fs::copy(source, &temp_backup).map_err(|e| MutxError::BackupFailed {
-        path: source.clone(),
-        source: e,
+    // Copy to temporary file, making room once if the disk is full and
+    // pruning is allowed
+    let owner = location.source.as_deref();
+    let copied = match copy_to_temp(source, &temp_backup, owner) {
+        Err(MutxError::Io(e)) if is_out_of_space(&e) => match config.prune_on_enospc {
+            Some(keep_newest) => match prune_backups(
+                source,
+                config.suffix.as_str(),
+                config.directory.as_deref(),
+                keep_newest,
+            ) {
+                Ok(freed) if freed > 0 => {
+                    warn!(
+                        "Out of space backing up {}; pruned {} bytes of older backups and retrying",
+                        source.display(),
+                        freed
+                    );
+                    copy_to_temp(source, &temp_backup, owner)
+                }
+                // Nothing to free, or pruning failed: report the original error
+                _ => Err(MutxError::Io(e)),
+            },
+            None => Err(MutxError::Io(e)),
+        },
+        result => result,
+    };
+    copied.map_err(|e| match e {
+        MutxError::Io(e) => MutxError::BackupFailed {
+            path: source.clone(),
+            source: e,
+        },
+        other => other,
     })?;
 
     // Atomically rename temp to final backup name
@@ -81,33 +230,225 @@ pub fn create_backup(config: &BackupConfig) -> Result<PathBuf> {
         }
     })?;
 
+    // Make the rename durable too, or a crash could lose the backup on a
+    // backup directory that is not the target's filesystem
+    StageDir::for_target(&backup_path)
+        .and_then(|dir| dir.sync())
+        .map_err(|e| MutxError::BackupFailed {
+            path: source.clone(),
+            source: e,
+        })?;
+
     debug!("Backup created: {}", backup_path.display());
+
+    // The backup is safely made; a delta that cannot be stored only costs
+    // space, so it never fails the backup
+    if let Some(previous) = previous {
+        if let Err(e) = delta::store_as_delta(&previous, &backup_path) {
+            warn!("Keeping {} as a full backup: {}", previous.display(), e);
+        }
+    }
+
     Ok(backup_path)
 }
 
-fn generate_backup_path(config: &BackupConfig) -> Result<PathBuf> {
-    let filename = config
-        .source
-        .file_name()
-        .ok_or_else(|| MutxError::Other("Invalid source filename".to_string()))?
-        .to_string_lossy();
+/// Copy `source` into a freshly created temp file without following links,
+/// recording `owner` as the file it is a backup of.
+///
+/// `fs::copy` follows a symlink planted at the destination, so the temp file is
+/// created exclusively with [`apply_nofollow`] instead.
+fn copy_to_temp(source: &Path, temp: &Path, owner: Option<&Path>) -> Result<()> {
+    let mut opts = OpenOptions::new();
+    opts.write(true).create_new(true);
+    apply_nofollow(&mut opts);
 
-    let backup_name = if config.timestamp {
-        let timestamp = Local::now().format("%Y%m%d_%H%M%S");
-        format!("{}.{}{}", filename, timestamp, config.suffix)
-    } else {
-        format!("{}{}", filename, config.suffix)
-    };
+    let mut dest = opts.open(temp).map_err(MutxError::Io)?;
+    let result = verify_not_link(&dest, temp, |path| MutxError::SymlinkNotAllowed { path })
+        .and_then(|_| {
+            let mut src = File::open(source).map_err(MutxError::Io)?;
+            let mut hasher = DigestAlgorithm::Sha256.hasher();
+            let mut buffer = [0u8; 8192];
+            loop {
+                let n = src.read(&mut buffer).map_err(MutxError::Io)?;
+                if n == 0 {
+                    break;
+                }
+                hasher.update(&buffer[..n]);
+                dest.write_all(&buffer[..n]).map_err(MutxError::Io)?;
+            }
+            let permissions = src.metadata().map_err(MutxError::Io)?.permissions();
+            dest.set_permissions(permissions).map_err(MutxError::Io)?;
+            // Checked by `mutx backups check`; a filesystem without extended
+            // attributes only leaves the backup without them
+            if let Err(e) = set_xattr(&dest, BACKUP_SHA256_XATTR, hasher.finish_hex().as_bytes())
+                .and_then(|()| copy_xattr(source, &dest, COMPRESSION_XATTR))
+            {
+                debug!("Cannot record the checksum of {}: {}", temp.display(), e);
+            }
+            if let Some(owner) = owner {
+                let owner = to_nfc(&owner.to_string_lossy());
+                if let Err(e) = set_xattr(&dest, BACKUP_SOURCE_XATTR, owner.as_bytes()) {
+                    debug!("Cannot record the source of {}: {}", temp.display(), e);
+                }
+            }
+            // A backup may be the only surviving copy (see restore), so make
+            // it durable before it is renamed into place
+            dest.sync_all().map_err(MutxError::Io)?;
+            Ok(())
+        });
 
-    let backup_path = if let Some(dir) = &config.directory {
-        dir.join(backup_name)
-    } else {
-        config
-            .source
-            .parent()
-            .ok_or_else(|| MutxError::Other("Source file has no parent directory".to_string()))?
-            .join(backup_name)
+    if result.is_err() {
+        let _ = fs::remove_file(temp);
+    }
+    result
+}
+
+/// Length of the source path hash naming backups in a backup directory
+const NAMESPACE_HASH_LEN: usize = 16;
+
+/// Where the backups of a file go, and the names they are taken under.
+///
+/// Beside the source they are named after it, as they are in a backup
+/// directory, which holds files from anywhere. There, each backup records
+/// the canonical path of its source (`user.mutx.source`). A file whose name
+/// is already taken by the backups of a same-named file from another
+/// directory has its backups named `{filename}.{hash}` instead, after a
+/// hash of its canonical path, so the two never restore, prune or delta
+/// against each other's backups. Backups that record no source (taken by
+/// older releases, or on filesystems without extended attributes) belong
+/// to every file of their name.
+#[derive(Debug, Clone)]
+pub(crate) struct BackupLocation {
+    pub dir: PathBuf,
+    /// Name new backups start with
+    pub name: String,
+    filename: String,
+    /// Canonical path of the source, in a backup directory
+    source: Option<PathBuf>,
+    namespaced: String,
+}
+
+impl BackupLocation {
+    pub(crate) fn of(source: &Path, directory: Option<&Path>) -> Result<Self> {
+        let filename = source
+            .file_name()
+            .map(|n| to_nfc(&n.to_string_lossy()))
+            .ok_or_else(|| MutxError::Other("Invalid source filename".to_string()))?;
+
+        let Some(dir) = directory else {
+            let dir = match source.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+                _ => PathBuf::from("."),
+            };
+            return Ok(BackupLocation {
+                dir,
+                name: filename.clone(),
+                namespaced: filename.clone(),
+                filename,
+                source: None,
+            });
+        };
+
+        let canonical = PathBuf::from(to_nfc(&resolve_best_effort(source)?.to_string_lossy()));
+        let hash = format!(
+            "{:x}",
+            Sha256::digest(canonical.to_string_lossy().as_bytes())
+        );
+        let namespaced = format!("{}.{}", filename, &hash[..NAMESPACE_HASH_LEN]);
+        let mut location = BackupLocation {
+            dir: dir.to_path_buf(),
+            name: filename.clone(),
+            filename,
+            source: Some(canonical),
+            namespaced,
+        };
+        if location.name_taken() {
+            location.name = location.namespaced.clone();
+        }
+        Ok(location)
+    }
+
+    /// Whether the backup at `path`, named `{base}{timestamp}{suffix}`, is
+    /// one of this file's
+    pub(crate) fn owns(&self, path: &Path, base: &str) -> bool {
+        if base == self.namespaced {
+            return true;
+        }
+        base == self.filename
+            && match (&self.source, recorded_source(path)) {
+                (Some(source), Some(recorded)) => recorded == *source,
+                _ => true,
+            }
+    }
+
+    /// Whether a backup in the directory under the plain file name records
+    /// another file as its source
+    fn name_taken(&self) -> bool {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return false;
+        };
+        entries.flatten().any(|entry| {
+            let name = to_nfc(&entry.file_name().to_string_lossy());
+            let Some(rest) = name.strip_prefix(self.filename.as_str()) else {
+                return false;
+            };
+            if is_namespaced(rest) {
+                return false;
+            }
+            // A longer name of its own (`{filename}x.mutx.backup`) records
+            // a source with that other name
+            match (&self.source, recorded_source(&entry.path())) {
+                (Some(source), Some(recorded)) => {
+                    recorded != *source
+                        && recorded.file_name().map(|n| to_nfc(&n.to_string_lossy()))
+                            == Some(self.filename.clone())
+                }
+                _ => false,
+            }
+        })
+    }
+}
+
+/// Whether the rest of a backup name after the file name starts with a
+/// namespace hash (`.{hash}`), which a timestamp (`.YYYYMMDD_HHMMSS`) never
+/// looks like
+fn is_namespaced(rest: &str) -> bool {
+    let Some(rest) = rest.strip_prefix('.') else {
+        return false;
     };
+    let hex = rest.bytes().take_while(u8::is_ascii_hexdigit).count();
+    hex == NAMESPACE_HASH_LEN
+}
+
+/// The source a backup records, see [`BackupLocation`]
+fn recorded_source(backup: &Path) -> Option<PathBuf> {
+    let value = get_xattr(backup, BACKUP_SOURCE_XATTR).ok()??;
+    Some(PathBuf::from(String::from_utf8(value).ok()?))
+}
+
+fn generate_backup_path(config: &BackupConfig, location: &BackupLocation) -> Result<PathBuf> {
+    let (dir, filename) = (&location.dir, &location.name);
+
+    if !config.timestamp {
+        return Ok(dir.join(format!("{}{}", filename, config.suffix)));
+    }
+
+    // A backup taken earlier in the same second is never replaced: a delta
+    // may be stored against it. Later ones are numbered `-1`, `-2`, ... The
+    // target's lock serializes its backups, so the name cannot be taken
+    // between this check and the rename.
+    let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
+    let mut backup_path = dir.join(format!("{}.{}{}", filename, timestamp, config.suffix));
+    let mut sequence = 0u32;
+    while fs::symlink_metadata(&backup_path).is_ok() {
+        sequence = sequence
+            .checked_add(1)
+            .ok_or_else(|| MutxError::Other("Too many backups in one second".to_string()))?;
+        backup_path = dir.join(format!(
+            "{}.{}-{}{}",
+            filename, timestamp, sequence, config.suffix
+        ));
+    }
 
     Ok(backup_path)
 }
@@ -124,12 +465,15 @@ mod tests {
 
         let config = BackupConfig {
             source,
-            suffix: ".mutx.backup".to_string(),
+            suffix: BackupSuffix::default(),
             directory: None,
             timestamp: false,
+            prune_on_enospc: None,
+            delta: false,
         };
 
-        let path = generate_backup_path(&config).unwrap();
+        let location = BackupLocation::of(&config.source, config.directory.as_deref()).unwrap();
+        let path = generate_backup_path(&config, &location).unwrap();
         assert_eq!(
             path.file_name().unwrap().to_str().unwrap(),
             "test.txt.mutx.backup"
@@ -144,12 +488,23 @@ mod tests {
 
         let config = BackupConfig {
             source,
-            suffix: ".mutx.backup".to_string(),
+            suffix: BackupSuffix::default(),
             directory: Some(backup_dir.clone()),
             timestamp: false,
+            prune_on_enospc: None,
+            delta: false,
         };
 
-        let path = generate_backup_path(&config).unwrap();
+        let location = BackupLocation::of(&config.source, config.directory.as_deref()).unwrap();
+        let path = generate_backup_path(&config, &location).unwrap();
         assert_eq!(path.parent().unwrap(), backup_dir);
     }
+
+    #[test]
+    fn test_backup_suffix_validated_on_construction() {
+        assert_eq!(BackupSuffix::new(".bak").unwrap().as_str(), ".bak");
+        assert!(BackupSuffix::new("").is_err());
+        assert!(".".parse::<BackupSuffix>().is_err());
+        assert_eq!(BackupSuffix::default().as_str(), DEFAULT_BACKUP_SUFFIX);
+    }
 }
@@ -1,5 +1,21 @@
+use crate::cli::emit_env::EnvTarget;
+use crate::cli::style::ColorChoice;
+use crate::cli::webhook::WebhookUrl;
 use clap::{Parser, Subcommand};
+use mutx::lock::scope::ScopePolicy;
+use mutx::lock::LockFallback;
+use mutx::parse::{parse_duration, parse_size};
+use mutx::{
+    AgeSource, BackupSuffix, Compression, CompressionFormat, CriticalSection, DigestAlgorithm,
+    FileMode, Guarantee, LockBackend, ModePolicy, SharedGroup,
+};
+use std::ffi::OsString;
 use std::path::PathBuf;
+use std::time::Duration;
+
+fn parse_lock_hash_len(s: &str) -> Result<usize, String> {
+    mutx::lock::parse_hash_len(s).map_err(|e| e.to_string())
+}
 
 #[derive(Parser, Debug)]
 #[command(
@@ -16,14 +32,135 @@ pub struct Args {
     #[arg(value_name = "OUTPUT")]
     pub output: Option<PathBuf>,
 
+    /// Keep mutx state (journals, manifests) in DIR instead of the platform
+    /// state directory; sets MUTX_STATE_DIR. Locks stay in the cache directory
+    #[arg(long, value_name = "DIR")]
+    pub state_dir: Option<PathBuf>,
+
+    /// Where locks go when the lock cache directory is unavailable (no home
+    /// directory, DynamicUser): tmp (/tmp/mutx-<uid>, then beside the file;
+    /// default), adjacent (beside the file), or none (fail). Sets
+    /// MUTX_LOCK_FALLBACK
+    #[arg(long, value_name = "POLICY")]
+    pub lock_fallback: Option<LockFallback>,
+
+    /// When to color output: auto (only on terminals, unless NO_COLOR is
+    /// set), always, or never
+    #[arg(long, value_name = "WHEN", default_value = "auto")]
+    pub color: ColorChoice,
+
+    #[command(flatten)]
+    pub write: WriteArgs,
+}
+
+impl Args {
+    /// Whether the command prints JSON to stdout, so a failure should be
+    /// reported there as JSON too
+    pub fn json_output(&self) -> bool {
+        match &self.command {
+            None => self.write.json,
+            Some(Command::Write { args, .. }) => args.json,
+            Some(Command::Housekeep { operation }) => match operation {
+                HousekeepOperation::Locks { json, .. }
+                | HousekeepOperation::Backups { json, .. }
+                | HousekeepOperation::Temps { json, .. }
+                | HousekeepOperation::Run { json, .. }
+                | HousekeepOperation::All { json, .. } => *json,
+                HousekeepOperation::Daemon { .. } => false,
+            },
+            Some(Command::Status { json, .. }) => *json,
+            Some(_) => false,
+        }
+    }
+}
+
+/// Options shared by the implicit (`mutx OUTPUT`) and explicit (`mutx write OUTPUT`) forms
+#[derive(clap::Args, Debug, Clone)]
+pub struct WriteArgs {
     /// Read from file instead of stdin
     #[arg(short, long, value_name = "FILE")]
     pub input: Option<PathBuf>,
 
+    /// Allow --input to name a character device or FIFO, such as
+    /// /dev/urandom, and read it until it ends
+    #[arg(long, requires = "input")]
+    pub input_allow_special: bool,
+
+    /// Treat OUTPUT as a directory and write OUTPUT/<input file name>, like cp
+    #[arg(long, requires = "input")]
+    pub into_dir: bool,
+
     /// Use streaming mode (constant memory)
-    #[arg(long)]
+    #[arg(long, conflicts_with = "in_memory")]
     pub stream: bool,
 
+    /// Hold all input in memory until commit instead of spilling large
+    /// inputs to disk
+    #[arg(long)]
+    pub in_memory: bool,
+
+    /// Transform the content before writing (lf, trailing-newline); repeat
+    /// or separate with commas to chain, applied in order
+    #[arg(long = "transform", value_name = "NAME", value_delimiter = ',')]
+    pub transforms: Vec<String>,
+
+    /// Compress the written file: gzip or zstd, optionally with a level
+    /// (gzip:9, zstd:19). Applied after any --transform
+    #[arg(long, value_name = "FORMAT[:LEVEL]")]
+    pub compress: Option<Compression>,
+
+    /// Decompress the input (gzip or zstd) before writing
+    #[arg(long, value_name = "FORMAT")]
+    pub decompress: Option<CompressionFormat>,
+
+    /// Also write OUTPUT.sha256 (sha256sum format) under the same lock
+    #[arg(long, value_name = "ALGORITHM")]
+    pub emit_digest: Option<DigestAlgorithm>,
+
+    /// Also write a detached signature of OUTPUT under the same lock:
+    /// OUTPUT.minisig for a minisign secret key, OUTPUT.sig for an OpenSSH key
+    #[arg(long, value_name = "KEY")]
+    pub sign: Option<PathBuf>,
+
+    /// Publish the content over file descriptor FD (e.g. 3 with 3>&1)
+    /// instead of replacing OUTPUT, framed so the reader applies it only
+    /// once complete. OUTPUT still names the write and its lock
+    #[cfg(all(feature = "fd-sink", unix))]
+    #[arg(
+        long,
+        value_name = "FD",
+        conflicts_with_all = [
+            "backup", "emit_digest", "sign", "emit_env", "passthrough_special", "min_free",
+            "respect_readonly", "collaborative", "reclaim_on_enospc",
+        ]
+    )]
+    pub sink_fd: Option<i32>,
+
+    /// Permissions of the written file: preserve (the replaced file's mode,
+    /// default), umask (as a newly created file), or octal such as 0640
+    #[arg(long = "mode", value_name = "MODE")]
+    pub file_mode: Option<ModePolicy>,
+
+    /// Give the file the umask default instead of the replaced file's mode
+    /// (same as --mode umask)
+    #[arg(long, conflicts_with = "file_mode")]
+    pub no_preserve_mode: bool,
+
+    /// If the filesystem fills up while writing or backing up, delete the
+    /// oldest timestamped backups of OUTPUT, never the newest, to make room,
+    /// and retry
+    #[arg(long = "reclaim-backups-on-enospc")]
+    pub reclaim_on_enospc: bool,
+
+    /// Fail instead of replacing a read-only output file
+    #[arg(long)]
+    pub respect_readonly: bool,
+
+    /// Share the output, lock file and backups with GROUP (name or GID):
+    /// set their group and g+rw, and make backup directories setgid
+    #[arg(long, value_name = "GROUP")]
+    pub collaborative: Option<SharedGroup>,
+
     /// Fail immediately if locked (default: wait)
     #[arg(long)]
     pub no_wait: bool,
@@ -41,10 +178,132 @@ pub struct Args {
     #[arg(long, value_name = "MILLISECONDS", requires = "timeout")]
     pub max_poll_interval: Option<u64>,
 
+    /// Take over the lock if the process recorded as holding it no longer
+    /// runs on this host (flock backend only)
+    #[arg(long)]
+    pub break_stale_locks: bool,
+
+    /// Take over the lock at once if its recorded holder is an earlier run
+    /// of this same command line, by the same user in the same directory,
+    /// that no longer runs (flock backend only)
+    #[arg(long)]
+    pub reclaim_own: bool,
+
+    /// Hold the lock under a lease of this length (e.g. "30s"), refreshed
+    /// while the write runs; waiters take over a lock whose lease expired
+    /// (flock backend only)
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub lock_lease: Option<Duration>,
+
+    /// Wait for the lock in arrival order, queued behind earlier --fair
+    /// waiters instead of racing them
+    #[arg(long, conflicts_with = "no_wait")]
+    pub fair: bool,
+
+    /// Never show the spinner printed on a terminal while waiting for the lock
+    #[arg(long)]
+    pub no_spinner: bool,
+
+    /// Warn once the lock wait exceeds this long (e.g. "10s") and keep
+    /// waiting; only --timeout gives up
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, conflicts_with = "no_wait")]
+    pub warn_after: Option<Duration>,
+
+    /// Also POST the --warn-after warning as JSON (mutx.lock_wait.v1) to
+    /// this http:// URL
+    #[arg(long, value_name = "URL", requires = "warn_after")]
+    pub warn_webhook: Option<WebhookUrl>,
+
+    /// Write directly to OUTPUT under the lock if it is a special file (a
+    /// FIFO, a device, /dev/stdout), which cannot be replaced atomically
+    #[arg(long)]
+    pub passthrough_special: bool,
+
+    /// Keep retrying to replace OUTPUT for this long (e.g. "10s", default
+    /// 2s) while another process has it open without delete sharing, as
+    /// Windows editors and scanners do; 0 fails at once
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub rename_retry: Option<Duration>,
+
+    /// Take no lock at all, only replace OUTPUT atomically, for callers that
+    /// exclude other writers themselves. Nothing is created in the lock
+    /// cache and the write is reported as not exclusive
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "no_wait", "timeout", "fair", "break_stale_locks", "reclaim_own", "lock_lease",
+            "warn_after", "lock_file", "lock_hash_len", "lock_beside", "lock_mode",
+            "strict_locking", "collaborative",
+        ]
+    )]
+    pub no_lock: bool,
+
+    /// How much of the write holds the lock: full (read input, back up and
+    /// commit; default), backup-and-commit (read input first), or
+    /// commit-only (lock only the rename; the backup may predate it)
+    #[arg(long, value_name = "SECTION", default_value = "full")]
+    pub critical_section: CriticalSection,
+
     /// Custom lock file location
     #[arg(long, value_name = "PATH")]
     pub lock_file: Option<PathBuf>,
 
+    /// Require custom lock files to live inside DIR and use a .lock extension
+    #[arg(long, value_name = "DIR", requires = "lock_file")]
+    pub lock_root: Option<PathBuf>,
+
+    /// Hex characters of the path hash in derived lock names (8-64, or "full")
+    #[arg(long, value_name = "LEN", value_parser = parse_lock_hash_len, conflicts_with = "lock_file")]
+    pub lock_hash_len: Option<usize>,
+
+    /// Lock OUTPUT.lock in OUTPUT's directory instead of a file in the lock
+    /// cache, visible to other tools and shared by every user who can write
+    /// there
+    #[arg(long, conflicts_with_all = ["lock_file", "lock_hash_len"])]
+    pub lock_beside: bool,
+
+    /// Permissions of the lock file, e.g. 0666 so other users can lock it in
+    /// a shared directory (default: lock_mode from the config file, else the
+    /// umask default)
+    #[arg(long, value_name = "MODE")]
+    pub lock_mode: Option<FileMode>,
+
+    /// When the lock cache and OUTPUT are on different filesystems in a container:
+    /// warn (default), adjacent (lock beside OUTPUT), or ignore
+    #[arg(long, value_name = "POLICY", default_value = "warn")]
+    pub lock_scope: ScopePolicy,
+
+    /// Locking mechanism: flock (default), ofd (Linux open file description
+    /// locks), dotlock (OUTPUT.lock, compatible with dotlockfile/procmail),
+    /// atomic-create (OUTPUT.lock created exclusively, for NFS), target
+    /// (flock on OUTPUT itself, like flock(1)) or remote (lockd, mutx
+    /// daemon or Redis server, requires the cluster feature)
+    #[arg(long, value_name = "BACKEND", default_value = "flock")]
+    pub lock_backend: LockBackend,
+
+    /// Lock server for --lock-backend remote: the address (HOST:PORT) of a
+    /// lockd server, unix:[PATH] for the socket of a mutx daemon (default
+    /// path if omitted), or a redis://[[USER]:PASSWORD@]HOST[:PORT][/DB] URL
+    /// (requires the redis feature; REDISCLI_AUTH supplies the password
+    /// instead, keeping it out of the process list)
+    #[cfg(feature = "cluster")]
+    #[arg(long, value_name = "ADDR")]
+    pub lock_server: Option<String>,
+
+    /// Key to lock on the server (default: canonical OUTPUT path)
+    #[cfg(feature = "cluster")]
+    #[arg(long, value_name = "KEY", requires = "lock_server")]
+    pub lock_key: Option<String>,
+
+    /// Store the lease's fencing token in the user.mutx.fencing_token xattr of OUTPUT
+    #[cfg(feature = "cluster")]
+    #[arg(long, requires = "lock_server")]
+    pub fencing_xattr: bool,
+
+    /// Fail instead of locking on filesystems where flock may not exclude other writers
+    #[arg(long)]
+    pub strict_locking: bool,
+
     /// Follow symbolic links for output files
     #[arg(long)]
     pub follow_symlinks: bool,
@@ -54,20 +313,25 @@ pub struct Args {
     #[arg(long)]
     pub follow_lock_symlinks: bool,
 
+    /// Write a symlinked output to the file it points to, keeping the link
+    /// (the default with --follow-symlinks; implies --follow-symlinks)
+    #[arg(long, conflicts_with = "replace_symlink")]
+    pub write_through_symlink: bool,
+
+    /// Replace a symlinked output with a regular file, leaving its old
+    /// target untouched (implies --follow-symlinks)
+    #[arg(long)]
+    pub replace_symlink: bool,
+
     /// Create backup before overwrite
     #[arg(short = 'b', long)]
     pub backup: bool,
 
-    /// Backup filename suffix
-    #[arg(
-        long,
-        value_name = "SUFFIX",
-        default_value = ".mutx.backup",
-        requires = "backup"
-    )]
-    pub backup_suffix: String,
+    /// Backup filename suffix (default: .mutx.backup, or backup_suffix from the config file)
+    #[arg(long, value_name = "SUFFIX", requires = "backup")]
+    pub backup_suffix: Option<BackupSuffix>,
 
-    /// Store backups in directory
+    /// Store backups in directory (default: beside OUTPUT, or backup_dir from the config file)
     #[arg(long, value_name = "DIR", requires = "backup")]
     pub backup_dir: Option<PathBuf>,
 
@@ -75,11 +339,82 @@ pub struct Args {
     #[arg(long, requires = "backup")]
     pub backup_timestamp: bool,
 
+    /// Store the previous timestamped backup as a delta against the new one,
+    /// keeping only the newest backup in full; restore rebuilds older ones
+    #[arg(long, requires = "backup_timestamp", conflicts_with = "collaborative")]
+    pub backup_delta: bool,
+
+    /// Abort the write if the backup cannot be made (default)
+    #[arg(long, requires = "backup", conflicts_with = "backup_best_effort")]
+    pub backup_required: bool,
+
+    /// Warn and write anyway if the backup cannot be made; the JSON report
+    /// records the failure
+    #[arg(long, requires = "backup")]
+    pub backup_best_effort: bool,
+
+    /// Send systemd keepalive notifications while waiting for the lock and writing
+    #[arg(long)]
+    pub notify_systemd: bool,
+
+    /// Fail instead of writing when any of these guarantees would be degraded
+    /// (comma-separated: atomic, durable, exclusive)
+    #[arg(long, value_name = "GUARANTEES", value_delimiter = ',')]
+    pub require: Vec<Guarantee>,
+
+    /// Fail without replacing OUTPUT if the content exceeds SIZE (e.g. 10M, 1GiB)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub max_size: Option<u64>,
+
+    /// Fail without replacing OUTPUT if less than SIZE would remain free on its filesystem
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub min_free: Option<u64>,
+
+    /// Print a JSON report of the write to stdout
+    #[arg(long)]
+    pub json: bool,
+
+    /// Print shell-evaluable MUTX_OUTPUT, MUTX_LOCK_PATH, MUTX_BACKUP_PATH and
+    /// MUTX_CHANGED assignments, to stdout or to file descriptor FD
+    /// (--emit-env=3)
+    #[arg(
+        long,
+        value_name = "FD",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "stdout"
+    )]
+    pub emit_env: Option<EnvTarget>,
+
     /// Verbose output
     #[arg(short = 'v', action = clap::ArgAction::Count)]
     pub verbose: u8,
 }
 
+#[derive(Subcommand, Debug)]
+pub enum BackupsOperation {
+    /// Verify that every backup of FILE can be restored: it reads in full,
+    /// matches the checksum recorded when it was taken, and content mutx
+    /// compressed decodes to its end
+    Check {
+        /// File whose backups to check
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Backup filename suffix (default: .mutx.backup, or backup_suffix from the config file)
+        #[arg(long, value_name = "SUFFIX")]
+        backup_suffix: Option<BackupSuffix>,
+
+        /// Directory holding backups (default: next to FILE, or backup_dir from the config file)
+        #[arg(long, value_name = "DIR")]
+        backup_dir: Option<PathBuf>,
+
+        /// Print a JSON report (schema mutx.backup_check.v1) instead of one line per backup
+        #[arg(long)]
+        json: bool,
+    },
+}
+
 #[derive(Subcommand, Debug)]
 pub enum HousekeepOperation {
     /// Clean orphaned lock files from cache directory
@@ -99,14 +434,23 @@ pub enum HousekeepOperation {
 
         #[arg(short = 'v', long)]
         verbose: bool,
+
+        /// Print a JSON report (schema mutx.housekeep.v1) instead of the summary
+        #[arg(long)]
+        json: bool,
     },
 
     /// Clean old backup files
     Backups {
-        /// Directory to clean (default: current directory)
+        /// Directory to clean (default: backup_dir from the config file if it
+        /// exists, otherwise the current directory)
         #[arg(value_name = "DIR")]
         dir: Option<PathBuf>,
 
+        /// Clean both the configured backup_dir and the current directory
+        #[arg(long, conflicts_with = "dir")]
+        everywhere: bool,
+
         #[arg(short = 'r', long)]
         recursive: bool,
 
@@ -116,9 +460,96 @@ pub enum HousekeepOperation {
         #[arg(long, value_name = "N")]
         keep_newest: Option<usize>,
 
-        /// Backup suffix to match (default: .mutx.backup)
-        #[arg(long, value_name = "SUFFIX", default_value = ".mutx.backup")]
-        suffix: String,
+        /// Then remove the oldest timestamped backups until all backups fit in SIZE (e.g. 500M)
+        #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+        max_total_size: Option<u64>,
+
+        /// Backup suffix to match (default: .mutx.backup, or backup_suffix from the config file)
+        #[arg(long, value_name = "SUFFIX")]
+        suffix: Option<BackupSuffix>,
+
+        /// Where backup age comes from: name (embedded timestamp, default), mtime, or newest
+        #[arg(long, value_name = "SOURCE", default_value = "name")]
+        age_source: AgeSource,
+
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+
+        #[arg(short = 'v', long)]
+        verbose: bool,
+
+        /// Print a JSON report (schema mutx.housekeep.v1) instead of the summary
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Clean temp files left by interrupted writes and backups
+    Temps {
+        /// Directory to clean (default: current directory)
+        #[arg(value_name = "DIR")]
+        dir: Option<PathBuf>,
+
+        #[arg(short = 'r', long)]
+        recursive: bool,
+
+        /// Only remove temps untouched for this long, sparing writes in progress
+        #[arg(long, value_name = "DURATION", default_value = "1h")]
+        older_than: String,
+
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+
+        #[arg(short = 'v', long)]
+        verbose: bool,
+
+        /// Print a JSON report (schema mutx.housekeep.v1) instead of the summary
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Apply the retention rules of a policy file listing any number of
+    /// directories, once
+    Run {
+        /// Policy file (TOML) (default: housekeep.toml next to the config file)
+        #[arg(long, value_name = "FILE")]
+        config: Option<PathBuf>,
+
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+
+        #[arg(short = 'v', long)]
+        verbose: bool,
+
+        /// Print a JSON report (schema mutx.housekeep.v1) instead of the summary
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Keep applying the retention rules of a policy file on a schedule,
+    /// for hosts or containers without cron or systemd timers
+    Daemon {
+        /// Policy file (TOML) listing the lock, backup and temp directories
+        /// to clean
+        #[arg(long, value_name = "FILE")]
+        config: PathBuf,
+
+        /// Time between passes (default: interval from the policy, or 1h)
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+        interval: Option<Duration>,
+
+        /// Upper bound of the random delay added to each interval (default:
+        /// jitter from the policy, or a tenth of the interval)
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+        jitter: Option<Duration>,
+
+        /// JSON file rewritten after every pass, for health checks (default:
+        /// heartbeat from the policy)
+        #[arg(long, value_name = "FILE")]
+        heartbeat: Option<PathBuf>,
+
+        /// Make a single pass and exit, failing if any rule failed
+        #[arg(long)]
+        once: bool,
 
         #[arg(short = 'n', long)]
         dry_run: bool,
@@ -150,15 +581,27 @@ pub enum HousekeepOperation {
         #[arg(long, value_name = "N")]
         keep_newest: Option<usize>,
 
-        /// Backup suffix to match (default: .mutx.backup)
-        #[arg(long, value_name = "SUFFIX", default_value = ".mutx.backup")]
-        suffix: String,
+        /// Then remove the oldest timestamped backups until all backups fit in SIZE (e.g. 500M)
+        #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+        max_total_size: Option<u64>,
+
+        /// Backup suffix to match (default: .mutx.backup, or backup_suffix from the config file)
+        #[arg(long, value_name = "SUFFIX")]
+        suffix: Option<BackupSuffix>,
+
+        /// Where backup age comes from: name (embedded timestamp, default), mtime, or newest
+        #[arg(long, value_name = "SOURCE", default_value = "name")]
+        age_source: AgeSource,
 
         #[arg(short = 'n', long)]
         dry_run: bool,
 
         #[arg(short = 'v', long)]
         verbose: bool,
+
+        /// Print a JSON report (schema mutx.housekeep.v1) instead of the summary
+        #[arg(long)]
+        json: bool,
     },
 }
 
@@ -170,73 +613,398 @@ pub enum Command {
         #[arg(value_name = "OUTPUT")]
         output: PathBuf,
 
-        /// Read from file instead of stdin
-        #[arg(short, long, value_name = "FILE")]
-        input: Option<PathBuf>,
+        #[command(flatten)]
+        args: Box<WriteArgs>,
+    },
 
-        /// Use streaming mode (constant memory)
+    /// Clean up lock files and backups
+    Housekeep {
+        #[command(subcommand)]
+        operation: HousekeepOperation,
+    },
+
+    /// Inspect the backups of a file
+    Backups {
+        #[command(subcommand)]
+        operation: BackupsOperation,
+    },
+
+    /// Restore a file from one of its backups
+    Restore {
+        /// File to restore
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Backup to restore from (default: the newest backup of FILE)
+        #[arg(long, value_name = "BACKUP")]
+        from: Option<PathBuf>,
+
+        /// Back up the current FILE before restoring over it (default)
+        #[arg(long, overrides_with = "no_backup_current")]
+        backup_current: bool,
+
+        /// Do not back up the current FILE before restoring over it
         #[arg(long)]
-        stream: bool,
+        no_backup_current: bool,
+
+        /// Backup filename suffix, for finding backups and capturing the current FILE
+        /// (default: .mutx.backup, or backup_suffix from the config file)
+        #[arg(long, value_name = "SUFFIX")]
+        backup_suffix: Option<BackupSuffix>,
+
+        /// Directory holding backups (default: next to FILE, or backup_dir from the config file)
+        #[arg(long, value_name = "DIR")]
+        backup_dir: Option<PathBuf>,
+
+        /// Custom lock file location (must match the one used by writers)
+        #[arg(long, value_name = "PATH")]
+        lock_file: Option<PathBuf>,
+
+        /// Use the lock FILE.lock beside FILE, for writers using --lock-beside
+        #[arg(long, conflicts_with = "lock_file")]
+        lock_beside: bool,
+
+        /// Permissions of the lock file, as given to writers with --lock-mode
+        /// (default: lock_mode from the config file)
+        #[arg(long, value_name = "MODE")]
+        lock_mode: Option<FileMode>,
+
+        /// Fail immediately if FILE is locked
+        #[arg(long, conflicts_with = "timeout")]
+        no_wait: bool,
+
+        /// Lock acquisition timeout in milliseconds
+        #[arg(short = 't', long, value_name = "MILLISECONDS")]
+        timeout: Option<u64>,
 
-        /// Fail immediately if locked (default: wait)
+        /// Never show the spinner printed on a terminal while waiting for the lock
         #[arg(long)]
+        no_spinner: bool,
+
+        /// Verbose output
+        #[arg(short = 'v', action = clap::ArgAction::Count)]
+        verbose: u8,
+    },
+
+    /// Run a lock server for --lock-backend remote clients
+    #[cfg(feature = "cluster")]
+    Lockd {
+        /// Address to listen on
+        #[arg(long, value_name = "ADDR", default_value = "127.0.0.1:7878")]
+        listen: String,
+    },
+
+    /// Run a lock server for the processes of this host on a Unix socket,
+    /// for --lock-backend remote --lock-server unix:[PATH]
+    #[cfg(all(feature = "cluster", unix))]
+    Daemon {
+        /// Socket to listen on (default: $XDG_RUNTIME_DIR/mutx/daemon.sock)
+        #[arg(long, value_name = "PATH")]
+        socket: Option<PathBuf>,
+    },
+
+    /// Run every check a write of FILE makes before it locks (input, symlink
+    /// policy, lock path, permissions, free space, backups, guarantees)
+    /// without writing or locking anything. Takes the options of write.
+    Check {
+        /// File a write would replace
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        #[command(flatten)]
+        args: Box<WriteArgs>,
+    },
+
+    /// Diagnose the locking environment for a target path
+    Doctor {
+        /// File or directory to check (default: current directory)
+        #[arg(value_name = "PATH")]
+        path: Option<PathBuf>,
+    },
+
+    /// Print a file to stdout while holding a shared lock, so it is never
+    /// read mid-write. Any number of readers can hold the lock at once.
+    Read {
+        /// File to read
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Custom lock file location (must match the one used by writers)
+        #[arg(long, value_name = "PATH")]
+        lock_file: Option<PathBuf>,
+
+        /// Use the lock FILE.lock beside FILE, for writers using --lock-beside
+        #[arg(long, conflicts_with = "lock_file")]
+        lock_beside: bool,
+
+        /// Permissions of the lock file, as given to writers with --lock-mode
+        /// (default: lock_mode from the config file)
+        #[arg(long, value_name = "MODE")]
+        lock_mode: Option<FileMode>,
+
+        /// Fail immediately if a writer holds the lock
+        #[arg(long, conflicts_with = "timeout")]
         no_wait: bool,
 
-        /// Wait timeout in milliseconds (implies wait mode)
-        #[arg(
-            short = 't',
-            long,
-            value_name = "MILLISECONDS",
-            conflicts_with = "no_wait"
-        )]
+        /// Lock acquisition timeout in milliseconds
+        #[arg(short = 't', long, value_name = "MILLISECONDS")]
         timeout: Option<u64>,
 
-        /// Maximum polling interval in milliseconds (default: 1000)
-        #[arg(long, value_name = "MILLISECONDS", requires = "timeout")]
-        max_poll_interval: Option<u64>,
+        /// Wait for the lock in arrival order, queued with --fair writers
+        #[arg(long, conflicts_with = "no_wait")]
+        fair: bool,
+
+        /// Never show the spinner printed on a terminal while waiting for the lock
+        #[arg(long)]
+        no_spinner: bool,
+
+        /// Verbose output
+        #[arg(short = 'v', action = clap::ArgAction::Count)]
+        verbose: u8,
+    },
+
+    /// Show whether FILE is locked and by whom, without taking the lock
+    Status {
+        /// File whose lock to probe
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
 
-        /// Custom lock file location
+        /// Custom lock file location (must match the one used by writers)
         #[arg(long, value_name = "PATH")]
         lock_file: Option<PathBuf>,
 
-        /// Follow symbolic links for output files
+        /// Probe the lock FILE.lock beside FILE, for writers using --lock-beside
+        #[arg(long, conflicts_with = "lock_file")]
+        lock_beside: bool,
+
+        /// Print the status as JSON (schema mutx.lock_status.v1)
         #[arg(long)]
-        follow_symlinks: bool,
+        json: bool,
+    },
 
-        /// Follow symbolic links even for lock files (implies --follow-symlinks)
-        /// WARNING: May be a security risk
+    /// Wait until the lock of FILE is free, without writing anything.
+    /// Exits 0 once it is, or 2 on timeout
+    Wait {
+        /// File whose lock to wait for
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Custom lock file location (must match the one used by writers)
+        #[arg(long, value_name = "PATH")]
+        lock_file: Option<PathBuf>,
+
+        /// Wait for the lock FILE.lock beside FILE, for writers using --lock-beside
+        #[arg(long, conflicts_with = "lock_file")]
+        lock_beside: bool,
+
+        /// Give up after this long (e.g. "30s"; default: wait indefinitely)
+        #[arg(short = 't', long, value_name = "DURATION", value_parser = parse_duration)]
+        timeout: Option<Duration>,
+
+        /// Take the lock and release it at once instead of watching it, so
+        /// the wait queues with writers like a write would
         #[arg(long)]
-        follow_lock_symlinks: bool,
-
-        /// Create backup before overwrite
-        #[arg(short = 'b', long)]
-        backup: bool,
-
-        /// Backup filename suffix
-        #[arg(
-            long,
-            value_name = "SUFFIX",
-            default_value = ".mutx.backup",
-            requires = "backup"
-        )]
-        backup_suffix: String,
-
-        /// Store backups in directory
-        #[arg(long, value_name = "DIR", requires = "backup")]
-        backup_dir: Option<PathBuf>,
+        acquire: bool,
+    },
+
+    /// Run a command while holding the lock of FILE, like flock(1), and
+    /// exit with the command's exit code
+    Exec {
+        /// File whose lock to hold
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Command to run, and its arguments
+        #[arg(value_name = "COMMAND", last = true, required = true)]
+        command: Vec<OsString>,
+
+        /// Custom lock file location (must match the one used by writers)
+        #[arg(long, value_name = "PATH")]
+        lock_file: Option<PathBuf>,
+
+        /// Use the lock FILE.lock beside FILE, for writers using --lock-beside
+        #[arg(long, conflicts_with = "lock_file")]
+        lock_beside: bool,
+
+        /// Permissions of the lock file, as given to writers with --lock-mode
+        /// (default: lock_mode from the config file)
+        #[arg(long, value_name = "MODE")]
+        lock_mode: Option<FileMode>,
 
-        /// Add timestamp to backup filename
-        #[arg(long, requires = "backup")]
-        backup_timestamp: bool,
+        /// Take the lock shared, excluding writers but not readers or other
+        /// shared holders
+        #[arg(short = 's', long)]
+        shared: bool,
+
+        /// Fail immediately if FILE is locked
+        #[arg(short = 'n', long, conflicts_with = "timeout")]
+        no_wait: bool,
+
+        /// Lock acquisition timeout in milliseconds
+        #[arg(short = 't', long, value_name = "MILLISECONDS")]
+        timeout: Option<u64>,
+
+        /// Exit with CODE when the lock cannot be taken (default: 2), so a
+        /// busy lock can be told apart from the command's own failures
+        #[arg(short = 'E', long, value_name = "CODE")]
+        conflict_exit_code: Option<u8>,
+
+        /// Never show the spinner printed on a terminal while waiting for the lock
+        #[arg(long)]
+        no_spinner: bool,
 
         /// Verbose output
         #[arg(short = 'v', action = clap::ArgAction::Count)]
         verbose: u8,
     },
 
-    /// Clean up lock files and backups
-    Housekeep {
+    /// Verify the detached signature written by --sign
+    VerifySignature {
+        /// Signed file
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Public key: a minisign public key or an OpenSSH public key
+        #[arg(long, short = 'k', value_name = "PUBKEY")]
+        key: PathBuf,
+
+        /// Signature file (default: FILE.minisig or FILE.sig, by key type)
+        #[arg(long, value_name = "SIGNATURE")]
+        signature: Option<PathBuf>,
+    },
+
+    /// Hold the lock of FILE until SIGTERM or `mutx unlock FILE`, across
+    /// several commands, or inspect mutx lock files
+    #[command(args_conflicts_with_subcommands = true, arg_required_else_help = true)]
+    Lock {
         #[command(subcommand)]
-        operation: HousekeepOperation,
+        operation: Option<LockOperation>,
+
+        #[command(flatten)]
+        hold: HoldArgs,
+    },
+
+    /// Release a lock held by `mutx lock`, by asking its process to exit
+    Unlock {
+        /// File whose lock to release
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Custom lock file location, as given to `mutx lock`
+        #[arg(long, value_name = "PATH")]
+        lock_file: Option<PathBuf>,
+
+        /// Release FILE.lock beside FILE, as taken with `mutx lock --lock-beside`
+        #[arg(long, conflicts_with = "lock_file")]
+        lock_beside: bool,
+
+        /// Break a wedged lock whose holder is gone (a crashed host, a killed
+        /// process) instead of signalling a `mutx lock` holder. Refused while
+        /// the holder is known to be running; the old lock file is kept as
+        /// LOCK.broken-<MILLIS>
+        #[arg(long)]
+        force: bool,
+
+        /// Break the lock without asking for confirmation
+        #[arg(short = 'y', long, requires = "force")]
+        yes: bool,
+    },
+
+    /// Describe the JSON documents mutx prints and writes
+    Schema {
+        #[command(subcommand)]
+        operation: SchemaOperation,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SchemaOperation {
+    /// Print the JSON Schema of one format, or of all of them keyed by name
+    Dump {
+        /// Format to print, e.g. mutx.write.v1 (default: all)
+        #[arg(value_name = "NAME")]
+        name: Option<String>,
+    },
+}
+
+/// Options of `mutx lock FILE`
+#[derive(clap::Args, Debug, Clone)]
+pub struct HoldArgs {
+    /// File whose lock to hold
+    #[arg(value_name = "FILE")]
+    pub file: Option<PathBuf>,
+
+    /// Custom lock file location (must match the one used by writers)
+    #[arg(long, value_name = "PATH")]
+    pub lock_file: Option<PathBuf>,
+
+    /// Hold the lock FILE.lock beside FILE, for writers using --lock-beside
+    #[arg(long, conflicts_with = "lock_file")]
+    pub lock_beside: bool,
+
+    /// Permissions of the lock file, as given to writers with --lock-mode
+    /// (default: lock_mode from the config file)
+    #[arg(long, value_name = "MODE")]
+    pub lock_mode: Option<FileMode>,
+
+    /// Fail immediately if FILE is locked
+    #[arg(long, conflicts_with = "timeout")]
+    pub no_wait: bool,
+
+    /// Lock acquisition timeout in milliseconds
+    #[arg(short = 't', long, value_name = "MILLISECONDS")]
+    pub timeout: Option<u64>,
+
+    /// Hold the lock from a background process and exit once it is
+    /// acquired, printing that process's PID
+    #[arg(long)]
+    pub background: bool,
+
+    /// Run as the process started by --background: print only the PID
+    #[arg(long, hide = true, conflicts_with = "background")]
+    pub detached: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LockOperation {
+    /// Print the lock file path mutx derives for FILE
+    Path {
+        /// File whose lock path to print
+        #[arg(value_name = "FILE", required_unless_present = "print_algorithm")]
+        file: Option<PathBuf>,
+
+        /// Describe the lock naming algorithm instead, for reimplementation in other tools
+        #[arg(long)]
+        print_algorithm: bool,
+
+        /// Hex characters of the path hash in derived lock names (8-64, or "full")
+        #[arg(long, value_name = "LEN", value_parser = parse_lock_hash_len)]
+        lock_hash_len: Option<usize>,
+
+        /// Print FILE.lock beside FILE, the lock used with --lock-beside
+        #[arg(long, conflicts_with_all = ["lock_hash_len", "print_algorithm"])]
+        lock_beside: bool,
+    },
+
+    /// Show which process holds (or last failed to release) the lock of FILE
+    Holder {
+        /// File whose lock to inspect
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Lock file to inspect instead of the derived one
+        #[arg(long, value_name = "PATH")]
+        lock_file: Option<PathBuf>,
+
+        /// Inspect FILE.lock beside FILE, for writers using --lock-beside
+        #[arg(long, conflicts_with = "lock_file")]
+        lock_beside: bool,
+    },
+
+    /// Summarize the lock cache: location, size, oldest lock, held vs orphaned
+    CacheInfo {
+        /// Directory to inspect (default: platform lock cache directory)
+        #[arg(value_name = "DIR")]
+        dir: Option<PathBuf>,
     },
 }
@@ -0,0 +1,102 @@
+use crate::cli::style::{self, Style};
+use crate::cli::{resolve_backup_dir, resolve_backup_suffix};
+use mutx::delta;
+use mutx::schema::{self, BackupCheckReport, BackupCheckResult, CheckStatus, Versioned};
+use mutx::{list_backups, verify_backup, BackupSuffix, MutxError, Result, VerifiedBackup};
+use std::path::{Path, PathBuf};
+
+pub fn execute_backups_check(
+    file: PathBuf,
+    backup_suffix: Option<BackupSuffix>,
+    backup_dir: Option<PathBuf>,
+    json: bool,
+) -> Result<()> {
+    let backup_suffix = resolve_backup_suffix(backup_suffix)?;
+    let backup_dir = resolve_backup_dir(backup_dir)?;
+
+    let backups = list_backups(&file, backup_suffix.as_str(), backup_dir.as_deref())?;
+    if backups.is_empty() {
+        return Err(MutxError::Other(format!(
+            "No backups of {} found",
+            file.display()
+        )));
+    }
+
+    let results: Vec<BackupCheckResult> = backups.iter().map(|path| check_backup(path)).collect();
+    let failed = results
+        .iter()
+        .filter(|result| result.status == CheckStatus::Fail)
+        .count();
+    let report = BackupCheckReport {
+        file: file.clone(),
+        passed: failed == 0,
+        backups: results,
+    };
+
+    if json {
+        println!(
+            "{}",
+            Versioned::new(schema::BACKUP_CHECK, &report).to_json()?
+        );
+    } else {
+        for result in &report.backups {
+            let tag = match result.status {
+                CheckStatus::Fail => style::tag(Style::Fail, "FAIL"),
+                _ => style::tag(Style::Ok, "ok"),
+            };
+            println!("{} {}: {}", tag, result.path.display(), result.detail);
+        }
+    }
+
+    if failed > 0 {
+        return Err(MutxError::Other(format!(
+            "{} of {} backups of {} failed the check",
+            failed,
+            report.backups.len(),
+            file.display()
+        )));
+    }
+    Ok(())
+}
+
+fn check_backup(path: &Path) -> BackupCheckResult {
+    match verify_backup(path) {
+        Ok(verified) => BackupCheckResult {
+            path: path.to_path_buf(),
+            status: CheckStatus::Pass,
+            delta: Some(verified.delta),
+            size: Some(verified.size),
+            compression: verified.decoded.map(|(format, _)| format.to_string()),
+            detail: describe(&verified),
+        },
+        Err(e) => BackupCheckResult {
+            path: path.to_path_buf(),
+            status: CheckStatus::Fail,
+            delta: delta::is_delta(path).ok(),
+            size: None,
+            compression: None,
+            detail: e.to_string(),
+        },
+    }
+}
+
+/// What was verified of a backup that passed
+fn describe(verified: &VerifiedBackup) -> String {
+    let mut detail = if verified.delta {
+        format!(
+            "delta, rebuilds to {} bytes matching its recorded SHA-256",
+            verified.size
+        )
+    } else if verified.checksum {
+        format!("{} bytes, matching its recorded SHA-256", verified.size)
+    } else {
+        format!(
+            "{} bytes, read in full; no checksum recorded",
+            verified.size
+        )
+    };
+    if let Some((format, decoded)) = verified.decoded {
+        detail.push_str(&format!("; {}, decodes to {} bytes", format, decoded));
+    }
+    detail
+}
@@ -0,0 +1,484 @@
+//! `mutx check FILE`: every validation a write of FILE performs before it
+//! locks, run without writing, locking or creating anything.
+//!
+//! Each check is run on its own and reported as pass, warn, fail or skip,
+//! so CI can vet a deployment target ahead of the deploy window. A check
+//! that fails is a write that would fail; a warning is a write that would
+//! go ahead less safely than it could.
+
+use crate::cli::lock_location::{existing_dir, parent_dir, LockLocation};
+use crate::cli::style::{self, Style};
+use crate::cli::write_command::{normalize_output, resolve_output, validate_input};
+use crate::cli::{resolve_backup_dir, resolve_backup_suffix, WriteArgs};
+use mutx::dirs::can_create;
+use mutx::lock::propagation::check_lock_propagation;
+use mutx::parse::format_size;
+use mutx::schema::{self, CheckReport, CheckResult, CheckStatus, Versioned};
+use mutx::utils::disk::available_space;
+use mutx::utils::resolve_symlink_target;
+use mutx::write::{is_readonly, is_special_file};
+use mutx::{
+    check_lock_symlink, check_symlink, validate_lock_path, AtomicWriter, FileLock, LockBackend,
+    LockStatus, MutxError, Result, SigningKey, SymlinkPolicy, WriteMode,
+};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Default)]
+struct Checks {
+    results: Vec<CheckResult>,
+}
+
+impl Checks {
+    fn push(&mut self, name: &str, status: CheckStatus, detail: impl ToString) {
+        self.results.push(CheckResult {
+            name: name.to_string(),
+            status,
+            detail: detail.to_string(),
+        });
+    }
+
+    /// Record the outcome of a check that fails with an error
+    fn record(&mut self, name: &str, result: Result<String>) {
+        match result {
+            Ok(detail) => self.push(name, CheckStatus::Pass, detail),
+            Err(e) => self.push(name, CheckStatus::Fail, e),
+        }
+    }
+
+    fn failed(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|check| check.status == CheckStatus::Fail)
+            .count()
+    }
+}
+
+pub fn execute_check(file: PathBuf, args: WriteArgs) -> Result<()> {
+    let json = args.json;
+    let checks = run_checks(&file, args);
+
+    let failed = checks.failed();
+    let report = CheckReport {
+        file: file.clone(),
+        passed: failed == 0,
+        checks: checks.results,
+    };
+    if json {
+        println!("{}", Versioned::new(schema::CHECK, &report).to_json()?);
+    } else {
+        for check in &report.checks {
+            let tag = match check.status {
+                CheckStatus::Pass => style::tag(Style::Ok, "ok"),
+                CheckStatus::Warn => style::tag(Style::Warn, "warn"),
+                CheckStatus::Fail => style::tag(Style::Fail, "FAIL"),
+                CheckStatus::Skip => style::tag(Style::Dim, "skip"),
+            };
+            println!("{} {}: {}", tag, check.name, check.detail);
+        }
+    }
+
+    if failed > 0 {
+        return Err(MutxError::Other(format!(
+            "{} of {} checks failed for {}",
+            failed,
+            report.checks.len(),
+            file.display()
+        )));
+    }
+    Ok(())
+}
+
+/// Run every check of a write of `file` with `args`, in the order the write
+/// performs them. Checks needing the target are skipped if it is unusable.
+fn run_checks(file: &Path, args: WriteArgs) -> Checks {
+    let WriteArgs {
+        input,
+        input_allow_special,
+        into_dir,
+        sign,
+        respect_readonly,
+        reclaim_on_enospc,
+        passthrough_special,
+        no_lock,
+        lock_file,
+        lock_root,
+        lock_hash_len,
+        lock_beside,
+        lock_backend,
+        #[cfg(feature = "cluster")]
+        lock_server,
+        lock_scope,
+        strict_locking,
+        follow_symlinks,
+        follow_lock_symlinks,
+        write_through_symlink,
+        replace_symlink,
+        backup,
+        backup_suffix,
+        backup_dir,
+        require,
+        max_size,
+        min_free,
+        ..
+    } = args;
+    let mut checks = Checks::default();
+
+    let follow_symlinks_effective =
+        follow_lock_symlinks || follow_symlinks || write_through_symlink || replace_symlink;
+    let symlink_policy = if replace_symlink {
+        SymlinkPolicy::Replace
+    } else {
+        SymlinkPolicy::WriteThrough
+    };
+
+    match &input {
+        Some(input) => checks.record(
+            "input",
+            validate_input(input, input_allow_special, follow_symlinks_effective)
+                .map(|()| format!("{} is readable", input.display())),
+        ),
+        None => checks.push("input", CheckStatus::Skip, "reading stdin"),
+    }
+
+    let output = normalize_output(file.to_path_buf(), into_dir)
+        .and_then(|output| resolve_output(output, input.as_deref(), into_dir));
+    let output = match output {
+        Ok(output) => output,
+        Err(e) => {
+            checks.push("target", CheckStatus::Fail, e);
+            return checks;
+        }
+    };
+
+    let special = is_special_file(&output);
+    if special {
+        if !passthrough_special {
+            checks.push("target", CheckStatus::Fail, MutxError::SpecialFile(output));
+            return checks;
+        }
+        checks.push(
+            "target",
+            CheckStatus::Warn,
+            format!(
+                "{} is a special file, written in place (not atomic)",
+                output.display()
+            ),
+        );
+        checks.push("symlink", CheckStatus::Skip, "special file");
+    } else {
+        let detail = if output.exists() {
+            format!("{} is replaced", output.display())
+        } else {
+            format!("{} is created", output.display())
+        };
+        checks.push("target", CheckStatus::Pass, detail);
+    }
+
+    // The file actually replaced, and the one whose lock is taken
+    let (output, lock_target) = if special {
+        (output.clone(), output)
+    } else {
+        let target = check_symlink(&output, follow_symlinks_effective)
+            .and_then(|()| symlink_policy.target_for(&output))
+            .and_then(|target| Ok((resolve_symlink_target(&target)?, target)));
+        match target {
+            Ok((lock_target, target)) => {
+                let detail = if target == output {
+                    format!("{} is replaced itself", output.display())
+                } else {
+                    format!("writes through to {}", target.display())
+                };
+                checks.push("symlink", CheckStatus::Pass, detail);
+                (target, lock_target)
+            }
+            Err(e) => {
+                checks.push("symlink", CheckStatus::Fail, e);
+                return checks;
+            }
+        }
+    };
+
+    if special {
+        checks.push("read_only", CheckStatus::Skip, "special file");
+    } else if is_readonly(&output) {
+        if respect_readonly {
+            checks.push(
+                "read_only",
+                CheckStatus::Fail,
+                MutxError::TargetReadOnly(output.clone()),
+            );
+        } else {
+            checks.push(
+                "read_only",
+                CheckStatus::Warn,
+                "target is read-only and is replaced anyway (--respect-readonly refuses)",
+            );
+        }
+    } else {
+        checks.push("read_only", CheckStatus::Pass, "target is not read-only");
+    }
+
+    let dir = parent_dir(&output);
+    if special {
+        checks.push("directory", CheckStatus::Skip, "special file");
+    } else if !dir.is_dir() {
+        checks.push(
+            "directory",
+            CheckStatus::Fail,
+            format!("{} does not exist", dir.display()),
+        );
+    } else if !can_create(dir) {
+        checks.push(
+            "directory",
+            CheckStatus::Fail,
+            format!("{} is not writable", dir.display()),
+        );
+    } else {
+        checks.push(
+            "directory",
+            CheckStatus::Pass,
+            format!("{} is writable", dir.display()),
+        );
+    }
+
+    // Lock path derivation, without creating the lock cache
+    let lock_path = if no_lock {
+        checks.push("lock_path", CheckStatus::Warn, "--no-lock: not locking");
+        None
+    } else if lock_backend == LockBackend::Remote {
+        #[cfg(feature = "cluster")]
+        let server = lock_server;
+        #[cfg(not(feature = "cluster"))]
+        let server: Option<String> = None;
+        match server {
+            Some(server) => checks.push(
+                "lock_path",
+                CheckStatus::Pass,
+                format!("locked on {}", server),
+            ),
+            None => checks.push(
+                "lock_path",
+                CheckStatus::Fail,
+                "--lock-backend remote requires --lock-server",
+            ),
+        }
+        None
+    } else {
+        let location = LockLocation {
+            output: &output,
+            lock_target: &lock_target,
+            special,
+            lock_file: lock_file.as_deref(),
+            lock_root: lock_root.as_deref(),
+            lock_backend,
+            lock_beside,
+            lock_hash_len,
+            lock_scope,
+            collaborative: None,
+        };
+        let derived = location.preview().and_then(|lock_path| {
+            // The target backend locks OUTPUT by design
+            if lock_backend != LockBackend::Target {
+                validate_lock_path(&lock_path, &output)?;
+            }
+            check_lock_symlink(&lock_path, follow_lock_symlinks)?;
+            Ok(lock_path)
+        });
+        match derived {
+            Ok(lock_path) => {
+                checks.push("lock_path", CheckStatus::Pass, lock_path.display());
+                Some(lock_path)
+            }
+            Err(e) => {
+                checks.push("lock_path", CheckStatus::Fail, e);
+                None
+            }
+        }
+    };
+
+    // Dotlocks rely on link(2), which is safe on network filesystems
+    let exclusive = match &lock_path {
+        None => {
+            checks.push("lock_filesystem", CheckStatus::Skip, "no lock file");
+            false
+        }
+        Some(lock_path) if lock_backend.is_file_lock() || lock_backend == LockBackend::Target => {
+            // The lock cache may not exist yet; its filesystem is where it
+            // would be created
+            let probe = match lock_path.file_name() {
+                Some(name) => existing_dir(parent_dir(lock_path)).join(name),
+                None => lock_path.clone(),
+            };
+            match check_lock_propagation(&probe, &output) {
+                Ok(()) => {
+                    checks.push(
+                        "lock_filesystem",
+                        CheckStatus::Pass,
+                        "locks are visible to all writers",
+                    );
+                    true
+                }
+                Err(e) => {
+                    let status = if strict_locking {
+                        CheckStatus::Fail
+                    } else {
+                        CheckStatus::Warn
+                    };
+                    checks.push("lock_filesystem", status, e);
+                    false
+                }
+            }
+        }
+        Some(_) => {
+            checks.push(
+                "lock_filesystem",
+                CheckStatus::Pass,
+                format!("{} locks need no flock support", lock_backend),
+            );
+            true
+        }
+    };
+
+    match &lock_path {
+        Some(lock_path) if lock_backend.is_file_lock() => match FileLock::status(lock_path) {
+            Ok(LockStatus::Free) => checks.push("lock_status", CheckStatus::Pass, "free"),
+            Ok(LockStatus::Held { pid, .. }) => checks.push(
+                "lock_status",
+                CheckStatus::Warn,
+                match pid {
+                    Some(pid) => format!("held by PID {}; a write would wait", pid),
+                    None => "held; a write would wait".to_string(),
+                },
+            ),
+            Err(e) => checks.push("lock_status", CheckStatus::Fail, e),
+        },
+        _ => checks.push(
+            "lock_status",
+            CheckStatus::Skip,
+            "only flock locks can be probed",
+        ),
+    }
+
+    if special {
+        checks.push("disk_space", CheckStatus::Skip, "special file");
+    } else {
+        check_space(&mut checks, dir, input.as_deref(), max_size, min_free);
+    }
+
+    if backup || reclaim_on_enospc {
+        let backup_dir = resolve_backup_suffix(backup_suffix)
+            .and_then(|_| resolve_backup_dir(backup_dir))
+            .and_then(|backup_dir| {
+                if special && backup {
+                    return Err(MutxError::Other(format!(
+                        "Cannot back up special file {}",
+                        output.display()
+                    )));
+                }
+                let backup_dir = backup_dir.unwrap_or_else(|| dir.to_path_buf());
+                if backup_dir.exists() && !backup_dir.is_dir() {
+                    return Err(MutxError::NotADirectory(backup_dir));
+                }
+                if !can_create(&backup_dir) {
+                    return Err(MutxError::Other(format!(
+                        "{} cannot be created or written",
+                        backup_dir.display()
+                    )));
+                }
+                Ok(format!("backups go to {}", backup_dir.display()))
+            });
+        checks.record("backup", backup_dir);
+    } else {
+        checks.push("backup", CheckStatus::Skip, "no backup requested");
+    }
+
+    match &sign {
+        Some(key) => checks.record(
+            "signing_key",
+            SigningKey::load(key).map(|_| format!("{} loads", key.display())),
+        ),
+        None => checks.push("signing_key", CheckStatus::Skip, "not signing"),
+    }
+
+    let guarantees = AtomicWriter::new(&output, WriteMode::Auto).and_then(|writer| {
+        let guarantees = writer
+            .with_passthrough_special(passthrough_special)
+            .with_exclusive(exclusive)
+            .guarantees();
+        guarantees.require(&require, &output)?;
+        Ok(guarantees)
+    });
+    match guarantees {
+        Ok(guarantees) => checks.push(
+            "guarantees",
+            CheckStatus::Pass,
+            format!(
+                "atomic: {}, durable: {}, exclusive: {}",
+                guarantees.atomic, guarantees.durable, guarantees.exclusive
+            ),
+        ),
+        Err(e) => checks.push("guarantees", CheckStatus::Fail, e),
+    }
+
+    checks
+}
+
+/// Whether the input fits under `max_size` and leaves `min_free` bytes on
+/// the target's filesystem
+fn check_space(
+    checks: &mut Checks,
+    dir: &Path,
+    input: Option<&Path>,
+    max_size: Option<u64>,
+    min_free: Option<u64>,
+) {
+    let size = input
+        .and_then(|input| fs::metadata(input).ok())
+        .map(|m| m.len());
+    if let (Some(size), Some(max_size)) = (size, max_size) {
+        if size > max_size {
+            checks.push(
+                "disk_space",
+                CheckStatus::Fail,
+                format!(
+                    "input is {}, over --max-size {}",
+                    format_size(size),
+                    format_size(max_size)
+                ),
+            );
+            return;
+        }
+    }
+
+    let available = match available_space(existing_dir(dir)) {
+        Ok(available) => available,
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => {
+            checks.push("disk_space", CheckStatus::Skip, e);
+            return;
+        }
+        Err(e) => {
+            checks.push("disk_space", CheckStatus::Fail, e);
+            return;
+        }
+    };
+    let needed = size.unwrap_or(0) + min_free.unwrap_or(0);
+    if available < needed {
+        checks.push(
+            "disk_space",
+            CheckStatus::Fail,
+            format!(
+                "{} available, {} needed",
+                format_size(available),
+                format_size(needed)
+            ),
+        );
+    } else {
+        checks.push(
+            "disk_space",
+            CheckStatus::Pass,
+            format!("{} available", format_size(available)),
+        );
+    }
+}
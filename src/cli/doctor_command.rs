@@ -0,0 +1,106 @@
+use crate::cli::style::{self, Style};
+use mutx::dirs::{has_cachedir_tag, state_dir};
+use mutx::lock::propagation::{check_lock_propagation, flock_support, FlockSupport};
+use mutx::lock::scope::in_container;
+use mutx::lock::{derive_lock_path, get_lock_cache_dir};
+use mutx::{MutxError, Result};
+use std::path::PathBuf;
+
+enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
+
+fn report(status: Status, label: &str, detail: impl std::fmt::Display) -> bool {
+    let tag = match status {
+        Status::Ok => style::tag(Style::Ok, "ok"),
+        Status::Warn => style::tag(Style::Warn, "warn"),
+        Status::Fail => style::tag(Style::Fail, "FAIL"),
+    };
+    println!("{} {}: {}", tag, label, detail);
+    matches!(status, Status::Fail)
+}
+
+fn support_status(support: &FlockSupport) -> Status {
+    match support {
+        FlockSupport::Reliable { .. } => Status::Ok,
+        FlockSupport::MountLocal { .. } | FlockSupport::Unknown => Status::Warn,
+        FlockSupport::Unreliable { .. } => Status::Fail,
+    }
+}
+
+pub fn execute_doctor(path: Option<PathBuf>) -> Result<()> {
+    let target = path.unwrap_or_else(|| PathBuf::from("."));
+    let mut failed = false;
+
+    let cache_dir = match get_lock_cache_dir() {
+        Ok(dir) => {
+            report(Status::Ok, "Lock cache directory", dir.display());
+            Some(dir)
+        }
+        Err(e) => {
+            failed |= report(Status::Fail, "Lock cache directory", e);
+            None
+        }
+    };
+
+    if let Some(cache_dir) = &cache_dir {
+        if !has_cachedir_tag(cache_dir) {
+            report(
+                Status::Warn,
+                "Lock cache tag",
+                "CACHEDIR.TAG missing or invalid",
+            );
+        }
+    }
+
+    match state_dir() {
+        Some(dir) => report(Status::Ok, "State directory", dir.display()),
+        None => report(Status::Warn, "State directory", "cannot be determined"),
+    };
+
+    if let Some(cache_dir) = &cache_dir {
+        let support = flock_support(cache_dir);
+        failed |= report(support_status(&support), "Lock cache filesystem", &support);
+    }
+
+    let target_support = flock_support(&target);
+    failed |= report(
+        support_status(&target_support),
+        "Target filesystem",
+        &target_support,
+    );
+
+    report(
+        Status::Ok,
+        "Running in container",
+        if in_container() { "yes" } else { "no" },
+    );
+
+    // For a directory, probe the scope of a hypothetical file inside it
+    let output = if target.is_dir() {
+        target.join(".mutx-doctor-probe")
+    } else {
+        target.clone()
+    };
+    match derive_lock_path(&output, false) {
+        Ok(lock_path) => match check_lock_propagation(&lock_path, &output) {
+            Ok(()) => {
+                report(Status::Ok, "Lock scope", "locks are visible to all writers");
+            }
+            Err(e) => failed |= report(Status::Fail, "Lock scope", e),
+        },
+        Err(e) => failed |= report(Status::Fail, "Lock path derivation", e),
+    }
+
+    if failed {
+        return Err(MutxError::Other(
+            "Locking for this target cannot be trusted; consider --lock-backend dotlock \
+             or a --lock-file on a local filesystem shared by all writers"
+                .to_string(),
+        ));
+    }
+
+    Ok(())
+}
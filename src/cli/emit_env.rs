@@ -0,0 +1,181 @@
+//! Shell-evaluable results for wrapper scripts (`--emit-env`).
+//!
+//! Each line is a `NAME='value'` assignment, single-quoted so `eval` never
+//! expands anything in a path:
+//!
+//! ```text
+//! MUTX_OUTPUT='/srv/app.conf'
+//! MUTX_LOCK_PATH='/home/me/.cache/mutx/locks/app.conf.1a2b3c4d.lock'
+//! MUTX_BACKUP_PATH=''
+//! MUTX_CHANGED=1
+//! ```
+
+use mutx::{MutxError, Result};
+use std::ffi::OsStr;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// Where the assignments are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvTarget {
+    Stdout,
+    /// An inherited file descriptor, e.g. 3 from `3>&1` or `3>file`
+    Fd(i32),
+}
+
+impl fmt::Display for EnvTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvTarget::Stdout => write!(f, "stdout"),
+            EnvTarget::Fd(fd) => write!(f, "{}", fd),
+        }
+    }
+}
+
+impl FromStr for EnvTarget {
+    type Err = MutxError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "stdout" | "-" | "1" => Ok(EnvTarget::Stdout),
+            _ => match s.parse::<i32>() {
+                Ok(fd) if fd >= 0 => Ok(EnvTarget::Fd(fd)),
+                _ => Err(MutxError::Other(format!(
+                    "Invalid --emit-env target '{}': expected stdout or a file descriptor number",
+                    s
+                ))),
+            },
+        }
+    }
+}
+
+impl EnvTarget {
+    /// Fail early if the target file descriptor is not open, before anything
+    /// is written
+    pub fn check(&self) -> Result<()> {
+        match self {
+            EnvTarget::Stdout => Ok(()),
+            EnvTarget::Fd(fd) => check_fd(*fd),
+        }
+    }
+}
+
+/// Assignments collected during a write
+#[derive(Debug, Default)]
+pub struct EnvReport {
+    lines: Vec<u8>,
+}
+
+impl EnvReport {
+    /// Add `NAME='value'`
+    pub fn set(&mut self, name: &str, value: &OsStr) {
+        self.lines.extend_from_slice(name.as_bytes());
+        self.lines.push(b'=');
+        self.lines.extend_from_slice(&shell_quote(value));
+        self.lines.push(b'\n');
+    }
+
+    /// Add `NAME=1` or `NAME=0`
+    pub fn set_flag(&mut self, name: &str, value: bool) {
+        let line = format!("{}={}\n", name, u8::from(value));
+        self.lines.extend_from_slice(line.as_bytes());
+    }
+
+    /// Print the assignments to `target`
+    pub fn emit(&self, target: EnvTarget) -> Result<()> {
+        match target {
+            EnvTarget::Stdout => {
+                let mut stdout = io::stdout().lock();
+                stdout.write_all(&self.lines)?;
+                stdout.flush()?;
+                Ok(())
+            }
+            EnvTarget::Fd(fd) => write_fd(fd, &self.lines),
+        }
+    }
+}
+
+/// Wrap `value` in single quotes; an embedded quote becomes `'\''`
+fn shell_quote(value: &OsStr) -> Vec<u8> {
+    let mut quoted = vec![b'\''];
+    for &byte in os_bytes(value).iter() {
+        if byte == b'\'' {
+            quoted.extend_from_slice(b"'\\''");
+        } else {
+            quoted.push(byte);
+        }
+    }
+    quoted.push(b'\'');
+    quoted
+}
+
+#[cfg(unix)]
+fn os_bytes(value: &OsStr) -> std::borrow::Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    std::borrow::Cow::Borrowed(value.as_bytes())
+}
+
+#[cfg(not(unix))]
+fn os_bytes(value: &OsStr) -> std::borrow::Cow<'_, [u8]> {
+    match value.to_string_lossy() {
+        std::borrow::Cow::Borrowed(s) => std::borrow::Cow::Borrowed(s.as_bytes()),
+        std::borrow::Cow::Owned(s) => std::borrow::Cow::Owned(s.into_bytes()),
+    }
+}
+
+#[cfg(unix)]
+fn write_fd(fd: i32, data: &[u8]) -> Result<()> {
+    use std::fs::File;
+    use std::mem::ManuallyDrop;
+    use std::os::unix::io::FromRawFd;
+
+    check_fd(fd)?;
+    // Only borrow the descriptor: it belongs to the calling shell
+    let mut file = ManuallyDrop::new(unsafe { File::from_raw_fd(fd) });
+    file.write_all(data)?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn check_fd(fd: i32) -> Result<()> {
+    if unsafe { libc::fcntl(fd, libc::F_GETFD) } == -1 {
+        return Err(MutxError::Other(format!(
+            "--emit-env: file descriptor {} is not open (run with {}>&1 or {}>FILE)",
+            fd, fd, fd
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_fd(fd: i32) -> Result<()> {
+    Err(MutxError::Other(format!(
+        "--emit-env: writing to file descriptor {} is only supported on Unix",
+        fd
+    )))
+}
+
+#[cfg(not(unix))]
+fn write_fd(fd: i32, _data: &[u8]) -> Result<()> {
+    check_fd(fd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote(OsStr::new("/srv/a b")), b"'/srv/a b'");
+        assert_eq!(shell_quote(OsStr::new("it's $HOME")), b"'it'\\''s $HOME'");
+        assert_eq!(shell_quote(OsStr::new("")), b"''");
+    }
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!("stdout".parse::<EnvTarget>().unwrap(), EnvTarget::Stdout);
+        assert_eq!("3".parse::<EnvTarget>().unwrap(), EnvTarget::Fd(3));
+        assert!("fd".parse::<EnvTarget>().is_err());
+    }
+}
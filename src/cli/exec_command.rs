@@ -0,0 +1,151 @@
+//! `mutx exec FILE -- COMMAND`: running a command under a file's lock, as
+//! flock(1) does, but with the lock path writers derive for FILE.
+//!
+//! The lock file is opened close-on-exec, so the command does not inherit
+//! it: the lock is held by mutx, which waits for the command and releases
+//! it once the command has exited, then exits with the command's code.
+//! The command finds the lock listed in `MUTX_HELD_LOCKS`, so mutx run by
+//! it fails on the same lock instead of waiting forever (see
+//! [`held_locks`](crate::cli::held_locks)).
+
+use crate::cli::held_locks::{self, HeldLock, HELD_LOCKS_ENV};
+use crate::cli::style;
+use crate::cli::{acquire_lock, apply_lock_mode, placement, resolve_lock_mode, signals, Command};
+use mutx::utils::process::terminate;
+use mutx::{
+    derive_lock_path, CancelToken, LockBackend, LockStrategy, MutxError, Result, TimeoutConfig,
+};
+use std::ffi::OsString;
+use std::io;
+use std::process::{self, ExitStatus};
+use std::time::Duration;
+
+/// How often the command is checked for having exited, and mutx for a
+/// signal to pass on to it
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+pub fn execute_exec(cmd: Command) -> Result<()> {
+    let Command::Exec {
+        file,
+        command,
+        lock_file,
+        lock_beside,
+        lock_mode,
+        shared,
+        no_wait,
+        timeout,
+        conflict_exit_code,
+        no_spinner,
+        verbose,
+    } = cmd
+    else {
+        return Err(MutxError::Other(
+            "Internal error: expected Exec command".to_string(),
+        ));
+    };
+
+    let lock_strategy = if no_wait {
+        LockStrategy::NoWait
+    } else if let Some(timeout_ms) = timeout {
+        LockStrategy::Timeout(TimeoutConfig::new(Duration::from_millis(timeout_ms)))
+    } else {
+        LockStrategy::Wait
+    };
+    let lock_strategy = if shared {
+        lock_strategy.shared()
+    } else {
+        lock_strategy
+    };
+
+    let lock_path = match lock_file {
+        Some(custom) => derive_lock_path(&custom, true)?,
+        None => placement(lock_beside).derive(&file)?,
+    };
+    let lock_mode = resolve_lock_mode(lock_mode)?;
+
+    let interrupted = signals::install();
+    let mut lock = match acquire_lock(
+        &lock_path,
+        lock_strategy,
+        LockBackend::Flock,
+        verbose,
+        no_spinner,
+        Some(&interrupted),
+    ) {
+        Err(e @ (MutxError::LockWouldBlock { .. } | MutxError::LockTimeout { .. })) => {
+            match conflict_exit_code {
+                Some(code) => {
+                    eprintln!("{} {}", style::error_header(), e);
+                    process::exit(code.into());
+                }
+                None => return Err(e),
+            }
+        }
+        result => result?,
+    };
+    apply_lock_mode(&lock, lock_mode);
+    let command_line = command
+        .iter()
+        .map(|arg| arg.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ");
+    lock.record_command(&command_line);
+    lock.record_target(&file);
+    let held = held_locks::for_child(HeldLock::new(&lock_path, shared, &file, command_line))?;
+    if verbose > 0 {
+        eprintln!("Lock acquired: {}", lock_path.display());
+    }
+
+    let status = run(&command, &held, &interrupted);
+    drop(lock);
+    match status {
+        Ok(status) => process::exit(exit_code(status)),
+        Err(e) => {
+            eprintln!(
+                "{} Cannot run {}: {}",
+                style::error_header(),
+                command[0].to_string_lossy(),
+                e
+            );
+            // As a shell reports a command it cannot find or execute
+            process::exit(if e.kind() == io::ErrorKind::NotFound {
+                127
+            } else {
+                126
+            });
+        }
+    }
+}
+
+/// Run `command` to completion with `held` as its `MUTX_HELD_LOCKS`,
+/// passing SIGTERM on to it the first time mutx is interrupted so the lock
+/// is only released once it has exited
+fn run(command: &[OsString], held: &str, interrupted: &CancelToken) -> io::Result<ExitStatus> {
+    let mut child = process::Command::new(&command[0])
+        .args(&command[1..])
+        .env(HELD_LOCKS_ENV, held)
+        .spawn()?;
+    let mut forwarded = false;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if interrupted.is_cancelled() && !forwarded {
+            forwarded = true;
+            let _ = terminate(child.id());
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// The command's exit code, or 128 plus the signal that killed it
+fn exit_code(status: ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    status.code().unwrap_or(1)
+}
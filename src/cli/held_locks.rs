@@ -0,0 +1,108 @@
+//! Deadlock detection for mutx invoked under a lock mutx holds.
+//!
+//! `mutx exec` holds its lock while it waits for its command, so a command
+//! that runs mutx on the same file would wait for its own parent forever.
+//! `mutx exec` therefore lists the locks it and its ancestors hold in
+//! `MUTX_HELD_LOCKS` for the command, as a JSON array:
+//!
+//! ```text
+//! [{"pid":4242,"lock":"/home/me/.cache/mutx/locks/app.conf.1a2b3c4d.lock","shared":false,"target":"/srv/app.conf","command":"./deploy.sh"}]
+//! ```
+//!
+//! Every acquisition checks the list first and fails with
+//! [`MutxError::LockCycle`] if a holder still running would conflict,
+//! naming each holder between it and this process.
+
+use mutx::lock::canonical_output_path;
+use mutx::utils::process::pid_is_alive;
+use mutx::{MutxError, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+pub const HELD_LOCKS_ENV: &str = "MUTX_HELD_LOCKS";
+
+/// A lock held by a mutx process this one runs under
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeldLock {
+    pub pid: u32,
+    /// Canonical lock path, so any spelling of it matches
+    pub lock: PathBuf,
+    pub shared: bool,
+    pub target: PathBuf,
+    /// Command the holder waits for
+    pub command: String,
+}
+
+impl HeldLock {
+    pub fn new(lock_path: &Path, shared: bool, target: &Path, command: String) -> Self {
+        HeldLock {
+            pid: std::process::id(),
+            lock: canonical_lock_path(lock_path),
+            shared,
+            target: target.to_path_buf(),
+            command,
+        }
+    }
+}
+
+fn canonical_lock_path(lock_path: &Path) -> PathBuf {
+    canonical_output_path(lock_path).unwrap_or_else(|_| lock_path.to_path_buf())
+}
+
+/// Locks held by the mutx processes this one runs under, outermost first.
+/// A malformed list is ignored: it only ever turns a hang into an error.
+pub fn inherited() -> Vec<HeldLock> {
+    let Some(value) = std::env::var_os(HELD_LOCKS_ENV) else {
+        return Vec::new();
+    };
+    match serde_json::from_str(&value.to_string_lossy()) {
+        Ok(held) => held,
+        Err(e) => {
+            warn!("Ignoring malformed {}: {}", HELD_LOCKS_ENV, e);
+            Vec::new()
+        }
+    }
+}
+
+/// The value of [`HELD_LOCKS_ENV`] for a command run while holding `lock`
+pub fn for_child(lock: HeldLock) -> Result<String> {
+    let mut held = inherited();
+    held.push(lock);
+    serde_json::to_string(&held).map_err(|e| MutxError::Other(e.to_string()))
+}
+
+/// Fail instead of waiting for `lock_path` if a mutx process this one runs
+/// under holds it in a conflicting mode
+pub fn check_cycle(lock_path: &Path, shared: bool) -> Result<()> {
+    let held = inherited();
+    let lock = canonical_lock_path(lock_path);
+    let Some(first) = held.iter().position(|holder| {
+        holder.lock == lock && !(holder.shared && shared) && pid_is_alive(holder.pid) != Some(false)
+    }) else {
+        return Ok(());
+    };
+
+    let mut chain = String::new();
+    for holder in &held[first..] {
+        let _ = writeln!(
+            chain,
+            "  PID {} holds {} for {} and waits for `{}`",
+            holder.pid,
+            holder.lock.display(),
+            holder.target.display(),
+            holder.command
+        );
+    }
+    let _ = write!(
+        chain,
+        "  which runs this mutx (PID {}), waiting for {}",
+        std::process::id(),
+        lock.display()
+    );
+    Err(MutxError::LockCycle {
+        path: lock_path.to_path_buf(),
+        chain,
+    })
+}
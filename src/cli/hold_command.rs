@@ -0,0 +1,317 @@
+//! `mutx lock FILE` and `mutx unlock FILE`: holding a file's lock across
+//! several external commands.
+//!
+//! A lock lives as long as the process holding it, so `mutx lock` stays
+//! running until SIGTERM (or SIGINT), which `mutx unlock` sends. With
+//! `--background` it holds the lock from a detached copy of itself and
+//! exits as soon as that copy has the lock, printing its PID.
+//!
+//! `mutx unlock --force` breaks a lock whose holder is gone without
+//! releasing it, after checking what it can of the holder and asking.
+
+use crate::cli::style::{paint, Stream, Style};
+use crate::cli::{acquire_lock, apply_lock_mode, placement, resolve_lock_mode, signals, HoldArgs};
+use mutx::utils::process::terminate;
+use mutx::{
+    derive_lock_path, FileLock, LockBackend, LockHolder, LockStatus, LockStrategy, MutxError,
+    Result, TimeoutConfig,
+};
+use std::io::{self, BufRead, BufReader, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Recorded as the holding command, so `mutx unlock` only ever signals a
+/// lock held this way and never a write in progress
+const HOLD_COMMAND: &str = "mutx lock";
+
+/// How often the holder checks for a signal, and `mutx unlock` for the
+/// lock to be released
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long `mutx unlock` waits for the holder to exit
+const RELEASE_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn lock_path(file: &Path, lock_file: Option<PathBuf>, lock_beside: bool) -> Result<PathBuf> {
+    match lock_file {
+        Some(custom) => derive_lock_path(&custom, true),
+        None => placement(lock_beside).derive(file),
+    }
+}
+
+pub fn execute_hold(args: HoldArgs) -> Result<()> {
+    if args.background {
+        return hold_in_background(&args);
+    }
+    let HoldArgs {
+        file,
+        lock_file,
+        lock_beside,
+        lock_mode,
+        no_wait,
+        timeout,
+        background: _,
+        detached,
+    } = args;
+    let file = file.ok_or_else(|| MutxError::Other("FILE argument required".to_string()))?;
+
+    let lock_strategy = if no_wait {
+        LockStrategy::NoWait
+    } else if let Some(timeout_ms) = timeout {
+        LockStrategy::Timeout(TimeoutConfig::new(Duration::from_millis(timeout_ms)))
+    } else {
+        LockStrategy::Wait
+    };
+    let lock_path = lock_path(&file, lock_file, lock_beside)?;
+    let lock_mode = resolve_lock_mode(lock_mode)?;
+
+    let released = signals::install();
+    let mut lock = acquire_lock(
+        &lock_path,
+        lock_strategy,
+        LockBackend::Flock,
+        0,
+        true,
+        Some(&released),
+    )?;
+    apply_lock_mode(&lock, lock_mode);
+    lock.record_command(HOLD_COMMAND);
+    lock.record_target(&file);
+
+    // The PID is what scripts keep; --background relays this line, and
+    // stops reading stderr once it has it
+    println!("{}", std::process::id());
+    let _ = std::io::stdout().flush();
+    if !detached {
+        eprintln!("{}", holding_message(&lock_path, &file));
+    }
+
+    while !released.is_cancelled() {
+        std::thread::sleep(POLL_INTERVAL);
+    }
+    drop(lock);
+    Ok(())
+}
+
+fn holding_message(lock_path: &Path, file: &Path) -> String {
+    format!(
+        "Holding lock {} until SIGTERM or `mutx unlock {}`",
+        lock_path.display(),
+        file.display()
+    )
+}
+
+/// Run `mutx lock` without --background as a detached child, and exit with
+/// its PID once it holds the lock or with its exit code if it fails to
+fn hold_in_background(args: &HoldArgs) -> Result<()> {
+    let file = args
+        .file
+        .as_ref()
+        .ok_or_else(|| MutxError::Other("FILE argument required".to_string()))?;
+    let exe = std::env::current_exe()
+        .map_err(|e| MutxError::Other(format!("Cannot locate the mutx executable: {}", e)))?;
+
+    let mut command = Command::new(exe);
+    command.arg("lock").arg(file).arg("--detached");
+    if let Some(lock_file) = &args.lock_file {
+        command.arg("--lock-file").arg(lock_file);
+    }
+    if args.lock_beside {
+        command.arg("--lock-beside");
+    }
+    if let Some(mode) = &args.lock_mode {
+        command.arg("--lock-mode").arg(mode.to_string());
+    }
+    if args.no_wait {
+        command.arg("--no-wait");
+    }
+    if let Some(timeout) = args.timeout {
+        command.arg("--timeout").arg(timeout.to_string());
+    }
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // Out of the terminal's process group, so Ctrl-C in the shell that
+    // started it does not release the lock
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| MutxError::Other(format!("Cannot start lock holder: {}", e)))?;
+    let mut ready = String::new();
+    if let Some(stdout) = child.stdout.take() {
+        let _ = BufReader::new(stdout).read_line(&mut ready);
+    }
+    if ready.trim().is_empty() {
+        // The child failed to take the lock; pass on why
+        if let Some(mut stderr) = child.stderr.take() {
+            let _ = std::io::copy(&mut stderr, &mut std::io::stderr());
+        }
+        let status = child
+            .wait()
+            .map_err(|e| MutxError::Other(format!("Lock holder failed: {}", e)))?;
+        std::process::exit(status.code().unwrap_or(1));
+    }
+    println!("{}", ready.trim());
+
+    let lock_path = lock_path(file, args.lock_file.clone(), args.lock_beside)?;
+    eprintln!("{}", holding_message(&lock_path, file));
+    Ok(())
+}
+
+pub fn execute_unlock(file: PathBuf, lock_file: Option<PathBuf>, lock_beside: bool) -> Result<()> {
+    let lock_path = lock_path(&file, lock_file, lock_beside)?;
+    match FileLock::status(&lock_path)? {
+        LockStatus::Free => {
+            eprintln!("Not locked ({})", lock_path.display());
+            return Ok(());
+        }
+        // `mutx lock` holds exclusively; a payload under a shared lock is
+        // left over and names no process holding it now
+        LockStatus::Held { shared: true, .. } => {
+            return Err(MutxError::Other(format!(
+                "{} is held shared, not by `mutx lock`; refusing to release it",
+                lock_path.display()
+            )));
+        }
+        LockStatus::Held { .. } => {}
+    }
+
+    let holder = LockHolder::read(&lock_path)?
+        .filter(|holder| holder.command.as_deref() == Some(HOLD_COMMAND))
+        .ok_or_else(|| {
+            MutxError::Other(format!(
+                "{} is not held by `mutx lock`; refusing to release it",
+                lock_path.display()
+            ))
+        })?;
+    match holder.is_same_process() {
+        Some(true) => {}
+        Some(false) => {
+            return Err(MutxError::Other(format!(
+                "{} names {}, which is no longer running; refusing to signal its PID",
+                lock_path.display(),
+                holder
+            )));
+        }
+        None => {
+            return Err(MutxError::Other(format!(
+                "{} is held by {}, which cannot be signalled from this host",
+                lock_path.display(),
+                holder
+            )));
+        }
+    }
+    terminate(holder.pid)
+        .map_err(|e| MutxError::Other(format!("Cannot signal PID {}: {}", holder.pid, e)))?;
+
+    let deadline = Instant::now() + RELEASE_TIMEOUT;
+    while FileLock::status(&lock_path)?.is_held() {
+        if Instant::now() >= deadline {
+            return Err(MutxError::Other(format!(
+                "PID {} did not release {} within {}s",
+                holder.pid,
+                lock_path.display(),
+                RELEASE_TIMEOUT.as_secs()
+            )));
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+    Ok(())
+}
+
+/// Break the lock of `file` for a holder that is gone. A holder known to
+/// be running, or still refreshing its lease, is never broken; one that
+/// cannot be checked from this host is broken only once confirmed.
+pub fn execute_force_unlock(
+    file: PathBuf,
+    lock_file: Option<PathBuf>,
+    lock_beside: bool,
+    yes: bool,
+) -> Result<()> {
+    let lock_path = lock_path(&file, lock_file, lock_beside)?;
+    match FileLock::status(&lock_path)? {
+        LockStatus::Free => {
+            eprintln!("Not locked ({})", lock_path.display());
+            return Ok(());
+        }
+        // Any payload is left over from an earlier writer; the kernel
+        // releases a shared lock with the last reader holding it
+        LockStatus::Held { shared: true, .. } => {
+            return Err(MutxError::Other(format!(
+                "{} is held shared by live readers; refusing to break it",
+                lock_path.display()
+            )))
+        }
+        LockStatus::Held { .. } => {}
+    }
+
+    let holder = LockHolder::read(&lock_path)?;
+    let verdict = match &holder {
+        Some(holder) if holder.is_alive() == Some(true) => {
+            return Err(MutxError::Other(format!(
+                "{} is held by {}, which is still running; stop it instead of breaking its lock",
+                lock_path.display(),
+                holder
+            )))
+        }
+        Some(holder) if holder.lease().is_some() && !holder.lease_expired(&lock_path) => {
+            return Err(MutxError::Other(format!(
+                "{} is held by {}, whose lease is still being refreshed",
+                lock_path.display(),
+                holder
+            )))
+        }
+        Some(holder) if holder.is_alive() == Some(false) => {
+            format!("{} (no longer running)", holder)
+        }
+        Some(holder) if holder.lease_expired(&lock_path) => {
+            format!("{} (lease expired)", holder)
+        }
+        Some(holder) => format!(
+            "{} (UNVERIFIED: it cannot be checked from this host)",
+            holder
+        ),
+        None => "unknown (UNVERIFIED: no holder is recorded)".to_string(),
+    };
+
+    eprintln!(
+        "{} forcibly breaking the lock on {}",
+        paint(Stream::Stderr, Style::Fail, "WARNING:"),
+        file.display()
+    );
+    eprintln!("  Lock file: {}", lock_path.display());
+    eprintln!("  Holder: {}", verdict);
+    eprintln!(
+        "If the holder is in fact still running, it and the next writer will both \
+         believe they hold the lock, and writes may be lost."
+    );
+    if !yes && !confirm("Break this lock?")? {
+        return Err(MutxError::Other("Lock not broken".to_string()));
+    }
+
+    let backup = FileLock::force_break(&lock_path)?;
+    eprintln!("Lock broken; the old lock file is {}", backup.display());
+    Ok(())
+}
+
+/// Ask `question` on the terminal; refuses outright when stdin is not one
+fn confirm(question: &str) -> Result<bool> {
+    if !io::stdin().is_terminal() {
+        return Err(MutxError::Other(
+            "Refusing to break the lock without confirmation; pass --yes".to_string(),
+        ));
+    }
+    eprint!("{} [y/N] ", question);
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(
+        answer.trim().to_ascii_lowercase().as_str(),
+        "y" | "yes"
+    ))
+}
@@ -1,25 +1,14 @@
-use crate::cli::{Command, HousekeepOperation};
-use mutx::housekeep::{clean_backups, clean_locks, CleanBackupConfig, CleanLockConfig};
+use crate::cli::style::{paint, Stream, Style};
+use crate::cli::{resolve_backup_suffix, signals, Command, HousekeepOperation};
+use mutx::housekeep::{
+    clean_backups, clean_locks, clean_temps, CleanBackupConfig, CleanLockConfig, CleanTempConfig,
+};
+use mutx::janitor::{self, Heartbeat, JanitorPass, JanitorPolicy};
 use mutx::lock::get_lock_cache_dir;
-use mutx::utils::parse_duration;
-use mutx::{MutxError, Result};
-use std::path::PathBuf;
-
-fn validate_suffix(suffix: &str) -> Result<()> {
-    if suffix.is_empty() {
-        return Err(MutxError::Other(
-            "Backup suffix cannot be empty".to_string(),
-        ));
-    }
-
-    if suffix == "." {
-        return Err(MutxError::Other(
-            "Backup suffix cannot be a single dot".to_string(),
-        ));
-    }
-
-    Ok(())
-}
+use mutx::parse::parse_duration;
+use mutx::schema::{self, HousekeepReport, Versioned};
+use mutx::{Config, MutxError, Result};
+use std::path::{Path, PathBuf};
 
 pub fn execute_housekeep(cmd: Command) -> Result<()> {
     let Command::Housekeep { operation } = cmd else {
@@ -35,6 +24,7 @@ pub fn execute_housekeep(cmd: Command) -> Result<()> {
             older_than,
             dry_run,
             verbose,
+            json,
         } => {
             // Smart default: use cache directory
             let target_dir = match dir {
@@ -52,37 +42,157 @@ pub fn execute_housekeep(cmd: Command) -> Result<()> {
             };
 
             let cleaned = clean_locks(&config)?;
+            if json {
+                return print_report(&HousekeepReport {
+                    dry_run,
+                    locks: cleaned,
+                    ..Default::default()
+                });
+            }
             report_cleaning_results("lock", &cleaned, verbose, dry_run);
             Ok(())
         }
 
         HousekeepOperation::Backups {
             dir,
+            everywhere,
             recursive,
             older_than,
             keep_newest,
+            max_total_size,
             suffix,
+            age_source,
             dry_run,
             verbose,
+            json,
         } => {
-            validate_suffix(&suffix)?;
+            let suffix = resolve_backup_suffix(suffix)?;
 
-            // Smart default: use current directory
-            let target_dir = dir.unwrap_or_else(|| PathBuf::from("."));
+            // Smart default: the central backup directory if one is
+            // configured and exists, otherwise the current directory
+            let central = Config::load()?.backup_dir.filter(|d| d.is_dir());
+            let target_dirs = match (dir, central) {
+                (Some(d), _) => vec![d],
+                (None, Some(central)) if everywhere => {
+                    let cwd = PathBuf::from(".");
+                    if same_dir(&central, &cwd) {
+                        vec![central]
+                    } else {
+                        vec![central, cwd]
+                    }
+                }
+                (None, Some(central)) => vec![central],
+                (None, None) => vec![PathBuf::from(".")],
+            };
 
             let duration = older_than.map(|s| parse_duration(&s)).transpose()?;
 
-            let config = CleanBackupConfig {
-                dir: target_dir,
+            let mut cleaned = Vec::new();
+            for target_dir in target_dirs {
+                let config = CleanBackupConfig {
+                    dir: target_dir,
+                    recursive,
+                    older_than: duration,
+                    keep_newest,
+                    suffix: suffix.clone(),
+                    dry_run,
+                    age_source,
+                    max_total_size,
+                };
+                cleaned.extend(clean_backups(&config)?);
+            }
+
+            if json {
+                return print_report(&HousekeepReport {
+                    dry_run,
+                    backups: cleaned,
+                    ..Default::default()
+                });
+            }
+            report_cleaning_results("backup", &cleaned, verbose, dry_run);
+            Ok(())
+        }
+
+        HousekeepOperation::Temps {
+            dir,
+            recursive,
+            older_than,
+            dry_run,
+            verbose,
+            json,
+        } => {
+            let config = CleanTempConfig {
+                dir: dir.unwrap_or_else(|| PathBuf::from(".")),
                 recursive,
-                older_than: duration,
-                keep_newest,
-                suffix,
+                older_than: Some(parse_duration(&older_than)?),
                 dry_run,
             };
 
-            let cleaned = clean_backups(&config)?;
-            report_cleaning_results("backup", &cleaned, verbose, dry_run);
+            let cleaned = clean_temps(&config)?;
+            if json {
+                return print_report(&HousekeepReport {
+                    dry_run,
+                    temps: cleaned,
+                    ..Default::default()
+                });
+            }
+            report_cleaning_results("temp", &cleaned, verbose, dry_run);
+            Ok(())
+        }
+
+        HousekeepOperation::Run {
+            config,
+            dry_run,
+            verbose,
+            json,
+        } => {
+            let path = match config {
+                Some(path) => path,
+                None => JanitorPolicy::default_path().ok_or_else(|| {
+                    MutxError::Other(
+                        "Cannot determine the config directory; pass --config".to_string(),
+                    )
+                })?,
+            };
+            let mut policy = JanitorPolicy::from_path(&path, &resolve_backup_suffix(None)?)?;
+            if dry_run {
+                policy = policy.dry_run();
+            }
+            run_policy_once(&policy, verbose, dry_run, json)
+        }
+
+        HousekeepOperation::Daemon {
+            config,
+            interval,
+            jitter,
+            heartbeat,
+            once,
+            dry_run,
+            verbose,
+        } => {
+            let mut policy = JanitorPolicy::from_path(&config, &resolve_backup_suffix(None)?)?;
+            if dry_run {
+                policy = policy.dry_run();
+            }
+            policy.interval = interval.or(policy.interval);
+            policy.jitter = jitter.or(policy.jitter);
+            policy.heartbeat = heartbeat.or(policy.heartbeat);
+            if policy.interval().is_zero() {
+                return Err(MutxError::Other(
+                    "--interval must be greater than zero".to_string(),
+                ));
+            }
+
+            if once {
+                return run_policy_once(&policy, verbose, dry_run, false);
+            }
+
+            // SIGINT and SIGTERM stop the loop between passes (see signals)
+            let stop = signals::install();
+            let passes = janitor::run(&policy, &stop, |pass| report_pass(pass, verbose, dry_run))?;
+            if verbose {
+                println!("Stopped after {} pass(es)", passes);
+            }
             Ok(())
         }
 
@@ -93,11 +203,14 @@ pub fn execute_housekeep(cmd: Command) -> Result<()> {
             recursive,
             older_than,
             keep_newest,
+            max_total_size,
             suffix,
+            age_source,
             dry_run,
             verbose,
+            json,
         } => {
-            validate_suffix(&suffix)?;
+            let suffix = resolve_backup_suffix(suffix)?;
 
             // Validation: require either dir OR both locks_dir and backups_dir
             let (locks_path, backups_path) = match (dir, locks_dir, backups_dir) {
@@ -129,9 +242,20 @@ pub fn execute_housekeep(cmd: Command) -> Result<()> {
                 keep_newest,
                 suffix,
                 dry_run,
+                age_source,
+                max_total_size,
             };
             let cleaned_backups = clean_backups(&backup_config)?;
 
+            if json {
+                return print_report(&HousekeepReport {
+                    dry_run,
+                    locks: cleaned_locks,
+                    backups: cleaned_backups,
+                    ..Default::default()
+                });
+            }
+
             // Report both
             report_cleaning_results("lock", &cleaned_locks, verbose, dry_run);
             report_cleaning_results("backup", &cleaned_backups, verbose, dry_run);
@@ -140,11 +264,68 @@ pub fn execute_housekeep(cmd: Command) -> Result<()> {
     }
 }
 
+/// Make one pass over `policy`, writing its heartbeat if it has one, and
+/// fail if any rule failed
+fn run_policy_once(policy: &JanitorPolicy, verbose: bool, dry_run: bool, json: bool) -> Result<()> {
+    let pass = janitor::run_pass(policy);
+    if json {
+        print_report(&HousekeepReport {
+            dry_run,
+            locks: pass.locks.clone(),
+            backups: pass.backups.clone(),
+            temps: pass.temps.clone(),
+            errors: pass.errors.clone(),
+        })?;
+    } else {
+        report_pass(&pass, verbose, dry_run);
+    }
+    if let Some(path) = &policy.heartbeat {
+        janitor::write_heartbeat(path, &Heartbeat::after(&pass, 1, policy.interval()))?;
+    }
+    if !pass.errors.is_empty() {
+        return Err(MutxError::Other(format!(
+            "{} housekeeping rule(s) failed",
+            pass.errors.len()
+        )));
+    }
+    Ok(())
+}
+
+fn report_pass(pass: &JanitorPass, verbose: bool, dry_run: bool) {
+    report_cleaning_results("lock", &pass.locks, verbose, dry_run);
+    report_cleaning_results("backup", &pass.backups, verbose, dry_run);
+    report_cleaning_results("temp", &pass.temps, verbose, dry_run);
+}
+
+/// Whether `a` and `b` name the same directory
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn print_report(report: &HousekeepReport) -> Result<()> {
+    println!("{}", Versioned::new(schema::HOUSEKEEP, report).to_json()?);
+    Ok(())
+}
+
 fn report_cleaning_results(item_type: &str, cleaned: &[PathBuf], verbose: bool, dry_run: bool) {
-    let verb = if dry_run { "Would clean" } else { "Cleaned" };
+    let verb = if dry_run {
+        paint(Stream::Stdout, Style::Warn, "Would clean")
+    } else {
+        paint(Stream::Stdout, Style::Ok, "Cleaned")
+    };
 
     if cleaned.is_empty() {
-        println!("No {} files to clean", item_type);
+        println!(
+            "{}",
+            paint(
+                Stream::Stdout,
+                Style::Dim,
+                format!("No {} files to clean", item_type)
+            )
+        );
     } else {
         println!("{} {} {} file(s)", verb, cleaned.len(), item_type);
         if verbose {
@@ -0,0 +1,102 @@
+use crate::cli::{placement, LockOperation};
+use chrono::{DateTime, Local};
+use mutx::lock::{
+    derive_lock_path, derive_lock_path_with_scheme, get_lock_cache_dir, lock_cache_dir_path,
+    LockHolder, LockPlacement, LockScheme, ALGORITHM, SCHEME_VERSION,
+};
+use mutx::{lock_cache_info, MutxError, Result};
+
+pub fn execute_lock(operation: LockOperation) -> Result<()> {
+    match operation {
+        LockOperation::Path {
+            file,
+            print_algorithm,
+            lock_hash_len,
+            lock_beside,
+        } => {
+            if print_algorithm {
+                print!("{}", ALGORITHM);
+                println!();
+                println!("Lock cache directory: {}", get_lock_cache_dir()?.display());
+                return Ok(());
+            }
+
+            let file =
+                file.ok_or_else(|| MutxError::Other("FILE argument required".to_string()))?;
+
+            if lock_beside {
+                println!("{}", LockPlacement::Sidecar.derive(&file)?.display());
+                return Ok(());
+            }
+
+            let mut scheme = LockScheme::default();
+            if let Some(hash_len) = lock_hash_len {
+                scheme = scheme.with_hash_len(hash_len);
+            }
+
+            let lock_path = derive_lock_path_with_scheme(&file, &scheme)?;
+            println!("{}", lock_path.display());
+            Ok(())
+        }
+        LockOperation::Holder {
+            file,
+            lock_file,
+            lock_beside,
+        } => {
+            let lock_path = match lock_file {
+                Some(custom) => derive_lock_path(&custom, true)?,
+                None => placement(lock_beside).derive(&file)?,
+            };
+
+            let Some(holder) = LockHolder::read(&lock_path)? else {
+                println!("No holder recorded in {}", lock_path.display());
+                return Ok(());
+            };
+
+            print!("{}", holder);
+            if let Some(target) = &holder.target {
+                print!(", writing {}", target.display());
+            }
+            if holder.is_alive() == Some(false) {
+                print!(" (not running)");
+            } else if holder.lease_expired(&lock_path) {
+                print!(" (lease expired)");
+            }
+            println!();
+            Ok(())
+        }
+        LockOperation::CacheInfo { dir } => {
+            let dir = match dir {
+                Some(d) => d,
+                None => lock_cache_dir_path()?,
+            };
+            let info = lock_cache_info(&dir)?;
+
+            println!("Cache directory: {}", info.dir.display());
+            print!("Scheme: v{}", SCHEME_VERSION);
+            if info.unmigrated > 0 {
+                print!(" ({} unmigrated v1 lock files)", info.unmigrated);
+            }
+            println!();
+            println!("Lock files: {}", info.files);
+            println!("Total size: {} bytes", info.total_bytes);
+            match &info.oldest {
+                Some((path, mtime)) => {
+                    let mtime: DateTime<Local> = (*mtime).into();
+                    println!(
+                        "Oldest lock: {} ({})",
+                        path.display(),
+                        mtime.format("%Y-%m-%d %H:%M:%S")
+                    );
+                }
+                None => println!("Oldest lock: none"),
+            }
+            println!("Held: {}", info.held);
+            println!("Orphaned: {}", info.orphaned);
+            if info.unreadable > 0 {
+                println!("Unreadable: {}", info.unreadable);
+            }
+            Ok(())
+        }
+    }
+}
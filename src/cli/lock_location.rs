@@ -0,0 +1,154 @@
+//! Where a write of OUTPUT takes its lock, shared by `mutx write` and
+//! `mutx check` so the two cannot drift apart.
+
+use mutx::lock::scope::{in_container, lock_scope_warning, sidecar_lock_path, ScopePolicy};
+use mutx::lock::{ensure_lock_dir, LockPlacement};
+use mutx::{
+    derive_lock_path, derive_lock_path_with_scheme, validate_custom_lock_path, LockBackend,
+    LockScheme, MutxError, Result, SharedGroup,
+};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// The lock options of a write, borrowed from its arguments
+pub(crate) struct LockLocation<'a> {
+    pub output: &'a Path,
+    pub lock_target: &'a Path,
+    pub special: bool,
+    pub lock_file: Option<&'a Path>,
+    pub lock_root: Option<&'a Path>,
+    pub lock_backend: LockBackend,
+    pub lock_beside: bool,
+    pub lock_hash_len: Option<usize>,
+    pub lock_scope: ScopePolicy,
+    pub collaborative: Option<&'a SharedGroup>,
+}
+
+impl LockLocation<'_> {
+    /// The lock path for a write, creating the lock cache and warning about
+    /// a lock other writers would not share
+    pub fn derive(&self) -> Result<PathBuf> {
+        self.resolve(true)
+    }
+
+    /// The lock path `derive` would return, without creating the lock cache
+    /// or logging anything
+    pub fn preview(&self) -> Result<PathBuf> {
+        self.resolve(false)
+    }
+
+    fn resolve(&self, write: bool) -> Result<PathBuf> {
+        if let Some(custom_lock) = self.lock_file {
+            let custom_lock = derive_lock_path(custom_lock, true)?;
+            validate_custom_lock_path(&custom_lock, self.output, self.lock_root)?;
+            return Ok(custom_lock);
+        }
+        if let Some(backend_lock) = self.lock_backend.default_lock_path(self.lock_target) {
+            // Opening a FIFO to lock it would block until a reader shows up
+            if self.special && self.lock_backend == LockBackend::Target {
+                return Err(MutxError::Other(format!(
+                    "--lock-backend target cannot lock special file {}; use --lock-file",
+                    self.output.display()
+                )));
+            }
+            return Ok(backend_lock);
+        }
+        if self.lock_beside {
+            // Never create lock files among device nodes
+            if self.special {
+                return Err(MutxError::Other(format!(
+                    "--lock-beside cannot place a lock beside special file {}; use --lock-file",
+                    self.output.display()
+                )));
+            }
+            // Already beside OUTPUT and shared by its writers, so neither the
+            // scope check nor the --collaborative warning applies
+            return LockPlacement::Sidecar.derive(self.lock_target);
+        }
+
+        let mut scheme = LockScheme::default();
+        if let Some(hash_len) = self.lock_hash_len {
+            scheme = scheme.with_hash_len(hash_len);
+        }
+        let derived = derive_lock_path_with_scheme(self.lock_target, &scheme)?;
+        // The scope checks inspect the lock's directory: a write creates it,
+        // a preview inspects the filesystem it would be created on
+        let lock_dir = if write {
+            ensure_lock_dir(&derived)?;
+            parent_dir(&derived)
+        } else {
+            existing_dir(parent_dir(&derived))
+        };
+        let lock_path =
+            resolve_lock_scope(&derived, lock_dir, self.lock_target, self.lock_scope, write);
+        if let (true, Some(group)) = (write, self.collaborative) {
+            if lock_path == derived {
+                warn!(
+                    "--collaborative {}: lock file {} is in your per-user cache, so other users \
+                     lock a different file; use --lock-file or --lock-backend dotlock",
+                    group,
+                    lock_path.display()
+                );
+            }
+        }
+        Ok(lock_path)
+    }
+}
+
+/// Apply the container lock-scope policy to a lock derived in the cache
+/// directory `lock_dir`
+fn resolve_lock_scope(
+    derived: &Path,
+    lock_dir: &Path,
+    output: &Path,
+    policy: ScopePolicy,
+    log: bool,
+) -> PathBuf {
+    if policy == ScopePolicy::Ignore {
+        return derived.to_path_buf();
+    }
+
+    match lock_scope_warning(lock_dir, output, in_container()) {
+        None => derived.to_path_buf(),
+        Some(message) => match policy {
+            ScopePolicy::Adjacent => {
+                let adjacent = sidecar_lock_path(output);
+                if log {
+                    warn!(
+                        "Lock cache is not shared with {}; locking {} instead",
+                        output.display(),
+                        adjacent.display()
+                    );
+                }
+                adjacent
+            }
+            _ => {
+                if log {
+                    warn!("{}", message);
+                }
+                derived.to_path_buf()
+            }
+        },
+    }
+}
+
+pub(crate) fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+/// `dir`, or its nearest ancestor that exists
+pub(crate) fn existing_dir(dir: &Path) -> &Path {
+    dir.ancestors()
+        .map(|ancestor| {
+            if ancestor.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                ancestor
+            }
+        })
+        .find(|ancestor| ancestor.exists())
+        .unwrap_or(dir)
+}
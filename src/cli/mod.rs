@@ -1,68 +1,248 @@
 mod args;
+mod backups_command;
+mod check_command;
+mod doctor_command;
+mod emit_env;
+mod exec_command;
+mod held_locks;
+mod hold_command;
 mod housekeep_command;
+mod lock_command;
+mod lock_location;
+mod read_command;
+mod restore_command;
+mod schema_command;
+mod signals;
+mod slow_lock;
+mod spinner;
+mod status_command;
+pub mod style;
+mod verify_command;
+mod wait_command;
+mod webhook;
 mod write_command;
 
-pub use args::{Args, Command, HousekeepOperation};
-use mutx::{MutxError, Result};
+pub use args::{
+    Args, BackupsOperation, Command, HoldArgs, HousekeepOperation, LockOperation, SchemaOperation,
+    WriteArgs,
+};
+use mutx::lock::LockPlacement;
+use mutx::{
+    AcquireStats, BackupSuffix, CancelToken, Config, FileLock, FileMode, LockBackend, LockRetry,
+    LockStrategy, MutxError, Result,
+};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Backup suffix from the command line, falling back to the config file and
+/// then the built-in default
+fn resolve_backup_suffix(suffix: Option<BackupSuffix>) -> Result<BackupSuffix> {
+    match suffix {
+        Some(suffix) => Ok(suffix),
+        None => Ok(Config::load()?.backup_suffix()),
+    }
+}
+
+/// Backup directory from the command line, falling back to the config file's
+/// central one; `None` keeps backups beside the file
+fn resolve_backup_dir(dir: Option<PathBuf>) -> Result<Option<PathBuf>> {
+    match dir {
+        Some(dir) => Ok(Some(dir)),
+        None => Ok(Config::load()?.backup_dir),
+    }
+}
+
+/// Lock cache from `MUTX_LOCK_DIR`, falling back to the config file's
+/// `lock_dir`, which is passed on through the environment like
+/// `--state-dir`. Either must be absolute, so that writers in other working
+/// directories find the same locks.
+fn resolve_lock_dir() -> Result<()> {
+    match std::env::var_os(mutx::dirs::LOCK_DIR_ENV).filter(|d| !d.is_empty()) {
+        Some(dir) if !Path::new(&dir).is_absolute() => Err(MutxError::Other(format!(
+            "{} must be absolute, got {}",
+            mutx::dirs::LOCK_DIR_ENV,
+            Path::new(&dir).display()
+        ))),
+        Some(_) => Ok(()),
+        None => {
+            if let Some(dir) = Config::load()?.lock_dir {
+                std::env::set_var(mutx::dirs::LOCK_DIR_ENV, dir);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Lock file permissions from the command line, falling back to the config
+/// file; `None` leaves lock files as created
+fn resolve_lock_mode(mode: Option<FileMode>) -> Result<Option<FileMode>> {
+    match mode {
+        Some(mode) => Ok(Some(mode)),
+        None => Ok(Config::load()?.lock_mode),
+    }
+}
+
+/// Give a held lock's file `mode`. Only warns on failure, e.g. for a lock
+/// file another user created with other permissions: the lock is held
+/// either way.
+fn apply_lock_mode(lock: &FileLock, mode: Option<FileMode>) {
+    if let Some(mode) = mode {
+        if let Err(e) = lock.set_mode(mode) {
+            warn!("{}", e);
+        }
+    }
+}
+
+/// Where derived locks go: beside the file with `--lock-beside`, otherwise
+/// in the lock cache
+fn placement(lock_beside: bool) -> LockPlacement {
+    if lock_beside {
+        LockPlacement::Sidecar
+    } else {
+        LockPlacement::Cache
+    }
+}
+
+/// Acquire a lock, printing each retry with -vvv and how long acquisition
+/// took with -vv. Waits longer than a second show a spinner on a terminal
+/// unless `no_spinner` (or -vvv, whose retry lines it would overwrite).
+/// With `cancel`, the wait ends with [`MutxError::Interrupted`] once it is
+/// cancelled. A lock held by a `mutx exec` this process runs under fails
+/// at once (see [`held_locks`]).
+fn acquire_lock(
+    lock_path: &Path,
+    strategy: LockStrategy,
+    backend: LockBackend,
+    verbose: u8,
+    no_spinner: bool,
+    cancel: Option<&CancelToken>,
+) -> Result<FileLock> {
+    held_locks::check_cycle(lock_path, strategy.is_shared())?;
+    let _spinner = spinner::WaitSpinner::start(lock_path, no_spinner || verbose >= 3);
+    let on_retry = |retry: &LockRetry| {
+        if verbose >= 3 {
+            eprintln!(
+                "Lock busy: {} (retry {} after {:.3}s, next attempt in {}ms)",
+                lock_path.display(),
+                retry.attempt,
+                retry.elapsed.as_secs_f64(),
+                retry.sleep.as_millis()
+            );
+        }
+    };
+    let lock = match cancel {
+        Some(cancel) => {
+            FileLock::acquire_cancellable_observed(lock_path, strategy, backend, cancel, on_retry)?
+        }
+        None => FileLock::acquire_observed(lock_path, strategy, backend, on_retry)?,
+    };
+    if verbose >= 2 {
+        report_lock_stats(&lock.stats());
+    }
+    Ok(lock)
+}
+
+fn report_lock_stats(stats: &AcquireStats) {
+    match stats.last_interval {
+        Some(last) => eprintln!(
+            "Lock acquired in {:.3}s after {} retries (last backoff {}ms)",
+            stats.elapsed.as_secs_f64(),
+            stats.retries,
+            last.as_millis()
+        ),
+        None => eprintln!(
+            "Lock acquired in {:.3}s without retrying",
+            stats.elapsed.as_secs_f64()
+        ),
+    }
+}
 
 pub fn run(args: Args) -> Result<()> {
+    if let Some(dir) = &args.state_dir {
+        // Through the environment, so hooks and child processes agree
+        std::env::set_var(mutx::dirs::STATE_DIR_ENV, dir);
+    }
+    if let Some(fallback) = args.lock_fallback {
+        std::env::set_var(mutx::lock::LOCK_FALLBACK_ENV, fallback.to_string());
+    }
+    resolve_lock_dir()?;
     match args.command {
-        Some(Command::Write {
-            output,
-            input,
-            stream,
-            no_wait,
-            timeout,
-            max_poll_interval,
-            backup,
-            backup_suffix,
-            backup_dir,
-            backup_timestamp,
-            lock_file,
-            follow_symlinks,
-            follow_lock_symlinks,
-            verbose,
-        }) => {
+        Some(Command::Write { output, args }) => {
             // Explicit: mutx write output.txt
-            write_command::execute_write(
-                output,
-                input,
-                stream,
-                no_wait,
-                timeout,
-                max_poll_interval,
-                backup,
-                backup_suffix,
-                backup_dir,
-                backup_timestamp,
-                lock_file,
-                follow_symlinks,
-                follow_lock_symlinks,
-                verbose,
-            )
+            write_command::execute_write(output, *args)
         }
         Some(Command::Housekeep { operation }) => {
             housekeep_command::execute_housekeep(Command::Housekeep { operation })
         }
+        Some(Command::Backups {
+            operation:
+                BackupsOperation::Check {
+                    file,
+                    backup_suffix,
+                    backup_dir,
+                    json,
+                },
+        }) => backups_command::execute_backups_check(file, backup_suffix, backup_dir, json),
+        Some(command @ Command::Restore { .. }) => restore_command::execute_restore(command),
+        Some(command @ Command::Read { .. }) => read_command::execute_read(command),
+        #[cfg(feature = "cluster")]
+        Some(Command::Lockd { listen }) => {
+            let server = mutx::lock::cluster::lockd::LockServer::bind(listen.as_str())?;
+            eprintln!("lockd listening on {}", server.local_addr()?);
+            server.serve()
+        }
+        #[cfg(all(feature = "cluster", unix))]
+        Some(Command::Daemon { socket }) => {
+            let socket = match socket {
+                Some(socket) => socket,
+                None => mutx::lock::cluster::daemon::default_socket_path()?,
+            };
+            let daemon = mutx::lock::cluster::daemon::LockDaemon::bind(&socket)?;
+            eprintln!(
+                "mutx daemon listening on {}",
+                daemon.socket_path().display()
+            );
+            daemon.serve()
+        }
+        Some(Command::Check { file, args }) => check_command::execute_check(file, *args),
+        Some(Command::Doctor { path }) => doctor_command::execute_doctor(path),
+        Some(Command::Lock {
+            operation: Some(operation),
+            ..
+        }) => lock_command::execute_lock(operation),
+        Some(Command::Lock {
+            operation: None,
+            hold,
+        }) => hold_command::execute_hold(hold),
+        Some(Command::Unlock {
+            file,
+            lock_file,
+            lock_beside,
+            force,
+            yes,
+        }) => {
+            if force {
+                hold_command::execute_force_unlock(file, lock_file, lock_beside, yes)
+            } else {
+                hold_command::execute_unlock(file, lock_file, lock_beside)
+            }
+        }
+        Some(Command::Schema { operation }) => schema_command::execute_schema(operation),
+        Some(command @ Command::Status { .. }) => status_command::execute_status(command),
+        Some(command @ Command::Wait { .. }) => wait_command::execute_wait(command),
+        Some(command @ Command::Exec { .. }) => exec_command::execute_exec(command),
+        Some(Command::VerifySignature {
+            file,
+            key,
+            signature,
+        }) => verify_command::execute_verify_signature(file, key, signature),
         None => {
             // Implicit: mutx output.txt
             // Use top-level args for backward compatibility
             write_command::execute_write(
                 args.output
                     .ok_or_else(|| MutxError::Other("OUTPUT argument required".to_string()))?,
-                args.input,
-                args.stream,
-                args.no_wait,
-                args.timeout,
-                args.max_poll_interval,
-                args.backup,
-                args.backup_suffix,
-                args.backup_dir,
-                args.backup_timestamp,
-                args.lock_file,
-                args.follow_symlinks,
-                args.follow_lock_symlinks,
-                args.verbose,
+                args.write,
             )
         }
     }
@@ -0,0 +1,67 @@
+use crate::cli::{acquire_lock, apply_lock_mode, placement, resolve_lock_mode, Command};
+use mutx::{derive_lock_path, LockBackend, LockStrategy, MutxError, Result, TimeoutConfig};
+use std::fs::File;
+use std::io;
+use std::time::Duration;
+
+pub fn execute_read(cmd: Command) -> Result<()> {
+    let Command::Read {
+        file,
+        lock_file,
+        lock_beside,
+        lock_mode,
+        no_wait,
+        timeout,
+        fair,
+        no_spinner,
+        verbose,
+    } = cmd
+    else {
+        return Err(MutxError::Other(
+            "Internal error: expected Read command".to_string(),
+        ));
+    };
+
+    let lock_strategy = if no_wait {
+        LockStrategy::NoWait
+    } else if let Some(timeout_ms) = timeout {
+        LockStrategy::Timeout(TimeoutConfig::new(Duration::from_millis(timeout_ms)))
+    } else {
+        LockStrategy::Wait
+    };
+    let lock_strategy = if fair {
+        lock_strategy.fair()
+    } else {
+        lock_strategy
+    };
+
+    // The writers' lock, taken shared: readers only exclude writers
+    let lock_path = match lock_file {
+        Some(custom) => derive_lock_path(&custom, true)?,
+        None => placement(lock_beside).derive(&file)?,
+    };
+    let lock_mode = resolve_lock_mode(lock_mode)?;
+    let lock = acquire_lock(
+        &lock_path,
+        lock_strategy.shared(),
+        LockBackend::Flock,
+        verbose,
+        no_spinner,
+        None,
+    )?;
+    apply_lock_mode(&lock, lock_mode);
+    if verbose > 0 {
+        eprintln!("Shared lock acquired: {}", lock_path.display());
+    }
+
+    let mut input = File::open(&file).map_err(|e| MutxError::ReadFailed {
+        path: file.clone(),
+        source: e,
+    })?;
+    io::copy(&mut input, &mut io::stdout().lock()).map_err(|e| MutxError::ReadFailed {
+        path: file,
+        source: e,
+    })?;
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use crate::cli::{
+    acquire_lock, apply_lock_mode, placement, resolve_backup_dir, resolve_backup_suffix,
+    resolve_lock_mode, Command,
+};
+use mutx::{
+    derive_lock_path, find_latest_backup, restore_backup, LockBackend, LockStrategy, MutxError,
+    RestoreConfig, Result, TimeoutConfig,
+};
+use std::time::Duration;
+
+pub fn execute_restore(cmd: Command) -> Result<()> {
+    let Command::Restore {
+        file,
+        from,
+        backup_current: _,
+        no_backup_current,
+        backup_suffix,
+        backup_dir,
+        lock_file,
+        lock_beside,
+        lock_mode,
+        no_wait,
+        timeout,
+        no_spinner,
+        verbose,
+    } = cmd
+    else {
+        return Err(MutxError::Other(
+            "Internal error: expected Restore command".to_string(),
+        ));
+    };
+
+    let backup_suffix = resolve_backup_suffix(backup_suffix)?;
+    let backup_dir = resolve_backup_dir(backup_dir)?;
+
+    let backup = match from {
+        Some(backup) => backup,
+        None => find_latest_backup(&file, backup_suffix.as_str(), backup_dir.as_deref())?
+            .ok_or_else(|| MutxError::Other(format!("No backups of {} found", file.display())))?,
+    };
+
+    let lock_strategy = if no_wait {
+        LockStrategy::NoWait
+    } else if let Some(timeout_ms) = timeout {
+        LockStrategy::Timeout(TimeoutConfig::new(Duration::from_millis(timeout_ms)))
+    } else {
+        LockStrategy::Wait
+    };
+
+    // Take the same lock writers use, so a restore never interleaves with a write
+    let lock_path = match lock_file {
+        Some(custom) => derive_lock_path(&custom, true)?,
+        None => placement(lock_beside).derive(&file)?,
+    };
+    let lock_mode = resolve_lock_mode(lock_mode)?;
+    let lock = acquire_lock(
+        &lock_path,
+        lock_strategy,
+        LockBackend::Flock,
+        verbose,
+        no_spinner,
+        None,
+    )?;
+    apply_lock_mode(&lock, lock_mode);
+
+    let report = restore_backup(&RestoreConfig {
+        target: file.clone(),
+        backup,
+        backup_current: !no_backup_current,
+        suffix: backup_suffix,
+        directory: backup_dir,
+        // Always timestamp the captured state so it never replaces the backup
+        // being restored
+        timestamp: true,
+    })?;
+
+    if verbose > 0 {
+        if let Some(previous) = &report.previous {
+            eprintln!("Previous state saved: {}", previous.display());
+        }
+        eprintln!(
+            "Restored {} from {}",
+            file.display(),
+            report.restored_from.display()
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use crate::cli::SchemaOperation;
+use mutx::schema::{self, SCHEMAS};
+use mutx::{MutxError, Result};
+use serde_json::{Map, Value};
+
+pub fn execute_schema(operation: SchemaOperation) -> Result<()> {
+    match operation {
+        SchemaOperation::Dump { name: Some(name) } => {
+            let document = schema::schema(&name).ok_or_else(|| {
+                let known: Vec<&str> = SCHEMAS.iter().map(|(name, _)| *name).collect();
+                MutxError::Other(format!(
+                    "Unknown schema '{}': expected one of {}",
+                    name,
+                    known.join(", ")
+                ))
+            })?;
+            print!("{}", document);
+            Ok(())
+        }
+        SchemaOperation::Dump { name: None } => {
+            let mut all = Map::new();
+            for (name, document) in SCHEMAS {
+                let document: Value = serde_json::from_str(document).map_err(|e| {
+                    MutxError::Other(format!("Invalid built-in schema {}: {}", name, e))
+                })?;
+                all.insert(name.to_string(), document);
+            }
+            let json = serde_json::to_string_pretty(&all)
+                .map_err(|e| MutxError::Other(format!("Cannot encode schemas: {}", e)))?;
+            println!("{}", json);
+            Ok(())
+        }
+    }
+}
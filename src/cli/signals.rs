@@ -0,0 +1,59 @@
+//! Turning SIGINT and SIGTERM into a clean `Interrupted` exit.
+//!
+//! Killed outright, a write leaves its temp file behind and its holder
+//! payload in the lock file. Instead the handler only sets a flag. It is
+//! installed without `SA_RESTART`, so a lock wait blocked in `flock` and a
+//! blocking read of the input both return `EINTR` and check it; polling
+//! lock waits check it between attempts.
+//! Returning [`MutxError::Interrupted`](mutx::MutxError) then drops the
+//! writer (removing the temp file) and the lock, and exits with code 3.
+//! A second signal kills the process as usual, in case the first is stuck
+//! somewhere the flag is not checked.
+
+use mutx::CancelToken;
+#[cfg(unix)]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(unix)]
+use std::sync::{Arc, OnceLock};
+
+#[cfg(unix)]
+static INTERRUPTED: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+/// Install the handlers, returning the token they cancel. Elsewhere than
+/// Unix the token is never cancelled and Ctrl-C ends the process directly.
+#[cfg(unix)]
+pub fn install() -> CancelToken {
+    let flag = INTERRUPTED
+        .get_or_init(|| Arc::new(AtomicBool::new(false)))
+        .clone();
+    for signal in [libc::SIGINT, libc::SIGTERM] {
+        // SAFETY: the handler only touches an already initialized atomic
+        // and async-signal-safe libc calls
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            libc::sigemptyset(&mut action.sa_mask);
+            libc::sigaction(signal, &action, std::ptr::null_mut());
+        }
+    }
+    CancelToken::from(flag)
+}
+
+#[cfg(not(unix))]
+pub fn install() -> CancelToken {
+    CancelToken::new()
+}
+
+#[cfg(unix)]
+extern "C" fn on_signal(signal: libc::c_int) {
+    let Some(flag) = INTERRUPTED.get() else {
+        return;
+    };
+    if flag.swap(true, Ordering::SeqCst) {
+        // SAFETY: signal and raise are async-signal-safe
+        unsafe {
+            libc::signal(signal, libc::SIG_DFL);
+            libc::raise(signal);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! Early warning when a lock wait runs long, for `--warn-after`.
+
+use crate::cli::webhook::WebhookUrl;
+use mutx::schema::{self, LockWaitReport, Versioned};
+use mutx::LockHolder;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// When to warn about a slow lock wait, and where besides stderr
+#[derive(Debug, Clone)]
+pub struct SlowLockAlert {
+    pub after: Duration,
+    pub webhook: Option<WebhookUrl>,
+}
+
+/// Watches one lock wait until dropped, warning once if it outlasts the
+/// alert's threshold. The wait itself goes on; only `--timeout` ends it.
+///
+/// Dropping the watch stops it without waiting: a webhook still in flight
+/// finishes on its own thread rather than hold up the write under the lock.
+pub struct SlowLockWatch {
+    stop: Option<Sender<()>>,
+}
+
+impl SlowLockWatch {
+    pub fn start(lock_path: &Path, alert: &SlowLockAlert) -> Self {
+        let lock_path = lock_path.to_path_buf();
+        let alert = alert.clone();
+        let start = Instant::now();
+        let (stop, stopped) = mpsc::channel::<()>();
+        std::thread::spawn(move || {
+            if stopped.recv_timeout(alert.after) == Err(RecvTimeoutError::Timeout) {
+                raise(lock_path, start.elapsed(), &alert);
+            }
+        });
+        SlowLockWatch { stop: Some(stop) }
+    }
+}
+
+impl Drop for SlowLockWatch {
+    fn drop(&mut self) {
+        // Wakes the thread if it is still waiting; it is not joined
+        self.stop.take();
+    }
+}
+
+fn raise(lock_path: PathBuf, waited: Duration, alert: &SlowLockAlert) {
+    // The holder can't be read on Windows while it holds the lock
+    let holder = LockHolder::read(&lock_path).ok().flatten();
+    let report = LockWaitReport {
+        lock_path,
+        waited_ms: waited.as_millis() as u64,
+        threshold_ms: alert.after.as_millis() as u64,
+        holder_pid: holder.as_ref().map(|h| h.pid),
+        holder_host: holder.as_ref().map(|h| h.hostname.clone()),
+        holder_command: holder.and_then(|h| h.command),
+    };
+    warn!(
+        lock = %report.lock_path.display(),
+        waited_ms = report.waited_ms,
+        holder_pid = report.holder_pid,
+        "Still waiting for lock after {:.1}s; continuing",
+        waited.as_secs_f64()
+    );
+
+    if let Some(webhook) = &alert.webhook {
+        let result = Versioned::new(schema::LOCK_WAIT, &report)
+            .to_json()
+            .map_err(|e| e.to_string())
+            .and_then(|body| webhook.post_json(&body).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            warn!("Cannot notify webhook {}: {}", webhook, e);
+        }
+    }
+}
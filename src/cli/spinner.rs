@@ -0,0 +1,114 @@
+//! Single-line spinner shown while an interactive command waits for a lock.
+
+use mutx::LockHolder;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// How long to wait silently before showing the spinner, so uncontended or
+/// briefly contended locks print nothing
+const SHOW_AFTER: Duration = Duration::from_secs(1);
+const FRAME_INTERVAL: Duration = Duration::from_millis(100);
+const FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// Spinner redrawn on stderr until dropped, when its line is cleared
+pub struct WaitSpinner {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl WaitSpinner {
+    /// Start a spinner for a wait on `lock_path`, unless `disabled` or
+    /// stderr is not a terminal
+    pub fn start(lock_path: &Path, disabled: bool) -> Option<Self> {
+        if disabled || !io::stderr().is_terminal() {
+            return None;
+        }
+        let lock_path = lock_path.to_path_buf();
+        let (stop, stopped) = mpsc::channel::<()>();
+        let thread = std::thread::spawn(move || spin(&lock_path, &stopped));
+        Some(WaitSpinner {
+            stop: Some(stop),
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for WaitSpinner {
+    fn drop(&mut self) {
+        // Dropping the sender wakes the thread, which clears its line
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn spin(lock_path: &Path, stopped: &mpsc::Receiver<()>) {
+    let start = Instant::now();
+    if stopped.recv_timeout(SHOW_AFTER) != Err(RecvTimeoutError::Timeout) {
+        return;
+    }
+
+    let mut stderr = io::stderr();
+    let mut frame = 0;
+    loop {
+        let line = status_line(lock_path, start.elapsed(), FRAMES[frame % FRAMES.len()]);
+        let _ = write!(stderr, "\r\x1b[2K{}", line);
+        let _ = stderr.flush();
+        frame += 1;
+        if stopped.recv_timeout(FRAME_INTERVAL) != Err(RecvTimeoutError::Timeout) {
+            break;
+        }
+    }
+    let _ = write!(stderr, "\r\x1b[2K");
+    let _ = stderr.flush();
+}
+
+fn status_line(lock_path: &Path, elapsed: Duration, frame: char) -> String {
+    let mut line = format!(
+        "{} Waiting {:.1}s for lock {}",
+        frame,
+        elapsed.as_secs_f64(),
+        lock_path.display()
+    );
+    // The holder can't be read on Windows while it holds the lock
+    if let Ok(Some(holder)) = LockHolder::read(lock_path) {
+        line.push_str(&format!(", held by PID {}", holder.pid));
+        if let Some(command) = &holder.command {
+            line.push_str(&format!(" ({})", command));
+        }
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_status_line_names_holder() {
+        let temp = TempDir::new().unwrap();
+        let lock_path = temp.path().join("out.lock");
+        let mut holder = LockHolder::current();
+        holder.pid = 4242;
+        holder.command = Some("deploy".to_string());
+        std::fs::write(&lock_path, serde_json::to_vec(&holder).unwrap()).unwrap();
+
+        let line = status_line(&lock_path, Duration::from_millis(2500), '⠋');
+        assert!(line.starts_with("⠋ Waiting 2.5s for lock "));
+        assert!(line.ends_with(", held by PID 4242 (deploy)"));
+    }
+
+    #[test]
+    fn test_status_line_without_holder() {
+        let temp = TempDir::new().unwrap();
+        let lock_path = temp.path().join("out.lock");
+
+        let line = status_line(&lock_path, Duration::from_secs(1), '⠙');
+        assert!(!line.contains("held by"));
+    }
+}
@@ -0,0 +1,54 @@
+use crate::cli::{placement, Command};
+use mutx::schema::{self, LockStatusReport, Versioned};
+use mutx::{derive_lock_path, FileLock, LockHolder, LockStatus, MutxError, Result};
+
+pub fn execute_status(cmd: Command) -> Result<()> {
+    let Command::Status {
+        file,
+        lock_file,
+        lock_beside,
+        json,
+    } = cmd
+    else {
+        return Err(MutxError::Other(
+            "Internal error: expected Status command".to_string(),
+        ));
+    };
+
+    let lock_path = match lock_file {
+        Some(custom) => derive_lock_path(&custom, true)?,
+        None => placement(lock_beside).derive(&file)?,
+    };
+    let status = FileLock::status(&lock_path)?;
+
+    if json {
+        let report = LockStatusReport {
+            file,
+            lock_path,
+            status,
+        };
+        println!(
+            "{}",
+            Versioned::new(schema::LOCK_STATUS, &report).to_json()?
+        );
+        return Ok(());
+    }
+
+    match status {
+        LockStatus::Free => println!("Not locked ({})", lock_path.display()),
+        LockStatus::Held { shared: true, .. } => {
+            println!("Locked by readers ({})", lock_path.display())
+        }
+        LockStatus::Held { .. } => match LockHolder::read(&lock_path)? {
+            Some(holder) => {
+                print!("Locked by {}", holder);
+                if holder.is_alive() == Some(false) {
+                    print!(" (not running)");
+                }
+                println!(" ({})", lock_path.display());
+            }
+            None => println!("Locked, no holder recorded ({})", lock_path.display()),
+        },
+    }
+    Ok(())
+}
@@ -0,0 +1,132 @@
+//! Colored output, as chosen with `--color`.
+//!
+//! With the default `auto`, a stream is colored only when it is a terminal,
+//! `NO_COLOR` is unset or empty and `TERM` is not `dumb`, so piped output and
+//! logs never carry escape codes. Everything painted here reads the same
+//! without color: the color only repeats what the text already says.
+
+use mutx::{MutxError, Result};
+use std::fmt;
+use std::io::{self, IsTerminal};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// When to color output (`--color`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Only on terminals (default)
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl fmt::Display for ColorChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColorChoice::Auto => write!(f, "auto"),
+            ColorChoice::Always => write!(f, "always"),
+            ColorChoice::Never => write!(f, "never"),
+        }
+    }
+}
+
+impl FromStr for ColorChoice {
+    type Err = MutxError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(MutxError::Other(format!(
+                "Unknown color choice '{}': expected one of auto, always, never",
+                s
+            ))),
+        }
+    }
+}
+
+static CHOICE: AtomicU8 = AtomicU8::new(0);
+
+/// Use `choice` for all output from here on
+pub fn set_color(choice: ColorChoice) {
+    CHOICE.store(choice as u8, Ordering::Relaxed);
+}
+
+fn color_choice() -> ColorChoice {
+    match CHOICE.load(Ordering::Relaxed) {
+        1 => ColorChoice::Always,
+        2 => ColorChoice::Never,
+        _ => ColorChoice::Auto,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Whether output to `stream` is colored
+pub fn enabled(stream: Stream) -> bool {
+    match color_choice() {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+            let dumb = std::env::var_os("TERM").is_some_and(|term| term == "dumb");
+            let terminal = match stream {
+                Stream::Stdout => io::stdout().is_terminal(),
+                Stream::Stderr => io::stderr().is_terminal(),
+            };
+            terminal && !no_color && !dumb
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// Something succeeded or was done
+    Ok,
+    /// Something was skipped or only would have been done
+    Warn,
+    /// Something failed
+    Fail,
+    /// Of little interest
+    Dim,
+}
+
+impl Style {
+    fn sgr(self) -> &'static str {
+        match self {
+            Style::Ok => "32",
+            Style::Warn => "33",
+            Style::Fail => "1;31",
+            Style::Dim => "2",
+        }
+    }
+}
+
+/// `text` in `style` if `stream` is colored, else unchanged
+pub fn paint(stream: Stream, style: Style, text: impl fmt::Display) -> String {
+    if enabled(stream) {
+        format!("\x1b[{}m{}\x1b[0m", style.sgr(), text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// The `[  ok]`-style tag of a check result printed to stdout, padded
+/// before it is painted so columns line up either way
+pub fn tag(style: Style, label: &str) -> String {
+    format!(
+        "[{}]",
+        paint(Stream::Stdout, style, format!("{:>4}", label))
+    )
+}
+
+/// `Error:`, leading an error printed to stderr
+pub fn error_header() -> String {
+    paint(Stream::Stderr, Style::Fail, "Error:")
+}
@@ -0,0 +1,12 @@
+use mutx::{verify_signature, Result};
+use std::path::PathBuf;
+
+pub fn execute_verify_signature(
+    file: PathBuf,
+    key: PathBuf,
+    signature: Option<PathBuf>,
+) -> Result<()> {
+    let kind = verify_signature(&file, &key, signature.as_deref())?;
+    println!("{}: OK ({} signature)", file.display(), kind);
+    Ok(())
+}
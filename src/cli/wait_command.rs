@@ -0,0 +1,56 @@
+use crate::cli::{acquire_lock, placement, Command};
+use mutx::{
+    derive_lock_path, FileLock, LockBackend, LockHolder, LockStrategy, MutxError, Result,
+    TimeoutConfig,
+};
+use std::time::{Duration, Instant};
+
+/// How often the lock is probed while waiting without --acquire
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+pub fn execute_wait(cmd: Command) -> Result<()> {
+    let Command::Wait {
+        file,
+        lock_file,
+        lock_beside,
+        timeout,
+        acquire,
+    } = cmd
+    else {
+        return Err(MutxError::Other(
+            "Internal error: expected Wait command".to_string(),
+        ));
+    };
+
+    let lock_path = match lock_file {
+        Some(custom) => derive_lock_path(&custom, true)?,
+        None => placement(lock_beside).derive(&file)?,
+    };
+
+    if acquire {
+        let strategy = match timeout {
+            Some(timeout) => LockStrategy::Timeout(TimeoutConfig::new(timeout)),
+            None => LockStrategy::Wait,
+        };
+        let lock = acquire_lock(&lock_path, strategy, LockBackend::Flock, 0, false, None)?;
+        drop(lock);
+        return Ok(());
+    }
+
+    // Probing never creates the lock file, so waiting on a file nobody
+    // writes leaves nothing behind
+    let start = Instant::now();
+    while FileLock::status(&lock_path)?.is_held() {
+        if let Some(timeout) = timeout {
+            if start.elapsed() >= timeout {
+                return Err(MutxError::LockTimeout {
+                    holder: LockHolder::read(&lock_path).ok().flatten().map(Box::new),
+                    path: lock_path,
+                    duration: timeout,
+                });
+            }
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+    Ok(())
+}
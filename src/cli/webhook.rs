@@ -0,0 +1,123 @@
+//! Minimal HTTP client for posting JSON notifications to a webhook.
+//!
+//! Only plain `http://` URLs are supported; relay through a local endpoint
+//! to reach an HTTPS service.
+
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// How long connecting, sending and waiting for the response may each take
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// An `http://host[:port][/path]` URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookUrl {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl FromStr for WebhookUrl {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rest = s.strip_prefix("http://").ok_or_else(|| {
+            "only http:// webhook URLs are supported; relay HTTPS through a local endpoint"
+                .to_string()
+        })?;
+        let (authority, path) = match rest.find('/') {
+            Some(slash) => rest.split_at(slash),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            // Not the colons of a bracketed IPv6 address
+            Some((host, port)) if !port.ends_with(']') => {
+                let port = port
+                    .parse()
+                    .map_err(|_| format!("invalid port in webhook URL: {}", s))?;
+                (host, port)
+            }
+            _ => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(format!("missing host in webhook URL: {}", s));
+        }
+        Ok(WebhookUrl {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for WebhookUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "http://{}:{}{}", self.host, self.port, self.path)
+    }
+}
+
+impl WebhookUrl {
+    /// POST `body` as JSON, failing unless the response status is 2xx
+    pub fn post_json(&self, body: &str) -> io::Result<()> {
+        let host = self.host.trim_start_matches('[').trim_end_matches(']');
+        let addr = (host, self.port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "host has no address"))?;
+        let mut stream = TcpStream::connect_timeout(&addr, TIMEOUT)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: {}:{}\r\nUser-Agent: mutx/{}\r\n\
+             Content-Type: application/json\r\nContent-Length: {}\r\n\
+             Connection: close\r\n\r\n{}",
+            self.path,
+            self.host,
+            self.port,
+            env!("CARGO_PKG_VERSION"),
+            body.len(),
+            body
+        )?;
+        stream.flush()?;
+
+        let mut status_line = String::new();
+        BufReader::new(stream).read_line(&mut status_line)?;
+        match status_line.split_whitespace().nth(1) {
+            Some(code) if code.starts_with('2') => Ok(()),
+            Some(code) => Err(io::Error::other(format!("webhook answered {}", code))),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "webhook sent no HTTP response",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_url() {
+        let url: WebhookUrl = "http://alerts.internal:8080/hooks/mutx".parse().unwrap();
+        assert_eq!(url.to_string(), "http://alerts.internal:8080/hooks/mutx");
+
+        let url: WebhookUrl = "http://127.0.0.1".parse().unwrap();
+        assert_eq!(url.to_string(), "http://127.0.0.1:80/");
+
+        let url: WebhookUrl = "http://[::1]".parse().unwrap();
+        assert_eq!(url.to_string(), "http://[::1]:80/");
+    }
+
+    #[test]
+    fn test_parse_rejects_unsupported_urls() {
+        assert!("https://example.com/hook".parse::<WebhookUrl>().is_err());
+        assert!("http://:80/hook".parse::<WebhookUrl>().is_err());
+        assert!("http://host:port/hook".parse::<WebhookUrl>().is_err());
+    }
+}
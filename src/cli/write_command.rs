@@ -1,49 +1,186 @@
+use crate::cli::emit_env::EnvReport;
+use crate::cli::lock_location::LockLocation;
+use crate::cli::slow_lock::{SlowLockAlert, SlowLockWatch};
+use crate::cli::{
+    acquire_lock, apply_lock_mode, resolve_backup_dir, resolve_backup_suffix, resolve_lock_mode,
+    signals, WriteArgs,
+};
+use mutx::lock::propagation::check_lock_propagation;
+use mutx::parse::format_size;
+use mutx::schema::{self, Versioned};
+use mutx::systemd::{Notifier, DEFAULT_KEEPALIVE_INTERVAL};
+use mutx::utils::{names_directory, normalize_path, resolve_symlink_target};
+use mutx::write::{file_type_name, is_readonly, is_special_file};
 use mutx::{
-    check_lock_symlink, check_symlink, create_backup, derive_lock_path, validate_backup_suffix,
-    validate_lock_path, AtomicWriter, BackupConfig, FileLock, LockStrategy, MutxError, Result,
-    TimeoutConfig, WriteMode,
+    check_lock_symlink, check_symlink, create_backup, reclaim_backups, validate_lock_path,
+    write_digest_file, write_signature_file, AtomicWriter, BackupConfig, BackupOutcome,
+    BackupSuffix, DigestAlgorithm, FileLock, LockBackend, LockPolicy, LockStrategy, ModePolicy,
+    MutxError, Result, SigningKey, SymlinkPolicy, TimeoutConfig, TransformRegistry, WriteMode,
 };
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{self, Read};
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 use std::time::Duration;
+use tracing::{debug, warn};
+
+pub fn execute_write(output: PathBuf, args: WriteArgs) -> Result<()> {
+    let WriteArgs {
+        input,
+        input_allow_special,
+        into_dir,
+        stream,
+        in_memory,
+        transforms,
+        compress,
+        decompress,
+        emit_digest,
+        sign,
+        #[cfg(all(feature = "fd-sink", unix))]
+        sink_fd,
+        file_mode,
+        no_preserve_mode,
+        collaborative,
+        respect_readonly,
+        reclaim_on_enospc,
+        no_wait,
+        timeout,
+        max_poll_interval,
+        break_stale_locks,
+        reclaim_own,
+        lock_lease,
+        fair,
+        no_spinner,
+        warn_after,
+        warn_webhook,
+        passthrough_special,
+        rename_retry,
+        no_lock,
+        critical_section,
+        lock_file,
+        lock_root,
+        lock_hash_len,
+        lock_beside,
+        lock_mode,
+        lock_backend,
+        #[cfg(feature = "cluster")]
+        lock_server,
+        #[cfg(feature = "cluster")]
+        lock_key,
+        #[cfg(feature = "cluster")]
+        fencing_xattr,
+        lock_scope,
+        strict_locking,
+        follow_symlinks,
+        follow_lock_symlinks,
+        write_through_symlink,
+        replace_symlink,
+        backup,
+        backup_suffix,
+        backup_dir,
+        backup_timestamp,
+        backup_delta,
+        // The default; only there to be explicit about it
+        backup_required: _,
+        backup_best_effort,
+        notify_systemd,
+        require,
+        max_size,
+        min_free,
+        json,
+        emit_env,
+        verbose,
+    } = args;
+
+    let output = normalize_output(output, into_dir)?;
+    let output = resolve_output(output, input.as_deref(), into_dir)?;
 
-#[allow(clippy::too_many_arguments)]
-pub fn execute_write(
-    output: PathBuf,
-    input: Option<PathBuf>,
-    stream: bool,
-    no_wait: bool,
-    timeout: Option<u64>,
-    max_poll_interval: Option<u64>,
-    backup: bool,
-    backup_suffix: String,
-    backup_dir: Option<PathBuf>,
-    backup_timestamp: bool,
-    lock_file: Option<PathBuf>,
-    follow_symlinks: bool,
-    follow_lock_symlinks: bool,
-    verbose: u8,
-) -> Result<()> {
     // Determine symlink policy
-    let follow_symlinks_effective = follow_lock_symlinks || follow_symlinks;
+    let follow_symlinks_effective =
+        follow_lock_symlinks || follow_symlinks || write_through_symlink || replace_symlink;
+    let symlink_policy = if replace_symlink {
+        SymlinkPolicy::Replace
+    } else {
+        SymlinkPolicy::WriteThrough
+    };
     let follow_lock_symlinks_effective = follow_lock_symlinks;
 
     // Validate input file exists if provided
     if let Some(input_path) = &input {
-        if !input_path.exists() {
-            return Err(MutxError::PathNotFound(input_path.clone()));
+        validate_input(input_path, input_allow_special, follow_symlinks_effective)?;
+    }
+
+    // FIFOs and devices cannot be renamed over; with --passthrough-special
+    // they are written in place, through a symlink such as /dev/stdout
+    // since the link itself is not replaced
+    let special = is_special_file(&output);
+    if special {
+        if !passthrough_special {
+            return Err(MutxError::SpecialFile(output));
+        }
+        if backup {
+            return Err(MutxError::Other(format!(
+                "Cannot back up special file {}",
+                output.display()
+            )));
         }
-        if !input_path.is_file() {
-            return Err(MutxError::NotAFile(input_path.clone()));
+        if verbose > 0 {
+            eprintln!(
+                "Target is a special file, writing to it directly (not atomic): {}",
+                output.display()
+            );
         }
+    } else {
+        // Check if output is a symlink
+        check_symlink(&output, follow_symlinks_effective)?;
+    }
+
+    // Everything from here on (lock derivation, backups, the rename) works
+    // on the file actually replaced, so two links to one file share a lock
+    let output = if special {
+        output
+    } else {
+        let target = symlink_policy.target_for(&output)?;
+        if target != output && verbose > 0 {
+            eprintln!(
+                "Writing through symlink {} to {}",
+                output.display(),
+                target.display()
+            );
+        }
+        target
+    };
+    // A replaced link still locks the file it pointed to, so writers going
+    // through the link and writers naming the file directly contend
+    let lock_target = if special {
+        output.clone()
+    } else {
+        resolve_symlink_target(&output)?
+    };
 
-        // Check if input is a symlink
-        check_symlink(input_path, follow_symlinks_effective)?;
+    // Replacing a file only needs write access to its directory, so the
+    // read-only bit does not stop the write unless asked to
+    if !special && is_readonly(&output) {
+        if respect_readonly {
+            return Err(MutxError::TargetReadOnly(output));
+        }
+        if verbose > 0 {
+            eprintln!(
+                "Target is read-only, replacing it anyway: {} (use --respect-readonly to refuse)",
+                output.display()
+            );
+        }
     }
 
-    // Check if output is a symlink
-    check_symlink(&output, follow_symlinks_effective)?;
+    // Resolve the backup suffix and directory if backups are made or
+    // reclaimed (fail fast on a bad config file before lock)
+    let (backup_suffix, backup_dir) = if backup || reclaim_on_enospc {
+        (
+            resolve_backup_suffix(backup_suffix)?,
+            resolve_backup_dir(backup_dir)?,
+        )
+    } else {
+        (BackupSuffix::default(), None)
+    };
 
     // Validate backup directory is a directory if provided
     if let Some(backup_dir_ref) = &backup_dir {
@@ -52,11 +189,14 @@ pub fn execute_write(
         }
     }
 
-    // Validate backup suffix if backup is requested (fail fast before lock)
-    if backup {
-        validate_backup_suffix(&backup_suffix)?;
+    if let Some(target) = emit_env {
+        target.check()?;
     }
 
+    // Load the signing key before locking, so a bad key or password fails
+    // the write instead of leaving an unsigned target
+    let signing_key = sign.as_deref().map(SigningKey::load).transpose()?;
+
     // Determine lock strategy
     let lock_strategy = if no_wait {
         LockStrategy::NoWait
@@ -71,51 +211,316 @@ pub fn execute_write(
     } else {
         LockStrategy::Wait
     };
+    let lock_strategy = if break_stale_locks {
+        lock_strategy.break_stale()
+    } else {
+        lock_strategy
+    };
+    let lock_strategy = if reclaim_own {
+        lock_strategy.reclaim_own()
+    } else {
+        lock_strategy
+    };
+    let lock_strategy = if fair {
+        lock_strategy.fair()
+    } else {
+        lock_strategy
+    };
+    let lock_strategy = match lock_lease {
+        Some(ttl) => lock_strategy.lease(ttl),
+        None => lock_strategy,
+    };
 
-    // Determine lock file path
-    let lock_path = if let Some(custom_lock) = lock_file {
-        custom_lock
+    let lock_mode = resolve_lock_mode(lock_mode)?;
+    let lock_policy = if no_lock {
+        LockPolicy::None
     } else {
-        derive_lock_path(&output, false)?
+        LockPolicy::Lock(lock_strategy)
     };
 
-    // Validate lock path
-    validate_lock_path(&lock_path, &output)?;
+    // Determine lock file path; --no-lock derives nothing, so nothing is
+    // created in the lock cache
+    let lock_path = if !lock_policy.locks() {
+        None
+    } else {
+        let location = LockLocation {
+            output: &output,
+            lock_target: &lock_target,
+            special,
+            lock_file: lock_file.as_deref(),
+            lock_root: lock_root.as_deref(),
+            lock_backend,
+            lock_beside,
+            lock_hash_len,
+            lock_scope,
+            collaborative: collaborative.as_ref(),
+        };
+        Some(location.derive()?)
+    };
 
-    // Check if lock path is a symlink
-    check_lock_symlink(&lock_path, follow_lock_symlinks_effective)?;
+    if let Some(lock_path) = &lock_path {
+        // Validate lock path; the target backend locks OUTPUT by design
+        if lock_backend != LockBackend::Target {
+            validate_lock_path(lock_path, &output)?;
+        }
 
-    // Acquire lock
-    let _lock = FileLock::acquire(&lock_path, lock_strategy)?;
+        // Check if lock path is a symlink
+        check_lock_symlink(lock_path, follow_lock_symlinks_effective)?;
+    }
 
-    if verbose > 0 {
-        eprintln!("Lock acquired: {}", lock_path.display());
+    // Dotlocks rely on link(2), which is safe on network filesystems
+    let exclusive = match &lock_path {
+        None => false,
+        Some(lock_path) if lock_backend.is_file_lock() || lock_backend == LockBackend::Target => {
+            match check_lock_propagation(lock_path, &output) {
+                Ok(()) => true,
+                Err(e) if strict_locking => return Err(e),
+                Err(_) => false,
+            }
+        }
+        Some(_) => true,
+    };
+
+    // Determine write mode
+    let mode = if stream {
+        WriteMode::Streaming
+    } else if in_memory {
+        WriteMode::InMemory
+    } else {
+        WriteMode::Auto
+    };
+
+    let mode_policy = if no_preserve_mode {
+        ModePolicy::Umask
+    } else {
+        file_mode.unwrap_or_default()
+    };
+
+    let mut writer = AtomicWriter::new(&output, mode)?
+        .default_mode_policy(mode_policy)
+        .with_follow_symlinks(follow_symlinks_effective)
+        .with_respect_readonly(respect_readonly)
+        .with_passthrough_special(passthrough_special)
+        .with_exclusive(exclusive);
+    if let Some(group) = &collaborative {
+        writer = writer.with_shared_group(group.clone());
+    }
+    if let Some(rename_retry) = rename_retry {
+        writer = writer.with_rename_retry(rename_retry);
+    }
+    if let Some(max_size) = max_size {
+        writer = writer.with_max_size(max_size);
+    }
+    if let Some(min_free) = min_free {
+        writer = writer.with_min_free(min_free);
     }
+    if reclaim_on_enospc {
+        let (target, suffix, dir) = (output.clone(), backup_suffix.clone(), backup_dir.clone());
+        writer = writer.with_space_reclaimer(move |needed| {
+            let freed = reclaim_backups(&target, &suffix, dir.as_deref(), needed)?;
+            if verbose > 0 {
+                eprintln!(
+                    "Out of space: removed old backups of {} ({}), retrying",
+                    target.display(),
+                    format_size(freed)
+                );
+            }
+            Ok(freed)
+        });
+    }
+    let registry = TransformRegistry::default();
+    for name in &transforms {
+        writer = writer.with_transform(registry.create(name)?);
+    }
+    if let Some(compression) = compress {
+        writer = writer
+            .with_transform(compression.transform()?)
+            .with_compression_xattr(Some(compression.format));
+    }
+    // MUTX_CHANGED compares digests of the old and new content
+    let change_digest = emit_env.map(|_| emit_digest.unwrap_or(DigestAlgorithm::Sha256));
+    if let Some(algorithm) = emit_digest.or(change_digest) {
+        writer = writer.with_digest(algorithm);
+    }
+    #[cfg(all(feature = "fd-sink", unix))]
+    if let Some(fd) = sink_fd {
+        writer = writer.with_sink(Box::new(mutx::sink::FdSink::from_fd(fd)?));
+    }
+    writer.guarantees().require(&require, &output)?;
+
+    // Keep systemd from timing us out while we wait for the lock and stream
+    let _keepalive = if notify_systemd {
+        match Notifier::from_env() {
+            Some(notifier) => Some(notifier.keepalive(
+                DEFAULT_KEEPALIVE_INTERVAL,
+                &format!("mutx writing {}", output.display()),
+            )),
+            None => {
+                debug!("--notify-systemd given but NOTIFY_SOCKET is not set");
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let slow_lock_alert = warn_after.map(|after| SlowLockAlert {
+        after,
+        webhook: warn_webhook,
+    });
+
+    // From here on Ctrl-C and SIGTERM end the write cleanly (see signals)
+    let interrupted = signals::install();
+
+    // Acquire lock: before reading the input, or with a shorter
+    // --critical-section once it is staged
+    let take_lock = || -> Result<Option<FileLock>> {
+        let (LockPolicy::Lock(lock_strategy), Some(lock_path)) = (&lock_policy, &lock_path) else {
+            // Loud on purpose: nothing stops another writer racing this one
+            warn!(
+                "--no-lock: not locking {}; concurrent writers are not excluded",
+                output.display()
+            );
+            return Ok(None);
+        };
+        let watch = slow_lock_alert
+            .as_ref()
+            .map(|alert| SlowLockWatch::start(lock_path, alert));
+        #[cfg(feature = "cluster")]
+        let lock = if lock_backend == LockBackend::Remote {
+            let server = lock_server.clone().ok_or_else(|| {
+                MutxError::Other("--lock-backend remote requires --lock-server".to_string())
+            })?;
+            let key = match lock_key.clone() {
+                Some(key) => key,
+                None => mutx::lock::canonical_output_path(&output)?
+                    .to_string_lossy()
+                    .into_owned(),
+            };
+            let (service, server) = lock_service(&server)?;
+            let lock = mutx::FileLock::acquire_remote(&*service, &key, lock_strategy.clone())?;
+            if verbose > 0 {
+                eprintln!("Lock acquired: {} on {}", key, server);
+            }
+            if verbose > 1 {
+                crate::cli::report_lock_stats(&lock.stats());
+            }
+            lock
+        } else {
+            acquire_lock(
+                lock_path,
+                lock_strategy.clone(),
+                lock_backend,
+                verbose,
+                no_spinner,
+                Some(&interrupted),
+            )?
+        };
+        #[cfg(not(feature = "cluster"))]
+        let lock = acquire_lock(
+            lock_path,
+            lock_strategy.clone(),
+            lock_backend,
+            verbose,
+            no_spinner,
+            Some(&interrupted),
+        )?;
+        drop(watch);
+
+        // Remote waits are not cancellable, so a signal may have arrived since
+        if interrupted.is_cancelled() {
+            return Err(MutxError::Interrupted);
+        }
+        let mut lock = lock;
+        lock.record_target(&output);
+
+        if verbose > 0 && lock_backend != LockBackend::Remote {
+            eprintln!("Lock acquired: {}", lock_path.display());
+        }
+
+        apply_lock_mode(&lock, lock_mode);
+
+        // Other members open the lock file for writing, so it must be group
+        // writable; dotlocks only live while held
+        if let Some(group) = collaborative
+            .as_ref()
+            .filter(|_| lock_backend.is_file_lock())
+        {
+            group.share_file(lock_path)?;
+        }
+        Ok(Some(lock))
+    };
+
+    // Digest of the replaced content for MUTX_CHANGED, and the backup if
+    // requested
+    let create = || -> Result<PathBuf> {
+        if let (Some(group), Some(dir)) = (&collaborative, &backup_dir) {
+            fs::create_dir_all(dir).map_err(|e| MutxError::BackupFailed {
+                path: output.clone(),
+                source: e,
+            })?;
+            group.share_dir(dir)?;
+        }
 
-    // Create backup if requested
-    if backup {
         let backup_config = BackupConfig {
             source: output.clone(),
-            suffix: backup_suffix,
-            directory: backup_dir,
+            suffix: backup_suffix.clone(),
+            directory: backup_dir.clone(),
             timestamp: backup_timestamp,
+            prune_on_enospc: reclaim_on_enospc.then_some(1),
+            delta: backup_delta,
         };
 
-        let backup_path = create_backup(&backup_config)?;
-        if verbose > 0 {
-            eprintln!("Backup created: {}", backup_path.display());
+        let created = create_backup(&backup_config)?;
+        if let Some(group) = &collaborative {
+            group.share_file(&created)?;
+        }
+        Ok(created)
+    };
+    let back_up = || -> Result<(Option<String>, Option<BackupOutcome>)> {
+        // Reading a special file back would consume or block on it
+        let previous_digest = match change_digest {
+            Some(algorithm) if !special => algorithm.digest_file(&output)?,
+            _ => None,
+        };
+        if !backup {
+            return Ok((previous_digest, None));
         }
-    }
 
-    // Determine write mode
-    let mode = if stream {
-        WriteMode::Streaming
-    } else {
-        WriteMode::Simple
+        let outcome = match create() {
+            Ok(created) => {
+                if verbose > 0 {
+                    eprintln!("Backup created: {}", created.display());
+                }
+                BackupOutcome::Created { path: created }
+            }
+            // Some pipelines would rather lose the backup than the write
+            Err(e) if backup_best_effort => {
+                warn!("{}; writing without a backup", e);
+                BackupOutcome::Failed {
+                    error: e.to_string(),
+                }
+            }
+            Err(e) => return Err(e),
+        };
+        Ok((previous_digest, Some(outcome)))
     };
 
-    // Create writer
-    let mut writer = AtomicWriter::new(&output, mode)?;
+    let mut lock = None;
+    let mut previous = (None, None);
+    if critical_section.locks_input() {
+        lock = take_lock()?;
+    }
+    // commit-only backs up before staging, so the backup is as close as it
+    // gets to the version the rename replaces
+    if critical_section.locks_input() || !critical_section.locks_backup() {
+        previous = back_up()?;
+    }
+
+    #[cfg(feature = "cluster")]
+    {
+        writer = writer.with_fencing_xattr(fencing_xattr);
+    }
 
     // Read input
     let mut input_reader: Box<dyn Read> = if let Some(input_file) = input {
@@ -126,23 +531,225 @@ pub fn execute_write(
     } else {
         Box::new(io::stdin())
     };
+    if let Some(format) = decompress {
+        input_reader = format.decoder(input_reader)?;
+    }
 
     // Copy data
     let mut buffer = [0u8; 8192];
     loop {
-        let n = input_reader.read(&mut buffer)?;
-        if n == 0 {
-            break;
+        let read = input_reader.read(&mut buffer);
+        // Checked after every read, including the last: a producer killed by
+        // the same Ctrl-C ends the input early, which must not be committed
+        if interrupted.is_cancelled() {
+            return Err(MutxError::Interrupted);
         }
+        let n = match read {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
         writer.write_all(&buffer[..n])?;
     }
 
+    if !critical_section.locks_input() {
+        lock = take_lock()?;
+        if critical_section.locks_backup() {
+            previous = back_up()?;
+        }
+    }
+    let (previous_digest, backup_outcome) = previous;
+    writer = writer
+        .with_fencing_token(lock.as_ref().and_then(|lock| lock.fencing_token()))
+        .with_lease_loss(lock.as_ref().and_then(|lock| lock.lease_loss()));
+
     // Commit write
-    writer.commit()?;
+    let mut report = writer.commit()?;
+    report.backup = backup_outcome;
+
+    // The side file is replaced while the lock is still held, so no other
+    // writer can slip in between content and checksum
+    if let (Some(algorithm), Some(digest)) = (emit_digest, &report.digest) {
+        let side_file = write_digest_file(&output, algorithm, digest)?;
+        if verbose > 0 {
+            eprintln!("Digest written: {}", side_file.display());
+        }
+    }
+
+    if let Some(key) = &signing_key {
+        let side_file = write_signature_file(&output, key)?;
+        if verbose > 0 {
+            eprintln!("Signature written: {}", side_file.display());
+        }
+    }
 
     if verbose > 0 {
         eprintln!("Write completed: {}", output.display());
+        if let Some(token) = report.fencing_token {
+            eprintln!("Fencing token: {}", token);
+        }
+    }
+
+    if let Some(target) = emit_env {
+        let mut env = EnvReport::default();
+        env.set("MUTX_OUTPUT", output.as_os_str());
+        env.set(
+            "MUTX_LOCK_PATH",
+            lock.as_ref()
+                .map_or("".as_ref(), |lock| lock.path().as_os_str()),
+        );
+        env.set(
+            "MUTX_BACKUP_PATH",
+            report
+                .backup
+                .as_ref()
+                .and_then(BackupOutcome::path)
+                .map_or("".as_ref(), Path::as_os_str),
+        );
+        env.set_flag("MUTX_CHANGED", previous_digest != report.digest);
+        env.emit(target)?;
+    }
+
+    if json {
+        println!("{}", Versioned::new(schema::WRITE, &report).to_json()?);
     }
 
     Ok(())
 }
+
+/// The lock service `--lock-server` names: a `redis://` URL, `unix:` and
+/// the socket of a mutx daemon, or the HOST:PORT of a lockd server. Also returns the server to show in
+/// messages, without any password.
+#[cfg(feature = "cluster")]
+fn lock_service(server: &str) -> Result<(Box<dyn mutx::lock::cluster::LockService>, String)> {
+    if let Some(rest) = server.strip_prefix("redis://") {
+        let shown = match rest.rsplit_once('@') {
+            Some((_, host)) => format!("redis://{}", host),
+            None => server.to_string(),
+        };
+        #[cfg(feature = "redis")]
+        {
+            use mutx::lock::cluster::redis::{RedisLockService, REDIS_PASSWORD_ENV};
+            let mut service = RedisLockService::from_url(server)?;
+            // Kept out of the URL, where any user could read it in ps
+            if let Some(password) = std::env::var(REDIS_PASSWORD_ENV)
+                .ok()
+                .filter(|password| !password.is_empty())
+            {
+                service = service.with_password(password);
+            }
+            return Ok((Box::new(service), shown));
+        }
+        #[cfg(not(feature = "redis"))]
+        return Err(MutxError::Other(format!(
+            "Locking on {} requires the redis feature",
+            shown
+        )));
+    }
+    if let Some(path) = server.strip_prefix("unix:") {
+        #[cfg(unix)]
+        {
+            use mutx::lock::cluster::daemon::DaemonClient;
+            let client = if path.is_empty() {
+                DaemonClient::from_default_socket()?
+            } else {
+                DaemonClient::new(path)
+            };
+            return Ok((Box::new(client), server.to_string()));
+        }
+        #[cfg(not(unix))]
+        return Err(MutxError::Other(format!(
+            "Locking on {} requires Unix sockets",
+            path
+        )));
+    }
+    Ok((
+        Box::new(mutx::lock::cluster::lockd::LockdClient::new(server)),
+        server.to_string(),
+    ))
+}
+
+/// Fail unless `--input` names a regular file (or, with `allow_special`, a
+/// device or FIFO) that the symlink policy lets mutx read
+pub(crate) fn validate_input(
+    input: &Path,
+    allow_special: bool,
+    follow_symlinks: bool,
+) -> Result<()> {
+    if !input.exists() {
+        return Err(MutxError::PathNotFound(input.to_path_buf()));
+    }
+    let file_type = fs::metadata(input)?.file_type();
+    if !file_type.is_file() {
+        let kind = file_type_name(&file_type);
+        let special = matches!(kind, "character device" | "FIFO" | "special file");
+        if !(special && allow_special) {
+            return Err(MutxError::InputNotRegular {
+                path: input.to_path_buf(),
+                kind,
+            });
+        }
+    }
+
+    // Check if input is a symlink
+    check_symlink(input, follow_symlinks)
+}
+
+/// OUTPUT with `.` and `..` folded away, so the lock path, backup names and
+/// messages all name the file the same way. A path spelled as a directory
+/// (`file.txt/`, `dir/.`) is refused unless `into_dir` expects one.
+pub(crate) fn normalize_output(output: PathBuf, into_dir: bool) -> Result<PathBuf> {
+    let mut normalized = normalize_path(&output);
+    // `..` after a symlinked directory climbs out of the link's target, not
+    // back to where the link is; keep the path as given if the two differ
+    if output.components().any(|c| c == Component::ParentDir) {
+        let resolve = |path: &Path| match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.canonicalize().ok(),
+            _ => Path::new(".").canonicalize().ok(),
+        };
+        if let (Some(given), Some(lexical)) = (resolve(&output), resolve(&normalized)) {
+            if given != lexical {
+                normalized = output.clone();
+            }
+        }
+    }
+
+    if names_directory(&output) && !into_dir {
+        if normalized.is_dir() {
+            return Err(MutxError::IsADirectory(normalized));
+        }
+        return Err(MutxError::NotADirectory(output));
+    }
+    Ok(normalized)
+}
+
+/// The file to write: OUTPUT itself, or with `into_dir` the input's name
+/// inside the directory OUTPUT. Directories are refused here rather than
+/// when the rename fails.
+pub(crate) fn resolve_output(
+    output: PathBuf,
+    input: Option<&Path>,
+    into_dir: bool,
+) -> Result<PathBuf> {
+    if !into_dir {
+        if output.is_dir() {
+            return Err(MutxError::IsADirectory(output));
+        }
+        return Ok(output);
+    }
+
+    if !output.is_dir() {
+        return Err(MutxError::NotADirectory(output));
+    }
+    let Some(name) = input.and_then(Path::file_name) else {
+        return Err(MutxError::Other(
+            "--into-dir needs an --input naming a file".to_string(),
+        ));
+    };
+    let target = output.join(name);
+    if target.is_dir() {
+        return Err(MutxError::IsADirectory(target));
+    }
+    Ok(target)
+}
@@ -0,0 +1,197 @@
+//! Group-shared files for teams writing to a common directory
+//! (`--collaborative GROUP`).
+//!
+//! The target, its lock file and its backups are given the group and made
+//! group-readable and -writable, and backup directories get the setgid bit so
+//! files created in them inherit the group. Files that already carry the group
+//! and permissions are left alone, so members who do not own a file can still
+//! use it. Unix only.
+
+use crate::error::{MutxError, Result};
+#[cfg(unix)]
+use crate::utils::apply_nofollow;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Group read and write permission bits
+const GROUP_RW: u32 = 0o060;
+
+/// Group read, write and search permission bits plus setgid, for directories
+const GROUP_DIR: u32 = 0o2070;
+
+/// A resolved group that written files are shared with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedGroup {
+    name: String,
+    gid: u32,
+}
+
+impl SharedGroup {
+    /// Group with the given ID
+    pub fn from_gid(gid: u32) -> Self {
+        SharedGroup {
+            name: gid.to_string(),
+            gid,
+        }
+    }
+
+    /// Numeric group ID
+    pub fn gid(&self) -> u32 {
+        self.gid
+    }
+
+    /// Give an open file the group and `g+rw`
+    pub fn share_handle(&self, file: &File) -> io::Result<()> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{MetadataExt, PermissionsExt};
+            let metadata = file.metadata()?;
+            if metadata.gid() != self.gid {
+                std::os::unix::fs::fchown(file, None, Some(self.gid))?;
+            }
+            let mode = metadata.mode() & 0o7777;
+            if mode & GROUP_RW != GROUP_RW {
+                file.set_permissions(std::fs::Permissions::from_mode(mode | GROUP_RW))?;
+            }
+            Ok(())
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = file;
+            Err(unsupported())
+        }
+    }
+
+    /// Give the file at `path` the group and `g+rw`
+    pub fn share_file(&self, path: &Path) -> Result<()> {
+        self.share_path(path, GROUP_RW)
+    }
+
+    /// Give the directory at `path` the group and `g+rwxs`, so files created
+    /// in it inherit the group
+    pub fn share_dir(&self, path: &Path) -> Result<()> {
+        self.share_path(path, GROUP_DIR)
+    }
+
+    fn share_path(&self, path: &Path, bits: u32) -> Result<()> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{MetadataExt, PermissionsExt};
+            let failed = |e: io::Error| {
+                MutxError::PermissionDenied(format!(
+                    "cannot share {} with group {}: {}",
+                    path.display(),
+                    self.name,
+                    e
+                ))
+            };
+
+            let metadata = std::fs::symlink_metadata(path).map_err(MutxError::Io)?;
+            if !metadata.file_type().is_symlink()
+                && metadata.gid() == self.gid
+                && metadata.mode() & bits == bits
+            {
+                return Ok(());
+            }
+
+            // Changed through a descriptor opened without following symlinks,
+            // so a link swapped in for the file cannot redirect the chmod
+            let mut opts = std::fs::OpenOptions::new();
+            opts.read(true);
+            apply_nofollow(&mut opts);
+            let file = opts.open(path).map_err(failed)?;
+            let metadata = file.metadata().map_err(MutxError::Io)?;
+            if metadata.gid() != self.gid {
+                std::os::unix::fs::fchown(&file, None, Some(self.gid)).map_err(failed)?;
+            }
+            let mode = metadata.mode() & 0o7777;
+            if mode & bits != bits {
+                file.set_permissions(std::fs::Permissions::from_mode(mode | bits))
+                    .map_err(failed)?;
+            }
+            Ok(())
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = (path, bits);
+            Err(MutxError::Io(unsupported()))
+        }
+    }
+}
+
+impl fmt::Display for SharedGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl FromStr for SharedGroup {
+    type Err = MutxError;
+
+    /// A group name, or a numeric group ID
+    fn from_str(s: &str) -> Result<Self> {
+        if let Ok(gid) = s.parse::<u32>() {
+            return Ok(SharedGroup::from_gid(gid));
+        }
+        match lookup_group(s)? {
+            Some(gid) => Ok(SharedGroup {
+                name: s.to_string(),
+                gid,
+            }),
+            None => Err(MutxError::Other(format!("Unknown group '{}'", s))),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn lookup_group(name: &str) -> Result<Option<u32>> {
+    let c_name = std::ffi::CString::new(name)
+        .map_err(|_| MutxError::Other(format!("Invalid group name '{}'", name)))?;
+    let mut buf = vec![0 as libc::c_char; 1024];
+    loop {
+        let mut group = std::mem::MaybeUninit::<libc::group>::uninit();
+        let mut result = std::ptr::null_mut();
+        let rc = unsafe {
+            libc::getgrnam_r(
+                c_name.as_ptr(),
+                group.as_mut_ptr(),
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut result,
+            )
+        };
+        match rc {
+            0 if result.is_null() => return Ok(None),
+            0 => return Ok(Some(unsafe { group.assume_init() }.gr_gid)),
+            libc::ERANGE if buf.len() < 1 << 20 => buf.resize(buf.len() * 2, 0),
+            err => return Err(MutxError::Io(io::Error::from_raw_os_error(err))),
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn lookup_group(_name: &str) -> Result<Option<u32>> {
+    Err(MutxError::Io(unsupported()))
+}
+
+#[cfg(not(unix))]
+fn unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "group-shared files are only supported on Unix",
+    )
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_group() {
+        assert_eq!("0".parse::<SharedGroup>().unwrap().gid(), 0);
+        assert!("no-such-group-mutx".parse::<SharedGroup>().is_err());
+    }
+}
@@ -0,0 +1,228 @@
+//! gzip and zstd compression of written content (`--compress`) and of input
+//! files (`--decompress`). Needs the `compression` feature (on by default).
+
+use crate::error::{MutxError, Result};
+use crate::transform::Transform;
+use std::fmt;
+use std::io::Read;
+use std::str::FromStr;
+
+/// Supported compression formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionFormat {
+    Gzip,
+    Zstd,
+}
+
+impl CompressionFormat {
+    /// Level used when none is given
+    pub fn default_level(&self) -> i32 {
+        match self {
+            CompressionFormat::Gzip => 6,
+            CompressionFormat::Zstd => 3,
+        }
+    }
+
+    /// Accepted compression levels
+    pub fn levels(&self) -> std::ops::RangeInclusive<i32> {
+        match self {
+            CompressionFormat::Gzip => 0..=9,
+            CompressionFormat::Zstd => 1..=22,
+        }
+    }
+
+    /// Wrap `reader` so it yields the decompressed content
+    pub fn decoder<'a>(&self, reader: Box<dyn Read + 'a>) -> Result<Box<dyn Read + 'a>> {
+        #[cfg(feature = "compression")]
+        {
+            Ok(match self {
+                CompressionFormat::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+                CompressionFormat::Zstd => Box::new(zstd::stream::read::Decoder::new(reader)?),
+            })
+        }
+        #[cfg(not(feature = "compression"))]
+        {
+            let _ = reader;
+            Err(feature_disabled())
+        }
+    }
+}
+
+impl fmt::Display for CompressionFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompressionFormat::Gzip => write!(f, "gzip"),
+            CompressionFormat::Zstd => write!(f, "zstd"),
+        }
+    }
+}
+
+impl FromStr for CompressionFormat {
+    type Err = MutxError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "gzip" | "gz" => Ok(CompressionFormat::Gzip),
+            "zstd" | "zst" => Ok(CompressionFormat::Zstd),
+            _ => Err(MutxError::Other(format!(
+                "Unknown compression format '{}': expected gzip or zstd",
+                s
+            ))),
+        }
+    }
+}
+
+/// A format and level, written `format[:level]` (e.g. `zstd:19`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compression {
+    pub format: CompressionFormat,
+    pub level: i32,
+}
+
+impl Compression {
+    /// Streaming compressor producing this format
+    pub fn transform(&self) -> Result<Box<dyn Transform>> {
+        #[cfg(feature = "compression")]
+        {
+            Ok(match self.format {
+                CompressionFormat::Gzip => Box::new(GzipTransform(flate2::write::GzEncoder::new(
+                    Vec::new(),
+                    flate2::Compression::new(self.level as u32),
+                ))),
+                CompressionFormat::Zstd => Box::new(ZstdTransform(
+                    zstd::stream::write::Encoder::new(Vec::new(), self.level)?,
+                )),
+            })
+        }
+        #[cfg(not(feature = "compression"))]
+        Err(feature_disabled())
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.format, self.level)
+    }
+}
+
+impl FromStr for Compression {
+    type Err = MutxError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (format, level) = match s.split_once(':') {
+            Some((format, level)) => (format, Some(level)),
+            None => (s, None),
+        };
+        let format: CompressionFormat = format.parse()?;
+
+        let level = match level {
+            None => format.default_level(),
+            Some(level) => {
+                let levels = format.levels();
+                level
+                    .trim()
+                    .parse()
+                    .ok()
+                    .filter(|l| levels.contains(l))
+                    .ok_or_else(|| {
+                        MutxError::Other(format!(
+                            "Invalid {} level '{}': expected {} to {}",
+                            format,
+                            level,
+                            levels.start(),
+                            levels.end()
+                        ))
+                    })?
+            }
+        };
+
+        Ok(Compression { format, level })
+    }
+}
+
+#[cfg(not(feature = "compression"))]
+fn feature_disabled() -> MutxError {
+    MutxError::Other(
+        "Compression requires mutx to be built with the 'compression' feature".to_string(),
+    )
+}
+
+#[cfg(feature = "compression")]
+struct GzipTransform(flate2::write::GzEncoder<Vec<u8>>);
+
+#[cfg(feature = "compression")]
+impl Transform for GzipTransform {
+    fn transform(&mut self, chunk: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        use std::io::Write;
+        self.0.write_all(chunk)?;
+        out.append(self.0.get_mut());
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut Vec<u8>) -> Result<()> {
+        self.0.try_finish()?;
+        out.append(self.0.get_mut());
+        Ok(())
+    }
+}
+
+#[cfg(feature = "compression")]
+struct ZstdTransform(zstd::stream::write::Encoder<'static, Vec<u8>>);
+
+#[cfg(feature = "compression")]
+impl Transform for ZstdTransform {
+    fn transform(&mut self, chunk: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        use std::io::Write;
+        self.0.write_all(chunk)?;
+        out.append(self.0.get_mut());
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut Vec<u8>) -> Result<()> {
+        self.0.do_finish()?;
+        out.append(self.0.get_mut());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_compression_spec() {
+        let spec: Compression = "zstd:19".parse().unwrap();
+        assert_eq!(spec.format, CompressionFormat::Zstd);
+        assert_eq!(spec.level, 19);
+
+        let spec: Compression = "gzip".parse().unwrap();
+        assert_eq!(spec.level, 6);
+
+        assert!("gzip:10".parse::<Compression>().is_err());
+        assert!("zstd:fast".parse::<Compression>().is_err());
+        assert!("brotli".parse::<Compression>().is_err());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_round_trip() {
+        for spec in ["gzip:1", "zstd:3"] {
+            let spec: Compression = spec.parse().unwrap();
+            let mut transform = spec.transform().unwrap();
+
+            let mut compressed = Vec::new();
+            for chunk in [&b"hello "[..], b"compressed ", b"world"] {
+                transform.transform(chunk, &mut compressed).unwrap();
+            }
+            transform.finish(&mut compressed).unwrap();
+
+            let mut decoded = String::new();
+            spec.format
+                .decoder(Box::new(&compressed[..]))
+                .unwrap()
+                .read_to_string(&mut decoded)
+                .unwrap();
+            assert_eq!(decoded, "hello compressed world");
+        }
+    }
+}
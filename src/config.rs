@@ -0,0 +1,190 @@
+//! User configuration file.
+//!
+//! Settings are read from `$MUTX_CONFIG` if set, otherwise from `config.toml`
+//! in the platform config directory (e.g. `~/.config/mutx/config.toml`). A
+//! missing file is the same as an empty one. Command-line flags always take
+//! precedence over the file.
+//!
+//! ```toml
+//! backup_suffix = ".bak"
+//! backup_dir = "/var/backups/mutx"
+//! lock_dir = "/run/lock/mutx"
+//! lock_mode = "0666"
+//! ```
+
+use crate::backup::BackupSuffix;
+use crate::error::{MutxError, Result};
+use crate::write::FileMode;
+use directories::ProjectDirs;
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Environment variable naming an alternative configuration file
+pub const CONFIG_ENV: &str = "MUTX_CONFIG";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Suffix for backups written by `--backup` and matched by housekeep and
+    /// restore (default: [`crate::DEFAULT_BACKUP_SUFFIX`]). An invalid
+    /// suffix fails loading the file.
+    pub backup_suffix: Option<BackupSuffix>,
+    /// Central directory for backups written by `--backup` without
+    /// `--backup-dir`, which restore searches and `housekeep backups` cleans
+    /// by default. Relative paths are taken from the working directory.
+    pub backup_dir: Option<PathBuf>,
+    /// Directory for derived lock files instead of the per-user cache, such
+    /// as `/run/lock/mutx` on servers or a tmpfs in containers. Must be
+    /// absolute; `MUTX_LOCK_DIR` takes precedence.
+    pub lock_dir: Option<PathBuf>,
+    /// Permissions given to lock files mutx uses, such as `"0666"` so other
+    /// users can coordinate on files in a shared directory. `--lock-mode`
+    /// takes precedence.
+    pub lock_mode: Option<FileMode>,
+}
+
+impl Config {
+    /// Location of the configuration file, if one can be determined
+    pub fn default_path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os(CONFIG_ENV) {
+            return Some(PathBuf::from(path));
+        }
+        ProjectDirs::from("", "", "mutx").map(|dirs| dirs.config_dir().join("config.toml"))
+    }
+
+    /// Load the configuration from [`Config::default_path`]
+    pub fn load() -> Result<Self> {
+        match Self::default_path() {
+            Some(path) => Self::from_path(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Load the configuration from `path`; a missing file yields the defaults
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => {
+                return Err(MutxError::ReadFailed {
+                    path: path.to_path_buf(),
+                    source: e,
+                })
+            }
+        };
+
+        let config: Self = toml::from_str(&contents).map_err(|e| MutxError::InvalidConfig {
+            path: path.to_path_buf(),
+            message: e.message().to_string(),
+        })?;
+        // Writers in different working directories must find the same locks
+        if let Some(lock_dir) = config.lock_dir.as_ref().filter(|d| !d.is_absolute()) {
+            return Err(MutxError::InvalidConfig {
+                path: path.to_path_buf(),
+                message: format!("lock_dir must be absolute, got {}", lock_dir.display()),
+            });
+        }
+        Ok(config)
+    }
+
+    /// Effective backup suffix: the configured one or the built-in default
+    pub fn backup_suffix(&self) -> BackupSuffix {
+        self.backup_suffix.clone().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::DEFAULT_BACKUP_SUFFIX;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_missing_file_is_default() {
+        let temp = TempDir::new().unwrap();
+        let config = Config::from_path(&temp.path().join("config.toml")).unwrap();
+        assert_eq!(config, Config::default());
+        assert_eq!(config.backup_suffix().as_str(), DEFAULT_BACKUP_SUFFIX);
+    }
+
+    #[test]
+    fn test_backup_suffix_override() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("config.toml");
+        fs::write(&path, "backup_suffix = \".bak\"\n").unwrap();
+
+        assert_eq!(
+            Config::from_path(&path).unwrap().backup_suffix().as_str(),
+            ".bak"
+        );
+    }
+
+    #[test]
+    fn test_backup_dir() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("config.toml");
+        fs::write(&path, "backup_dir = \"/var/backups/mutx\"\n").unwrap();
+
+        assert_eq!(
+            Config::from_path(&path).unwrap().backup_dir,
+            Some(PathBuf::from("/var/backups/mutx"))
+        );
+    }
+
+    #[test]
+    fn test_invalid_backup_suffix_rejected() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("config.toml");
+        fs::write(&path, "backup_suffix = \".\"\n").unwrap();
+
+        assert!(matches!(
+            Config::from_path(&path),
+            Err(MutxError::InvalidConfig { .. })
+        ));
+    }
+
+    #[test]
+    fn test_lock_dir_must_be_absolute() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("config.toml");
+        fs::write(&path, "lock_dir = \"/run/lock/mutx\"\n").unwrap();
+        assert_eq!(
+            Config::from_path(&path).unwrap().lock_dir,
+            Some(PathBuf::from("/run/lock/mutx"))
+        );
+
+        fs::write(&path, "lock_dir = \"locks\"\n").unwrap();
+        assert!(matches!(
+            Config::from_path(&path),
+            Err(MutxError::InvalidConfig { .. })
+        ));
+    }
+
+    #[test]
+    fn test_lock_mode() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("config.toml");
+        fs::write(&path, "lock_mode = \"0666\"\n").unwrap();
+        assert_eq!(
+            Config::from_path(&path).unwrap().lock_mode,
+            Some(FileMode::new(0o666).unwrap())
+        );
+
+        fs::write(&path, "lock_mode = \"rw\"\n").unwrap();
+        assert!(Config::from_path(&path).is_err());
+    }
+
+    #[test]
+    fn test_invalid_file_rejected() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("config.toml");
+        fs::write(&path, "backup_suffix = 3\n").unwrap();
+
+        assert!(matches!(
+            Config::from_path(&path),
+            Err(MutxError::InvalidConfig { .. })
+        ));
+    }
+}
@@ -0,0 +1,684 @@
+//! Delta backups: older backups stored as differences from newer ones.
+//!
+//! With [`BackupConfig::delta`](crate::BackupConfig), every new backup is a
+//! full copy and the backup before it is rewritten as a delta against it, in
+//! the manner of rsync: the newer backup is cut into fixed-size blocks, and
+//! the older one is stored as references to those blocks plus the bytes
+//! found in none of them. A file rewritten daily with small changes then
+//! costs little more than one full copy, however many versions are kept.
+//!
+//! The newest backup is always complete, so restoring it is a plain copy.
+//! An older one is rebuilt by applying its delta to the version after it,
+//! walking the chain forward to the full backup. Removing the oldest
+//! backups first, as housekeep and `--reclaim-backups-on-enospc` do, never
+//! breaks a chain; removing a backup by hand breaks every delta older than
+//! it.
+//!
+//! A delta file is a header line
+//!
+//! ```text
+//! mutx-delta 1 <block size> <file name of the newer backup>\n
+//! ```
+//!
+//! followed by instructions, integers little-endian: `C <u64 first block>
+//! <u32 count>` copies blocks of the newer backup, `L <u32 length> <bytes>`
+//! inserts bytes, and `E <u64 size> <32-byte SHA-256>` ends the delta with
+//! the size and digest of the version it rebuilds, checked on every rebuild.
+
+use crate::error::{MutxError, Result};
+use crate::utils::xattr::{copy_xattr, BACKUP_SOURCE_XATTR, COMPRESSION_XATTR};
+use crate::utils::{apply_nofollow, unique_temp_path, verify_not_link};
+use crate::write::StageDir;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+const MAGIC: &[u8] = b"mutx-delta 1 ";
+
+/// Block sizes grow with the square root of the newer backup's size, as in
+/// rsync, within these bounds
+const MIN_BLOCK_SIZE: usize = 1024;
+const MAX_BLOCK_SIZE: usize = 64 * 1024;
+
+/// Unmatched bytes held before they are written out as an insertion
+const MAX_LITERAL: usize = 1024 * 1024;
+
+/// How much of the older version is read at a time
+const READ_CHUNK: usize = 64 * 1024;
+
+/// Whether `path` holds a delta rather than a full backup
+pub fn is_delta(path: &Path) -> Result<bool> {
+    let mut file = File::open(path).map_err(|e| read_failed(path, e))?;
+    let mut magic = [0u8; MAGIC.len()];
+    match file.read_exact(&mut magic) {
+        Ok(()) => Ok(magic == MAGIC),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(read_failed(path, e)),
+    }
+}
+
+/// Pass the contents of the backup at `path` to `emit`, rebuilding them
+/// from the chain of deltas if it is one, and return their size.
+///
+/// Intermediate versions are rebuilt into temp files beside the backups,
+/// which are removed as soon as the next version no longer needs them.
+/// Fails without rebuilding anything if a backup in the chain is missing,
+/// and fails part way (`emit` having seen some of the content) if one has
+/// been modified since its delta was taken.
+pub fn reconstruct(path: &Path, mut emit: impl FnMut(&[u8]) -> Result<()>) -> Result<u64> {
+    // Follow the chain to the full backup at its head
+    let mut chain = vec![path.to_path_buf()];
+    loop {
+        let newest = chain.last().expect("chain is never empty");
+        let Some(header) = Header::read_from(newest)? else {
+            break;
+        };
+        let base = header.base_path(newest)?;
+        if chain.contains(&base) {
+            return Err(corrupt(path, "its chain of deltas loops"));
+        }
+        if !base.exists() {
+            return Err(MutxError::Other(format!(
+                "Cannot rebuild {}: the backup it is a delta against, {}, is missing",
+                path.display(),
+                base.display()
+            )));
+        }
+        chain.push(base);
+    }
+
+    let full = chain.pop().expect("chain is never empty");
+    if chain.is_empty() {
+        return copy_full(&full, emit);
+    }
+    debug!(
+        "Rebuilding {} from {} through {} delta(s)",
+        path.display(),
+        full.display(),
+        chain.len()
+    );
+
+    // Rebuild each version from the one after it, newest first, until only
+    // the requested one is left
+    let mut scratch: Option<Scratch> = None;
+    while chain.len() > 1 {
+        let delta = chain.pop().expect("chain has more than one entry");
+        let next = Scratch::create(&delta)?;
+        {
+            let mut out = BufWriter::new(&next.file);
+            let base = scratch
+                .as_ref()
+                .map_or(full.as_path(), |s| s.path.as_path());
+            apply(&delta, base, |buf| {
+                out.write_all(buf).map_err(|e| write_failed(&next.path, e))
+            })?;
+            out.flush().map_err(|e| write_failed(&next.path, e))?;
+        }
+        scratch = Some(next);
+    }
+    let base = scratch
+        .as_ref()
+        .map_or(full.as_path(), |s| s.path.as_path());
+    apply(&chain[0], base, &mut emit)
+}
+
+/// Replace the full backup `version` with a delta against `base`, a newer
+/// backup in the same directory. Returns false, leaving `version` as it
+/// is, when it is already a delta or the delta would be no smaller.
+///
+/// The delta is checked by rebuilding `version` from it before the
+/// original is replaced, and keeps its permissions and mtime, so finding
+/// the newest backup and aging backups by mtime are unaffected.
+pub(crate) fn store_as_delta(version: &Path, base: &Path) -> Result<bool> {
+    if !same_directory(version, base) || is_delta(version)? {
+        return Ok(false);
+    }
+    let metadata = fs::symlink_metadata(version).map_err(|e| read_failed(version, e))?;
+    if !metadata.is_file() {
+        return Ok(false);
+    }
+
+    let temp = unique_temp_path(version);
+    let mut opts = OpenOptions::new();
+    opts.write(true).create_new(true);
+    apply_nofollow(&mut opts);
+    let file = opts.open(&temp).map_err(|e| write_failed(&temp, e))?;
+
+    let result = verify_not_link(&file, &temp, |path| MutxError::SymlinkNotAllowed { path })
+        .and_then(|()| {
+            let mut out = BufWriter::new(&file);
+            encode(version, base, &mut out)?;
+            out.flush().map_err(|e| write_failed(&temp, e))?;
+            drop(out);
+
+            let size = file.metadata().map_err(|e| write_failed(&temp, e))?.len();
+            if size >= metadata.len() {
+                return Ok(false);
+            }
+            apply(&temp, base, |_| Ok(()))?;
+
+            file.set_permissions(metadata.permissions())
+                .map_err(|e| write_failed(&temp, e))?;
+            if let Err(e) = copy_xattr(version, &file, COMPRESSION_XATTR)
+                .and_then(|()| copy_xattr(version, &file, BACKUP_SOURCE_XATTR))
+            {
+                debug!(
+                    "Cannot carry the attributes of {} over: {}",
+                    version.display(),
+                    e
+                );
+            }
+            set_mtime(&file, &metadata).map_err(|e| write_failed(&temp, e))?;
+            file.sync_all().map_err(|e| write_failed(&temp, e))?;
+            fs::rename(&temp, version).map_err(|e| write_failed(version, e))?;
+            StageDir::for_target(version)
+                .and_then(|dir| dir.sync())
+                .map_err(|e| write_failed(version, e))?;
+            debug!(
+                "Stored {} as a delta of {} bytes against {}",
+                version.display(),
+                size,
+                base.display()
+            );
+            Ok(true)
+        });
+
+    if !matches!(result, Ok(true)) {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Whether `a` and `b` are in the same directory, however their paths
+/// spell it (`./data.bin` and `data.bin` are)
+fn same_directory(a: &Path, b: &Path) -> bool {
+    let dir = |path: &Path| match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    match (fs::canonicalize(dir(a)), fs::canonicalize(dir(b))) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Give `file` the modification time in `metadata`, leaving its access
+/// time alone
+#[cfg(unix)]
+fn set_mtime(file: &File, metadata: &fs::Metadata) -> io::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+    use std::os::unix::io::AsRawFd;
+
+    let times = [
+        libc::timespec {
+            tv_sec: 0,
+            tv_nsec: libc::UTIME_OMIT as _,
+        },
+        libc::timespec {
+            tv_sec: metadata.mtime() as libc::time_t,
+            tv_nsec: metadata.mtime_nsec() as _,
+        },
+    ];
+    if unsafe { libc::futimens(file.as_raw_fd(), times.as_ptr()) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Without a way to keep its mtime, a delta would pass for the newest
+/// backup, so none is stored
+#[cfg(not(unix))]
+fn set_mtime(_file: &File, _metadata: &fs::Metadata) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "cannot keep the backup's modification time on this platform",
+    ))
+}
+
+/// Write the delta that rebuilds `version` from `base`
+fn encode(version: &Path, base: &Path, out: &mut impl Write) -> Result<()> {
+    let base_name = base
+        .file_name()
+        .and_then(|n| n.to_str())
+        .filter(|n| !n.contains('\n'))
+        .ok_or_else(|| MutxError::Other(format!("Cannot delta against {}", base.display())))?;
+    let base_len = fs::metadata(base).map_err(|e| read_failed(base, e))?.len();
+    let block_size = block_size_for(base_len);
+    let signatures = Signatures::of(base, block_size)?;
+
+    let mut encoder = Encoder {
+        out,
+        pending_copy: None,
+    };
+    encoder
+        .header(block_size, base_name)
+        .map_err(MutxError::Io)?;
+
+    let mut input = File::open(version).map_err(|e| read_failed(version, e))?;
+    let mut hasher = Sha256::new();
+    let mut size = 0u64;
+    let mut eof = false;
+
+    // data[..pos] is unmatched; data[pos..pos + block_size] is the window
+    // compared against the base's blocks
+    let mut data: Vec<u8> = Vec::new();
+    let mut pos = 0;
+    let mut rolling: Option<Rolling> = None;
+    loop {
+        while data.len() <= pos + block_size && !eof {
+            let start = data.len();
+            data.resize(start + READ_CHUNK, 0);
+            let n = loop {
+                match input.read(&mut data[start..]) {
+                    Ok(n) => break n,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(read_failed(version, e)),
+                }
+            };
+            data.truncate(start + n);
+            hasher.update(&data[start..]);
+            size += n as u64;
+            eof = n == 0;
+        }
+        if data.len() < pos + block_size {
+            break;
+        }
+
+        let window = &data[pos..pos + block_size];
+        let checksum = *rolling.get_or_insert_with(|| Rolling::new(window));
+        if let Some(index) = signatures.find(checksum.digest(), window) {
+            encoder.literal(&data[..pos]).map_err(MutxError::Io)?;
+            encoder.copy(index).map_err(MutxError::Io)?;
+            data.drain(..pos + block_size);
+            pos = 0;
+            rolling = None;
+            continue;
+        }
+
+        // Slide the window one byte
+        if data.len() == pos + block_size {
+            break;
+        }
+        rolling = Some(checksum.roll(data[pos], data[pos + block_size]));
+        pos += 1;
+        if pos >= MAX_LITERAL {
+            encoder.literal(&data[..pos]).map_err(MutxError::Io)?;
+            data.drain(..pos);
+            pos = 0;
+        }
+    }
+    encoder.literal(&data).map_err(MutxError::Io)?;
+    encoder
+        .end(size, &hasher.finalize().into())
+        .map_err(MutxError::Io)
+}
+
+/// Rebuild the version `delta` describes from `base`, passing it to `emit`
+fn apply(delta: &Path, base: &Path, mut emit: impl FnMut(&[u8]) -> Result<()>) -> Result<u64> {
+    let mut reader = BufReader::new(File::open(delta).map_err(|e| read_failed(delta, e))?);
+    let header = Header::parse(&mut reader)
+        .map_err(|e| read_failed(delta, e))?
+        .ok_or_else(|| corrupt(delta, "it is not a delta"))?;
+    let mut base_file = File::open(base).map_err(|e| read_failed(base, e))?;
+    let base_changed = || {
+        corrupt(
+            delta,
+            &format!("{} has changed since the delta was taken", base.display()),
+        )
+    };
+
+    let mut hasher = Sha256::new();
+    let mut size = 0u64;
+    let mut buf = vec![0u8; header.block_size];
+    loop {
+        let op = read_u8(&mut reader).map_err(|e| read_failed(delta, e))?;
+        match op {
+            b'C' => {
+                let first = read_u64(&mut reader).map_err(|e| read_failed(delta, e))?;
+                let count = read_u32(&mut reader).map_err(|e| read_failed(delta, e))?;
+                let offset = first
+                    .checked_mul(header.block_size as u64)
+                    .ok_or_else(|| corrupt(delta, "a block is out of range"))?;
+                base_file
+                    .seek(SeekFrom::Start(offset))
+                    .map_err(|e| read_failed(base, e))?;
+                for _ in 0..count {
+                    base_file.read_exact(&mut buf).map_err(|e| {
+                        if e.kind() == io::ErrorKind::UnexpectedEof {
+                            base_changed()
+                        } else {
+                            read_failed(base, e)
+                        }
+                    })?;
+                    hasher.update(&buf);
+                    emit(&buf)?;
+                    size += buf.len() as u64;
+                }
+            }
+            b'L' => {
+                let mut remaining =
+                    read_u32(&mut reader).map_err(|e| read_failed(delta, e))? as usize;
+                while remaining > 0 {
+                    let n = remaining.min(buf.len());
+                    reader
+                        .read_exact(&mut buf[..n])
+                        .map_err(|e| read_failed(delta, e))?;
+                    hasher.update(&buf[..n]);
+                    emit(&buf[..n])?;
+                    size += n as u64;
+                    remaining -= n;
+                }
+            }
+            b'E' => {
+                let expected_size = read_u64(&mut reader).map_err(|e| read_failed(delta, e))?;
+                let mut expected_digest = [0u8; 32];
+                reader
+                    .read_exact(&mut expected_digest)
+                    .map_err(|e| read_failed(delta, e))?;
+                let digest: [u8; 32] = hasher.finalize().into();
+                if size != expected_size || digest != expected_digest {
+                    return Err(base_changed());
+                }
+                return Ok(size);
+            }
+            _ => return Err(corrupt(delta, "it holds an unknown instruction")),
+        }
+    }
+}
+
+fn copy_full(path: &Path, mut emit: impl FnMut(&[u8]) -> Result<()>) -> Result<u64> {
+    let mut file = File::open(path).map_err(|e| read_failed(path, e))?;
+    let mut buf = vec![0u8; READ_CHUNK];
+    let mut size = 0u64;
+    loop {
+        let n = match file.read(&mut buf) {
+            Ok(0) => return Ok(size),
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(read_failed(path, e)),
+        };
+        emit(&buf[..n])?;
+        size += n as u64;
+    }
+}
+
+fn block_size_for(len: u64) -> usize {
+    let mut size = MIN_BLOCK_SIZE;
+    while size < MAX_BLOCK_SIZE && (size as u64) * (size as u64) < len {
+        size *= 2;
+    }
+    size
+}
+
+struct Header {
+    block_size: usize,
+    base: String,
+}
+
+impl Header {
+    /// The header of `path`, or `None` if it is a full backup
+    fn read_from(path: &Path) -> Result<Option<Header>> {
+        let file = File::open(path).map_err(|e| read_failed(path, e))?;
+        Header::parse(&mut BufReader::new(file)).map_err(|e| read_failed(path, e))
+    }
+
+    fn parse(reader: &mut impl BufRead) -> io::Result<Option<Header>> {
+        let mut magic = [0u8; MAGIC.len()];
+        match reader.read_exact(&mut magic) {
+            Ok(()) if magic == MAGIC => {}
+            Ok(()) => return Ok(None),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        let mut line = Vec::new();
+        reader.read_until(b'\n', &mut line)?;
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid delta header");
+        let line = std::str::from_utf8(&line)
+            .ok()
+            .and_then(|l| l.strip_suffix('\n'))
+            .ok_or_else(invalid)?;
+        let (block_size, base) = line.split_once(' ').ok_or_else(invalid)?;
+        let block_size: usize = block_size.parse().map_err(|_| invalid())?;
+        if !(MIN_BLOCK_SIZE..=MAX_BLOCK_SIZE).contains(&block_size) {
+            return Err(invalid());
+        }
+        Ok(Some(Header {
+            block_size,
+            base: base.to_string(),
+        }))
+    }
+
+    /// The newer backup the delta at `path` applies to, which must be a
+    /// plain file name in the same directory
+    fn base_path(&self, path: &Path) -> Result<PathBuf> {
+        let name = Path::new(&self.base);
+        if name.file_name() != Some(name.as_os_str()) {
+            return Err(corrupt(path, "it names a base outside its directory"));
+        }
+        Ok(path.with_file_name(name))
+    }
+}
+
+/// The blocks of the newer backup, by weak then strong checksum
+struct Signatures {
+    blocks: HashMap<u32, Vec<([u8; 16], u64)>>,
+}
+
+impl Signatures {
+    fn of(base: &Path, block_size: usize) -> Result<Signatures> {
+        let mut file = BufReader::new(File::open(base).map_err(|e| read_failed(base, e))?);
+        let mut blocks: HashMap<u32, Vec<([u8; 16], u64)>> = HashMap::new();
+        let mut buf = vec![0u8; block_size];
+        let mut index = 0u64;
+        loop {
+            match file.read_exact(&mut buf) {
+                Ok(()) => {}
+                // A short last block is never matched
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(read_failed(base, e)),
+            }
+            blocks
+                .entry(Rolling::new(&buf).digest())
+                .or_default()
+                .push((strong(&buf), index));
+            index += 1;
+        }
+        Ok(Signatures { blocks })
+    }
+
+    fn find(&self, weak: u32, window: &[u8]) -> Option<u64> {
+        let candidates = self.blocks.get(&weak)?;
+        let strong = strong(window);
+        candidates
+            .iter()
+            .find(|(digest, _)| *digest == strong)
+            .map(|(_, index)| *index)
+    }
+}
+
+fn strong(block: &[u8]) -> [u8; 16] {
+    let digest = Sha256::digest(block);
+    let mut truncated = [0u8; 16];
+    truncated.copy_from_slice(&digest[..16]);
+    truncated
+}
+
+/// rsync's rolling checksum, updated in constant time as the window slides
+#[derive(Clone, Copy)]
+struct Rolling {
+    a: u32,
+    b: u32,
+    len: u32,
+}
+
+impl Rolling {
+    fn new(window: &[u8]) -> Rolling {
+        let len = window.len() as u32;
+        let (mut a, mut b) = (0u32, 0u32);
+        for (i, &byte) in window.iter().enumerate() {
+            a = a.wrapping_add(byte as u32);
+            b = b.wrapping_add((len - i as u32).wrapping_mul(byte as u32));
+        }
+        Rolling { a, b, len }
+    }
+
+    fn roll(self, out: u8, into: u8) -> Rolling {
+        let a = self.a.wrapping_sub(out as u32).wrapping_add(into as u32);
+        let b = self
+            .b
+            .wrapping_sub(self.len.wrapping_mul(out as u32))
+            .wrapping_add(a);
+        Rolling { a, b, ..self }
+    }
+
+    fn digest(&self) -> u32 {
+        (self.a & 0xffff) | (self.b << 16)
+    }
+}
+
+/// Writes instructions, merging copies of consecutive blocks
+struct Encoder<'a, W: Write> {
+    out: &'a mut W,
+    pending_copy: Option<(u64, u32)>,
+}
+
+impl<W: Write> Encoder<'_, W> {
+    fn header(&mut self, block_size: usize, base: &str) -> io::Result<()> {
+        self.out.write_all(MAGIC)?;
+        writeln!(self.out, "{} {}", block_size, base)
+    }
+
+    fn copy(&mut self, index: u64) -> io::Result<()> {
+        match &mut self.pending_copy {
+            Some((first, count)) if *first + *count as u64 == index && *count < u32::MAX => {
+                *count += 1;
+                Ok(())
+            }
+            _ => {
+                self.flush_copy()?;
+                self.pending_copy = Some((index, 1));
+                Ok(())
+            }
+        }
+    }
+
+    fn literal(&mut self, bytes: &[u8]) -> io::Result<()> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        self.flush_copy()?;
+        for chunk in bytes.chunks(u32::MAX as usize) {
+            self.out.write_all(b"L")?;
+            self.out.write_all(&(chunk.len() as u32).to_le_bytes())?;
+            self.out.write_all(chunk)?;
+        }
+        Ok(())
+    }
+
+    fn end(&mut self, size: u64, digest: &[u8; 32]) -> io::Result<()> {
+        self.flush_copy()?;
+        self.out.write_all(b"E")?;
+        self.out.write_all(&size.to_le_bytes())?;
+        self.out.write_all(digest)
+    }
+
+    fn flush_copy(&mut self) -> io::Result<()> {
+        if let Some((first, count)) = self.pending_copy.take() {
+            self.out.write_all(b"C")?;
+            self.out.write_all(&first.to_le_bytes())?;
+            self.out.write_all(&count.to_le_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// A rebuilt intermediate version, removed when dropped
+pub(crate) struct Scratch {
+    pub(crate) path: PathBuf,
+    pub(crate) file: File,
+}
+
+impl Scratch {
+    pub(crate) fn create(beside: &Path) -> Result<Scratch> {
+        let path = unique_temp_path(beside);
+        let mut opts = OpenOptions::new();
+        opts.read(true).write(true).create_new(true);
+        apply_nofollow(&mut opts);
+        let file = opts.open(&path).map_err(|e| write_failed(&path, e))?;
+        Ok(Scratch { path, file })
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn read_u8(reader: &mut impl Read) -> io::Result<u8> {
+    let mut buf = [0u8; 1];
+    reader.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_failed(path: &Path, source: io::Error) -> MutxError {
+    MutxError::ReadFailed {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+fn write_failed(path: &Path, source: io::Error) -> MutxError {
+    MutxError::WriteFailed {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+fn corrupt(path: &Path, why: &str) -> MutxError {
+    MutxError::Other(format!(
+        "Cannot rebuild delta backup {}: {}",
+        path.display(),
+        why
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rolling_checksum_matches_fresh_computation() {
+        let data: Vec<u8> = (0..200u32).map(|i| (i * 37 % 251) as u8).collect();
+        let mut rolling = Rolling::new(&data[..64]);
+        for start in 1..=data.len() - 64 {
+            rolling = rolling.roll(data[start - 1], data[start + 63]);
+            assert_eq!(
+                rolling.digest(),
+                Rolling::new(&data[start..start + 64]).digest()
+            );
+        }
+    }
+
+    #[test]
+    fn test_block_size_grows_with_base() {
+        assert_eq!(block_size_for(0), MIN_BLOCK_SIZE);
+        assert_eq!(block_size_for(16 * 1024 * 1024), 4096);
+        assert_eq!(block_size_for(8 << 30), MAX_BLOCK_SIZE);
+    }
+}
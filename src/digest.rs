@@ -0,0 +1,148 @@
+//! Checksum side files (`--emit-digest`).
+//!
+//! The digest is computed over the bytes committed to the target and written
+//! to `<target>.<algorithm>` in the format of `sha256sum`, so downstream
+//! verifiers can run `sha256sum -c target.sha256` in the target's directory.
+
+use crate::error::{MutxError, Result};
+use crate::write::{AtomicWriter, WriteMode};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Supported digest algorithms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigestAlgorithm {
+    Sha256,
+}
+
+impl DigestAlgorithm {
+    /// Incremental hasher for this algorithm
+    pub fn hasher(&self) -> Hasher {
+        match self {
+            DigestAlgorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+        }
+    }
+
+    /// Digest of the current content of `path`, or `None` if it does not exist
+    pub fn digest_file(&self, path: &Path) -> Result<Option<String>> {
+        let mut file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(MutxError::ReadFailed {
+                    path: path.to_path_buf(),
+                    source: e,
+                })
+            }
+        };
+        let mut hasher = self.hasher();
+        let mut buffer = [0u8; 8192];
+        loop {
+            let n = file.read(&mut buffer).map_err(|e| MutxError::ReadFailed {
+                path: path.to_path_buf(),
+                source: e,
+            })?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buffer[..n]);
+        }
+        Ok(Some(hasher.finish_hex()))
+    }
+
+    /// Side file holding the digest of `target`: `<target>.sha256`
+    pub fn side_file(&self, target: &Path) -> PathBuf {
+        let mut name = target.as_os_str().to_os_string();
+        name.push(format!(".{}", self));
+        PathBuf::from(name)
+    }
+}
+
+impl fmt::Display for DigestAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DigestAlgorithm::Sha256 => write!(f, "sha256"),
+        }
+    }
+}
+
+impl FromStr for DigestAlgorithm {
+    type Err = MutxError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "sha256" => Ok(DigestAlgorithm::Sha256),
+            _ => Err(MutxError::Other(format!(
+                "Unknown digest algorithm '{}': expected sha256",
+                s
+            ))),
+        }
+    }
+}
+
+/// Running digest of written data
+#[derive(Debug, Clone)]
+pub enum Hasher {
+    Sha256(Sha256),
+}
+
+impl Hasher {
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Sha256(hasher) => hasher.update(data),
+        }
+    }
+
+    /// Lowercase hex digest
+    pub fn finish_hex(self) -> String {
+        match self {
+            Hasher::Sha256(hasher) => format!("{:x}", hasher.finalize()),
+        }
+    }
+}
+
+/// Atomically write the digest side file for `target`, in `sha256sum`
+/// format (`<hex>  <file name>`), and return its path.
+///
+/// Call it while still holding the target's lock so no other writer can
+/// replace the target between its commit and the digest's.
+pub fn write_digest_file(target: &Path, algorithm: DigestAlgorithm, hex: &str) -> Result<PathBuf> {
+    let side_file = algorithm.side_file(target);
+    let name = target
+        .file_name()
+        .ok_or_else(|| MutxError::Other("Output path has no filename".to_string()))?
+        .to_string_lossy();
+
+    let mut writer = AtomicWriter::new(&side_file, WriteMode::InMemory)?;
+    writer.write_all(format!("{}  {}\n", hex, name).as_bytes())?;
+    writer.commit()?;
+    Ok(side_file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256_of_known_input() {
+        let mut hasher = DigestAlgorithm::Sha256.hasher();
+        hasher.update(b"ab");
+        hasher.update(b"c");
+        assert_eq!(
+            hasher.finish_hex(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_side_file_name() {
+        assert_eq!(
+            DigestAlgorithm::Sha256.side_file(Path::new("/srv/app.tar")),
+            Path::new("/srv/app.tar.sha256")
+        );
+    }
+}
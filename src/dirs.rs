@@ -0,0 +1,218 @@
+//! Where mutx keeps its own files.
+//!
+//! Locks live under the cache directory: `$XDG_CACHE_HOME/mutx` when that
+//! variable holds an absolute path (on every platform, not just Linux),
+//! otherwise the platform cache directory (e.g. `~/Library/Caches/mutx`).
+//! The lock cache is marked with a `CACHEDIR.TAG` so backup tools skip it.
+//!
+//! `$MUTX_LOCK_DIR` moves the lock cache itself, ahead of all of these. The
+//! CLI sets it from `lock_dir` in the configuration file when it is unset.
+//!
+//! State that must outlive cache cleanups (journals, manifests) lives under
+//! the state directory: `$MUTX_STATE_DIR` (set by `--state-dir`), then
+//! `$XDG_STATE_HOME/mutx`, then the platform default (`~/.local/state/mutx`
+//! on Linux, the local data directory elsewhere).
+//!
+//! Sockets live under the runtime directory: `$XDG_RUNTIME_DIR/mutx`, or
+//! else the per-user temp directory.
+
+use directories::ProjectDirs;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Environment variable relocating the state directory
+pub const STATE_DIR_ENV: &str = "MUTX_STATE_DIR";
+
+/// Environment variable relocating the lock cache
+pub const LOCK_DIR_ENV: &str = "MUTX_LOCK_DIR";
+
+/// Name of the cache directory tag file
+pub const CACHEDIR_TAG: &str = "CACHEDIR.TAG";
+
+/// First line every cache directory tag must start with, from the Cache
+/// Directory Tagging Specification
+pub const CACHEDIR_TAG_SIGNATURE: &str = "Signature: 8a477f597d28d172789f06886806bc55";
+
+/// The mutx cache directory, if one can be determined
+pub fn cache_dir() -> Option<PathBuf> {
+    if let Some(base) = xdg_base("XDG_CACHE_HOME") {
+        return Some(base.join("mutx"));
+    }
+    ProjectDirs::from("", "", "mutx").map(|dirs| dirs.cache_dir().to_path_buf())
+}
+
+/// The mutx state directory, if one can be determined
+pub fn state_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os(STATE_DIR_ENV).filter(|d| !d.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+    if let Some(base) = xdg_base("XDG_STATE_HOME") {
+        return Some(base.join("mutx"));
+    }
+    ProjectDirs::from("", "", "mutx").map(|dirs| {
+        dirs.state_dir()
+            .unwrap_or_else(|| dirs.data_local_dir())
+            .to_path_buf()
+    })
+}
+
+/// The mutx runtime directory, for sockets, if one can be determined
+pub fn runtime_dir() -> Option<PathBuf> {
+    xdg_base("XDG_RUNTIME_DIR")
+        .map(|base| base.join("mutx"))
+        .or_else(user_temp_dir)
+}
+
+/// The lock cache named by `$MUTX_LOCK_DIR`, if set
+pub fn lock_dir_override() -> Option<PathBuf> {
+    std::env::var_os(LOCK_DIR_ENV)
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Per-user directory under the system temp directory (`/tmp/mutx-<uid>`),
+/// for when the cache directory is unavailable. `None` if it exists but is
+/// not a private directory of this user, or on platforms without user ids.
+pub fn user_temp_dir() -> Option<PathBuf> {
+    #[cfg(unix)]
+    {
+        // SAFETY: geteuid cannot fail
+        let uid = unsafe { libc::geteuid() };
+        let dir = std::env::temp_dir().join(format!("mutx-{}", uid));
+        match verify_private_dir(&dir) {
+            Ok(()) => Some(dir),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Some(dir),
+            Err(e) => {
+                tracing::warn!("Not using {}: {}", dir.display(), e);
+                None
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// Check that `dir` is a real directory owned by this user that no one else
+/// can access
+#[cfg(unix)]
+pub fn verify_private_dir(dir: &Path) -> io::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = std::fs::symlink_metadata(dir)?;
+    // SAFETY: geteuid cannot fail
+    let uid = unsafe { libc::geteuid() };
+    if !metadata.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "not a directory",
+        ));
+    }
+    if metadata.uid() != uid {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("owned by uid {}", metadata.uid()),
+        ));
+    }
+    if metadata.mode() & 0o077 != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("accessible to others (mode {:o})", metadata.mode() & 0o777),
+        ));
+    }
+    Ok(())
+}
+
+/// Whether `dir` exists or could be created: its nearest existing ancestor
+/// is a directory this process may write to
+pub fn can_create(dir: &Path) -> bool {
+    let Some(existing) = dir.ancestors().find(|a| a.exists()) else {
+        return false;
+    };
+    existing.is_dir() && is_writable(existing)
+}
+
+#[cfg(unix)]
+fn is_writable(dir: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(path) = std::ffi::CString::new(dir.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: path is a valid NUL-terminated string
+    unsafe { libc::access(path.as_ptr(), libc::W_OK | libc::X_OK) == 0 }
+}
+
+#[cfg(not(unix))]
+fn is_writable(dir: &Path) -> bool {
+    std::fs::metadata(dir).is_ok_and(|m| !m.permissions().readonly())
+}
+
+/// Create `dir` and any missing parents, accessible only to this user as
+/// the XDG base directory specification asks
+pub fn create_private_dir_all(dir: &Path) -> io::Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(dir)
+}
+
+/// An XDG base directory variable, ignored unless absolute as the
+/// specification requires
+fn xdg_base(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+}
+
+/// Mark `dir` as a cache directory. An existing tag file is left as it is.
+pub fn write_cachedir_tag(dir: &Path) -> io::Result<()> {
+    let mut file = match OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(dir.join(CACHEDIR_TAG))
+    {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    writeln!(file, "{}", CACHEDIR_TAG_SIGNATURE)?;
+    writeln!(
+        file,
+        "# This file is a cache directory tag created by mutx."
+    )?;
+    writeln!(file, "# For information about cache directory tags, see:")?;
+    writeln!(file, "#\thttps://bford.info/cachedir/")
+}
+
+/// Whether `dir` carries a valid cache directory tag
+pub fn has_cachedir_tag(dir: &Path) -> bool {
+    std::fs::read(dir.join(CACHEDIR_TAG))
+        .is_ok_and(|contents| contents.starts_with(CACHEDIR_TAG_SIGNATURE.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_cachedir_tag_is_written_once() {
+        let temp = TempDir::new().unwrap();
+        assert!(!has_cachedir_tag(temp.path()));
+
+        write_cachedir_tag(temp.path()).unwrap();
+        assert!(has_cachedir_tag(temp.path()));
+
+        let tag = temp.path().join(CACHEDIR_TAG);
+        std::fs::write(&tag, "custom").unwrap();
+        write_cachedir_tag(temp.path()).unwrap();
+        assert_eq!(std::fs::read_to_string(&tag).unwrap(), "custom");
+    }
+}
@@ -1,3 +1,6 @@
+use crate::lock::LockHolder;
+use crate::parse::format_size;
+use crate::write::engine::WriteStep;
 use std::io;
 use std::path::PathBuf;
 use std::time::Duration;
@@ -5,11 +8,29 @@ use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum MutxError {
-    #[error("Failed to acquire lock on {path}: timeout after {duration:?}")]
-    LockTimeout { path: PathBuf, duration: Duration },
+    #[error("Failed to acquire lock on {path}: timeout after {duration:?}{}", held_by(.holder))]
+    LockTimeout {
+        path: PathBuf,
+        duration: Duration,
+        /// Holder recorded in the lock file, if any (see [`LockHolder`])
+        holder: Option<Box<LockHolder>>,
+    },
 
-    #[error("Failed to acquire lock on {0}: file is locked by another process")]
-    LockWouldBlock(PathBuf),
+    #[error("Failed to acquire lock on {path}: file is locked by another process{}", held_by(.holder))]
+    LockWouldBlock {
+        path: PathBuf,
+        /// Holder recorded in the lock file, if any (see [`LockHolder`])
+        holder: Option<Box<LockHolder>>,
+    },
+
+    /// The lock is held by a process waiting for this one to finish, as
+    /// when a command run by `mutx exec` invokes mutx on the same file
+    #[error("Waiting for the lock on {path} would deadlock:\n{chain}")]
+    LockCycle {
+        path: PathBuf,
+        /// One line per holder in the cycle, outermost first
+        chain: String,
+    },
 
     #[error("Failed to create lock file {path}: {source}")]
     LockCreationFailed { path: PathBuf, source: io::Error },
@@ -20,6 +41,20 @@ pub enum MutxError {
     #[error("Failed to write to {path}: {source}")]
     WriteFailed { path: PathBuf, source: io::Error },
 
+    #[error("Out of space writing {path} after {bytes_written} bytes: {source}")]
+    OutOfSpace {
+        path: PathBuf,
+        bytes_written: u64,
+        source: io::Error,
+    },
+
+    #[error("Failed to write to {path} while {step}: {source}")]
+    WriteStepFailed {
+        path: PathBuf,
+        step: WriteStep,
+        source: io::Error,
+    },
+
     #[error("Failed to create backup of {path}: {source}")]
     BackupFailed { path: PathBuf, source: io::Error },
 
@@ -29,30 +64,98 @@ pub enum MutxError {
     #[error("Invalid duration format '{input}': {message}")]
     InvalidDuration { input: String, message: String },
 
+    #[error("Invalid size '{input}': {message}")]
+    InvalidSize { input: String, message: String },
+
+    #[error("Refusing to write {path}: content exceeds the size limit of {}", format_size(*.limit))]
+    SizeLimitExceeded { path: PathBuf, limit: u64 },
+
+    #[error(
+        "Refusing to commit {path}: only {} would remain free, below the minimum of {}",
+        format_size(*.available),
+        format_size(*.min_free)
+    )]
+    BelowMinFree {
+        path: PathBuf,
+        available: u64,
+        min_free: u64,
+    },
+
     #[error("Invalid file permissions '{input}': must be octal (e.g., 0644)")]
     InvalidPermissions { input: String },
 
     #[error("Path does not exist: {0}")]
     PathNotFound(PathBuf),
 
+    #[error(
+        "Target file is read-only: {0}\nMake it writable or drop --respect-readonly to replace it."
+    )]
+    TargetReadOnly(PathBuf),
+
     #[error("Path is not a file: {0}")]
     NotAFile(PathBuf),
 
+    #[error("Input is a {kind}, not a regular file: {path}\n{}", input_guidance(.kind))]
+    InputNotRegular { path: PathBuf, kind: &'static str },
+
+    #[error("Target is in use by another process: {path}\nIt is open without delete sharing (editors, antivirus scanners and search indexers do this) and could not be replaced within {waited:.1?}. Close the program holding it, or wait longer with --rename-retry.")]
+    TargetInUse {
+        path: PathBuf,
+        waited: Duration,
+        source: io::Error,
+    },
+
+    #[error("Target is a special file and cannot be replaced atomically: {0}\nUse --passthrough-special to write to it directly under the lock.")]
+    SpecialFile(PathBuf),
+
     #[error("Path is not a directory: {0}")]
     NotADirectory(PathBuf),
 
+    #[error("Target is a directory: {0}\nName a file inside it, or use --into-dir with --input to write DIR/<input file name>.")]
+    IsADirectory(PathBuf),
+
     #[error("Path is a symbolic link: {path}\nUse --follow-symlinks to allow symlinks.\nThis is disabled by default for security.")]
     SymlinkNotAllowed { path: PathBuf },
 
     #[error("Lock file path is a symbolic link: {path}\nUse --follow-lock-symlinks to allow symlinks to lock files.\nWARNING: This may be a security risk.")]
     LockSymlinkNotAllowed { path: PathBuf },
 
+    #[error("Too many levels of symbolic links resolving {path}\nThe link chain loops or is longer than 40 links.")]
+    SymlinkLoop { path: PathBuf },
+
     #[error("Lock file path cannot equal output file path.\nLock: {lock_path}\nOutput: {output_path}\nSpecify a different path with --lock-file.")]
     LockPathCollision {
         lock_path: PathBuf,
         output_path: PathBuf,
     },
 
+    #[error("Invalid lock file path {path}: {reason}")]
+    InvalidLockPath { path: PathBuf, reason: String },
+
+    #[error("Lock file path escapes the allowed lock root.\nLock: {path}\nRoot: {root}")]
+    LockPathOutsideRoot { path: PathBuf, root: PathBuf },
+
+    #[error("Path escapes its configured directory.\nPath: {path}\nDirectory: {base}")]
+    PathEscapes { path: PathBuf, base: PathBuf },
+
+    #[error("Refusing to commit {path}: the lease of its lock was lost and may have been granted to another holder")]
+    LeaseLost { path: PathBuf },
+
+    #[error("Locking on {path} cannot guarantee mutual exclusion: {reason}")]
+    UnreliableLocking { path: PathBuf, reason: String },
+
+    #[error("Cannot write {path} with the required guarantees: {missing} unavailable")]
+    GuaranteeUnavailable { path: PathBuf, missing: String },
+
+    #[error("Failed to sign {path}: {reason}")]
+    SigningFailed { path: PathBuf, reason: String },
+
+    #[error("Signature verification failed for {path}: {reason}")]
+    SignatureInvalid { path: PathBuf, reason: String },
+
+    #[error("Invalid configuration file {path}: {message}")]
+    InvalidConfig { path: PathBuf, message: String },
+
     #[error("Failed to create cache directory {path}: {source}")]
     CacheDirectoryFailed { path: PathBuf, source: io::Error },
 
@@ -72,7 +175,9 @@ pub enum MutxError {
 impl MutxError {
     pub fn exit_code(&self) -> i32 {
         match self {
-            MutxError::LockTimeout { .. } | MutxError::LockWouldBlock(_) => 2,
+            MutxError::LockTimeout { .. }
+            | MutxError::LockWouldBlock { .. }
+            | MutxError::LockCycle { .. } => 2,
             // On Windows, lock failures may come through as LockAcquisitionFailed
             // with raw_os_error 33 (ERROR_LOCK_VIOLATION) instead of WouldBlock
             MutxError::LockAcquisitionFailed { source, .. }
@@ -81,6 +186,8 @@ impl MutxError {
                 2
             }
             MutxError::Interrupted => 3,
+            MutxError::BackupFailed { .. } => 4,
+            MutxError::InputNotRegular { .. } => 5,
             MutxError::PermissionDenied(_) => 1,
             MutxError::Io(e) if e.kind() == io::ErrorKind::PermissionDenied => 1,
             MutxError::Io(e) if e.kind() == io::ErrorKind::Interrupted => 3,
@@ -88,6 +195,55 @@ impl MutxError {
         }
     }
 
+    /// Stable snake_case name of the error, reported as `kind` in
+    /// `mutx.error.v1` documents (see [`crate::schema`]). Unlike the message,
+    /// it never changes for an existing error.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            MutxError::LockTimeout { .. } => "lock_timeout",
+            MutxError::LockWouldBlock { .. } => "lock_would_block",
+            MutxError::LockCycle { .. } => "lock_cycle",
+            MutxError::LockCreationFailed { .. } => "lock_creation_failed",
+            MutxError::LockAcquisitionFailed { .. } => "lock_acquisition_failed",
+            MutxError::WriteFailed { .. } => "write_failed",
+            MutxError::OutOfSpace { .. } => "out_of_space",
+            MutxError::WriteStepFailed { .. } => "write_step_failed",
+            MutxError::BackupFailed { .. } => "backup_failed",
+            MutxError::ReadFailed { .. } => "read_failed",
+            MutxError::InvalidDuration { .. } => "invalid_duration",
+            MutxError::InvalidSize { .. } => "invalid_size",
+            MutxError::SizeLimitExceeded { .. } => "size_limit_exceeded",
+            MutxError::BelowMinFree { .. } => "below_min_free",
+            MutxError::InvalidPermissions { .. } => "invalid_permissions",
+            MutxError::PathNotFound(_) => "path_not_found",
+            MutxError::TargetReadOnly(_) => "target_read_only",
+            MutxError::NotAFile(_) => "not_a_file",
+            MutxError::InputNotRegular { .. } => "input_not_regular",
+            MutxError::TargetInUse { .. } => "target_in_use",
+            MutxError::SpecialFile(_) => "special_file",
+            MutxError::NotADirectory(_) => "not_a_directory",
+            MutxError::IsADirectory(_) => "is_a_directory",
+            MutxError::SymlinkNotAllowed { .. } => "symlink_not_allowed",
+            MutxError::LockSymlinkNotAllowed { .. } => "lock_symlink_not_allowed",
+            MutxError::SymlinkLoop { .. } => "symlink_loop",
+            MutxError::LockPathCollision { .. } => "lock_path_collision",
+            MutxError::InvalidLockPath { .. } => "invalid_lock_path",
+            MutxError::LockPathOutsideRoot { .. } => "lock_path_outside_root",
+            MutxError::PathEscapes { .. } => "path_escapes",
+            MutxError::LeaseLost { .. } => "lease_lost",
+            MutxError::UnreliableLocking { .. } => "unreliable_locking",
+            MutxError::GuaranteeUnavailable { .. } => "guarantee_unavailable",
+            MutxError::SigningFailed { .. } => "signing_failed",
+            MutxError::SignatureInvalid { .. } => "signature_invalid",
+            MutxError::InvalidConfig { .. } => "invalid_config",
+            MutxError::CacheDirectoryFailed { .. } => "cache_directory_failed",
+            MutxError::Interrupted => "interrupted",
+            MutxError::PermissionDenied(_) => "permission_denied",
+            MutxError::Io(_) => "io",
+            MutxError::Other(_) => "other",
+        }
+    }
+
     /// Check if an I/O error indicates lock contention (file locked by another process)
     fn is_lock_contention_error(e: &io::Error) -> bool {
         // Check for WouldBlock (Unix)
@@ -110,11 +266,34 @@ impl MutxError {
         MutxError::LockTimeout {
             path: PathBuf::new(),
             duration,
+            holder: None,
         }
     }
 
     pub fn lock_would_block(path: impl Into<PathBuf>) -> Self {
-        MutxError::LockWouldBlock(path.into())
+        MutxError::LockWouldBlock {
+            path: path.into(),
+            holder: None,
+        }
+    }
+}
+
+/// ` (held by PID 1234 on host since 14:02)`, or nothing without a holder
+fn held_by(holder: &Option<Box<LockHolder>>) -> String {
+    match holder {
+        Some(holder) => format!(" (held by {})", holder),
+        None => String::new(),
+    }
+}
+
+/// What to do instead of reading an input of the given kind (see
+/// [`crate::write::file_type_name`])
+fn input_guidance(kind: &str) -> &'static str {
+    match kind {
+        "directory" => "Name a file inside it with --input.",
+        "socket" => "Sockets cannot be opened as files; read it with a tool such as socat and pipe the output to mutx.",
+        "block device" => "Devices holding filesystems are not read as input; copy what you need with dd and pipe it to mutx.",
+        _ => "Use --input-allow-special to read it anyway, until it ends; add --max-size to fail rather than read an endless device such as /dev/urandom forever.",
     }
 }
 
@@ -1,7 +1,16 @@
+use crate::backup::{BackupLocation, BackupSuffix, DEFAULT_BACKUP_SUFFIX, LEGACY_BACKUP_SUFFIX};
 use crate::error::{MutxError, Result};
+use crate::lock::is_lock_shard;
+use crate::lock::queue::{queue_dir, remove_idle};
+use crate::utils::{
+    is_mutx_scratch, is_mutx_temp, pid_is_alive, temp_owner_pid, temp_target_name, to_nfc,
+};
+use chrono::{Local, NaiveDateTime, TimeZone};
 use fs2::FileExt;
+use std::fmt;
 use std::fs::{self, File};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::time::{Duration, SystemTime};
 use tracing::{debug, warn};
 
@@ -13,6 +22,16 @@ pub struct CleanLockConfig {
     pub dry_run: bool,
 }
 
+#[derive(Debug, Clone)]
+pub struct CleanTempConfig {
+    pub dir: PathBuf,
+    pub recursive: bool,
+    /// Only remove temps not modified for this long, so writes still in
+    /// progress are left alone
+    pub older_than: Option<Duration>,
+    pub dry_run: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct CleanBackupConfig {
     pub dir: PathBuf,
@@ -20,15 +39,88 @@ pub struct CleanBackupConfig {
     pub older_than: Option<Duration>,
     pub keep_newest: Option<usize>,
     pub dry_run: bool,
-    pub suffix: String,
+    pub suffix: BackupSuffix,
+    pub age_source: AgeSource,
+    /// After the age and count limits, remove the oldest timestamped backups
+    /// until all backups found take at most this many bytes
+    pub max_total_size: Option<u64>,
+}
+
+/// Where a backup's age is taken from for `older_than` and `keep_newest`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AgeSource {
+    /// The timestamp embedded in the backup's name, falling back to mtime for
+    /// backups without one (default). Survives copies and rsyncs that reset
+    /// mtime.
+    #[default]
+    Name,
+    /// The file's modification time
+    Mtime,
+    /// Whichever of the two is more recent, so a backup only counts as old
+    /// when both agree
+    Newest,
+}
+
+impl fmt::Display for AgeSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AgeSource::Name => write!(f, "name"),
+            AgeSource::Mtime => write!(f, "mtime"),
+            AgeSource::Newest => write!(f, "newest"),
+        }
+    }
+}
+
+impl FromStr for AgeSource {
+    type Err = MutxError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "name" => Ok(AgeSource::Name),
+            "mtime" => Ok(AgeSource::Mtime),
+            "newest" => Ok(AgeSource::Newest),
+            _ => Err(MutxError::Other(format!(
+                "Unknown age source '{}': expected one of name, mtime, newest",
+                s
+            ))),
+        }
+    }
+}
+
+impl AgeSource {
+    /// Effective time of a backup given its name timestamp and mtime
+    fn resolve(&self, named: Option<SystemTime>, mtime: SystemTime) -> SystemTime {
+        match (self, named) {
+            (AgeSource::Name, Some(named)) => named,
+            (AgeSource::Newest, Some(named)) => named.max(mtime),
+            _ => mtime,
+        }
+    }
 }
 
 /// Clean orphaned lock files
 pub fn clean_locks(config: &CleanLockConfig) -> Result<Vec<PathBuf>> {
     let mut cleaned = Vec::new();
 
-    visit_directory(&config.dir, config.recursive, &mut |path| {
+    visit_lock_directory(&config.dir, config.recursive, &mut |path| {
         if is_lock_file(path) {
+            // Before the lock itself, whose removal would hide its queue
+            let queue = queue_dir(path);
+            if queue.is_dir() {
+                match remove_idle(&queue, config.dry_run) {
+                    Ok(true) => {
+                        if config.dry_run {
+                            debug!("Would remove lock queue: {}", queue.display());
+                        } else {
+                            debug!("Removed idle lock queue: {}", queue.display());
+                        }
+                        cleaned.push(queue);
+                    }
+                    Ok(false) => debug!("Lock queue in use, skipping: {}", queue.display()),
+                    Err(e) => warn!("Failed to remove lock queue {}: {}", queue.display(), e),
+                }
+            }
+
             match is_orphaned(path, config.older_than) {
                 Ok(true) => {
                     if config.dry_run {
@@ -66,48 +158,138 @@ pub fn clean_locks(config: &CleanLockConfig) -> Result<Vec<PathBuf>> {
     Ok(cleaned)
 }
 
-/// Clean old backup files
+/// Summary of a lock cache directory, as shown by `mutx lock cache-info`
+#[derive(Debug, Clone, Default)]
+pub struct LockCacheInfo {
+    pub dir: PathBuf,
+    /// Lock files found, in shards and at the top level
+    pub files: u64,
+    /// Combined size of the lock files, in bytes
+    pub total_bytes: u64,
+    /// Least recently modified lock file and its modification time
+    pub oldest: Option<(PathBuf, SystemTime)>,
+    /// Lock files currently locked by a running process
+    pub held: u64,
+    /// Lock files nobody holds, which `mutx housekeep locks` would remove
+    pub orphaned: u64,
+    /// Lock files that could not be checked
+    pub unreadable: u64,
+    /// Lock files at the top level of the cache, named under scheme v1 and
+    /// not yet moved into a shard
+    pub unmigrated: u64,
+}
+
+/// Count and classify the lock files in `dir` without changing anything
+pub fn lock_cache_info(dir: &Path) -> Result<LockCacheInfo> {
+    let mut info = LockCacheInfo {
+        dir: dir.to_path_buf(),
+        ..Default::default()
+    };
+    if !dir.exists() {
+        return Ok(info);
+    }
+
+    visit_lock_directory(dir, false, &mut |path| {
+        if !is_lock_file(path) {
+            return Ok(());
+        }
+        let Ok(metadata) = fs::metadata(path) else {
+            // Released and removed since the directory was listed
+            return Ok(());
+        };
+        info.files += 1;
+        info.total_bytes += metadata.len();
+        if path.parent() == Some(dir) {
+            info.unmigrated += 1;
+        }
+        if let Ok(mtime) = metadata.modified() {
+            if info
+                .oldest
+                .as_ref()
+                .map_or(true, |(_, oldest)| mtime < *oldest)
+            {
+                info.oldest = Some((path.to_path_buf(), mtime));
+            }
+        }
+        match is_orphaned(path, None) {
+            Ok(true) => info.orphaned += 1,
+            Ok(false) => info.held += 1,
+            Err(e) => {
+                debug!("Error checking lock file {}: {}", path.display(), e);
+                info.unreadable += 1;
+            }
+        }
+        Ok(())
+    })?;
+
+    Ok(info)
+}
+
+/// Clean old backup files, and temps left behind by backups that were
+/// interrupted before their rename (unless their writer is still running)
 pub fn clean_backups(config: &CleanBackupConfig) -> Result<Vec<PathBuf>> {
     use std::collections::HashMap;
 
-    let mut backups: HashMap<String, Vec<(PathBuf, SystemTime)>> = HashMap::new();
+    // (path, effective time, whether the name carries a timestamp, its
+    // number within the second)
+    let mut backups: HashMap<String, Vec<(PathBuf, SystemTime, bool, u32)>> = HashMap::new();
+    let mut doomed = Vec::new();
 
     // Collect all backups grouped by base filename
     visit_directory(&config.dir, config.recursive, &mut |path| {
-        if is_backup_file(path, &config.suffix) {
+        if is_backup_temp(path, config.suffix.as_str()) {
+            if is_stale_temp(path, config.older_than) {
+                doomed.push(path.to_path_buf());
+            }
+        } else if let Some(suffix) = matching_backup_suffix(path, config.suffix.as_str()) {
             if let Ok(metadata) = fs::metadata(path) {
                 if let Ok(mtime) = metadata.modified() {
-                    let base = extract_base_filename(path, &config.suffix);
-                    backups
-                        .entry(base)
-                        .or_default()
-                        .push((path.to_path_buf(), mtime));
+                    let base = extract_base_filename(path, suffix);
+                    let named = backup_name_timestamp(path, suffix);
+                    backups.entry(base).or_default().push((
+                        path.to_path_buf(),
+                        config.age_source.resolve(named, mtime),
+                        named.is_some(),
+                        backup_name_sequence(path, suffix),
+                    ));
                 }
             }
         }
         Ok(())
     })?;
 
-    let mut cleaned = Vec::new();
+    // Timestamped backups that survive the age and count limits, with their
+    // time and size, for max_total_size
+    let mut kept: Vec<(PathBuf, SystemTime, u64)> = Vec::new();
+    let mut kept_total = 0u64;
 
     // Process each group of backups
     for (_, mut group) in backups {
-        // Sort by modification time (newest first)
-        group.sort_by(|a, b| b.1.cmp(&a.1));
+        // Sort by age (newest first)
+        group.sort_by_key(|b| std::cmp::Reverse((b.1, b.3)));
+
+        // The suffix-only backup (`file.txt.mutx.backup`) is a single "latest"
+        // slot that every untimestamped backup overwrites. It is not part of
+        // the timestamped history, so keep_newest neither counts nor removes
+        // it; only older_than applies.
+        let mut timestamped_seen = 0;
 
-        for (idx, (path, mtime)) in group.iter().enumerate() {
+        for (path, time, timestamped, _) in group {
             let mut should_delete = false;
 
             // Check keep_newest
-            if let Some(keep) = config.keep_newest {
-                if idx >= keep {
-                    should_delete = true;
+            if timestamped {
+                if let Some(keep) = config.keep_newest {
+                    if timestamped_seen >= keep {
+                        should_delete = true;
+                    }
                 }
+                timestamped_seen += 1;
             }
 
             // Check older_than
             if let Some(max_age) = config.older_than {
-                if let Ok(elapsed) = SystemTime::now().duration_since(*mtime) {
+                if let Ok(elapsed) = SystemTime::now().duration_since(time) {
                     if elapsed > max_age {
                         should_delete = true;
                     }
@@ -115,31 +297,245 @@ pub fn clean_backups(config: &CleanBackupConfig) -> Result<Vec<PathBuf>> {
             }
 
             if should_delete {
-                if config.dry_run {
-                    debug!("Would remove backup: {}", path.display());
-                    cleaned.push(path.clone());
-                } else {
-                    match fs::remove_file(path) {
-                        Ok(_) => {
-                            debug!("Removed old backup: {}", path.display());
-                            cleaned.push(path.clone());
-                        }
-                        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                            debug!("Backup file already removed: {}", path.display());
-                        }
-                        Err(e) => {
-                            warn!("Failed to remove backup {}: {}", path.display(), e);
-                        }
-                    }
+                doomed.push(path);
+            } else if config.max_total_size.is_some() {
+                let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                kept_total += size;
+                if timestamped {
+                    kept.push((path, time, size));
                 }
             }
         }
     }
 
+    // Like keep_newest, the size limit leaves the latest slots alone
+    if let Some(limit) = config.max_total_size {
+        kept.sort_by_key(|b| b.1);
+        for (path, _, size) in kept {
+            if kept_total <= limit {
+                break;
+            }
+            kept_total -= size;
+            doomed.push(path);
+        }
+    }
+
+    let mut cleaned = Vec::new();
+    for path in doomed {
+        if config.dry_run {
+            debug!("Would remove backup: {}", path.display());
+            cleaned.push(path);
+        } else {
+            match fs::remove_file(&path) {
+                Ok(_) => {
+                    debug!("Removed old backup: {}", path.display());
+                    cleaned.push(path);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    debug!("Backup file already removed: {}", path.display());
+                }
+                Err(e) => {
+                    warn!("Failed to remove backup {}: {}", path.display(), e);
+                }
+            }
+        }
+    }
+
+    Ok(cleaned)
+}
+
+/// Free `needed` bytes for a write to `target` by removing its timestamped
+/// backups, oldest first, and return the number of bytes freed.
+///
+/// Backups are looked for in `directory`, or beside the target. The
+/// suffix-only backup and the newest timestamped one are kept, so the most
+/// recent copy survives, and fewer bytes are freed if that is all there is.
+pub fn reclaim_backups(
+    target: &Path,
+    suffix: &BackupSuffix,
+    directory: Option<&Path>,
+    needed: u64,
+) -> Result<u64> {
+    let mut backups = timestamped_backups(target, suffix.as_str(), directory)?;
+    backups.pop();
+    Ok(remove_oldest(backups, needed))
+}
+
+/// Remove the oldest timestamped backups of `target` until at most
+/// `keep_newest` remain, and return the number of bytes freed. The
+/// suffix-only backup is never removed.
+pub fn prune_backups(
+    target: &Path,
+    suffix: &str,
+    directory: Option<&Path>,
+    keep_newest: usize,
+) -> Result<u64> {
+    let mut backups = timestamped_backups(target, suffix, directory)?;
+
+    // Oldest first; keep the newest
+    backups.truncate(backups.len().saturating_sub(keep_newest));
+    Ok(remove_oldest(backups, u64::MAX))
+}
+
+/// Remove `backups`, oldest first, until `needed` bytes are freed, and
+/// return the number of bytes freed
+fn remove_oldest(backups: Vec<(SystemTime, PathBuf)>, needed: u64) -> u64 {
+    let mut freed = 0;
+    for (_, path) in backups {
+        if freed >= needed {
+            break;
+        }
+        let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        match fs::remove_file(&path) {
+            Ok(()) => {
+                debug!("Removed backup to free space: {}", path.display());
+                freed += size;
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Failed to remove backup {}: {}", path.display(), e),
+        }
+    }
+    freed
+}
+
+/// The timestamped backups of `target` in `directory` (or beside the
+/// target), with the time in their names, oldest first
+pub(crate) fn timestamped_backups(
+    target: &Path,
+    suffix: &str,
+    directory: Option<&Path>,
+) -> Result<Vec<(SystemTime, PathBuf)>> {
+    let location = BackupLocation::of(target, directory)?;
+
+    let mut backups = Vec::new();
+    visit_directory(&location.dir, false, &mut |path| {
+        if let Some(suffix) = matching_backup_suffix(path, suffix) {
+            if location.owns(path, &extract_base_filename(path, suffix)) {
+                if let Some(time) = backup_name_timestamp(path, suffix) {
+                    backups.push((time, backup_name_sequence(path, suffix), path.to_path_buf()));
+                }
+            }
+        }
+        Ok(())
+    })?;
+    backups.sort();
+    Ok(backups
+        .into_iter()
+        .map(|(time, _, path)| (time, path))
+        .collect())
+}
+
+/// Clean temporary files left behind by interrupted writes and backups.
+///
+/// Matches files ending in [`TEMP_SUFFIX`](crate::utils::TEMP_SUFFIX), plus
+/// temp files of the `atomic_write_file` naming scheme used by earlier
+/// releases (`.<name>.<6 alphanumerics>`) when `<name>` exists beside them,
+/// and scratch directories of interrupted writes (ending in
+/// [`SCRATCH_SUFFIX`](crate::utils::SCRATCH_SUFFIX)), with their contents.
+/// Temps whose name records a PID still running on this host are skipped.
+pub fn clean_temps(config: &CleanTempConfig) -> Result<Vec<PathBuf>> {
+    let mut cleaned = Vec::new();
+
+    visit_entries(&config.dir, config.recursive, true, &mut |path| {
+        let scratch = is_mutx_scratch(path);
+        if !scratch && !is_mutx_temp(path) && !is_legacy_write_temp(path) {
+            return Ok(());
+        }
+
+        if !is_stale_temp(path, config.older_than) {
+            return Ok(());
+        }
+
+        if config.dry_run {
+            debug!("Would remove temp file: {}", path.display());
+            cleaned.push(path.to_path_buf());
+            return Ok(());
+        }
+
+        let removed = if scratch {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        };
+        match removed {
+            Ok(_) => {
+                debug!("Removed temp file: {}", path.display());
+                cleaned.push(path.to_path_buf());
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                debug!("Temp file already removed: {}", path.display());
+            }
+            Err(e) => {
+                warn!("Failed to remove temp file {}: {}", path.display(), e);
+            }
+        }
+        Ok(())
+    })?;
+
     Ok(cleaned)
 }
 
+/// Whether a temp file can be removed: its writer is not running on this
+/// host, and it is at least `older_than` old when that is given
+fn is_stale_temp(path: &Path, older_than: Option<Duration>) -> bool {
+    // A temp whose writer is still running is a write in progress
+    if temp_owner_pid(path).and_then(pid_is_alive) == Some(true) {
+        debug!("Temp file owner is running, skipping: {}", path.display());
+        return false;
+    }
+
+    if let Some(max_age) = older_than {
+        let mtime = fs::metadata(path).and_then(|m| m.modified());
+        match mtime.map(|mtime| SystemTime::now().duration_since(mtime)) {
+            Ok(Ok(elapsed)) if elapsed >= max_age => {}
+            Ok(_) => {
+                debug!("Temp file is recent, skipping: {}", path.display());
+                return false;
+            }
+            Err(e) => {
+                warn!("Error checking temp file {}: {}", path.display(), e);
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Whether `path` is a temp staging a backup with `suffix`
+fn is_backup_temp(path: &Path, suffix: &str) -> bool {
+    is_mutx_temp(path)
+        && temp_target_name(path).is_some_and(|name| is_backup_file(Path::new(name), suffix))
+}
+
+/// `.<name>.<6 alphanumerics>` next to an existing `<name>`
+fn is_legacy_write_temp(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    let Some((target, random)) = name
+        .strip_prefix('.')
+        .and_then(|rest| rest.rsplit_once('.'))
+    else {
+        return false;
+    };
+
+    !target.is_empty()
+        && random.len() == 6
+        && random.chars().all(|c| c.is_ascii_alphanumeric())
+        && path.with_file_name(target).is_file()
+}
+
 fn visit_directory<F>(dir: &Path, recursive: bool, visitor: &mut F) -> Result<()>
+where
+    F: FnMut(&Path) -> Result<()>,
+{
+    visit_entries(dir, recursive, false, visitor)
+}
+
+/// Like [`visit_directory`], also visiting the scratch directories of
+/// writes with `scratch`. They are never descended into: each is one temp,
+/// not a tree to search.
+fn visit_entries<F>(dir: &Path, recursive: bool, scratch: bool, visitor: &mut F) -> Result<()>
 where
     F: FnMut(&Path) -> Result<()>,
 {
@@ -161,8 +557,12 @@ where
             continue;
         }
 
-        if file_type.is_dir() && recursive {
-            visit_directory(&path, recursive, visitor)?;
+        if file_type.is_dir() && is_mutx_scratch(&path) {
+            if scratch {
+                visitor(&path)?;
+            }
+        } else if file_type.is_dir() && recursive {
+            visit_entries(&path, recursive, scratch, visitor)?;
         } else if file_type.is_file() {
             visitor(&path)?;
         }
@@ -170,27 +570,99 @@ where
     Ok(())
 }
 
+/// Like [`visit_directory`], but always descends into the lock cache's shard
+/// subdirectories (see [`crate::lock::lock_shard`])
+fn visit_lock_directory<F>(dir: &Path, recursive: bool, visitor: &mut F) -> Result<()>
+where
+    F: FnMut(&Path) -> Result<()>,
+{
+    visit_directory(dir, recursive, visitor)?;
+    if recursive {
+        return Ok(());
+    }
+
+    let entries = fs::read_dir(dir).map_err(|e| MutxError::ReadFailed {
+        path: dir.to_path_buf(),
+        source: e,
+    })?;
+    for entry in entries {
+        let entry = entry.map_err(MutxError::Io)?;
+        let is_shard = entry.file_name().to_str().is_some_and(is_lock_shard);
+        // file_type() does not follow symlinks, so linked shards are skipped
+        if is_shard && entry.file_type().map_err(MutxError::Io)?.is_dir() {
+            visit_directory(&entry.path(), false, visitor)?;
+        }
+    }
+    Ok(())
+}
+
 fn is_lock_file(path: &Path) -> bool {
     path.extension().and_then(|s| s.to_str()) == Some("lock")
 }
 
-fn is_backup_file(path: &Path, suffix: &str) -> bool {
+/// Suffix under which `path` counts as a backup when cleaning with `suffix`.
+///
+/// Cleaning with the default suffix also picks up timestamped backups written
+/// with [`LEGACY_BACKUP_SUFFIX`] by older releases, grouped with their current
+/// counterparts. Untimestamped legacy names (`file.backup`) are left alone:
+/// they cannot be told apart from backups made by hand.
+fn matching_backup_suffix<'a>(path: &Path, suffix: &'a str) -> Option<&'a str> {
+    if is_backup_file(path, suffix) {
+        return Some(suffix);
+    }
+    if suffix == DEFAULT_BACKUP_SUFFIX
+        && is_backup_file(path, LEGACY_BACKUP_SUFFIX)
+        && split_backup_name(path, LEGACY_BACKUP_SUFFIX).1.is_some()
+    {
+        return Some(LEGACY_BACKUP_SUFFIX);
+    }
+    None
+}
+
+pub(crate) fn is_backup_file(path: &Path, suffix: &str) -> bool {
     path.file_name()
         .and_then(|s| s.to_str())
-        .map(|name| name.ends_with(suffix))
+        .map(|name| to_nfc(name).ends_with(&to_nfc(suffix)))
         .unwrap_or(false)
 }
 
-fn extract_base_filename(path: &Path, suffix: &str) -> String {
+pub(crate) fn extract_base_filename(path: &Path, suffix: &str) -> String {
+    split_backup_name(path, suffix).0
+}
+
+/// Time encoded in a timestamped backup's name (`name.YYYYMMDD_HHMMSS<suffix>`),
+/// interpreted in local time as written by [`crate::backup::create_backup`]
+pub(crate) fn backup_name_timestamp(path: &Path, suffix: &str) -> Option<SystemTime> {
+    let timestamp = split_backup_name(path, suffix).1?;
+    let naive = NaiveDateTime::parse_from_str(&timestamp[..15], "%Y%m%d_%H%M%S").ok()?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(SystemTime::from)
+}
+
+/// Number of a timestamped backup taken in the same second as earlier ones
+/// (`name.YYYYMMDD_HHMMSS-N<suffix>`), or 0 for the first
+fn backup_name_sequence(path: &Path, suffix: &str) -> u32 {
+    split_backup_name(path, suffix)
+        .1
+        .and_then(|timestamp| timestamp.get(16..)?.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Split a backup filename into its base filename and optional timestamp
+fn split_backup_name(path: &Path, suffix: &str) -> (String, Option<String>) {
     let name = path
         .file_name()
         .and_then(|n| n.to_str())
-        .unwrap_or("unknown");
+        .map(to_nfc)
+        .unwrap_or_else(|| "unknown".to_string());
+    let suffix = to_nfc(suffix);
 
     // Must end with the suffix
-    let without_suffix = match name.strip_suffix(suffix) {
+    let without_suffix = match name.strip_suffix(suffix.as_str()) {
         Some(s) => s,
-        None => return name.to_string(),
+        None => return (name, None),
     };
 
     // Try to parse timestamp: filename.YYYYMMDD_HHMMSS
@@ -198,16 +670,22 @@ fn extract_base_filename(path: &Path, suffix: &str) -> String {
     if parts.len() == 2 {
         let timestamp = parts[0];
         if is_valid_timestamp(timestamp) {
-            return parts[1].to_string(); // Base filename without timestamp
+            // Base filename without timestamp
+            return (parts[1].to_string(), Some(timestamp.to_string()));
         }
     }
 
     // No timestamp found, return without suffix
-    without_suffix.to_string()
+    (without_suffix.to_string(), None)
 }
 
 fn is_valid_timestamp(s: &str) -> bool {
-    // YYYYMMDD_HHMMSS format (15 chars)
+    // YYYYMMDD_HHMMSS format (15 chars), numbered -N when backups share a second
+    let s = match s.split_once('-') {
+        Some((s, n)) if !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()) => s,
+        Some(_) => return false,
+        None => s,
+    };
     if s.len() != 15 {
         return false;
     }
@@ -223,6 +701,18 @@ fn is_valid_timestamp(s: &str) -> bool {
 }
 
 fn is_orphaned(lock_path: &Path, older_than: Option<Duration>) -> Result<bool> {
+    // A lock file linked between its scheme v1 name and its shard is how
+    // older releases and this one share a lock; removing either name would
+    // let them take different locks
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        if fs::metadata(lock_path).map_err(MutxError::Io)?.nlink() > 1 {
+            return Ok(false);
+        }
+    }
+
     // Check age filter first
     if let Some(max_age) = older_than {
         let metadata = fs::metadata(lock_path).map_err(MutxError::Io)?;
@@ -0,0 +1,595 @@
+//! Long-running housekeeping for environments without cron or systemd timers.
+//!
+//! A policy file lists the directories to clean and how, using the same
+//! settings as the `housekeep` subcommands:
+//!
+//! ```toml
+//! interval = "1h"
+//! jitter = "5m"
+//! heartbeat = "/var/run/mutx-janitor.json"
+//!
+//! [[locks]]              # dir defaults to the lock cache directory
+//! older_than = "7d"
+//!
+//! [[backups]]
+//! dir = "/var/backups/mutx"
+//! older_than = "30d"
+//! keep_newest = 5
+//! max_total_size = "500M"
+//!
+//! [[temps]]
+//! dir = "/data"
+//! recursive = true
+//! older_than = "1h"
+//! ```
+//!
+//! Rules can also be grouped by directory, each `[[directory]]` table
+//! giving the path (and its `recursive` and `dry_run` defaults) once:
+//!
+//! ```toml
+//! [[directory]]
+//! path = "/srv/app"
+//! recursive = true
+//! backups = { keep_newest = 5 }
+//! temps = { older_than = "2h" }
+//! ```
+//!
+//! [`run`] applies every rule, sleeps for the interval plus a random share
+//! of the jitter (so replicas started together spread out), and repeats
+//! until cancelled. After each pass it rewrites the heartbeat file with what
+//! happened and when the next pass is due; a health check only needs to
+//! look at the file's age.
+
+use crate::backup::BackupSuffix;
+use crate::config::Config;
+use crate::error::{MutxError, Result};
+use crate::housekeep::{
+    clean_backups, clean_locks, clean_temps, AgeSource, CleanBackupConfig, CleanLockConfig,
+    CleanTempConfig,
+};
+use crate::lock::{get_lock_cache_dir, CancelToken};
+use crate::parse::{parse_duration, parse_size};
+use crate::schema::{self, Versioned};
+use crate::write::{AtomicWriter, WriteMode};
+use chrono::{DateTime, Local};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Time between passes when neither the policy nor the caller sets one
+pub const DEFAULT_JANITOR_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Longest stretch the janitor sleeps without checking for cancellation
+const CANCEL_CHECK_INTERVAL: Duration = Duration::from_millis(200);
+
+/// The retention rules applied on every pass, with the schedule to apply
+/// them on
+#[derive(Debug, Clone, Default)]
+pub struct JanitorPolicy {
+    /// Time between passes
+    pub interval: Option<Duration>,
+    /// Upper bound of the random delay added to each interval (default: a
+    /// tenth of the interval)
+    pub jitter: Option<Duration>,
+    /// File rewritten with a [`Heartbeat`] after every pass
+    pub heartbeat: Option<PathBuf>,
+    pub locks: Vec<CleanLockConfig>,
+    pub backups: Vec<CleanBackupConfig>,
+    pub temps: Vec<CleanTempConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawPolicy {
+    interval: Option<String>,
+    jitter: Option<String>,
+    heartbeat: Option<PathBuf>,
+    #[serde(default)]
+    locks: Vec<RawLockRule>,
+    #[serde(default)]
+    backups: Vec<RawBackupRule>,
+    #[serde(default)]
+    temps: Vec<RawTempRule>,
+    #[serde(default)]
+    directory: Vec<RawDirectory>,
+}
+
+/// A `[[directory]]` table: one path with its own rules for each kind of file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawDirectory {
+    path: PathBuf,
+    #[serde(default)]
+    recursive: bool,
+    #[serde(default)]
+    dry_run: bool,
+    locks: Option<RawLockRule>,
+    backups: Option<RawBackupRule>,
+    temps: Option<RawTempRule>,
+}
+
+/// Settings shared by every kind of rule: where it applies, and whether it
+/// only reports
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct RawScope {
+    dir: Option<PathBuf>,
+    recursive: Option<bool>,
+    dry_run: Option<bool>,
+}
+
+impl RawScope {
+    /// The rule's directory, recursion and dry-run setting, taken from
+    /// `directory` when the rule is part of a `[[directory]]` table
+    fn resolve(
+        self,
+        kind: &str,
+        directory: Option<&RawDirectory>,
+    ) -> std::result::Result<(PathBuf, bool, bool), String> {
+        match (self.dir, directory) {
+            (Some(dir), None) => Ok((
+                dir,
+                self.recursive.unwrap_or(false),
+                self.dry_run.unwrap_or(false),
+            )),
+            (None, Some(directory)) => Ok((
+                directory.path.clone(),
+                self.recursive.unwrap_or(directory.recursive),
+                self.dry_run.unwrap_or(directory.dry_run),
+            )),
+            (Some(_), Some(directory)) => Err(format!(
+                "{} rule of directory {} cannot set its own dir",
+                kind,
+                directory.path.display()
+            )),
+            (None, None) => Err(format!("[[{}]] rule needs a dir", kind)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawLockRule {
+    #[serde(flatten)]
+    scope: RawScope,
+    older_than: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawBackupRule {
+    #[serde(flatten)]
+    scope: RawScope,
+    older_than: Option<String>,
+    keep_newest: Option<usize>,
+    max_total_size: Option<String>,
+    suffix: Option<BackupSuffix>,
+    age_source: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawTempRule {
+    #[serde(flatten)]
+    scope: RawScope,
+    older_than: Option<String>,
+}
+
+fn parse_optional_duration(value: Option<String>) -> std::result::Result<Option<Duration>, String> {
+    value
+        .map(|s| parse_duration(&s).map_err(|e| e.to_string()))
+        .transpose()
+}
+
+impl RawLockRule {
+    fn into_config(
+        self,
+        directory: Option<&RawDirectory>,
+    ) -> std::result::Result<CleanLockConfig, String> {
+        // Top-level lock rules default to the lock cache directory
+        let scope = match (&self.scope.dir, directory) {
+            (None, None) => RawScope {
+                dir: Some(get_lock_cache_dir().map_err(|e| e.to_string())?),
+                ..self.scope
+            },
+            _ => self.scope,
+        };
+        let (dir, recursive, dry_run) = scope.resolve("locks", directory)?;
+        Ok(CleanLockConfig {
+            dir,
+            recursive,
+            older_than: parse_optional_duration(self.older_than)?,
+            dry_run,
+        })
+    }
+}
+
+impl RawBackupRule {
+    fn into_config(
+        self,
+        directory: Option<&RawDirectory>,
+        default_suffix: &BackupSuffix,
+    ) -> std::result::Result<CleanBackupConfig, String> {
+        let (dir, recursive, dry_run) = self.scope.resolve("backups", directory)?;
+        Ok(CleanBackupConfig {
+            dir,
+            recursive,
+            older_than: parse_optional_duration(self.older_than)?,
+            keep_newest: self.keep_newest,
+            dry_run,
+            suffix: self.suffix.unwrap_or_else(|| default_suffix.clone()),
+            age_source: self
+                .age_source
+                .map(|s| s.parse::<AgeSource>().map_err(|e| e.to_string()))
+                .transpose()?
+                .unwrap_or_default(),
+            max_total_size: self
+                .max_total_size
+                .map(|s| parse_size(&s).map_err(|e| e.to_string()))
+                .transpose()?,
+        })
+    }
+}
+
+impl RawTempRule {
+    fn into_config(
+        self,
+        directory: Option<&RawDirectory>,
+    ) -> std::result::Result<CleanTempConfig, String> {
+        let (dir, recursive, dry_run) = self.scope.resolve("temps", directory)?;
+        Ok(CleanTempConfig {
+            dir,
+            recursive,
+            // Spare writes in progress, like `housekeep temps`
+            older_than: Some(
+                parse_optional_duration(self.older_than)?.unwrap_or(Duration::from_secs(3600)),
+            ),
+            dry_run,
+        })
+    }
+}
+
+/// File name of the default policy, next to the configuration file
+pub const HOUSEKEEP_POLICY_FILE: &str = "housekeep.toml";
+
+impl JanitorPolicy {
+    /// Location of the default policy: [`HOUSEKEEP_POLICY_FILE`] in the
+    /// directory of [`Config::default_path`]
+    pub fn default_path() -> Option<PathBuf> {
+        Config::default_path().map(|config| config.with_file_name(HOUSEKEEP_POLICY_FILE))
+    }
+
+    /// Load a policy file. Backup rules that name no `suffix` clean
+    /// backups ending in `backup_suffix`, such as the configured one.
+    pub fn from_path(path: &Path, backup_suffix: &BackupSuffix) -> Result<Self> {
+        let contents = fs::read_to_string(path).map_err(|e| MutxError::ReadFailed {
+            path: path.to_path_buf(),
+            source: e,
+        })?;
+        Self::parse(&contents, backup_suffix).map_err(|message| MutxError::InvalidConfig {
+            path: path.to_path_buf(),
+            message,
+        })
+    }
+
+    fn parse(contents: &str, backup_suffix: &BackupSuffix) -> std::result::Result<Self, String> {
+        let mut raw: RawPolicy = toml::from_str(contents).map_err(|e| e.message().to_string())?;
+        let interval = parse_optional_duration(raw.interval)?;
+        if interval == Some(Duration::ZERO) {
+            return Err("interval must be greater than zero".to_string());
+        }
+        let mut policy = JanitorPolicy {
+            interval,
+            jitter: parse_optional_duration(raw.jitter)?,
+            heartbeat: raw.heartbeat,
+            ..Default::default()
+        };
+
+        for rule in raw.locks {
+            policy.locks.push(rule.into_config(None)?);
+        }
+        for rule in raw.backups {
+            policy.backups.push(rule.into_config(None, backup_suffix)?);
+        }
+        for rule in raw.temps {
+            policy.temps.push(rule.into_config(None)?);
+        }
+        for mut directory in raw.directory.drain(..) {
+            let (locks, backups, temps) = (
+                directory.locks.take(),
+                directory.backups.take(),
+                directory.temps.take(),
+            );
+            if locks.is_none() && backups.is_none() && temps.is_none() {
+                return Err(format!(
+                    "directory {} has no locks, backups or temps rules",
+                    directory.path.display()
+                ));
+            }
+            if let Some(rule) = locks {
+                policy.locks.push(rule.into_config(Some(&directory))?);
+            }
+            if let Some(rule) = backups {
+                policy
+                    .backups
+                    .push(rule.into_config(Some(&directory), backup_suffix)?);
+            }
+            if let Some(rule) = temps {
+                policy.temps.push(rule.into_config(Some(&directory))?);
+            }
+        }
+
+        Ok(policy)
+    }
+
+    /// The same policy, only reporting what every rule would remove
+    pub fn dry_run(mut self) -> Self {
+        self.locks.iter_mut().for_each(|rule| rule.dry_run = true);
+        self.backups.iter_mut().for_each(|rule| rule.dry_run = true);
+        self.temps.iter_mut().for_each(|rule| rule.dry_run = true);
+        self
+    }
+
+    /// Effective time between passes
+    pub fn interval(&self) -> Duration {
+        self.interval.unwrap_or(DEFAULT_JANITOR_INTERVAL)
+    }
+
+    /// Effective upper bound of the random delay added to each interval
+    pub fn jitter(&self) -> Duration {
+        self.jitter.unwrap_or(self.interval() / 10)
+    }
+}
+
+/// What one pass over a policy removed (or, for dry-run rules, would remove)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JanitorPass {
+    pub locks: Vec<PathBuf>,
+    pub backups: Vec<PathBuf>,
+    pub temps: Vec<PathBuf>,
+    /// Rules that failed; the others still ran
+    pub errors: Vec<String>,
+}
+
+/// Contents of the heartbeat file, as a [`schema::STATUS`] document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Heartbeat {
+    pub pid: u32,
+    /// Passes completed since the janitor started
+    pub passes: u64,
+    /// When the last pass finished (RFC 3339)
+    pub last_run: String,
+    /// When the next pass is due (RFC 3339)
+    pub next_run: String,
+    pub cleaned_locks: usize,
+    pub cleaned_backups: usize,
+    pub cleaned_temps: usize,
+    pub errors: Vec<String>,
+}
+
+/// Apply every rule of `policy` once. A failing rule is recorded in
+/// [`JanitorPass::errors`] and does not stop the others.
+pub fn run_pass(policy: &JanitorPolicy) -> JanitorPass {
+    let mut pass = JanitorPass::default();
+    let mut record =
+        |dir: &Path, result: Result<Vec<PathBuf>>, into: &mut Vec<PathBuf>| match result {
+            Ok(cleaned) => into.extend(cleaned),
+            Err(e) => {
+                warn!("Housekeeping {} failed: {}", dir.display(), e);
+                pass.errors.push(format!("{}: {}", dir.display(), e));
+            }
+        };
+
+    let (mut locks, mut backups, mut temps) = (Vec::new(), Vec::new(), Vec::new());
+    for rule in &policy.locks {
+        record(&rule.dir, clean_locks(rule), &mut locks);
+    }
+    for rule in &policy.backups {
+        record(&rule.dir, clean_backups(rule), &mut backups);
+    }
+    for rule in &policy.temps {
+        record(&rule.dir, clean_temps(rule), &mut temps);
+    }
+    pass.locks = locks;
+    pass.backups = backups;
+    pass.temps = temps;
+    pass
+}
+
+/// Apply `policy` every interval until `cancel` is cancelled, starting with
+/// a pass right away. `on_pass` sees each pass as it completes. Returns the
+/// number of passes made.
+pub fn run(
+    policy: &JanitorPolicy,
+    cancel: &CancelToken,
+    mut on_pass: impl FnMut(&JanitorPass),
+) -> Result<u64> {
+    let mut passes = 0;
+    while !cancel.is_cancelled() {
+        let pass = run_pass(policy);
+        passes += 1;
+        on_pass(&pass);
+
+        // A heartbeat that cannot be written shows up as a stale one; the
+        // housekeeping itself goes on
+        let pause = next_pause(policy);
+        if let Some(path) = &policy.heartbeat {
+            if let Err(e) = write_heartbeat(path, &Heartbeat::after(&pass, passes, pause)) {
+                warn!("Cannot write heartbeat {}: {}", path.display(), e);
+            }
+        }
+
+        let wake = Instant::now() + pause;
+        while !cancel.is_cancelled() {
+            let now = Instant::now();
+            if now >= wake {
+                break;
+            }
+            std::thread::sleep((wake - now).min(CANCEL_CHECK_INTERVAL));
+        }
+    }
+    Ok(passes)
+}
+
+/// The interval plus a random share of the jitter
+fn next_pause(policy: &JanitorPolicy) -> Duration {
+    let jitter = policy.jitter().as_millis() as u64;
+    let extra = if jitter == 0 {
+        0
+    } else {
+        rand::thread_rng().gen_range(0..=jitter)
+    };
+    policy.interval() + Duration::from_millis(extra)
+}
+
+impl Heartbeat {
+    /// The heartbeat recorded after pass number `passes`, with the next pass
+    /// `pause` from now
+    pub fn after(pass: &JanitorPass, passes: u64, pause: Duration) -> Self {
+        let now = Local::now();
+        let next: DateTime<Local> =
+            now + chrono::Duration::from_std(pause).unwrap_or(chrono::Duration::zero());
+        Heartbeat {
+            pid: std::process::id(),
+            passes,
+            last_run: now.to_rfc3339(),
+            next_run: next.to_rfc3339(),
+            cleaned_locks: pass.locks.len(),
+            cleaned_backups: pass.backups.len(),
+            cleaned_temps: pass.temps.len(),
+            errors: pass.errors.clone(),
+        }
+    }
+}
+
+/// Atomically replace the heartbeat file at `path`
+pub fn write_heartbeat(path: &Path, heartbeat: &Heartbeat) -> Result<()> {
+    let mut json = Versioned::new(schema::STATUS, heartbeat)
+        .to_json_pretty()?
+        .into_bytes();
+    json.push(b'\n');
+    let mut writer = AtomicWriter::new(path, WriteMode::InMemory)?;
+    writer.write_all(&json)?;
+    writer.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(contents: &str) -> std::result::Result<JanitorPolicy, String> {
+        JanitorPolicy::parse(contents, &BackupSuffix::default())
+    }
+
+    #[test]
+    fn test_parse_policy() {
+        let policy = parse(
+            r#"
+            interval = "10m"
+            heartbeat = "/tmp/hb.json"
+
+            [[locks]]
+            dir = "/tmp/locks"
+            older_than = "7d"
+
+            [[backups]]
+            dir = "/tmp/backups"
+            keep_newest = 3
+            max_total_size = "1M"
+            age_source = "mtime"
+            suffix = ".bak"
+
+            [[temps]]
+            dir = "/tmp/data"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(policy.interval(), Duration::from_secs(600));
+        assert_eq!(policy.jitter(), Duration::from_secs(60));
+        assert_eq!(
+            policy.locks[0].older_than,
+            Some(Duration::from_secs(7 * 86400))
+        );
+        assert_eq!(policy.backups[0].max_total_size, Some(1024 * 1024));
+        assert_eq!(policy.backups[0].age_source, AgeSource::Mtime);
+        assert_eq!(policy.backups[0].suffix.as_str(), ".bak");
+        assert_eq!(policy.temps[0].older_than, Some(Duration::from_secs(3600)));
+        assert!(policy.dry_run().backups[0].dry_run);
+    }
+
+    #[test]
+    fn test_parse_directory_tables() {
+        let policy = parse(
+            r#"
+            [[directory]]
+            path = "/srv/app"
+            recursive = true
+
+            [directory.backups]
+            keep_newest = 2
+
+            [directory.temps]
+            older_than = "2h"
+            recursive = false
+
+            [[directory]]
+            path = "/srv/locks"
+            dry_run = true
+            locks = { older_than = "1d" }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(policy.backups[0].dir, PathBuf::from("/srv/app"));
+        assert!(policy.backups[0].recursive);
+        assert_eq!(policy.backups[0].keep_newest, Some(2));
+        assert!(!policy.temps[0].recursive);
+        assert_eq!(policy.temps[0].older_than, Some(Duration::from_secs(7200)));
+        assert_eq!(policy.locks[0].dir, PathBuf::from("/srv/locks"));
+        assert!(policy.locks[0].dry_run);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_directories() {
+        assert!(parse("[[directory]]\npath = \"/a\"").is_err());
+        assert!(parse("[[directory]]\npath = \"/a\"\ntemps = { dir = \"/b\" }").is_err());
+        assert!(parse("[[directory]]\npath = \"/a\"\ntemps = { retain = 1 }").is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_values() {
+        assert!(parse("interval = \"soon\"").is_err());
+        assert!(parse("interval = \"0s\"").is_err());
+        assert!(parse("[[backups]]\nretain = 3\ndir = \"x\"").is_err());
+        assert!(parse("[[temps]]\nrecursive = true").is_err());
+    }
+
+    #[test]
+    fn test_backup_rules_default_to_given_suffix() {
+        let policy = JanitorPolicy::parse(
+            "[[backups]]\ndir = \"/a\"\n\n[[backups]]\ndir = \"/b\"\nsuffix = \".old\"",
+            &".bak".parse().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(policy.backups[0].suffix.as_str(), ".bak");
+        assert_eq!(policy.backups[1].suffix.as_str(), ".old");
+    }
+
+    #[test]
+    fn test_pause_stays_within_jitter() {
+        let policy = JanitorPolicy {
+            interval: Some(Duration::from_secs(10)),
+            jitter: Some(Duration::from_secs(2)),
+            ..Default::default()
+        };
+        for _ in 0..50 {
+            let pause = next_pause(&policy);
+            assert!(pause >= Duration::from_secs(10) && pause <= Duration::from_secs(12));
+        }
+    }
+}
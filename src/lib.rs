@@ -1,16 +1,60 @@
 //! Atomic file write library with file locking support
 
 pub mod backup;
+pub mod collaborative;
+pub mod compress;
+pub mod config;
+pub mod delta;
+pub mod digest;
+pub mod dirs;
 pub mod error;
 pub mod housekeep;
+pub mod janitor;
 pub mod lock;
+pub mod parse;
+pub mod restore;
+pub mod schema;
+pub mod sign;
+pub mod sink;
+pub(crate) mod statefile;
+pub mod systemd;
+#[cfg(feature = "test-util")]
+pub mod test_support;
+pub mod transform;
 pub mod utils;
 pub mod write;
 
 // Re-export for convenience
-pub use backup::{create_backup, validate_backup_suffix, BackupConfig};
+pub use backup::{
+    create_backup, validate_backup_suffix, BackupConfig, BackupOutcome, BackupSuffix,
+    DEFAULT_BACKUP_SUFFIX, LEGACY_BACKUP_SUFFIX,
+};
+pub use collaborative::SharedGroup;
+pub use compress::{Compression, CompressionFormat};
+pub use config::Config;
+pub use digest::{write_digest_file, DigestAlgorithm};
 pub use error::{MutxError, Result};
-pub use housekeep::{clean_backups, clean_locks, CleanBackupConfig, CleanLockConfig};
-pub use lock::{derive_lock_path, validate_lock_path, FileLock, LockStrategy, TimeoutConfig};
-pub use utils::{check_lock_symlink, check_symlink};
-pub use write::{AtomicWriter, WriteMode};
+pub use housekeep::{
+    clean_backups, clean_locks, clean_temps, lock_cache_info, prune_backups, reclaim_backups,
+    AgeSource, CleanBackupConfig, CleanLockConfig, CleanTempConfig, LockCacheInfo,
+};
+pub use lock::{
+    derive_lock_path, derive_lock_path_with_scheme, validate_custom_lock_path, validate_lock_path,
+    AcquireStats, CancelToken, FileLock, HoldOverrun, LeaseLoss, LockBackend, LockHolder,
+    LockPolicy, LockRetry, LockScheme, LockSet, LockStatus, LockStrategy, TimeoutConfig,
+};
+pub use restore::{
+    find_latest_backup, list_backups, restore_backup, verify_backup, RestoreConfig, RestoreReport,
+    VerifiedBackup,
+};
+pub use sign::{verify_signature, write_signature_file, SignatureKind, SigningKey};
+pub use sink::Sink;
+pub use transform::{Transform, TransformRegistry};
+pub use utils::{check_lock_symlink, check_symlink, SymlinkPolicy};
+#[cfg(feature = "tokio")]
+pub use write::AsyncAtomicWriter;
+pub use write::{
+    AtomicWriter, AtomicWriterPool, CriticalSection, FileMode, FsyncPolicy, Guarantee, Guarantees,
+    ModePolicy, SpaceReclaimer, TempStrategy, WriteBatch, WriteMode, WriteReport, WriteStep,
+    DEFAULT_SPILL_THRESHOLD,
+};
@@ -1,14 +1,32 @@
 use crate::error::{MutxError, Result};
+use crate::lock::atomic_create::{CreateLock, ATOMIC_CREATE_STALE_AFTER};
+use crate::lock::backend::LockBackend;
+use crate::lock::cancel::CancelToken;
+#[cfg(feature = "cluster")]
+use crate::lock::cluster::{LockService, RemoteLease};
+use crate::lock::dotlock::DotLock;
+use crate::lock::holder::{self, LockHolder};
+use crate::lock::lease::{Heartbeat, LeaseLoss};
+use crate::lock::max_hold::{HoldOverrun, HoldOverrunHandler, HoldWatch};
+use crate::lock::naming::LockPathStrategy;
+use crate::lock::ofd;
+use crate::lock::path::{canonical_output_path, ensure_lock_dir};
+use crate::lock::queue::Ticket;
+use crate::lock::range;
+use crate::lock::registry::{self, Registration};
+use crate::utils::{apply_nofollow, unique_temp_path, verify_not_link};
+use crate::write::FileMode;
 use fs2::FileExt;
 use rand::Rng;
-use std::fs::{File, OpenOptions};
+use std::fs::{self, File, OpenOptions};
 use std::io;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
-use tracing::debug;
+use tracing::{debug, warn};
 
 /// Check if an I/O error indicates lock contention (file locked by another process)
-fn is_lock_contention(e: &io::Error) -> bool {
+pub(crate) fn is_lock_contention(e: &io::Error) -> bool {
     // Check for WouldBlock (Unix)
     if e.kind() == io::ErrorKind::WouldBlock {
         return true;
@@ -25,17 +43,32 @@ fn is_lock_contention(e: &io::Error) -> bool {
     false
 }
 
+/// How long to wait for a lock, and how often to try it meanwhile.
+///
+/// Attempts are spaced by an interval that starts at `initial_interval` and
+/// grows by `multiplier` after every attempt up to `max_poll_interval`, plus
+/// a random jitter drawn from `jitter` so waiters do not retry in lockstep.
+/// `Wait` and `Deadline` poll with the defaults of [`TimeoutConfig::new`].
 #[derive(Debug, Clone)]
 pub struct TimeoutConfig {
     pub duration: Duration,
     pub max_poll_interval: Duration,
+    pub initial_interval: Duration,
+    /// Growth of the interval per attempt; values below 1 count as 1
+    pub multiplier: f64,
+    pub jitter: Range<Duration>,
 }
 
 impl TimeoutConfig {
+    /// Wait up to `duration`, polling after 10ms at first, then 1.5 times
+    /// longer each attempt up to 1s, with 0-100ms of jitter
     pub fn new(duration: Duration) -> Self {
         Self {
             duration,
             max_poll_interval: Duration::from_millis(1000),
+            initial_interval: Duration::from_millis(10),
+            multiplier: 1.5,
+            jitter: Duration::ZERO..Duration::from_millis(100),
         }
     }
 
@@ -43,6 +76,25 @@ impl TimeoutConfig {
         self.max_poll_interval = max_interval;
         self
     }
+
+    /// Interval before the second attempt, and again after each restart;
+    /// intervals below 1ms count as 1ms so a zero interval cannot spin
+    pub fn with_initial_interval(mut self, initial_interval: Duration) -> Self {
+        self.initial_interval = initial_interval;
+        self
+    }
+
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Range random jitter is drawn from; an empty range such as
+    /// `Duration::ZERO..Duration::ZERO` adds none
+    pub fn with_jitter(mut self, jitter: Range<Duration>) -> Self {
+        self.jitter = jitter;
+        self
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -50,121 +102,1445 @@ pub enum LockStrategy {
     Wait,
     NoWait,
     Timeout(TimeoutConfig),
+    /// Wait until the given instant, then fail with a timeout. Unlike a
+    /// `Timeout`, one deadline can be passed to several acquisitions made
+    /// one after another to share a single budget.
+    Deadline(Instant),
+    /// A shared (read) lock, waited for as the inner strategy says. Any
+    /// number of shared holders can coexist; an exclusive lock waits for all
+    /// of them, and they wait for it. Only the `flock` backend supports it.
+    Shared(Box<LockStrategy>),
+    /// The inner strategy, taking over the lock when the holder recorded in
+    /// it (see [`LockHolder`]) is a process on this host that no longer
+    /// runs. Only the `flock` backend records holders, so only it breaks them.
+    BreakStale(Box<LockStrategy>),
+    /// The inner strategy, holding the lock under a lease of the given
+    /// length: the holder records it in its [`LockHolder`] payload and
+    /// touches the lock file every third of it, and the lock is stale once
+    /// the file's mtime is older. Also breaks stale locks, like `BreakStale`.
+    Lease(Duration, Box<LockStrategy>),
+    /// The inner strategy, waiting in arrival order: each waiter takes a
+    /// ticket in a queue beside the lock file and only tries the lock once
+    /// every earlier waiter has acquired it or given up. Waiters that do not
+    /// queue can still take the lock ahead of the queue. Not supported by
+    /// the async or remote acquisition.
+    Fair(Box<LockStrategy>),
+    /// The inner strategy, taking over the lock at once when its recorded
+    /// holder is a process on this host that no longer runs and was invoked
+    /// like this one (see [`LockHolder::same_invoker`]): an earlier run of
+    /// the same command that crashed. Narrower than `BreakStale`, which takes
+    /// over from any dead holder. Only the `flock` backend records holders.
+    ReclaimOwn(Box<LockStrategy>),
+    /// The inner strategy, succeeding at once with a nested handle when
+    /// this thread already holds the lock exclusively, instead of waiting
+    /// for itself. The lock is held until every handle is dropped. Relies
+    /// on the in-process registry (see [`crate::lock::set_in_process_registry`]).
+    Reentrant(Box<LockStrategy>),
+}
+
+impl LockStrategy {
+    /// The same waiting behavior, taking a shared lock
+    pub fn shared(self) -> Self {
+        match self {
+            LockStrategy::Shared(_) => self,
+            other => LockStrategy::Shared(Box::new(other)),
+        }
+    }
+
+    /// The same strategy, breaking locks left behind by dead holders
+    pub fn break_stale(self) -> Self {
+        if self.breaks_stale() {
+            self
+        } else {
+            LockStrategy::BreakStale(Box::new(self))
+        }
+    }
+
+    /// The same strategy, holding the lock under a lease of length `ttl`,
+    /// at least [`MIN_LEASE`]
+    pub fn lease(self, ttl: Duration) -> Self {
+        match self {
+            LockStrategy::Lease(_, inner) => LockStrategy::Lease(ttl, inner),
+            other => LockStrategy::Lease(ttl, Box::new(other)),
+        }
+    }
+
+    /// The same strategy, taking over locks left behind by an earlier run of
+    /// the same command
+    pub fn reclaim_own(self) -> Self {
+        if self.reclaims_own() {
+            self
+        } else {
+            LockStrategy::ReclaimOwn(Box::new(self))
+        }
+    }
+
+    /// The same strategy, nesting in a lock this thread already holds
+    pub fn reentrant(self) -> Self {
+        if self.is_reentrant() {
+            self
+        } else {
+            LockStrategy::Reentrant(Box::new(self))
+        }
+    }
+
+    /// The same strategy, waiting in arrival order
+    pub fn fair(self) -> Self {
+        if self.is_fair() {
+            self
+        } else {
+            LockStrategy::Fair(Box::new(self))
+        }
+    }
+
+    /// Whether this takes a shared lock
+    pub fn is_shared(&self) -> bool {
+        match self {
+            LockStrategy::Shared(_) => true,
+            LockStrategy::BreakStale(inner)
+            | LockStrategy::Lease(_, inner)
+            | LockStrategy::Fair(inner)
+            | LockStrategy::ReclaimOwn(inner)
+            | LockStrategy::Reentrant(inner) => inner.is_shared(),
+            _ => false,
+        }
+    }
+
+    /// Whether waiters queue in arrival order
+    pub fn is_fair(&self) -> bool {
+        match self {
+            LockStrategy::Fair(_) => true,
+            LockStrategy::Shared(inner)
+            | LockStrategy::BreakStale(inner)
+            | LockStrategy::Lease(_, inner)
+            | LockStrategy::ReclaimOwn(inner)
+            | LockStrategy::Reentrant(inner) => inner.is_fair(),
+            _ => false,
+        }
+    }
+
+    /// Whether a thread already holding the lock gets a nested handle
+    pub fn is_reentrant(&self) -> bool {
+        match self {
+            LockStrategy::Reentrant(_) => true,
+            LockStrategy::Shared(inner)
+            | LockStrategy::BreakStale(inner)
+            | LockStrategy::Lease(_, inner)
+            | LockStrategy::Fair(inner)
+            | LockStrategy::ReclaimOwn(inner) => inner.is_reentrant(),
+            _ => false,
+        }
+    }
+
+    /// Whether this breaks locks whose recorded holder has exited or let its
+    /// lease expire
+    pub fn breaks_stale(&self) -> bool {
+        match self {
+            LockStrategy::BreakStale(_) | LockStrategy::Lease(..) => true,
+            LockStrategy::Shared(inner)
+            | LockStrategy::Fair(inner)
+            | LockStrategy::ReclaimOwn(inner)
+            | LockStrategy::Reentrant(inner) => inner.breaks_stale(),
+            _ => false,
+        }
+    }
+
+    /// Whether this takes over locks whose recorded holder is a dead earlier
+    /// run of the same command
+    pub fn reclaims_own(&self) -> bool {
+        match self {
+            LockStrategy::ReclaimOwn(_) => true,
+            LockStrategy::Shared(inner)
+            | LockStrategy::BreakStale(inner)
+            | LockStrategy::Lease(_, inner)
+            | LockStrategy::Fair(inner)
+            | LockStrategy::Reentrant(inner) => inner.reclaims_own(),
+            _ => false,
+        }
+    }
+
+    /// Length of the lease the lock is held under, if any
+    pub fn lease_ttl(&self) -> Option<Duration> {
+        match self {
+            LockStrategy::Lease(ttl, _) => Some(*ttl),
+            LockStrategy::Shared(inner)
+            | LockStrategy::BreakStale(inner)
+            | LockStrategy::Fair(inner)
+            | LockStrategy::ReclaimOwn(inner)
+            | LockStrategy::Reentrant(inner) => inner.lease_ttl(),
+            _ => None,
+        }
+    }
+
+    /// The same strategy with `elapsed` taken off a `Timeout`, for locks
+    /// acquired one after another under a single deadline
+    pub(crate) fn remaining(&self, elapsed: Duration) -> LockStrategy {
+        match self {
+            LockStrategy::Timeout(config) => LockStrategy::Timeout(TimeoutConfig {
+                duration: config.duration.saturating_sub(elapsed),
+                ..config.clone()
+            }),
+            LockStrategy::Shared(inner) => LockStrategy::Shared(Box::new(inner.remaining(elapsed))),
+            LockStrategy::BreakStale(inner) => {
+                LockStrategy::BreakStale(Box::new(inner.remaining(elapsed)))
+            }
+            LockStrategy::Lease(ttl, inner) => {
+                LockStrategy::Lease(*ttl, Box::new(inner.remaining(elapsed)))
+            }
+            LockStrategy::Fair(inner) => LockStrategy::Fair(Box::new(inner.remaining(elapsed))),
+            LockStrategy::ReclaimOwn(inner) => {
+                LockStrategy::ReclaimOwn(Box::new(inner.remaining(elapsed)))
+            }
+            LockStrategy::Reentrant(inner) => {
+                LockStrategy::Reentrant(Box::new(inner.remaining(elapsed)))
+            }
+            other => other.clone(),
+        }
+    }
+
+    /// The same strategy, trying once instead of waiting
+    #[cfg(feature = "tokio")]
+    pub(crate) fn once(&self) -> LockStrategy {
+        match self {
+            LockStrategy::Shared(inner) => LockStrategy::Shared(Box::new(inner.once())),
+            LockStrategy::BreakStale(inner) => LockStrategy::BreakStale(Box::new(inner.once())),
+            LockStrategy::Lease(ttl, inner) => LockStrategy::Lease(*ttl, Box::new(inner.once())),
+            LockStrategy::ReclaimOwn(inner) => LockStrategy::ReclaimOwn(Box::new(inner.once())),
+            LockStrategy::Reentrant(inner) => LockStrategy::Reentrant(Box::new(inner.once())),
+            _ => LockStrategy::NoWait,
+        }
+    }
+
+    /// How much longer a `Timeout` or `Deadline` lets the lock be waited
+    /// for, counting a `Timeout` from now
+    fn time_left(&self) -> Option<Duration> {
+        match self.waiting() {
+            LockStrategy::Timeout(config) => Some(config.duration),
+            LockStrategy::Deadline(deadline) => {
+                Some(deadline.saturating_duration_since(Instant::now()))
+            }
+            _ => None,
+        }
+    }
+
+    /// How the lock is waited for, ignoring whether it is shared, breaks
+    /// stale holders, is leased or queues
+    fn waiting(&self) -> &LockStrategy {
+        match self {
+            LockStrategy::Shared(inner)
+            | LockStrategy::BreakStale(inner)
+            | LockStrategy::Lease(_, inner)
+            | LockStrategy::Fair(inner)
+            | LockStrategy::ReclaimOwn(inner)
+            | LockStrategy::Reentrant(inner) => inner.waiting(),
+            other => other,
+        }
+    }
+}
+
+/// Keeps the underlying lock alive; released when dropped
+#[derive(Debug)]
+#[allow(dead_code)]
+enum LockHandle {
+    Flock(File),
+    Dotlock(DotLock),
+    Created(CreateLock),
+    #[cfg(feature = "cluster")]
+    Remote(Box<dyn RemoteLease>),
+    /// Nested in a lock the thread already held, which keeps it
+    Nested,
 }
 
 #[derive(Debug)]
 pub struct FileLock {
     #[allow(dead_code)]
-    file: File,
+    handle: LockHandle,
     path: PathBuf,
+    backend: LockBackend,
+    shared: bool,
+    holder: Option<LockHolder>,
+    heartbeat: Option<Heartbeat>,
+    range: Option<(u64, u64)>,
+    pub(crate) stats: AcquireStats,
+    acquired: Instant,
+    hold_watch: Option<HoldWatch>,
+    /// Claim in the in-process registry; declared last so it is given up
+    /// only once the lock itself has been released
+    registration: Option<Registration>,
+}
+
+/// How long acquiring a lock took and how often it had to wait
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AcquireStats {
+    /// Time from the first attempt until the lock was held
+    pub elapsed: Duration,
+    /// Attempts that found the lock taken and backed off
+    pub retries: u32,
+    /// The last backoff slept before the lock was acquired
+    pub last_interval: Option<Duration>,
+}
+
+/// One failed attempt while polling for a lock, as passed to the observer of
+/// [`FileLock::acquire_observed`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockRetry {
+    /// Number of this retry, from 1
+    pub attempt: u32,
+    /// Time spent waiting so far
+    pub elapsed: Duration,
+    /// Backoff before the next attempt
+    pub sleep: Duration,
+}
+
+/// Shortest lock lease. The lease is recorded in whole milliseconds and
+/// refreshed every third of it, so a shorter one would record no lease or
+/// refresh in a busy loop.
+pub const MIN_LEASE: Duration = Duration::from_millis(10);
+
+/// Longest stretch a cancellable acquisition sleeps without checking its
+/// [`CancelToken`]
+const CANCEL_CHECK_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Tracks the retries of one acquisition, reports them as they happen and
+/// checks for cancellation
+pub(crate) struct Attempts<'a> {
+    start: Instant,
+    retries: u32,
+    last_interval: Option<Duration>,
+    on_retry: &'a mut dyn FnMut(&LockRetry),
+    cancel: Option<&'a CancelToken>,
+    ticket: Option<Ticket>,
+}
+
+impl<'a> Attempts<'a> {
+    pub(crate) fn new(on_retry: &'a mut dyn FnMut(&LockRetry)) -> Self {
+        Attempts {
+            start: Instant::now(),
+            retries: 0,
+            last_interval: None,
+            on_retry,
+            cancel: None,
+            ticket: None,
+        }
+    }
+
+    fn with_cancel(mut self, cancel: &'a CancelToken) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Wait in `ticket`'s queue; the ticket is given up with the attempts
+    fn with_ticket(mut self, ticket: Ticket) -> Self {
+        self.ticket = Some(ticket);
+        self
+    }
+
+    /// Whether the acquisition queues, so it must poll rather than block in
+    /// the kernel
+    fn must_poll(&self) -> bool {
+        self.ticket.is_some()
+    }
+
+    /// Whether it is this acquisition's turn to try the lock
+    fn is_turn(&self) -> Result<bool> {
+        match &self.ticket {
+            Some(ticket) => ticket.is_next(),
+            None => Ok(true),
+        }
+    }
+
+    fn check_cancelled(&self) -> Result<()> {
+        match self.cancel {
+            Some(cancel) if cancel.is_cancelled() => Err(MutxError::Interrupted),
+            _ => Ok(()),
+        }
+    }
+
+    /// Sleep for `pause`, waking early with an error if cancelled
+    fn sleep(&self, pause: Duration) -> Result<()> {
+        if self.cancel.is_none() {
+            std::thread::sleep(pause);
+            return Ok(());
+        }
+        let wake = Instant::now() + pause;
+        loop {
+            self.check_cancelled()?;
+            let now = Instant::now();
+            if now >= wake {
+                return Ok(());
+            }
+            std::thread::sleep((wake - now).min(CANCEL_CHECK_INTERVAL));
+        }
+    }
+
+    /// Record a failed attempt followed by `sleep`
+    pub(crate) fn retry(&mut self, sleep: Duration) {
+        self.retries += 1;
+        self.last_interval = Some(sleep);
+        (self.on_retry)(&LockRetry {
+            attempt: self.retries,
+            elapsed: self.start.elapsed(),
+            sleep,
+        });
+    }
+
+    pub(crate) fn stats(&self) -> AcquireStats {
+        AcquireStats {
+            elapsed: self.start.elapsed(),
+            retries: self.retries,
+            last_interval: self.last_interval,
+        }
+    }
 }
 
 impl FileLock {
-    /// Acquire an exclusive lock on the specified file
+    /// Acquire a lock on the specified file, exclusive unless `strategy` is
+    /// [`LockStrategy::Shared`]
     pub fn acquire(lock_path: &Path, strategy: LockStrategy) -> Result<Self> {
+        Self::acquire_with_backend(lock_path, strategy, LockBackend::Flock)
+    }
+
+    /// Lock `output` at the lock file `paths` picks for it
+    pub fn acquire_for(
+        output: &Path,
+        paths: &dyn LockPathStrategy,
+        strategy: LockStrategy,
+    ) -> Result<Self> {
+        let lock_path = paths.lock_path(&canonical_output_path(output)?)?;
+        Self::acquire(&lock_path, strategy)
+    }
+
+    /// Acquire a lock on the specified file using a specific backend
+    pub fn acquire_with_backend(
+        lock_path: &Path,
+        strategy: LockStrategy,
+        backend: LockBackend,
+    ) -> Result<Self> {
+        Self::acquire_observed(lock_path, strategy, backend, |_| {})
+    }
+
+    /// [`FileLock::acquire_with_backend`], calling `on_retry` each time an
+    /// attempt finds the lock taken and backs off. The blocking `Wait` of the
+    /// `flock` backend waits in the kernel and makes no retries.
+    pub fn acquire_observed(
+        lock_path: &Path,
+        strategy: LockStrategy,
+        backend: LockBackend,
+        mut on_retry: impl FnMut(&LockRetry),
+    ) -> Result<Self> {
+        Self::acquire_tracked(lock_path, strategy, backend, Attempts::new(&mut on_retry))
+    }
+
+    /// [`FileLock::acquire_with_backend`], giving up with
+    /// [`MutxError::Interrupted`] once `cancel` is cancelled. Polling waits
+    /// notice it within a few milliseconds. The blocking `Wait` of the
+    /// `flock` and `ofd` backends still waits in the kernel and only notices
+    /// it when the wait is interrupted (`EINTR`), so `cancel` should be
+    /// cancelled from a signal handler installed without `SA_RESTART`, as
+    /// the CLI's is.
+    pub fn acquire_cancellable(
+        lock_path: &Path,
+        strategy: LockStrategy,
+        backend: LockBackend,
+        cancel: &CancelToken,
+    ) -> Result<Self> {
+        Self::acquire_cancellable_observed(lock_path, strategy, backend, cancel, |_| {})
+    }
+
+    /// [`FileLock::acquire_cancellable`], calling `on_retry` like
+    /// [`FileLock::acquire_observed`]
+    pub fn acquire_cancellable_observed(
+        lock_path: &Path,
+        strategy: LockStrategy,
+        backend: LockBackend,
+        cancel: &CancelToken,
+        mut on_retry: impl FnMut(&LockRetry),
+    ) -> Result<Self> {
+        let attempts = Attempts::new(&mut on_retry).with_cancel(cancel);
+        Self::acquire_tracked(lock_path, strategy, backend, attempts)
+    }
+
+    fn acquire_tracked(
+        lock_path: &Path,
+        strategy: LockStrategy,
+        backend: LockBackend,
+        mut attempts: Attempts,
+    ) -> Result<Self> {
         debug!(
-            "Acquiring lock: {} (strategy: {:?})",
+            "Acquiring lock: {} (strategy: {:?}, backend: {})",
             lock_path.display(),
-            strategy
+            strategy,
+            backend
         );
 
-        // Create lock file
-        let mut opts = OpenOptions::new();
-        opts.create(true).write(true).truncate(true);
+        if strategy.is_shared() && !backend.is_file_lock() && backend != LockBackend::Target {
+            return Err(MutxError::Other(format!(
+                "Shared locks are not supported by the {} backend",
+                backend
+            )));
+        }
 
-        // On Unix, use O_NOFOLLOW to reject symlinks at OS level
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::OpenOptionsExt;
-            opts.custom_flags(libc::O_NOFOLLOW);
+        if let Some(ttl) = strategy.lease_ttl() {
+            if !backend.is_file_lock() {
+                return Err(MutxError::Other(format!(
+                    "Leased locks are not supported by the {} backend",
+                    backend
+                )));
+            }
+            if ttl < MIN_LEASE {
+                return Err(MutxError::Other(format!(
+                    "Lock lease must be at least {}ms, got {:?}",
+                    MIN_LEASE.as_millis(),
+                    ttl
+                )));
+            }
         }
 
-        let file = opts
-            .open(lock_path)
-            .map_err(|e| MutxError::LockCreationFailed {
-                path: lock_path.to_path_buf(),
-                source: e,
-            })?;
+        // The target is data: a holder payload would overwrite it
+        if backend == LockBackend::Target && (strategy.breaks_stale() || strategy.reclaims_own()) {
+            return Err(MutxError::Other(
+                "Breaking stale locks is not supported by the target backend".to_string(),
+            ));
+        }
 
-        // Acquire lock based on strategy
-        match strategy {
-            LockStrategy::Wait => {
-                file.lock_exclusive()
-                    .map_err(|e| MutxError::LockAcquisitionFailed {
-                        path: lock_path.to_path_buf(),
-                        source: e,
-                    })?;
-            }
-            LockStrategy::NoWait => {
-                file.try_lock_exclusive().map_err(|e| {
-                    if is_lock_contention(&e) {
-                        MutxError::LockWouldBlock(lock_path.to_path_buf())
-                    } else {
-                        MutxError::LockAcquisitionFailed {
-                            path: lock_path.to_path_buf(),
-                            source: e,
-                        }
-                    }
-                })?;
-            }
-            LockStrategy::Timeout(config) => {
-                let start = Instant::now();
-                let mut current_interval = Duration::from_millis(10);
-                let mut rng = rand::thread_rng();
-
-                loop {
-                    match file.try_lock_exclusive() {
-                        Ok(_) => break,
-                        Err(e) if is_lock_contention(&e) => {
-                            if start.elapsed() >= config.duration {
-                                return Err(MutxError::LockTimeout {
-                                    path: lock_path.to_path_buf(),
-                                    duration: config.duration,
-                                });
-                            }
+        ensure_lock_dir(lock_path)?;
 
-                            // Calculate sleep time with backoff + jitter
-                            let base_interval = current_interval.min(config.max_poll_interval);
-                            let jitter = Duration::from_millis(rng.gen_range(0..100));
-                            let sleep_time = base_interval + jitter;
+        // Serialize with this process's other threads before the
+        // filesystem, where they may not exclude each other
+        let key = (registry::in_process_registry_enabled() && backend != LockBackend::Remote)
+            .then(|| registry::key(lock_path));
+        if let (Some(key), true) = (&key, strategy.is_reentrant()) {
+            if let Some(nested) = Registration::try_nest(key) {
+                debug!("Lock nested in this thread's: {}", lock_path.display());
+                return Ok(FileLock {
+                    handle: LockHandle::Nested,
+                    path: lock_path.to_path_buf(),
+                    backend,
+                    shared: false,
+                    holder: None,
+                    heartbeat: None,
+                    range: None,
+                    stats: attempts.stats(),
+                    acquired: Instant::now(),
+                    hold_watch: None,
+                    registration: Some(nested),
+                });
+            }
+        }
 
-                            std::thread::sleep(sleep_time);
+        if strategy.is_fair() {
+            attempts = attempts.with_ticket(Ticket::take(lock_path)?);
+        }
 
-                            // Exponential backoff for next iteration (1.5x multiplier)
-                            current_interval = Duration::from_millis(
-                                (current_interval.as_millis() as f64 * 1.5) as u64,
-                            );
-                        }
-                        Err(e) => {
-                            return Err(MutxError::LockAcquisitionFailed {
+        let mut strategy = strategy;
+        let mut registration = None;
+        if let Some(key) = key {
+            let started = Instant::now();
+            let breaks_stale = (strategy.breaks_stale() || strategy.reclaims_own())
+                && !strategy.is_shared()
+                && backend.is_file_lock();
+            let own_only = !strategy.breaks_stale();
+            let claim = poll_until_acquired(lock_path, &strategy, &mut attempts, || {
+                match Registration::try_claim(&key, strategy.is_shared()) {
+                    None if breaks_stale
+                        && is_stale(
+                            &LockHolder::read(lock_path)?,
+                            lock_path,
+                            backend,
+                            own_only,
+                        ) =>
+                    {
+                        Ok(Registration::take_over(&key))
+                    }
+                    claim => Ok(claim),
+                }
+            })
+            .map_err(|e| {
+                if backend.is_file_lock() {
+                    with_holder(e, lock_path, backend)
+                } else {
+                    e
+                }
+            })?;
+            strategy = strategy.remaining(started.elapsed());
+            registration = Some(claim);
+        }
+
+        let mut holder = None;
+        let mut heartbeat = None;
+        let handle = match backend {
+            LockBackend::Flock | LockBackend::Ofd => {
+                let file = acquire_flock(lock_path, &strategy, backend, &mut attempts)?;
+                if !strategy.is_shared() {
+                    let mut current = LockHolder::current();
+                    if let Some(ttl) = strategy.lease_ttl() {
+                        current.lease_ms = Some(ttl.as_millis() as u64);
+                        heartbeat = Some(Heartbeat::start(&file, lock_path, ttl).map_err(|e| {
+                            MutxError::LockAcquisitionFailed {
                                 path: lock_path.to_path_buf(),
                                 source: e,
-                            });
-                        }
+                            }
+                        })?);
                     }
+                    holder = record_holder(&file, lock_path, current);
                 }
+                LockHandle::Flock(file)
             }
-        }
+            LockBackend::Target => {
+                LockHandle::Flock(acquire_flock(lock_path, &strategy, backend, &mut attempts)?)
+            }
+            LockBackend::Dotlock => LockHandle::Dotlock(poll_until_acquired(
+                lock_path,
+                &strategy,
+                &mut attempts,
+                || DotLock::try_acquire(lock_path),
+            )?),
+            LockBackend::AtomicCreate => LockHandle::Created(poll_until_acquired(
+                lock_path,
+                &strategy,
+                &mut attempts,
+                || CreateLock::try_acquire(lock_path, ATOMIC_CREATE_STALE_AFTER),
+            )?),
+            LockBackend::Remote => {
+                return Err(MutxError::Other(
+                    "The remote backend needs a lock service, see FileLock::acquire_remote"
+                        .to_string(),
+                ))
+            }
+        };
 
         debug!("Lock acquired: {}", lock_path.display());
 
         Ok(FileLock {
-            file,
+            handle,
             path: lock_path.to_path_buf(),
+            backend,
+            shared: strategy.is_shared(),
+            holder,
+            heartbeat,
+            range: None,
+            stats: attempts.stats(),
+            acquired: Instant::now(),
+            hold_watch: None,
+            registration,
+        })
+    }
+
+    /// Acquire an exclusive lease on `key` from a network lock service.
+    ///
+    /// `strategy` behaves as for file locks: `NoWait` asks once, `Wait` and
+    /// `Timeout` poll the service with backoff, or leave the waiting to it if
+    /// it [can wait](LockService::can_wait). The lease is released when the
+    /// returned lock is dropped; [`FileLock::path`] reports the key.
+    #[cfg(feature = "cluster")]
+    pub fn acquire_remote(
+        service: &dyn LockService,
+        key: &str,
+        strategy: LockStrategy,
+    ) -> Result<Self> {
+        debug!("Acquiring remote lock: {} (strategy: {:?})", key, strategy);
+
+        let path = PathBuf::from(key);
+        let mut ignore = |_: &LockRetry| {};
+        let mut attempts = Attempts::new(&mut ignore);
+        let lease = match strategy.waiting() {
+            LockStrategy::Wait if service.can_wait() => service
+                .acquire_waiting(key, None)?
+                .ok_or_else(|| MutxError::lock_would_block(&path))?,
+            waiting @ (LockStrategy::Timeout(_) | LockStrategy::Deadline(_))
+                if service.can_wait() =>
+            {
+                let duration = waiting.time_left().unwrap_or_default();
+                service
+                    .acquire_waiting(key, Some(duration))?
+                    .ok_or_else(|| MutxError::LockTimeout {
+                        path: path.clone(),
+                        duration,
+                        holder: None,
+                    })?
+            }
+            _ => poll_until_acquired(&path, &strategy, &mut attempts, || service.try_acquire(key))?,
+        };
+
+        debug!("Remote lock acquired: {} (token {})", key, lease.token());
+
+        Ok(FileLock {
+            handle: LockHandle::Remote(lease),
+            path,
+            backend: LockBackend::Remote,
+            shared: false,
+            holder: None,
+            heartbeat: None,
+            range: None,
+            stats: attempts.stats(),
+            acquired: Instant::now(),
+            hold_watch: None,
+            registration: None,
+        })
+    }
+
+    /// Lock `len` bytes of the file at `path` from `offset`, so writers can
+    /// update disjoint regions of one large file (e.g. fixed-size records) at
+    /// the same time. The file itself is locked, created empty if missing,
+    /// and the range may extend past its end.
+    ///
+    /// Exclusive unless `strategy` is [`LockStrategy::Shared`]; breaking stale
+    /// locks, leases and fair queueing are not supported. Range locks are OFD
+    /// record locks on Linux, `fcntl` record locks on other Unix systems
+    /// (shared by the threads of a process, and released when it closes any
+    /// descriptor of the file) and `LockFileEx` locks on Windows; none of
+    /// them exclude whole-file `flock` locks. [`FileLock::backend`]
+    /// reports `Ofd` on Linux and `Flock` elsewhere.
+    pub fn acquire_range(
+        path: &Path,
+        offset: u64,
+        len: u64,
+        strategy: LockStrategy,
+    ) -> Result<Self> {
+        debug!(
+            "Acquiring range lock: {} bytes {}+{} (strategy: {:?})",
+            path.display(),
+            offset,
+            len,
+            strategy
+        );
+
+        if len == 0 {
+            return Err(MutxError::Other("Lock range must not be empty".to_string()));
+        }
+        if offset
+            .checked_add(len)
+            .filter(|end| *end <= i64::MAX as u64)
+            .is_none()
+        {
+            return Err(MutxError::Other(format!(
+                "Lock range {}+{} ends past the largest file offset",
+                offset, len
+            )));
+        }
+        if strategy.breaks_stale()
+            || strategy.reclaims_own()
+            || strategy.lease_ttl().is_some()
+            || strategy.is_fair()
+        {
+            return Err(MutxError::Other(
+                "Range locks only support waiting and shared strategies".to_string(),
+            ));
+        }
+
+        let shared = strategy.is_shared();
+        let file = open_flock_file(path, shared, LockBackend::Flock)?;
+        let acquisition_failed = |e| MutxError::LockAcquisitionFailed {
+            path: path.to_path_buf(),
+            source: e,
+        };
+        let mut ignore = |_: &LockRetry| {};
+        let mut attempts = Attempts::new(&mut ignore);
+        match strategy.waiting() {
+            LockStrategy::Wait => {
+                range::lock(&file, offset, len, shared, true).map_err(acquisition_failed)?
+            }
+            waiting => poll_until_acquired(path, waiting, &mut attempts, || {
+                match range::lock(&file, offset, len, shared, false) {
+                    Ok(()) => Ok(Some(())),
+                    Err(e) if is_lock_contention(&e) => Ok(None),
+                    Err(e) => Err(acquisition_failed(e)),
+                }
+            })?,
+        }
+
+        debug!("Range lock acquired: {}", path.display());
+
+        Ok(FileLock {
+            handle: LockHandle::Flock(file),
+            path: path.to_path_buf(),
+            backend: if cfg!(target_os = "linux") {
+                LockBackend::Ofd
+            } else {
+                LockBackend::Flock
+            },
+            shared,
+            holder: None,
+            heartbeat: None,
+            range: Some((offset, len)),
+            stats: attempts.stats(),
+            acquired: Instant::now(),
+            hold_watch: None,
+            registration: None,
         })
     }
 
+    /// Break the lock at `lock_path` whoever holds it, for a holder that is
+    /// gone without its lock being released, such as an NFS client whose
+    /// host crashed. Returns where the old lock file, with the holder it
+    /// records, was moved (`<lock>.broken-<unix millis>`).
+    ///
+    /// The holder is not checked: callers must make sure it no longer runs
+    /// (see [`LockHolder::is_alive`] and [`LockHolder::lease_expired`]),
+    /// or it and the next acquirer will both hold the lock. Moving the file
+    /// aside rather than unlocking it frees the path for new acquisitions.
+    /// Waiters still blocked on the old file notice it is gone once they get
+    /// it, and retry on the new one, as when a stale lock is broken.
+    pub fn force_break(lock_path: &Path) -> Result<PathBuf> {
+        let Some(_guard) = DotLock::try_acquire(&break_guard_path(lock_path))? else {
+            return Err(MutxError::Other(format!(
+                "{} is being broken by another process",
+                lock_path.display()
+            )));
+        };
+
+        let millis = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|since| since.as_millis())
+            .unwrap_or_default();
+        let mut backup = lock_path.as_os_str().to_os_string();
+        backup.push(format!(".broken-{}", millis));
+        let backup = PathBuf::from(backup);
+
+        fs::rename(lock_path, &backup).map_err(|e| MutxError::LockAcquisitionFailed {
+            path: lock_path.to_path_buf(),
+            source: e,
+        })?;
+        warn!(
+            "Forcibly broke lock {}; it was moved to {}",
+            lock_path.display(),
+            backup.display()
+        );
+        Ok(backup)
+    }
+
+    /// How long acquiring this lock took and how often it retried
+    pub fn stats(&self) -> AcquireStats {
+        self.stats
+    }
+
+    /// How long this lock has been held
+    pub fn held_for(&self) -> Duration {
+        self.acquired.elapsed()
+    }
+
+    /// Log a warning if this lock is still held `max_hold` after it was
+    /// acquired, to spot a stuck holder. The lock is not released. Replaces
+    /// any earlier maximum.
+    pub fn set_max_hold(&mut self, max_hold: Duration) {
+        self.watch_hold(max_hold, None);
+    }
+
+    /// Like [`FileLock::set_max_hold`], also calling `handler` once from a
+    /// background thread when the maximum is exceeded, e.g. to cancel the
+    /// work holding the lock or abort the process
+    pub fn set_max_hold_with<F>(&mut self, max_hold: Duration, handler: F)
+    where
+        F: FnOnce(&HoldOverrun) + Send + 'static,
+    {
+        self.watch_hold(max_hold, Some(Box::new(handler)));
+    }
+
+    fn watch_hold(&mut self, max_hold: Duration, handler: Option<HoldOverrunHandler>) {
+        // Stop the earlier watch before its maximum can fire
+        self.hold_watch.take();
+        let remaining = max_hold.saturating_sub(self.held_for());
+        self.hold_watch = Some(HoldWatch::start(&self.path, max_hold, remaining, handler));
+    }
+
     /// Get the lock file path
     pub fn path(&self) -> &Path {
         &self.path
     }
+
+    /// Whether this is a shared (read) lock
+    pub fn is_shared(&self) -> bool {
+        self.shared
+    }
+
+    /// Holder payload recorded in the lock file (exclusive `flock` locks
+    /// only, see [`LockHolder`])
+    pub fn holder(&self) -> Option<&LockHolder> {
+        self.holder.as_ref()
+    }
+
+    /// Add the file this lock protects to the recorded holder payload
+    pub fn record_target(&mut self, target: &Path) {
+        if let (LockHandle::Flock(file), Some(current)) = (&self.handle, &self.holder) {
+            let mut updated = current.clone();
+            updated.target = Some(target.to_path_buf());
+            self.holder = record_holder(file, &self.path, updated);
+        }
+    }
+
+    /// Record `command` as the holding program in the holder payload, in
+    /// place of this executable's name
+    pub fn record_command(&mut self, command: &str) {
+        if let (LockHandle::Flock(file), Some(current)) = (&self.handle, &self.holder) {
+            let mut updated = current.clone();
+            updated.command = Some(command.to_string());
+            self.holder = record_holder(file, &self.path, updated);
+        }
+    }
+
+    /// Offset and length of the locked bytes, for locks taken with
+    /// [`FileLock::acquire_range`]
+    pub fn range(&self) -> Option<(u64, u64)> {
+        self.range
+    }
+
+    /// Get the backend holding this lock
+    pub fn backend(&self) -> LockBackend {
+        self.backend
+    }
+
+    /// Fencing token of the lease behind this lock, for backends that issue
+    /// them (currently only `Remote`). Tokens increase with every grant.
+    pub fn fencing_token(&self) -> Option<u64> {
+        match &self.handle {
+            #[cfg(feature = "cluster")]
+            LockHandle::Remote(lease) => Some(lease.token()),
+            _ => None,
+        }
+    }
+
+    /// Flag set if the lease behind this lock is lost while held, for
+    /// backends whose leases can expire (currently only a Redis `Remote`
+    /// service). Pass it to [`AtomicWriter::with_lease_loss`] so a write
+    /// under a lost lease is not committed.
+    ///
+    /// [`AtomicWriter::with_lease_loss`]: crate::AtomicWriter::with_lease_loss
+    pub fn lease_loss(&self) -> Option<LeaseLoss> {
+        match &self.handle {
+            #[cfg(feature = "cluster")]
+            LockHandle::Remote(lease) => lease.loss(),
+            _ => None,
+        }
+    }
+
+    /// Give the lock file the permission bits `mode`, e.g. `0o666` so other
+    /// users can open it in a shared directory. Applied regardless of the
+    /// umask; a lock file that already has `mode` is left alone, so locks
+    /// created by another user work as long as they match. No effect for
+    /// remote leases, the target backend, range locks, or on platforms
+    /// without Unix permissions.
+    pub fn set_mode(&self, mode: FileMode) -> Result<()> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            #[cfg(feature = "cluster")]
+            if matches!(self.handle, LockHandle::Remote(_)) {
+                return Ok(());
+            }
+            // The target, or the file of a range lock, keeps its own
+            // permissions
+            if self.backend == LockBackend::Target || self.range.is_some() {
+                return Ok(());
+            }
+            let failed = |e: io::Error| {
+                MutxError::PermissionDenied(format!(
+                    "cannot set mode {} on lock file {}: {}",
+                    mode,
+                    self.path.display(),
+                    e
+                ))
+            };
+
+            // Through the held descriptor where there is one, so a lock file
+            // replaced since cannot be chmodded instead
+            let held = match &self.handle {
+                LockHandle::Flock(file) => Some(file),
+                _ => None,
+            };
+            let metadata = match held {
+                Some(file) => file.metadata(),
+                None => fs::metadata(&self.path),
+            }
+            .map_err(failed)?;
+            if metadata.permissions().mode() & 0o7777 == mode.bits() {
+                return Ok(());
+            }
+            let permissions = fs::Permissions::from_mode(mode.bits());
+            match held {
+                Some(file) => file.set_permissions(permissions),
+                None => fs::set_permissions(&self.path, permissions),
+            }
+            .map_err(failed)
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = mode;
+            Ok(())
+        }
+    }
+}
+
+fn acquire_flock(
+    lock_path: &Path,
+    strategy: &LockStrategy,
+    backend: LockBackend,
+    attempts: &mut Attempts,
+) -> Result<File> {
+    let shared = strategy.is_shared();
+    let breaks_stale = (strategy.breaks_stale() || strategy.reclaims_own()) && !shared;
+    let own_only = !strategy.breaks_stale();
+    let blocking = !attempts.must_poll();
+    let mut file = open_flock_file(lock_path, shared, backend)?;
+
+    let acquisition_failed = |e| MutxError::LockAcquisitionFailed {
+        path: lock_path.to_path_buf(),
+        source: e,
+    };
+    // The file at the path may be replaced while we wait on it: a target on
+    // every write, a lock file when a stale or wedged lock is broken. Holding
+    // the old one excludes nobody, so every acquisition is checked and retried
+    // on the file now at the path.
+    match strategy.waiting() {
+        LockStrategy::Wait if blocking && (shared || !breaks_stale) => loop {
+            match lock_file(&file, backend, shared, true) {
+                // A signal ended the wait: give up if it cancelled us
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {
+                    attempts.check_cancelled()?;
+                    continue;
+                }
+                result => result.map_err(acquisition_failed)?,
+            }
+            if is_current_lock_file(&file, lock_path) {
+                break;
+            }
+            file = open_flock_file(lock_path, shared, backend)?;
+        },
+        waiting => {
+            poll_until_acquired(lock_path, waiting, attempts, || {
+                match lock_file(&file, backend, shared, false) {
+                    Ok(_) if !is_current_lock_file(&file, lock_path) => {
+                        // Locked a file another process broke or replaced
+                        // while we opened it; the lock is on the new one
+                        file = open_flock_file(lock_path, shared, backend)?;
+                        Ok(None)
+                    }
+                    Ok(_) => Ok(Some(())),
+                    Err(e) if is_lock_contention(&e) && breaks_stale => {
+                        if !is_current_lock_file(&file, lock_path) {
+                            file = open_flock_file(lock_path, shared, backend)?;
+                        } else if let Some(taken) = break_stale_lock(lock_path, backend, own_only)?
+                        {
+                            file = taken;
+                            return Ok(Some(()));
+                        }
+                        Ok(None)
+                    }
+                    Err(e) if is_lock_contention(&e) => Ok(None),
+                    Err(e) => Err(acquisition_failed(e)),
+                }
+            })
+            .map_err(|e| match backend {
+                LockBackend::Target => e,
+                _ => with_holder(e, lock_path, backend),
+            })?;
+        }
+    }
+
+    Ok(file)
+}
+
+/// Take the kernel lock of a file-lock backend on the open lock file,
+/// waiting for it if `wait`
+fn lock_file(file: &File, backend: LockBackend, shared: bool, wait: bool) -> io::Result<()> {
+    // Called through the trait: newer std has inherent `File` lock methods
+    // with the same names but different error types
+    match (backend, shared, wait) {
+        (LockBackend::Ofd, _, _) => ofd::lock(file, shared, wait),
+        (_, true, true) => FileExt::lock_shared(file),
+        (_, true, false) => FileExt::try_lock_shared(file),
+        (_, false, true) => FileExt::lock_exclusive(file),
+        (_, false, false) => FileExt::try_lock_exclusive(file),
+    }
+}
+
+fn open_flock_file(lock_path: &Path, shared: bool, backend: LockBackend) -> Result<File> {
+    let opened = match backend {
+        LockBackend::Target => open_target_file(lock_path),
+        _ => open_lock_file(lock_path, shared),
+    };
+    let file = opened.map_err(|e| MutxError::LockCreationFailed {
+        path: lock_path.to_path_buf(),
+        source: e,
+    })?;
+    verify_not_link(&file, lock_path, |path| MutxError::LockSymlinkNotAllowed {
+        path,
+    })?;
+    Ok(file)
+}
+
+/// Take over the lock at `lock_path` if its recorded holder is a process on
+/// this host that no longer runs, or has let its lease expire. With
+/// `own_only`, only a dead holder invoked like this process is taken over.
+///
+/// The lock file is replaced rather than unlocked: a fresh file is locked
+/// under a temporary name and renamed over it, so waiters still blocked on
+/// the old file notice it is gone (see [`is_current_lock_file`]). A
+/// `<lock>.break` dotlock keeps two processes from breaking the same lock.
+/// Returns the locked replacement, or `None` if the lock is not stale.
+fn break_stale_lock(
+    lock_path: &Path,
+    backend: LockBackend,
+    own_only: bool,
+) -> Result<Option<File>> {
+    let holder = LockHolder::read(lock_path)?;
+    if !is_stale(&holder, lock_path, backend, own_only) {
+        return Ok(None);
+    }
+
+    let Some(_guard) = DotLock::try_acquire(&break_guard_path(lock_path))? else {
+        return Ok(None);
+    };
+    // Someone may have broken it, or the holder refreshed its lease, between
+    // our read and the guard
+    let again = LockHolder::read(lock_path)?;
+    if again != holder || !is_stale(&again, lock_path, backend, own_only) {
+        return Ok(None);
+    }
+
+    let temp_path = unique_temp_path(lock_path);
+    let creation_failed = |e| MutxError::LockCreationFailed {
+        path: lock_path.to_path_buf(),
+        source: e,
+    };
+    let mut opts = OpenOptions::new();
+    opts.read(true).write(true).create_new(true);
+    apply_nofollow(&mut opts);
+    let file = opts.open(&temp_path).map_err(creation_failed)?;
+    let replaced =
+        lock_file(&file, backend, false, false).and_then(|_| fs::rename(&temp_path, lock_path));
+    if let Err(e) = replaced {
+        let _ = fs::remove_file(&temp_path);
+        return Err(MutxError::LockAcquisitionFailed {
+            path: lock_path.to_path_buf(),
+            source: e,
+        });
+    }
+
+    if let Some(holder) = holder {
+        let reason = if own_only {
+            "earlier run of this command, no longer running"
+        } else if holder.is_alive() == Some(false) {
+            "no longer running"
+        } else {
+            "lease expired"
+        };
+        warn!(
+            "Broke stale lock {} held by {} ({})",
+            lock_path.display(),
+            holder,
+            reason
+        );
+    }
+    Ok(Some(file))
+}
+
+/// The `<lock>.break` dotlock held while breaking the lock at `lock_path`
+fn break_guard_path(lock_path: &Path) -> PathBuf {
+    let mut guard_path = lock_path.as_os_str().to_os_string();
+    guard_path.push(".break");
+    PathBuf::from(guard_path)
+}
+
+/// Whether `holder`, read from `lock_path`, is one [`break_stale_lock`]
+/// takes over. Never while readers hold the lock: they record no holder, so
+/// the payload is one left behind by an earlier exclusive holder.
+fn is_stale(
+    holder: &Option<LockHolder>,
+    lock_path: &Path,
+    backend: LockBackend,
+    own_only: bool,
+) -> bool {
+    holder.as_ref().is_some_and(|holder| {
+        let dead = holder.is_alive() == Some(false);
+        let stale = if own_only {
+            dead && holder.same_invoker()
+        } else {
+            dead || holder.lease_expired(lock_path)
+        };
+        stale && !shared_lock_available(lock_path, backend)
+    })
+}
+
+/// Whether a shared lock on `lock_path` can be taken right now, i.e. no
+/// exclusive holder has it. Contended then, the lock is held by readers.
+fn shared_lock_available(lock_path: &Path, backend: LockBackend) -> bool {
+    // A descriptor of our own, whose lock is released when it is closed
+    match open_lock_file(lock_path, true) {
+        Ok(file) => lock_file(&file, backend, true, false).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Whether `file` is still the file at `lock_path`, i.e. it was not broken
+/// and replaced after we opened it
+#[cfg(unix)]
+fn is_current_lock_file(file: &File, lock_path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (file.metadata(), fs::symlink_metadata(lock_path)) {
+        (Ok(held), Ok(current)) => held.dev() == current.dev() && held.ino() == current.ino(),
+        _ => false,
+    }
+}
+
+/// Windows cannot rename over an open file, so a lock file is never
+/// replaced under a holder
+#[cfg(not(unix))]
+fn is_current_lock_file(_file: &File, lock_path: &Path) -> bool {
+    lock_path.exists()
+}
+
+/// Name the process holding the lock in a contention error, from the
+/// payload it recorded in the lock file. Nobody is named while only readers
+/// hold it, as the payload is then one a dead writer left behind.
+pub(crate) fn with_holder(err: MutxError, lock_path: &Path, backend: LockBackend) -> MutxError {
+    let read = || {
+        if shared_lock_available(lock_path, backend) {
+            return None;
+        }
+        LockHolder::read(lock_path).ok().flatten().map(Box::new)
+    };
+    match err {
+        MutxError::LockWouldBlock { path, holder: None } => MutxError::LockWouldBlock {
+            path,
+            holder: read(),
+        },
+        MutxError::LockTimeout {
+            path,
+            duration,
+            holder: None,
+        } => MutxError::LockTimeout {
+            path,
+            duration,
+            holder: read(),
+        },
+        other => other,
+    }
+}
+
+/// Write `holder` into the held lock file. The payload is only diagnostic,
+/// so failing to write it does not fail the lock.
+fn record_holder(file: &File, lock_path: &Path, holder: LockHolder) -> Option<LockHolder> {
+    match holder.write_to(file) {
+        Ok(()) => Some(holder),
+        Err(e) => {
+            debug!(
+                "Could not record lock holder in {}: {}",
+                lock_path.display(),
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Open (creating if needed) the lock file. It is never truncated here: the
+/// current holder's payload must survive until the lock is ours. Readers
+/// taking a shared lock fall back to opening an existing lock file
+/// read-only, so they need no write access to it.
+fn open_lock_file(lock_path: &Path, shared: bool) -> io::Result<File> {
+    let mut opts = OpenOptions::new();
+    if shared {
+        opts.read(true).write(true).create(true);
+    } else {
+        opts.create(true).write(true).truncate(false);
+    }
+
+    // Reject symlinks at OS level (O_NOFOLLOW on Unix, reparse points on Windows)
+    apply_nofollow(&mut opts);
+
+    match opts.open(lock_path) {
+        Err(e) if shared && e.kind() == io::ErrorKind::PermissionDenied => {
+            let mut opts = OpenOptions::new();
+            opts.read(true);
+            apply_nofollow(&mut opts);
+            opts.open(lock_path)
+        }
+        result => result,
+    }
+}
+
+/// Open the target of the target backend read-only, as `flock(1)` does, so
+/// read-only files can be locked too. A missing target is created empty.
+fn open_target_file(target: &Path) -> io::Result<File> {
+    let mut opts = OpenOptions::new();
+    opts.read(true);
+    apply_nofollow(&mut opts);
+    match opts.open(target) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            let mut opts = OpenOptions::new();
+            opts.create(true).write(true).truncate(false);
+            apply_nofollow(&mut opts);
+            opts.open(target)
+        }
+        result => result,
+    }
+}
+
+/// Repeatedly call `try_acquire` according to `strategy` until it yields a lock.
+///
+/// `try_acquire` returns `Ok(None)` on contention. `NoWait` tries once, `Wait`
+/// polls indefinitely and `Timeout` polls until its duration elapses, sleeping
+/// with exponential backoff plus jitter between attempts.
+pub(crate) fn poll_until_acquired<T>(
+    lock_path: &Path,
+    strategy: &LockStrategy,
+    attempts: &mut Attempts,
+    mut try_acquire: impl FnMut() -> Result<Option<T>>,
+) -> Result<T> {
+    attempts.check_cancelled()?;
+    let Some(mut backoff) = Backoff::new(strategy) else {
+        if !attempts.is_turn()? {
+            return Err(MutxError::lock_would_block(lock_path));
+        }
+        return try_acquire()?.ok_or_else(|| MutxError::lock_would_block(lock_path));
+    };
+
+    let mut was_turn = false;
+    loop {
+        if attempts.is_turn()? {
+            // Reaching the head of the queue after a long wait should not
+            // leave the lock idle for a whole maximum backoff
+            if !was_turn {
+                was_turn = true;
+                backoff.restart();
+            }
+            if let Some(acquired) = try_acquire()? {
+                return Ok(acquired);
+            }
+        }
+        let pause = backoff.next_sleep(lock_path)?;
+        attempts.retry(pause);
+        attempts.sleep(pause)?;
+    }
+}
+
+/// Shortest pause between lock attempts, whatever the configured intervals
+const MIN_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Pause between lock attempts: exponential backoff plus jitter, capped at
+/// the strategy's maximum poll interval (see [`TimeoutConfig`])
+#[derive(Debug)]
+pub(crate) struct Backoff {
+    deadline: Option<Duration>,
+    policy: TimeoutConfig,
+    start: Instant,
+    current_interval: Duration,
+}
+
+impl Backoff {
+    /// Backoff for `strategy`, or `None` if it does not wait (`NoWait`)
+    pub(crate) fn new(strategy: &LockStrategy) -> Option<Self> {
+        let (deadline, policy) = match strategy.waiting() {
+            LockStrategy::NoWait => return None,
+            LockStrategy::Wait => (None, TimeoutConfig::new(Duration::ZERO)),
+            LockStrategy::Timeout(config) => (Some(config.duration), config.clone()),
+            deadline @ LockStrategy::Deadline(_) => {
+                (deadline.time_left(), TimeoutConfig::new(Duration::ZERO))
+            }
+            LockStrategy::Shared(_)
+            | LockStrategy::BreakStale(_)
+            | LockStrategy::Lease(..)
+            | LockStrategy::Fair(_)
+            | LockStrategy::ReclaimOwn(_)
+            | LockStrategy::Reentrant(_) => unreachable!("waiting() unwraps wrapping strategies"),
+        };
+        Some(Backoff {
+            deadline,
+            current_interval: policy.initial_interval,
+            policy,
+            start: Instant::now(),
+        })
+    }
+
+    /// Go back to the shortest interval, keeping the deadline
+    fn restart(&mut self) {
+        self.current_interval = self.policy.initial_interval;
+    }
+
+    /// How long to sleep before the next attempt, or a timeout error once
+    /// the deadline has passed
+    pub(crate) fn next_sleep(&mut self, lock_path: &Path) -> Result<Duration> {
+        if let Some(duration) = self.deadline {
+            if self.start.elapsed() >= duration {
+                return Err(MutxError::LockTimeout {
+                    path: lock_path.to_path_buf(),
+                    duration,
+                    holder: None,
+                });
+            }
+        }
+
+        // Calculate sleep time with backoff + jitter
+        let max_interval = self.policy.max_poll_interval;
+        let base_interval = self
+            .current_interval
+            .min(max_interval)
+            .max(MIN_POLL_INTERVAL);
+        let jitter = if self.policy.jitter.is_empty() {
+            self.policy.jitter.start
+        } else {
+            rand::thread_rng().gen_range(self.policy.jitter.clone())
+        };
+
+        // Exponential backoff for next iteration, stopping at the cap so the
+        // interval cannot overflow
+        let multiplier = self.policy.multiplier.max(1.0);
+        self.current_interval =
+            Duration::try_from_secs_f64(base_interval.as_secs_f64() * multiplier)
+                .map_or(max_interval, |next| next.min(max_interval));
+
+        Ok(base_interval + jitter)
+    }
 }
 
 impl Drop for FileLock {
     fn drop(&mut self) {
-        // Lock is automatically released when file handle is dropped
-        // We do NOT delete the lock file - it persists for proper mutual exclusion
-        // Run `mutx housekeep --locks` to clean orphaned locks
-        debug!("Lock released (file persists): {}", self.path.display());
+        // An outermost handle dropped before the handles nested in it hands
+        // the lock over to the registry, which releases it with the last
+        if let Some(registration) = self.registration.take() {
+            let parked = !registration.is_nested()
+                && registration.park_if_outlived(|| FileLock {
+                    handle: std::mem::replace(&mut self.handle, LockHandle::Nested),
+                    path: self.path.clone(),
+                    backend: self.backend,
+                    shared: self.shared,
+                    holder: self.holder.take(),
+                    heartbeat: self.heartbeat.take(),
+                    range: self.range,
+                    stats: self.stats,
+                    acquired: self.acquired,
+                    hold_watch: self.hold_watch.take(),
+                    registration: None,
+                });
+            self.registration = Some(registration);
+            if parked {
+                return;
+            }
+        }
+        if matches!(self.handle, LockHandle::Nested) {
+            return;
+        }
+        match self.backend {
+            // Lock is automatically released when file handle is dropped
+            // We do NOT delete the lock file - it persists for proper mutual exclusion
+            // Run `mutx housekeep locks` to clean orphaned locks
+            LockBackend::Flock | LockBackend::Ofd => {
+                // Stop refreshing before the payload goes
+                self.heartbeat.take();
+                if let (LockHandle::Flock(file), Some(_)) = (&self.handle, &self.holder) {
+                    let _ = holder::clear(file);
+                }
+                debug!("Lock released (file persists): {}", self.path.display())
+            }
+            // The dotlock protocol releases by removing the file (see DotLock's Drop)
+            LockBackend::Dotlock => debug!("Releasing dotlock: {}", self.path.display()),
+            LockBackend::AtomicCreate => {
+                debug!("Releasing atomic-create lock: {}", self.path.display())
+            }
+            LockBackend::Remote => debug!("Releasing remote lock: {}", self.path.display()),
+            LockBackend::Target => debug!("Lock released: {}", self.path.display()),
+        }
     }
 }
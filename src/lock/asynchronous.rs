@@ -0,0 +1,53 @@
+//! Lock acquisition for async code (`tokio` feature).
+//!
+//! Waiting never blocks the runtime: each attempt tries the lock once
+//! without blocking, and the pause between attempts is a `tokio::time`
+//! sleep with the same backoff as [`FileLock::acquire`]. `Wait` therefore
+//! polls rather than blocking in the kernel.
+
+use crate::error::{MutxError, Result};
+use crate::lock::acquisition::{with_holder, AcquireStats, Backoff};
+use crate::lock::backend::LockBackend;
+use crate::lock::{FileLock, LockStrategy};
+use std::path::Path;
+use std::time::Instant;
+
+impl FileLock {
+    /// Acquire a lock on the specified file without blocking the async
+    /// runtime while waiting for it
+    pub async fn acquire_async(lock_path: &Path, strategy: LockStrategy) -> Result<Self> {
+        Self::acquire_async_with_backend(lock_path, strategy, LockBackend::Flock).await
+    }
+
+    /// [`FileLock::acquire_async`] using a specific backend
+    pub async fn acquire_async_with_backend(
+        lock_path: &Path,
+        strategy: LockStrategy,
+        backend: LockBackend,
+    ) -> Result<Self> {
+        let Some(mut backoff) = Backoff::new(&strategy) else {
+            return Self::acquire_with_backend(lock_path, strategy, backend);
+        };
+        let attempt = strategy.once();
+        let start = Instant::now();
+        let mut stats = AcquireStats::default();
+
+        loop {
+            match Self::acquire_with_backend(lock_path, attempt.clone(), backend) {
+                Err(MutxError::LockWouldBlock { .. }) => {}
+                Ok(mut lock) => {
+                    stats.elapsed = start.elapsed();
+                    lock.stats = stats;
+                    return Ok(lock);
+                }
+                Err(e) => return Err(e),
+            }
+            let pause = backoff
+                .next_sleep(lock_path)
+                .map_err(|e| with_holder(e, lock_path, backend))?;
+            stats.retries += 1;
+            stats.last_interval = Some(pause);
+            tokio::time::sleep(pause).await;
+        }
+    }
+}
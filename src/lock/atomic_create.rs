@@ -0,0 +1,283 @@
+//! Lock files created with `O_CREAT | O_EXCL`, for NFS and other filesystems
+//! where `flock` does not exclude other hosts.
+//!
+//! The lock is held while the file exists. It is created exclusively, which
+//! NFSv3 and later perform atomically on the server, and holds
+//! `hostname:pid`. The holder rewrites it every third of
+//! [`ATOMIC_CREATE_STALE_AFTER`] to keep its mtime fresh. A lock is stale
+//! when its PID no longer runs on this host, or when its mtime is older than
+//! that, so the hosts' clocks must roughly agree. Releasing removes the file.
+
+use crate::error::{MutxError, Result};
+use crate::lock::lease::Heartbeat;
+use crate::utils::process::{hostname, pid_is_alive};
+use crate::utils::{apply_nofollow, unique_temp_path, verify_not_link};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::{debug, warn};
+
+/// Age of the mtime after which an atomic-create lock is considered stale.
+/// Holders refresh it every third of this.
+pub const ATOMIC_CREATE_STALE_AFTER: Duration = Duration::from_secs(60);
+
+/// A held atomic-create lock; removed when dropped
+#[derive(Debug)]
+pub(crate) struct CreateLock {
+    path: PathBuf,
+    file: File,
+    payload: String,
+    heartbeat: Option<Heartbeat>,
+}
+
+impl CreateLock {
+    /// Try once to create the lock, breaking it first if it is stale.
+    ///
+    /// Returns `Ok(None)` if another live holder has it.
+    pub(crate) fn try_acquire(path: &Path, stale_after: Duration) -> Result<Option<Self>> {
+        if let Some(lock) = Self::try_create(path, stale_after)? {
+            return Ok(Some(lock));
+        }
+        if let Some(stale) = stale_lock(path, stale_after)? {
+            if break_stale(path, &stale)? {
+                return Self::try_create(path, stale_after);
+            }
+        }
+        Ok(None)
+    }
+
+    fn try_create(path: &Path, stale_after: Duration) -> Result<Option<Self>> {
+        let creation_failed = |e: io::Error| MutxError::LockCreationFailed {
+            path: path.to_path_buf(),
+            source: e,
+        };
+
+        let mut opts = OpenOptions::new();
+        opts.write(true).create_new(true);
+        apply_nofollow(&mut opts);
+        let file = match opts.open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => return Ok(None),
+            Err(e) => return Err(creation_failed(e)),
+        };
+
+        let payload = format!(
+            "{}:{}\n",
+            hostname().unwrap_or_default(),
+            std::process::id()
+        );
+        let mut lock = CreateLock {
+            path: path.to_path_buf(),
+            file,
+            payload,
+            heartbeat: None,
+        };
+        // Dropping `lock` on failure removes the file again
+        verify_not_link(&lock.file, path, |path| MutxError::LockSymlinkNotAllowed {
+            path,
+        })?;
+        rewrite(&lock.file, &lock.payload).map_err(creation_failed)?;
+
+        let (file, payload) = (
+            lock.file.try_clone().map_err(creation_failed)?,
+            lock.payload.clone(),
+        );
+        lock.heartbeat = Some(Heartbeat::every(stale_after / 3, path, move || {
+            rewrite(&file, &payload)
+        }));
+        Ok(Some(lock))
+    }
+
+    /// Whether the file at our path is still the one we created; it may have
+    /// been broken as stale and re-created by someone else
+    fn is_ours(&self) -> bool {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+
+            match (self.file.metadata(), fs::symlink_metadata(&self.path)) {
+                (Ok(held), Ok(current)) => {
+                    held.dev() == current.dev() && held.ino() == current.ino()
+                }
+                _ => false,
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            fs::read_to_string(&self.path).is_ok_and(|content| content == self.payload)
+        }
+    }
+}
+
+impl Drop for CreateLock {
+    fn drop(&mut self) {
+        // Stop refreshing before the file goes
+        self.heartbeat.take();
+        if self.is_ours() {
+            if let Err(e) = fs::remove_file(&self.path) {
+                warn!("Failed to remove lock {}: {}", self.path.display(), e);
+            } else {
+                debug!("Atomic-create lock released: {}", self.path.display());
+            }
+        }
+    }
+}
+
+/// Replace the content of the held lock file with `payload`, which also
+/// refreshes its mtime, and sync it so other hosts see both
+fn rewrite(mut file: &File, payload: &str) -> io::Result<()> {
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(payload.as_bytes())?;
+    file.sync_all()
+}
+
+/// `(hostname, pid)` recorded in the lock file at `path`
+fn read_holder(path: &Path) -> Option<(String, u32)> {
+    parse_holder(&fs::read_to_string(path).ok()?)
+}
+
+fn parse_holder(content: &str) -> Option<(String, u32)> {
+    let (host, pid) = content.trim().rsplit_once(':')?;
+    Some((host.to_string(), pid.parse().ok()?))
+}
+
+/// The metadata of the lock file at `path` if it is stale. The holder and
+/// the mtime are read through one open file, so they describe the same
+/// lock, which [`break_stale`] then checks it moved.
+fn stale_lock(path: &Path, stale_after: Duration) -> Result<Option<fs::Metadata>> {
+    let mut opts = OpenOptions::new();
+    opts.read(true);
+    apply_nofollow(&mut opts);
+    let mut file = match opts.open(path) {
+        Ok(file) => file,
+        // Released between our create attempt and now; retrying will tell
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(MutxError::Io(e)),
+    };
+    let metadata = file.metadata().map_err(MutxError::Io)?;
+
+    let mut content = String::new();
+    if file.read_to_string(&mut content).is_ok() {
+        if let Some((host, pid)) = parse_holder(&content) {
+            if hostname().is_some_and(|ours| ours == host) && pid_is_alive(pid) == Some(false) {
+                return Ok(Some(metadata));
+            }
+        }
+    }
+
+    let mtime = metadata.modified().map_err(MutxError::Io)?;
+    let stale = SystemTime::now()
+        .duration_since(mtime)
+        .is_ok_and(|age| age > stale_after);
+    Ok(stale.then_some(metadata))
+}
+
+/// Whether two metadata snapshots are of the same file
+fn same_file(a: &fs::Metadata, b: &fs::Metadata) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        a.dev() == b.dev() && a.ino() == b.ino()
+    }
+    #[cfg(not(unix))]
+    {
+        a.len() == b.len() && a.modified().ok() == b.modified().ok()
+    }
+}
+
+/// Remove the stale lock at `path`, `stale` being what was found stale, and
+/// return whether the name is free.
+///
+/// The lock is renamed aside first, so of several processes breaking it only
+/// one moves it. If what was moved is not the lock found stale (a live
+/// holder created a fresh one after our check), it is linked back; if that
+/// fails, the fresh holder has lost its lock and the error is returned.
+fn break_stale(path: &Path, stale: &fs::Metadata) -> Result<bool> {
+    let failed = |e: io::Error| MutxError::LockAcquisitionFailed {
+        path: path.to_path_buf(),
+        source: e,
+    };
+
+    let aside = unique_temp_path(path);
+    match fs::rename(path, &aside) {
+        Ok(()) => {}
+        // Someone else broke it first
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(true),
+        Err(e) => return Err(failed(e)),
+    }
+
+    let moved = fs::symlink_metadata(&aside).map_err(failed)?;
+    if !same_file(&moved, stale) {
+        let restored = fs::hard_link(&aside, path);
+        let _ = fs::remove_file(&aside);
+        restored.map_err(failed)?;
+        return Ok(false);
+    }
+
+    let holder = read_holder(&aside);
+    fs::remove_file(&aside).map_err(failed)?;
+    match holder {
+        Some((host, pid)) => warn!(
+            "Removed stale lock {} held by PID {} on {}",
+            path.display(),
+            pid,
+            host
+        ),
+        None => warn!("Removed stale lock {}", path.display()),
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_holder_refreshes_mtime() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("out.lock");
+        let stale_after = Duration::from_millis(300);
+
+        let _lock = CreateLock::try_acquire(&path, stale_after)
+            .unwrap()
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(600));
+
+        assert!(stale_lock(&path, stale_after).unwrap().is_none());
+        assert!(CreateLock::try_acquire(&path, stale_after)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_lock_recreated_after_the_check_is_put_back() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("out.lock");
+        fs::write(&path, "").unwrap();
+        let checked = fs::metadata(&path).unwrap();
+
+        // Another process breaks it and takes a fresh lock between our check
+        // and our rename
+        fs::rename(&path, temp.path().join("broken")).unwrap();
+        let _fresh = CreateLock::try_acquire(&path, ATOMIC_CREATE_STALE_AFTER)
+            .unwrap()
+            .unwrap();
+        let fresh = fs::metadata(&path).unwrap();
+
+        assert!(!break_stale(&path, &checked).unwrap());
+        assert!(same_file(&fs::metadata(&path).unwrap(), &fresh));
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_read_holder_keeps_colons_in_hostname() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("out.lock");
+        fs::write(&path, "fe80::1:4242\n").unwrap();
+
+        assert_eq!(read_holder(&path), Some(("fe80::1".to_string(), 4242)));
+    }
+}
@@ -0,0 +1,102 @@
+use crate::error::{MutxError, Result};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Mechanism used to obtain mutual exclusion
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LockBackend {
+    /// Advisory `flock`/`LockFileEx` on a persistent lock file (default)
+    #[default]
+    Flock,
+    /// Classic dotlockfile/procmail protocol: the lock is held while
+    /// `<target>.lock` exists, created atomically and containing the holder PID
+    Dotlock,
+    /// Lease from a network lock service (requires the `cluster` feature)
+    Remote,
+    /// Open file description lock (`F_OFD_SETLK`) on a persistent lock file,
+    /// Linux only. Behaves like `Flock`, but does not exclude `flock` users
+    Ofd,
+    /// `<target>.lock` created with `O_CREAT | O_EXCL`, holding
+    /// `hostname:pid`, with mtime-based staleness. Works on NFS, where
+    /// `flock` may not exclude other hosts
+    AtomicCreate,
+    /// `flock` on the target file itself, as `flock(1)` does, so mutx
+    /// excludes scripts and daemons that already lock the data file. No
+    /// holder is recorded; shared locks work, leases and breaking stale
+    /// locks do not
+    Target,
+}
+
+impl LockBackend {
+    /// Lock path used when the backend places its own lock file, if any.
+    ///
+    /// Dotlock-aware tools expect `<target>.lock` next to the target, so that
+    /// placement is part of the protocol rather than a choice. Atomic-create
+    /// locks go there too, as other hosts only see the shared filesystem.
+    /// The target backend locks `output` itself.
+    pub fn default_lock_path(&self, output: &Path) -> Option<PathBuf> {
+        match self {
+            LockBackend::Flock | LockBackend::Ofd | LockBackend::Remote => None,
+            LockBackend::Dotlock | LockBackend::AtomicCreate => Some(dotlock_path(output)),
+            LockBackend::Target => Some(output.to_path_buf()),
+        }
+    }
+
+    /// Whether the lock is a kernel lock on a persistent lock file (`Flock`
+    /// or `Ofd`), which records its holder and supports shared locks, leases
+    /// and breaking stale locks
+    pub fn is_file_lock(&self) -> bool {
+        matches!(self, LockBackend::Flock | LockBackend::Ofd)
+    }
+}
+
+impl fmt::Display for LockBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockBackend::Flock => write!(f, "flock"),
+            LockBackend::Dotlock => write!(f, "dotlock"),
+            LockBackend::Remote => write!(f, "remote"),
+            LockBackend::Ofd => write!(f, "ofd"),
+            LockBackend::AtomicCreate => write!(f, "atomic-create"),
+            LockBackend::Target => write!(f, "target"),
+        }
+    }
+}
+
+impl FromStr for LockBackend {
+    type Err = MutxError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "flock" => Ok(LockBackend::Flock),
+            "dotlock" => Ok(LockBackend::Dotlock),
+            "atomic-create" => Ok(LockBackend::AtomicCreate),
+            "target" => Ok(LockBackend::Target),
+            #[cfg(feature = "cluster")]
+            "remote" => Ok(LockBackend::Remote),
+            #[cfg(not(feature = "cluster"))]
+            "remote" => Err(MutxError::Other(
+                "The remote lock backend requires mutx to be built with the 'cluster' feature"
+                    .to_string(),
+            )),
+            #[cfg(target_os = "linux")]
+            "ofd" => Ok(LockBackend::Ofd),
+            #[cfg(not(target_os = "linux"))]
+            "ofd" => Err(MutxError::Other(
+                "The ofd lock backend is only available on Linux".to_string(),
+            )),
+            _ => Err(MutxError::Other(format!(
+                "Unknown lock backend '{}': expected one of flock, ofd, dotlock, atomic-create, target, remote",
+                s
+            ))),
+        }
+    }
+}
+
+/// `<target>.lock`, the conventional dotlock file for `target`
+pub fn dotlock_path(target: &Path) -> PathBuf {
+    let mut name = target.as_os_str().to_os_string();
+    name.push(".lock");
+    PathBuf::from(name)
+}
@@ -0,0 +1,35 @@
+//! Aborting a pending lock acquisition from another thread.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Shared flag that makes [`FileLock::acquire_cancellable`](crate::FileLock)
+/// give up with [`MutxError::Interrupted`](crate::MutxError::Interrupted).
+///
+/// Clones share the flag, so one clone can be handed to the waiting thread
+/// and another to whatever decides to stop it (a signal handler, a
+/// shutdown hook).
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Abort acquisitions waiting on this token, now and in the future
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Share an existing flag, e.g. one set by `signal_hook::flag::register`
+impl From<Arc<AtomicBool>> for CancelToken {
+    fn from(flag: Arc<AtomicBool>) -> Self {
+        CancelToken(flag)
+    }
+}
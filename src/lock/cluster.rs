@@ -0,0 +1,66 @@
+//! Lock coordination through an external service, for hosts that do not share
+//! a POSIX filesystem with reliable locking.
+//!
+//! A [`LockService`] grants exclusive [`RemoteLease`]s on string keys. Leases
+//! are released when dropped. [`FileLock::acquire_remote`] drives a service
+//! with the usual [`LockStrategy`] semantics (no-wait, wait, timeout).
+//!
+//! mutx ships a reference service, [`lockd`], a small TCP lock server started
+//! with `mutx lockd`, and on Unix the same server on a local socket,
+//! [`daemon`], started with `mutx daemon`. With the `redis` feature, [`redis`] grants leases from
+//! a Redis server instead. Other services (etcd leases, ZooKeeper, a cloud
+//! lock API) plug in by implementing the two traits below.
+//!
+//! [`FileLock::acquire_remote`]: crate::lock::FileLock::acquire_remote
+//! [`LockStrategy`]: crate::lock::LockStrategy
+
+#[cfg(unix)]
+pub mod daemon;
+pub mod lockd;
+#[cfg(feature = "redis")]
+pub mod redis;
+
+use crate::error::Result;
+use crate::lock::LeaseLoss;
+use std::fmt;
+use std::time::Duration;
+
+/// A coordination service able to grant exclusive leases on keys
+pub trait LockService: Send + Sync + fmt::Debug {
+    /// Try once to acquire `key`.
+    ///
+    /// Returns `Ok(None)` when another client currently holds it.
+    fn try_acquire(&self, key: &str) -> Result<Option<Box<dyn RemoteLease>>>;
+
+    /// Acquire `key`, waiting up to `timeout` (indefinitely if `None`) for
+    /// the server to hand it over once released.
+    ///
+    /// Returns `Ok(None)` when it is still held at the timeout. Only called
+    /// if [`can_wait`](LockService::can_wait) says so; the default asks once.
+    fn acquire_waiting(
+        &self,
+        key: &str,
+        timeout: Option<Duration>,
+    ) -> Result<Option<Box<dyn RemoteLease>>> {
+        let _ = timeout;
+        self.try_acquire(key)
+    }
+
+    /// Whether the service waits for keys itself, so callers need not poll
+    fn can_wait(&self) -> bool {
+        false
+    }
+}
+
+/// An exclusive lease granted by a [`LockService`]; released on drop
+pub trait RemoteLease: Send + fmt::Debug {
+    /// Token issued with this lease. Tokens grow monotonically across all
+    /// leases granted by a service, so they can be used for fencing.
+    fn token(&self) -> u64;
+
+    /// Flag set once the lease is lost while held, for services whose
+    /// leases can expire under a live holder. `None` if it cannot be.
+    fn loss(&self) -> Option<LeaseLoss> {
+        None
+    }
+}
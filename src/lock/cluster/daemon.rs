@@ -0,0 +1,132 @@
+//! `mutx daemon`: the [`lockd`] server on a Unix socket, for
+//! the processes of one host.
+//!
+//! The daemon owns the lock table, so locking through it depends on no
+//! filesystem: it excludes writers as well on NFS or a FUSE mount without
+//! `flock` as on local disk. Waiting clients are handed a released key
+//! straight away instead of polling for it, and a client that dies loses
+//! its leases with its connection.
+//!
+//! The socket is created in a directory only its user can enter
+//! (`$XDG_RUNTIME_DIR/mutx/daemon.sock` by default, see
+//! [`default_socket_path`]), so each user runs their own daemon.
+
+use super::lockd::{self, LockTable};
+use super::{LockService, RemoteLease};
+use crate::dirs::{create_private_dir_all, runtime_dir};
+use crate::error::{MutxError, Result};
+use std::fs;
+use std::io;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+/// Name of the daemon's socket in the runtime directory
+pub const SOCKET_NAME: &str = "daemon.sock";
+
+/// Where `mutx daemon` listens unless told otherwise
+pub fn default_socket_path() -> Result<PathBuf> {
+    runtime_dir()
+        .map(|dir| dir.join(SOCKET_NAME))
+        .ok_or_else(|| {
+            MutxError::Other(
+                "No runtime directory for the daemon socket; set XDG_RUNTIME_DIR or pass --socket"
+                    .to_string(),
+            )
+        })
+}
+
+/// Lock server on a Unix socket; the socket is removed when it is dropped
+#[derive(Debug)]
+pub struct LockDaemon {
+    listener: UnixListener,
+    path: PathBuf,
+    table: Arc<LockTable>,
+}
+
+impl LockDaemon {
+    /// Listen on the socket at `path`, creating its directory private to
+    /// this user if missing. A socket left behind by a daemon that is gone
+    /// is replaced; one a daemon still answers on is an error.
+    pub fn bind(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            create_private_dir_all(dir)?;
+        }
+        if fs::symlink_metadata(path).is_ok() {
+            if UnixStream::connect(path).is_ok() {
+                return Err(MutxError::Io(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    format!("A daemon is already listening on {}", path.display()),
+                )));
+            }
+            fs::remove_file(path)?;
+        }
+
+        let listener = UnixListener::bind(path)?;
+        Ok(LockDaemon {
+            listener,
+            path: path.to_path_buf(),
+            table: Arc::default(),
+        })
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Accept and serve clients forever, one thread per connection
+    pub fn serve(self) -> Result<()> {
+        for stream in self.listener.incoming() {
+            match stream {
+                Ok(stream) => self.table.serve(stream),
+                Err(e) => warn!("daemon: failed to accept connection: {}", e),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for LockDaemon {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Client for a [`LockDaemon`]
+#[derive(Debug, Clone)]
+pub struct DaemonClient {
+    path: PathBuf,
+}
+
+impl DaemonClient {
+    /// Client for the daemon listening on `path`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        DaemonClient { path: path.into() }
+    }
+
+    /// Client for the daemon at [`default_socket_path`]
+    pub fn from_default_socket() -> Result<Self> {
+        Ok(DaemonClient::new(default_socket_path()?))
+    }
+}
+
+impl LockService for DaemonClient {
+    fn try_acquire(&self, key: &str) -> Result<Option<Box<dyn RemoteLease>>> {
+        lockd::request(key, None, || UnixStream::connect(&self.path))
+    }
+
+    fn acquire_waiting(
+        &self,
+        key: &str,
+        timeout: Option<Duration>,
+    ) -> Result<Option<Box<dyn RemoteLease>>> {
+        lockd::request(key, Some(timeout), || UnixStream::connect(&self.path))
+    }
+
+    fn can_wait(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,342 @@
+//! `lockd`: a minimal TCP lock server and matching [`LockService`] client.
+//!
+//! Protocol (one request per line, UTF-8):
+//!
+//! ```text
+//! C: ACQUIRE <key>            S: OK <token> | BUSY | ERR <message>
+//! C: WAIT <millis|-> <key>    S: OK <token> | BUSY | ERR <message>
+//! C: RELEASE                  S: OK
+//! ```
+//!
+//! `WAIT` holds the reply until the key is released, or for at most
+//! `millis` milliseconds (`-` waits indefinitely), so waiting clients are
+//! handed the key without polling. Servers from before `WAIT` answer it
+//! with `ERR unknown request`, and the client falls back to polling them
+//! with `ACQUIRE`.
+//!
+//! Each lease is bound to the connection that acquired it. When the
+//! connection closes, for example because the client crashed, the server
+//! releases every lease it held, mirroring how flock locks die with their
+//! process. The same protocol is served on a Unix socket by
+//! [`daemon`](super::daemon).
+
+use super::{LockService, RemoteLease};
+use crate::error::{MutxError, Result};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// How often a server that does not understand `WAIT` is asked again
+const FALLBACK_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A connection the protocol is spoken on
+pub(super) trait Stream: Read + Write + Send + fmt::Debug + Sized + 'static {
+    fn try_clone(&self) -> io::Result<Self>;
+}
+
+impl Stream for TcpStream {
+    fn try_clone(&self) -> io::Result<Self> {
+        TcpStream::try_clone(self)
+    }
+}
+
+#[cfg(unix)]
+impl Stream for std::os::unix::net::UnixStream {
+    fn try_clone(&self) -> io::Result<Self> {
+        std::os::unix::net::UnixStream::try_clone(self)
+    }
+}
+
+#[derive(Debug, Default)]
+struct ServerState {
+    /// key -> id of the connection holding it
+    held: HashMap<String, u64>,
+    next_connection: u64,
+    next_token: u64,
+}
+
+/// Keys held by the connections of one server
+#[derive(Debug, Default)]
+pub(super) struct LockTable {
+    state: Mutex<ServerState>,
+    /// Signalled whenever keys are released
+    released: Condvar,
+}
+
+impl LockTable {
+    fn state(&self) -> MutexGuard<'_, ServerState> {
+        // A panicking connection thread must not take the whole server down
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Serve `stream` on a thread of its own, releasing its leases when it
+    /// closes
+    pub(super) fn serve<S: Stream>(self: &Arc<Self>, stream: S) {
+        let table = Arc::clone(self);
+        let connection = {
+            let mut state = table.state();
+            state.next_connection += 1;
+            state.next_connection
+        };
+
+        std::thread::spawn(move || {
+            if let Err(e) = table.serve_connection(connection, stream) {
+                debug!("lockd: connection {} ended: {}", connection, e);
+            }
+            table.release(connection);
+        });
+    }
+
+    fn serve_connection<S: Stream>(&self, connection: u64, stream: S) -> io::Result<()> {
+        let mut writer = stream.try_clone()?;
+        let reader = BufReader::new(stream);
+
+        for line in reader.lines() {
+            let line = line?;
+            let reply = if let Some(key) = line.strip_prefix("ACQUIRE ") {
+                self.grant(key, connection, Some(Duration::ZERO))
+            } else if let Some(request) = line.strip_prefix("WAIT ") {
+                match parse_wait(request) {
+                    Some((timeout, key)) => self.grant(key, connection, timeout),
+                    None => format!("ERR malformed request: {}", line),
+                }
+            } else if line == "RELEASE" {
+                self.release(connection);
+                "OK".to_string()
+            } else {
+                format!("ERR unknown request: {}", line)
+            };
+            writer.write_all(format!("{}\n", reply).as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Grant `key` to `connection` once it is free, waiting at most
+    /// `timeout` (indefinitely if `None`), and return the reply
+    fn grant(&self, key: &str, connection: u64, timeout: Option<Duration>) -> String {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut state = self.state();
+        while state.held.contains_key(key) {
+            state = match deadline {
+                None => self
+                    .released
+                    .wait(state)
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return "BUSY".to_string();
+                    }
+                    self.released
+                        .wait_timeout(state, deadline - now)
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .0
+                }
+            };
+        }
+        state.next_token += 1;
+        let token = state.next_token;
+        state.held.insert(key.to_string(), connection);
+        debug!("lockd: {} acquired by connection {}", key, connection);
+        format!("OK {}", token)
+    }
+
+    fn release(&self, connection: u64) {
+        self.state().held.retain(|_, holder| *holder != connection);
+        self.released.notify_all();
+    }
+}
+
+/// `<millis|-> <key>` of a `WAIT` request
+fn parse_wait(request: &str) -> Option<(Option<Duration>, &str)> {
+    let (timeout, key) = request.split_once(' ')?;
+    let timeout = match timeout {
+        "-" => None,
+        millis => Some(Duration::from_millis(millis.parse().ok()?)),
+    };
+    Some((timeout, key))
+}
+
+/// TCP lock server
+#[derive(Debug)]
+pub struct LockServer {
+    listener: TcpListener,
+    table: Arc<LockTable>,
+}
+
+impl LockServer {
+    /// Bind the server to `addr` (use port 0 for an ephemeral port)
+    pub fn bind(addr: impl ToSocketAddrs) -> Result<Self> {
+        let listener = TcpListener::bind(addr).map_err(MutxError::Io)?;
+        Ok(LockServer {
+            listener,
+            table: Arc::default(),
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        self.listener.local_addr().map_err(MutxError::Io)
+    }
+
+    /// Accept and serve clients forever, one thread per connection
+    pub fn serve(self) -> Result<()> {
+        for stream in self.listener.incoming() {
+            match stream {
+                Ok(stream) => self.table.serve(stream),
+                Err(e) => warn!("lockd: failed to accept connection: {}", e),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Client for a [`LockServer`]
+#[derive(Debug, Clone)]
+pub struct LockdClient {
+    addr: String,
+}
+
+impl LockdClient {
+    /// Client for the server at `addr` (`host:port`)
+    pub fn new(addr: impl Into<String>) -> Self {
+        LockdClient { addr: addr.into() }
+    }
+}
+
+impl LockService for LockdClient {
+    fn try_acquire(&self, key: &str) -> Result<Option<Box<dyn RemoteLease>>> {
+        request(key, None, || TcpStream::connect(&self.addr))
+    }
+
+    fn acquire_waiting(
+        &self,
+        key: &str,
+        timeout: Option<Duration>,
+    ) -> Result<Option<Box<dyn RemoteLease>>> {
+        request(key, Some(timeout), || TcpStream::connect(&self.addr))
+    }
+
+    fn can_wait(&self) -> bool {
+        true
+    }
+}
+
+/// Ask the server `connect` reaches for `key`: once (`wait` is `None`), or
+/// waiting up to the given timeout
+pub(super) fn request<S: Stream>(
+    key: &str,
+    wait: Option<Option<Duration>>,
+    connect: impl FnOnce() -> io::Result<S>,
+) -> Result<Option<Box<dyn RemoteLease>>> {
+    if key.contains('\n') {
+        return Err(MutxError::Other(
+            "Lock keys cannot contain newlines".to_string(),
+        ));
+    }
+
+    let unavailable = |e: io::Error| MutxError::LockAcquisitionFailed {
+        path: key.into(),
+        source: e,
+    };
+
+    let stream = connect().map_err(unavailable)?;
+    let mut writer = stream.try_clone().map_err(unavailable)?;
+    let mut reader = BufReader::new(stream);
+
+    let request = match wait {
+        None => format!("ACQUIRE {}\n", key),
+        Some(None) => format!("WAIT - {}\n", key),
+        // Rounded up, so the wait never ends before the caller's deadline
+        Some(Some(timeout)) => format!("WAIT {} {}\n", timeout.as_micros().div_ceil(1000), key),
+    };
+    let mut reply = exchange(&mut writer, &mut reader, &request).map_err(unavailable)?;
+
+    // A server older than `WAIT` is polled instead, as for services that
+    // cannot wait
+    if let Some(wait) = wait.filter(|_| reply.starts_with("ERR unknown request")) {
+        debug!("lockd: server cannot wait, polling for {}", key);
+        let deadline = wait.map(|timeout| Instant::now() + timeout);
+        let acquire = format!("ACQUIRE {}\n", key);
+        loop {
+            reply = exchange(&mut writer, &mut reader, &acquire).map_err(unavailable)?;
+            if reply != "BUSY" {
+                break;
+            }
+            let now = Instant::now();
+            match deadline {
+                Some(deadline) if now >= deadline => break,
+                Some(deadline) => std::thread::sleep(FALLBACK_POLL_INTERVAL.min(deadline - now)),
+                None => std::thread::sleep(FALLBACK_POLL_INTERVAL),
+            }
+        }
+    }
+    let reply = reply.as_str();
+
+    if reply == "BUSY" {
+        return Ok(None);
+    }
+    if let Some(token) = reply.strip_prefix("OK ") {
+        let token = token
+            .parse()
+            .map_err(|_| MutxError::Other(format!("lockd sent an invalid token: {}", token)))?;
+        return Ok(Some(Box::new(LockdLease {
+            key: key.to_string(),
+            token,
+            writer,
+            reader,
+        })));
+    }
+
+    Err(MutxError::Other(format!(
+        "lockd refused to lock {}: {}",
+        key, reply
+    )))
+}
+
+/// Send `request` and read the reply to it, without its line ending
+fn exchange<S: Stream>(
+    writer: &mut S,
+    reader: &mut BufReader<S>,
+    request: &str,
+) -> io::Result<String> {
+    writer.write_all(request.as_bytes())?;
+    let mut reply = String::new();
+    reader.read_line(&mut reply)?;
+    reply.truncate(reply.trim_end().len());
+    Ok(reply)
+}
+
+/// Lease held on a lockd connection
+#[derive(Debug)]
+struct LockdLease<S: Stream> {
+    key: String,
+    token: u64,
+    writer: S,
+    reader: BufReader<S>,
+}
+
+impl<S: Stream> RemoteLease for LockdLease<S> {
+    fn token(&self) -> u64 {
+        self.token
+    }
+}
+
+impl<S: Stream> Drop for LockdLease<S> {
+    fn drop(&mut self) {
+        // Closing the connection releases the lease even if this fails
+        let mut reply = String::new();
+        if let Err(e) = self
+            .writer
+            .write_all(b"RELEASE\n")
+            .and_then(|_| self.reader.read_line(&mut reply).map(|_| ()))
+        {
+            warn!("lockd: failed to release {} cleanly: {}", self.key, e);
+        }
+    }
+}
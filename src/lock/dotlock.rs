@@ -0,0 +1,139 @@
+//! Dotlock protocol compatible with liblockfile's `dotlockfile` and procmail.
+//!
+//! A lock is held while `<target>.lock` exists. It is created atomically by
+//! writing the holder PID to a uniquely named temp file in the same directory
+//! and hard-linking it to the lock name (`link(2)` fails if the name exists and
+//! is atomic even on NFS). A lock is stale when its PID no longer runs on this
+//! host, or, if no PID can be read or checked, when it is older than
+//! [`DOTLOCK_STALE_AFTER`]. Releasing removes the file.
+
+use crate::error::{MutxError, Result};
+use crate::utils::{apply_nofollow, pid_is_alive, verify_not_link};
+use rand::Rng;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::{debug, warn};
+
+/// Age after which a dotlock without a live, checkable PID is considered stale
+/// (liblockfile uses the same five minute rule)
+pub const DOTLOCK_STALE_AFTER: Duration = Duration::from_secs(300);
+
+/// A held dotlock; removed when dropped
+#[derive(Debug)]
+pub(crate) struct DotLock {
+    path: PathBuf,
+    pid: u32,
+}
+
+impl DotLock {
+    /// Try once to create the dotlock, breaking it first if it is stale.
+    ///
+    /// Returns `Ok(None)` if another live holder has it.
+    pub(crate) fn try_acquire(path: &Path) -> Result<Option<Self>> {
+        let pid = std::process::id();
+
+        if try_link_create(path, pid)? {
+            return Ok(Some(DotLock {
+                path: path.to_path_buf(),
+                pid,
+            }));
+        }
+
+        if is_stale(path)? {
+            warn!("Removing stale dotlock: {}", path.display());
+            match fs::remove_file(path) {
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    return Err(MutxError::LockAcquisitionFailed {
+                        path: path.to_path_buf(),
+                        source: e,
+                    })
+                }
+            }
+            if try_link_create(path, pid)? {
+                return Ok(Some(DotLock {
+                    path: path.to_path_buf(),
+                    pid,
+                }));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl Drop for DotLock {
+    fn drop(&mut self) {
+        // Only remove the lock if it is still ours; it may have been broken
+        // as stale and re-created by someone else in the meantime
+        if read_pid(&self.path) == Some(self.pid) {
+            if let Err(e) = fs::remove_file(&self.path) {
+                warn!("Failed to remove dotlock {}: {}", self.path.display(), e);
+            } else {
+                debug!("Dotlock released: {}", self.path.display());
+            }
+        }
+    }
+}
+
+/// Create `path` via a temp file + hard link. Returns false if it already exists.
+fn try_link_create(path: &Path, pid: u32) -> Result<bool> {
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty());
+    let temp_name = format!(".lk{}.{:08x}", pid, rand::thread_rng().gen::<u32>());
+    let temp_path = match dir {
+        Some(dir) => dir.join(temp_name),
+        None => PathBuf::from(temp_name),
+    };
+
+    let creation_failed = |e: io::Error| MutxError::LockCreationFailed {
+        path: path.to_path_buf(),
+        source: e,
+    };
+
+    let mut opts = OpenOptions::new();
+    opts.write(true).create_new(true);
+    apply_nofollow(&mut opts);
+    let mut temp = opts.open(&temp_path).map_err(creation_failed)?;
+
+    let result = verify_not_link(&temp, &temp_path, |path| MutxError::LockSymlinkNotAllowed {
+        path,
+    })
+    .and_then(|_| {
+        temp.write_all(format!("{}\n", pid).as_bytes())
+            .map_err(creation_failed)
+    })
+    .and_then(|_| match fs::hard_link(&temp_path, path) {
+        Ok(_) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Ok(false),
+        Err(e) => Err(creation_failed(e)),
+    });
+
+    drop(temp);
+    let _ = fs::remove_file(&temp_path);
+    result
+}
+
+fn read_pid(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+fn is_stale(path: &Path) -> Result<bool> {
+    if let Some(alive) = read_pid(path).and_then(pid_is_alive) {
+        return Ok(!alive);
+    }
+
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        // Vanished between our create attempt and now; retrying will tell
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(MutxError::Io(e)),
+    };
+    let mtime = metadata.modified().map_err(MutxError::Io)?;
+    Ok(SystemTime::now()
+        .duration_since(mtime)
+        .map(|age| age > DOTLOCK_STALE_AFTER)
+        .unwrap_or(false))
+}
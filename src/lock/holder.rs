@@ -0,0 +1,209 @@
+//! Who holds a lock: a small JSON payload written into `flock` lock files.
+//!
+//! The payload is written once the exclusive lock is acquired and cleared
+//! again on release, so a non-empty lock file names its current holder, or a
+//! holder that crashed without releasing (see [`LockHolder::is_alive`]).
+//! Shared locks, dotlocks (which keep the plain PID format other tools
+//! expect) and remote leases do not record a holder.
+//!
+//! On Windows the lock covers the file's contents, so the payload can only
+//! be read once the lock is released.
+
+use crate::error::{MutxError, Result};
+use crate::utils::process::{hostname, pid_is_alive, process_start_time};
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Process holding a lock
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockHolder {
+    pub pid: u32,
+    pub hostname: String,
+    /// When the lock was acquired, in seconds since the Unix epoch
+    pub acquired_at: u64,
+    /// Name of the holding program
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// File the lock protects, once the holder has recorded it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<PathBuf>,
+    /// Length of the holder's lease in milliseconds, if it holds one: the
+    /// lock counts as stale once the lock file's mtime is older than this
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lease_ms: Option<u64>,
+    /// Who invoked the holder, see [`invoker_fingerprint`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+    /// When the holding process started, see [`process_start_time`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<u64>,
+}
+
+impl LockHolder {
+    /// The current process, acquiring a lock now
+    pub fn current() -> Self {
+        LockHolder {
+            pid: std::process::id(),
+            hostname: hostname().unwrap_or_default(),
+            acquired_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            command: std::env::args_os()
+                .next()
+                .as_deref()
+                .and_then(|argv0| Path::new(argv0).file_name())
+                .map(|name| name.to_string_lossy().into_owned()),
+            target: None,
+            lease_ms: None,
+            fingerprint: invoker_fingerprint(),
+            started_at: process_start_time(std::process::id()),
+        }
+    }
+
+    /// Length of the holder's lease, if it holds one
+    pub fn lease(&self) -> Option<Duration> {
+        self.lease_ms.map(Duration::from_millis)
+    }
+
+    /// Whether the holder holds a lease on the lock file at `lock_path` and
+    /// has not refreshed it for longer than the lease lasts
+    pub fn lease_expired(&self, lock_path: &Path) -> bool {
+        let Some(lease) = self.lease() else {
+            return false;
+        };
+        std::fs::metadata(lock_path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|refreshed| SystemTime::now().duration_since(refreshed).ok())
+            .is_some_and(|age| age > lease)
+    }
+
+    /// Holder recorded in the lock file at `lock_path`; `None` if the file is
+    /// missing, empty (released) or holds no mutx payload
+    pub fn read(lock_path: &Path) -> Result<Option<Self>> {
+        let mut content = String::new();
+        match File::open(lock_path).and_then(|mut f| f.read_to_string(&mut content)) {
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(MutxError::ReadFailed {
+                    path: lock_path.to_path_buf(),
+                    source: e,
+                })
+            }
+        }
+        Ok(serde_json::from_str(content.trim()).ok())
+    }
+
+    /// Whether the holding process is still running. `None` when that
+    /// cannot be told, e.g. the holder is on another host.
+    pub fn is_alive(&self) -> Option<bool> {
+        match hostname() {
+            Some(host) if host == self.hostname => pid_is_alive(self.pid),
+            _ => None,
+        }
+    }
+
+    /// Whether the holder's PID still names the process that took the
+    /// lock: it is running on this host and, where its start time was
+    /// recorded, started then. `None` when that cannot be told.
+    pub fn is_same_process(&self) -> Option<bool> {
+        if self.is_alive()? {
+            match self.started_at {
+                Some(started) => Some(process_start_time(self.pid) == Some(started)),
+                None => Some(true),
+            }
+        } else {
+            Some(false)
+        }
+    }
+
+    /// Whether the holder was invoked the same way as this process, i.e. is
+    /// an earlier run of the same command by the same user
+    pub fn same_invoker(&self) -> bool {
+        self.fingerprint.is_some() && self.fingerprint == invoker_fingerprint()
+    }
+
+    /// Replace the content of the held lock file with this payload
+    pub(crate) fn write_to(&self, mut file: &File) -> io::Result<()> {
+        let payload = serde_json::to_vec(self)?;
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(&payload)?;
+        file.write_all(b"\n")
+    }
+}
+
+impl fmt::Display for LockHolder {
+    /// `PID 1234 on host since 14:02`; the date is added unless it is today
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PID {} on {}", self.pid, self.hostname)?;
+        if let Some(since) = Local.timestamp_opt(self.acquired_at as i64, 0).single() {
+            if since.date_naive() == Local::now().date_naive() {
+                write!(f, " since {}", since.format("%H:%M"))?;
+            } else {
+                write!(f, " since {}", since.format("%Y-%m-%d %H:%M"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fingerprint of how this process was invoked: a hash of its user, working
+/// directory and command line. Every run of the same cron job or service
+/// shares it, though each runs in a new session; the same command run by
+/// another user or from another directory does not. `None` if the working
+/// directory cannot be read.
+pub fn invoker_fingerprint() -> Option<String> {
+    let cwd = std::env::current_dir().ok()?;
+    let mut hasher = Sha256::new();
+    #[cfg(unix)]
+    {
+        // SAFETY: geteuid cannot fail
+        let uid = unsafe { libc::geteuid() };
+        hasher.update(uid.to_le_bytes());
+    }
+    hasher.update(cwd.as_os_str().as_encoded_bytes());
+    hasher.update([0]);
+    for arg in std::env::args_os() {
+        hasher.update(arg.as_encoded_bytes());
+        hasher.update([0]);
+    }
+    let hex = format!("{:x}", hasher.finalize());
+    Some(hex[..16].to_string())
+}
+
+/// Empty the lock file before it is released
+pub(crate) fn clear(file: &File) -> io::Result<()> {
+    file.set_len(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_holder_round_trip() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("out.lock");
+        let file = File::create(&path).unwrap();
+
+        let mut holder = LockHolder::current();
+        holder.target = Some(PathBuf::from("/srv/out.txt"));
+        holder.write_to(&file).unwrap();
+        assert_eq!(LockHolder::read(&path).unwrap(), Some(holder.clone()));
+        assert_ne!(holder.is_alive(), Some(false));
+        assert!(holder.same_invoker());
+
+        clear(&file).unwrap();
+        assert_eq!(LockHolder::read(&path).unwrap(), None);
+    }
+}
@@ -0,0 +1,133 @@
+//! Lock leases: a holder that keeps its lock file's mtime fresh while it
+//! works, so others can tell a crashed holder from a slow one.
+//!
+//! A holder taking an exclusive `flock` lock with a lease records the lease
+//! length in its [`LockHolder`](crate::LockHolder) payload and touches the lock file every third
+//! of it. Once the mtime is older than the lease, processes waiting with
+//! [`LockStrategy::BreakStale`](crate::LockStrategy) or a lease of their own
+//! treat the lock as stale and take it over, even where the kernel lock is
+//! unreliable (NFS) or was inherited by a surviving child process.
+//!
+//! On Windows the payload cannot be read while the lock is held, so leases
+//! are recorded but never expire there.
+
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Set once a held lease is known to be lost: it expired and may have been
+/// granted to another holder. Clones share the flag, so a writer holding
+/// one refuses to commit under a lease lost after it was acquired (see
+/// [`AtomicWriter::with_lease_loss`](crate::AtomicWriter::with_lease_loss)).
+#[derive(Debug, Clone, Default)]
+pub struct LeaseLoss(Arc<AtomicBool>);
+
+impl LeaseLoss {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that the lease is lost; it stays lost
+    pub fn mark_lost(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_lost(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Background refresh of a held lease; stops when dropped
+#[derive(Debug)]
+pub(crate) struct Heartbeat {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Heartbeat {
+    /// Touch `file` (the held lock file at `lock_path`) every third of `ttl`
+    pub(crate) fn start(file: &File, lock_path: &Path, ttl: Duration) -> io::Result<Self> {
+        let file = file.try_clone()?;
+        Ok(Self::every(ttl / 3, lock_path, move || touch(&file)))
+    }
+
+    /// Call `refresh` for the held lock at `lock_path` every `interval`
+    pub(crate) fn every<F>(interval: Duration, lock_path: &Path, mut refresh: F) -> Self
+    where
+        F: FnMut() -> io::Result<()> + Send + 'static,
+    {
+        let lock_path = lock_path.to_path_buf();
+        let (stop, stopped) = mpsc::channel::<()>();
+        let thread = std::thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                if let Err(e) = refresh() {
+                    warn!(
+                        "Failed to refresh lock lease {}: {}",
+                        lock_path.display(),
+                        e
+                    );
+                }
+            }
+        });
+
+        debug!("Refreshing lock lease every {:?}", interval);
+        Heartbeat {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        // Dropping the sender wakes the thread immediately
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Set the file's mtime to now
+#[cfg(unix)]
+fn touch(file: &File) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    // A null times array sets both timestamps to the current time
+    if unsafe { libc::futimens(file.as_raw_fd(), std::ptr::null()) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn touch(_file: &File) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+    use tempfile::TempDir;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_touch_refreshes_mtime() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("out.lock");
+        let file = File::create(&path).unwrap();
+        let old = SystemTime::now() - Duration::from_secs(3600);
+        filetime::set_file_mtime(&path, filetime::FileTime::from_system_time(old)).unwrap();
+
+        touch(&file).unwrap();
+        let mtime = std::fs::metadata(&path).unwrap().modified().unwrap();
+        assert!(mtime > old + Duration::from_secs(3000));
+    }
+}
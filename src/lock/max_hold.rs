@@ -0,0 +1,80 @@
+//! Maximum hold durations: a watchdog for holders expected to release their
+//! lock within a bound, so a stuck writer in a long-running service is
+//! noticed instead of silently starving everyone waiting behind it.
+//!
+//! Once a lock set up with [`FileLock::set_max_hold`](crate::FileLock::set_max_hold)
+//! has been held for longer than its maximum, a warning is logged and the
+//! handler, if any, is called once from the watchdog's thread. Nothing is
+//! released: the handler decides what a stuck holder means, for example by
+//! cancelling a [`CancelToken`](crate::CancelToken) the work checks, or
+//! aborting the process so its lock dies with it.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::warn;
+
+/// Called once when a lock outlives its maximum hold duration
+pub(crate) type HoldOverrunHandler = Box<dyn FnOnce(&HoldOverrun) + Send + 'static>;
+
+/// A lock held for longer than its maximum hold duration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HoldOverrun {
+    pub lock_path: PathBuf,
+    pub max_hold: Duration,
+}
+
+/// Background watch of a held lock's hold duration; stops when dropped
+#[derive(Debug)]
+pub(crate) struct HoldWatch {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl HoldWatch {
+    /// Report the lock at `lock_path` as overrun after `remaining`, the
+    /// part of `max_hold` it has not been held for yet
+    pub(crate) fn start(
+        lock_path: &Path,
+        max_hold: Duration,
+        remaining: Duration,
+        handler: Option<HoldOverrunHandler>,
+    ) -> Self {
+        let overrun = HoldOverrun {
+            lock_path: lock_path.to_path_buf(),
+            max_hold,
+        };
+        let (stop, stopped) = mpsc::channel::<()>();
+        let thread = std::thread::spawn(move || {
+            if let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(remaining) {
+                warn!(
+                    "Lock {} held for longer than its maximum of {:?}; its holder may be stuck",
+                    overrun.lock_path.display(),
+                    overrun.max_hold
+                );
+                if let Some(handler) = handler {
+                    handler(&overrun);
+                }
+            }
+        });
+
+        HoldWatch {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for HoldWatch {
+    fn drop(&mut self) {
+        // Dropping the sender wakes the thread immediately
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            // A handler releasing this lock would join its own thread
+            if thread.thread().id() != std::thread::current().id() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
@@ -1,5 +1,47 @@
 mod acquisition;
+#[cfg(feature = "tokio")]
+mod asynchronous;
+mod atomic_create;
+mod backend;
+mod cancel;
+#[cfg(feature = "cluster")]
+pub mod cluster;
+mod dotlock;
+mod holder;
+mod lease;
+mod max_hold;
+mod naming;
+mod ofd;
 mod path;
+mod policy;
+pub mod propagation;
+pub(crate) mod queue;
+mod range;
+mod registry;
+mod scheme;
+pub mod scope;
+mod set;
+mod status;
 
-pub use acquisition::{FileLock, LockStrategy, TimeoutConfig};
-pub use path::{derive_lock_path, get_lock_cache_dir, validate_lock_path};
+pub use acquisition::{AcquireStats, FileLock, LockRetry, LockStrategy, TimeoutConfig, MIN_LEASE};
+pub use atomic_create::ATOMIC_CREATE_STALE_AFTER;
+pub use backend::{dotlock_path, LockBackend};
+pub use cancel::CancelToken;
+pub use dotlock::DOTLOCK_STALE_AFTER;
+pub use holder::{invoker_fingerprint, LockHolder};
+pub use lease::LeaseLoss;
+pub use max_hold::HoldOverrun;
+pub use naming::{CacheDir, FlatHash, LockPathStrategy, LockPlacement, Sidecar};
+pub use path::{
+    canonical_output_path, derive_lock_path, derive_lock_path_unchecked,
+    derive_lock_path_with_scheme, ensure_lock_dir, get_lock_cache_dir, lock_cache_dir_path,
+    validate_custom_lock_path, validate_lock_path, LockFallback, LOCK_FALLBACK_ENV,
+};
+pub use policy::LockPolicy;
+pub use registry::{in_process_registry_enabled, set_in_process_registry};
+pub use scheme::{
+    is_lock_shard, lock_filename, lock_shard, parse_hash_len, LockScheme, ALGORITHM, FULL_HASH_LEN,
+    MAX_LOCK_FILENAME_BYTES, MIN_HASH_LEN, SCHEME_VERSION, SHARD_LEN,
+};
+pub use set::LockSet;
+pub use status::LockStatus;
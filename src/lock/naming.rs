@@ -0,0 +1,209 @@
+//! Where the lock file for an output lives.
+//!
+//! [`derive_lock_path`](crate::lock::derive_lock_path) always uses
+//! [`CacheDir`]. Library users that need locks elsewhere pick another
+//! [`LockPathStrategy`], or implement their own, and pass it to
+//! [`FileLock::acquire_for`](crate::FileLock::acquire_for),
+//! [`AtomicWriter::lock`](crate::AtomicWriter::lock) or
+//! [`AtomicWriterPool::with_lock_paths`](crate::AtomicWriterPool::with_lock_paths).
+//! Every process writing an output must use the same strategy, or their
+//! locks never meet.
+
+use crate::error::{MutxError, Result};
+use crate::lock::path::{canonical_output_path, derive_lock_path_unchecked};
+use crate::lock::scheme::{path_hash, LockScheme, FULL_HASH_LEN, MIN_HASH_LEN};
+use crate::lock::scope::sidecar_lock_path;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Maps an output to the lock file guarding it.
+///
+/// Implemented for closures, so `|canonical: &Path| Ok(...)` works as a
+/// custom strategy.
+pub trait LockPathStrategy: Send + Sync {
+    /// Lock file for the output at `canonical`, an absolute path with
+    /// symlinks resolved (see [`crate::lock::canonical_output_path`]).
+    /// Creates nothing.
+    fn lock_path(&self, canonical: &Path) -> Result<PathBuf>;
+}
+
+impl<F> LockPathStrategy for F
+where
+    F: Fn(&Path) -> Result<PathBuf> + Send + Sync,
+{
+    fn lock_path(&self, canonical: &Path) -> Result<PathBuf> {
+        self(canonical)
+    }
+}
+
+/// The per-user lock cache, named by [`crate::lock::ALGORITHM`] (default)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheDir {
+    scheme: LockScheme,
+}
+
+impl CacheDir {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_scheme(mut self, scheme: LockScheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+}
+
+impl LockPathStrategy for CacheDir {
+    fn lock_path(&self, canonical: &Path) -> Result<PathBuf> {
+        self.scheme.validate()?;
+        derive_lock_path_unchecked(canonical, &self.scheme)
+    }
+}
+
+/// `<output>.lock` beside the output, shared by every user who can write
+/// the directory
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Sidecar;
+
+impl LockPathStrategy for Sidecar {
+    fn lock_path(&self, canonical: &Path) -> Result<PathBuf> {
+        Ok(sidecar_lock_path(canonical))
+    }
+}
+
+/// The two places mutx itself puts derived locks: the per-user cache
+/// ([`CacheDir`], default) or beside the output ([`Sidecar`], as chosen by
+/// `mutx --lock-beside`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LockPlacement {
+    #[default]
+    Cache,
+    Sidecar,
+}
+
+impl LockPlacement {
+    /// Lock file for `output`, which need not exist yet
+    pub fn derive(&self, output: &Path) -> Result<PathBuf> {
+        self.lock_path(&canonical_output_path(output)?)
+    }
+}
+
+impl LockPathStrategy for LockPlacement {
+    fn lock_path(&self, canonical: &Path) -> Result<PathBuf> {
+        match self {
+            LockPlacement::Cache => CacheDir::new().lock_path(canonical),
+            LockPlacement::Sidecar => Sidecar.lock_path(canonical),
+        }
+    }
+}
+
+impl fmt::Display for LockPlacement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockPlacement::Cache => write!(f, "cache"),
+            LockPlacement::Sidecar => write!(f, "sidecar"),
+        }
+    }
+}
+
+impl FromStr for LockPlacement {
+    type Err = MutxError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "cache" => Ok(LockPlacement::Cache),
+            "sidecar" | "beside" => Ok(LockPlacement::Sidecar),
+            _ => Err(MutxError::Other(format!(
+                "Unknown lock placement '{}': expected one of cache, sidecar",
+                s
+            ))),
+        }
+    }
+}
+
+/// `<dir>/<hash>.lock`: the hex SHA-256 of the output path, in one flat
+/// directory such as a tmpfs shared by a group of services. The directory
+/// must already exist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlatHash {
+    dir: PathBuf,
+    hash_len: usize,
+}
+
+impl FlatHash {
+    /// Full 64 character hashes in `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        FlatHash {
+            dir: dir.into(),
+            hash_len: FULL_HASH_LEN,
+        }
+    }
+
+    /// Hex characters of the hash to keep (8-64)
+    pub fn with_hash_len(mut self, hash_len: usize) -> Self {
+        self.hash_len = hash_len;
+        self
+    }
+}
+
+impl LockPathStrategy for FlatHash {
+    fn lock_path(&self, canonical: &Path) -> Result<PathBuf> {
+        if !(MIN_HASH_LEN..=FULL_HASH_LEN).contains(&self.hash_len) {
+            return Err(MutxError::Other(format!(
+                "Lock hash length must be between {} and {}, got {}",
+                MIN_HASH_LEN, FULL_HASH_LEN, self.hash_len
+            )));
+        }
+        let hash = path_hash(canonical);
+        Ok(self.dir.join(format!("{}.lock", &hash[..self.hash_len])))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_strategies() {
+        let canonical = Path::new("/srv/app/config.json");
+
+        assert_eq!(
+            Sidecar.lock_path(canonical).unwrap(),
+            Path::new("/srv/app/config.json.lock")
+        );
+
+        let flat = FlatHash::new("/run/locks").with_hash_len(16);
+        let path = flat.lock_path(canonical).unwrap();
+        assert_eq!(path.parent().unwrap(), Path::new("/run/locks"));
+        assert_eq!(path.file_name().unwrap().len(), 16 + ".lock".len());
+        assert!(FlatHash::new("/run/locks")
+            .with_hash_len(4)
+            .lock_path(canonical)
+            .is_err());
+    }
+
+    #[test]
+    fn test_lock_placement() {
+        let canonical = Path::new("/srv/app/config.json");
+        assert_eq!(
+            LockPlacement::Sidecar.lock_path(canonical).unwrap(),
+            Path::new("/srv/app/config.json.lock")
+        );
+        assert_eq!(
+            "sidecar".parse::<LockPlacement>().unwrap(),
+            LockPlacement::Sidecar
+        );
+        assert_eq!(LockPlacement::default().to_string(), "cache");
+        assert!("home".parse::<LockPlacement>().is_err());
+    }
+
+    #[test]
+    fn test_closure_is_a_strategy() {
+        let custom = |canonical: &Path| Ok(canonical.with_extension("lck"));
+        assert_eq!(
+            custom.lock_path(Path::new("/a/b.txt")).unwrap(),
+            Path::new("/a/b.lck")
+        );
+    }
+}
@@ -0,0 +1,70 @@
+//! Open file description locks (`F_OFD_SETLK`), Linux only.
+//!
+//! Like `flock`, an OFD lock belongs to the open file description rather
+//! than the process, so it is shared by duplicated descriptors, released when
+//! the last of them closes, and two opens of the same file conflict even
+//! within one process or thread. Unlike classic POSIX record locks, closing
+//! some other descriptor for the file does not drop it. They are record
+//! locks, so they are forwarded to NFS servers like `fcntl` locks.
+//!
+//! OFD and `flock` locks do not see each other: every writer of a file must
+//! use the same backend.
+
+use std::fs::File;
+use std::io;
+
+/// Lock the whole of `file`, shared or exclusive, waiting for a conflicting
+/// lock to go away if `wait`. Contention is reported as `WouldBlock`.
+pub(crate) fn lock(file: &File, shared: bool, wait: bool) -> io::Result<()> {
+    // A zero length covers the whole file, however it grows
+    lock_range(file, 0, 0, shared, wait)
+}
+
+/// Lock `len` bytes of `file` from `offset`, like [`lock`]
+#[cfg(target_os = "linux")]
+pub(crate) fn lock_range(
+    file: &File,
+    offset: libc::off_t,
+    len: libc::off_t,
+    shared: bool,
+    wait: bool,
+) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    // SAFETY: flock is plain data; zeroed leaves l_pid = 0, as OFD locks
+    // require
+    let mut request: libc::flock = unsafe { std::mem::zeroed() };
+    request.l_type = if shared { libc::F_RDLCK } else { libc::F_WRLCK } as libc::c_short;
+    request.l_whence = libc::SEEK_SET as libc::c_short;
+    request.l_start = offset;
+    request.l_len = len;
+    let command = if wait {
+        libc::F_OFD_SETLKW
+    } else {
+        libc::F_OFD_SETLK
+    };
+
+    if unsafe { libc::fcntl(file.as_raw_fd(), command, &request) } == -1 {
+        let err = io::Error::last_os_error();
+        // A conflicting lock is reported as EAGAIN or EACCES
+        return match err.raw_os_error() {
+            Some(libc::EACCES) => Err(io::ErrorKind::WouldBlock.into()),
+            _ => Err(err),
+        };
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn lock_range(
+    _file: &File,
+    _offset: i64,
+    _len: i64,
+    _shared: bool,
+    _wait: bool,
+) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "OFD locks are only available on Linux",
+    ))
+}
@@ -1,18 +1,227 @@
+#[cfg(unix)]
+use crate::dirs::verify_private_dir;
+use crate::dirs::{
+    cache_dir, can_create, create_private_dir_all, lock_dir_override, user_temp_dir,
+    write_cachedir_tag,
+};
 use crate::error::{MutxError, Result};
-use directories::ProjectDirs;
-use sha2::{Digest, Sha256};
+use crate::lock::naming::{CacheDir, LockPathStrategy};
+use crate::lock::scheme::{is_lock_shard, lock_filename, lock_shard, LockScheme};
+use crate::lock::scope::sidecar_lock_path;
+use crate::utils::path::resolve_best_effort;
+use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tracing::{debug, warn};
 
 /// Derive the lock file path for a given output file
 pub fn derive_lock_path(output_path: &Path, is_custom: bool) -> Result<PathBuf> {
     if is_custom {
-        // Custom lock paths are used as-is, but must be validated
+        // Custom lock paths are used as-is, but must at least be a file path
+        if output_path.is_dir() {
+            return Err(MutxError::InvalidLockPath {
+                path: output_path.to_path_buf(),
+                reason: "path is a directory".to_string(),
+            });
+        }
         return Ok(output_path.to_path_buf());
     }
 
-    // Get canonical absolute path
-    let canonical = output_path.canonicalize().or_else(|_| {
+    derive_lock_path_with_scheme(output_path, &LockScheme::default())
+}
+
+/// Derive the lock file path for a given output file using a specific naming scheme.
+///
+/// Nothing is created: the cache and shard directories are made by
+/// [`ensure_lock_dir`] when a lock is actually taken there.
+pub fn derive_lock_path_with_scheme(output_path: &Path, scheme: &LockScheme) -> Result<PathBuf> {
+    scheme.validate()?;
+
+    let canonical = canonical_output_path(output_path)?;
+    CacheDir::new()
+        .with_scheme(scheme.clone())
+        .lock_path(&canonical)
+}
+
+/// Lock path for an already-canonicalized output path.
+///
+/// Like [`lock_filename`] this creates no files, so tooling that only needs
+/// the path string can call it without canonicalizing or creating the cache.
+pub fn derive_lock_path_unchecked(canonical: &Path, scheme: &LockScheme) -> Result<PathBuf> {
+    let lock_filename = lock_filename(canonical, scheme)?;
+
+    let Some(cache_dir) = locate_lock_cache(LockFallback::from_env())? else {
+        debug!(
+            "No lock cache available, locking beside {}",
+            canonical.display()
+        );
+        return Ok(sidecar_lock_path(canonical));
+    };
+
+    // Locks are spread over 256 shard directories so no single directory
+    // grows to tens of thousands of entries
+    Ok(cache_dir.join(lock_shard(canonical)).join(lock_filename))
+}
+
+/// Where derived locks go when the lock cache directory cannot be used,
+/// e.g. for systemd `DynamicUser=` services or containers without a home
+/// directory or passwd entry.
+///
+/// `MUTX_LOCK_DIR` is always tried first and the platform cache directory
+/// second. The policy is read from `MUTX_LOCK_FALLBACK`, which
+/// `--lock-fallback` sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LockFallback {
+    /// A private per-user directory in the system temp directory
+    /// (`/tmp/mutx-<uid>`), then a lock beside the output (default)
+    #[default]
+    Temp,
+    /// A lock beside the output (`<output>.lock`)
+    Adjacent,
+    /// Fail, as without a fallback
+    None,
+}
+
+impl LockFallback {
+    /// The policy named by `MUTX_LOCK_FALLBACK`, or the default
+    pub fn from_env() -> Self {
+        let Some(value) = std::env::var_os(LOCK_FALLBACK_ENV).filter(|v| !v.is_empty()) else {
+            return Self::default();
+        };
+        match value.to_string_lossy().parse() {
+            Ok(policy) => policy,
+            Err(e) => {
+                warn!("Ignoring {}: {}", LOCK_FALLBACK_ENV, e);
+                Self::default()
+            }
+        }
+    }
+}
+
+impl fmt::Display for LockFallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockFallback::Temp => write!(f, "tmp"),
+            LockFallback::Adjacent => write!(f, "adjacent"),
+            LockFallback::None => write!(f, "none"),
+        }
+    }
+}
+
+impl FromStr for LockFallback {
+    type Err = MutxError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "tmp" | "temp" => Ok(LockFallback::Temp),
+            "adjacent" => Ok(LockFallback::Adjacent),
+            "none" => Ok(LockFallback::None),
+            _ => Err(MutxError::Other(format!(
+                "Unknown lock fallback '{}': expected one of tmp, adjacent, none",
+                s
+            ))),
+        }
+    }
+}
+
+/// Environment variable holding the [`LockFallback`] policy
+pub const LOCK_FALLBACK_ENV: &str = "MUTX_LOCK_FALLBACK";
+
+/// The lock cache directory chosen by the fallback chain, or `None` when
+/// locks should go beside their outputs
+fn locate_lock_cache(fallback: LockFallback) -> Result<Option<PathBuf>> {
+    if let Some(dir) = lock_dir_override() {
+        return Ok(Some(dir));
+    }
+    let cache = cache_dir().map(|dir| dir.join("locks"));
+    if let Some(cache) = cache.as_ref().filter(|dir| can_create(dir)) {
+        return Ok(Some(cache.clone()));
+    }
+
+    if fallback == LockFallback::Temp {
+        if let Some(temp) = user_temp_dir() {
+            debug!("Lock cache unavailable, using {}", temp.display());
+            return Ok(Some(temp.join("locks")));
+        }
+    }
+    match (fallback, cache) {
+        (LockFallback::None, Some(cache)) => Ok(Some(cache)),
+        (LockFallback::None, None) => Err(MutxError::Other(
+            "Failed to determine lock cache directory. \
+                 Try specifying an explicit directory with the DIR argument."
+                .to_string(),
+        )),
+        _ => Ok(None),
+    }
+}
+
+/// Create the cache shard directory for a derived lock path, and move a
+/// scheme v1 lock file for it into place.
+///
+/// Paths outside the lock cache (custom `--lock-file` paths, dotlocks) are
+/// left alone: their directories are never created implicitly.
+pub fn ensure_lock_dir(lock_path: &Path) -> Result<()> {
+    let Ok(Some(cache_dir)) = locate_lock_cache(LockFallback::from_env()) else {
+        return Ok(());
+    };
+    let Some(shard_dir) = lock_path.parent() else {
+        return Ok(());
+    };
+    let in_cache = shard_dir.parent() == Some(cache_dir.as_path())
+        && shard_dir
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(is_lock_shard);
+    if !in_cache {
+        return Ok(());
+    }
+
+    if !shard_dir.exists() {
+        create_cache_dir(&cache_dir)?;
+        fs::create_dir_all(shard_dir).map_err(|e| MutxError::CacheDirectoryFailed {
+            path: shard_dir.to_path_buf(),
+            source: e,
+        })?;
+        tag_cache_dir(&cache_dir);
+    }
+    if let Some(name) = lock_path.file_name() {
+        migrate_flat_lock(&cache_dir.join(name), lock_path);
+    }
+    Ok(())
+}
+
+/// Link a lock file left at its scheme v1 location (directly in the cache
+/// directory) into its shard.
+///
+/// The old name is kept: older releases still lock it, and as both names
+/// share one inode they keep excluding anyone locking the new one. If the
+/// shard already has a lock file the old one is left for `housekeep locks`.
+fn migrate_flat_lock(flat: &Path, sharded: &Path) {
+    if sharded.exists() || !flat.is_file() {
+        return;
+    }
+    match fs::hard_link(flat, sharded) {
+        Ok(()) => debug!(
+            "Linked lock file {} to {}",
+            flat.display(),
+            sharded.display()
+        ),
+        // Another process migrated it first, or it was released and removed
+        Err(e)
+            if matches!(
+                e.kind(),
+                std::io::ErrorKind::AlreadyExists | std::io::ErrorKind::NotFound
+            ) => {}
+        Err(e) => warn!("Failed to link lock file {}: {}", flat.display(), e),
+    }
+}
+
+/// Resolve an output path to the canonical absolute path used for lock naming.
+///
+/// The output itself may not exist yet; its parent directory must.
+pub fn canonical_output_path(output_path: &Path) -> Result<PathBuf> {
+    output_path.canonicalize().or_else(|_| {
         // If file doesn't exist yet, canonicalize parent and append filename
         let parent = output_path
             .parent()
@@ -30,67 +239,7 @@ pub fn derive_lock_path(output_path: &Path, is_custom: bool) -> Result<PathBuf>
             .ok_or_else(|| MutxError::Other("Output path has no filename".to_string()))?;
 
         Ok(parent_canonical.join(filename))
-    })?;
-
-    // Extract path components
-    let components: Vec<_> = canonical.components().collect();
-
-    // Get filename
-    let filename = canonical
-        .file_name()
-        .ok_or_else(|| MutxError::Other("Output path has no filename".to_string()))?
-        .to_str()
-        .ok_or_else(|| MutxError::Other("Non-UTF8 filename".to_string()))?;
-
-    // Get parent directory name
-    let parent_name = canonical
-        .parent()
-        .and_then(|p| p.file_name())
-        .and_then(|n| n.to_str())
-        .unwrap_or("root");
-
-    // Build initialism from ancestor directories (excluding parent and filename)
-    // Limit to last 3 ancestors for readability (hash provides uniqueness)
-    let mut initialism = String::new();
-    if components.len() > 2 {
-        // Parent is at components.len() - 2 (filename is at components.len() - 1)
-        // Get up to 3 ancestors before parent (for human readability)
-        let parent_idx = components.len() - 2;
-        let start_idx = if parent_idx > 3 {
-            parent_idx - 3 // Last 3 ancestors before parent
-        } else {
-            1 // Start after root
-        };
-
-        for component in &components[start_idx..parent_idx] {
-            if let Some(name) = component.as_os_str().to_str() {
-                if let Some(first_char) = name.chars().next() {
-                    if first_char.is_alphanumeric() {
-                        initialism.push(first_char.to_ascii_lowercase());
-                        initialism.push('.');
-                    }
-                }
-            }
-        }
-    }
-
-    // Compute hash of canonical path
-    let mut hasher = Sha256::new();
-    hasher.update(canonical.to_string_lossy().as_bytes());
-    let hash_bytes = hasher.finalize();
-    let hash = format!("{:x}", hash_bytes);
-    let hash_short = &hash[..8];
-
-    // Build lock filename: {initialism}{parent}.{filename}.{hash}.lock
-    let lock_filename = format!(
-        "{}{}.{}.{}.lock",
-        initialism, parent_name, filename, hash_short
-    );
-
-    // Get platform cache directory
-    let cache_dir = get_lock_cache_dir()?;
-
-    Ok(cache_dir.join(lock_filename))
+    })
 }
 
 /// Get the platform-specific cache directory for lock files.
@@ -111,25 +260,50 @@ pub fn derive_lock_path(output_path: &Path, is_custom: bool) -> Result<PathBuf>
 /// chmod 755 ~/.cache  # Restore
 /// ```
 pub fn get_lock_cache_dir() -> Result<PathBuf> {
-    let proj_dirs = ProjectDirs::from("", "", "mutx").ok_or_else(|| {
-        MutxError::Other(
-            "Failed to determine lock cache directory. \
-                 Try specifying an explicit directory with the DIR argument."
-                .to_string(),
-        )
-    })?;
+    let cache_dir = lock_cache_dir_path()?;
+    create_cache_dir(&cache_dir)?;
+    tag_cache_dir(&cache_dir);
 
-    let cache_dir = proj_dirs.cache_dir().join("locks");
+    Ok(cache_dir)
+}
 
-    // Create directory if it doesn't exist
+/// Create the lock cache directory if it doesn't exist. The per-user temp
+/// fallback is created private and checked again afterwards, in case someone
+/// else made it first.
+fn create_cache_dir(cache_dir: &Path) -> Result<()> {
+    let failed = |e| MutxError::CacheDirectoryFailed {
+        path: cache_dir.to_path_buf(),
+        source: e,
+    };
+    if let Some(temp) = user_temp_dir().filter(|temp| cache_dir.starts_with(temp)) {
+        create_private_dir_all(&temp).map_err(failed)?;
+        #[cfg(unix)]
+        verify_private_dir(&temp).map_err(failed)?;
+    }
     if !cache_dir.exists() {
-        fs::create_dir_all(&cache_dir).map_err(|e| MutxError::CacheDirectoryFailed {
-            path: cache_dir.clone(),
-            source: e,
-        })?;
+        fs::create_dir_all(cache_dir).map_err(failed)?;
     }
+    Ok(())
+}
 
-    Ok(cache_dir)
+/// Mark the lock cache so backup tools skip it; failure only costs backup space
+fn tag_cache_dir(cache_dir: &Path) {
+    if let Err(e) = write_cachedir_tag(cache_dir) {
+        debug!("Failed to tag lock cache {}: {}", cache_dir.display(), e);
+    }
+}
+
+/// Location of the lock cache directory, without creating it. With the
+/// [`LockFallback::Adjacent`] policy and no usable cache this is an error,
+/// as there is no single directory holding the locks.
+pub fn lock_cache_dir_path() -> Result<PathBuf> {
+    locate_lock_cache(LockFallback::from_env())?.ok_or_else(|| {
+        MutxError::Other(
+            "No usable lock cache directory; locks are kept beside their outputs. \
+                 Try specifying an explicit directory with the DIR argument."
+                .to_string(),
+        )
+    })
 }
 
 /// Validate that lock path doesn't equal output path
@@ -153,6 +327,73 @@ pub fn validate_lock_path(lock_path: &Path, output_path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Validate a user-supplied lock file path against the output it protects.
+///
+/// Rejects directories and lock paths nested underneath the output file. A lock
+/// file without a `.lock` extension only produces a warning, unless `lock_root`
+/// is given: wrapper and setuid deployments use the root to pin lock files to a
+/// single trusted directory, so there the extension is mandatory and the
+/// resolved lock path (after following symlinked parents) must stay inside it.
+pub fn validate_custom_lock_path(
+    lock_path: &Path,
+    output_path: &Path,
+    lock_root: Option<&Path>,
+) -> Result<()> {
+    if lock_path.is_dir() {
+        return Err(MutxError::InvalidLockPath {
+            path: lock_path.to_path_buf(),
+            reason: "path is a directory".to_string(),
+        });
+    }
+
+    let lock_resolved = resolve_best_effort(lock_path)?;
+    let output_resolved = resolve_best_effort(output_path)?;
+
+    if lock_resolved != output_resolved && lock_resolved.starts_with(&output_resolved) {
+        return Err(MutxError::InvalidLockPath {
+            path: lock_path.to_path_buf(),
+            reason: format!("path is inside the output file {}", output_path.display()),
+        });
+    }
+
+    let has_lock_extension = lock_path.extension().and_then(|e| e.to_str()) == Some("lock");
+
+    match lock_root {
+        Some(root) => {
+            if !has_lock_extension {
+                return Err(MutxError::InvalidLockPath {
+                    path: lock_path.to_path_buf(),
+                    reason: "a .lock extension is required when --lock-root is set".to_string(),
+                });
+            }
+
+            let root_canonical = root
+                .canonicalize()
+                .map_err(|_| MutxError::NotADirectory(root.to_path_buf()))?;
+            if !root_canonical.is_dir() {
+                return Err(MutxError::NotADirectory(root.to_path_buf()));
+            }
+
+            if !lock_resolved.starts_with(&root_canonical) {
+                return Err(MutxError::LockPathOutsideRoot {
+                    path: lock_path.to_path_buf(),
+                    root: root.to_path_buf(),
+                });
+            }
+        }
+        None => {
+            if !has_lock_extension {
+                warn!(
+                    "Custom lock file {} does not use a .lock extension",
+                    lock_path.display()
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
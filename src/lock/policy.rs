@@ -0,0 +1,88 @@
+//! Whether a write is locked at all.
+
+use crate::error::Result;
+use crate::lock::acquisition::{FileLock, LockStrategy};
+use crate::lock::backend::LockBackend;
+use crate::lock::path::derive_lock_path;
+use std::path::Path;
+use tracing::warn;
+
+/// Whether to take a lock for a write, and how to wait for it
+#[derive(Debug, Clone)]
+pub enum LockPolicy {
+    /// Lock the output, waiting as the strategy says (default: wait forever)
+    Lock(LockStrategy),
+    /// Take no lock, for callers that exclude other writers by other means.
+    /// The write stays atomic, but nothing is derived or created in the lock
+    /// cache, and writers must report `exclusive: false` (see
+    /// [`crate::AtomicWriter::with_exclusive`]).
+    None,
+}
+
+impl Default for LockPolicy {
+    fn default() -> Self {
+        LockPolicy::Lock(LockStrategy::Wait)
+    }
+}
+
+impl From<LockStrategy> for LockPolicy {
+    fn from(strategy: LockStrategy) -> Self {
+        LockPolicy::Lock(strategy)
+    }
+}
+
+impl LockPolicy {
+    /// Whether a lock is taken
+    pub fn locks(&self) -> bool {
+        matches!(self, LockPolicy::Lock(_))
+    }
+
+    /// The waiting strategy, unless no lock is taken
+    pub fn strategy(&self) -> Option<&LockStrategy> {
+        match self {
+            LockPolicy::Lock(strategy) => Some(strategy),
+            LockPolicy::None => None,
+        }
+    }
+
+    /// Acquire the default lock for `output` with `backend`, or nothing under
+    /// [`LockPolicy::None`], which logs a warning instead
+    pub fn acquire_for(&self, output: &Path, backend: LockBackend) -> Result<Option<FileLock>> {
+        let LockPolicy::Lock(strategy) = self else {
+            warn!(
+                "Not locking {}: concurrent writers are not excluded",
+                output.display()
+            );
+            return Ok(None);
+        };
+        let lock_path = match backend.default_lock_path(output) {
+            Some(path) => path,
+            None => derive_lock_path(output, false)?,
+        };
+        FileLock::acquire_with_backend(&lock_path, strategy.clone(), backend).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_none_takes_no_lock() {
+        let temp = TempDir::new().unwrap();
+        let output = temp.path().join("out.txt");
+
+        let lock = LockPolicy::None
+            .acquire_for(&output, LockBackend::Dotlock)
+            .unwrap();
+        assert!(lock.is_none());
+        assert!(!temp.path().join("out.txt.lock").exists());
+
+        let lock = LockPolicy::from(LockStrategy::NoWait)
+            .acquire_for(&output, LockBackend::Dotlock)
+            .unwrap();
+        assert!(lock.is_some());
+        assert!(temp.path().join("out.txt.lock").exists());
+    }
+}
@@ -0,0 +1,224 @@
+//! Detection of filesystems where `flock` may not provide real exclusion.
+//!
+//! On network and FUSE filesystems `flock` is often implemented locally on
+//! each client (or not at all), so two hosts both "acquire" the lock. On
+//! overlayfs each container gets its own upper layer, so a lock file in one
+//! container's overlay is invisible to every other container. These checks
+//! let mutx fail loudly (`--strict-locking`, `mutx doctor`) instead of
+//! silently providing false mutual exclusion.
+
+use crate::error::{MutxError, Result};
+use crate::lock::scope::{in_container, lock_scope_warning};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// How far a `flock` on a given filesystem can be trusted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlockSupport {
+    /// Local filesystem with kernel-wide flock semantics
+    Reliable { fs_type: String },
+    /// Flock works, but only among processes sharing this mount (overlayfs):
+    /// writers in other containers or mount namespaces are not excluded
+    MountLocal { fs_type: String },
+    /// Network or FUSE filesystem where flock may not propagate between clients
+    Unreliable { fs_type: String },
+    /// Filesystem type could not be determined on this platform
+    Unknown,
+}
+
+impl FlockSupport {
+    pub fn fs_type(&self) -> Option<&str> {
+        match self {
+            FlockSupport::Reliable { fs_type }
+            | FlockSupport::MountLocal { fs_type }
+            | FlockSupport::Unreliable { fs_type } => Some(fs_type),
+            FlockSupport::Unknown => None,
+        }
+    }
+}
+
+impl fmt::Display for FlockSupport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlockSupport::Reliable { fs_type } => write!(f, "reliable ({})", fs_type),
+            FlockSupport::MountLocal { fs_type } => write!(
+                f,
+                "local to this mount ({}): other containers are not excluded",
+                fs_type
+            ),
+            FlockSupport::Unreliable { fs_type } => write!(
+                f,
+                "unreliable ({}): flock may not propagate between clients",
+                fs_type
+            ),
+            FlockSupport::Unknown => write!(f, "unknown filesystem type"),
+        }
+    }
+}
+
+/// Classify a filesystem type name as reported by `/proc/self/mountinfo`
+pub fn classify_fs_type(fs_type: &str) -> FlockSupport {
+    let fs_type = fs_type.to_string();
+    let base = fs_type.split('.').next().unwrap_or_default();
+
+    match base {
+        "overlay" | "aufs" => FlockSupport::MountLocal { fs_type },
+        "nfs" | "nfs4" | "cifs" | "smb3" | "smbfs" | "fuse" | "fuseblk" | "9p" | "afs" | "ceph"
+        | "glusterfs" | "lustre" | "vboxsf" | "virtiofs" => FlockSupport::Unreliable { fs_type },
+        _ => FlockSupport::Reliable { fs_type },
+    }
+}
+
+/// Determine how reliable `flock` is for files in `path` (a file or directory)
+pub fn flock_support(path: &Path) -> FlockSupport {
+    let Ok(mountinfo) = std::fs::read_to_string("/proc/self/mountinfo") else {
+        return FlockSupport::Unknown;
+    };
+
+    // Resolve the nearest existing ancestor so not-yet-created files work
+    let mut probe = path.to_path_buf();
+    let resolved = loop {
+        if let Ok(canonical) = probe.canonicalize() {
+            break canonical;
+        }
+        if !probe.pop() {
+            return FlockSupport::Unknown;
+        }
+    };
+
+    match fs_type_from_mountinfo(&mountinfo, &resolved) {
+        Some(fs_type) => classify_fs_type(&fs_type),
+        None => FlockSupport::Unknown,
+    }
+}
+
+/// Fail if a flock on `lock_path` cannot be trusted to exclude other writers of `output`.
+///
+/// Network and FUSE filesystems always fail. Overlay filesystems and any
+/// container whose lock directory is on a different filesystem than the
+/// output fail when the lock would be private to this container.
+pub fn check_lock_propagation(lock_path: &Path, output: &Path) -> Result<()> {
+    let lock_dir = match lock_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    let support = flock_support(lock_dir);
+    let problem = match &support {
+        FlockSupport::Unreliable { .. } => Some(support.to_string()),
+        FlockSupport::MountLocal { .. } => lock_scope_warning(lock_dir, output, true),
+        _ => lock_scope_warning(lock_dir, output, in_container()),
+    };
+
+    match problem {
+        Some(reason) => Err(MutxError::UnreliableLocking {
+            path: lock_path.to_path_buf(),
+            reason,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Find the filesystem type of the mount containing `path` in mountinfo text.
+///
+/// Picks the longest matching mount point; among equal mount points the last
+/// entry wins, since later mounts shadow earlier ones.
+pub fn fs_type_from_mountinfo(mountinfo: &str, path: &Path) -> Option<String> {
+    let mut best: Option<(usize, String)> = None;
+
+    for line in mountinfo.lines() {
+        let mut halves = line.splitn(2, " - ");
+        let (Some(pre), Some(post)) = (halves.next(), halves.next()) else {
+            continue;
+        };
+        let Some(mount_point) = pre.split(' ').nth(4) else {
+            continue;
+        };
+        let Some(fs_type) = post.split(' ').next() else {
+            continue;
+        };
+
+        let mount_point = PathBuf::from(unescape_mountinfo(mount_point));
+        if path.starts_with(&mount_point) {
+            let depth = mount_point.components().count();
+            if best.as_ref().map_or(true, |(d, _)| depth >= *d) {
+                best = Some((depth, fs_type.to_string()));
+            }
+        }
+    }
+
+    best.map(|(_, fs_type)| fs_type)
+}
+
+/// Decode the octal escapes (`\040` for space, etc.) used in mountinfo paths
+fn unescape_mountinfo(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' {
+            let octal = bytes
+                .get(i + 1..i + 4)
+                .filter(|d| d.iter().all(|b| (b'0'..=b'7').contains(b)));
+            if let Some(digits) = octal {
+                let value = digits
+                    .iter()
+                    .fold(0u32, |acc, b| acc * 8 + u32::from(b - b'0'));
+                out.push(value as u8);
+                i += 4;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MOUNTINFO: &str = "\
+28 1 254:0 / / rw,relatime - ext4 /dev/vda rw
+26 25 0:24 / /dev/shm rw,relatime - tmpfs tmpfs rw
+31 26 0:27 / /dev/shm rw,relatime - overlay overlay rw
+50 28 0:50 / /mnt/shared\\040data rw,relatime - nfs4 server:/export rw
+";
+
+    #[test]
+    fn test_longest_and_latest_mount_wins() {
+        assert_eq!(
+            fs_type_from_mountinfo(MOUNTINFO, Path::new("/home/user")).as_deref(),
+            Some("ext4")
+        );
+        assert_eq!(
+            fs_type_from_mountinfo(MOUNTINFO, Path::new("/dev/shm/x")).as_deref(),
+            Some("overlay")
+        );
+        assert_eq!(
+            fs_type_from_mountinfo(MOUNTINFO, Path::new("/mnt/shared data/f")).as_deref(),
+            Some("nfs4")
+        );
+    }
+
+    #[test]
+    fn test_classification() {
+        assert!(matches!(
+            classify_fs_type("ext4"),
+            FlockSupport::Reliable { .. }
+        ));
+        assert!(matches!(
+            classify_fs_type("overlay"),
+            FlockSupport::MountLocal { .. }
+        ));
+        assert!(matches!(
+            classify_fs_type("fuse.sshfs"),
+            FlockSupport::Unreliable { .. }
+        ));
+        assert!(matches!(
+            classify_fs_type("nfs4"),
+            FlockSupport::Unreliable { .. }
+        ));
+    }
+}
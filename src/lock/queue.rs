@@ -0,0 +1,299 @@
+//! Arrival-order queue for [`LockStrategy::Fair`](crate::LockStrategy).
+//!
+//! Waiters draw increasing sequence numbers from the [`StateFile`]
+//! `<lock>.queue/seq` under an exclusive lock on `<lock>.queue/lock`, the
+//! queue's counter lock, and each publishes a ticket file
+//! `<lock>.queue/<seq>.<pid>`, which it keeps locked for as long as it waits.
+//! Only the waiter holding the lowest live ticket tries the lock itself. A
+//! ticket whose file can be locked by someone else belongs to a waiter that
+//! exited without cleaning up, and is removed by whoever finds it.
+//!
+//! Tickets are drawn and published under the counter's lock, so the last
+//! waiter to leave can take that lock, find the queue empty and remove it
+//! (as `mutx housekeep locks` also does). A waiter that then locks the
+//! counter lock it opened finds it unlinked and starts over with a new queue.
+
+use crate::error::{MutxError, Result};
+use crate::statefile::StateFile;
+use crate::utils::{apply_nofollow, unique_temp_path};
+use fs2::FileExt;
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// A place in the queue for one lock; leaves the queue when dropped
+#[derive(Debug)]
+pub(crate) struct Ticket {
+    dir: PathBuf,
+    path: PathBuf,
+    seq: u64,
+    #[allow(dead_code)]
+    file: File,
+}
+
+impl Ticket {
+    /// Join the back of the queue for `lock_path`
+    pub(crate) fn take(lock_path: &Path) -> Result<Self> {
+        let dir = queue_dir(lock_path);
+        let failed = |e| MutxError::LockCreationFailed {
+            path: dir.clone(),
+            source: e,
+        };
+        let counter = loop {
+            match fs::create_dir(&dir) {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(failed(e)),
+            }
+            match lock_counter(&dir, true) {
+                Ok(Some(counter)) => break counter,
+                // Removed by the last waiter to leave since we created it
+                Ok(None) => continue,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(failed(e)),
+            }
+        };
+        let seq = StateFile::new(dir.join("seq")).next_generation()?;
+        let path = dir.join(format!("{:020}.{}", seq, std::process::id()));
+
+        // Lock the ticket under a temporary name and then publish it, so no
+        // one sees an unlocked ticket and takes it for abandoned
+        let temp = unique_temp_path(&path);
+        let mut opts = OpenOptions::new();
+        opts.write(true).create_new(true);
+        apply_nofollow(&mut opts);
+        let file = opts.open(&temp).map_err(failed)?;
+        let published = FileExt::try_lock_exclusive(&file).and_then(|_| fs::rename(&temp, &path));
+        if let Err(e) = published {
+            let _ = fs::remove_file(&temp);
+            return Err(failed(e));
+        }
+        drop(counter);
+
+        debug!("Queued for lock {} as #{}", lock_path.display(), seq);
+        Ok(Ticket {
+            dir,
+            path,
+            seq,
+            file,
+        })
+    }
+
+    /// Whether every waiter that arrived earlier has left the queue, so this
+    /// one may try the lock. Removes tickets of waiters that have exited.
+    pub(crate) fn is_next(&self) -> Result<bool> {
+        let entries = fs::read_dir(&self.dir).map_err(|e| MutxError::LockAcquisitionFailed {
+            path: self.dir.clone(),
+            source: e,
+        })?;
+        let mut next = true;
+        for entry in entries.flatten() {
+            let earlier = ticket_seq(&entry.file_name()).is_some_and(|seq| seq < self.seq);
+            if earlier && !remove_if_abandoned(&entry.path()) {
+                next = false;
+            }
+        }
+        Ok(next)
+    }
+}
+
+impl Drop for Ticket {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+        match remove_idle(&self.dir, false) {
+            Ok(true) => debug!("Removed empty lock queue: {}", self.dir.display()),
+            Ok(false) => {}
+            Err(e) => debug!("Keeping lock queue {}: {}", self.dir.display(), e),
+        }
+    }
+}
+
+/// The queue directory beside `lock_path`
+pub(crate) fn queue_dir(lock_path: &Path) -> PathBuf {
+    let mut dir = OsString::from(lock_path.as_os_str());
+    dir.push(".queue");
+    PathBuf::from(dir)
+}
+
+/// Sequence number of a published ticket file name
+fn ticket_seq(name: &std::ffi::OsStr) -> Option<u64> {
+    let (seq, pid) = name.to_str()?.split_once('.')?;
+    pid.parse::<u32>().ok()?;
+    seq.parse().ok()
+}
+
+/// Lock the counter lock of the queue in `dir`, waiting for it if `wait`.
+/// `None` if it was busy, or was removed with the queue before we locked it.
+fn lock_counter(dir: &Path, wait: bool) -> io::Result<Option<File>> {
+    let path = dir.join("lock");
+    let mut opts = OpenOptions::new();
+    opts.read(true).write(true).create(wait).truncate(false);
+    apply_nofollow(&mut opts);
+    let file = opts.open(&path)?;
+    if wait {
+        FileExt::lock_exclusive(&file)?;
+    } else if FileExt::try_lock_exclusive(&file).is_err() {
+        return Ok(None);
+    }
+    Ok(is_linked(&file, &path).then_some(file))
+}
+
+/// Whether `file` is still the file at `path`
+#[cfg(unix)]
+fn is_linked(file: &File, path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (file.metadata(), fs::symlink_metadata(path)) {
+        (Ok(held), Ok(current)) => held.dev() == current.dev() && held.ino() == current.ino(),
+        _ => false,
+    }
+}
+
+/// Windows cannot remove an open file, so the counter is never unlinked
+/// under a waiter
+#[cfg(not(unix))]
+fn is_linked(_file: &File, path: &Path) -> bool {
+    path.exists()
+}
+
+/// Remove the queue directory `dir` if no waiter is in it or joining it,
+/// along with the tickets of waiters that exited. Returns whether it is (or,
+/// with `dry_run`, would be) gone.
+pub(crate) fn remove_idle(dir: &Path, dry_run: bool) -> io::Result<bool> {
+    let counter = match lock_counter(dir, false) {
+        Ok(Some(counter)) => counter,
+        Ok(None) => return Ok(false),
+        // Already removed, or never held a ticket
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(!dir.exists()),
+        Err(e) => return Err(e),
+    };
+
+    let mut leftovers = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if name == "lock" {
+            continue;
+        }
+        if ticket_seq(&name).is_some() && !is_abandoned(&entry.path()) {
+            return Ok(false);
+        }
+        // The sequence counter, abandoned tickets, and temporaries of waiters
+        // that exited while publishing one, as none is published without the
+        // counter lock
+        leftovers.push(entry.path());
+    }
+    if dry_run {
+        return Ok(true);
+    }
+
+    for path in leftovers {
+        fs::remove_file(path)?;
+    }
+    fs::remove_file(dir.join("lock"))?;
+    fs::remove_dir(dir)?;
+    drop(counter);
+    Ok(true)
+}
+
+/// Whether no waiter holds the ticket at `path` any more
+fn is_abandoned(path: &Path) -> bool {
+    let Ok(file) = OpenOptions::new().write(true).open(path) else {
+        // Left the queue since we listed it
+        return true;
+    };
+    FileExt::try_lock_exclusive(&file).is_ok()
+}
+
+/// Remove the ticket at `path` if no waiter holds it any more. Returns
+/// whether it is gone.
+fn remove_if_abandoned(path: &Path) -> bool {
+    if !is_abandoned(path) {
+        return false;
+    }
+    debug!("Removing abandoned queue ticket: {}", path.display());
+    let _ = fs::remove_file(path);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_tickets_are_served_in_order() {
+        let temp = TempDir::new().unwrap();
+        let lock_path = temp.path().join("out.lock");
+
+        let first = Ticket::take(&lock_path).unwrap();
+        let second = Ticket::take(&lock_path).unwrap();
+        assert!(second.seq > first.seq);
+        assert!(first.is_next().unwrap());
+        assert!(!second.is_next().unwrap());
+
+        drop(first);
+        assert!(second.is_next().unwrap());
+    }
+
+    #[test]
+    fn test_abandoned_ticket_is_removed() {
+        let temp = TempDir::new().unwrap();
+        let lock_path = temp.path().join("out.lock");
+        let abandoned = queue_dir(&lock_path).join(format!("{:020}.1", 0));
+
+        let ticket = Ticket::take(&lock_path).unwrap();
+        fs::write(&abandoned, b"").unwrap();
+        assert!(ticket.is_next().unwrap());
+        assert!(!abandoned.exists());
+    }
+
+    #[test]
+    fn test_last_waiter_removes_queue() {
+        let temp = TempDir::new().unwrap();
+        let lock_path = temp.path().join("out.lock");
+
+        let first = Ticket::take(&lock_path).unwrap();
+        let second = Ticket::take(&lock_path).unwrap();
+        drop(first);
+        assert!(queue_dir(&lock_path).join("seq").exists());
+
+        drop(second);
+        assert!(!queue_dir(&lock_path).exists());
+        assert!(Ticket::take(&lock_path).unwrap().is_next().unwrap());
+    }
+
+    #[test]
+    fn test_busy_queue_is_kept() {
+        let temp = TempDir::new().unwrap();
+        let lock_path = temp.path().join("out.lock");
+
+        let ticket = Ticket::take(&lock_path).unwrap();
+        assert!(!remove_idle(&queue_dir(&lock_path), false).unwrap());
+        assert!(ticket.is_next().unwrap());
+    }
+
+    #[test]
+    fn test_corrupt_counter_is_an_error() {
+        let temp = TempDir::new().unwrap();
+        let lock_path = temp.path().join("out.lock");
+        let dir = queue_dir(&lock_path);
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("seq"), "garbage").unwrap();
+
+        assert!(Ticket::take(&lock_path).is_err());
+        assert_eq!(fs::read_to_string(dir.join("seq")).unwrap(), "garbage");
+    }
+
+    #[test]
+    fn test_other_names_are_ignored() {
+        assert_eq!(ticket_seq("00000000000000000042.77".as_ref()), Some(42));
+        assert_eq!(ticket_seq("seq".as_ref()), None);
+        assert_eq!(
+            ticket_seq(".00000000000000000042.77-x.mutx.tmp".as_ref()),
+            None
+        );
+    }
+}
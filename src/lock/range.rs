@@ -0,0 +1,124 @@
+//! Byte-range locks on part of a file, for writers that update disjoint
+//! regions of one large file in place.
+//!
+//! Linux uses OFD record locks, which belong to the open file like `flock`
+//! locks. Other Unix systems fall back to classic `fcntl` record locks: they
+//! belong to the process, so threads of one process do not exclude each
+//! other, and closing *any* descriptor of the file releases them. Windows
+//! uses `LockFileEx`, whose locks are mandatory for reads and writes through
+//! other handles.
+
+use std::fs::File;
+use std::io;
+
+/// Lock `len` bytes of `file` from `offset`, shared or exclusive, waiting
+/// for a conflicting lock to go away if `wait`. Contention is reported as
+/// `WouldBlock`. The range must fit an `i64` end offset.
+#[cfg(target_os = "linux")]
+pub(crate) fn lock(file: &File, offset: u64, len: u64, shared: bool, wait: bool) -> io::Result<()> {
+    let (offset, len) = (offset as libc::off_t, len as libc::off_t);
+    crate::lock::ofd::lock_range(file, offset, len, shared, wait)
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+pub(crate) fn lock(file: &File, offset: u64, len: u64, shared: bool, wait: bool) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    // SAFETY: flock is plain data
+    let mut request: libc::flock = unsafe { std::mem::zeroed() };
+    request.l_type = if shared { libc::F_RDLCK } else { libc::F_WRLCK } as libc::c_short;
+    request.l_whence = libc::SEEK_SET as libc::c_short;
+    request.l_start = offset as libc::off_t;
+    request.l_len = len as libc::off_t;
+    let command = if wait { libc::F_SETLKW } else { libc::F_SETLK };
+
+    if unsafe { libc::fcntl(file.as_raw_fd(), command, &request) } == -1 {
+        let err = io::Error::last_os_error();
+        // A conflicting lock is reported as EAGAIN or EACCES
+        return match err.raw_os_error() {
+            Some(libc::EACCES) => Err(io::ErrorKind::WouldBlock.into()),
+            _ => Err(err),
+        };
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+pub(crate) fn lock(file: &File, offset: u64, len: u64, shared: bool, wait: bool) -> io::Result<()> {
+    use std::ffi::c_void;
+    use std::os::windows::io::AsRawHandle;
+
+    const LOCKFILE_FAIL_IMMEDIATELY: u32 = 0x0000_0001;
+    const LOCKFILE_EXCLUSIVE_LOCK: u32 = 0x0000_0002;
+    /// `ERROR_LOCK_VIOLATION`: part of the range is locked by someone else
+    const ERROR_LOCK_VIOLATION: i32 = 33;
+
+    #[repr(C)]
+    struct Overlapped {
+        internal: usize,
+        internal_high: usize,
+        offset: u32,
+        offset_high: u32,
+        event: *mut c_void,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn LockFileEx(
+            file: *mut c_void,
+            flags: u32,
+            reserved: u32,
+            len_low: u32,
+            len_high: u32,
+            overlapped: *mut Overlapped,
+        ) -> i32;
+    }
+
+    let mut flags = 0;
+    if !shared {
+        flags |= LOCKFILE_EXCLUSIVE_LOCK;
+    }
+    if !wait {
+        flags |= LOCKFILE_FAIL_IMMEDIATELY;
+    }
+    let mut overlapped = Overlapped {
+        internal: 0,
+        internal_high: 0,
+        offset: offset as u32,
+        offset_high: (offset >> 32) as u32,
+        event: std::ptr::null_mut(),
+    };
+
+    let ok = unsafe {
+        LockFileEx(
+            file.as_raw_handle() as *mut c_void,
+            flags,
+            0,
+            len as u32,
+            (len >> 32) as u32,
+            &mut overlapped,
+        )
+    };
+    if ok == 0 {
+        let err = io::Error::last_os_error();
+        return match err.raw_os_error() {
+            Some(ERROR_LOCK_VIOLATION) => Err(io::ErrorKind::WouldBlock.into()),
+            _ => Err(err),
+        };
+    }
+    Ok(())
+}
+
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn lock(
+    _file: &File,
+    _offset: u64,
+    _len: u64,
+    _shared: bool,
+    _wait: bool,
+) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Byte-range locks are not available on this platform",
+    ))
+}
@@ -0,0 +1,230 @@
+//! In-process registry of held locks, so threads of one process exclude
+//! each other as reliably as separate processes do.
+//!
+//! Whether two threads opening the same lock file exclude each other
+//! depends on the platform and backend: `fcntl` record locks (which `flock`
+//! becomes on some NFS clients) belong to the process, so a second thread
+//! is granted a lock the first already holds. Every acquisition therefore
+//! first claims the lock's canonical path here, and only touches the
+//! filesystem once no other thread holds it. Shared claims coexist with
+//! each other, like shared locks.
+//!
+//! A thread acquiring a lock it already holds exclusively waits for itself,
+//! as it would on the lock file, unless the strategy is
+//! [`LockStrategy::reentrant`](crate::LockStrategy::reentrant): then it gets
+//! a nested handle at once, and the lock stays held until the last handle
+//! is dropped, in whatever order.
+//!
+//! A strategy that breaks stale locks may take over a claim whose lock
+//! file names a holder it would break, such as a hung thread whose lease
+//! expired; the hung thread's handles then release nothing.
+//!
+//! [`set_in_process_registry`] turns the registry off for the whole
+//! process, for applications that coordinate their own threads or
+//! deliberately share one lock between them.
+
+use crate::lock::acquisition::FileLock;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::thread::{self, ThreadId};
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Source of [`Entry::generation`]s, unique for the life of the process
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+static HELD: OnceLock<Mutex<HashMap<PathBuf, Entry>>> = OnceLock::new();
+
+/// Claim locks in the in-process registry before taking them (the
+/// default), or not. Turning it off restores the platform's own behavior
+/// between threads; locks already claimed are still released normally.
+pub fn set_in_process_registry(enabled: bool) {
+    ENABLED.store(enabled, Ordering::SeqCst);
+}
+
+/// Whether acquisitions claim locks in the in-process registry
+pub fn in_process_registry_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+#[derive(Debug)]
+struct Entry {
+    /// Changed when the claim is taken over, so the handles it was taken
+    /// from give up nothing when dropped
+    generation: u64,
+    /// Thread holding the lock exclusively, and how many handles it has
+    owner: Option<ThreadId>,
+    depth: usize,
+    /// Handles holding the lock shared, in any thread
+    shared: usize,
+    /// Outermost handles dropped while nested ones still hold the lock,
+    /// released with the last of them
+    parked: Vec<FileLock>,
+}
+
+impl Entry {
+    fn new() -> Self {
+        Entry {
+            generation: GENERATION.fetch_add(1, Ordering::Relaxed),
+            owner: None,
+            depth: 0,
+            shared: 0,
+            parked: Vec::new(),
+        }
+    }
+}
+
+fn held() -> MutexGuard<'static, HashMap<PathBuf, Entry>> {
+    HELD.get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// The key `lock_path` is claimed under: its directory canonicalized, so
+/// relative and absolute spellings of one lock meet
+pub(crate) fn key(lock_path: &Path) -> PathBuf {
+    let (Some(parent), Some(name)) = (lock_path.parent(), lock_path.file_name()) else {
+        return lock_path.to_path_buf();
+    };
+    let parent = if parent.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        parent
+    };
+    std::fs::canonicalize(parent)
+        .map(|dir| dir.join(name))
+        .unwrap_or_else(|_| lock_path.to_path_buf())
+}
+
+/// A claim on a lock in the registry, given up when dropped
+#[derive(Debug)]
+pub(crate) struct Registration {
+    key: PathBuf,
+    generation: u64,
+    shared: bool,
+    nested: bool,
+}
+
+impl Registration {
+    /// Claim `key` without waiting: `None` if any other handle holds it in
+    /// a conflicting mode, this thread's own included
+    pub(crate) fn try_claim(key: &Path, shared: bool) -> Option<Registration> {
+        let mut held = held();
+        let entry = held.entry(key.to_path_buf()).or_insert_with(Entry::new);
+        match entry.owner {
+            Some(_) => return None,
+            None if shared => entry.shared += 1,
+            None if entry.shared == 0 => {
+                entry.owner = Some(thread::current().id());
+                entry.depth = 1;
+            }
+            None => return None,
+        }
+        Some(Registration {
+            key: key.to_path_buf(),
+            generation: entry.generation,
+            shared,
+            nested: false,
+        })
+    }
+
+    /// Nest in this thread's exclusive claim on `key`, if it has one
+    pub(crate) fn try_nest(key: &Path) -> Option<Registration> {
+        let mut held = held();
+        let entry = held.get_mut(key)?;
+        if entry.owner != Some(thread::current().id()) || entry.depth == 0 {
+            return None;
+        }
+        entry.depth += 1;
+        Some(Registration {
+            key: key.to_path_buf(),
+            generation: entry.generation,
+            shared: false,
+            nested: true,
+        })
+    }
+
+    /// Take `key` over from the thread holding it exclusively, for breaking
+    /// a lock its holder has abandoned. `None` if no thread holds it so.
+    pub(crate) fn take_over(key: &Path) -> Option<Registration> {
+        let (registration, parked) = {
+            let mut held = held();
+            let entry = held.get_mut(key)?;
+            entry.owner?;
+            entry.generation = GENERATION.fetch_add(1, Ordering::Relaxed);
+            entry.owner = Some(thread::current().id());
+            entry.depth = 1;
+            let registration = Registration {
+                key: key.to_path_buf(),
+                generation: entry.generation,
+                shared: false,
+                nested: false,
+            };
+            (registration, std::mem::take(&mut entry.parked))
+        };
+        drop(parked);
+        Some(registration)
+    }
+
+    /// Whether this claim nests in one the thread already held, so there
+    /// is nothing to take on the filesystem
+    pub(crate) fn is_nested(&self) -> bool {
+        self.nested
+    }
+
+    /// Keep the lock `take` yields held until the last nested handle goes,
+    /// if any remain. Returns false, without calling `take`, if none do.
+    pub(crate) fn park_if_outlived(&self, take: impl FnOnce() -> FileLock) -> bool {
+        if self.shared {
+            return false;
+        }
+        let mut held = held();
+        match held.get_mut(&self.key) {
+            Some(entry) if entry.generation == self.generation && entry.depth > 1 => {
+                entry.parked.push(take());
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        let parked = {
+            let mut held = held();
+            let Some(entry) = held.get_mut(&self.key) else {
+                return;
+            };
+            if entry.generation != self.generation {
+                return;
+            }
+            if self.shared {
+                entry.shared -= 1;
+                if entry.owner.is_none() && entry.shared == 0 {
+                    held.remove(&self.key);
+                }
+                return;
+            }
+            entry.depth -= 1;
+            if entry.depth > 0 {
+                return;
+            }
+            std::mem::take(&mut entry.parked)
+        };
+        // Release a parked lock before other threads may claim it
+        drop(parked);
+
+        let mut held = held();
+        if let Some(entry) = held.get_mut(&self.key) {
+            if entry.generation == self.generation && entry.depth == 0 {
+                entry.owner = None;
+                if entry.shared == 0 && entry.parked.is_empty() {
+                    held.remove(&self.key);
+                }
+            }
+        }
+    }
+}
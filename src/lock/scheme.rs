@@ -0,0 +1,270 @@
+//! Lock filename derivation.
+//!
+//! The naming algorithm is a stable, documented contract so that tools written
+//! in other languages can compute the same lock path as mutx and take part in
+//! the same locking. See [`ALGORITHM`] for the specification and
+//! [`lock_filename`] for the reference implementation.
+
+use crate::error::{MutxError, Result};
+use crate::utils::path::to_nfc;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+/// Length of a full SHA-256 digest in hex characters
+pub const FULL_HASH_LEN: usize = 64;
+
+/// Shortest hash length accepted by [`LockScheme::validate`]
+pub const MIN_HASH_LEN: usize = 8;
+
+/// Upper bound for generated lock filenames, in bytes.
+///
+/// Most filesystems allow 255 bytes per name, but eCryptfs-encrypted home
+/// directories (where the per-user cache usually lives) only allow 143.
+pub const MAX_LOCK_FILENAME_BYTES: usize = 143;
+
+/// Version of the lock naming scheme described by [`ALGORITHM`]
+pub const SCHEME_VERSION: u32 = 2;
+
+/// Human-readable specification of the lock filename algorithm.
+///
+/// Printed by `mutx lock path --print-algorithm`. Any change to
+/// [`lock_filename`] must be reflected here and treated as a breaking change.
+pub const ALGORITHM: &str = "\
+mutx lock filename algorithm (scheme v2)
+
+Input: the canonical absolute path of the output file. If the file does not
+exist yet, canonicalize its parent directory and append the file name.
+
+1. Normalize the path string to Unicode NFC.
+2. FILENAME is the final path component. PARENT is the name of the parent
+   directory, or \"root\" if there is none.
+3. INITIALISM: take up to 3 directory components immediately above PARENT
+   (never the filesystem root). For each, if its first character is
+   alphanumeric, append that character (ASCII letters lowercased) and a \".\".
+4. HASH: lowercase hex SHA-256 of the UTF-8 bytes of the normalized path,
+   truncated to HASH_LEN characters (default 8, up to 64).
+5. PREFIX = INITIALISM + PARENT + \".\" + FILENAME, truncated on a character
+   boundary so that the whole name is at most 143 bytes.
+6. Lock filename = PREFIX + \".\" + HASH + \".lock\".
+7. SHARD: the first 2 characters of the untruncated hex SHA-256 from step 4.
+   The lock file is placed in the SHARD subdirectory of the lock cache
+   directory (see `mutx lock path` for the directory on this system).
+
+Example: /home/alice/projects/app/config.json
+  -> <shard>/h.a.p.app.config.json.<hash>.lock
+
+Scheme v1 placed lock files directly in the lock cache directory; mutx moves
+such files into their shard the next time it derives their path.
+";
+
+/// Number of hex characters naming a lock shard directory (256 shards)
+pub const SHARD_LEN: usize = 2;
+
+/// Naming parameters for derived lock files.
+///
+/// The default produces the historical `{initialism}{parent}.{filename}.{hash}.lock`
+/// names with an 8 hex character hash. Large monorepos with millions of
+/// files can raise `hash_len` (up to [`FULL_HASH_LEN`]) to make collisions
+/// between unrelated outputs vanishingly unlikely.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockScheme {
+    pub hash_len: usize,
+}
+
+impl Default for LockScheme {
+    fn default() -> Self {
+        Self {
+            hash_len: MIN_HASH_LEN,
+        }
+    }
+}
+
+impl LockScheme {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_hash_len(mut self, hash_len: usize) -> Self {
+        self.hash_len = hash_len;
+        self
+    }
+
+    /// Use the full 64 character SHA-256 hex digest
+    pub fn with_full_hash(self) -> Self {
+        self.with_hash_len(FULL_HASH_LEN)
+    }
+
+    /// Check that the scheme can produce valid lock names
+    pub fn validate(&self) -> Result<()> {
+        if !(MIN_HASH_LEN..=FULL_HASH_LEN).contains(&self.hash_len) {
+            return Err(MutxError::Other(format!(
+                "Lock hash length must be between {} and {}, got {}",
+                MIN_HASH_LEN, FULL_HASH_LEN, self.hash_len
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Compute the lock filename for an already-canonicalized output path.
+///
+/// This is a pure function: it performs no filesystem access, so callers that
+/// only need the name (or that canonicalize paths themselves) can use it
+/// directly. It implements [`ALGORITHM`].
+pub fn lock_filename(canonical: &Path, scheme: &LockScheme) -> Result<String> {
+    scheme.validate()?;
+
+    // Extract path components
+    let components: Vec<_> = canonical.components().collect();
+
+    // Get filename
+    let filename = canonical
+        .file_name()
+        .ok_or_else(|| MutxError::Other("Output path has no filename".to_string()))?
+        .to_str()
+        .map(to_nfc)
+        .ok_or_else(|| MutxError::Other("Non-UTF8 filename".to_string()))?;
+
+    // Get parent directory name
+    let parent_name = canonical
+        .parent()
+        .and_then(|p| p.file_name())
+        .and_then(|n| n.to_str())
+        .map(to_nfc)
+        .unwrap_or_else(|| "root".to_string());
+
+    // Build initialism from ancestor directories (excluding parent and filename)
+    // Limit to last 3 ancestors for readability (hash provides uniqueness)
+    let mut initialism = String::new();
+    if components.len() > 2 {
+        // Parent is at components.len() - 2 (filename is at components.len() - 1)
+        // Get up to 3 ancestors before parent (for human readability)
+        let parent_idx = components.len() - 2;
+        let start_idx = if parent_idx > 3 {
+            parent_idx - 3 // Last 3 ancestors before parent
+        } else {
+            1 // Start after root
+        };
+
+        for component in &components[start_idx..parent_idx] {
+            if let Some(name) = component.as_os_str().to_str().map(to_nfc) {
+                if let Some(first_char) = name.chars().next() {
+                    if first_char.is_alphanumeric() {
+                        initialism.push(first_char.to_ascii_lowercase());
+                        initialism.push('.');
+                    }
+                }
+            }
+        }
+    }
+
+    let hash = path_hash(canonical);
+    let hash_short = &hash[..scheme.hash_len];
+
+    // Build lock filename: {initialism}{parent}.{filename}.{hash}.lock
+    // The readable prefix is clamped so the whole name fits the filesystem
+    // limit; the hash is always kept intact since it carries the uniqueness
+    let hash_part = format!(".{}.lock", hash_short);
+    let prefix = format!("{}{}.{}", initialism, parent_name, filename);
+    let prefix = clamp_to_bytes(&prefix, MAX_LOCK_FILENAME_BYTES - hash_part.len());
+    let lock_filename = format!("{}{}", prefix, hash_part);
+
+    Ok(lock_filename)
+}
+
+/// Shard subdirectory of the lock cache holding the lock for an
+/// already-canonicalized output path (step 7 of [`ALGORITHM`])
+pub fn lock_shard(canonical: &Path) -> String {
+    path_hash(canonical)[..SHARD_LEN].to_string()
+}
+
+/// Whether `name` is a lock shard directory name
+pub fn is_lock_shard(name: &str) -> bool {
+    name.len() == SHARD_LEN && name.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// Lowercase hex SHA-256 of the NFC-normalized path, so NFD and NFC
+/// spellings share a lock
+pub(crate) fn path_hash(canonical: &Path) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(to_nfc(&canonical.to_string_lossy()).as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Truncate `s` to at most `max` bytes without splitting a UTF-8 character
+fn clamp_to_bytes(s: &str, max: usize) -> &str {
+    if s.len() <= max {
+        return s;
+    }
+    let mut end = max;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+/// Parse a `--lock-hash-len` value: a number of hex characters or `full`
+pub fn parse_hash_len(s: &str) -> Result<usize> {
+    let len = if s.eq_ignore_ascii_case("full") {
+        FULL_HASH_LEN
+    } else {
+        s.trim().parse().map_err(|_| {
+            MutxError::Other(format!(
+                "Invalid lock hash length '{}': expected a number or 'full'",
+                s
+            ))
+        })?
+    };
+
+    LockScheme::new().with_hash_len(len).validate()?;
+    Ok(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clamp_to_bytes_respects_char_boundaries() {
+        assert_eq!(clamp_to_bytes("abc", 10), "abc");
+        assert_eq!(clamp_to_bytes("abcdef", 3), "abc");
+        // "é" is two bytes; clamping inside it backs off to the boundary
+        assert_eq!(clamp_to_bytes("a\u{e9}b", 2), "a");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_lock_filename_matches_documented_example() {
+        let name = lock_filename(
+            Path::new("/home/alice/projects/app/config.json"),
+            &LockScheme::default(),
+        )
+        .unwrap();
+
+        let mut hasher = Sha256::new();
+        hasher.update(b"/home/alice/projects/app/config.json");
+        let hash = format!("{:x}", hasher.finalize());
+
+        assert_eq!(name, format!("h.a.p.app.config.json.{}.lock", &hash[..8]));
+    }
+
+    #[test]
+    fn test_lock_shard_is_hash_prefix() {
+        let path = Path::new("/home/alice/projects/app/config.json");
+        let shard = lock_shard(path);
+
+        assert!(is_lock_shard(&shard));
+        assert!(path_hash(path).starts_with(&shard));
+        assert!(!is_lock_shard("0g"));
+        assert!(!is_lock_shard("abc"));
+    }
+
+    #[test]
+    fn test_parse_hash_len() {
+        assert_eq!(parse_hash_len("16").unwrap(), 16);
+        assert_eq!(parse_hash_len("full").unwrap(), FULL_HASH_LEN);
+        assert!(parse_hash_len("4").is_err());
+        assert!(parse_hash_len("65").is_err());
+        assert!(parse_hash_len("abc").is_err());
+    }
+}
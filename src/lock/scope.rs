@@ -0,0 +1,122 @@
+//! Detection of lock files that cannot provide exclusion across containers.
+//!
+//! Derived locks live in the per-user cache directory. Inside a container that
+//! directory is usually on the container's own (ephemeral, per-container)
+//! filesystem, while the output may sit on a volume bind-mounted into several
+//! containers. Each container then locks its own private lock file and writers
+//! in different containers do not exclude each other.
+
+use crate::error::{MutxError, Result};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// What to do when the lock directory and output are on different filesystems
+/// inside a container
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScopePolicy {
+    /// Log a warning and keep using the cache directory lock (default)
+    #[default]
+    Warn,
+    /// Place the lock next to the output instead, on the shared volume
+    Adjacent,
+    /// Skip the check
+    Ignore,
+}
+
+impl fmt::Display for ScopePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScopePolicy::Warn => write!(f, "warn"),
+            ScopePolicy::Adjacent => write!(f, "adjacent"),
+            ScopePolicy::Ignore => write!(f, "ignore"),
+        }
+    }
+}
+
+impl FromStr for ScopePolicy {
+    type Err = MutxError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "warn" => Ok(ScopePolicy::Warn),
+            "adjacent" => Ok(ScopePolicy::Adjacent),
+            "ignore" => Ok(ScopePolicy::Ignore),
+            _ => Err(MutxError::Other(format!(
+                "Unknown lock scope policy '{}': expected one of warn, adjacent, ignore",
+                s
+            ))),
+        }
+    }
+}
+
+/// `<output>.lock`, a lock file placed next to the output
+pub fn sidecar_lock_path(output: &Path) -> PathBuf {
+    let mut name = output.as_os_str().to_os_string();
+    name.push(".lock");
+    PathBuf::from(name)
+}
+
+/// Best-effort detection of running inside a container.
+///
+/// Checks the `container` environment variable (set by systemd-nspawn, podman
+/// and LXC), the `/.dockerenv` and `/run/.containerenv` marker files, and
+/// container runtime names in `/proc/1/cgroup`.
+pub fn in_container() -> bool {
+    if std::env::var_os("container").is_some_and(|v| !v.is_empty()) {
+        return true;
+    }
+
+    if Path::new("/.dockerenv").exists() || Path::new("/run/.containerenv").exists() {
+        return true;
+    }
+
+    std::fs::read_to_string("/proc/1/cgroup")
+        .map(|cgroup| {
+            ["docker", "kubepods", "containerd", "lxc", "libpod"]
+                .iter()
+                .any(|runtime| cgroup.contains(runtime))
+        })
+        .unwrap_or(false)
+}
+
+/// Explain why a lock in `lock_dir` may not exclude other writers of `output`.
+///
+/// Returns `None` when the scope looks fine: not in a container, or the lock
+/// directory and the output's directory are on the same device.
+pub fn lock_scope_warning(lock_dir: &Path, output: &Path, in_container: bool) -> Option<String> {
+    if !in_container {
+        return None;
+    }
+
+    let output_dir = match output.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    let lock_dev = device_of(lock_dir)?;
+    let output_dev = device_of(output_dir)?;
+    if lock_dev == output_dev {
+        return None;
+    }
+
+    Some(format!(
+        "Lock directory {} and output directory {} are on different filesystems inside a \
+         container; writers in other containers sharing {} will not be excluded. \
+         Use --lock-scope adjacent or --lock-file on the shared volume.",
+        lock_dir.display(),
+        output_dir.display(),
+        output_dir.display()
+    ))
+}
+
+#[cfg(unix)]
+fn device_of(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path).ok().map(|m| m.dev())
+}
+
+#[cfg(not(unix))]
+fn device_of(_path: &Path) -> Option<u64> {
+    None
+}
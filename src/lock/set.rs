@@ -0,0 +1,108 @@
+//! Several locks taken together for multi-file updates.
+//!
+//! Two processes updating overlapping sets of files deadlock if each takes
+//! its locks in its own order. A [`LockSet`] always takes them in sorted
+//! path order, so whoever gets the first shared lock proceeds and the other
+//! waits on that one lock, holding nothing the first needs.
+
+use crate::error::Result;
+use crate::lock::acquisition::{FileLock, LockStrategy};
+use crate::lock::backend::LockBackend;
+use crate::lock::path::derive_lock_path;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tracing::debug;
+
+/// A set of held locks, released together (in reverse order) when dropped
+#[derive(Debug)]
+pub struct LockSet {
+    locks: Vec<FileLock>,
+}
+
+impl LockSet {
+    /// Acquire the lock files at `lock_paths` with the `flock` backend, see
+    /// [`LockSet::acquire_with_backend`]
+    pub fn acquire<P: AsRef<Path>>(
+        lock_paths: impl IntoIterator<Item = P>,
+        strategy: LockStrategy,
+    ) -> Result<Self> {
+        Self::acquire_with_backend(lock_paths, strategy, LockBackend::Flock)
+    }
+
+    /// Acquire every lock file in `lock_paths`, or none of them.
+    ///
+    /// Paths are deduplicated and taken in sorted order; every process must
+    /// spell the same lock the same way (derived lock paths always are). A
+    /// `Timeout` covers the whole set, not each lock, as a `Deadline` does.
+    /// If any lock cannot be taken, those already held are released and its
+    /// error is returned.
+    pub fn acquire_with_backend<P: AsRef<Path>>(
+        lock_paths: impl IntoIterator<Item = P>,
+        strategy: LockStrategy,
+        backend: LockBackend,
+    ) -> Result<Self> {
+        let mut paths: Vec<PathBuf> = lock_paths
+            .into_iter()
+            .map(|p| p.as_ref().to_path_buf())
+            .collect();
+        paths.sort();
+        paths.dedup();
+
+        let start = Instant::now();
+        let mut set = LockSet {
+            locks: Vec::with_capacity(paths.len()),
+        };
+        for path in &paths {
+            let remaining = strategy.remaining(start.elapsed());
+            // On failure `set` is dropped, releasing what it holds
+            set.locks
+                .push(FileLock::acquire_with_backend(path, remaining, backend)?);
+        }
+
+        debug!(
+            "Acquired {} locks in {:?}",
+            set.locks.len(),
+            start.elapsed()
+        );
+        Ok(set)
+    }
+
+    /// Lock the files in `outputs` through their derived lock paths (see
+    /// [`derive_lock_path`])
+    pub fn for_outputs<P: AsRef<Path>>(
+        outputs: impl IntoIterator<Item = P>,
+        strategy: LockStrategy,
+    ) -> Result<Self> {
+        let lock_paths = outputs
+            .into_iter()
+            .map(|output| derive_lock_path(output.as_ref(), false))
+            .collect::<Result<Vec<_>>>()?;
+        Self::acquire(lock_paths, strategy)
+    }
+
+    /// The held locks, in the order they were taken
+    pub fn locks(&self) -> &[FileLock] {
+        &self.locks
+    }
+
+    /// Paths of the held locks, in the order they were taken
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.locks.iter().map(FileLock::path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.locks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.locks.is_empty()
+    }
+}
+
+impl Drop for LockSet {
+    fn drop(&mut self) {
+        while let Some(lock) = self.locks.pop() {
+            drop(lock);
+        }
+    }
+}
@@ -1,24 +1,39 @@
 use clap::Parser;
+use mutx::schema::{self, ErrorReport, Versioned};
 use mutx::MutxError;
 use std::process;
 
 mod cli;
 
+use cli::style::{self, Stream};
+
 fn main() {
+    let args = cli::Args::parse();
+    let json = args.json_output();
+    style::set_color(args.color);
+
     // Initialize tracing (use RUST_LOG env var to control output)
     tracing_subscriber::fmt()
         .with_writer(std::io::stderr)
+        .with_ansi(style::enabled(Stream::Stderr))
         .init();
 
-    let args = cli::Args::parse();
-
     if let Err(e) = cli::run(args) {
-        eprintln!("Error: {}", e);
+        eprintln!("{} {}", style::error_header(), e);
         let exit_code = match e {
-            MutxError::LockTimeout { .. } | MutxError::LockWouldBlock(_) => 2,
+            MutxError::LockTimeout { .. } | MutxError::LockWouldBlock { .. } => 2,
             MutxError::Interrupted => 3,
             _ => e.exit_code(),
         };
+        if json {
+            let report = ErrorReport {
+                exit_code,
+                ..ErrorReport::from(&e)
+            };
+            if let Ok(report) = Versioned::new(schema::ERROR, &report).to_json() {
+                println!("{}", report);
+            }
+        }
         process::exit(exit_code);
     }
 }
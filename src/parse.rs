@@ -0,0 +1,200 @@
+//! Parsers for the values mutx accepts on the command line, for tools that
+//! take the same options.
+//!
+//! Bad input is reported with the input echoed back:
+//!
+//! - [`parse_duration`]: `30`, `30s`, `5m`, `2h`, `7d`; [`MutxError::InvalidDuration`]
+//! - [`parse_size`]: `512`, `10K`, `10M`, `1GiB`, `2T`; [`MutxError::InvalidSize`]
+//!   ([`format_size`] writes sizes back in the same form)
+//! - [`parse_mode`]: `0644`, `0o2750`; [`MutxError::InvalidPermissions`]
+//! - [`parse_backup_suffix`] / [`validate_backup_suffix`]: `.bak`, `~`;
+//!   [`MutxError::Other`]
+
+use crate::backup::BackupSuffix;
+use crate::error::{MutxError, Result};
+use crate::write::FileMode;
+use std::time::Duration;
+
+pub use crate::backup::validate_backup_suffix;
+
+/// Parse a duration string like "30s", "5m", "2h", "7d"
+/// Defaults to seconds if no unit specified
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let s = s.trim();
+
+    if s.is_empty() {
+        return Err(MutxError::InvalidDuration {
+            input: s.to_string(),
+            message: "empty string".to_string(),
+        });
+    }
+
+    let (num_str, unit) = if let Some(stripped) = s.strip_suffix('s') {
+        (stripped, 's')
+    } else if let Some(stripped) = s.strip_suffix('m') {
+        (stripped, 'm')
+    } else if let Some(stripped) = s.strip_suffix('h') {
+        (stripped, 'h')
+    } else if let Some(stripped) = s.strip_suffix('d') {
+        (stripped, 'd')
+    } else {
+        // No unit, assume seconds
+        (s, 's')
+    };
+
+    let value: u64 = num_str.parse().map_err(|_| MutxError::InvalidDuration {
+        input: s.to_string(),
+        message: "expected format: NUMBER[s|m|h|d] (e.g., '30s', '5m', '2h', '7d')".to_string(),
+    })?;
+
+    let multiplier: u64 = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 60 * 60 * 24,
+        _ => unreachable!(),
+    };
+    let seconds = value
+        .checked_mul(multiplier)
+        .ok_or_else(|| MutxError::InvalidDuration {
+            input: s.to_string(),
+            message: "duration too large".to_string(),
+        })?;
+
+    Ok(Duration::from_secs(seconds))
+}
+
+/// Size units, largest first, as (binary prefix, shift)
+const SIZE_UNITS: [(char, u32); 4] = [('T', 40), ('G', 30), ('M', 20), ('K', 10)];
+
+/// Parse a byte size like "512", "10K", "10M", "1G" or "2T". Units are
+/// powers of 1024 and may also be written `KiB`, `MiB`, ... (or `Ki`); a
+/// bare number or a `B` suffix is bytes.
+pub fn parse_size(s: &str) -> Result<u64> {
+    let s = s.trim();
+    let invalid = |message: &str| MutxError::InvalidSize {
+        input: s.to_string(),
+        message: message.to_string(),
+    };
+
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (num_str, unit) = s.split_at(split);
+    if num_str.is_empty() {
+        return Err(invalid(
+            "expected format: NUMBER[K|M|G|T] (e.g., '512', '10M', '1GiB')",
+        ));
+    }
+
+    let shift = match unit {
+        "" | "B" => 0,
+        _ => {
+            let mut chars = unit.chars();
+            let prefix = chars.next().map(|c| c.to_ascii_uppercase());
+            let rest = chars.as_str();
+            match SIZE_UNITS.iter().find(|(unit, _)| Some(*unit) == prefix) {
+                Some((_, shift)) if matches!(rest, "" | "i" | "iB") => *shift,
+                _ => {
+                    return Err(invalid(
+                        "unknown unit, expected K, M, G or T (or KiB, MiB, ...)",
+                    ))
+                }
+            }
+        }
+    };
+
+    let value: u64 = num_str.parse().map_err(|_| invalid("number too large"))?;
+    value
+        .checked_mul(1 << shift)
+        .ok_or_else(|| invalid("size too large"))
+}
+
+/// Format `bytes` in the largest unit that represents it exactly ("10M",
+/// "1536K", "1000"), so [`parse_size`] reads back the same value
+pub fn format_size(bytes: u64) -> String {
+    for (unit, shift) in SIZE_UNITS {
+        if bytes != 0 && bytes % (1 << shift) == 0 {
+            return format!("{}{}", bytes >> shift, unit);
+        }
+    }
+    bytes.to_string()
+}
+
+/// Parse an octal permission mode like "0644" or "0o2750"
+pub fn parse_mode(s: &str) -> Result<FileMode> {
+    s.trim().parse()
+}
+
+/// Parse and validate a backup suffix like ".bak"
+pub fn parse_backup_suffix(s: &str) -> Result<BackupSuffix> {
+    s.parse()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_units() {
+        assert_eq!(parse_duration("1s").unwrap().as_secs(), 1);
+        assert_eq!(parse_duration("1m").unwrap().as_secs(), 60);
+        assert_eq!(parse_duration("1h").unwrap().as_secs(), 3600);
+        assert_eq!(parse_duration("1d").unwrap().as_secs(), 86400);
+    }
+
+    #[test]
+    fn test_duration_overflow_is_an_error() {
+        let max = u64::MAX.to_string();
+        assert_eq!(parse_duration(&max).unwrap().as_secs(), u64::MAX);
+        for unit in ["m", "h", "d"] {
+            let err = parse_duration(&format!("{}{}", max, unit)).unwrap_err();
+            assert!(err.to_string().contains("duration too large"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_size_units() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("10K").unwrap(), 10 * 1024);
+        assert_eq!(parse_size("10M").unwrap(), 10 << 20);
+        assert_eq!(parse_size("1g").unwrap(), 1 << 30);
+        assert_eq!(parse_size("2T").unwrap(), 2 << 40);
+    }
+
+    #[test]
+    fn test_binary_unit_forms() {
+        assert_eq!(parse_size("500MiB").unwrap(), 500 << 20);
+        assert_eq!(parse_size("2Ki").unwrap(), 2048);
+        assert_eq!(parse_size("64B").unwrap(), 64);
+    }
+
+    #[test]
+    fn test_format_size_round_trips() {
+        assert_eq!(format_size(0), "0");
+        assert_eq!(format_size(1000), "1000");
+        assert_eq!(format_size(1536), "1536");
+        assert_eq!(format_size(3 << 19), "1536K");
+        assert_eq!(format_size(10 << 20), "10M");
+        for bytes in [0, 1, 1000, 1024, 1536, 10 << 20, 3 << 30, 5 << 40] {
+            assert_eq!(parse_size(&format_size(bytes)).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn test_invalid_sizes() {
+        for input in [
+            "",
+            "M",
+            "10X",
+            "-1K",
+            "1.5G",
+            "99999999999T",
+            "10KB",
+            "1Gi B",
+        ] {
+            assert!(
+                matches!(parse_size(input), Err(MutxError::InvalidSize { .. })),
+                "{input}"
+            );
+        }
+    }
+}
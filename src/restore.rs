@@ -0,0 +1,282 @@
+use crate::backup::{create_backup, BackupConfig, BackupLocation, BackupSuffix};
+use crate::compress::CompressionFormat;
+use crate::delta::{self, Scratch};
+use crate::digest::DigestAlgorithm;
+use crate::error::{MutxError, Result};
+use crate::housekeep::{extract_base_filename, is_backup_file};
+use crate::utils::xattr::{get_xattr, BACKUP_SHA256_XATTR, COMPRESSION_XATTR};
+use crate::utils::{apply_nofollow, verify_not_link};
+use crate::write::{AtomicWriter, WriteMode};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tracing::debug;
+
+#[derive(Debug, Clone)]
+pub struct RestoreConfig {
+    /// File to restore
+    pub target: PathBuf,
+    /// Backup whose contents replace the target
+    pub backup: PathBuf,
+    /// Capture the current target as a backup before replacing it
+    pub backup_current: bool,
+    /// Suffix for the captured backup
+    pub suffix: BackupSuffix,
+    /// Directory for the captured backup (default: next to the target)
+    pub directory: Option<PathBuf>,
+    /// Add a timestamp to the captured backup's name
+    pub timestamp: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestoreReport {
+    /// Backup the target was restored from
+    pub restored_from: PathBuf,
+    /// Backup of the state the restore replaced, if one was taken
+    pub previous: Option<PathBuf>,
+}
+
+/// Replace `config.target` with the contents of `config.backup`.
+///
+/// The restore is ordered so an interruption never loses both versions: the
+/// backup is first staged into a temp file beside the target, then the current
+/// target is captured (with `backup_current`), and only then is the staged
+/// file renamed over the target. Until that rename the target is untouched;
+/// after it, the previous state is already on disk.
+///
+/// The caller is responsible for holding the target's lock.
+pub fn restore_backup(config: &RestoreConfig) -> Result<RestoreReport> {
+    let backup = &config.backup;
+
+    // Stage the backup contents first, so capturing the current target cannot
+    // clobber the backup before it has been read (same name, same suffix)
+    let mut source = open_backup(backup)?;
+
+    let mut writer = AtomicWriter::new(&config.target, WriteMode::Streaming)?;
+    if delta::is_delta(backup)? {
+        // Rebuilt from the newer backups it was stored against
+        delta::reconstruct(backup, |buf| writer.write_all(buf))?;
+    } else {
+        let mut buffer = [0u8; 8192];
+        loop {
+            let n = source
+                .read(&mut buffer)
+                .map_err(|e| MutxError::ReadFailed {
+                    path: backup.clone(),
+                    source: e,
+                })?;
+            if n == 0 {
+                break;
+            }
+            writer.write_all(&buffer[..n])?;
+        }
+    }
+
+    let previous = if config.backup_current && config.target.exists() {
+        let captured = create_backup(&BackupConfig {
+            source: config.target.clone(),
+            suffix: config.suffix.clone(),
+            directory: config.directory.clone(),
+            timestamp: config.timestamp,
+            prune_on_enospc: None,
+            delta: false,
+        })?;
+        debug!("Captured current state: {}", captured.display());
+        Some(captured)
+    } else {
+        None
+    };
+
+    writer.commit()?;
+    debug!(
+        "Restored {} from {}",
+        config.target.display(),
+        backup.display()
+    );
+
+    Ok(RestoreReport {
+        restored_from: backup.clone(),
+        previous,
+    })
+}
+
+/// Find the most recent backup of `target` with `suffix`, looking in
+/// `directory` or, by default, next to the target.
+pub fn find_latest_backup(
+    target: &Path,
+    suffix: &str,
+    directory: Option<&Path>,
+) -> Result<Option<PathBuf>> {
+    Ok(list_backups(target, suffix, directory)?.pop())
+}
+
+/// Every backup of `target` with `suffix`, timestamped or not, looking in
+/// `directory` or, by default, next to the target. Oldest first, by
+/// modification time.
+pub fn list_backups(target: &Path, suffix: &str, directory: Option<&Path>) -> Result<Vec<PathBuf>> {
+    let location = BackupLocation::of(target, directory)?;
+    let dir = location.dir.clone();
+
+    let entries = fs::read_dir(&dir).map_err(|e| MutxError::ReadFailed {
+        path: dir.clone(),
+        source: e,
+    })?;
+
+    let mut backups: Vec<(SystemTime, PathBuf)> = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(MutxError::Io)?;
+        let path = entry.path();
+        if !entry.file_type().map_err(MutxError::Io)?.is_file()
+            || !is_backup_file(&path, suffix)
+            || !location.owns(&path, &extract_base_filename(&path, suffix))
+        {
+            continue;
+        }
+
+        let mtime = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .map_err(MutxError::Io)?;
+        backups.push((mtime, path));
+    }
+
+    backups.sort();
+    Ok(backups.into_iter().map(|(_, path)| path).collect())
+}
+
+/// What [`verify_backup`] found in a backup that reads back intact
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedBackup {
+    /// Stored as a delta, rebuilt from the newer backups it was taken
+    /// against and checked against the size and SHA-256 it recorded
+    pub delta: bool,
+    /// Size of its contents (once rebuilt, for a delta)
+    pub size: u64,
+    /// Its contents matched a SHA-256 recorded when it was taken: always
+    /// for a delta, and for a full backup that has one recorded
+    pub checksum: bool,
+    /// Compression of its contents, as recorded for a backup of a file mutx
+    /// wrote with `--compress`, and the size they decode to
+    pub decoded: Option<(CompressionFormat, u64)>,
+}
+
+/// Check that `backup` could be restored: it opens as a regular file and
+/// reads in full, and matches the SHA-256 recorded when it was taken (a
+/// delta records its own; a full backup in the `user.mutx.sha256`
+/// extended attribute). Content mutx compressed (a backup of a file written
+/// with `--compress`, recorded in `user.mutx.compression`) must also decode
+/// to its end, which checks the format's own checksum.
+///
+/// A full backup taken where extended attributes are unavailable, or by an
+/// older release, records no checksum, and is only checked to read in full.
+/// A delta is rebuilt into a temp file beside it, removed once read.
+pub fn verify_backup(backup: &Path) -> Result<VerifiedBackup> {
+    let source = open_backup(backup)?;
+    let read_failed = |e: io::Error| MutxError::ReadFailed {
+        path: backup.to_path_buf(),
+        source: e,
+    };
+
+    let delta = delta::is_delta(backup)?;
+    let scratch;
+    let (size, mut contents) = if delta {
+        scratch = Scratch::create(backup)?;
+        let size = {
+            let mut out = BufWriter::new(&scratch.file);
+            let size = delta::reconstruct(backup, |buf| {
+                out.write_all(buf).map_err(|e| MutxError::WriteFailed {
+                    path: scratch.path.clone(),
+                    source: e,
+                })
+            })?;
+            out.flush().map_err(MutxError::Io)?;
+            size
+        };
+        (size, &scratch.file)
+    } else {
+        let size = source.metadata().map_err(MutxError::Io)?.len();
+        (size, &source)
+    };
+
+    // A delta is checked against its recorded checksum as it rebuilds
+    let checksum = delta || {
+        let recorded = get_xattr(backup, BACKUP_SHA256_XATTR)
+            .ok()
+            .flatten()
+            .map(|hex| String::from_utf8_lossy(&hex).into_owned());
+        let mut hasher = DigestAlgorithm::Sha256.hasher();
+        let mut buffer = [0u8; 8192];
+        loop {
+            let n = contents.read(&mut buffer).map_err(read_failed)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buffer[..n]);
+        }
+        match recorded {
+            Some(recorded) if recorded != hasher.finish_hex() => {
+                return Err(MutxError::Other(format!(
+                    "Backup {} does not match the SHA-256 recorded when it was taken",
+                    backup.display()
+                )));
+            }
+            recorded => recorded.is_some(),
+        }
+    };
+
+    let compression = get_xattr(backup, COMPRESSION_XATTR)
+        .ok()
+        .flatten()
+        .and_then(|format| {
+            String::from_utf8_lossy(&format)
+                .parse::<CompressionFormat>()
+                .ok()
+        });
+    let decoded = match compression {
+        Some(format) => {
+            contents.seek(SeekFrom::Start(0)).map_err(read_failed)?;
+            let mut decoder = format.decoder(Box::new(contents))?;
+            let decoded_size = io::copy(&mut decoder, &mut io::sink()).map_err(|e| {
+                MutxError::Other(format!(
+                    "Cannot decode {} backup {}: {}",
+                    format,
+                    backup.display(),
+                    e
+                ))
+            })?;
+            Some((format, decoded_size))
+        }
+        None => None,
+    };
+
+    debug!("Verified backup {}", backup.display());
+    Ok(VerifiedBackup {
+        delta,
+        size,
+        checksum,
+        decoded,
+    })
+}
+
+/// Open `backup` for reading, refusing symlinks and anything but a regular file
+fn open_backup(backup: &Path) -> Result<File> {
+    if !backup.exists() {
+        return Err(MutxError::PathNotFound(backup.to_path_buf()));
+    }
+
+    let mut opts = OpenOptions::new();
+    opts.read(true);
+    apply_nofollow(&mut opts);
+    let source = opts.open(backup).map_err(|e| MutxError::ReadFailed {
+        path: backup.to_path_buf(),
+        source: e,
+    })?;
+    verify_not_link(&source, backup, |path| MutxError::SymlinkNotAllowed {
+        path,
+    })?;
+    if !source.metadata().map_err(MutxError::Io)?.is_file() {
+        return Err(MutxError::NotAFile(backup.to_path_buf()));
+    }
+    Ok(source)
+}
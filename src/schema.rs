@@ -0,0 +1,214 @@
+//! Versioned JSON documents: the reports printed with `--json`, the
+//! `housekeep daemon` heartbeat, and errors of failing `--json` commands.
+//!
+//! Every document carries a `"schema"` field naming its format and version,
+//! such as `"mutx.write.v1"`. Within a version fields are only ever added,
+//! so parsers should ignore fields they do not know; removing, renaming or
+//! retyping a field means a new version. [`SCHEMAS`] holds the JSON Schema
+//! of every format, as printed by `mutx schema dump`.
+
+use crate::error::{MutxError, Result};
+use crate::lock::LockStatus;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Report of `mutx write --json` ([`crate::WriteReport`])
+pub const WRITE: &str = "mutx.write.v1";
+/// Report of `mutx housekeep ... --json` ([`HousekeepReport`])
+pub const HOUSEKEEP: &str = "mutx.housekeep.v1";
+/// Heartbeat file of `mutx housekeep daemon` ([`crate::janitor::Heartbeat`])
+pub const STATUS: &str = "mutx.status.v1";
+/// Error of a failing `--json` command ([`ErrorReport`])
+pub const ERROR: &str = "mutx.error.v1";
+/// Report of `mutx status --json` ([`LockStatusReport`])
+pub const LOCK_STATUS: &str = "mutx.lock_status.v1";
+/// Warning of `mutx write --warn-after` ([`LockWaitReport`])
+pub const LOCK_WAIT: &str = "mutx.lock_wait.v1";
+/// Report of `mutx check --json` ([`CheckReport`])
+pub const CHECK: &str = "mutx.check.v1";
+/// Report of `mutx backups check --json` ([`BackupCheckReport`])
+pub const BACKUP_CHECK: &str = "mutx.backup_check.v1";
+
+/// Every format with its JSON Schema (draft 2020-12)
+pub const SCHEMAS: &[(&str, &str)] = &[
+    (WRITE, include_str!("../schemas/mutx.write.v1.json")),
+    (HOUSEKEEP, include_str!("../schemas/mutx.housekeep.v1.json")),
+    (STATUS, include_str!("../schemas/mutx.status.v1.json")),
+    (ERROR, include_str!("../schemas/mutx.error.v1.json")),
+    (
+        LOCK_STATUS,
+        include_str!("../schemas/mutx.lock_status.v1.json"),
+    ),
+    (LOCK_WAIT, include_str!("../schemas/mutx.lock_wait.v1.json")),
+    (CHECK, include_str!("../schemas/mutx.check.v1.json")),
+    (
+        BACKUP_CHECK,
+        include_str!("../schemas/mutx.backup_check.v1.json"),
+    ),
+];
+
+/// The JSON Schema of the format `name`, e.g. `"mutx.write.v1"`
+pub fn schema(name: &str) -> Option<&'static str> {
+    SCHEMAS
+        .iter()
+        .find(|(schema, _)| *schema == name)
+        .map(|(_, document)| *document)
+}
+
+/// `document` with a leading `"schema": name` field
+#[derive(Debug, Clone, Serialize)]
+pub struct Versioned<'a, T> {
+    pub schema: &'static str,
+    #[serde(flatten)]
+    pub document: &'a T,
+}
+
+impl<'a, T: Serialize> Versioned<'a, T> {
+    pub fn new(schema: &'static str, document: &'a T) -> Self {
+        Versioned { schema, document }
+    }
+
+    /// One line of JSON
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(self).map_err(|e| self.encode_failed(e))
+    }
+
+    /// Indented JSON, for files people read too
+    pub fn to_json_pretty(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(|e| self.encode_failed(e))
+    }
+
+    fn encode_failed(&self, e: serde_json::Error) -> MutxError {
+        MutxError::Other(format!("Cannot encode {} document: {}", self.schema, e))
+    }
+}
+
+/// Files removed (or, with `dry_run`, that would be) by one housekeep command
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HousekeepReport {
+    pub dry_run: bool,
+    pub locks: Vec<PathBuf>,
+    pub backups: Vec<PathBuf>,
+    pub temps: Vec<PathBuf>,
+    /// Rules that failed, for `housekeep run`
+    pub errors: Vec<String>,
+}
+
+/// Whether the lock of `file` is held, from `mutx status`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LockStatusReport {
+    pub file: PathBuf,
+    pub lock_path: PathBuf,
+    #[serde(flatten)]
+    pub status: LockStatus,
+}
+
+/// A lock wait that outlasted its `--warn-after` threshold and goes on,
+/// posted to the `--warn-webhook`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockWaitReport {
+    pub lock_path: PathBuf,
+    /// Time waited so far, in milliseconds
+    pub waited_ms: u64,
+    pub threshold_ms: u64,
+    /// The recorded holder's PID, if it recorded one
+    pub holder_pid: Option<u32>,
+    pub holder_host: Option<String>,
+    pub holder_command: Option<String>,
+}
+
+/// Outcome of the checks `mutx check` runs for a write to `file`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckReport {
+    pub file: PathBuf,
+    /// Whether no check failed; warnings do not stop a write
+    pub passed: bool,
+    pub checks: Vec<CheckResult>,
+}
+
+/// One check of a [`CheckReport`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckResult {
+    /// Stable identifier, such as `"lock_path"`
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    /// The write would go ahead, but not as safely as it could
+    Warn,
+    /// The write would fail
+    Fail,
+    /// Does not apply to this write
+    Skip,
+}
+
+/// Outcome of `mutx backups check` for the backups of `file`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupCheckReport {
+    pub file: PathBuf,
+    /// Whether every backup could be restored
+    pub passed: bool,
+    /// Oldest first
+    pub backups: Vec<BackupCheckResult>,
+}
+
+/// One backup of a [`BackupCheckReport`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupCheckResult {
+    pub path: PathBuf,
+    /// [`CheckStatus::Pass`] or [`CheckStatus::Fail`]
+    pub status: CheckStatus,
+    /// Whether it is stored as a delta; `None` if that could not be read
+    pub delta: Option<bool>,
+    /// Size of its contents (rebuilt, for a delta), if they could be read
+    pub size: Option<u64>,
+    /// `"gzip"` or `"zstd"` for compressed contents
+    pub compression: Option<String>,
+    pub detail: String,
+}
+
+/// A failed command, for callers parsing stdout
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorReport {
+    /// [`MutxError::kind`]
+    pub kind: String,
+    pub message: String,
+    pub exit_code: i32,
+}
+
+impl From<&MutxError> for ErrorReport {
+    fn from(error: &MutxError) -> Self {
+        ErrorReport {
+            kind: error.kind().to_string(),
+            message: error.to_string(),
+            exit_code: error.exit_code(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_schema_names_itself() {
+        for (name, document) in SCHEMAS {
+            let document: serde_json::Value = serde_json::from_str(document).unwrap();
+            assert_eq!(document["$id"], *name);
+            assert_eq!(document["properties"]["schema"]["const"], *name);
+        }
+        assert!(schema("mutx.write.v0").is_none());
+    }
+
+    #[test]
+    fn test_versioned_leads_with_schema() {
+        let report = HousekeepReport::default();
+        let json = Versioned::new(HOUSEKEEP, &report).to_json().unwrap();
+        assert!(json.starts_with("{\"schema\":\"mutx.housekeep.v1\","));
+    }
+}
@@ -0,0 +1,142 @@
+//! Destinations a write is published to other than a local file.
+//!
+//! By default an [`AtomicWriter`](crate::AtomicWriter) stages its content
+//! beside the target and renames it into place. Given a [`Sink`] with
+//! `with_sink`, it instead hands the finished content to the sink, which
+//! publishes it all-or-nothing by its own means: a temp upload and rename on
+//! an SFTP server, a conditional put on an object store, or a framing
+//! protocol on a pipe. Everything before that is unchanged: the lock is
+//! still taken on the target path, and size limits, transforms, digests and
+//! required guarantees apply as for a local write.
+//!
+//! The crate ships `FdSink` (feature `fd-sink`); network sinks live in
+//! downstream crates, which implement [`Sink`] against their own clients.
+
+use crate::error::Result;
+use std::io::Read;
+
+/// Where a committed write is published
+pub trait Sink: Send {
+    /// The destination, for messages, e.g. `fd 3` or `s3://bucket/key`
+    fn destination(&self) -> String;
+
+    /// Publish `content`, exactly `len` bytes, replacing what the
+    /// destination held. On error the destination must be left as it was,
+    /// or at least in a state its readers recognize as incomplete.
+    fn publish(&mut self, content: &mut dyn Read, len: u64) -> Result<()>;
+
+    /// Whether readers of the destination see either the old or the new
+    /// content, never a mix
+    fn atomic(&self) -> bool {
+        true
+    }
+
+    /// Whether the content is on stable storage once
+    /// [`Sink::publish`] returns
+    fn durable(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(feature = "fd-sink")]
+pub use fd::FdSink;
+
+#[cfg(feature = "fd-sink")]
+mod fd {
+    use super::Sink;
+    use crate::digest::DigestAlgorithm;
+    use crate::error::{MutxError, Result};
+    use std::io::{self, Read, Write};
+
+    /// Publishes to a stream, typically an inherited file descriptor, that
+    /// cannot be renamed: atomicity is by protocol instead. The content is
+    /// framed as
+    ///
+    /// ```text
+    /// mutx-publish 1 <length>\n
+    /// <length bytes of content>
+    /// mutx-commit sha256:<hex digest of the content>\n
+    /// ```
+    ///
+    /// and the reader applies it only once the commit line has arrived and
+    /// matches, the equivalent of the rename. A write that fails or is
+    /// interrupted never sends the commit line. One frame is sent per write,
+    /// so a long-running reader can take several from the same stream.
+    pub struct FdSink<W> {
+        writer: W,
+        destination: String,
+    }
+
+    impl<W: Write + Send> FdSink<W> {
+        /// Publish to `writer`, described as `destination` in messages
+        pub fn new(writer: W, destination: impl Into<String>) -> Self {
+            FdSink {
+                writer,
+                destination: destination.into(),
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    impl FdSink<std::fs::File> {
+        /// Publish to the inherited file descriptor `fd`, e.g. 3 from
+        /// `3>&1` or `3>>FILE`. Fails if it is not open.
+        pub fn from_fd(fd: i32) -> Result<Self> {
+            use std::os::unix::io::FromRawFd;
+
+            if unsafe { libc::fcntl(fd, libc::F_GETFD) } == -1 {
+                return Err(MutxError::Other(format!(
+                    "File descriptor {} is not open (run with {}>&1 or {}>FILE)",
+                    fd, fd, fd
+                )));
+            }
+            // SAFETY: the descriptor is open and nothing else in mutx uses it
+            let file = unsafe { std::fs::File::from_raw_fd(fd) };
+            Ok(FdSink::new(file, format!("fd {}", fd)))
+        }
+    }
+
+    impl<W: Write + Send> Sink for FdSink<W> {
+        fn destination(&self) -> String {
+            self.destination.clone()
+        }
+
+        fn publish(&mut self, content: &mut dyn Read, len: u64) -> Result<()> {
+            let failed = |source: io::Error| {
+                MutxError::Other(format!(
+                    "Cannot publish to {}: {}",
+                    self.destination, source
+                ))
+            };
+            let mut hasher = DigestAlgorithm::Sha256.hasher();
+            let mut frame = || -> io::Result<()> {
+                writeln!(self.writer, "mutx-publish 1 {}", len)?;
+                let mut buf = [0u8; 64 * 1024];
+                let mut sent = 0u64;
+                loop {
+                    let n = match content.read(&mut buf) {
+                        Ok(0) => break,
+                        Ok(n) => n,
+                        Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                        Err(e) => return Err(e),
+                    };
+                    hasher.update(&buf[..n]);
+                    self.writer.write_all(&buf[..n])?;
+                    sent += n as u64;
+                }
+                if sent != len {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        format!("staged content is {} bytes, expected {}", sent, len),
+                    ));
+                }
+                Ok(())
+            };
+            frame().map_err(failed)?;
+
+            writeln!(self.writer, "mutx-commit sha256:{}", hasher.finish_hex())
+                .and_then(|()| self.writer.flush())
+                .map_err(failed)
+        }
+    }
+}
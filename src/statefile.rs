@@ -0,0 +1,143 @@
+//! Files mutx maintains about its own work, such as the sequence counter of
+//! a fair lock queue.
+//!
+//! Several mutx processes may update the same state file at once, so every
+//! update goes through the same machinery as user writes: an exclusive
+//! [`FileLock`] on the sidecar `<file>.lock`, then an atomic replacement
+//! through [`AtomicWriter`]. A writer killed mid-update leaves the previous
+//! contents in place.
+
+use crate::error::{MutxError, Result};
+use crate::lock::scope::sidecar_lock_path;
+use crate::lock::{FileLock, LockStrategy};
+use crate::write::{AtomicWriter, WriteMode};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use tracing::debug;
+
+/// A state file shared between mutx processes
+#[derive(Debug, Clone)]
+pub struct StateFile {
+    path: PathBuf,
+    lock_path: PathBuf,
+}
+
+impl StateFile {
+    /// The state file at `path`, locked through `<path>.lock`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let lock_path = sidecar_lock_path(&path);
+        StateFile { path, lock_path }
+    }
+
+    /// Increment the generation counter stored in the file and return the
+    /// new value; a missing or empty file counts from 0
+    pub fn next_generation(&self) -> Result<u64> {
+        let mut next = 0;
+        self.update(|current| {
+            let current = std::str::from_utf8(current.unwrap_or_default())
+                .ok()
+                .map(str::trim)
+                .unwrap_or("");
+            let generation = if current.is_empty() {
+                0
+            } else {
+                current.parse::<u64>().map_err(|_| {
+                    MutxError::Other(format!(
+                        "Invalid generation counter in {}: {:?}",
+                        self.path.display(),
+                        current
+                    ))
+                })?
+            };
+            next = generation + 1;
+            Ok(format!("{}\n", next).into_bytes())
+        })?;
+        Ok(next)
+    }
+
+    /// Replace the whole file with what `update` makes of its current
+    /// contents (`None` if it does not exist yet), holding the lock
+    /// throughout
+    fn update<F>(&self, update: F) -> Result<()>
+    where
+        F: FnOnce(Option<&[u8]>) -> Result<Vec<u8>>,
+    {
+        self.ensure_parent()?;
+        debug!("Locking state file {}", self.path.display());
+        let _lock = FileLock::acquire(&self.lock_path, LockStrategy::Wait.break_stale())?;
+
+        let current = self.read()?;
+        let contents = update(current.as_deref())?;
+        let mut writer = AtomicWriter::new(&self.path, WriteMode::InMemory)?;
+        writer.write_all(&contents)?;
+        writer.commit()?;
+        Ok(())
+    }
+
+    fn read(&self) -> Result<Option<Vec<u8>>> {
+        match fs::read(&self.path) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(MutxError::ReadFailed {
+                path: self.path.clone(),
+                source: e,
+            }),
+        }
+    }
+
+    fn ensure_parent(&self) -> Result<()> {
+        match self.path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => {
+                fs::create_dir_all(parent).map_err(|e| MutxError::WriteFailed {
+                    path: parent.to_path_buf(),
+                    source: e,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_concurrent_generations_are_unique() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("generation");
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let state = StateFile::new(&path);
+                thread::spawn(move || {
+                    (0..10)
+                        .map(|_| state.next_generation().unwrap())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let mut generations: Vec<u64> = handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect();
+        generations.sort_unstable();
+
+        assert_eq!(generations, (1..=80).collect::<Vec<_>>());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "80\n");
+    }
+
+    #[test]
+    fn test_invalid_generation_is_kept() {
+        let temp = TempDir::new().unwrap();
+        let state = StateFile::new(temp.path().join("generation"));
+        fs::write(&state.path, "seven").unwrap();
+
+        assert!(state.next_generation().is_err());
+        assert_eq!(fs::read_to_string(&state.path).unwrap(), "seven");
+    }
+}
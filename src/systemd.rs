@@ -0,0 +1,152 @@
+//! Minimal `sd_notify` client for running under systemd.
+//!
+//! Services that call mutx from `ExecStartPre=` (or as `Type=notify` units) can
+//! be killed by start timeouts while mutx waits for a lock or streams a large
+//! input. [`Notifier::keepalive`] runs a background thread that periodically
+//! sends `EXTEND_TIMEOUT_USEC` (and `WATCHDOG=1` when a watchdog is armed) so
+//! systemd keeps waiting for as long as mutx is making progress.
+
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Default interval between keepalive messages
+pub const DEFAULT_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Connection to the systemd notification socket named by `$NOTIFY_SOCKET`
+#[derive(Debug, Clone)]
+pub struct Notifier {
+    socket_path: String,
+    watchdog: Option<Duration>,
+}
+
+impl Notifier {
+    /// Create a notifier for an explicit socket path, with no watchdog
+    pub fn new(socket_path: impl Into<String>) -> Self {
+        Notifier {
+            socket_path: socket_path.into(),
+            watchdog: None,
+        }
+    }
+
+    /// Also send `WATCHDOG=1`, at least twice per `period`
+    pub fn with_watchdog(mut self, period: Duration) -> Self {
+        self.watchdog = Some(period);
+        self
+    }
+
+    /// Create a notifier from the environment.
+    ///
+    /// Returns `None` when not running under systemd (no `$NOTIFY_SOCKET`).
+    pub fn from_env() -> Option<Self> {
+        let socket_path = std::env::var("NOTIFY_SOCKET").ok()?;
+        if socket_path.is_empty() {
+            return None;
+        }
+
+        // The watchdog applies to us only if WATCHDOG_PID is unset or our PID
+        let watchdog_pid_matches = std::env::var("WATCHDOG_PID")
+            .ok()
+            .and_then(|pid| pid.parse::<u32>().ok())
+            .map_or(true, |pid| pid == std::process::id());
+        let watchdog = std::env::var("WATCHDOG_USEC")
+            .ok()
+            .and_then(|usec| usec.parse::<u64>().ok())
+            .filter(|usec| *usec > 0 && watchdog_pid_matches)
+            .map(Duration::from_micros);
+
+        Some(Notifier {
+            socket_path,
+            watchdog,
+        })
+    }
+
+    /// Send a raw notification (newline-separated `KEY=VALUE` assignments)
+    pub fn notify(&self, state: &str) -> std::io::Result<()> {
+        send(&self.socket_path, state)
+    }
+
+    /// Start sending keepalive messages every `interval` (shortened to half
+    /// the watchdog period if one is armed) until the returned guard is dropped.
+    ///
+    /// Each message extends the current systemd timeout by three intervals and
+    /// carries `status` as the unit's status text.
+    pub fn keepalive(&self, interval: Duration, status: &str) -> Keepalive {
+        let interval = match self.watchdog {
+            Some(watchdog) => interval.min(watchdog / 2),
+            None => interval,
+        };
+
+        let mut message = format!(
+            "EXTEND_TIMEOUT_USEC={}\nSTATUS={}",
+            (interval * 3).as_micros(),
+            status
+        );
+        if self.watchdog.is_some() {
+            message.push_str("\nWATCHDOG=1");
+        }
+
+        let notifier = self.clone();
+        let (stop, stopped) = mpsc::channel::<()>();
+        let thread = std::thread::spawn(move || loop {
+            if let Err(e) = notifier.notify(&message) {
+                warn!("Failed to notify systemd: {}", e);
+            }
+            match stopped.recv_timeout(interval) {
+                Err(RecvTimeoutError::Timeout) => continue,
+                _ => break,
+            }
+        });
+
+        debug!("Started systemd keepalive every {:?}", interval);
+        Keepalive {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+}
+
+/// Background keepalive; stops when dropped
+#[derive(Debug)]
+pub struct Keepalive {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for Keepalive {
+    fn drop(&mut self) {
+        // Dropping the sender wakes the thread immediately
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(unix)]
+fn send(socket_path: &str, state: &str) -> std::io::Result<()> {
+    use std::os::unix::net::UnixDatagram;
+
+    let socket = UnixDatagram::unbound()?;
+
+    // Names starting with '@' are Linux abstract namespace sockets
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    if let Some(name) = socket_path.strip_prefix('@') {
+        #[cfg(target_os = "android")]
+        use std::os::android::net::SocketAddrExt;
+        #[cfg(target_os = "linux")]
+        use std::os::linux::net::SocketAddrExt;
+        let addr = std::os::unix::net::SocketAddr::from_abstract_name(name.as_bytes())?;
+        socket.send_to_addr(state.as_bytes(), &addr)?;
+        return Ok(());
+    }
+
+    socket.send_to(state.as_bytes(), socket_path)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn send(_socket_path: &str, _state: &str) -> std::io::Result<()> {
+    Ok(())
+}
@@ -0,0 +1,235 @@
+//! Fixtures for testing applications that embed mutx (feature `test-util`).
+//!
+//! These build the awkward states that are hard to reach on purpose: a lock
+//! another writer is holding, a lock left behind by a crashed one, a
+//! directory of backups of known ages, and a reader checking that a file is
+//! only ever seen whole while it is being replaced.
+//!
+//! The assertion helpers panic like `assert!`, so they belong in tests only.
+
+use crate::backup::BackupSuffix;
+use crate::error::{MutxError, Result};
+use crate::lock::{derive_lock_path, ensure_lock_dir, FileLock, LockHolder, LockStrategy};
+use crate::utils::temp::is_mutx_temp;
+use chrono::{DateTime, Local};
+use filetime::FileTime;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+/// PID recorded in fake orphaned locks: above any kernel's `pid_max`, so
+/// never a running process
+const DEAD_PID: u32 = i32::MAX as u32;
+
+/// Take the lock mutx derives for `output`, so writers find it contended
+/// until the returned lock is dropped. Fails if it is already held.
+pub fn hold_lock(output: &Path) -> Result<FileLock> {
+    FileLock::acquire(&derive_lock_path(output, false)?, LockStrategy::NoWait)
+}
+
+/// [`hold_lock`] for `duration`, then release it from a background thread.
+/// The lock is held by the time this returns; join the handle to wait for
+/// the release.
+pub fn hold_lock_for(output: &Path, duration: Duration) -> Result<JoinHandle<()>> {
+    let lock = hold_lock(output)?;
+    Ok(thread::spawn(move || {
+        thread::sleep(duration);
+        drop(lock);
+    }))
+}
+
+/// Leave the lock file for `output` as a crashed writer would: present, not
+/// held, last touched `age` ago and naming a holder that no longer runs.
+/// Returns the lock file's path.
+pub fn orphaned_lock(output: &Path, age: Duration) -> Result<PathBuf> {
+    let lock_path = derive_lock_path(output, false)?;
+    ensure_lock_dir(&lock_path)?;
+
+    let holder = LockHolder {
+        pid: DEAD_PID,
+        target: Some(output.to_path_buf()),
+        ..LockHolder::current()
+    };
+    let payload = serde_json::to_vec(&holder)
+        .map_err(|e| MutxError::Other(format!("Cannot encode lock holder: {}", e)))?;
+    fs::write(&lock_path, payload).map_err(|e| MutxError::WriteFailed {
+        path: lock_path.clone(),
+        source: e,
+    })?;
+    set_age(&lock_path, age)?;
+    Ok(lock_path)
+}
+
+/// Timestamped backups of one file with chosen ages, named and dated the way
+/// `--backup --backup-timestamp` would have left them
+#[derive(Debug, Clone)]
+pub struct BackupTree {
+    target: PathBuf,
+    suffix: BackupSuffix,
+    directory: Option<PathBuf>,
+}
+
+impl BackupTree {
+    /// Backups of `target` beside it, with the default suffix
+    pub fn new(target: impl Into<PathBuf>) -> Self {
+        BackupTree {
+            target: target.into(),
+            suffix: BackupSuffix::default(),
+            directory: None,
+        }
+    }
+
+    pub fn with_suffix(mut self, suffix: BackupSuffix) -> Self {
+        self.suffix = suffix;
+        self
+    }
+
+    /// Put the backups in a central directory instead, like `--backup-dir`
+    pub fn with_directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.directory = Some(directory.into());
+        self
+    }
+
+    /// Create one backup holding `contents`, made `age` ago. Backups are
+    /// named to the second, so ages must differ by at least a second.
+    pub fn add(&self, age: Duration, contents: &[u8]) -> Result<PathBuf> {
+        let made = SystemTime::now() - age;
+        let stamp = DateTime::<Local>::from(made).format("%Y%m%d_%H%M%S");
+        let name = self
+            .target
+            .file_name()
+            .ok_or_else(|| MutxError::Other("Invalid backup target filename".to_string()))?
+            .to_string_lossy();
+        let dir = match &self.directory {
+            Some(dir) => dir.as_path(),
+            None => self
+                .target
+                .parent()
+                .ok_or_else(|| MutxError::Other("Backup target has no parent".to_string()))?,
+        };
+        fs::create_dir_all(dir).map_err(|e| MutxError::WriteFailed {
+            path: dir.to_path_buf(),
+            source: e,
+        })?;
+
+        let path = dir.join(format!("{}.{}{}", name, stamp, self.suffix));
+        fs::write(&path, contents).map_err(|e| MutxError::WriteFailed {
+            path: path.clone(),
+            source: e,
+        })?;
+        set_age(&path, age)?;
+        Ok(path)
+    }
+
+    /// One backup per age, each holding its position (`backup 0`,
+    /// `backup 1`, ...). Returns the paths in the order given.
+    pub fn add_all(&self, ages: &[Duration]) -> Result<Vec<PathBuf>> {
+        ages.iter()
+            .enumerate()
+            .map(|(i, age)| self.add(*age, format!("backup {}", i).as_bytes()))
+            .collect()
+    }
+}
+
+/// A reader thread that keeps reading a file while it is being written and
+/// checks every read against the contents it may legitimately hold
+pub struct AtomicityWatcher {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<std::result::Result<usize, String>>,
+}
+
+impl AtomicityWatcher {
+    /// Start reading `path`, which must only ever contain one of `allowed`.
+    /// It may be missing until it first appears, but never afterwards.
+    pub fn start(path: impl Into<PathBuf>, allowed: Vec<Vec<u8>>) -> Self {
+        let path = path.into();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::clone(&stop);
+        let thread = thread::spawn(move || {
+            let mut reads = 0;
+            let mut seen = false;
+            while !stopped.load(Ordering::Relaxed) {
+                match fs::read(&path) {
+                    Ok(contents) if allowed.contains(&contents) => seen = true,
+                    Ok(contents) => {
+                        return Err(format!(
+                            "{} held unexpected contents: {:?}",
+                            path.display(),
+                            String::from_utf8_lossy(&contents)
+                        ))
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::NotFound && !seen => {}
+                    Err(e) => return Err(format!("{} could not be read: {}", path.display(), e)),
+                }
+                reads += 1;
+                thread::yield_now();
+            }
+            Ok(reads)
+        });
+        AtomicityWatcher { stop, thread }
+    }
+
+    /// Stop reading and return how many reads were checked.
+    ///
+    /// # Panics
+    ///
+    /// If any read saw contents outside the allowed set, or the file
+    /// disappeared after it had appeared.
+    pub fn finish(self) -> usize {
+        self.stop.store(true, Ordering::Relaxed);
+        match self.thread.join().expect("atomicity watcher panicked") {
+            Ok(reads) => reads,
+            Err(violation) => panic!("write was not atomic: {}", violation),
+        }
+    }
+}
+
+/// Assert that `path` holds one of `allowed`
+///
+/// # Panics
+///
+/// If it cannot be read or holds anything else
+#[track_caller]
+pub fn assert_contents_one_of(path: &Path, allowed: &[&[u8]]) {
+    let contents =
+        fs::read(path).unwrap_or_else(|e| panic!("cannot read {}: {}", path.display(), e));
+    assert!(
+        allowed.contains(&contents.as_slice()),
+        "{} holds unexpected contents: {:?}",
+        path.display(),
+        String::from_utf8_lossy(&contents)
+    );
+}
+
+/// Assert that no mutx temporary file is left in `dir`
+///
+/// # Panics
+///
+/// If `dir` cannot be listed or holds a temp, naming the leftovers
+#[track_caller]
+pub fn assert_no_temp_files(dir: &Path) {
+    let leftovers: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("cannot list {}: {}", dir.display(), e))
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| is_mutx_temp(path))
+        .collect();
+    assert!(
+        leftovers.is_empty(),
+        "temporary files left in {}: {:?}",
+        dir.display(),
+        leftovers
+    );
+}
+
+/// Backdate the mtime of `path` by `age`
+fn set_age(path: &Path, age: Duration) -> Result<()> {
+    let mtime = FileTime::from_system_time(SystemTime::now() - age);
+    filetime::set_file_mtime(path, mtime).map_err(|e| MutxError::WriteFailed {
+        path: path.to_path_buf(),
+        source: e,
+    })
+}
@@ -0,0 +1,206 @@
+//! Streaming content transforms applied between the input and the temp file.
+//!
+//! A [`Transform`] receives the data chunk by chunk as it is written and emits
+//! replacement bytes, so normalization, templating or compression can run
+//! without buffering the whole input. Transforms are chained on an
+//! [`AtomicWriter`](crate::AtomicWriter) with `with_transform`, each feeding
+//! the next. A [`TransformRegistry`] maps names to constructors; the CLI's
+//! `--transform` option looks names up in [`TransformRegistry::default`], and
+//! downstream crates can register their own.
+
+use crate::error::{MutxError, Result};
+use std::collections::BTreeMap;
+
+/// One stage of the write pipeline
+pub trait Transform: Send {
+    /// Process one chunk of input, appending the output to `out`.
+    ///
+    /// Chunk boundaries are arbitrary; a transform that needs to look ahead
+    /// keeps the unfinished part and emits it on a later call or in
+    /// [`Transform::finish`].
+    fn transform(&mut self, chunk: &[u8], out: &mut Vec<u8>) -> Result<()>;
+
+    /// Called once after the last chunk to emit any held-back output
+    fn finish(&mut self, _out: &mut Vec<u8>) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Constructor for a registered transform
+pub type TransformFactory = Box<dyn Fn() -> Box<dyn Transform> + Send + Sync>;
+
+/// Named transforms available to a write
+pub struct TransformRegistry {
+    factories: BTreeMap<String, TransformFactory>,
+}
+
+impl TransformRegistry {
+    /// A registry without any transforms
+    pub fn empty() -> Self {
+        TransformRegistry {
+            factories: BTreeMap::new(),
+        }
+    }
+
+    /// Register `factory` under `name`, replacing any transform of that name
+    pub fn register<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn() -> Box<dyn Transform> + Send + Sync + 'static,
+    {
+        self.factories.insert(name.to_string(), Box::new(factory));
+    }
+
+    /// A fresh instance of the transform registered as `name`
+    pub fn create(&self, name: &str) -> Result<Box<dyn Transform>> {
+        match self.factories.get(name) {
+            Some(factory) => Ok(factory()),
+            None => Err(MutxError::Other(format!(
+                "Unknown transform '{}': expected one of {}",
+                name,
+                self.names().collect::<Vec<_>>().join(", ")
+            ))),
+        }
+    }
+
+    /// Registered transform names, sorted
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.factories.keys().map(String::as_str)
+    }
+}
+
+impl Default for TransformRegistry {
+    /// The built-in transforms: `lf` and `trailing-newline`
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register("lf", || Box::new(LineFeeds::default()));
+        registry.register("trailing-newline", || Box::new(TrailingNewline::default()));
+        registry
+    }
+}
+
+/// Convert CRLF line endings to LF (`lf`)
+#[derive(Debug, Default)]
+pub struct LineFeeds {
+    /// A `\r` ending the previous chunk, not yet known to start a CRLF
+    pending_cr: bool,
+}
+
+impl Transform for LineFeeds {
+    fn transform(&mut self, chunk: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        for &byte in chunk {
+            if self.pending_cr && byte != b'\n' {
+                out.push(b'\r');
+            }
+            self.pending_cr = byte == b'\r';
+            if !self.pending_cr {
+                out.push(byte);
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut Vec<u8>) -> Result<()> {
+        if std::mem::take(&mut self.pending_cr) {
+            out.push(b'\r');
+        }
+        Ok(())
+    }
+}
+
+/// Make sure non-empty output ends with a newline (`trailing-newline`)
+#[derive(Debug, Default)]
+pub struct TrailingNewline {
+    last: Option<u8>,
+}
+
+impl Transform for TrailingNewline {
+    fn transform(&mut self, chunk: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        if let Some(&last) = chunk.last() {
+            self.last = Some(last);
+        }
+        out.extend_from_slice(chunk);
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut Vec<u8>) -> Result<()> {
+        if matches!(self.last, Some(last) if last != b'\n') {
+            out.push(b'\n');
+        }
+        Ok(())
+    }
+}
+
+/// Run `chunk` through every transform in turn
+pub(crate) fn apply(transforms: &mut [Box<dyn Transform>], chunk: &[u8]) -> Result<Vec<u8>> {
+    let mut data = chunk.to_vec();
+    for transform in transforms.iter_mut() {
+        let mut out = Vec::with_capacity(data.len());
+        transform.transform(&data, &mut out)?;
+        data = out;
+    }
+    Ok(data)
+}
+
+/// Finish every transform in turn, passing each one's held-back output
+/// through the rest of the chain
+pub(crate) fn finish(transforms: &mut [Box<dyn Transform>]) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    for transform in transforms.iter_mut() {
+        let mut out = Vec::new();
+        if !data.is_empty() {
+            transform.transform(&data, &mut out)?;
+        }
+        transform.finish(&mut out)?;
+        data = out;
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(transform: &mut dyn Transform, chunks: &[&[u8]]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for chunk in chunks {
+            transform.transform(chunk, &mut out).unwrap();
+        }
+        transform.finish(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_line_feeds_across_chunk_boundary() {
+        let out = run(&mut LineFeeds::default(), &[b"a\r", b"\nb\rc\r"]);
+        assert_eq!(out, b"a\nb\rc\r");
+    }
+
+    #[test]
+    fn test_trailing_newline() {
+        assert_eq!(run(&mut TrailingNewline::default(), &[b"a", b""]), b"a\n");
+        assert_eq!(run(&mut TrailingNewline::default(), &[b"a\n"]), b"a\n");
+        assert_eq!(run(&mut TrailingNewline::default(), &[]), b"");
+    }
+
+    #[test]
+    fn test_finish_feeds_held_output_down_the_chain() {
+        let mut chain: Vec<Box<dyn Transform>> = vec![
+            Box::new(TrailingNewline::default()),
+            Box::new(LineFeeds::default()),
+        ];
+        let mut out = apply(&mut chain, b"x\r").unwrap();
+        out.extend(finish(&mut chain).unwrap());
+        // TrailingNewline's "\n" completes the CRLF held by LineFeeds
+        assert_eq!(out, b"x\n");
+    }
+
+    #[test]
+    fn test_unknown_transform_lists_names() {
+        let err = TransformRegistry::default()
+            .create("gzip")
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.contains("lf, trailing-newline"));
+    }
+}
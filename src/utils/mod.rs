@@ -1,5 +1,18 @@
-mod duration;
+pub mod disk;
+pub mod path;
+pub mod process;
 pub mod symlink;
+pub mod temp;
+pub mod xattr;
 
-pub use duration::parse_duration;
-pub use symlink::{check_lock_symlink, check_symlink};
+pub use crate::parse::{format_size, parse_duration, parse_size};
+pub use path::{ensure_within, names_directory, normalize_path, to_nfc};
+pub use process::pid_is_alive;
+pub use symlink::{
+    apply_nofollow, check_lock_symlink, check_symlink, resolve_symlink_target, verify_not_link,
+    SymlinkPolicy,
+};
+pub use temp::{
+    is_mutx_scratch, is_mutx_temp, temp_owner_pid, temp_path_for, temp_target_name,
+    unique_scratch_path, unique_temp_path, SCRATCH_SUFFIX, TEMP_SUFFIX,
+};
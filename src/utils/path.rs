@@ -0,0 +1,154 @@
+use crate::error::{MutxError, Result};
+use std::path::{Component, Path, PathBuf};
+use unicode_normalization::UnicodeNormalization;
+
+/// Normalize a name to Unicode NFC.
+///
+/// macOS hands out decomposed (NFD) filenames while most other sources produce
+/// composed (NFC) ones. Generated lock and backup names, and every comparison
+/// between them, go through this so both spellings of a filename agree.
+pub fn to_nfc(name: &str) -> String {
+    name.nfc().collect()
+}
+
+/// Lexically normalize a path: drop `.` components and fold `..` into the
+/// preceding component. `..` that would climb above a root is discarded; a
+/// leading `..` on a relative path is kept, and a relative path that folds
+/// away entirely becomes `.`. A trailing separator is dropped (see
+/// [`names_directory`]).
+///
+/// The filesystem is not consulted, so `link/..` folds to the directory
+/// containing `link` even when `link` is a symlink to a directory elsewhere.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => {}
+                _ => normalized.push(".."),
+            },
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+
+    if normalized.as_os_str().is_empty() {
+        normalized.push(".");
+    }
+    normalized
+}
+
+/// Whether `path` is spelled as a directory: it ends in a separator or in a
+/// `.` or `..` component, all of which [`normalize_path`] and
+/// [`Path::components`] drop.
+pub fn names_directory(path: &Path) -> bool {
+    let raw = path.as_os_str().to_string_lossy();
+    let trimmed = raw.trim_end_matches(std::path::is_separator);
+    if trimmed.len() != raw.len() {
+        return true;
+    }
+    let last = trimmed.rsplit(std::path::is_separator).next().unwrap_or("");
+    last == "." || last == ".."
+}
+
+/// Resolve a path that may not exist yet: make it absolute, normalize it
+/// lexically, then canonicalize the longest existing ancestor (following any
+/// symlinks in it) and re-append the remaining components.
+pub(crate) fn resolve_best_effort(path: &Path) -> Result<PathBuf> {
+    if let Ok(canonical) = path.canonicalize() {
+        return Ok(canonical);
+    }
+
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().map_err(MutxError::Io)?.join(path)
+    };
+    let absolute = normalize_path(&absolute);
+
+    let mut existing = absolute.as_path();
+    let mut remainder = Vec::new();
+    while let Some(parent) = existing.parent() {
+        if let Some(name) = existing.file_name() {
+            remainder.push(name.to_os_string());
+        }
+        existing = parent;
+        if let Ok(canonical) = existing.canonicalize() {
+            let mut resolved = canonical;
+            for name in remainder.iter().rev() {
+                resolved.push(name);
+            }
+            return Ok(resolved);
+        }
+    }
+
+    Ok(absolute)
+}
+
+/// Ensure `path` stays inside `base` once `.`/`..` are resolved and symlinks in
+/// existing ancestors are followed.
+///
+/// Returns the resolved path on success and [`MutxError::PathEscapes`] when the
+/// path would land outside `base` (for example through a `..` in a filename or
+/// suffix, or a symlinked subdirectory pointing elsewhere).
+pub fn ensure_within(path: &Path, base: &Path) -> Result<PathBuf> {
+    let resolved_base = resolve_best_effort(base)?;
+    let resolved = resolve_best_effort(path)?;
+
+    if resolved == resolved_base || !resolved.starts_with(&resolved_base) {
+        return Err(MutxError::PathEscapes {
+            path: path.to_path_buf(),
+            base: base.to_path_buf(),
+        });
+    }
+
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_nfc_composes_decomposed_input() {
+        // "café" with a combining acute accent (NFD) vs precomposed é (NFC)
+        assert_eq!(to_nfc("cafe\u{301}"), "caf\u{e9}");
+        assert_eq!(to_nfc("caf\u{e9}"), "caf\u{e9}");
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(
+            normalize_path(Path::new("/a/./b/../c")),
+            PathBuf::from("/a/c")
+        );
+        assert_eq!(normalize_path(Path::new("/../a")), PathBuf::from("/a"));
+        assert_eq!(
+            normalize_path(Path::new("../a/b/..")),
+            PathBuf::from("../a")
+        );
+        assert_eq!(
+            normalize_path(Path::new("a/b/../../..")),
+            PathBuf::from("..")
+        );
+        assert_eq!(normalize_path(Path::new("a/..")), PathBuf::from("."));
+        assert_eq!(
+            normalize_path(Path::new("dir/./file.txt/")),
+            PathBuf::from("dir/file.txt")
+        );
+    }
+
+    #[test]
+    fn test_names_directory() {
+        for path in ["file.txt/", "dir/.", "dir/..", ".", "..", "/"] {
+            assert!(names_directory(Path::new(path)), "{path}");
+        }
+        for path in ["file.txt", "dir/./file.txt", "dir/../file.txt", "/a"] {
+            assert!(!names_directory(Path::new(path)), "{path}");
+        }
+    }
+}
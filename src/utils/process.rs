@@ -0,0 +1,128 @@
+/// Check whether a process with the given PID exists on this host.
+///
+/// Returns `None` when liveness cannot be determined on this platform, so
+/// callers can fall back to other staleness signals (such as lock age).
+pub fn pid_is_alive(pid: u32) -> Option<bool> {
+    #[cfg(unix)]
+    {
+        let Ok(pid) = libc::pid_t::try_from(pid) else {
+            return Some(false);
+        };
+        if pid <= 0 {
+            return Some(false);
+        }
+        // Signal 0 performs the permission and existence checks only
+        let rc = unsafe { libc::kill(pid, 0) };
+        if rc == 0 {
+            return Some(true);
+        }
+        match std::io::Error::last_os_error().raw_os_error() {
+            Some(libc::ESRCH) => Some(false),
+            // EPERM: the process exists but belongs to someone else
+            Some(libc::EPERM) => Some(true),
+            _ => None,
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = pid;
+        None
+    }
+}
+
+/// Ask the process `pid` on this host to exit, with `SIGTERM`
+pub fn terminate(pid: u32) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        let pid = libc::pid_t::try_from(pid)
+            .ok()
+            .filter(|pid| *pid > 0)
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::InvalidInput))?;
+        if unsafe { libc::kill(pid, libc::SIGTERM) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = pid;
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Signalling processes is not supported on this platform",
+        ))
+    }
+}
+
+/// When the process `pid` on this host started, in clock ticks since boot.
+/// Together with the PID this names one process, as PIDs are reused.
+/// `None` when the process is gone or this platform does not tell.
+pub fn process_start_time(pid: u32) -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        // The command name is in parentheses and may itself contain them;
+        // the start time is the 20th field after it
+        let (_, fields) = stat.rsplit_once(')')?;
+        fields.split_whitespace().nth(19)?.parse().ok()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = pid;
+        None
+    }
+}
+
+/// Name of this host, if it can be determined
+pub fn hostname() -> Option<String> {
+    #[cfg(unix)]
+    {
+        let mut buf = [0u8; 256];
+        let rc = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) };
+        if rc != 0 {
+            return None;
+        }
+        let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+        Some(String::from_utf8_lossy(&buf[..len]).into_owned()).filter(|name| !name.is_empty())
+    }
+
+    #[cfg(not(unix))]
+    {
+        std::env::var("COMPUTERNAME").ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_current_process_is_alive() {
+        assert_eq!(pid_is_alive(std::process::id()), Some(true));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_invalid_pid_is_not_alive() {
+        assert_eq!(pid_is_alive(0), Some(false));
+        assert_eq!(pid_is_alive(u32::MAX), Some(false));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_start_time_names_one_process() {
+        let started = process_start_time(std::process::id());
+        assert!(started.is_some());
+        assert_eq!(process_start_time(std::process::id()), started);
+        assert_eq!(process_start_time(u32::MAX), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hostname_is_known() {
+        assert!(hostname().is_some());
+    }
+}
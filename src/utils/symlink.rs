@@ -1,5 +1,62 @@
 use crate::error::{MutxError, Result};
-use std::path::Path;
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+/// `FILE_FLAG_OPEN_REPARSE_POINT`: open the reparse point itself, not its target
+#[cfg(windows)]
+const FILE_FLAG_OPEN_REPARSE_POINT: u32 = 0x0020_0000;
+
+/// `FILE_ATTRIBUTE_REPARSE_POINT`: set on symlinks and junctions
+#[cfg(windows)]
+const FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x0000_0400;
+
+/// Configure `opts` so the final path component is never followed if it is a link.
+///
+/// On Unix this sets `O_NOFOLLOW`, which makes the open fail with `ELOOP`.
+/// On Windows it sets `FILE_FLAG_OPEN_REPARSE_POINT`, which opens the link itself;
+/// pair it with [`verify_not_link`] to reject the handle.
+pub fn apply_nofollow(opts: &mut OpenOptions) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        opts.custom_flags(libc::O_NOFOLLOW);
+    }
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::OpenOptionsExt;
+        opts.custom_flags(FILE_FLAG_OPEN_REPARSE_POINT);
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    let _ = opts;
+}
+
+/// Check that a handle opened with [`apply_nofollow`] does not refer to a link.
+///
+/// Inspects the open handle rather than the path, so there is no window between
+/// the check and later use of the file.
+pub fn verify_not_link(
+    file: &File,
+    path: &Path,
+    on_link: impl FnOnce(PathBuf) -> MutxError,
+) -> Result<()> {
+    let metadata = file.metadata().map_err(MutxError::Io)?;
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        if metadata.file_attributes() & FILE_ATTRIBUTE_REPARSE_POINT != 0 {
+            return Err(on_link(path.to_path_buf()));
+        }
+    }
+
+    if metadata.file_type().is_symlink() {
+        return Err(on_link(path.to_path_buf()));
+    }
+
+    Ok(())
+}
 
 /// Check if a path is a symlink and validate against policy
 pub fn check_symlink(path: &Path, follow_symlinks: bool) -> Result<()> {
@@ -22,6 +79,53 @@ pub fn check_symlink(path: &Path, follow_symlinks: bool) -> Result<()> {
     }
 }
 
+/// Longest symlink chain followed before giving up, matching Linux's `ELOOP` limit
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// What a write through a symlinked output replaces
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Replace the file the link points to, leaving the link in place
+    #[default]
+    WriteThrough,
+    /// Replace the link itself with a regular file; the old target is untouched
+    Replace,
+}
+
+impl SymlinkPolicy {
+    /// The path a write to `path` should replace under this policy
+    pub fn target_for(self, path: &Path) -> Result<PathBuf> {
+        match self {
+            SymlinkPolicy::WriteThrough => resolve_symlink_target(path),
+            SymlinkPolicy::Replace => Ok(path.to_path_buf()),
+        }
+    }
+}
+
+/// Follow `path` through any chain of symlinks in its final component.
+///
+/// Relative link targets are resolved against the directory containing the
+/// link, as the kernel does. Unlike [`Path::canonicalize`] the final target
+/// need not exist, so a dangling link resolves to the file it would create.
+/// Returns `path` unchanged if it is not a symlink.
+pub fn resolve_symlink_target(path: &Path) -> Result<PathBuf> {
+    let mut current = path.to_path_buf();
+    for _ in 0..MAX_SYMLINK_HOPS {
+        match current.symlink_metadata() {
+            Ok(metadata) if metadata.file_type().is_symlink() => {}
+            _ => return Ok(current),
+        }
+        let link = std::fs::read_link(&current).map_err(MutxError::Io)?;
+        current = match current.parent() {
+            Some(parent) if link.is_relative() => parent.join(link),
+            _ => link,
+        };
+    }
+    Err(MutxError::SymlinkLoop {
+        path: path.to_path_buf(),
+    })
+}
+
 /// Check if a lock path is a symlink (stricter check)
 pub fn check_lock_symlink(path: &Path, follow_lock_symlinks: bool) -> Result<()> {
     // If path doesn't exist, it's not a symlink
@@ -48,6 +152,41 @@ mod tests {
     use std::fs;
     use tempfile::TempDir;
 
+    #[test]
+    #[cfg(unix)]
+    fn test_resolve_relative_chain() {
+        use std::os::unix::fs::symlink;
+
+        let temp = TempDir::new().unwrap();
+        fs::create_dir(temp.path().join("sub")).unwrap();
+        symlink("../real.txt", temp.path().join("sub/inner")).unwrap();
+        symlink("sub/inner", temp.path().join("outer")).unwrap();
+
+        let resolved = resolve_symlink_target(&temp.path().join("outer")).unwrap();
+        assert_eq!(resolved, temp.path().join("sub/../real.txt"));
+        assert_eq!(
+            SymlinkPolicy::Replace
+                .target_for(&temp.path().join("outer"))
+                .unwrap(),
+            temp.path().join("outer")
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_resolve_loop_fails() {
+        use std::os::unix::fs::symlink;
+
+        let temp = TempDir::new().unwrap();
+        symlink("b", temp.path().join("a")).unwrap();
+        symlink("a", temp.path().join("b")).unwrap();
+
+        assert!(matches!(
+            resolve_symlink_target(&temp.path().join("a")),
+            Err(MutxError::SymlinkLoop { .. })
+        ));
+    }
+
     #[test]
     fn test_nonexistent_path_allowed() {
         let temp = TempDir::new().unwrap();
@@ -57,6 +196,37 @@ mod tests {
         assert!(check_lock_symlink(&path, false).is_ok());
     }
 
+    #[test]
+    fn test_nofollow_open_accepts_regular_file() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("file.txt");
+        fs::write(&path, b"data").unwrap();
+
+        let mut opts = OpenOptions::new();
+        opts.read(true);
+        apply_nofollow(&mut opts);
+        let file = opts.open(&path).unwrap();
+
+        assert!(
+            verify_not_link(&file, &path, |path| MutxError::SymlinkNotAllowed { path }).is_ok()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_nofollow_open_rejects_symlink() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("target.txt");
+        let link = temp.path().join("link.txt");
+        fs::write(&target, b"data").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        let mut opts = OpenOptions::new();
+        opts.read(true);
+        apply_nofollow(&mut opts);
+        assert!(opts.open(&link).is_err());
+    }
+
     #[test]
     fn test_regular_file_allowed() {
         let temp = TempDir::new().unwrap();
@@ -0,0 +1,138 @@
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use std::path::{Path, PathBuf};
+
+/// Suffix carried by every temporary file mutx creates, so leftovers from an
+/// interrupted run can be recognized and cleaned by `mutx housekeep temps`
+pub const TEMP_SUFFIX: &str = ".mutx.tmp";
+
+/// Suffix of the per-write scratch directories from [`unique_scratch_path`]
+pub const SCRATCH_SUFFIX: &str = ".mutx.scratch";
+
+/// Temporary staging path for `path`: the same name with [`TEMP_SUFFIX`] appended
+pub fn temp_path_for(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(TEMP_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// A fresh, hidden temporary path beside `target`:
+/// `.<name>.<pid>-<random>.mutx.tmp`.
+///
+/// The PID identifies the writer, so housekeep can leave temps of running
+/// processes alone; the random part keeps concurrent writers apart.
+pub fn unique_temp_path(target: &Path) -> PathBuf {
+    unique_path(target, TEMP_SUFFIX)
+}
+
+/// A fresh, hidden scratch directory path beside `target`, named like
+/// [`unique_temp_path`] but ending in [`SCRATCH_SUFFIX`]
+pub fn unique_scratch_path(target: &Path) -> PathBuf {
+    unique_path(target, SCRATCH_SUFFIX)
+}
+
+fn unique_path(target: &Path, suffix: &str) -> PathBuf {
+    let name = target
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let random: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(8)
+        .map(char::from)
+        .collect();
+
+    target.with_file_name(format!(
+        ".{}.{}-{}{}",
+        name,
+        std::process::id(),
+        random,
+        suffix
+    ))
+}
+
+/// PID of the process that created a temp from [`unique_temp_path`] or a
+/// scratch directory from [`unique_scratch_path`]
+pub fn temp_owner_pid(path: &Path) -> Option<u32> {
+    let name = path.file_name()?.to_str()?;
+    let name = name
+        .strip_suffix(TEMP_SUFFIX)
+        .or_else(|| name.strip_suffix(SCRATCH_SUFFIX))?;
+    let (_, tag) = name.rsplit_once('.')?;
+    let (pid, _) = tag.split_once('-')?;
+    pid.parse().ok()
+}
+
+/// File name a mutx temp stages: `<name>` for both `.<name>.<pid>-<random>`
+/// and `<name>` with [`TEMP_SUFFIX`] appended
+pub fn temp_target_name(path: &Path) -> Option<&str> {
+    let name = path.file_name()?.to_str()?.strip_suffix(TEMP_SUFFIX)?;
+    if temp_owner_pid(path).is_some() {
+        let (target, _) = name.strip_prefix('.')?.rsplit_once('.')?;
+        return Some(target);
+    }
+    Some(name)
+}
+
+/// Whether `path` is named like a mutx temporary file
+pub fn is_mutx_temp(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .map(|name| name.len() > TEMP_SUFFIX.len() && name.ends_with(TEMP_SUFFIX))
+        .unwrap_or(false)
+}
+
+/// Whether `path` is named like a mutx scratch directory
+pub fn is_mutx_scratch(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .map(|name| name.len() > SCRATCH_SUFFIX.len() && name.ends_with(SCRATCH_SUFFIX))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_temp_path_round_trip() {
+        let temp = temp_path_for(Path::new("/data/file.txt.mutx.backup"));
+        assert_eq!(temp, PathBuf::from("/data/file.txt.mutx.backup.mutx.tmp"));
+        assert!(is_mutx_temp(&temp));
+        assert!(!is_mutx_temp(Path::new("/data/file.txt")));
+        assert!(!is_mutx_temp(Path::new("/data/.mutx.tmp")));
+    }
+
+    #[test]
+    fn test_unique_temp_path() {
+        let target = Path::new("/data/file.txt");
+        let first = unique_temp_path(target);
+        let second = unique_temp_path(target);
+
+        assert_ne!(first, second);
+        assert_eq!(first.parent(), target.parent());
+        assert!(first
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with(".file.txt."));
+        assert!(is_mutx_temp(&first));
+        assert_eq!(temp_owner_pid(&first), Some(std::process::id()));
+        assert_eq!(temp_owner_pid(Path::new("/data/file.txt.mutx.tmp")), None);
+        assert_eq!(temp_target_name(&first), Some("file.txt"));
+        assert_eq!(
+            temp_target_name(Path::new("/data/file.txt.mutx.tmp")),
+            Some("file.txt")
+        );
+    }
+
+    #[test]
+    fn test_unique_scratch_path() {
+        let scratch = unique_scratch_path(Path::new("/data/file.txt"));
+        assert!(is_mutx_scratch(&scratch));
+        assert!(!is_mutx_temp(&scratch));
+        assert_eq!(temp_owner_pid(&scratch), Some(std::process::id()));
+        assert!(!is_mutx_scratch(Path::new("/data/.mutx.scratch")));
+    }
+}
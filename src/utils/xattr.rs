@@ -0,0 +1,115 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// Extended attribute carrying the fencing token of the lock holder that wrote
+/// a file.
+pub const FENCING_TOKEN_XATTR: &str = "user.mutx.fencing_token";
+
+/// Extended attribute carrying the SHA-256 of a backup's content, in hex,
+/// recorded as the backup is taken.
+pub const BACKUP_SHA256_XATTR: &str = "user.mutx.sha256";
+
+/// Extended attribute carrying the canonical path of the file a backup in a
+/// backup directory was taken of.
+pub const BACKUP_SOURCE_XATTR: &str = "user.mutx.source";
+
+/// Extended attribute naming the compression format of content mutx
+/// compressed (`--compress`); backups of the file carry it over.
+pub const COMPRESSION_XATTR: &str = "user.mutx.compression";
+
+/// Set an extended attribute on an open file.
+///
+/// Returns [`io::ErrorKind::Unsupported`] on platforms without extended
+/// attributes; filesystems that lack them report their own error (typically
+/// `ENOTSUP`).
+pub fn set_xattr(file: &File, name: &str, value: &[u8]) -> io::Result<()> {
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    {
+        use std::os::unix::io::AsRawFd;
+
+        let name = std::ffi::CString::new(name)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let fd = file.as_raw_fd();
+        let value_ptr = value.as_ptr() as *const libc::c_void;
+
+        #[cfg(target_os = "linux")]
+        let rc = unsafe { libc::fsetxattr(fd, name.as_ptr(), value_ptr, value.len(), 0) };
+        #[cfg(target_os = "macos")]
+        let rc = unsafe { libc::fsetxattr(fd, name.as_ptr(), value_ptr, value.len(), 0, 0) };
+
+        if rc == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        let _ = (file, name, value);
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "extended attributes are not supported on this platform",
+        ))
+    }
+}
+
+/// Read an extended attribute from a path, returning `None` when it is not set.
+pub fn get_xattr(path: &Path, name: &str) -> io::Result<Option<Vec<u8>>> {
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    {
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = std::ffi::CString::new(path.as_os_str().as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let name = std::ffi::CString::new(name)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        let read = |buf: &mut [u8]| -> isize {
+            let buf_ptr = buf.as_mut_ptr() as *mut libc::c_void;
+            #[cfg(target_os = "linux")]
+            let len = unsafe { libc::getxattr(path.as_ptr(), name.as_ptr(), buf_ptr, buf.len()) };
+            #[cfg(target_os = "macos")]
+            let len =
+                unsafe { libc::getxattr(path.as_ptr(), name.as_ptr(), buf_ptr, buf.len(), 0, 0) };
+            len
+        };
+
+        // The first call only measures the value
+        let len = read(&mut []);
+        if len < 0 {
+            let err = io::Error::last_os_error();
+            #[cfg(target_os = "linux")]
+            let missing = err.raw_os_error() == Some(libc::ENODATA);
+            #[cfg(target_os = "macos")]
+            let missing = err.raw_os_error() == Some(libc::ENOATTR);
+            return if missing { Ok(None) } else { Err(err) };
+        }
+
+        let mut value = vec![0u8; len as usize];
+        let len = read(&mut value);
+        if len < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        value.truncate(len as usize);
+        Ok(Some(value))
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        let _ = (path, name);
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "extended attributes are not supported on this platform",
+        ))
+    }
+}
+
+/// Copy the extended attribute `name` of `from` to `to`, if it is set
+pub fn copy_xattr(from: &Path, to: &File, name: &str) -> io::Result<()> {
+    match get_xattr(from, name)? {
+        Some(value) => set_xattr(to, name, &value),
+        None => Ok(()),
+    }
+}
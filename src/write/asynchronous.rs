@@ -0,0 +1,137 @@
+//! Async streaming into an [`AtomicWriter`] (`tokio` feature).
+//!
+//! The writer's blocking file operations run on tokio's blocking thread
+//! pool, one chunk at a time, so async tasks can stream into the temp file
+//! without stalling the runtime. As with `tokio::fs::File`, a write returns
+//! as soon as its chunk is handed off; an error in the background surfaces
+//! from the next write, flush or [`AsyncAtomicWriter::commit`].
+
+use crate::error::{MutxError, Result};
+use crate::write::{AtomicWriter, WriteReport};
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use tokio::io::AsyncWrite;
+use tokio::task::JoinHandle;
+
+/// Largest chunk handed to the blocking pool per write
+const MAX_CHUNK: usize = 64 * 1024;
+
+/// [`AtomicWriter`] implementing [`AsyncWrite`]. Nothing is visible at the
+/// target until [`commit`](Self::commit); dropping the writer discards the
+/// temp file.
+#[derive(Debug)]
+pub struct AsyncAtomicWriter {
+    state: State,
+}
+
+enum State {
+    Idle(Option<Box<AtomicWriter>>),
+    Busy(JoinHandle<(Box<AtomicWriter>, Result<()>)>),
+}
+
+impl std::fmt::Debug for State {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            State::Idle(writer) => f
+                .debug_tuple("Idle")
+                .field(&writer.as_ref().map(|w| w.target()))
+                .finish(),
+            State::Busy(_) => f.write_str("Busy"),
+        }
+    }
+}
+
+impl AsyncAtomicWriter {
+    /// Stream into `writer`, configured as usual with its builder methods
+    pub fn new(writer: AtomicWriter) -> Self {
+        AsyncAtomicWriter {
+            state: State::Idle(Some(Box::new(writer))),
+        }
+    }
+
+    /// Wait for pending writes, then make the data durable and atomically
+    /// replace the target
+    pub async fn commit(mut self) -> Result<WriteReport> {
+        std::future::poll_fn(|cx| self.poll_idle(cx)).await?;
+        let writer = self.take_writer()?;
+        tokio::task::spawn_blocking(move || writer.commit())
+            .await
+            .map_err(|e| MutxError::Other(format!("Commit task failed: {}", e)))?
+    }
+
+    /// Wait for the write in progress, if any, and report its outcome
+    fn poll_idle(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        if let State::Busy(task) = &mut self.state {
+            let outcome = ready!(Pin::new(task).poll(cx));
+            return Poll::Ready(match outcome {
+                Ok((writer, result)) => {
+                    // A failed writer is not reused: its stream is incomplete
+                    self.state = State::Idle(result.is_ok().then_some(writer));
+                    result
+                }
+                Err(e) => {
+                    self.state = State::Idle(None);
+                    Err(MutxError::Other(format!("Write task failed: {}", e)))
+                }
+            });
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn take_writer(&mut self) -> Result<Box<AtomicWriter>> {
+        match &mut self.state {
+            State::Idle(writer) => writer.take().ok_or_else(|| {
+                MutxError::Other("Writer is unusable after an earlier error".to_string())
+            }),
+            State::Busy(_) => unreachable!("poll_idle waits for pending writes"),
+        }
+    }
+}
+
+impl From<AtomicWriter> for AsyncAtomicWriter {
+    fn from(writer: AtomicWriter) -> Self {
+        Self::new(writer)
+    }
+}
+
+impl AsyncWrite for AsyncAtomicWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        ready!(this.poll_idle(cx)).map_err(into_io)?;
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        let mut writer = this.take_writer().map_err(into_io)?;
+        let chunk = buf[..buf.len().min(MAX_CHUNK)].to_vec();
+        let len = chunk.len();
+        this.state = State::Busy(tokio::task::spawn_blocking(move || {
+            let result = writer.write_all(&chunk);
+            (writer, result)
+        }));
+        Poll::Ready(Ok(len))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_idle(cx).map_err(into_io)
+    }
+
+    /// Flushes pending writes; the target is only replaced by
+    /// [`AsyncAtomicWriter::commit`]
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush(cx)
+    }
+}
+
+fn into_io(err: MutxError) -> io::Error {
+    match err {
+        MutxError::Io(e) => e,
+        other => io::Error::other(other),
+    }
+}
@@ -0,0 +1,975 @@
+//! Staging and atomic replacement of files.
+//!
+//! A [`StagedFile`] is written beside its target and only becomes visible
+//! when [`StagedFile::commit`] replaces the target in one step:
+//!
+//! 1. create the temp file (named, or unnamed with `O_TMPFILE` on Linux)
+//! 2. copy permissions and, where allowed, ownership from an existing target,
+//!    then apply the [`ModePolicy`]
+//! 3. `fsync` the contents
+//! 4. rename over the target (`ReplaceFileW` on Windows, so the target's
+//!    ACLs and attributes survive)
+//! 5. `fsync` the directory so the rename itself is durable (Unix)
+//!
+//! On Unix all of these go through one descriptor for the target's directory
+//! ([`StageDir`]) using `openat`, `fstatat`, `renameat` and `unlinkat`, so a
+//! directory swapped or renamed after staging begins cannot redirect the temp
+//! file or the commit elsewhere.
+//!
+//! Each step reports failures as [`MutxError::WriteStepFailed`] naming the
+//! step, or [`MutxError::OutOfSpace`] when the filesystem or quota is full,
+//! and an uncommitted staged file is removed when dropped.
+//!
+//! On Windows a target another process has open without `FILE_SHARE_DELETE`
+//! (editors, antivirus scanners, the search indexer) cannot be replaced until
+//! it is closed. Step 4 retries with backoff for a while (see
+//! [`DEFAULT_RENAME_RETRY`]) and then fails with [`MutxError::TargetInUse`].
+
+use crate::error::{MutxError, Result};
+use crate::utils::unique_temp_path;
+use serde::Deserialize;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::fs::File;
+use std::fs::Permissions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+#[cfg(unix)]
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How long commit keeps retrying to replace a target another process has
+/// open without delete sharing
+pub const DEFAULT_RENAME_RETRY: Duration = Duration::from_secs(2);
+
+/// Longest pause between attempts to replace a target in use
+const MAX_RENAME_PAUSE: Duration = Duration::from_millis(250);
+
+/// A step of staging and committing a file, for error reporting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteStep {
+    CreateTemp,
+    CopyPermissions,
+    SetPermissions,
+    Write,
+    Sync,
+    Rename,
+    SyncDirectory,
+}
+
+impl fmt::Display for WriteStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WriteStep::CreateTemp => write!(f, "creating temp file"),
+            WriteStep::CopyPermissions => write!(f, "copying permissions"),
+            WriteStep::SetPermissions => write!(f, "setting permissions"),
+            WriteStep::Write => write!(f, "writing temp file"),
+            WriteStep::Sync => write!(f, "syncing temp file"),
+            WriteStep::Rename => write!(f, "replacing target"),
+            WriteStep::SyncDirectory => write!(f, "syncing directory"),
+        }
+    }
+}
+
+/// How the temporary file is created
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TempStrategy {
+    /// A hidden `.<name>.<pid>-<random>.mutx.tmp` file beside the target
+    /// (default). Leftovers from a crash can be found by `housekeep temps`.
+    #[default]
+    Named,
+    /// An unnamed `O_TMPFILE` inode that only gets a name at commit, so a
+    /// crash leaves nothing behind. Linux only; falls back to `Named` where
+    /// the kernel or filesystem does not support it.
+    Unnamed,
+}
+
+/// Permissions of the file a write produces
+///
+/// A temp file is created with mode `0666` minus the process umask (and any
+/// default ACL of the directory), exactly like a file created in place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ModePolicy {
+    /// Keep the mode (and, when allowed, the owner) of the file being
+    /// replaced; a new file gets the umask default (default)
+    #[default]
+    PreserveExisting,
+    /// Always use the umask default, even when replacing a file. The owner
+    /// is still kept.
+    Umask,
+    /// Always use this mode, e.g. `0o640`. On Windows only the read-only
+    /// attribute is derived from it (set when no write bit is present).
+    Explicit(FileMode),
+}
+
+impl fmt::Display for ModePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModePolicy::PreserveExisting => write!(f, "preserve"),
+            ModePolicy::Umask => write!(f, "umask"),
+            ModePolicy::Explicit(mode) => write!(f, "{}", mode),
+        }
+    }
+}
+
+impl FromStr for ModePolicy {
+    type Err = MutxError;
+
+    /// `preserve`, `umask`, or an octal mode such as `0644`
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "preserve" => Ok(ModePolicy::PreserveExisting),
+            "umask" => Ok(ModePolicy::Umask),
+            _ => s.parse().map(ModePolicy::Explicit),
+        }
+    }
+}
+
+/// Unix permission bits, validated to fit in `0o7777` when constructed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub struct FileMode(u32);
+
+impl FileMode {
+    /// Permission bits `mode`, e.g. `0o640`; setuid, setgid and sticky are
+    /// allowed, file type bits are not
+    pub fn new(mode: u32) -> Result<Self> {
+        if mode > 0o7777 {
+            return Err(MutxError::InvalidPermissions {
+                input: format!("{:o}", mode),
+            });
+        }
+        Ok(FileMode(mode))
+    }
+
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+}
+
+impl fmt::Display for FileMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04o}", self.0)
+    }
+}
+
+impl FromStr for FileMode {
+    type Err = MutxError;
+
+    /// An octal mode such as `0644` or `0o644`
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || MutxError::InvalidPermissions {
+            input: s.to_string(),
+        };
+        let digits = s
+            .strip_prefix("0o")
+            .or_else(|| s.strip_prefix("0O"))
+            .unwrap_or(s);
+        let mode = u32::from_str_radix(digits, 8).map_err(|_| invalid())?;
+        FileMode::new(mode).map_err(|_| invalid())
+    }
+}
+
+impl TryFrom<String> for FileMode {
+    type Error = MutxError;
+
+    fn try_from(mode: String) -> Result<Self> {
+        mode.parse()
+    }
+}
+
+/// Directory files are staged in and committed to
+///
+/// Cloning is cheap and shares the underlying descriptor, so one `StageDir`
+/// can serve any number of writes (see [`crate::AtomicWriterPool`]).
+#[derive(Debug, Clone)]
+pub struct StageDir {
+    path: PathBuf,
+    #[cfg(unix)]
+    fd: Arc<File>,
+}
+
+impl StageDir {
+    /// Open the directory at `path`
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Ok(StageDir {
+            path: path.to_path_buf(),
+            #[cfg(unix)]
+            fd: Arc::new(at::open_dir(path)?),
+        })
+    }
+
+    /// Open the directory containing `target`
+    pub fn for_target(target: &Path) -> io::Result<Self> {
+        Self::open(parent_dir(target))
+    }
+
+    /// Path the directory was opened at
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Flush renames and removals in the directory to stable storage. A no-op
+    /// where directories cannot be synced (Windows).
+    pub fn sync(&self) -> io::Result<()> {
+        #[cfg(unix)]
+        return self.fd.sync_all();
+        #[cfg(not(unix))]
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+impl StageDir {
+    fn create_new(&self, name: &OsStr) -> io::Result<File> {
+        at::create_new(&self.fd, name)
+    }
+
+    fn create_unnamed(&self) -> Option<File> {
+        at::create_unnamed(&self.fd)
+    }
+
+    fn link_unnamed(&self, file: &File, name: &OsStr) -> io::Result<()> {
+        at::link_unnamed(file, &self.fd, name)
+    }
+
+    fn copy_metadata(&self, name: &OsStr, file: &File) -> io::Result<()> {
+        at::copy_metadata(&self.fd, name, file)
+    }
+
+    fn replace(&self, from: &OsStr, to: &OsStr) -> io::Result<()> {
+        at::rename(&self.fd, from, to)
+    }
+
+    fn remove(&self, name: &OsStr) -> io::Result<()> {
+        at::unlink(&self.fd, name)
+    }
+}
+
+#[cfg(not(unix))]
+impl StageDir {
+    fn create_new(&self, name: &OsStr) -> io::Result<File> {
+        let mut opts = std::fs::OpenOptions::new();
+        opts.write(true).create_new(true);
+        crate::utils::apply_nofollow(&mut opts);
+        opts.open(self.path.join(name))
+    }
+
+    fn create_unnamed(&self) -> Option<File> {
+        None
+    }
+
+    fn link_unnamed(&self, _file: &File, _name: &OsStr) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "unnamed temp files are only supported on Linux",
+        ))
+    }
+
+    fn copy_metadata(&self, name: &OsStr, file: &File) -> io::Result<()> {
+        match std::fs::metadata(self.path.join(name)) {
+            Ok(existing) => file.set_permissions(existing.permissions()),
+            Err(_) => Ok(()),
+        }
+    }
+
+    fn replace(&self, from: &OsStr, to: &OsStr) -> io::Result<()> {
+        replace(&self.path.join(from), &self.path.join(to))
+    }
+
+    fn remove(&self, name: &OsStr) -> io::Result<()> {
+        std::fs::remove_file(self.path.join(name))
+    }
+}
+
+/// Directory containing `target`, `.` for a bare file name
+pub(crate) fn parent_dir(target: &Path) -> &Path {
+    match target.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+/// File staged beside a target, replacing it atomically on commit
+#[derive(Debug)]
+pub struct StagedFile {
+    file: File,
+    dir: StageDir,
+    /// Name of the temp file; `None` while an `O_TMPFILE` inode is unnamed
+    temp_name: Option<OsString>,
+    target_name: OsString,
+    target: PathBuf,
+    sync_directory: bool,
+    rename_retry: Duration,
+    bytes_written: u64,
+    /// Permissions the temp file was created with, before any were copied
+    created_permissions: Permissions,
+    committed: bool,
+}
+
+impl StagedFile {
+    /// Stage a new file for `target` using `strategy`
+    pub fn create(target: &Path, strategy: TempStrategy) -> Result<Self> {
+        let dir = StageDir::for_target(target).map_err(|e| MutxError::WriteStepFailed {
+            path: target.to_path_buf(),
+            step: WriteStep::CreateTemp,
+            source: e,
+        })?;
+        Self::create_in(dir, target, strategy)
+    }
+
+    /// Stage a new file for `target` in `dir`, an already open handle on the
+    /// target's directory
+    pub fn create_in(dir: StageDir, target: &Path, strategy: TempStrategy) -> Result<Self> {
+        let step = |step| move |e| step_error(target, step, 0, e);
+
+        let target_name = target
+            .file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))
+            .map_err(step(WriteStep::CreateTemp))?
+            .to_os_string();
+
+        let (file, temp_name) = match strategy {
+            TempStrategy::Unnamed => match dir.create_unnamed() {
+                Some(file) => (file, None),
+                None => create_named(&dir, &target_name).map_err(step(WriteStep::CreateTemp))?,
+            },
+            TempStrategy::Named => {
+                create_named(&dir, &target_name).map_err(step(WriteStep::CreateTemp))?
+            }
+        };
+
+        let created_permissions = file
+            .metadata()
+            .map_err(step(WriteStep::CreateTemp))?
+            .permissions();
+        let staged = StagedFile {
+            file,
+            dir,
+            temp_name,
+            target_name,
+            target: target.to_path_buf(),
+            sync_directory: true,
+            rename_retry: DEFAULT_RENAME_RETRY,
+            bytes_written: 0,
+            created_permissions,
+            committed: false,
+        };
+
+        // Replacing a file keeps its permissions (and, when allowed, its owner)
+        staged
+            .dir
+            .copy_metadata(&staged.target_name, &staged.file)
+            .map_err(step(WriteStep::CopyPermissions))?;
+        Ok(staged)
+    }
+
+    /// Skip the directory fsync after the rename, for callers that sync the
+    /// directory themselves once several files are committed
+    pub fn with_directory_sync(mut self, sync_directory: bool) -> Self {
+        self.sync_directory = sync_directory;
+        self
+    }
+
+    /// Keep retrying to replace a target in use by another process for up
+    /// to `rename_retry` (default [`DEFAULT_RENAME_RETRY`]); zero fails at
+    /// the first refusal
+    pub fn with_rename_retry(mut self, rename_retry: Duration) -> Self {
+        self.rename_retry = rename_retry;
+        self
+    }
+
+    /// Apply `policy` to the staged file. Staging already preserves the
+    /// existing target's mode, so only the other policies change anything.
+    pub fn apply_mode_policy(&self, policy: ModePolicy) -> Result<()> {
+        let permissions = match policy {
+            ModePolicy::PreserveExisting => return Ok(()),
+            ModePolicy::Umask => self.created_permissions.clone(),
+            ModePolicy::Explicit(mode) => {
+                permissions_from_mode(&self.created_permissions, mode.bits())
+            }
+        };
+        self.file
+            .set_permissions(permissions)
+            .map_err(|e| self.step_failed(WriteStep::SetPermissions, e))
+    }
+
+    /// Current permission bits of the staged file. On Windows these are
+    /// synthesized: `0444` when read-only, `0666` otherwise.
+    pub fn mode(&self) -> Result<u32> {
+        let permissions = self
+            .file
+            .metadata()
+            .map_err(|e| self.step_failed(WriteStep::SetPermissions, e))?
+            .permissions();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            Ok(permissions.mode() & 0o7777)
+        }
+        #[cfg(not(unix))]
+        Ok(if permissions.readonly() { 0o444 } else { 0o666 })
+    }
+
+    /// The open temp file, e.g. for setting extended attributes
+    pub fn file(&self) -> &File {
+        &self.file
+    }
+
+    /// Path of the temp file, if it has one yet
+    pub fn temp_path(&self) -> Option<PathBuf> {
+        self.temp_name.as_ref().map(|name| self.dir.path.join(name))
+    }
+
+    /// Append `buf` to the staged contents
+    ///
+    /// On failure [`StagedFile::bytes_written`] still counts the part of
+    /// `buf` that reached the file.
+    pub fn write_all(&mut self, mut buf: &[u8]) -> Result<()> {
+        while !buf.is_empty() {
+            match self.file.write(buf) {
+                Ok(0) => {
+                    return Err(self.step_failed(WriteStep::Write, io::ErrorKind::WriteZero.into()))
+                }
+                Ok(n) => {
+                    self.bytes_written += n as u64;
+                    buf = &buf[n..];
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(self.step_failed(WriteStep::Write, e)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Bytes written to the staged file so far
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    /// Make the staged contents durable and atomically replace the target
+    pub fn commit(mut self) -> Result<()> {
+        self.file
+            .sync_all()
+            .map_err(|e| self.step_failed(WriteStep::Sync, e))?;
+
+        let temp_name = match self.temp_name.clone() {
+            Some(name) => name,
+            None => {
+                let name = link_unnamed(&self.dir, &self.file, &self.target_name)
+                    .map_err(|e| self.step_failed(WriteStep::Rename, e))?;
+                self.temp_name = Some(name.clone());
+                name
+            }
+        };
+
+        let started = Instant::now();
+        let in_use = |e: &io::Error| is_sharing_violation(e) && !super::is_readonly(&self.target);
+        retry_while(self.rename_retry, in_use, || {
+            self.dir.replace(&temp_name, &self.target_name)
+        })
+        .map_err(|e| {
+            if in_use(&e) {
+                MutxError::TargetInUse {
+                    path: self.target.clone(),
+                    waited: started.elapsed(),
+                    source: e,
+                }
+            } else {
+                self.step_failed(WriteStep::Rename, e)
+            }
+        })?;
+        self.committed = true;
+
+        if !self.sync_directory {
+            return Ok(());
+        }
+        self.dir
+            .sync()
+            .map_err(|e| self.step_failed(WriteStep::SyncDirectory, e))
+    }
+
+    fn step_failed(&self, step: WriteStep, source: io::Error) -> MutxError {
+        step_error(&self.target, step, self.bytes_written, source)
+    }
+}
+
+/// Error for a failed step, reported as [`MutxError::OutOfSpace`] when the
+/// filesystem or quota is full
+fn step_error(target: &Path, step: WriteStep, bytes_written: u64, source: io::Error) -> MutxError {
+    if is_out_of_space(&source) {
+        return MutxError::OutOfSpace {
+            path: target.to_path_buf(),
+            bytes_written,
+            source,
+        };
+    }
+    MutxError::WriteStepFailed {
+        path: target.to_path_buf(),
+        step,
+        source,
+    }
+}
+
+/// Whether `e` means the filesystem is full or the user's quota exhausted
+pub fn is_out_of_space(e: &io::Error) -> bool {
+    #[cfg(unix)]
+    return matches!(e.raw_os_error(), Some(libc::ENOSPC | libc::EDQUOT));
+    // ERROR_HANDLE_DISK_FULL, ERROR_DISK_FULL
+    #[cfg(windows)]
+    return matches!(e.raw_os_error(), Some(39 | 112));
+    #[cfg(not(any(unix, windows)))]
+    false
+}
+
+/// Whether `e` means another process has the file open in a way that
+/// forbids replacing it. Only Windows refuses this; elsewhere open files
+/// can always be renamed over.
+pub fn is_sharing_violation(e: &io::Error) -> bool {
+    // ERROR_ACCESS_DENIED, ERROR_SHARING_VIOLATION, ERROR_LOCK_VIOLATION,
+    // ERROR_UNABLE_TO_REMOVE_REPLACED
+    #[cfg(windows)]
+    return matches!(e.raw_os_error(), Some(5 | 32 | 33 | 1175));
+    #[cfg(not(windows))]
+    {
+        let _ = e;
+        false
+    }
+}
+
+/// Run `op` until it succeeds, fails with an error `retryable` rejects, or
+/// `budget` has passed, pausing 10ms after the first failure and doubling
+/// the pause up to [`MAX_RENAME_PAUSE`]
+fn retry_while(
+    budget: Duration,
+    retryable: impl Fn(&io::Error) -> bool,
+    mut op: impl FnMut() -> io::Result<()>,
+) -> io::Result<()> {
+    let started = Instant::now();
+    let mut pause = Duration::from_millis(10);
+    loop {
+        match op() {
+            Err(e) if retryable(&e) && started.elapsed() < budget => {
+                std::thread::sleep(pause.min(budget.saturating_sub(started.elapsed())));
+                pause = (pause * 2).min(MAX_RENAME_PAUSE);
+            }
+            result => return result,
+        }
+    }
+}
+
+impl Drop for StagedFile {
+    fn drop(&mut self) {
+        if !self.committed {
+            if let Some(name) = &self.temp_name {
+                let _ = self.dir.remove(name);
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn permissions_from_mode(_created: &Permissions, mode: u32) -> Permissions {
+    use std::os::unix::fs::PermissionsExt;
+    Permissions::from_mode(mode)
+}
+
+#[cfg(not(unix))]
+fn permissions_from_mode(created: &Permissions, mode: u32) -> Permissions {
+    let mut permissions = created.clone();
+    permissions.set_readonly(mode & 0o222 == 0);
+    permissions
+}
+
+/// A fresh hidden temp name for `target_name`, see [`unique_temp_path`]
+fn temp_name_for(target_name: &OsStr) -> OsString {
+    unique_temp_path(Path::new(target_name))
+        .file_name()
+        .map(OsStr::to_os_string)
+        .unwrap_or_default()
+}
+
+/// Fresh temp names tried before a run of collisions fails the write
+const TEMP_NAME_ATTEMPTS: u32 = 16;
+
+fn create_named(dir: &StageDir, target_name: &OsStr) -> io::Result<(File, Option<OsString>)> {
+    let mut attempts = 0;
+    loop {
+        let name = temp_name_for(target_name);
+        match dir.create_new(&name) {
+            Ok(file) => return Ok((file, Some(name))),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists && attempts < TEMP_NAME_ATTEMPTS => {
+                attempts += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Give an `O_TMPFILE` inode a temp name beside the target, ready for rename
+fn link_unnamed(dir: &StageDir, file: &File, target_name: &OsStr) -> io::Result<OsString> {
+    let mut attempts = 0;
+    loop {
+        let name = temp_name_for(target_name);
+        match dir.link_unnamed(file, &name) {
+            Ok(()) => return Ok(name),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists && attempts < TEMP_NAME_ATTEMPTS => {
+                attempts += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Descriptor-relative file operations
+#[cfg(unix)]
+mod at {
+    use std::ffi::{CString, OsStr};
+    use std::fs::File;
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::io::{AsRawFd, FromRawFd};
+    use std::path::Path;
+
+    fn c_name(name: &OsStr) -> io::Result<CString> {
+        Ok(CString::new(name.as_bytes())?)
+    }
+
+    fn check(rc: libc::c_int) -> io::Result<()> {
+        if rc == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn open_at(dir: &File, name: &CString, flags: libc::c_int) -> io::Result<File> {
+        let mode = 0o666 as libc::c_uint;
+        let fd = unsafe { libc::openat(dir.as_raw_fd(), name.as_ptr(), flags, mode) };
+        if fd == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(unsafe { File::from_raw_fd(fd) })
+    }
+
+    pub(super) fn open_dir(path: &Path) -> io::Result<File> {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        std::fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_DIRECTORY)
+            .open(path)
+    }
+
+    pub(super) fn create_new(dir: &File, name: &OsStr) -> io::Result<File> {
+        let flags =
+            libc::O_WRONLY | libc::O_CREAT | libc::O_EXCL | libc::O_NOFOLLOW | libc::O_CLOEXEC;
+        open_at(dir, &c_name(name)?, flags)
+    }
+
+    /// Open an unnamed temp file in `dir`, or `None` when `O_TMPFILE` cannot
+    /// be used there
+    #[cfg(target_os = "linux")]
+    pub(super) fn create_unnamed(dir: &File) -> Option<File> {
+        // Naming the inode later goes through /proc/self/fd
+        if !Path::new("/proc/self/fd").is_dir() {
+            return None;
+        }
+        let flags = libc::O_TMPFILE | libc::O_WRONLY | libc::O_CLOEXEC;
+        open_at(dir, &c_name(OsStr::new(".")).ok()?, flags).ok()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(super) fn create_unnamed(_dir: &File) -> Option<File> {
+        None
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(super) fn link_unnamed(file: &File, dir: &File, name: &OsStr) -> io::Result<()> {
+        let proc_path = CString::new(format!("/proc/self/fd/{}", file.as_raw_fd()))?;
+        let name = c_name(name)?;
+        check(unsafe {
+            libc::linkat(
+                libc::AT_FDCWD,
+                proc_path.as_ptr(),
+                dir.as_raw_fd(),
+                name.as_ptr(),
+                libc::AT_SYMLINK_FOLLOW,
+            )
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(super) fn link_unnamed(_file: &File, _dir: &File, _name: &OsStr) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "unnamed temp files are only supported on Linux",
+        ))
+    }
+
+    /// Give `file` the mode and, when allowed, the owner of `name` in `dir`
+    pub(super) fn copy_metadata(dir: &File, name: &OsStr, file: &File) -> io::Result<()> {
+        let name = c_name(name)?;
+        let mut st = std::mem::MaybeUninit::<libc::stat>::uninit();
+        let rc = unsafe { libc::fstatat(dir.as_raw_fd(), name.as_ptr(), st.as_mut_ptr(), 0) };
+        if rc == -1 {
+            // Nothing to copy from a target that does not exist yet
+            let err = io::Error::last_os_error();
+            return match err.kind() {
+                io::ErrorKind::NotFound => Ok(()),
+                _ => Err(err),
+            };
+        }
+        let st = unsafe { st.assume_init() };
+
+        // Only root can give files away; anyone else keeps ownership. Owner
+        // first: a chown clears setuid and setgid, so the mode comes after.
+        let _ = std::os::unix::fs::fchown(file, Some(st.st_uid), Some(st.st_gid));
+        check(unsafe { libc::fchmod(file.as_raw_fd(), st.st_mode & 0o7777) })?;
+        Ok(())
+    }
+
+    pub(super) fn rename(dir: &File, from: &OsStr, to: &OsStr) -> io::Result<()> {
+        let (from, to) = (c_name(from)?, c_name(to)?);
+        let fd = dir.as_raw_fd();
+        check(unsafe { libc::renameat(fd, from.as_ptr(), fd, to.as_ptr()) })
+    }
+
+    pub(super) fn unlink(dir: &File, name: &OsStr) -> io::Result<()> {
+        let name = c_name(name)?;
+        check(unsafe { libc::unlinkat(dir.as_raw_fd(), name.as_ptr(), 0) })
+    }
+}
+
+/// Atomically move `temp` over `target`
+#[cfg(all(not(unix), not(windows)))]
+fn replace(temp: &Path, target: &Path) -> io::Result<()> {
+    std::fs::rename(temp, target)
+}
+
+/// Atomically move `temp` over `target`, keeping the target's ACLs and
+/// attributes when it exists
+#[cfg(windows)]
+fn replace(temp: &Path, target: &Path) -> io::Result<()> {
+    use std::os::windows::ffi::OsStrExt;
+
+    const REPLACEFILE_IGNORE_MERGE_ERRORS: u32 = 0x0000_0002;
+    /// `ERROR_FILE_NOT_FOUND`: the target does not exist yet
+    const ERROR_FILE_NOT_FOUND: i32 = 2;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn ReplaceFileW(
+            replaced: *const u16,
+            replacement: *const u16,
+            backup: *const u16,
+            flags: u32,
+            exclude: *mut std::ffi::c_void,
+            reserved: *mut std::ffi::c_void,
+        ) -> i32;
+    }
+
+    let wide = |p: &Path| -> Vec<u16> { p.as_os_str().encode_wide().chain(Some(0)).collect() };
+    let (target_w, temp_w) = (wide(target), wide(temp));
+
+    let ok = unsafe {
+        ReplaceFileW(
+            target_w.as_ptr(),
+            temp_w.as_ptr(),
+            std::ptr::null(),
+            REPLACEFILE_IGNORE_MERGE_ERRORS,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    if ok != 0 {
+        return Ok(());
+    }
+
+    let err = io::Error::last_os_error();
+    if err.raw_os_error() == Some(ERROR_FILE_NOT_FOUND) {
+        return std::fs::rename(temp, target);
+    }
+    Err(err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_named_commit_replaces_target() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("file.txt");
+        fs::write(&target, "old").unwrap();
+
+        let mut staged = StagedFile::create(&target, TempStrategy::Named).unwrap();
+        staged.write_all(b"new").unwrap();
+        assert!(staged.temp_path().unwrap().exists());
+        staged.commit().unwrap();
+
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_unnamed_commit_replaces_target() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("file.txt");
+
+        let mut staged = StagedFile::create(&target, TempStrategy::Unnamed).unwrap();
+        staged.write_all(b"new").unwrap();
+        staged.commit().unwrap();
+
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_drop_discards_staged_file() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("file.txt");
+
+        let mut staged = StagedFile::create(&target, TempStrategy::Named).unwrap();
+        staged.write_all(b"partial").unwrap();
+        drop(staged);
+
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_create_in_missing_directory_names_step() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("missing").join("file.txt");
+
+        let err = StagedFile::create(&target, TempStrategy::Named).unwrap_err();
+        assert!(matches!(
+            err,
+            MutxError::WriteStepFailed {
+                step: WriteStep::CreateTemp,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_parse_mode_policy() {
+        assert_eq!(
+            "0640".parse::<ModePolicy>().unwrap(),
+            ModePolicy::Explicit(FileMode::new(0o640).unwrap())
+        );
+        assert_eq!(
+            "0o755".parse::<ModePolicy>().unwrap(),
+            ModePolicy::Explicit(FileMode::new(0o755).unwrap())
+        );
+        assert_eq!("umask".parse::<ModePolicy>().unwrap(), ModePolicy::Umask);
+        assert_eq!(
+            "preserve".parse::<ModePolicy>().unwrap(),
+            ModePolicy::PreserveExisting
+        );
+        assert_eq!(
+            ModePolicy::Explicit(FileMode::new(0o640).unwrap()).to_string(),
+            "0640"
+        );
+        assert!(matches!(
+            "0899".parse::<ModePolicy>(),
+            Err(MutxError::InvalidPermissions { .. })
+        ));
+        assert!("17777".parse::<ModePolicy>().is_err());
+    }
+
+    #[test]
+    fn test_file_mode_validated_on_construction() {
+        assert_eq!(FileMode::new(0o2750).unwrap().bits(), 0o2750);
+        assert!(matches!(
+            FileMode::new(0o100644),
+            Err(MutxError::InvalidPermissions { .. })
+        ));
+        assert_eq!("0o600".parse::<FileMode>().unwrap().to_string(), "0600");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_out_of_space_is_reported_with_progress() {
+        let err = step_error(
+            Path::new("/data/out"),
+            WriteStep::Write,
+            4096,
+            io::Error::from_raw_os_error(libc::EDQUOT),
+        );
+        assert!(matches!(
+            err,
+            MutxError::OutOfSpace {
+                bytes_written: 4096,
+                ..
+            }
+        ));
+
+        let err = step_error(
+            Path::new("/data/out"),
+            WriteStep::Write,
+            0,
+            io::Error::from_raw_os_error(libc::EIO),
+        );
+        assert!(matches!(err, MutxError::WriteStepFailed { .. }));
+    }
+
+    #[test]
+    fn test_retry_while_succeeds_once_released() {
+        let mut attempts = 0;
+        let result = retry_while(
+            Duration::from_secs(5),
+            |e| e.kind() == io::ErrorKind::PermissionDenied,
+            || {
+                attempts += 1;
+                if attempts < 3 {
+                    Err(io::ErrorKind::PermissionDenied.into())
+                } else {
+                    Ok(())
+                }
+            },
+        );
+        assert!(result.is_ok());
+        assert_eq!(attempts, 3);
+    }
+
+    #[test]
+    fn test_retry_while_gives_up() {
+        let mut attempts = 0;
+        let started = Instant::now();
+        let result = retry_while(
+            Duration::from_millis(100),
+            |_| true,
+            || {
+                attempts += 1;
+                Err(io::ErrorKind::PermissionDenied.into())
+            },
+        );
+        assert!(result.is_err());
+        assert!(attempts > 1);
+        assert!(started.elapsed() >= Duration::from_millis(100));
+
+        // Other errors, and a zero budget, fail at once
+        attempts = 0;
+        let _ = retry_while(
+            Duration::from_secs(5),
+            |_| false,
+            || {
+                attempts += 1;
+                Err(io::ErrorKind::NotFound.into())
+            },
+        );
+        let _ = retry_while(
+            Duration::ZERO,
+            |_| true,
+            || {
+                attempts += 1;
+                Err(io::ErrorKind::PermissionDenied.into())
+            },
+        );
+        assert_eq!(attempts, 2);
+    }
+}
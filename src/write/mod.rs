@@ -0,0 +1,916 @@
+#[cfg(feature = "tokio")]
+mod asynchronous;
+mod batch;
+pub mod engine;
+mod pool;
+mod scratch;
+
+use crate::backup::BackupOutcome;
+use crate::collaborative::SharedGroup;
+use crate::compress::CompressionFormat;
+use crate::digest::{DigestAlgorithm, Hasher};
+use crate::error::{MutxError, Result};
+use crate::lock::{FileLock, LeaseLoss, LockPathStrategy, LockStrategy};
+use crate::sink::Sink;
+use crate::transform::{self, Transform};
+use crate::utils::check_symlink;
+use crate::utils::disk::available_space;
+use crate::utils::xattr::{set_xattr, COMPRESSION_XATTR, FENCING_TOKEN_XATTR};
+#[cfg(feature = "tokio")]
+pub use asynchronous::AsyncAtomicWriter;
+pub use batch::{FsyncPolicy, WriteBatch};
+use engine::StagedFile;
+pub use engine::{FileMode, ModePolicy, StageDir, TempStrategy, WriteStep, DEFAULT_RENAME_RETRY};
+pub use pool::AtomicWriterPool;
+use scratch::ScratchDir;
+use serde::Serialize;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::debug;
+
+/// Frees disk space when a write runs out of it, returning the bytes freed
+/// (see [`AtomicWriter::with_space_reclaimer`])
+pub type SpaceReclaimer = Box<dyn FnMut(u64) -> Result<u64> + Send>;
+
+/// Default size at which [`WriteMode::Auto`] moves buffered data to disk
+pub const DEFAULT_SPILL_THRESHOLD: usize = 8 * 1024 * 1024;
+
+/// How written data is held until commit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WriteMode {
+    /// Buffer in memory, spilling to the temp file once the data outgrows
+    /// the spill threshold (default)
+    #[default]
+    Auto,
+    /// Buffer everything in memory and create the temp file at commit
+    InMemory,
+    /// Write straight to the temp file (constant memory)
+    Streaming,
+    /// Former default, now the same as [`WriteMode::Auto`]: small writes stay
+    /// in memory, huge ones no longer exhaust it
+    #[deprecated(
+        since = "0.3.0",
+        note = "use WriteMode::Auto, or WriteMode::InMemory to keep everything in memory"
+    )]
+    Simple,
+}
+
+/// How much of a write runs under its lock.
+///
+/// Holding the lock for the whole write serializes writers completely, but
+/// a slow producer keeps everyone else waiting. Taking it later shortens
+/// the wait at the cost of what the lock protects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CriticalSection {
+    /// Lock before reading the input; the backup, staging and rename all
+    /// happen under the lock (default)
+    #[default]
+    Full,
+    /// Stage the input unlocked, then lock, back up the file being replaced
+    /// and rename. The backup is always the version this write replaced.
+    BackupAndCommit,
+    /// Back up and stage unlocked, locking only around the rename. Another
+    /// writer may commit in between, so the backup can be older than the
+    /// version replaced.
+    CommitOnly,
+}
+
+impl CriticalSection {
+    /// Whether the lock is held while the input is read and staged
+    pub fn locks_input(&self) -> bool {
+        matches!(self, CriticalSection::Full)
+    }
+
+    /// Whether the lock is held while the backup is made
+    pub fn locks_backup(&self) -> bool {
+        !matches!(self, CriticalSection::CommitOnly)
+    }
+}
+
+impl fmt::Display for CriticalSection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CriticalSection::Full => write!(f, "full"),
+            CriticalSection::BackupAndCommit => write!(f, "backup-and-commit"),
+            CriticalSection::CommitOnly => write!(f, "commit-only"),
+        }
+    }
+}
+
+impl FromStr for CriticalSection {
+    type Err = MutxError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "full" => Ok(CriticalSection::Full),
+            "backup-and-commit" => Ok(CriticalSection::BackupAndCommit),
+            "commit-only" => Ok(CriticalSection::CommitOnly),
+            _ => Err(MutxError::Other(format!(
+                "Unknown critical section '{}': expected full, backup-and-commit or commit-only",
+                s
+            ))),
+        }
+    }
+}
+
+/// A property a write may or may not be able to provide
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Guarantee {
+    /// Readers see either the old or the new content, never a mix
+    Atomic,
+    /// The new content and its directory entry are flushed to stable storage
+    /// before the write is reported complete
+    Durable,
+    /// The lock excludes every other mutx writer of the same file
+    Exclusive,
+}
+
+impl fmt::Display for Guarantee {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Guarantee::Atomic => write!(f, "atomic"),
+            Guarantee::Durable => write!(f, "durable"),
+            Guarantee::Exclusive => write!(f, "exclusive"),
+        }
+    }
+}
+
+impl FromStr for Guarantee {
+    type Err = MutxError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "atomic" => Ok(Guarantee::Atomic),
+            "durable" => Ok(Guarantee::Durable),
+            "exclusive" => Ok(Guarantee::Exclusive),
+            _ => Err(MutxError::Other(format!(
+                "Unknown guarantee '{}': expected atomic, durable or exclusive",
+                s
+            ))),
+        }
+    }
+}
+
+/// Guarantees a write provides. Anything `false` means mutx fell back to a
+/// weaker mode instead of failing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Guarantees {
+    pub atomic: bool,
+    pub durable: bool,
+    pub exclusive: bool,
+}
+
+impl Guarantees {
+    /// Whether `guarantee` is provided
+    pub fn provides(&self, guarantee: Guarantee) -> bool {
+        match guarantee {
+            Guarantee::Atomic => self.atomic,
+            Guarantee::Durable => self.durable,
+            Guarantee::Exclusive => self.exclusive,
+        }
+    }
+
+    /// Fail with [`MutxError::GuaranteeUnavailable`] unless every guarantee in
+    /// `required` is provided
+    pub fn require(&self, required: &[Guarantee], path: &Path) -> Result<()> {
+        let missing: Vec<String> = required
+            .iter()
+            .filter(|g| !self.provides(**g))
+            .map(|g| g.to_string())
+            .collect();
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(MutxError::GuaranteeUnavailable {
+                path: path.to_path_buf(),
+                missing: missing.join(", "),
+            })
+        }
+    }
+}
+
+/// Outcome of a committed write
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WriteReport {
+    /// File that was replaced
+    pub path: PathBuf,
+    /// Number of bytes in the new file
+    pub bytes_written: u64,
+    /// Fencing token of the lease the write was made under, if the lock
+    /// backend issues them. Downstream consumers can reject data carrying a
+    /// lower token than one they have already seen.
+    pub fencing_token: Option<u64>,
+    /// Guarantees the write was made with
+    pub guarantees: Guarantees,
+    /// Hex digest of the new file, if requested with
+    /// [`AtomicWriter::with_digest`]
+    pub digest: Option<String>,
+    /// Permission bits the new file was committed with (see
+    /// [`ModePolicy`]), serialized as an octal string such as `"0644"`
+    #[serde(serialize_with = "serialize_mode")]
+    pub mode: u32,
+    /// Backup of the replaced file, if one was asked for. The writer makes
+    /// no backups, so this is filled in by whoever made it.
+    pub backup: Option<BackupOutcome>,
+}
+
+fn serialize_mode<S: serde::Serializer>(
+    mode: &u32,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{:04o}", mode))
+}
+
+pub struct AtomicWriter {
+    mode: WriteMode,
+    target: PathBuf,
+    buffer: Vec<u8>,
+    temp_file: Option<StagedFile>,
+    temp_strategy: TempStrategy,
+    mode_policy: ModePolicy,
+    shared_group: Option<SharedGroup>,
+    directory: Option<StageDir>,
+    sync_directory: bool,
+    rename_retry: Duration,
+    spill_threshold: usize,
+    follow_symlinks: bool,
+    respect_readonly: bool,
+    bytes_written: u64,
+    fencing_token: Option<u64>,
+    fencing_xattr: bool,
+    compression_xattr: Option<CompressionFormat>,
+    lease_loss: Option<LeaseLoss>,
+    exclusive: bool,
+    transforms: Vec<Box<dyn Transform>>,
+    reclaimer: Option<SpaceReclaimer>,
+    hasher: Option<Hasher>,
+    max_size: Option<u64>,
+    min_free: Option<u64>,
+    passthrough: bool,
+    device: Option<File>,
+    lock: Option<(Box<dyn LockPathStrategy>, LockStrategy)>,
+    critical_section: CriticalSection,
+    held_lock: Option<FileLock>,
+    sink: Option<Box<dyn Sink>>,
+    /// Declared last so it outlives everything that may still use it
+    scratch: Option<ScratchDir>,
+}
+
+impl AtomicWriter {
+    /// Create a new atomic writer for the target file. Fails with
+    /// [`MutxError::IsADirectory`] if the target is an existing directory.
+    pub fn new(target: &Path, mode: WriteMode) -> Result<Self> {
+        if target.is_dir() {
+            return Err(MutxError::IsADirectory(target.to_path_buf()));
+        }
+        Ok(AtomicWriter {
+            mode,
+            target: target.to_path_buf(),
+            buffer: Vec::new(),
+            temp_file: None,
+            temp_strategy: TempStrategy::default(),
+            mode_policy: ModePolicy::default(),
+            shared_group: None,
+            directory: None,
+            sync_directory: true,
+            rename_retry: DEFAULT_RENAME_RETRY,
+            spill_threshold: DEFAULT_SPILL_THRESHOLD,
+            follow_symlinks: true,
+            respect_readonly: false,
+            bytes_written: 0,
+            fencing_token: None,
+            fencing_xattr: false,
+            compression_xattr: None,
+            lease_loss: None,
+            exclusive: true,
+            transforms: Vec::new(),
+            reclaimer: None,
+            hasher: None,
+            max_size: None,
+            min_free: None,
+            passthrough: false,
+            device: None,
+            lock: None,
+            critical_section: CriticalSection::default(),
+            held_lock: None,
+            sink: None,
+            scratch: None,
+        })
+    }
+
+    /// Refuse to commit if the target has become a symlink (or Windows reparse
+    /// point) since the writer was created. The check runs immediately before
+    /// the rename, narrowing the window left by validating the path up front.
+    pub fn with_follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.follow_symlinks = follow_symlinks;
+        self
+    }
+
+    /// Refuse to replace a read-only target. Replacing only needs write
+    /// access to the directory, so by default a read-only file is replaced
+    /// like any other; with this set commit fails with
+    /// [`MutxError::TargetReadOnly`], honoring the read-only bit as an
+    /// interlock. The check runs immediately before the rename.
+    pub fn with_respect_readonly(mut self, respect_readonly: bool) -> Self {
+        self.respect_readonly = respect_readonly;
+        self
+    }
+
+    /// Buffer at most `spill_threshold` bytes in memory in
+    /// [`WriteMode::Auto`] before moving to the temp file
+    pub fn with_spill_threshold(mut self, spill_threshold: usize) -> Self {
+        self.spill_threshold = spill_threshold;
+        self
+    }
+
+    /// Append `transform` to the pipeline the written data passes through
+    /// before reaching the temp file. Transforms run in the order added.
+    pub fn with_transform(mut self, transform: Box<dyn Transform>) -> Self {
+        self.transforms.push(transform);
+        self
+    }
+
+    /// When writing the temp file fails with [`MutxError::OutOfSpace`], call
+    /// `reclaimer` with the number of bytes still to write, to free about
+    /// that much space, and retry the rest of the write. Called again each
+    /// time space runs out, until it frees nothing.
+    pub fn with_space_reclaimer<F>(mut self, reclaimer: F) -> Self
+    where
+        F: FnMut(u64) -> Result<u64> + Send + 'static,
+    {
+        self.reclaimer = Some(Box::new(reclaimer));
+        self
+    }
+
+    /// Fail with [`MutxError::SizeLimitExceeded`] as soon as more than
+    /// `max_size` bytes (after any transforms) are written, leaving the
+    /// target untouched
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Refuse to commit with [`MutxError::BelowMinFree`] if less than
+    /// `min_free` bytes would remain available on the target's filesystem.
+    /// Checked once the content is staged, so it accounts for the new file;
+    /// skipped where free space cannot be queried (Windows).
+    pub fn with_min_free(mut self, min_free: u64) -> Self {
+        self.min_free = Some(min_free);
+        self
+    }
+
+    /// Compute a digest of the bytes committed to the target (after any
+    /// transforms), reported in [`WriteReport::digest`]
+    pub fn with_digest(mut self, algorithm: DigestAlgorithm) -> Self {
+        self.hasher = Some(algorithm.hasher());
+        self
+    }
+
+    /// Choose how the temporary file is created (see [`TempStrategy`])
+    pub fn with_temp_strategy(mut self, temp_strategy: TempStrategy) -> Self {
+        self.temp_strategy = temp_strategy;
+        self
+    }
+
+    /// Choose the permissions of the committed file (see [`ModePolicy`])
+    pub fn default_mode_policy(mut self, mode_policy: ModePolicy) -> Self {
+        self.mode_policy = mode_policy;
+        self
+    }
+
+    /// Give the committed file `group` and `g+rw`, on top of the
+    /// [`ModePolicy`]
+    pub fn with_shared_group(mut self, group: SharedGroup) -> Self {
+        self.shared_group = Some(group);
+        self
+    }
+
+    /// Stage and commit through an already open handle on the target's
+    /// directory rather than opening it for this write (see
+    /// [`AtomicWriterPool`])
+    pub fn with_directory(mut self, directory: StageDir) -> Self {
+        self.directory = Some(directory);
+        self
+    }
+
+    /// Whether commit fsyncs the directory after the rename (the default).
+    /// Without it the new file is atomic but the rename may be lost in a
+    /// crash until the directory is synced, e.g. by [`WriteBatch::finish`].
+    pub fn with_directory_sync(mut self, sync_directory: bool) -> Self {
+        self.sync_directory = sync_directory;
+        self
+    }
+
+    /// How long commit keeps retrying to replace a target another process
+    /// has open without delete sharing, as Windows programs often do,
+    /// before failing with [`MutxError::TargetInUse`] (default
+    /// [`DEFAULT_RENAME_RETRY`])
+    pub fn with_rename_retry(mut self, rename_retry: Duration) -> Self {
+        self.rename_retry = rename_retry;
+        self
+    }
+
+    /// Record the fencing token of the lock this write is made under
+    pub fn with_fencing_token(mut self, token: Option<u64>) -> Self {
+        self.fencing_token = token;
+        self
+    }
+
+    /// Refuse to commit once `loss` is set: the lease of the lock this write
+    /// is made under expired and may have been granted to another holder
+    /// (see [`FileLock::lease_loss`]). Checked immediately before the
+    /// rename.
+    pub fn with_lease_loss(mut self, loss: Option<LeaseLoss>) -> Self {
+        self.lease_loss = loss;
+        self
+    }
+
+    /// Also store the fencing token in the `user.mutx.fencing_token` extended
+    /// attribute of the new file. It is set on the temporary file, so it lands
+    /// atomically with the content. Commit fails if the filesystem does not
+    /// support extended attributes.
+    pub fn with_fencing_xattr(mut self, fencing_xattr: bool) -> Self {
+        self.fencing_xattr = fencing_xattr;
+        self
+    }
+
+    /// Record in the `user.mutx.compression` extended attribute of the new
+    /// file that its content is compressed in `format`, so its backups are
+    /// known to be. Best effort: the write goes ahead on filesystems without
+    /// extended attributes.
+    pub fn with_compression_xattr(mut self, format: Option<CompressionFormat>) -> Self {
+        self.compression_xattr = format;
+        self
+    }
+
+    /// Record whether the lock held for this write really excludes other
+    /// writers (see [`crate::lock::propagation::check_lock_propagation`])
+    pub fn with_exclusive(mut self, exclusive: bool) -> Self {
+        self.exclusive = exclusive;
+        self
+    }
+
+    /// If the target is a special file (a FIFO, a device, or a symlink to
+    /// one such as `/dev/stdout`), write to it directly instead of failing
+    /// with [`MutxError::SpecialFile`]. Such files cannot be replaced by a
+    /// rename, so the write is neither atomic nor durable: data reaches the
+    /// target as it is written. The target is opened on the first write.
+    pub fn with_passthrough_special(mut self, passthrough_special: bool) -> Self {
+        self.passthrough = passthrough_special && is_special_file(&self.target);
+        self
+    }
+
+    /// Publish the content to `sink` on commit instead of replacing the
+    /// target, which then only names the write: it is what gets locked, and
+    /// the path reported. The content is held in memory until commit, and
+    /// the permission and extended attribute options do not apply; the
+    /// report's mode is that of [`ModePolicy::Explicit`], or `0`.
+    pub fn with_sink(mut self, sink: Box<dyn Sink>) -> Self {
+        self.mode = WriteMode::InMemory;
+        self.sink = Some(sink);
+        self
+    }
+
+    /// File this writer replaces
+    pub fn target(&self) -> &Path {
+        &self.target
+    }
+
+    /// Have the writer lock its target itself, at the lock file `paths`
+    /// picks, over the part of the write `section` says: from the first
+    /// write for [`CriticalSection::Full`], otherwise only around the rename
+    /// in [`AtomicWriter::commit`] (the writer makes no backups). The lock is
+    /// released when the writer is committed or dropped.
+    pub fn with_lock(
+        mut self,
+        paths: impl LockPathStrategy + 'static,
+        strategy: LockStrategy,
+        section: CriticalSection,
+    ) -> Self {
+        self.lock = Some((Box::new(paths), strategy));
+        self.critical_section = section;
+        self
+    }
+
+    /// Take the lock configured with [`AtomicWriter::with_lock`], unless it
+    /// is already held
+    fn take_lock(&mut self) -> Result<()> {
+        if self.held_lock.is_some() {
+            return Ok(());
+        }
+        let Some((paths, strategy)) = &self.lock else {
+            return Ok(());
+        };
+        let lock = FileLock::acquire_for(&self.target, paths.as_ref(), strategy.clone())?;
+        if self.fencing_token.is_none() {
+            self.fencing_token = lock.fencing_token();
+        }
+        if self.lease_loss.is_none() {
+            self.lease_loss = lock.lease_loss();
+        }
+        self.held_lock = Some(lock);
+        Ok(())
+    }
+
+    /// Lock the target at the lock file `paths` picks for it. Hold the
+    /// returned lock until [`AtomicWriter::commit`] returns.
+    pub fn lock(&self, paths: &dyn LockPathStrategy, strategy: LockStrategy) -> Result<FileLock> {
+        FileLock::acquire_for(&self.target, paths, strategy)
+    }
+
+    /// A private directory for auxiliary files the write needs, such as a
+    /// transform's or validator's intermediate output. Created beside the
+    /// target on first use (under the writer's lock, if it locks from the
+    /// first write) and removed with its contents when the writer is
+    /// committed, fails to commit, or is dropped.
+    pub fn scratch_dir(&mut self) -> Result<&Path> {
+        if self.critical_section.locks_input() {
+            self.take_lock()?;
+        }
+        if self.scratch.is_none() {
+            self.scratch = Some(ScratchDir::create(&self.target)?);
+        }
+        Ok(self
+            .scratch
+            .as_ref()
+            .map_or(Path::new(""), ScratchDir::path))
+    }
+
+    /// Guarantees this writer will provide when committed
+    pub fn guarantees(&self) -> Guarantees {
+        if let Some(sink) = &self.sink {
+            return Guarantees {
+                atomic: sink.atomic(),
+                durable: sink.durable(),
+                exclusive: self.exclusive,
+            };
+        }
+        if self.passthrough {
+            return Guarantees {
+                atomic: false,
+                durable: false,
+                exclusive: self.exclusive,
+            };
+        }
+        Guarantees {
+            atomic: true,
+            // On Unix the rename is followed by an fsync of the directory;
+            // elsewhere only the file contents are synced
+            durable: cfg!(unix) && self.sync_directory,
+            exclusive: self.exclusive,
+        }
+    }
+
+    /// Write data (buffered in memory unless streaming)
+    pub fn write_all(&mut self, buf: &[u8]) -> Result<()> {
+        if self.critical_section.locks_input() {
+            self.take_lock()?;
+        }
+        if self.transforms.is_empty() {
+            return self.store(buf);
+        }
+        let transformed = transform::apply(&mut self.transforms, buf)?;
+        self.store(&transformed)
+    }
+
+    /// Hold `buf` according to the write mode
+    #[allow(deprecated)]
+    fn store(&mut self, buf: &[u8]) -> Result<()> {
+        if let Some(limit) = self.max_size {
+            if self.bytes_written + buf.len() as u64 > limit {
+                return Err(MutxError::SizeLimitExceeded {
+                    path: self.target.clone(),
+                    limit,
+                });
+            }
+        }
+        self.bytes_written += buf.len() as u64;
+        if let Some(hasher) = self.hasher.as_mut() {
+            hasher.update(buf);
+        }
+
+        if self.passthrough && self.sink.is_none() {
+            let target = &self.target;
+            let device = match self.device.as_mut() {
+                Some(device) => device,
+                None => self.device.insert(open_special(target)?),
+            };
+            return device.write_all(buf).map_err(|e| MutxError::WriteFailed {
+                path: target.clone(),
+                source: e,
+            });
+        }
+
+        match self.mode {
+            WriteMode::InMemory => {
+                self.buffer.extend_from_slice(buf);
+                Ok(())
+            }
+            WriteMode::Auto | WriteMode::Simple if self.temp_file.is_none() => {
+                self.buffer.extend_from_slice(buf);
+                if self.buffer.len() > self.spill_threshold {
+                    let mut temp = self.open_temp()?;
+                    write_temp(&mut temp, &self.buffer, &mut self.reclaimer)?;
+                    self.buffer = Vec::new();
+                    self.temp_file = Some(temp);
+                }
+                Ok(())
+            }
+            WriteMode::Auto | WriteMode::Simple | WriteMode::Streaming => {
+                // Initialize temp file on first write
+                if self.temp_file.is_none() {
+                    self.temp_file = Some(self.open_temp()?);
+                }
+
+                match self.temp_file.as_mut() {
+                    Some(temp) => write_temp(temp, buf, &mut self.reclaimer),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+
+    /// Whether data has moved from memory to the temp file
+    pub fn is_spilled(&self) -> bool {
+        self.temp_file.is_some()
+    }
+
+    /// Commit the write (atomic rename)
+    pub fn commit(mut self) -> Result<WriteReport> {
+        if !self.transforms.is_empty() {
+            let tail = transform::finish(&mut self.transforms)?;
+            self.store(&tail)?;
+        }
+        self.take_lock()?;
+        self.check_lease()?;
+
+        if self.sink.is_some() {
+            return self.commit_to_sink();
+        }
+        if self.passthrough {
+            return self.commit_passthrough();
+        }
+        // Renaming over a device node or FIFO would replace it with a
+        // regular file
+        if is_special_file(&self.target) {
+            return Err(MutxError::SpecialFile(self.target.clone()));
+        }
+
+        check_symlink(&self.target, self.follow_symlinks)?;
+        if self.respect_readonly && is_readonly(&self.target) {
+            return Err(MutxError::TargetReadOnly(self.target.clone()));
+        }
+
+        // Streaming and spilled writers already hold a temp file; buffered
+        // writers (or a stream that never received data) create it now
+        let mut temp = match self.temp_file.take() {
+            Some(temp) => temp,
+            None => self.open_temp()?,
+        };
+
+        if !self.buffer.is_empty() {
+            write_temp(&mut temp, &self.buffer, &mut self.reclaimer)?;
+        }
+
+        if let Some(min_free) = self.min_free {
+            self.check_min_free(min_free)?;
+        }
+
+        if self.fencing_xattr {
+            if let Some(token) = self.fencing_token {
+                set_xattr(
+                    temp.file(),
+                    FENCING_TOKEN_XATTR,
+                    token.to_string().as_bytes(),
+                )
+                .map_err(|e| MutxError::WriteFailed {
+                    path: self.target.clone(),
+                    source: e,
+                })?;
+            }
+        }
+
+        if let Some(format) = self.compression_xattr {
+            if let Err(e) = set_xattr(
+                temp.file(),
+                COMPRESSION_XATTR,
+                format.to_string().as_bytes(),
+            ) {
+                debug!(
+                    "Cannot record the compression of {}: {}",
+                    self.target.display(),
+                    e
+                );
+            }
+        }
+
+        temp.apply_mode_policy(self.mode_policy)?;
+        if let Some(group) = &self.shared_group {
+            group
+                .share_handle(temp.file())
+                .map_err(|e| MutxError::WriteStepFailed {
+                    path: self.target.clone(),
+                    step: WriteStep::SetPermissions,
+                    source: e,
+                })?;
+        }
+        let mode = temp.mode()?;
+
+        self.check_lease()?;
+        temp.with_directory_sync(self.sync_directory)
+            .with_rename_retry(self.rename_retry)
+            .commit()?;
+
+        let guarantees = self.guarantees();
+        Ok(WriteReport {
+            guarantees,
+            path: self.target,
+            bytes_written: self.bytes_written,
+            fencing_token: self.fencing_token,
+            digest: self.hasher.map(Hasher::finish_hex),
+            mode,
+            backup: None,
+        })
+    }
+
+    /// Fail if the lease of the lock this write is made under was lost
+    fn check_lease(&self) -> Result<()> {
+        if self.lease_loss.as_ref().is_some_and(LeaseLoss::is_lost) {
+            return Err(MutxError::LeaseLost {
+                path: self.target.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Publish the buffered content to the sink
+    fn commit_to_sink(mut self) -> Result<WriteReport> {
+        let guarantees = self.guarantees();
+        if let Some(mut sink) = self.sink.take() {
+            let len = self.buffer.len() as u64;
+            sink.publish(&mut self.buffer.as_slice(), len)?;
+        }
+        let mode = match self.mode_policy {
+            ModePolicy::Explicit(mode) => mode.bits(),
+            _ => 0,
+        };
+        Ok(WriteReport {
+            guarantees,
+            path: self.target,
+            bytes_written: self.bytes_written,
+            fencing_token: self.fencing_token,
+            digest: self.hasher.map(Hasher::finish_hex),
+            mode,
+            backup: None,
+        })
+    }
+
+    /// Finish a write made directly to a special file
+    fn commit_passthrough(mut self) -> Result<WriteReport> {
+        let device = match self.device.take() {
+            Some(device) => device,
+            None => open_special(&self.target)?,
+        };
+        let write_failed = |e| MutxError::WriteFailed {
+            path: self.target.clone(),
+            source: e,
+        };
+        let permissions = device.metadata().map_err(write_failed)?.permissions();
+        #[cfg(unix)]
+        let mode = {
+            use std::os::unix::fs::PermissionsExt;
+            permissions.mode() & 0o7777
+        };
+        #[cfg(not(unix))]
+        let mode = if permissions.readonly() { 0o444 } else { 0o666 };
+
+        let guarantees = self.guarantees();
+        Ok(WriteReport {
+            guarantees,
+            path: self.target,
+            bytes_written: self.bytes_written,
+            fencing_token: self.fencing_token,
+            digest: self.hasher.map(Hasher::finish_hex),
+            mode,
+            backup: None,
+        })
+    }
+
+    fn check_min_free(&self, min_free: u64) -> Result<()> {
+        let dir = match self.target.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        match available_space(dir) {
+            Ok(available) if available < min_free => Err(MutxError::BelowMinFree {
+                path: self.target.clone(),
+                available,
+                min_free,
+            }),
+            Ok(_) => Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::Unsupported => {
+                debug!(
+                    "Skipping free space check for {}: {}",
+                    self.target.display(),
+                    e
+                );
+                Ok(())
+            }
+            Err(e) => Err(MutxError::Io(e)),
+        }
+    }
+
+    fn open_temp(&self) -> Result<StagedFile> {
+        match &self.directory {
+            Some(directory) => {
+                StagedFile::create_in(directory.clone(), &self.target, self.temp_strategy)
+            }
+            None => StagedFile::create(&self.target, self.temp_strategy),
+        }
+    }
+}
+
+/// Write `buf` to the temp file; when the filesystem is full, free space with
+/// the reclaimer and retry what was not written, until it frees nothing
+fn write_temp(
+    temp: &mut StagedFile,
+    buf: &[u8],
+    reclaimer: &mut Option<SpaceReclaimer>,
+) -> Result<()> {
+    let start = temp.bytes_written();
+    loop {
+        let done = (temp.bytes_written() - start) as usize;
+        match temp.write_all(&buf[done..]) {
+            Err(err @ MutxError::OutOfSpace { .. }) => {
+                let Some(reclaim) = reclaimer.as_mut() else {
+                    return Err(err);
+                };
+                let remaining = buf.len() as u64 - (temp.bytes_written() - start);
+                let freed = reclaim(remaining)?;
+                if freed == 0 {
+                    return Err(err);
+                }
+                debug!("Freed {} bytes after running out of space, retrying", freed);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Open a special file for writing in place
+fn open_special(target: &Path) -> Result<File> {
+    OpenOptions::new()
+        .write(true)
+        .open(target)
+        .map_err(|e| MutxError::WriteFailed {
+            path: target.to_path_buf(),
+            source: e,
+        })
+}
+
+/// Whether `path` (following symlinks) exists and is neither a regular file
+/// nor a directory: a FIFO, socket or device, which cannot be replaced
+/// atomically
+pub fn is_special_file(path: &Path) -> bool {
+    std::fs::metadata(path).is_ok_and(|m| !m.is_file() && !m.is_dir())
+}
+
+/// Plain name of a file type, as error messages use it: "regular file",
+/// "directory", "FIFO", "character device" and so on
+pub fn file_type_name(file_type: &std::fs::FileType) -> &'static str {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        if file_type.is_fifo() {
+            return "FIFO";
+        }
+        if file_type.is_socket() {
+            return "socket";
+        }
+        if file_type.is_char_device() {
+            return "character device";
+        }
+        if file_type.is_block_device() {
+            return "block device";
+        }
+    }
+    if file_type.is_file() {
+        "regular file"
+    } else if file_type.is_dir() {
+        "directory"
+    } else if file_type.is_symlink() {
+        "symbolic link"
+    } else {
+        "special file"
+    }
+}
+
+/// Whether `path` is an existing file without write permission
+pub fn is_readonly(path: &Path) -> bool {
+    std::fs::metadata(path).is_ok_and(|m| m.permissions().readonly())
+}
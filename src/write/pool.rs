@@ -0,0 +1,168 @@
+use crate::error::{MutxError, Result};
+use crate::lock::{canonical_output_path, CacheDir, FileLock, LockPathStrategy, LockStrategy};
+use crate::write::{AtomicWriter, StageDir, WriteMode};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Per-target state that stays valid between writes
+#[derive(Debug)]
+struct PooledTarget {
+    canonical: PathBuf,
+    lock_path: PathBuf,
+    directory: StageDir,
+}
+
+/// Reuses path resolution and directory handles across repeated writes.
+///
+/// Programs that rewrite the same files over and over (a daemon refreshing a
+/// status file every second, say) otherwise pay for canonicalizing the path,
+/// hashing the lock file name and opening the directory for its fsync on
+/// every write. The pool does that once per target:
+///
+/// ```no_run
+/// use mutx::{AtomicWriterPool, LockStrategy, WriteMode};
+/// use std::path::Path;
+///
+/// let mut pool = AtomicWriterPool::new(WriteMode::Auto);
+/// let status = Path::new("/run/myapp/status.json");
+/// loop {
+///     let _lock = pool.lock_for(status, LockStrategy::Wait)?;
+///     let mut writer = pool.writer_for(status)?;
+///     writer.write_all(b"{\"ok\":true}")?;
+///     writer.commit()?;
+///     # break;
+/// }
+/// # Ok::<(), mutx::MutxError>(())
+/// ```
+///
+/// Symlinks in a target path are resolved when it is first used; call
+/// [`AtomicWriterPool::forget`] if the path may since point elsewhere.
+pub struct AtomicWriterPool {
+    mode: WriteMode,
+    lock_paths: Box<dyn LockPathStrategy>,
+    targets: HashMap<PathBuf, PooledTarget>,
+}
+
+impl fmt::Debug for AtomicWriterPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AtomicWriterPool")
+            .field("mode", &self.mode)
+            .field("targets", &self.targets)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AtomicWriterPool {
+    /// Create an empty pool whose writers use `mode`
+    pub fn new(mode: WriteMode) -> Self {
+        AtomicWriterPool {
+            mode,
+            lock_paths: Box::new(CacheDir::new()),
+            targets: HashMap::new(),
+        }
+    }
+
+    /// Place the locks of targets resolved from now on where `lock_paths`
+    /// says (default: [`CacheDir`])
+    pub fn with_lock_paths(mut self, lock_paths: impl LockPathStrategy + 'static) -> Self {
+        self.lock_paths = Box::new(lock_paths);
+        self
+    }
+
+    /// A fresh writer for `path`, set up from the cached state
+    pub fn writer_for(&mut self, path: &Path) -> Result<AtomicWriter> {
+        let mode = self.mode;
+        let target = self.target(path)?;
+        Ok(AtomicWriter::new(&target.canonical, mode)?.with_directory(target.directory.clone()))
+    }
+
+    /// Lock file `path`'s writers should hold, derived once per target
+    pub fn lock_path_for(&mut self, path: &Path) -> Result<PathBuf> {
+        Ok(self.target(path)?.lock_path.clone())
+    }
+
+    /// Acquire the default lock for `path` at its cached lock path
+    pub fn lock_for(&mut self, path: &Path, strategy: LockStrategy) -> Result<FileLock> {
+        FileLock::acquire(&self.target(path)?.lock_path, strategy)
+    }
+
+    /// Drop the cached state for `path`, so the next write resolves it again
+    pub fn forget(&mut self, path: &Path) {
+        self.targets.remove(path);
+    }
+
+    /// Number of targets with cached state
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    /// Whether no target has cached state yet
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    fn target(&mut self, path: &Path) -> Result<&PooledTarget> {
+        if !self.targets.contains_key(path) {
+            let target = PooledTarget::resolve(path, self.lock_paths.as_ref())?;
+            self.targets.insert(path.to_path_buf(), target);
+        }
+        Ok(&self.targets[path])
+    }
+}
+
+impl PooledTarget {
+    fn resolve(path: &Path, lock_paths: &dyn LockPathStrategy) -> Result<Self> {
+        let canonical = canonical_output_path(path)?;
+        let lock_path = lock_paths.lock_path(&canonical)?;
+
+        let directory = StageDir::for_target(&canonical).map_err(|e| MutxError::WriteFailed {
+            path: path.to_path_buf(),
+            source: e,
+        })?;
+
+        Ok(PooledTarget {
+            canonical,
+            lock_path,
+            directory,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lock::derive_lock_path;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_target_state_is_cached() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("status.txt");
+        let mut pool = AtomicWriterPool::new(WriteMode::Auto);
+
+        let first = pool.lock_path_for(&target).unwrap();
+        let second = pool.lock_path_for(&target).unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(first, derive_lock_path(&target, false).unwrap());
+        assert_eq!(pool.len(), 1);
+
+        pool.forget(&target);
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn test_missing_parent_is_not_cached() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("missing").join("status.txt");
+        let mut pool = AtomicWriterPool::new(WriteMode::Auto);
+
+        assert!(pool.writer_for(&target).is_err());
+        assert!(pool.is_empty());
+
+        fs::create_dir(temp.path().join("missing")).unwrap();
+        assert!(pool.writer_for(&target).is_ok());
+    }
+}
@@ -0,0 +1,52 @@
+//! Scratch directories for files a write needs besides its content.
+
+use crate::error::{MutxError, Result};
+use crate::utils::unique_scratch_path;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// A private directory beside a write's target, removed with everything in
+/// it when dropped. Left behind only if the process dies, in which case
+/// `mutx housekeep temps` removes it.
+#[derive(Debug)]
+pub(crate) struct ScratchDir {
+    path: PathBuf,
+}
+
+impl ScratchDir {
+    /// Create a scratch directory for writes to `target`, readable only by
+    /// this user
+    pub(crate) fn create(target: &Path) -> Result<Self> {
+        let path = unique_scratch_path(target);
+        let mut builder = fs::DirBuilder::new();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        builder.create(&path).map_err(|e| MutxError::WriteFailed {
+            path: path.clone(),
+            source: e,
+        })?;
+        debug!("Created scratch directory {}", path.display());
+        Ok(ScratchDir { path })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        match fs::remove_dir_all(&self.path) {
+            Ok(()) => debug!("Removed scratch directory {}", self.path.display()),
+            Err(e) => warn!(
+                "Failed to remove scratch directory {}: {}",
+                self.path.display(),
+                e
+            ),
+        }
+    }
+}
@@ -0,0 +1,66 @@
+#![cfg(feature = "tokio")]
+
+use mutx::{FileLock, LockStrategy, MutxError, TimeoutConfig};
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test_acquire_async_no_wait() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+
+    let held = FileLock::acquire_async(&lock_path, LockStrategy::NoWait)
+        .await
+        .unwrap();
+    assert_eq!(held.path(), lock_path);
+
+    let err = FileLock::acquire_async(&lock_path, LockStrategy::NoWait)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, MutxError::LockWouldBlock { .. }));
+}
+
+#[tokio::test]
+async fn test_acquire_async_timeout_names_holder() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+    let _held = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+
+    let strategy = LockStrategy::Timeout(TimeoutConfig::new(Duration::from_millis(200)));
+    let start = Instant::now();
+    let err = FileLock::acquire_async(&lock_path, strategy)
+        .await
+        .unwrap_err();
+    assert!(start.elapsed() >= Duration::from_millis(200));
+    match err {
+        MutxError::LockTimeout { holder, .. } => {
+            if cfg!(unix) {
+                assert_eq!(holder.unwrap().pid, std::process::id());
+            }
+        }
+        other => panic!("expected LockTimeout, got {:?}", other),
+    }
+}
+
+/// Waiting happens on the timer, so a single-threaded runtime keeps running
+/// the task that releases the lock
+#[tokio::test(flavor = "current_thread")]
+async fn test_wait_does_not_block_the_runtime() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+    let held = FileLock::acquire_async(&lock_path, LockStrategy::NoWait)
+        .await
+        .unwrap();
+
+    let release = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        drop(held);
+    });
+
+    let waiter = {
+        let lock_path = lock_path.clone();
+        tokio::spawn(async move { FileLock::acquire_async(&lock_path, LockStrategy::Wait).await })
+    };
+    waiter.await.unwrap().unwrap();
+    release.await.unwrap();
+}
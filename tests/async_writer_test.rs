@@ -0,0 +1,62 @@
+#![cfg(feature = "tokio")]
+
+use mutx::{AsyncAtomicWriter, AtomicWriter, MutxError, WriteMode};
+use std::fs;
+use tempfile::TempDir;
+use tokio::io::AsyncWriteExt;
+
+#[tokio::test]
+async fn test_async_stream_and_commit() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("out.bin");
+    fs::write(&target, "old").unwrap();
+
+    let writer = AtomicWriter::new(&target, WriteMode::Streaming).unwrap();
+    let mut writer = AsyncAtomicWriter::new(writer);
+    let chunk = vec![7u8; 100 * 1024];
+    for _ in 0..5 {
+        writer.write_all(&chunk).await.unwrap();
+    }
+    writer.flush().await.unwrap();
+    assert_eq!(fs::read(&target).unwrap(), b"old");
+
+    let report = writer.commit().await.unwrap();
+    assert_eq!(report.bytes_written, 500 * 1024);
+    assert_eq!(fs::read(&target).unwrap(), vec![7u8; 500 * 1024]);
+}
+
+#[tokio::test]
+async fn test_dropped_writer_leaves_target_alone() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("out.txt");
+    fs::write(&target, "old").unwrap();
+
+    let mut writer: AsyncAtomicWriter = AtomicWriter::new(&target, WriteMode::Streaming)
+        .unwrap()
+        .into();
+    writer.write_all(b"partial").await.unwrap();
+    writer.shutdown().await.unwrap();
+    drop(writer);
+
+    assert_eq!(fs::read_to_string(&target).unwrap(), "old");
+    let entries = fs::read_dir(temp.path()).unwrap().count();
+    assert_eq!(entries, 1);
+}
+
+#[tokio::test]
+async fn test_write_error_surfaces() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("out.txt");
+
+    let writer = AtomicWriter::new(&target, WriteMode::InMemory)
+        .unwrap()
+        .with_max_size(4);
+    let mut writer = AsyncAtomicWriter::new(writer);
+    writer.write_all(b"too long").await.unwrap();
+
+    let err = writer.flush().await.unwrap_err();
+    let source = err.get_ref().unwrap().downcast_ref::<MutxError>().unwrap();
+    assert!(matches!(source, MutxError::SizeLimitExceeded { .. }));
+    assert!(writer.commit().await.is_err());
+    assert!(!target.exists());
+}
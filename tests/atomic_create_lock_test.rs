@@ -0,0 +1,149 @@
+use assert_cmd::Command;
+use filetime::{set_file_mtime, FileTime};
+use mutx::lock::{dotlock_path, ATOMIC_CREATE_STALE_AFTER};
+use mutx::utils::process::hostname;
+use mutx::{FileLock, LockBackend, LockStrategy, MutxError};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+
+fn acquire(lock_path: &Path) -> mutx::Result<FileLock> {
+    FileLock::acquire_with_backend(lock_path, LockStrategy::NoWait, LockBackend::AtomicCreate)
+}
+
+fn age(path: &Path, by: Duration) {
+    let mtime = SystemTime::now() - by;
+    set_file_mtime(path, FileTime::from_system_time(mtime)).unwrap();
+}
+
+#[test]
+fn test_backend_parses_and_places_lock_beside_target() {
+    let backend: LockBackend = "atomic-create".parse().unwrap();
+    assert_eq!(backend, LockBackend::AtomicCreate);
+    assert_eq!(backend.to_string(), "atomic-create");
+
+    let target = Path::new("/srv/share/report.csv");
+    assert_eq!(
+        backend.default_lock_path(target),
+        Some(dotlock_path(target))
+    );
+}
+
+#[test]
+fn test_lock_records_host_and_pid_and_is_removed() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("report.csv.lock");
+
+    let lock = acquire(&lock_path).unwrap();
+    let content = fs::read_to_string(&lock_path).unwrap();
+    assert_eq!(
+        content.trim(),
+        format!("{}:{}", hostname().unwrap_or_default(), std::process::id())
+    );
+
+    drop(lock);
+    assert!(!lock_path.exists());
+}
+
+#[test]
+fn test_existing_lock_excludes() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("report.csv.lock");
+
+    let _held = acquire(&lock_path).unwrap();
+    assert!(matches!(
+        acquire(&lock_path),
+        Err(MutxError::LockWouldBlock { .. })
+    ));
+
+    // A fresh lock from another host is respected whatever its PID
+    let other = temp.path().join("other.lock");
+    fs::write(&other, "elsewhere.example:1\n").unwrap();
+    assert!(matches!(
+        acquire(&other),
+        Err(MutxError::LockWouldBlock { .. })
+    ));
+}
+
+#[test]
+fn test_lock_with_old_mtime_is_broken() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("report.csv.lock");
+    fs::write(&lock_path, "elsewhere.example:1\n").unwrap();
+    age(&lock_path, ATOMIC_CREATE_STALE_AFTER * 2);
+
+    let _lock = acquire(&lock_path).unwrap();
+    assert!(fs::read_to_string(&lock_path)
+        .unwrap()
+        .ends_with(&format!(":{}\n", std::process::id())));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_lock_of_dead_local_process_is_broken() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("report.csv.lock");
+    let mut child = std::process::Command::new("true").spawn().unwrap();
+    let dead_pid = child.id();
+    child.wait().unwrap();
+    fs::write(
+        &lock_path,
+        format!("{}:{}\n", hostname().unwrap(), dead_pid),
+    )
+    .unwrap();
+
+    acquire(&lock_path).unwrap();
+}
+
+#[test]
+fn test_release_leaves_a_replaced_lock_alone() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("report.csv.lock");
+
+    let lock = acquire(&lock_path).unwrap();
+    fs::remove_file(&lock_path).unwrap();
+    fs::write(&lock_path, "elsewhere.example:1\n").unwrap();
+    drop(lock);
+
+    assert_eq!(
+        fs::read_to_string(&lock_path).unwrap(),
+        "elsewhere.example:1\n"
+    );
+}
+
+#[test]
+fn test_shared_locks_are_refused() {
+    let temp = TempDir::new().unwrap();
+    let result = FileLock::acquire_with_backend(
+        &temp.path().join("report.csv.lock"),
+        LockStrategy::NoWait.shared(),
+        LockBackend::AtomicCreate,
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_cli_atomic_create_backend() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("report.csv");
+    let lock_path = dotlock_path(&output);
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--lock-backend", "atomic-create"])
+        .write_stdin("a,b\n")
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&output).unwrap(), "a,b\n");
+    assert!(!lock_path.exists());
+
+    fs::write(&lock_path, "elsewhere.example:1\n").unwrap();
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--lock-backend", "atomic-create", "--no-wait"])
+        .write_stdin("c,d\n")
+        .assert()
+        .code(2);
+    assert_eq!(fs::read_to_string(&output).unwrap(), "a,b\n");
+}
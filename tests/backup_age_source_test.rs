@@ -0,0 +1,155 @@
+use assert_cmd::Command;
+use chrono::{Duration as ChronoDuration, Local};
+use filetime::{set_file_mtime, FileTime};
+use mutx::housekeep::{clean_backups, AgeSource, CleanBackupConfig};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+
+const DAY: u64 = 24 * 60 * 60;
+
+fn timestamp_days_ago(days: i64) -> String {
+    (Local::now() - ChronoDuration::days(days))
+        .format("%Y%m%d_%H%M%S")
+        .to_string()
+}
+
+fn backup(dir: &Path, name: &str, mtime_days_ago: u64) -> PathBuf {
+    let path = dir.join(name);
+    fs::write(&path, "backup").unwrap();
+    let mtime = SystemTime::now() - Duration::from_secs(mtime_days_ago * DAY);
+    set_file_mtime(&path, FileTime::from_system_time(mtime)).unwrap();
+    path
+}
+
+fn clean(dir: &Path, age_source: AgeSource) -> Vec<PathBuf> {
+    clean_backups(&CleanBackupConfig {
+        dir: dir.to_path_buf(),
+        recursive: false,
+        older_than: Some(Duration::from_secs(7 * DAY)),
+        keep_newest: None,
+        dry_run: true,
+        suffix: ".mutx.backup".parse().unwrap(),
+        age_source,
+        max_total_size: None,
+    })
+    .unwrap()
+}
+
+/// A backup taken 30 days ago but copied today (fresh mtime), and one taken
+/// today whose mtime was preserved from an old file
+fn setup() -> (TempDir, PathBuf, PathBuf) {
+    let temp = TempDir::new().unwrap();
+    let copied = backup(
+        temp.path(),
+        &format!("a.txt.{}.mutx.backup", timestamp_days_ago(30)),
+        0,
+    );
+    let preserved = backup(
+        temp.path(),
+        &format!("b.txt.{}.mutx.backup", timestamp_days_ago(0)),
+        30,
+    );
+    (temp, copied, preserved)
+}
+
+#[test]
+fn test_age_source_name_uses_embedded_timestamp() {
+    let (temp, copied, _) = setup();
+    assert_eq!(clean(temp.path(), AgeSource::Name), vec![copied]);
+}
+
+#[test]
+fn test_age_source_mtime() {
+    let (temp, _, preserved) = setup();
+    assert_eq!(clean(temp.path(), AgeSource::Mtime), vec![preserved]);
+}
+
+#[test]
+fn test_age_source_newest_requires_both_to_be_old() {
+    let (temp, _, _) = setup();
+    assert!(clean(temp.path(), AgeSource::Newest).is_empty());
+
+    let old = backup(
+        temp.path(),
+        &format!("c.txt.{}.mutx.backup", timestamp_days_ago(30)),
+        30,
+    );
+    assert_eq!(clean(temp.path(), AgeSource::Newest), vec![old]);
+}
+
+#[test]
+fn test_age_source_name_falls_back_to_mtime() {
+    let temp = TempDir::new().unwrap();
+    let old = backup(temp.path(), "d.txt.mutx.backup", 30);
+    assert_eq!(clean(temp.path(), AgeSource::Name), vec![old]);
+}
+
+#[test]
+fn test_keep_newest_orders_by_age_source() {
+    let temp = TempDir::new().unwrap();
+    // Newer by name, older by mtime
+    let newer = backup(
+        temp.path(),
+        &format!("e.txt.{}.mutx.backup", timestamp_days_ago(1)),
+        10,
+    );
+    let older = backup(
+        temp.path(),
+        &format!("e.txt.{}.mutx.backup", timestamp_days_ago(2)),
+        0,
+    );
+
+    let config = CleanBackupConfig {
+        dir: temp.path().to_path_buf(),
+        recursive: false,
+        older_than: None,
+        keep_newest: Some(1),
+        dry_run: true,
+        suffix: ".mutx.backup".parse().unwrap(),
+        age_source: AgeSource::Name,
+        max_total_size: None,
+    };
+    assert_eq!(clean_backups(&config).unwrap(), vec![older.clone()]);
+
+    let config = CleanBackupConfig {
+        age_source: AgeSource::Mtime,
+        ..config
+    };
+    assert_eq!(clean_backups(&config).unwrap(), vec![newer]);
+}
+
+#[test]
+fn test_cli_age_source() {
+    let (temp, copied, preserved) = setup();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["housekeep", "backups"])
+        .arg(temp.path())
+        .args(["--older-than", "7d", "--age-source", "mtime"])
+        .assert()
+        .success();
+    assert!(copied.exists());
+    assert!(!preserved.exists());
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["housekeep", "backups"])
+        .arg(temp.path())
+        .args(["--older-than", "7d"])
+        .assert()
+        .success();
+    assert!(!copied.exists());
+}
+
+#[test]
+fn test_cli_rejects_unknown_age_source() {
+    let temp = TempDir::new().unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["housekeep", "backups"])
+        .arg(temp.path())
+        .args(["--age-source", "ctime"])
+        .assert()
+        .failure();
+}
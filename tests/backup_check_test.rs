@@ -0,0 +1,294 @@
+use assert_cmd::Command;
+use filetime::{set_file_mtime, FileTime};
+use mutx::backup::{create_backup, BackupConfig};
+use mutx::schema::{BackupCheckReport, BackupCheckResult, CheckStatus};
+#[cfg(feature = "compression")]
+use mutx::utils::xattr::COMPRESSION_XATTR;
+use mutx::utils::xattr::{set_xattr, BACKUP_SHA256_XATTR};
+use predicates::prelude::*;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn mutx(temp: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("mutx").unwrap();
+    cmd.env("XDG_CACHE_HOME", temp.path().join("cache"))
+        .env("MUTX_CONFIG", temp.path().join("config.toml"))
+        .env_remove("MUTX_LOCK_DIR");
+    cmd
+}
+
+/// `mutx backups check --json` for `file`, and whether it exited successfully
+fn check(temp: &TempDir, file: &Path) -> (BackupCheckReport, bool) {
+    let output = mutx(temp)
+        .args(["backups", "check", "--json"])
+        .arg(file)
+        .output()
+        .unwrap();
+    let report = serde_json::from_slice(&output.stdout).unwrap();
+    (report, output.status.success())
+}
+
+fn result<'a>(report: &'a BackupCheckReport, backup: &Path) -> &'a BackupCheckResult {
+    report
+        .backups
+        .iter()
+        .find(|result| result.path == backup)
+        .unwrap_or_else(|| panic!("{} not checked in {:?}", backup.display(), report))
+}
+
+/// Content that does not compress into a delta by luck
+fn content(len: usize, seed: u64) -> Vec<u8> {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 56) as u8
+        })
+        .collect()
+}
+
+/// A full backup of `data` from long ago, as an earlier `--backup-timestamp` run leaves
+fn old_backup(dir: &Path, name: &str, data: &[u8]) -> PathBuf {
+    let path = dir.join(format!("{}.20240101_000000.mutx.backup", name));
+    fs::write(&path, data).unwrap();
+    set_file_mtime(&path, FileTime::from_unix_time(1_704_067_200, 0)).unwrap();
+    path
+}
+
+/// Back up `source` with `data`, storing the backup before it as a delta
+fn delta_backup(source: &Path, data: &[u8]) -> PathBuf {
+    fs::write(source, data).unwrap();
+    create_backup(&BackupConfig {
+        source: source.to_path_buf(),
+        suffix: ".mutx.backup".parse().unwrap(),
+        directory: None,
+        timestamp: true,
+        prune_on_enospc: None,
+        delta: true,
+    })
+    .unwrap()
+}
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn test_intact_backups_pass() {
+    let temp = TempDir::new().unwrap();
+    let source = temp.path().join("state.bin");
+    let v1 = content(200_000, 1);
+    let mut v2 = v1.clone();
+    v2[1_000] ^= 0xff;
+    let previous = old_backup(temp.path(), "state.bin", &v1);
+    let newest = delta_backup(&source, &v2);
+
+    let (report, success) = check(&temp, &source);
+    assert!(success);
+    assert!(report.passed);
+    assert_eq!(report.backups.len(), 2);
+    // Oldest first
+    assert_eq!(report.backups[0].path, previous);
+
+    let delta = result(&report, &previous);
+    assert_eq!(delta.status, CheckStatus::Pass);
+    assert_eq!(delta.delta, Some(true));
+    assert_eq!(delta.size, Some(200_000));
+    assert!(
+        delta.detail.contains("recorded SHA-256"),
+        "{}",
+        delta.detail
+    );
+
+    let full = result(&report, &newest);
+    assert_eq!(full.status, CheckStatus::Pass);
+    assert_eq!(full.delta, Some(false));
+    assert_eq!(full.compression, None);
+}
+
+#[test]
+fn test_delta_against_modified_backup_fails() {
+    let temp = TempDir::new().unwrap();
+    let source = temp.path().join("state.bin");
+    let v1 = content(200_000, 2);
+    let mut v2 = v1.clone();
+    v2[5_000] ^= 0xff;
+    let previous = old_backup(temp.path(), "state.bin", &v1);
+    let newest = delta_backup(&source, &v2);
+
+    // The newest backup no longer matches its checksum, nor rebuilds the
+    // one before it
+    let mut modified = fs::read(&newest).unwrap();
+    modified[100_000] ^= 0xff;
+    fs::write(&newest, modified).unwrap();
+
+    let (report, success) = check(&temp, &source);
+    assert!(!success);
+    assert!(!report.passed);
+    assert_eq!(result(&report, &newest).status, CheckStatus::Fail);
+    let delta = result(&report, &previous);
+    assert_eq!(delta.status, CheckStatus::Fail);
+    assert_eq!(delta.delta, Some(true));
+    assert_eq!(delta.size, None);
+
+    // The rebuilt version is not left behind
+    assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 3);
+}
+
+/// Record `format` on `backup` as mutx does for content it compressed
+#[cfg(feature = "compression")]
+fn mark_compressed(backup: &Path, format: &str) {
+    let file = fs::File::open(backup).unwrap();
+    set_xattr(&file, COMPRESSION_XATTR, format.as_bytes()).unwrap();
+}
+
+#[test]
+fn test_modified_full_backup_fails() {
+    let temp = TempDir::new().unwrap();
+    let source = temp.path().join("app.conf");
+    fs::write(&source, "version one").unwrap();
+    let backup = delta_backup(&source, b"version one");
+
+    let (report, _) = check(&temp, &source);
+    let intact = result(&report, &backup);
+    assert_eq!(intact.status, CheckStatus::Pass);
+    assert!(
+        intact.detail.contains("matching its recorded SHA-256"),
+        "{}",
+        intact.detail
+    );
+
+    // Same length, different bytes: only the checksum tells
+    fs::write(&backup, "version 0ne").unwrap();
+    let (report, success) = check(&temp, &source);
+    assert!(!success);
+    let modified = result(&report, &backup);
+    assert_eq!(modified.status, CheckStatus::Fail);
+    assert!(
+        modified.detail.contains("does not match the SHA-256"),
+        "{}",
+        modified.detail
+    );
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn test_compressed_backups_are_decoded() {
+    let temp = TempDir::new().unwrap();
+    let source = temp.path().join("app.json.gz");
+    for (content, backup) in [
+        ("{\"key\": \"value\"}", false),
+        ("{\"key\": \"other\"}", true),
+    ] {
+        mutx(&temp)
+            .arg(&source)
+            .args(["--compress", "gzip"])
+            .args(backup.then_some("--backup"))
+            .write_stdin(content)
+            .assert()
+            .success();
+    }
+    let backup = temp.path().join("app.json.gz.mutx.backup");
+    let compressed = gzip(b"{}");
+    let truncated = old_backup(
+        temp.path(),
+        "app.json.gz",
+        &compressed[..compressed.len() - 6],
+    );
+    mark_compressed(&truncated, "gzip");
+
+    let (report, success) = check(&temp, &source);
+    assert!(!success);
+
+    let decoded = result(&report, &backup);
+    assert_eq!(decoded.status, CheckStatus::Pass);
+    assert_eq!(decoded.compression.as_deref(), Some("gzip"));
+    assert!(
+        decoded.detail.contains("decodes to 16 bytes"),
+        "{}",
+        decoded.detail
+    );
+
+    let failed = result(&report, &truncated);
+    assert_eq!(failed.status, CheckStatus::Fail);
+    assert!(
+        failed.detail.contains("Cannot decode gzip"),
+        "{}",
+        failed.detail
+    );
+}
+
+#[test]
+fn test_content_that_only_looks_compressed_is_not_decoded() {
+    let temp = TempDir::new().unwrap();
+    let source = temp.path().join("app.conf");
+    // A file that happens to start with the gzip magic number
+    let backup = old_backup(temp.path(), "app.conf", &gzip(b"older")[..8]);
+
+    let (report, success) = check(&temp, &source);
+    assert!(success);
+    let checked = result(&report, &backup);
+    assert_eq!(checked.status, CheckStatus::Pass);
+    assert_eq!(checked.compression, None);
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn test_verify_backup_reports_decoded_size() {
+    let temp = TempDir::new().unwrap();
+    let backup = temp.path().join("out.mutx.backup");
+    let compressed = gzip(&[b'a'; 4096]);
+    fs::write(&backup, &compressed).unwrap();
+    mark_compressed(&backup, "gzip");
+
+    let verified = mutx::verify_backup(&backup).unwrap();
+    assert!(!verified.delta);
+    assert!(!verified.checksum);
+    assert_eq!(verified.size, compressed.len() as u64);
+    assert_eq!(
+        verified.decoded,
+        Some((mutx::CompressionFormat::Gzip, 4096))
+    );
+}
+
+#[test]
+fn test_no_backups_fails() {
+    let temp = TempDir::new().unwrap();
+    let source = temp.path().join("app.conf");
+    fs::write(&source, "current").unwrap();
+
+    mutx(&temp)
+        .args(["backups", "check"])
+        .arg(&source)
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("No backups of"));
+}
+
+#[test]
+fn test_text_report() {
+    let temp = TempDir::new().unwrap();
+    let source = temp.path().join("app.conf");
+    fs::write(temp.path().join("app.conf.mutx.backup"), "old").unwrap();
+    let broken = old_backup(temp.path(), "app.conf", b"older");
+    let file = fs::File::open(&broken).unwrap();
+    set_xattr(&file, BACKUP_SHA256_XATTR, b"0000").unwrap();
+
+    mutx(&temp)
+        .args(["backups", "check"])
+        .arg(&source)
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("[  ok]"))
+        .stdout(predicate::str::contains(
+            "3 bytes, read in full; no checksum recorded",
+        ))
+        .stdout(predicate::str::contains("[FAIL]"))
+        .stderr(predicate::str::contains("1 of 2 backups of"));
+}
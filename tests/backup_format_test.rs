@@ -10,9 +10,11 @@ fn test_backup_filename_format_with_timestamp() {
 
     let config = BackupConfig {
         source: source.clone(),
-        suffix: ".mutx.backup".to_string(),
+        suffix: ".mutx.backup".parse().unwrap(),
         directory: None,
         timestamp: true,
+        prune_on_enospc: None,
+        delta: false,
     };
 
     let backup_path = create_backup(&config).unwrap();
@@ -45,9 +47,11 @@ fn test_backup_filename_format_without_timestamp() {
 
     let config = BackupConfig {
         source,
-        suffix: ".mutx.backup".to_string(),
+        suffix: ".mutx.backup".parse().unwrap(),
         directory: None,
         timestamp: false,
+        prune_on_enospc: None,
+        delta: false,
     };
 
     let backup_path = create_backup(&config).unwrap();
@@ -56,3 +60,41 @@ fn test_backup_filename_format_without_timestamp() {
     // Without timestamp: config.json.mutx.backup
     assert_eq!(filename, "config.json.mutx.backup");
 }
+
+#[test]
+fn test_backups_in_one_second_are_numbered_and_age_in_order() {
+    let temp = TempDir::new().unwrap();
+    let source = temp.path().join("data.txt");
+    let config = BackupConfig {
+        source: source.clone(),
+        suffix: ".mutx.backup".parse().unwrap(),
+        directory: None,
+        timestamp: true,
+        prune_on_enospc: None,
+        delta: false,
+    };
+
+    let mut backups = Vec::new();
+    for version in ["one", "two", "three"] {
+        fs::write(&source, version).unwrap();
+        backups.push(create_backup(&config).unwrap());
+    }
+    let names: Vec<&str> = backups
+        .iter()
+        .map(|path| path.file_name().unwrap().to_str().unwrap())
+        .collect();
+    // A backup in the same second as the one before it is numbered (the
+    // clock may tick over between them)
+    for pair in names.windows(2) {
+        assert_ne!(pair[0], pair[1]);
+        if pair[0][..24] == pair[1][..24] {
+            assert!(pair[1][24..].starts_with('-'), "{}", pair[1]);
+        }
+    }
+
+    // The numbered backups are the newer ones
+    mutx::housekeep::prune_backups(&source, ".mutx.backup", None, 1).unwrap();
+    assert!(!backups[0].exists());
+    assert!(!backups[1].exists());
+    assert_eq!(fs::read_to_string(&backups[2]).unwrap(), "three");
+}
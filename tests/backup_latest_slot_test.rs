@@ -0,0 +1,109 @@
+use assert_cmd::Command;
+use filetime::{set_file_mtime, FileTime};
+use mutx::housekeep::{clean_backups, AgeSource, CleanBackupConfig};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn backup(dir: &Path, name: &str, mtime: i64) -> PathBuf {
+    let path = dir.join(name);
+    fs::write(&path, name).unwrap();
+    set_file_mtime(&path, FileTime::from_unix_time(mtime, 0)).unwrap();
+    path
+}
+
+fn keep_newest(dir: &Path, keep: usize, older_than: Option<Duration>) -> Vec<PathBuf> {
+    let mut cleaned = clean_backups(&CleanBackupConfig {
+        dir: dir.to_path_buf(),
+        recursive: false,
+        older_than,
+        keep_newest: Some(keep),
+        dry_run: false,
+        suffix: ".mutx.backup".parse().unwrap(),
+        age_source: AgeSource::Name,
+        max_total_size: None,
+    })
+    .unwrap();
+    cleaned.sort();
+    cleaned
+}
+
+#[test]
+fn test_latest_slot_not_counted_by_keep_newest() {
+    let temp = TempDir::new().unwrap();
+    // The latest slot is the most recent by mtime; it must not push a
+    // timestamped backup out of the kept set
+    let latest = backup(temp.path(), "file.txt.mutx.backup", 4_000_000_000);
+    let newest = backup(
+        temp.path(),
+        "file.txt.20260103_000000.mutx.backup",
+        1_000_000,
+    );
+    let middle = backup(
+        temp.path(),
+        "file.txt.20260102_000000.mutx.backup",
+        1_000_000,
+    );
+    let oldest = backup(
+        temp.path(),
+        "file.txt.20260101_000000.mutx.backup",
+        1_000_000,
+    );
+
+    assert_eq!(keep_newest(temp.path(), 2, None), vec![oldest]);
+    assert!(latest.exists());
+    assert!(newest.exists());
+    assert!(middle.exists());
+}
+
+#[test]
+fn test_latest_slot_survives_keep_zero() {
+    let temp = TempDir::new().unwrap();
+    let latest = backup(temp.path(), "file.txt.mutx.backup", 1_000_000);
+    let stamped = backup(
+        temp.path(),
+        "file.txt.20260101_000000.mutx.backup",
+        1_000_000,
+    );
+
+    assert_eq!(keep_newest(temp.path(), 0, None), vec![stamped]);
+    assert!(latest.exists());
+}
+
+#[test]
+fn test_latest_slot_still_subject_to_older_than() {
+    let temp = TempDir::new().unwrap();
+    let latest = backup(temp.path(), "file.txt.mutx.backup", 1_000_000);
+
+    assert_eq!(
+        keep_newest(temp.path(), 5, Some(Duration::from_secs(60))),
+        vec![latest]
+    );
+}
+
+#[test]
+fn test_cli_keep_newest_with_mixed_backups() {
+    let temp = TempDir::new().unwrap();
+    let latest = backup(temp.path(), "file.txt.mutx.backup", 1_000_000);
+    let newer = backup(
+        temp.path(),
+        "file.txt.20260102_000000.mutx.backup",
+        1_000_000,
+    );
+    let older = backup(
+        temp.path(),
+        "file.txt.20260101_000000.mutx.backup",
+        1_000_000,
+    );
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["housekeep", "backups", "--keep-newest", "1"])
+        .arg(temp.path())
+        .assert()
+        .success();
+
+    assert!(latest.exists());
+    assert!(newer.exists());
+    assert!(!older.exists());
+}
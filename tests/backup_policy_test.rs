@@ -0,0 +1,104 @@
+use assert_cmd::Command;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// A backup directory that cannot be created, because a file is in the way
+fn blocked_backup_dir(temp: &TempDir) -> PathBuf {
+    let file = temp.path().join("not-a-dir");
+    fs::write(&file, "").unwrap();
+    file.join("backups")
+}
+
+fn write(output: &Path, backup_dir: &Path) -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
+    cmd.arg(output)
+        .arg("--backup")
+        .arg("--backup-dir")
+        .arg(backup_dir);
+    cmd
+}
+
+#[test]
+fn test_backup_failure_aborts_with_exit_code_4() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("app.conf");
+    fs::write(&output, "old").unwrap();
+    let backup_dir = blocked_backup_dir(&temp);
+
+    write(&output, &backup_dir)
+        .write_stdin("new")
+        .assert()
+        .code(4);
+    write(&output, &backup_dir)
+        .arg("--backup-required")
+        .write_stdin("new")
+        .assert()
+        .code(4);
+    assert_eq!(fs::read_to_string(&output).unwrap(), "old");
+}
+
+#[test]
+fn test_best_effort_writes_without_backup() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("app.conf");
+    fs::write(&output, "old").unwrap();
+    let backup_dir = blocked_backup_dir(&temp);
+
+    let out = write(&output, &backup_dir)
+        .args(["--backup-best-effort", "--json"])
+        .write_stdin("new")
+        .assert()
+        .success()
+        .stderr(predicates::str::contains("writing without a backup"))
+        .get_output()
+        .stdout
+        .clone();
+    assert_eq!(fs::read_to_string(&output).unwrap(), "new");
+
+    let report: Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(report["backup"]["status"], "failed");
+    assert!(report["backup"]["error"]
+        .as_str()
+        .unwrap()
+        .contains("backup"));
+}
+
+#[test]
+fn test_best_effort_still_backs_up_when_it_can() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("app.conf");
+    fs::write(&output, "old").unwrap();
+    let backup_dir = temp.path().join("backups");
+
+    write(&output, &backup_dir)
+        .arg("--backup-best-effort")
+        .write_stdin("new")
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(backup_dir.join("app.conf.mutx.backup")).unwrap(),
+        "old"
+    );
+}
+
+#[test]
+fn test_policies_conflict_and_need_backup() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("app.conf");
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--backup", "--backup-required", "--backup-best-effort"])
+        .write_stdin("new")
+        .assert()
+        .failure();
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .arg("--backup-best-effort")
+        .write_stdin("new")
+        .assert()
+        .failure();
+    assert!(!output.exists());
+}
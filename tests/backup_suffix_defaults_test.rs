@@ -0,0 +1,171 @@
+use assert_cmd::Command;
+use filetime::{set_file_mtime, FileTime};
+use mutx::DEFAULT_BACKUP_SUFFIX;
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn age(path: &Path) {
+    set_file_mtime(path, FileTime::from_unix_time(1_000_000, 0)).unwrap();
+}
+
+fn mutx(config: &Path) -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
+    cmd.env("MUTX_CONFIG", config);
+    cmd
+}
+
+#[test]
+fn test_default_write_backups_cleaned_by_default_housekeep() {
+    let temp = TempDir::new().unwrap();
+    let config = temp.path().join("missing-config.toml");
+    let data = temp.path().join("data");
+    fs::create_dir(&data).unwrap();
+    let output = data.join("file.txt");
+    fs::write(&output, "v1").unwrap();
+
+    mutx(&config)
+        .arg(&output)
+        .arg("--backup")
+        .write_stdin("v2")
+        .assert()
+        .success();
+
+    let backup = data.join(format!("file.txt{}", DEFAULT_BACKUP_SUFFIX));
+    assert_eq!(fs::read_to_string(&backup).unwrap(), "v1");
+    age(&backup);
+
+    mutx(&config)
+        .args(["housekeep", "backups", "--older-than", "1h"])
+        .arg(&data)
+        .assert()
+        .success();
+    assert!(!backup.exists());
+}
+
+#[test]
+fn test_config_file_overrides_suffix_everywhere() {
+    let temp = TempDir::new().unwrap();
+    let config = temp.path().join("config.toml");
+    fs::write(&config, "backup_suffix = \".bak\"\n").unwrap();
+    let data = temp.path().join("data");
+    fs::create_dir(&data).unwrap();
+    let output = data.join("file.txt");
+    fs::write(&output, "v1").unwrap();
+
+    mutx(&config)
+        .arg(&output)
+        .arg("--backup")
+        .write_stdin("v2")
+        .assert()
+        .success();
+
+    let backup = data.join("file.txt.bak");
+    assert_eq!(fs::read_to_string(&backup).unwrap(), "v1");
+
+    // Restore finds the configured suffix too
+    mutx(&config)
+        .args(["restore", "--no-backup-current"])
+        .arg(&output)
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&output).unwrap(), "v1");
+
+    age(&backup);
+    mutx(&config)
+        .args(["housekeep", "backups", "--older-than", "1h"])
+        .arg(&data)
+        .assert()
+        .success();
+    assert!(!backup.exists());
+}
+
+#[test]
+fn test_cli_suffix_beats_config() {
+    let temp = TempDir::new().unwrap();
+    let config = temp.path().join("config.toml");
+    fs::write(&config, "backup_suffix = \".bak\"\n").unwrap();
+    let output = temp.path().join("file.txt");
+    fs::write(&output, "v1").unwrap();
+
+    mutx(&config)
+        .arg(&output)
+        .args(["--backup", "--backup-suffix", ".orig"])
+        .write_stdin("v2")
+        .assert()
+        .success();
+
+    assert!(temp.path().join("file.txt.orig").exists());
+    assert!(!temp.path().join("file.txt.bak").exists());
+}
+
+#[test]
+fn test_invalid_config_reported() {
+    let temp = TempDir::new().unwrap();
+    let config = temp.path().join("config.toml");
+    fs::write(&config, "backup_suffix = [\n").unwrap();
+    let output = temp.path().join("file.txt");
+    fs::write(&output, "v1").unwrap();
+
+    mutx(&config)
+        .arg(&output)
+        .arg("--backup")
+        .write_stdin("v2")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid configuration file"));
+    assert_eq!(fs::read_to_string(&output).unwrap(), "v1");
+}
+
+#[test]
+fn test_default_housekeep_migrates_legacy_timestamped_backups() {
+    let temp = TempDir::new().unwrap();
+    let config = temp.path().join("missing-config.toml");
+    let data = temp.path().join("data");
+    fs::create_dir(&data).unwrap();
+
+    let legacy = data.join("file.txt.20240101_000000.backup");
+    let current = data.join("file.txt.20240102_000000.mutx.backup");
+    let user_copy = data.join("file.txt.backup");
+    for path in [&legacy, &current, &user_copy] {
+        fs::write(path, "backup").unwrap();
+    }
+
+    // Legacy and current backups of file.txt form one retention group
+    mutx(&config)
+        .args(["housekeep", "backups", "--keep-newest", "1"])
+        .arg(&data)
+        .assert()
+        .success();
+
+    assert!(!legacy.exists());
+    assert!(current.exists());
+    assert!(
+        user_copy.exists(),
+        "untimestamped .backup files are not ours"
+    );
+}
+
+#[test]
+fn test_custom_suffix_ignores_legacy_backups() {
+    let temp = TempDir::new().unwrap();
+    let config = temp.path().join("missing-config.toml");
+    let legacy = temp.path().join("file.txt.20240101_000000.backup");
+    fs::write(&legacy, "backup").unwrap();
+    age(&legacy);
+
+    mutx(&config)
+        .args([
+            "housekeep",
+            "backups",
+            "--older-than",
+            "1h",
+            "--suffix",
+            ".bak",
+        ])
+        .arg(temp.path())
+        .assert()
+        .success();
+    assert!(legacy.exists());
+}
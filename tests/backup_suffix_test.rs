@@ -10,9 +10,11 @@ fn test_custom_suffix_without_timestamp() {
 
     let config = BackupConfig {
         source: target.clone(),
-        suffix: ".bak".to_string(),
+        suffix: ".bak".parse().unwrap(),
         directory: None,
         timestamp: false,
+        prune_on_enospc: None,
+        delta: false,
     };
 
     let backup_path = create_backup(&config).unwrap();
@@ -36,9 +38,11 @@ fn test_custom_suffix_with_timestamp() {
 
     let config = BackupConfig {
         source: target.clone(),
-        suffix: ".bak".to_string(),
+        suffix: ".bak".parse().unwrap(),
         directory: None,
         timestamp: true,
+        prune_on_enospc: None,
+        delta: false,
     };
 
     let backup_path = create_backup(&config).unwrap();
@@ -61,9 +65,11 @@ fn test_default_suffix() {
 
     let config = BackupConfig {
         source: target.clone(),
-        suffix: ".mutx.backup".to_string(),
+        suffix: ".mutx.backup".parse().unwrap(),
         directory: None,
         timestamp: false,
+        prune_on_enospc: None,
+        delta: false,
     };
 
     let backup_path = create_backup(&config).unwrap();
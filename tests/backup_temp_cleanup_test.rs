@@ -0,0 +1,117 @@
+use assert_cmd::Command;
+use mutx::backup::{create_backup, BackupConfig};
+use mutx::housekeep::{clean_backups, AgeSource, CleanBackupConfig};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+#[cfg(unix)]
+use std::process::Command as StdCommand;
+
+/// PID of a process that has already exited
+#[cfg(unix)]
+fn dead_pid() -> u32 {
+    let mut child = StdCommand::new("true").spawn().unwrap();
+    let pid = child.id();
+    child.wait().unwrap();
+    pid
+}
+
+#[cfg(unix)]
+fn touch(dir: &Path, name: &str) -> PathBuf {
+    let path = dir.join(name);
+    fs::write(&path, "partial").unwrap();
+    path
+}
+
+fn config(dir: &Path, dry_run: bool) -> CleanBackupConfig {
+    CleanBackupConfig {
+        dir: dir.to_path_buf(),
+        recursive: false,
+        older_than: None,
+        keep_newest: None,
+        dry_run,
+        suffix: ".mutx.backup".parse().unwrap(),
+        age_source: AgeSource::Name,
+        max_total_size: None,
+    }
+}
+
+#[test]
+fn test_backup_dir_holds_only_the_backup() {
+    let temp = TempDir::new().unwrap();
+    let source = temp.path().join("data.txt");
+    let backup_dir = temp.path().join("backups");
+    fs::write(&source, "original").unwrap();
+
+    for _ in 0..2 {
+        create_backup(&BackupConfig {
+            source: source.clone(),
+            suffix: ".mutx.backup".parse().unwrap(),
+            directory: Some(backup_dir.clone()),
+            timestamp: false,
+            prune_on_enospc: None,
+            delta: false,
+        })
+        .unwrap();
+    }
+
+    let names: Vec<_> = fs::read_dir(&backup_dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect();
+    assert_eq!(names, vec!["data.txt.mutx.backup"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_housekeep_removes_stale_backup_temps() {
+    let temp = TempDir::new().unwrap();
+    let dir = temp.path();
+    let dead = dead_pid();
+
+    let stale = touch(
+        dir,
+        &format!(".data.txt.mutx.backup.{}-a1b2c3d4.mutx.tmp", dead),
+    );
+    let legacy = touch(dir, "data.txt.mutx.backup.mutx.tmp");
+    let running = touch(
+        dir,
+        &format!(
+            ".data.txt.mutx.backup.{}-a1b2c3d4.mutx.tmp",
+            std::process::id()
+        ),
+    );
+    // Write temps are left to `housekeep temps`
+    let write_temp = touch(dir, &format!(".data.txt.{}-a1b2c3d4.mutx.tmp", dead));
+
+    let mut listed = clean_backups(&config(dir, true)).unwrap();
+    listed.sort();
+    let mut expected = vec![stale.clone(), legacy.clone()];
+    expected.sort();
+    assert_eq!(listed, expected);
+    assert!(stale.exists());
+
+    clean_backups(&config(dir, false)).unwrap();
+    assert!(!stale.exists());
+    assert!(!legacy.exists());
+    assert!(running.exists());
+    assert!(write_temp.exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cli_housekeep_backups_cleans_temps() {
+    let temp = TempDir::new().unwrap();
+    let stale = touch(
+        temp.path(),
+        &format!(".data.txt.mutx.backup.{}-a1b2c3d4.mutx.tmp", dead_pid()),
+    );
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["housekeep", "backups"])
+        .arg(temp.path())
+        .assert()
+        .success();
+    assert!(!stale.exists());
+}
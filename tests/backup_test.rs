@@ -10,9 +10,11 @@ fn test_simple_backup_creation() {
 
     let config = BackupConfig {
         source: target.clone(),
-        suffix: ".mutx.backup".to_string(),
+        suffix: ".mutx.backup".parse().unwrap(),
         directory: None,
         timestamp: false,
+        prune_on_enospc: None,
+        delta: false,
     };
 
     create_backup(&config).unwrap();
@@ -33,9 +35,11 @@ fn test_backup_with_timestamp() {
 
     let config = BackupConfig {
         source: target.clone(),
-        suffix: ".mutx.backup".to_string(),
+        suffix: ".mutx.backup".parse().unwrap(),
         directory: None,
         timestamp: true,
+        prune_on_enospc: None,
+        delta: false,
     };
 
     let backup_path = create_backup(&config).unwrap();
@@ -67,9 +71,11 @@ fn test_backup_to_directory() {
 
     let config = BackupConfig {
         source: target.clone(),
-        suffix: ".mutx.backup".to_string(),
+        suffix: ".mutx.backup".parse().unwrap(),
         directory: Some(backup_dir.clone()),
         timestamp: false,
+        prune_on_enospc: None,
+        delta: false,
     };
 
     create_backup(&config).unwrap();
@@ -86,9 +92,11 @@ fn test_backup_nonexistent_file_fails() {
 
     let config = BackupConfig {
         source: target.clone(),
-        suffix: ".mutx.backup".to_string(),
+        suffix: ".mutx.backup".parse().unwrap(),
         directory: None,
         timestamp: false,
+        prune_on_enospc: None,
+        delta: false,
     };
 
     let result = create_backup(&config);
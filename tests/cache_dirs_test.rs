@@ -0,0 +1,76 @@
+use assert_cmd::Command;
+use mutx::dirs::{has_cachedir_tag, CACHEDIR_TAG_SIGNATURE, STATE_DIR_ENV};
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn mutx(temp: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("mutx").unwrap();
+    cmd.env("XDG_CACHE_HOME", temp.path().join("cache"))
+        .env("XDG_STATE_HOME", temp.path().join("state"))
+        .env_remove(STATE_DIR_ENV);
+    cmd
+}
+
+#[test]
+fn test_xdg_cache_home_relocates_lock_cache() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    let locks = temp.path().join("cache").join("mutx").join("locks");
+
+    mutx(&temp)
+        .args(["lock", "path"])
+        .arg(&output)
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(locks.to_str().unwrap()));
+
+    mutx(&temp)
+        .arg(&output)
+        .write_stdin("data")
+        .assert()
+        .success();
+    assert!(has_cachedir_tag(&locks));
+    let tag = fs::read_to_string(locks.join("CACHEDIR.TAG")).unwrap();
+    assert!(tag.starts_with(CACHEDIR_TAG_SIGNATURE));
+}
+
+#[test]
+fn test_relative_xdg_cache_home_is_ignored() {
+    let temp = TempDir::new().unwrap();
+
+    mutx(&temp)
+        .env("XDG_CACHE_HOME", "relative/cache")
+        .args(["lock", "path"])
+        .arg(temp.path().join("out.txt"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("relative/cache").not());
+}
+
+#[test]
+fn test_state_dir_follows_xdg_state_home_and_flag() {
+    let temp = TempDir::new().unwrap();
+    let xdg_state = temp.path().join("state").join("mutx");
+    let custom = temp.path().join("custom-state");
+
+    mutx(&temp)
+        .arg("doctor")
+        .arg(temp.path())
+        .assert()
+        .stdout(predicate::str::contains(format!(
+            "State directory: {}",
+            xdg_state.display()
+        )));
+
+    mutx(&temp)
+        .arg("--state-dir")
+        .arg(&custom)
+        .arg("doctor")
+        .arg(temp.path())
+        .assert()
+        .stdout(predicate::str::contains(format!(
+            "State directory: {}",
+            custom.display()
+        )));
+}
@@ -0,0 +1,134 @@
+use assert_cmd::Command;
+use filetime::{set_file_mtime, FileTime};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// A config file naming `central` as the backup directory, and a separate
+/// working directory
+fn setup() -> (TempDir, PathBuf, PathBuf, PathBuf) {
+    let temp = TempDir::new().unwrap();
+    let central = temp.path().join("backups");
+    let work = temp.path().join("work");
+    fs::create_dir(&central).unwrap();
+    fs::create_dir(&work).unwrap();
+    let config = temp.path().join("config.toml");
+    fs::write(&config, format!("backup_dir = '{}'\n", central.display())).unwrap();
+    (temp, config, central, work)
+}
+
+fn mutx(config: &Path, cwd: &Path) -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
+    cmd.env("MUTX_CONFIG", config).current_dir(cwd);
+    cmd
+}
+
+fn old_backup(dir: &Path) -> PathBuf {
+    let path = dir.join("data.txt.mutx.backup");
+    fs::write(&path, "old").unwrap();
+    set_file_mtime(&path, FileTime::from_unix_time(1_000_000, 0)).unwrap();
+    path
+}
+
+#[test]
+fn test_write_and_restore_use_central_dir() {
+    let (_temp, config, central, work) = setup();
+    let output = work.join("data.txt");
+    fs::write(&output, "v1").unwrap();
+
+    mutx(&config, &work)
+        .arg(&output)
+        .arg("--backup")
+        .write_stdin("v2")
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(central.join("data.txt.mutx.backup")).unwrap(),
+        "v1"
+    );
+    assert!(!work.join("data.txt.mutx.backup").exists());
+
+    mutx(&config, &work)
+        .args(["restore"])
+        .arg(&output)
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&output).unwrap(), "v1");
+}
+
+#[test]
+fn test_same_named_files_keep_separate_backups() {
+    let (_temp, config, central, work) = setup();
+    let outputs = [work.join("a/data.txt"), work.join("b/data.txt")];
+    for (output, old) in outputs.iter().zip(["a1", "b1"]) {
+        fs::create_dir(output.parent().unwrap()).unwrap();
+        fs::write(output, old).unwrap();
+        mutx(&config, &work)
+            .arg(output)
+            .args(["--backup", "--backup-timestamp"])
+            .write_stdin("new")
+            .assert()
+            .success();
+    }
+    assert_eq!(fs::read_dir(&central).unwrap().count(), 2);
+
+    for (output, old) in outputs.iter().zip(["a1", "b1"]) {
+        mutx(&config, &work)
+            .args(["restore"])
+            .arg(output)
+            .assert()
+            .success();
+        assert_eq!(fs::read_to_string(output).unwrap(), old);
+    }
+}
+
+#[test]
+fn test_housekeep_defaults_to_central_dir() {
+    let (_temp, config, central, work) = setup();
+    let in_central = old_backup(&central);
+    let in_work = old_backup(&work);
+
+    mutx(&config, &work)
+        .args(["housekeep", "backups", "--older-than", "1d"])
+        .assert()
+        .success();
+    assert!(!in_central.exists());
+    assert!(in_work.exists());
+}
+
+#[test]
+fn test_housekeep_everywhere() {
+    let (_temp, config, central, work) = setup();
+    let in_central = old_backup(&central);
+    let in_work = old_backup(&work);
+
+    mutx(&config, &work)
+        .args(["housekeep", "backups", "--everywhere", "--older-than", "1d"])
+        .assert()
+        .success();
+    assert!(!in_central.exists());
+    assert!(!in_work.exists());
+}
+
+#[test]
+fn test_housekeep_falls_back_to_cwd_without_central_dir() {
+    let (_temp, config, central, work) = setup();
+    fs::remove_dir(&central).unwrap();
+    let in_work = old_backup(&work);
+
+    mutx(&config, &work)
+        .args(["housekeep", "backups", "--older-than", "1d"])
+        .assert()
+        .success();
+    assert!(!in_work.exists());
+}
+
+#[test]
+fn test_everywhere_conflicts_with_dir() {
+    let (_temp, config, _central, work) = setup();
+
+    mutx(&config, &work)
+        .args(["housekeep", "backups", "--everywhere", "."])
+        .assert()
+        .failure();
+}
@@ -0,0 +1,150 @@
+use assert_cmd::Command;
+use mutx::schema::{CheckReport, CheckStatus};
+use mutx::{FileLock, LockStrategy};
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn mutx(temp: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("mutx").unwrap();
+    cmd.env("XDG_CACHE_HOME", temp.path().join("cache"))
+        .env("MUTX_CONFIG", temp.path().join("config.toml"))
+        .env_remove("MUTX_LOCK_DIR");
+    cmd
+}
+
+/// `mutx check --json` for `file`, and whether it exited successfully
+fn check(temp: &TempDir, file: &Path, extra: &[&str]) -> (CheckReport, bool) {
+    let output = mutx(temp)
+        .arg("check")
+        .arg("--json")
+        .args(extra)
+        .arg(file)
+        .output()
+        .unwrap();
+    let report = serde_json::from_slice(&output.stdout).unwrap();
+    (report, output.status.success())
+}
+
+fn status(report: &CheckReport, name: &str) -> CheckStatus {
+    report
+        .checks
+        .iter()
+        .find(|check| check.name == name)
+        .unwrap_or_else(|| panic!("no {} check in {:?}", name, report))
+        .status
+}
+
+#[test]
+fn test_check_passes_and_creates_nothing() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("app.conf");
+    fs::write(&file, "old").unwrap();
+
+    let (report, success) = check(&temp, &file, &["--backup"]);
+    assert!(success);
+    assert!(report.passed);
+    for name in ["target", "directory", "lock_path", "lock_status", "backup"] {
+        assert_eq!(status(&report, name), CheckStatus::Pass, "{}", name);
+    }
+    assert_eq!(status(&report, "input"), CheckStatus::Skip);
+
+    assert_eq!(fs::read_to_string(&file).unwrap(), "old");
+    assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn test_missing_directory_fails() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("missing").join("app.conf");
+
+    let (report, success) = check(&temp, &file, &[]);
+    assert!(!success);
+    assert!(!report.passed);
+    assert_eq!(status(&report, "directory"), CheckStatus::Fail);
+}
+
+#[test]
+fn test_other_checks_run_after_a_failure() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("app.conf");
+    let input = temp.path().join("input");
+    fs::create_dir(&input).unwrap();
+
+    let input = input.to_str().unwrap();
+    let (report, success) = check(&temp, &file, &["--input", input]);
+    assert!(!success);
+    assert_eq!(status(&report, "input"), CheckStatus::Fail);
+    assert_eq!(status(&report, "lock_path"), CheckStatus::Pass);
+    assert_eq!(status(&report, "guarantees"), CheckStatus::Pass);
+}
+
+#[test]
+fn test_read_only_target_warns_unless_respected() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("app.conf");
+    fs::write(&file, "old").unwrap();
+    let mut permissions = fs::metadata(&file).unwrap().permissions();
+    permissions.set_readonly(true);
+    fs::set_permissions(&file, permissions).unwrap();
+
+    let (report, success) = check(&temp, &file, &[]);
+    assert!(success);
+    assert_eq!(status(&report, "read_only"), CheckStatus::Warn);
+
+    let (report, success) = check(&temp, &file, &["--respect-readonly"]);
+    assert!(!success);
+    assert_eq!(status(&report, "read_only"), CheckStatus::Fail);
+}
+
+#[test]
+fn test_held_lock_warns() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("app.conf");
+    let _held =
+        FileLock::acquire(&temp.path().join("app.conf.lock"), LockStrategy::NoWait).unwrap();
+
+    let (report, success) = check(&temp, &file, &["--lock-beside"]);
+    assert!(success);
+    assert_eq!(status(&report, "lock_status"), CheckStatus::Warn);
+}
+
+#[test]
+fn test_input_over_max_size_fails() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("app.conf");
+    let input = temp.path().join("input");
+    fs::write(&input, "0123456789").unwrap();
+
+    let input = input.to_str().unwrap();
+    let (report, success) = check(&temp, &file, &["--input", input, "--max-size", "4"]);
+    assert!(!success);
+    assert_eq!(status(&report, "disk_space"), CheckStatus::Fail);
+}
+
+#[test]
+fn test_unmet_guarantee_fails() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("app.conf");
+
+    let (report, success) = check(&temp, &file, &["--no-lock", "--require", "exclusive"]);
+    assert!(!success);
+    assert_eq!(status(&report, "lock_path"), CheckStatus::Warn);
+    assert_eq!(status(&report, "guarantees"), CheckStatus::Fail);
+}
+
+#[test]
+fn test_text_report() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("missing").join("app.conf");
+
+    mutx(&temp)
+        .arg("check")
+        .arg(&file)
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("[  ok] target:"))
+        .stdout(predicate::str::contains("[FAIL] directory:"))
+        .stderr(predicate::str::contains("checks failed for"));
+}
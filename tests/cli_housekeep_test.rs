@@ -53,6 +53,11 @@ fn test_housekeep_clean_backups() {
         "backup2",
     )
     .unwrap();
+    fs::write(
+        dir.path().join("file.txt.20260124_120000.mutx.backup"),
+        "backup3",
+    )
+    .unwrap();
 
     let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
     cmd.arg("housekeep")
@@ -63,14 +68,19 @@ fn test_housekeep_clean_backups() {
         .assert()
         .success();
 
-    // Should keep one backup (the newest one)
+    // Should keep the newest timestamped backup plus the untimestamped
+    // latest slot, which keep-newest does not count
     let backups: Vec<_> = fs::read_dir(dir.path())
         .unwrap()
         .filter_map(|e| e.ok())
         .filter(|e| e.path().to_str().unwrap().contains(".mutx.backup"))
         .collect();
 
-    assert_eq!(backups.len(), 1);
+    assert_eq!(backups.len(), 2);
+    assert!(!dir
+        .path()
+        .join("file.txt.20260124_120000.mutx.backup")
+        .exists());
 }
 
 #[test]
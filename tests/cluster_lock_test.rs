@@ -0,0 +1,168 @@
+#![cfg(feature = "cluster")]
+
+use assert_cmd::Command;
+use mutx::lock::cluster::lockd::{LockServer, LockdClient};
+use mutx::lock::cluster::LockService;
+use mutx::{FileLock, LockBackend, LockStrategy, MutxError};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use tempfile::TempDir;
+
+fn start_server() -> String {
+    let server = LockServer::bind("127.0.0.1:0").unwrap();
+    let addr = server.local_addr().unwrap().to_string();
+    std::thread::spawn(move || server.serve());
+    addr
+}
+
+#[test]
+fn test_remote_lock_excludes_second_client() {
+    let addr = start_server();
+    let client = LockdClient::new(addr.as_str());
+
+    let held = FileLock::acquire_remote(&client, "jobs/report", LockStrategy::NoWait).unwrap();
+    assert_eq!(held.backend(), LockBackend::Remote);
+
+    let result = FileLock::acquire_remote(&client, "jobs/report", LockStrategy::NoWait);
+    assert!(matches!(result, Err(MutxError::LockWouldBlock { .. })));
+
+    // Other keys are independent
+    assert!(FileLock::acquire_remote(&client, "jobs/other", LockStrategy::NoWait).is_ok());
+}
+
+#[test]
+fn test_remote_lock_released_on_drop() {
+    let addr = start_server();
+    let client = LockdClient::new(addr.as_str());
+
+    let held = FileLock::acquire_remote(&client, "key", LockStrategy::NoWait).unwrap();
+    drop(held);
+
+    assert!(FileLock::acquire_remote(&client, "key", LockStrategy::NoWait).is_ok());
+}
+
+#[test]
+fn test_remote_tokens_increase() {
+    let addr = start_server();
+    let client = LockdClient::new(addr.as_str());
+
+    let first = client.try_acquire("key").unwrap().unwrap().token();
+    let second = client.try_acquire("key").unwrap().unwrap().token();
+    assert!(second > first);
+}
+
+#[test]
+fn test_remote_backend_requires_service() {
+    let temp = TempDir::new().unwrap();
+    let lock = temp.path().join("x.lock");
+
+    let result = FileLock::acquire_with_backend(&lock, LockStrategy::NoWait, LockBackend::Remote);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_cli_write_with_remote_backend() {
+    let addr = start_server();
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("output.txt");
+
+    let client = LockdClient::new(addr.as_str());
+    let held = FileLock::acquire_remote(&client, "shared-key", LockStrategy::NoWait).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--lock-backend", "remote", "--lock-server", &addr])
+        .args(["--lock-key", "shared-key", "--no-wait"])
+        .write_stdin("blocked")
+        .assert()
+        .failure();
+    assert!(!output.exists());
+
+    drop(held);
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--lock-backend", "remote", "--lock-server", &addr])
+        .args(["--lock-key", "shared-key", "--no-wait"])
+        .write_stdin("data")
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&output).unwrap(), "data");
+}
+
+#[test]
+fn test_waiting_client_is_handed_released_key() {
+    let addr = start_server();
+    let client = LockdClient::new(addr.as_str());
+    let held = FileLock::acquire_remote(&client, "key", LockStrategy::NoWait).unwrap();
+
+    let waiter = std::thread::spawn(move || {
+        FileLock::acquire_remote(&client, "key", LockStrategy::Wait).map(|lock| lock.stats())
+    });
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    drop(held);
+
+    // Granted by the server, not found by polling
+    assert_eq!(waiter.join().unwrap().unwrap().retries, 0);
+}
+
+/// A server from before `WAIT`: it knows only `ACQUIRE`, and finds the key
+/// busy `busy_for` times before granting it
+fn start_server_without_wait(busy_for: usize) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut writer = stream.try_clone().unwrap();
+        let mut busy = busy_for;
+        for line in BufReader::new(stream).lines() {
+            let line = line.unwrap();
+            let reply = if line.starts_with("ACQUIRE ") && busy > 0 {
+                busy -= 1;
+                "BUSY".to_string()
+            } else if line.starts_with("ACQUIRE ") {
+                "OK 7".to_string()
+            } else if line == "RELEASE" {
+                "OK".to_string()
+            } else {
+                format!("ERR unknown request: {}", line)
+            };
+            writeln!(writer, "{}", reply).unwrap();
+        }
+    });
+    addr
+}
+
+#[test]
+fn test_waiting_on_older_server_polls() {
+    let client = LockdClient::new(start_server_without_wait(2));
+    let lease = client
+        .acquire_waiting("key", Some(std::time::Duration::from_secs(10)))
+        .unwrap()
+        .unwrap();
+    assert_eq!(lease.token(), 7);
+}
+
+#[test]
+fn test_waiting_on_older_server_times_out() {
+    let client = LockdClient::new(start_server_without_wait(usize::MAX));
+    let started = std::time::Instant::now();
+    let lease = client
+        .acquire_waiting("key", Some(std::time::Duration::from_millis(300)))
+        .unwrap();
+    assert!(lease.is_none());
+    assert!(started.elapsed() >= std::time::Duration::from_millis(300));
+}
+
+#[test]
+fn test_remote_deadline_times_out() {
+    let addr = start_server();
+    let client = LockdClient::new(addr.as_str());
+    let _held = FileLock::acquire_remote(&client, "jobs/report", LockStrategy::NoWait).unwrap();
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_millis(150);
+    let result = FileLock::acquire_remote(&client, "jobs/report", LockStrategy::Deadline(deadline));
+    assert!(matches!(result, Err(MutxError::LockTimeout { .. })));
+    assert!(std::time::Instant::now() >= deadline);
+}
@@ -0,0 +1,103 @@
+#![cfg(unix)]
+
+use assert_cmd::Command;
+use mutx::{AtomicWriter, SharedGroup, WriteMode};
+use std::fs;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::Path;
+use tempfile::TempDir;
+
+/// A group the test process may give files to: another group when running
+/// as root, otherwise the group new files already get
+fn usable_gid(dir: &Path) -> u32 {
+    let probe = dir.join("probe");
+    fs::write(&probe, "").unwrap();
+    let metadata = fs::metadata(&probe).unwrap();
+    fs::remove_file(&probe).unwrap();
+    if metadata.uid() == 0 {
+        metadata.gid() + 4242
+    } else {
+        metadata.gid()
+    }
+}
+
+fn assert_shared(path: &Path, gid: u32) {
+    let metadata = fs::metadata(path).unwrap();
+    assert_eq!(metadata.gid(), gid, "{}", path.display());
+    assert_eq!(metadata.mode() & 0o060, 0o060, "{}", path.display());
+}
+
+#[test]
+fn test_writer_shares_committed_file() {
+    let temp = TempDir::new().unwrap();
+    let gid = usable_gid(temp.path());
+    let target = temp.path().join("shared.txt");
+
+    let mut writer = AtomicWriter::new(&target, WriteMode::InMemory)
+        .unwrap()
+        .with_shared_group(SharedGroup::from_gid(gid));
+    writer.write_all(b"data").unwrap();
+    let report = writer.commit().unwrap();
+
+    assert_shared(&target, gid);
+    assert_eq!(report.mode & 0o060, 0o060);
+}
+
+#[test]
+fn test_cli_shares_output_lock_and_backups() {
+    let temp = TempDir::new().unwrap();
+    let gid = usable_gid(temp.path());
+    let output = temp.path().join("team.conf");
+    let lock = temp.path().join("team.conf.lock");
+    let backups = temp.path().join("backups");
+    fs::write(&output, "old").unwrap();
+    fs::set_permissions(&output, fs::Permissions::from_mode(0o600)).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .arg("--collaborative")
+        .arg(gid.to_string())
+        .arg("--lock-file")
+        .arg(&lock)
+        .arg("--backup")
+        .arg("--backup-dir")
+        .arg(&backups)
+        .write_stdin("new")
+        .assert()
+        .success();
+
+    assert_shared(&output, gid);
+    assert_shared(&lock, gid);
+    assert_shared(&backups.join("team.conf.mutx.backup"), gid);
+
+    let dir = fs::metadata(&backups).unwrap();
+    assert_eq!(dir.gid(), gid);
+    assert_eq!(dir.mode() & 0o2070, 0o2070);
+}
+
+#[test]
+fn test_cli_rejects_unknown_group() {
+    let temp = TempDir::new().unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(temp.path().join("out.txt"))
+        .args(["--collaborative", "no-such-group-mutx"])
+        .write_stdin("data")
+        .assert()
+        .failure();
+    assert!(!temp.path().join("out.txt").exists());
+}
+
+#[test]
+fn test_share_refuses_symlink() {
+    let temp = TempDir::new().unwrap();
+    let gid = usable_gid(temp.path());
+    let secret = temp.path().join("secret");
+    let link = temp.path().join("link");
+    fs::write(&secret, "").unwrap();
+    fs::set_permissions(&secret, fs::Permissions::from_mode(0o600)).unwrap();
+    std::os::unix::fs::symlink(&secret, &link).unwrap();
+
+    assert!(SharedGroup::from_gid(gid).share_file(&link).is_err());
+    assert_eq!(fs::metadata(&secret).unwrap().mode() & 0o777, 0o600);
+}
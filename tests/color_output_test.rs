@@ -0,0 +1,85 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+const ESCAPE: &str = "\x1b[";
+
+fn mutx(temp: &TempDir) -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
+    cmd.env("XDG_CACHE_HOME", temp.path().join("cache"))
+        .env("MUTX_CONFIG", temp.path().join("config.toml"))
+        .env_remove("NO_COLOR");
+    cmd
+}
+
+fn dry_run(temp: &TempDir, color: &[&str]) -> assert_cmd::assert::Assert {
+    let dir = temp.path().join("backups");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("file.txt.mutx.backup"), "old").unwrap();
+    mutx(temp)
+        .args(color)
+        .args(["housekeep", "backups", "--dry-run", "--older-than", "0s"])
+        .arg(&dir)
+        .assert()
+        .success()
+}
+
+#[test]
+fn test_piped_output_is_plain() {
+    let temp = TempDir::new().unwrap();
+    dry_run(&temp, &[])
+        .stdout(predicate::str::contains("Would clean 1 backup file(s)"))
+        .stdout(predicate::str::contains(ESCAPE).not());
+
+    // Log warnings and errors too
+    mutx(&temp)
+        .args(["write", "--no-lock"])
+        .arg(temp.path().join("out.txt"))
+        .write_stdin("data")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("WARN"))
+        .stderr(predicate::str::contains(ESCAPE).not());
+    mutx(&temp)
+        .arg("write")
+        .arg(temp.path().join("missing").join("out.txt"))
+        .write_stdin("data")
+        .assert()
+        .failure()
+        .stderr(predicate::str::starts_with("Error: "));
+}
+
+#[test]
+fn test_color_always_paints_pipes() {
+    let temp = TempDir::new().unwrap();
+    dry_run(&temp, &["--color", "always"]).stdout(predicate::str::contains(
+        "\x1b[33mWould clean\x1b[0m 1 backup file(s)",
+    ));
+
+    mutx(&temp)
+        .args(["--color", "always", "write"])
+        .arg(temp.path().join("missing").join("out.txt"))
+        .write_stdin("data")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("\x1b[1;31mError:\x1b[0m "));
+}
+
+#[test]
+fn test_color_never_overrides_terminal_detection() {
+    let temp = TempDir::new().unwrap();
+    dry_run(&temp, &["--color=never"]).stdout(predicate::str::contains(ESCAPE).not());
+}
+
+#[test]
+fn test_unknown_color_choice_is_rejected() {
+    let temp = TempDir::new().unwrap();
+    mutx(&temp)
+        .args(["--color", "sometimes", "doctor"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "expected one of auto, always, never",
+        ));
+}
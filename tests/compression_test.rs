@@ -0,0 +1,95 @@
+#![cfg(feature = "compression")]
+
+use assert_cmd::Command;
+use std::fs;
+use std::io::{Read, Write};
+use tempfile::TempDir;
+
+fn gunzip(data: &[u8]) -> String {
+    let mut out = String::new();
+    flate2::read::GzDecoder::new(data)
+        .read_to_string(&mut out)
+        .unwrap();
+    out
+}
+
+#[test]
+fn test_cli_compress_gzip() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("artifact.txt.gz");
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--compress", "gzip:9"])
+        .write_stdin("artifact contents")
+        .assert()
+        .success();
+
+    assert_eq!(gunzip(&fs::read(&output).unwrap()), "artifact contents");
+}
+
+#[test]
+fn test_cli_compress_zstd_after_transforms() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("artifact.txt.zst");
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--transform", "trailing-newline", "--compress", "zstd"])
+        .write_stdin("line")
+        .assert()
+        .success();
+
+    let decoded = zstd::stream::decode_all(&fs::read(&output).unwrap()[..]).unwrap();
+    assert_eq!(decoded, b"line\n");
+}
+
+#[test]
+fn test_cli_decompress_input() {
+    let temp = TempDir::new().unwrap();
+    let input = temp.path().join("input.gz");
+    let output = temp.path().join("output.txt");
+
+    let mut encoder =
+        flate2::write::GzEncoder::new(fs::File::create(&input).unwrap(), Default::default());
+    encoder.write_all(b"unpacked").unwrap();
+    encoder.finish().unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .arg("--input")
+        .arg(&input)
+        .args(["--decompress", "gzip"])
+        .assert()
+        .success();
+
+    assert_eq!(fs::read_to_string(&output).unwrap(), "unpacked");
+}
+
+#[test]
+fn test_cli_corrupt_compressed_input_keeps_target() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("output.txt");
+    fs::write(&output, "original").unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--decompress", "zstd"])
+        .write_stdin("definitely not zstd")
+        .assert()
+        .failure();
+
+    assert_eq!(fs::read_to_string(&output).unwrap(), "original");
+}
+
+#[test]
+fn test_cli_rejects_bad_level() {
+    let temp = TempDir::new().unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(temp.path().join("out.gz"))
+        .args(["--compress", "gzip:12"])
+        .write_stdin("x")
+        .assert()
+        .failure();
+}
@@ -0,0 +1,130 @@
+use mutx::lock::Sidecar;
+use mutx::{
+    AtomicWriter, CriticalSection, FileLock, LockBackend, LockStrategy, MutxError, WriteMode,
+};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+fn hold_dotlock(output: &Path) -> FileLock {
+    FileLock::acquire_with_backend(
+        &output.with_file_name("out.txt.lock"),
+        LockStrategy::NoWait,
+        LockBackend::Dotlock,
+    )
+    .unwrap()
+}
+
+fn spawn_write(output: &Path, section: &str, extra: &[&str]) -> Child {
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(output)
+        .args(["--lock-backend", "dotlock", "--critical-section", section])
+        .args(extra)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap()
+}
+
+fn wait_for(mut condition: impl FnMut() -> bool) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        if condition() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    false
+}
+
+#[test]
+fn test_parse_critical_section() {
+    for section in [
+        CriticalSection::Full,
+        CriticalSection::BackupAndCommit,
+        CriticalSection::CommitOnly,
+    ] {
+        assert_eq!(
+            section.to_string().parse::<CriticalSection>().unwrap(),
+            section
+        );
+    }
+    assert!("late".parse::<CriticalSection>().is_err());
+    assert_eq!(CriticalSection::default(), CriticalSection::Full);
+}
+
+#[test]
+fn test_writer_locks_as_section_says() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    let sidecar = temp.path().join("out.txt.lock");
+
+    let mut writer = AtomicWriter::new(&output, WriteMode::InMemory)
+        .unwrap()
+        .with_lock(Sidecar, LockStrategy::NoWait, CriticalSection::CommitOnly);
+    writer.write_all(b"late").unwrap();
+    // Staging took no lock
+    drop(FileLock::acquire(&sidecar, LockStrategy::NoWait).unwrap());
+    writer.commit().unwrap();
+    assert_eq!(fs::read_to_string(&output).unwrap(), "late");
+
+    let mut writer = AtomicWriter::new(&output, WriteMode::InMemory)
+        .unwrap()
+        .with_lock(Sidecar, LockStrategy::NoWait, CriticalSection::Full);
+    writer.write_all(b"early").unwrap();
+    let err = FileLock::acquire(&sidecar, LockStrategy::NoWait).unwrap_err();
+    assert!(matches!(err, MutxError::LockWouldBlock { .. }));
+    writer.commit().unwrap();
+    drop(FileLock::acquire(&sidecar, LockStrategy::NoWait).unwrap());
+}
+
+#[test]
+fn test_commit_only_stages_input_while_lock_is_held() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    let held = hold_dotlock(&output);
+
+    let mut child = spawn_write(&output, "commit-only", &[]);
+    let mut stdin = child.stdin.take().unwrap();
+    // More than a pipe buffer: only completes if mutx reads while waiting
+    let data = vec![b'x'; 1 << 20];
+    let feeder = thread::spawn(move || stdin.write_all(&data).is_ok());
+    assert!(wait_for(|| feeder.is_finished()));
+    assert!(feeder.join().unwrap());
+
+    drop(held);
+    assert!(child.wait().unwrap().success());
+    assert_eq!(fs::metadata(&output).unwrap().len(), 1 << 20);
+}
+
+#[test]
+fn test_backup_timing_follows_section() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    let backup = temp.path().join("out.txt.mutx.backup");
+
+    for (section, backed_up_early) in [("commit-only", true), ("backup-and-commit", false)] {
+        fs::write(&output, "old").unwrap();
+        let _ = fs::remove_file(&backup);
+        let held = hold_dotlock(&output);
+
+        let mut child = spawn_write(&output, section, &["--backup"]);
+        child.stdin.take().unwrap().write_all(b"new").unwrap();
+        if backed_up_early {
+            assert!(wait_for(|| backup.exists()), "{}", section);
+        } else {
+            thread::sleep(Duration::from_millis(300));
+            assert!(!backup.exists(), "{}", section);
+        }
+
+        drop(held);
+        assert!(child.wait().unwrap().success());
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "old");
+        assert_eq!(fs::read_to_string(&output).unwrap(), "new");
+    }
+}
@@ -0,0 +1,122 @@
+use assert_cmd::Command;
+use mutx::{derive_lock_path, validate_custom_lock_path, MutxError};
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_custom_lock_path_directory_rejected() {
+    let temp = TempDir::new().unwrap();
+
+    let result = derive_lock_path(temp.path(), true);
+    assert!(matches!(result, Err(MutxError::InvalidLockPath { .. })));
+}
+
+#[test]
+fn test_custom_lock_path_inside_output_rejected() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("output.txt");
+    let lock = output.join("inner.lock");
+
+    let result = validate_custom_lock_path(&lock, &output, None);
+    assert!(matches!(result, Err(MutxError::InvalidLockPath { .. })));
+}
+
+#[test]
+fn test_custom_lock_path_without_extension_allowed_without_root() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("output.txt");
+    let lock = temp.path().join("output.mutex");
+
+    assert!(validate_custom_lock_path(&lock, &output, None).is_ok());
+}
+
+#[test]
+fn test_lock_root_requires_lock_extension() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("output.txt");
+    let lock = temp.path().join("output.mutex");
+
+    let result = validate_custom_lock_path(&lock, &output, Some(temp.path()));
+    assert!(matches!(result, Err(MutxError::InvalidLockPath { .. })));
+}
+
+#[test]
+fn test_lock_root_containment() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("locks");
+    fs::create_dir(&root).unwrap();
+    let output = temp.path().join("output.txt");
+
+    let inside = root.join("output.lock");
+    assert!(validate_custom_lock_path(&inside, &output, Some(&root)).is_ok());
+
+    let outside = temp.path().join("output.lock");
+    let result = validate_custom_lock_path(&outside, &output, Some(&root));
+    assert!(matches!(result, Err(MutxError::LockPathOutsideRoot { .. })));
+
+    let traversal = root.join("..").join("escape.lock");
+    let result = validate_custom_lock_path(&traversal, &output, Some(&root));
+    assert!(matches!(result, Err(MutxError::LockPathOutsideRoot { .. })));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_lock_root_rejects_symlinked_escape() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("locks");
+    let elsewhere = temp.path().join("elsewhere");
+    fs::create_dir(&root).unwrap();
+    fs::create_dir(&elsewhere).unwrap();
+    std::os::unix::fs::symlink(&elsewhere, root.join("link")).unwrap();
+
+    let output = temp.path().join("output.txt");
+    let lock = root.join("link").join("output.lock");
+
+    let result = validate_custom_lock_path(&lock, &output, Some(&root));
+    assert!(matches!(result, Err(MutxError::LockPathOutsideRoot { .. })));
+}
+
+#[test]
+fn test_cli_lock_root_rejects_outside_lock() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("locks");
+    fs::create_dir(&root).unwrap();
+    let output = temp.path().join("output.txt");
+    let lock = temp.path().join("output.lock");
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
+    cmd.arg(&output)
+        .arg("--lock-file")
+        .arg(&lock)
+        .arg("--lock-root")
+        .arg(&root)
+        .write_stdin("data")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("escapes the allowed lock root"));
+
+    assert!(!output.exists());
+}
+
+#[test]
+fn test_cli_lock_root_accepts_contained_lock() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("locks");
+    fs::create_dir(&root).unwrap();
+    let output = temp.path().join("output.txt");
+    let lock = root.join("output.lock");
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
+    cmd.arg("write")
+        .arg(&output)
+        .arg("--lock-file")
+        .arg(&lock)
+        .arg("--lock-root")
+        .arg(&root)
+        .write_stdin("data")
+        .assert()
+        .success();
+
+    assert_eq!(fs::read_to_string(&output).unwrap(), "data");
+}
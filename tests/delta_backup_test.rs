@@ -0,0 +1,243 @@
+use assert_cmd::Command;
+use filetime::{set_file_mtime, FileTime};
+use mutx::backup::{create_backup, BackupConfig};
+use mutx::delta::{is_delta, reconstruct};
+use mutx::{restore_backup, RestoreConfig};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// Incompressible-looking content, so deltas are not mistaken for luck
+fn content(len: usize, seed: u64) -> Vec<u8> {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 56) as u8
+        })
+        .collect()
+}
+
+/// `data` with a few bytes changed, some inserted and some removed
+fn edit(data: &[u8], at: usize) -> Vec<u8> {
+    let mut edited = data.to_vec();
+    edited[at] ^= 0xff;
+    edited.splice(at * 2..at * 2, b"inserted".iter().copied());
+    edited.drain(at * 3..at * 3 + 100);
+    edited
+}
+
+fn delta_config(source: &Path) -> BackupConfig {
+    BackupConfig {
+        source: source.to_path_buf(),
+        suffix: ".mutx.backup".parse().unwrap(),
+        directory: None,
+        timestamp: true,
+        prune_on_enospc: None,
+        delta: true,
+    }
+}
+
+/// A full backup of `data` from long ago, as an earlier `--backup-timestamp` run leaves
+fn old_backup(dir: &Path, data: &[u8]) -> PathBuf {
+    let path = dir.join("state.bin.20240101_000000.mutx.backup");
+    fs::write(&path, data).unwrap();
+    set_file_mtime(&path, FileTime::from_unix_time(1_704_067_200, 0)).unwrap();
+    path
+}
+
+fn rebuilt(path: &Path) -> Vec<u8> {
+    let mut out = Vec::new();
+    reconstruct(path, |buf| {
+        out.extend_from_slice(buf);
+        Ok(())
+    })
+    .unwrap();
+    out
+}
+
+#[test]
+fn test_previous_backup_stored_as_delta() {
+    let temp = TempDir::new().unwrap();
+    let v1 = content(300_000, 1);
+    let v2 = edit(&v1, 50_000);
+    let previous = old_backup(temp.path(), &v1);
+    let source = temp.path().join("state.bin");
+    fs::write(&source, &v2).unwrap();
+
+    let newest = create_backup(&delta_config(&source)).unwrap();
+
+    assert!(!is_delta(&newest).unwrap());
+    assert_eq!(fs::read(&newest).unwrap(), v2);
+    assert!(is_delta(&previous).unwrap());
+    assert!(fs::metadata(&previous).unwrap().len() < 10_000);
+    // Its mtime is kept, so it is still not the newest backup
+    assert_eq!(
+        FileTime::from_last_modification_time(&fs::metadata(&previous).unwrap()).unix_seconds(),
+        1_704_067_200
+    );
+    assert_eq!(rebuilt(&previous), v1);
+}
+
+#[test]
+fn test_restore_rebuilds_every_version() {
+    let temp = TempDir::new().unwrap();
+    let v1 = content(200_000, 2);
+    let v2 = edit(&v1, 10_000);
+    let v3 = edit(&v2, 60_000);
+    let first = old_backup(temp.path(), &v1);
+    let source = temp.path().join("state.bin");
+
+    fs::write(&source, &v2).unwrap();
+    let second = create_backup(&delta_config(&source)).unwrap();
+    // Backup names have one-second resolution
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    fs::write(&source, &v3).unwrap();
+    let third = create_backup(&delta_config(&source)).unwrap();
+    assert!(is_delta(&first).unwrap() && is_delta(&second).unwrap());
+    assert!(!is_delta(&third).unwrap());
+
+    fs::write(&source, b"current").unwrap();
+    for (backup, expected) in [(&first, &v1), (&second, &v2), (&third, &v3)] {
+        restore_backup(&RestoreConfig {
+            target: source.clone(),
+            backup: backup.clone(),
+            backup_current: false,
+            suffix: ".mutx.backup".parse().unwrap(),
+            directory: None,
+            timestamp: true,
+        })
+        .unwrap();
+        assert_eq!(&fs::read(&source).unwrap(), expected);
+    }
+    // No rebuilt intermediate versions are left behind
+    assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 4);
+}
+
+#[test]
+fn test_changed_base_fails_restore() {
+    let temp = TempDir::new().unwrap();
+    let v1 = content(100_000, 3);
+    let previous = old_backup(temp.path(), &v1);
+    let source = temp.path().join("state.bin");
+    fs::write(&source, edit(&v1, 20_000)).unwrap();
+    let newest = create_backup(&delta_config(&source)).unwrap();
+
+    fs::write(&newest, content(100_000, 4)).unwrap();
+    fs::write(&source, b"current").unwrap();
+    let result = restore_backup(&RestoreConfig {
+        target: source.clone(),
+        backup: previous,
+        backup_current: false,
+        suffix: ".mutx.backup".parse().unwrap(),
+        directory: None,
+        timestamp: true,
+    });
+    assert!(result.unwrap_err().to_string().contains("has changed"));
+    assert_eq!(fs::read(&source).unwrap(), b"current");
+}
+
+#[test]
+fn test_unrelated_backup_stays_full() {
+    let temp = TempDir::new().unwrap();
+    let v1 = content(50_000, 5);
+    let previous = old_backup(temp.path(), &v1);
+    let source = temp.path().join("state.bin");
+    fs::write(&source, content(50_000, 6)).unwrap();
+
+    create_backup(&delta_config(&source)).unwrap();
+    assert!(!is_delta(&previous).unwrap());
+    assert_eq!(fs::read(&previous).unwrap(), v1);
+}
+
+#[test]
+fn test_cli_backup_delta_and_restore() {
+    let temp = TempDir::new().unwrap();
+    let v1 = content(100_000, 7);
+    let v2 = edit(&v1, 30_000);
+    let previous = old_backup(temp.path(), &v1);
+    let output = temp.path().join("state.bin");
+    fs::write(&output, &v2).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args([
+            "--lock-beside",
+            "--backup",
+            "--backup-timestamp",
+            "--backup-delta",
+        ])
+        .write_stdin("v3")
+        .assert()
+        .success();
+    assert!(is_delta(&previous).unwrap());
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["restore", "--lock-beside", "--no-backup-current", "--from"])
+        .arg(&previous)
+        .arg(&output)
+        .assert()
+        .success();
+    assert_eq!(fs::read(&output).unwrap(), v1);
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--backup", "--backup-delta"])
+        .write_stdin("v4")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_cli_backup_delta_with_relative_output() {
+    let temp = TempDir::new().unwrap();
+    let v1 = content(100_000, 8);
+    let v2 = edit(&v1, 20_000);
+    let previous = old_backup(temp.path(), &v1);
+    fs::write(temp.path().join("state.bin"), &v2).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .current_dir(temp.path())
+        .arg("state.bin")
+        .args([
+            "--lock-beside",
+            "--backup",
+            "--backup-timestamp",
+            "--backup-delta",
+        ])
+        .write_stdin("v3")
+        .assert()
+        .success();
+    assert!(is_delta(&previous).unwrap());
+    assert_eq!(rebuilt(&previous), v1);
+}
+
+#[test]
+fn test_backups_in_one_second_keep_every_version() {
+    let temp = TempDir::new().unwrap();
+    let source = temp.path().join("state.bin");
+    let versions: Vec<Vec<u8>> = (0..3)
+        .scan(content(100_000, 9), |data, i| {
+            let version = data.clone();
+            *data = edit(data, 10_000 + i * 5_000);
+            Some(version)
+        })
+        .collect();
+
+    // Far quicker than a second apart, so the timestamps collide
+    let mut backups = Vec::new();
+    for version in &versions {
+        fs::write(&source, version).unwrap();
+        backups.push(create_backup(&delta_config(&source)).unwrap());
+    }
+
+    let mut unique = backups.clone();
+    unique.dedup();
+    assert_eq!(unique.len(), 3);
+    for (backup, version) in backups.iter().zip(&versions) {
+        assert_eq!(&rebuilt(backup), version);
+    }
+    assert!(is_delta(&backups[0]).unwrap());
+}
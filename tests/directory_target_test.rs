@@ -0,0 +1,96 @@
+use assert_cmd::Command;
+use mutx::{AtomicWriter, MutxError, WriteMode};
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_writer_refuses_directory_target() {
+    let temp = TempDir::new().unwrap();
+    match AtomicWriter::new(temp.path(), WriteMode::Auto) {
+        Err(MutxError::IsADirectory(path)) => assert_eq!(path, temp.path()),
+        Err(other) => panic!("expected IsADirectory, got {:?}", other),
+        Ok(_) => panic!("expected IsADirectory"),
+    }
+}
+
+#[test]
+fn test_cli_refuses_directory_target_early() {
+    let temp = TempDir::new().unwrap();
+    let dir = temp.path().join("out");
+    fs::create_dir(&dir).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&dir)
+        .write_stdin("content")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("Target is a directory"))
+        .stderr(predicate::str::contains("--into-dir"));
+    assert!(dir.is_dir());
+}
+
+#[test]
+fn test_into_dir_writes_input_basename() {
+    let temp = TempDir::new().unwrap();
+    let dir = temp.path().join("out");
+    fs::create_dir(&dir).unwrap();
+    let input = temp.path().join("report.csv");
+    fs::write(&input, "a,b\n").unwrap();
+
+    for form in [&[][..], &["write"][..]] {
+        Command::new(env!("CARGO_BIN_EXE_mutx"))
+            .args(form)
+            .arg(&dir)
+            .arg("--into-dir")
+            .arg("--input")
+            .arg(&input)
+            .assert()
+            .success();
+        assert_eq!(fs::read_to_string(dir.join("report.csv")).unwrap(), "a,b\n");
+    }
+}
+
+#[test]
+fn test_into_dir_requires_directory_and_input() {
+    let temp = TempDir::new().unwrap();
+    let input = temp.path().join("report.csv");
+    fs::write(&input, "a,b\n").unwrap();
+
+    // OUTPUT must be an existing directory
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(temp.path().join("missing"))
+        .arg("--into-dir")
+        .arg("--input")
+        .arg(&input)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not a directory"));
+
+    // Input from stdin has no name to use
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(temp.path())
+        .arg("--into-dir")
+        .write_stdin("content")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_into_dir_refuses_directory_of_the_same_name() {
+    let temp = TempDir::new().unwrap();
+    let input = temp.path().join("report.csv");
+    fs::write(&input, "a,b\n").unwrap();
+    let dir = temp.path().join("out");
+    fs::create_dir_all(dir.join("report.csv")).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&dir)
+        .arg("--into-dir")
+        .arg("--input")
+        .arg(&input)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Target is a directory"));
+}
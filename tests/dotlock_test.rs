@@ -0,0 +1,112 @@
+use assert_cmd::Command;
+use filetime::{set_file_mtime, FileTime};
+use mutx::lock::{dotlock_path, DOTLOCK_STALE_AFTER};
+use mutx::{FileLock, LockBackend, LockStrategy, MutxError};
+use std::fs;
+use std::time::SystemTime;
+use tempfile::TempDir;
+
+#[test]
+fn test_dotlock_created_with_pid_and_removed_on_release() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = dotlock_path(&temp.path().join("mailbox"));
+
+    let lock =
+        FileLock::acquire_with_backend(&lock_path, LockStrategy::NoWait, LockBackend::Dotlock)
+            .unwrap();
+    let content = fs::read_to_string(&lock_path).unwrap();
+    assert_eq!(content.trim(), std::process::id().to_string());
+
+    drop(lock);
+    assert!(!lock_path.exists());
+}
+
+#[test]
+fn test_dotlock_held_by_live_process_blocks() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = dotlock_path(&temp.path().join("mailbox"));
+
+    let _held =
+        FileLock::acquire_with_backend(&lock_path, LockStrategy::NoWait, LockBackend::Dotlock)
+            .unwrap();
+    let result =
+        FileLock::acquire_with_backend(&lock_path, LockStrategy::NoWait, LockBackend::Dotlock);
+
+    assert!(matches!(result, Err(MutxError::LockWouldBlock { .. })));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_dotlock_with_dead_pid_is_broken() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = dotlock_path(&temp.path().join("mailbox"));
+
+    let mut child = std::process::Command::new("true").spawn().unwrap();
+    let dead_pid = child.id();
+    child.wait().unwrap();
+    fs::write(&lock_path, format!("{}\n", dead_pid)).unwrap();
+
+    let _lock =
+        FileLock::acquire_with_backend(&lock_path, LockStrategy::NoWait, LockBackend::Dotlock)
+            .unwrap();
+    let content = fs::read_to_string(&lock_path).unwrap();
+    assert_eq!(content.trim(), std::process::id().to_string());
+}
+
+#[test]
+fn test_dotlock_without_pid_uses_age() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = dotlock_path(&temp.path().join("mailbox"));
+
+    // Legacy tools (procmail) may leave an empty lock file
+    fs::write(&lock_path, b"").unwrap();
+    let result =
+        FileLock::acquire_with_backend(&lock_path, LockStrategy::NoWait, LockBackend::Dotlock);
+    assert!(matches!(result, Err(MutxError::LockWouldBlock { .. })));
+
+    let old = SystemTime::now() - DOTLOCK_STALE_AFTER * 2;
+    set_file_mtime(&lock_path, FileTime::from_system_time(old)).unwrap();
+    let lock =
+        FileLock::acquire_with_backend(&lock_path, LockStrategy::NoWait, LockBackend::Dotlock);
+    assert!(lock.is_ok());
+}
+
+#[test]
+fn test_cli_dotlock_backend_writes_and_releases() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("mailbox");
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
+    cmd.arg(&output)
+        .arg("--lock-backend")
+        .arg("dotlock")
+        .write_stdin("mail")
+        .assert()
+        .success();
+
+    assert_eq!(fs::read_to_string(&output).unwrap(), "mail");
+    assert!(!dotlock_path(&output).exists());
+}
+
+#[test]
+fn test_cli_dotlock_backend_honors_existing_lock() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("mailbox");
+    let _held = FileLock::acquire_with_backend(
+        &dotlock_path(&output),
+        LockStrategy::NoWait,
+        LockBackend::Dotlock,
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
+    cmd.arg(&output)
+        .arg("--lock-backend")
+        .arg("dotlock")
+        .arg("--no-wait")
+        .write_stdin("mail")
+        .assert()
+        .code(2);
+
+    assert!(!output.exists());
+}
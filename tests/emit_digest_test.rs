@@ -0,0 +1,66 @@
+use assert_cmd::Command;
+use mutx::{AtomicWriter, DigestAlgorithm, WriteMode};
+use std::fs;
+use std::process::Command as StdCommand;
+use tempfile::TempDir;
+
+const ABC_SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+#[test]
+fn test_report_carries_digest() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("out.txt");
+
+    let mut writer = AtomicWriter::new(&target, WriteMode::Streaming)
+        .unwrap()
+        .with_digest(DigestAlgorithm::Sha256);
+    writer.write_all(b"ab").unwrap();
+    writer.write_all(b"c").unwrap();
+    let report = writer.commit().unwrap();
+
+    assert_eq!(report.digest.as_deref(), Some(ABC_SHA256));
+}
+
+#[test]
+fn test_cli_emits_sha256sum_side_file() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("artifact.bin");
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--emit-digest", "sha256"])
+        .write_stdin("abc")
+        .assert()
+        .success();
+
+    let side = fs::read_to_string(temp.path().join("artifact.bin.sha256")).unwrap();
+    assert_eq!(side, format!("{}  artifact.bin\n", ABC_SHA256));
+
+    // Interoperates with coreutils where available
+    if let Ok(status) = StdCommand::new("sha256sum")
+        .args(["-c", "--status", "artifact.bin.sha256"])
+        .current_dir(temp.path())
+        .status()
+    {
+        assert!(status.success());
+    }
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn test_cli_digest_covers_written_bytes() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("artifact.gz");
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--compress", "gzip", "--emit-digest", "sha256"])
+        .write_stdin("abc")
+        .assert()
+        .success();
+
+    let mut hasher = DigestAlgorithm::Sha256.hasher();
+    hasher.update(&fs::read(&output).unwrap());
+    let side = fs::read_to_string(temp.path().join("artifact.gz.sha256")).unwrap();
+    assert!(side.starts_with(&hasher.finish_hex()));
+}
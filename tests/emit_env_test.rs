@@ -0,0 +1,83 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn emit_env(args: &[&str], output: &std::path::Path, input: &str) -> String {
+    let out = Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(output)
+        .args(args)
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{:?}", out);
+    String::from_utf8(out.stdout).unwrap()
+}
+
+#[test]
+fn test_emit_env_reports_change() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("app.conf");
+    let lock = temp.path().join("app.lock");
+    let lock_arg = format!("--lock-file={}", lock.display());
+
+    let stdout = emit_env(&["--emit-env", &lock_arg], &output, "one");
+    assert!(stdout.contains(&format!("MUTX_OUTPUT='{}'\n", output.display())));
+    assert!(stdout.contains(&format!("MUTX_LOCK_PATH='{}'\n", lock.display())));
+    assert!(stdout.contains("MUTX_BACKUP_PATH=''\n"));
+    assert!(stdout.contains("MUTX_CHANGED=1\n"));
+
+    let stdout = emit_env(&["--emit-env", &lock_arg], &output, "one");
+    assert!(stdout.contains("MUTX_CHANGED=0\n"));
+}
+
+#[test]
+fn test_emit_env_reports_backup() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("app.conf");
+    fs::write(&output, "old").unwrap();
+
+    let stdout = emit_env(&["--emit-env", "--backup"], &output, "new");
+    let backup = format!("{}.mutx.backup", output.display());
+    assert!(stdout.contains(&format!("MUTX_BACKUP_PATH='{}'\n", backup)));
+    assert_eq!(fs::read_to_string(backup).unwrap(), "old");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_emit_env_is_eval_safe() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("it's $HOME.conf");
+
+    // The assignments go to fd 3, which the shell reads back and evaluates
+    let script = format!(
+        "eval \"$(printf new | {} \"$1\" --emit-env=3 3>&1 >/dev/null)\" && \
+         printf '%s|%s' \"$MUTX_OUTPUT\" \"$MUTX_CHANGED\"",
+        env!("CARGO_BIN_EXE_mutx")
+    );
+    Command::new("sh")
+        .arg("-c")
+        .arg(script)
+        .arg("sh")
+        .arg(&output)
+        .assert()
+        .success()
+        .stdout(format!("{}|1", output.display()));
+    assert_eq!(fs::read_to_string(&output).unwrap(), "new");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_emit_env_closed_fd_fails() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("app.conf");
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .arg("--emit-env=9")
+        .write_stdin("new")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("file descriptor 9 is not open"));
+    assert!(!output.exists());
+}
@@ -26,3 +26,13 @@ fn test_interrupted_error_classification() {
     let err = MutxError::from(io_err);
     assert_eq!(err.exit_code(), 3);
 }
+
+#[test]
+fn test_input_not_regular_error_classification() {
+    let err = MutxError::InputNotRegular {
+        path: "/tmp".into(),
+        kind: "directory",
+    };
+    assert_eq!(err.exit_code(), 5);
+    assert_eq!(err.kind(), "input_not_regular");
+}
@@ -0,0 +1,106 @@
+use assert_cmd::Command;
+use mutx::{FileLock, LockStrategy};
+use predicates::prelude::*;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn exec(file: &Path, extra: &[&str], command: &[&str]) -> assert_cmd::assert::Assert {
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["exec", "--lock-beside"])
+        .args(extra)
+        .arg(file)
+        .arg("--")
+        .args(command)
+        .assert()
+}
+
+#[cfg(unix)]
+#[test]
+fn test_command_runs_under_the_lock() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("data.json");
+
+    // The command sees the lock held, by mutx on its behalf
+    exec(
+        &file,
+        &[],
+        &[
+            env!("CARGO_BIN_EXE_mutx"),
+            "lock",
+            "holder",
+            "--lock-beside",
+            file.to_str().unwrap(),
+        ],
+    )
+    .success()
+    .stdout(
+        predicate::str::starts_with("PID ").and(predicate::str::contains(format!(
+            "writing {}",
+            file.display()
+        ))),
+    );
+
+    // And it is released afterwards
+    let lock_path = temp.path().join("data.json.lock");
+    assert!(!FileLock::status(&lock_path).unwrap().is_held());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_exit_code_propagates() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("data.json");
+
+    exec(&file, &[], &["sh", "-c", "exit 7"]).code(7);
+    exec(&file, &[], &["true"]).success();
+    exec(&file, &[], &["sh", "-c", "kill -TERM $$"]).code(128 + 15);
+}
+
+#[test]
+fn test_missing_command_exits_127() {
+    let temp = TempDir::new().unwrap();
+
+    exec(
+        &temp.path().join("data.json"),
+        &[],
+        &["mutx-test-no-such-command"],
+    )
+    .code(127)
+    .stderr(predicate::str::contains(
+        "Cannot run mutx-test-no-such-command",
+    ));
+}
+
+#[test]
+fn test_busy_lock_skips_command() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("data.json");
+    let marker = temp.path().join("ran");
+    let _held =
+        FileLock::acquire(&temp.path().join("data.json.lock"), LockStrategy::NoWait).unwrap();
+
+    let touch = ["mutx-test-no-such-command", marker.to_str().unwrap()];
+    exec(&file, &["--no-wait"], &touch).code(2);
+    exec(&file, &["--timeout", "200", "-E", "75"], &touch).code(75);
+    assert!(!marker.exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_shared_holders_run_together() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("data.json");
+    let lock_path = temp.path().join("data.json.lock");
+    let _reader = FileLock::acquire(&lock_path, LockStrategy::NoWait.shared()).unwrap();
+
+    exec(&file, &["--shared", "--no-wait"], &["true"]).success();
+    exec(&file, &["--no-wait"], &["true"]).code(2);
+}
+
+#[test]
+fn test_command_required() {
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["exec", "data.json"])
+        .assert()
+        .failure();
+}
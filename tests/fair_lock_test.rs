@@ -0,0 +1,86 @@
+use assert_cmd::Command;
+use mutx::{FileLock, LockStrategy, MutxError, TimeoutConfig};
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn fair_timeout(ms: u64) -> LockStrategy {
+    LockStrategy::Timeout(TimeoutConfig::new(Duration::from_millis(ms))).fair()
+}
+
+#[test]
+fn test_fair_waiters_acquire_in_arrival_order() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+    let held = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+
+    let order = Arc::new(Mutex::new(Vec::new()));
+    let mut waiters = Vec::new();
+    for id in 0..5 {
+        let (lock_path, order) = (lock_path.clone(), Arc::clone(&order));
+        waiters.push(thread::spawn(move || {
+            let _lock = FileLock::acquire(&lock_path, fair_timeout(20_000)).unwrap();
+            order.lock().unwrap().push(id);
+            thread::sleep(Duration::from_millis(20));
+        }));
+        // Let each waiter take its ticket before the next arrives
+        thread::sleep(Duration::from_millis(100));
+    }
+    drop(held);
+    for waiter in waiters {
+        waiter.join().unwrap();
+    }
+
+    assert_eq!(*order.lock().unwrap(), vec![0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn test_fair_no_wait_does_not_jump_the_queue() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+    let held = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+
+    let waiter = {
+        let lock_path = lock_path.clone();
+        thread::spawn(move || FileLock::acquire(&lock_path, fair_timeout(5_000)).map(drop))
+    };
+    thread::sleep(Duration::from_millis(100));
+    drop(held);
+
+    // The lock itself may be free for a moment, but the queued waiter is first
+    let result = FileLock::acquire(&lock_path, LockStrategy::NoWait.fair());
+    if let Err(e) = &result {
+        assert!(matches!(e, MutxError::LockWouldBlock { .. }), "{e}");
+    }
+    drop(result);
+    waiter.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_fair_strategy_composes() {
+    let strategy = LockStrategy::Wait.fair().break_stale().fair();
+    assert!(strategy.is_fair());
+    assert!(strategy.breaks_stale());
+    assert!(!LockStrategy::Wait.is_fair());
+}
+
+#[test]
+fn test_cli_fair_write_leaves_no_queue() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    let lock_path = temp.path().join("out.lock");
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .arg("--lock-file")
+        .arg(&lock_path)
+        .arg("--fair")
+        .write_stdin("queued")
+        .assert()
+        .success();
+
+    assert_eq!(fs::read_to_string(&output).unwrap(), "queued");
+    assert!(!temp.path().join("out.lock.queue").exists());
+}
@@ -0,0 +1,112 @@
+use mutx::{AtomicWriter, FileLock, LockStrategy, WriteMode};
+use tempfile::TempDir;
+
+#[test]
+fn test_write_report_without_fencing() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("output.txt");
+    let lock = FileLock::acquire(&temp.path().join("output.lock"), LockStrategy::NoWait).unwrap();
+    assert_eq!(lock.fencing_token(), None);
+
+    let mut writer = AtomicWriter::new(&output, WriteMode::Streaming)
+        .unwrap()
+        .with_fencing_token(lock.fencing_token());
+    writer.write_all(b"hello ").unwrap();
+    writer.write_all(b"world").unwrap();
+    let report = writer.commit().unwrap();
+
+    assert_eq!(report.path, output);
+    assert_eq!(report.bytes_written, 11);
+    assert_eq!(report.fencing_token, None);
+}
+
+#[cfg(feature = "cluster")]
+mod cluster {
+    use super::*;
+    use assert_cmd::Command;
+    use mutx::lock::cluster::lockd::{LockServer, LockdClient};
+    use mutx::utils::xattr::{get_xattr, FENCING_TOKEN_XATTR};
+    use predicates::prelude::*;
+    use std::fs;
+
+    fn start_server() -> String {
+        let server = LockServer::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap().to_string();
+        std::thread::spawn(move || server.serve());
+        addr
+    }
+
+    fn xattrs_supported(dir: &std::path::Path) -> bool {
+        let probe = dir.join("xattr-probe");
+        let file = fs::File::create(&probe).unwrap();
+        mutx::utils::xattr::set_xattr(&file, "user.mutx.probe", b"1").is_ok()
+    }
+
+    #[test]
+    fn test_write_report_carries_increasing_tokens() {
+        let addr = start_server();
+        let client = LockdClient::new(addr.as_str());
+        let temp = TempDir::new().unwrap();
+        let output = temp.path().join("output.txt");
+
+        let mut tokens = Vec::new();
+        for _ in 0..2 {
+            let lock = FileLock::acquire_remote(&client, "report", LockStrategy::NoWait).unwrap();
+            let mut writer = AtomicWriter::new(&output, WriteMode::Auto)
+                .unwrap()
+                .with_fencing_token(lock.fencing_token());
+            writer.write_all(b"data").unwrap();
+            tokens.push(writer.commit().unwrap().fencing_token.unwrap());
+        }
+
+        assert!(tokens[1] > tokens[0]);
+    }
+
+    #[test]
+    fn test_fencing_token_embedded_in_xattr() {
+        let temp = TempDir::new().unwrap();
+        if !xattrs_supported(temp.path()) {
+            eprintln!("Skipping: filesystem does not support user xattrs");
+            return;
+        }
+
+        let output = temp.path().join("output.txt");
+        let mut writer = AtomicWriter::new(&output, WriteMode::Auto)
+            .unwrap()
+            .with_fencing_token(Some(42))
+            .with_fencing_xattr(true);
+        writer.write_all(b"data").unwrap();
+        writer.commit().unwrap();
+
+        assert_eq!(
+            get_xattr(&output, FENCING_TOKEN_XATTR).unwrap(),
+            Some(b"42".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_cli_reports_and_embeds_fencing_token() {
+        let temp = TempDir::new().unwrap();
+        if !xattrs_supported(temp.path()) {
+            eprintln!("Skipping: filesystem does not support user xattrs");
+            return;
+        }
+
+        let addr = start_server();
+        let output = temp.path().join("output.txt");
+
+        Command::new(env!("CARGO_BIN_EXE_mutx"))
+            .arg(&output)
+            .args(["--lock-backend", "remote", "--lock-server", &addr])
+            .args(["--fencing-xattr", "-v"])
+            .write_stdin("data")
+            .assert()
+            .success()
+            .stderr(predicate::str::contains("Fencing token: 1"));
+
+        assert_eq!(
+            get_xattr(&output, FENCING_TOKEN_XATTR).unwrap(),
+            Some(b"1".to_vec())
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use assert_cmd::Command;
+use mutx::lock::propagation::{check_lock_propagation, flock_support, FlockSupport};
+use predicates::prelude::*;
+use std::path::Path;
+use tempfile::TempDir;
+
+#[test]
+fn test_local_lock_beside_output_passes() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("output.txt");
+    let lock = temp.path().join("output.txt.lock");
+
+    if matches!(
+        flock_support(temp.path()),
+        FlockSupport::Reliable { .. } | FlockSupport::Unknown
+    ) {
+        assert!(check_lock_propagation(&lock, &output).is_ok());
+    }
+}
+
+#[test]
+fn test_cli_doctor_reports_checks() {
+    let temp = TempDir::new().unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
+    cmd.arg("doctor")
+        .arg(temp.path())
+        .assert()
+        .stdout(predicate::str::contains("Lock cache directory"))
+        .stdout(predicate::str::contains("Target filesystem"))
+        .stdout(predicate::str::contains("Lock scope"));
+}
+
+#[test]
+fn test_cli_strict_locking_allows_local_target() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("output.txt");
+    let lock = temp.path().join("output.lock");
+
+    if !matches!(flock_support(temp.path()), FlockSupport::Reliable { .. }) {
+        return;
+    }
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
+    cmd.arg(&output)
+        .arg("--strict-locking")
+        .arg("--lock-file")
+        .arg(&lock)
+        .write_stdin("data")
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_cli_strict_locking_rejects_private_container_lock() {
+    let shm = Path::new("/dev/shm");
+    if !shm.is_dir() {
+        return;
+    }
+    let dir = TempDir::new_in(shm).unwrap();
+    let output = dir.path().join("shared.txt");
+    let cache = mutx::lock::get_lock_cache_dir().unwrap();
+    if mutx::lock::scope::lock_scope_warning(&cache, &output, true).is_none() {
+        return;
+    }
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
+    cmd.arg(&output)
+        .arg("--strict-locking")
+        .env("container", "test")
+        .write_stdin("data")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "cannot guarantee mutual exclusion",
+        ));
+
+    assert!(!output.exists());
+}
@@ -0,0 +1,197 @@
+#![cfg(unix)]
+
+use assert_cmd::Command;
+use mutx::utils::process::hostname;
+use mutx::{FileLock, LockHolder, LockStatus, LockStrategy};
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use std::process::Command as StdCommand;
+use tempfile::TempDir;
+
+/// PID of a process that has already exited
+fn dead_pid() -> u32 {
+    let mut child = StdCommand::new("true").spawn().unwrap();
+    let pid = child.id();
+    child.wait().unwrap();
+    pid
+}
+
+/// Hold the lock at `lock_path` while it names `pid` on `host` as holder,
+/// as a lock left on a network mount by a crashed client does
+fn hold_as(lock_path: &Path, pid: u32, host: String) -> FileLock {
+    let lock = FileLock::acquire(lock_path, LockStrategy::NoWait).unwrap();
+    let holder = LockHolder {
+        pid,
+        hostname: host,
+        acquired_at: 0,
+        target: None,
+        command: None,
+        lease_ms: None,
+        fingerprint: None,
+        started_at: None,
+    };
+    fs::write(lock_path, serde_json::to_vec(&holder).unwrap()).unwrap();
+    lock
+}
+
+fn force_unlock(file: &Path, extra: &[&str]) -> assert_cmd::assert::Assert {
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["unlock", "--force", "--lock-beside"])
+        .args(extra)
+        .arg(file)
+        .assert()
+}
+
+/// Old lock files moved aside by a forced unlock
+fn broken_locks(dir: &Path) -> Vec<String> {
+    fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with("data.json.lock.broken-"))
+        .collect()
+}
+
+#[test]
+fn test_dead_holder_is_broken() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("data.json");
+    let lock_path = temp.path().join("data.json.lock");
+    let _wedged = hold_as(&lock_path, dead_pid(), hostname().unwrap());
+
+    force_unlock(&file, &["--yes"])
+        .success()
+        .stderr(predicate::str::contains("no longer running"))
+        .stderr(predicate::str::contains("Lock broken"));
+    assert_eq!(FileLock::status(&lock_path).unwrap(), LockStatus::Free);
+
+    // The holder it recorded is kept
+    let broken = broken_locks(temp.path());
+    assert_eq!(broken.len(), 1);
+    let saved = LockHolder::read(&temp.path().join(&broken[0])).unwrap();
+    assert!(saved.is_some());
+
+    // And writers get the lock again
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["--lock-beside", "--no-wait"])
+        .arg(&file)
+        .write_stdin("data")
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_running_holder_is_never_broken() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("data.json");
+    let lock_path = temp.path().join("data.json.lock");
+    let _held = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+
+    force_unlock(&file, &["--yes"])
+        .failure()
+        .stderr(predicate::str::contains("still running"));
+    assert!(FileLock::status(&lock_path).unwrap().is_held());
+    assert!(broken_locks(temp.path()).is_empty());
+}
+
+#[test]
+fn test_unverifiable_holder_is_flagged() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("data.json");
+    let lock_path = temp.path().join("data.json.lock");
+    let _wedged = hold_as(&lock_path, 4242, "crashed-host.invalid".to_string());
+
+    force_unlock(&file, &["--yes"])
+        .success()
+        .stderr(predicate::str::contains("UNVERIFIED"));
+    assert_eq!(FileLock::status(&lock_path).unwrap(), LockStatus::Free);
+}
+
+#[test]
+fn test_confirmation_required_without_yes() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("data.json");
+    let lock_path = temp.path().join("data.json.lock");
+    let _wedged = hold_as(&lock_path, dead_pid(), hostname().unwrap());
+
+    // stdin is not a terminal, so there is no one to ask
+    force_unlock(&file, &[])
+        .failure()
+        .stderr(predicate::str::contains("WARNING"))
+        .stderr(predicate::str::contains("pass --yes"));
+    assert!(FileLock::status(&lock_path).unwrap().is_held());
+}
+
+#[test]
+fn test_free_lock_needs_no_breaking() {
+    let temp = TempDir::new().unwrap();
+
+    force_unlock(&temp.path().join("data.json"), &[])
+        .success()
+        .stderr(predicate::str::contains("Not locked"));
+}
+
+#[test]
+fn test_yes_requires_force() {
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["unlock", "--yes", "data.json"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_lock_held_shared_is_never_broken() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("data.json");
+    let lock_path = temp.path().join("data.json.lock");
+    // Left by a writer that crashed before the readers came
+    drop(hold_as(&lock_path, dead_pid(), hostname().unwrap()));
+    let _reader = FileLock::acquire(&lock_path, LockStrategy::NoWait.shared()).unwrap();
+
+    force_unlock(&file, &["--yes"])
+        .failure()
+        .stderr(predicate::str::contains("held shared by live readers"));
+    assert!(broken_locks(temp.path()).is_empty());
+}
+
+#[test]
+fn test_waiter_on_broken_lock_waits_for_the_new_holder() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("data.json");
+    let lock_path = temp.path().join("data.json.lock");
+    let wedged = hold_as(&lock_path, dead_pid(), hostname().unwrap());
+
+    let exec = |extra: &[&str], script: String| {
+        StdCommand::new(env!("CARGO_BIN_EXE_mutx"))
+            .args(["exec", "--lock-beside"])
+            .args(extra)
+            .arg(&file)
+            .args(["--", "sh", "-c", &script])
+            .spawn()
+            .unwrap()
+    };
+    let waited = temp.path().join("waited");
+    let holds = temp.path().join("holds");
+
+    // Blocked in the kernel on the wedged lock file
+    let mut waiter = exec(&[], format!("touch '{}'", waited.display()));
+    std::thread::sleep(std::time::Duration::from_millis(300));
+
+    force_unlock(&file, &["--yes"]).success();
+    let mut holder = exec(
+        &["--no-wait"],
+        format!("touch '{}'; sleep 1", holds.display()),
+    );
+    while !holds.exists() {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    // The waiter gets the old file, which no longer excludes anyone
+    drop(wedged);
+    std::thread::sleep(std::time::Duration::from_millis(400));
+    assert!(!waited.exists(), "waiter ran alongside the new holder");
+
+    assert!(holder.wait().unwrap().success());
+    assert!(waiter.wait().unwrap().success());
+    assert!(waited.exists());
+}
@@ -0,0 +1,207 @@
+use assert_cmd::Command;
+use mutx::janitor::Heartbeat;
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn write_policy(dir: &Path, extra: &str) -> std::path::PathBuf {
+    let backups = dir.join("backups");
+    fs::create_dir_all(&backups).unwrap();
+    for stamp in ["20200101_000000", "20210101_000000"] {
+        fs::write(backups.join(format!("data.txt.{stamp}.mutx.backup")), stamp).unwrap();
+    }
+    let policy = dir.join("policy.toml");
+    fs::write(
+        &policy,
+        format!(
+            "heartbeat = {:?}\n{extra}\n[[backups]]\ndir = {:?}\nkeep_newest = 1\n",
+            dir.join("heartbeat.json"),
+            backups
+        ),
+    )
+    .unwrap();
+    policy
+}
+
+fn read_heartbeat(dir: &Path) -> Option<Heartbeat> {
+    let contents = fs::read_to_string(dir.join("heartbeat.json")).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+#[test]
+fn test_daemon_once_applies_policy_and_writes_heartbeat() {
+    let temp = TempDir::new().unwrap();
+    let policy = write_policy(temp.path(), "");
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["housekeep", "daemon", "--once", "--config"])
+        .arg(&policy)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Cleaned 1 backup file(s)"));
+
+    let backups = temp.path().join("backups");
+    assert!(!backups
+        .join("data.txt.20200101_000000.mutx.backup")
+        .exists());
+    assert!(backups
+        .join("data.txt.20210101_000000.mutx.backup")
+        .exists());
+
+    let heartbeat = read_heartbeat(temp.path()).unwrap();
+    assert_eq!(heartbeat.passes, 1);
+    assert_eq!(heartbeat.cleaned_backups, 1);
+    assert!(heartbeat.errors.is_empty());
+}
+
+#[test]
+fn test_daemon_once_dry_run_keeps_files() {
+    let temp = TempDir::new().unwrap();
+    let policy = write_policy(temp.path(), "");
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["housekeep", "daemon", "--once", "--dry-run", "--config"])
+        .arg(&policy)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would clean 1 backup file(s)"));
+    assert!(temp
+        .path()
+        .join("backups/data.txt.20200101_000000.mutx.backup")
+        .exists());
+}
+
+#[test]
+fn test_daemon_once_fails_on_failed_rule() {
+    let temp = TempDir::new().unwrap();
+    let policy = write_policy(
+        temp.path(),
+        &format!("[[temps]]\ndir = {:?}\n", temp.path().join("missing")),
+    );
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["housekeep", "daemon", "--once", "--config"])
+        .arg(&policy)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("1 housekeeping rule(s) failed"));
+    // The other rules still ran
+    assert_eq!(read_heartbeat(temp.path()).unwrap().cleaned_backups, 1);
+}
+
+#[test]
+fn test_daemon_rejects_invalid_policy() {
+    let temp = TempDir::new().unwrap();
+    let policy = temp.path().join("policy.toml");
+    fs::write(&policy, "interval = \"often\"\n").unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["housekeep", "daemon", "--config"])
+        .arg(&policy)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("policy.toml"));
+}
+
+#[test]
+fn test_backup_rules_use_configured_suffix() {
+    let temp = TempDir::new().unwrap();
+    let backups = temp.path().join("backups");
+    fs::create_dir_all(&backups).unwrap();
+    for stamp in ["20200101_000000", "20210101_000000"] {
+        fs::write(backups.join(format!("data.txt.{stamp}.bak")), stamp).unwrap();
+    }
+    let config = temp.path().join("config.toml");
+    fs::write(&config, "backup_suffix = \".bak\"\n").unwrap();
+    let policy = temp.path().join("policy.toml");
+    fs::write(
+        &policy,
+        format!("[[backups]]\ndir = {:?}\nkeep_newest = 1\n", backups),
+    )
+    .unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .env("MUTX_CONFIG", &config)
+        .args(["housekeep", "daemon", "--once", "--config"])
+        .arg(&policy)
+        .assert()
+        .success();
+    assert!(!backups.join("data.txt.20200101_000000.bak").exists());
+    assert!(backups.join("data.txt.20210101_000000.bak").exists());
+}
+
+#[test]
+fn test_daemon_rejects_zero_interval() {
+    let temp = TempDir::new().unwrap();
+    let policy = write_policy(temp.path(), "");
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["housekeep", "daemon", "--interval", "0", "--config"])
+        .arg(&policy)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("greater than zero"));
+}
+
+#[test]
+#[cfg(unix)]
+fn test_daemon_outlives_unwritable_heartbeat() {
+    use std::process::Stdio;
+    use std::time::Duration;
+
+    let temp = TempDir::new().unwrap();
+    let policy = temp.path().join("policy.toml");
+    fs::write(
+        &policy,
+        format!(
+            "interval = \"1s\"\nheartbeat = {:?}\n[[temps]]\ndir = {:?}\n",
+            temp.path().join("missing").join("heartbeat.json"),
+            temp.path()
+        ),
+    )
+    .unwrap();
+
+    let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["housekeep", "daemon", "--config"])
+        .arg(&policy)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    std::thread::sleep(Duration::from_millis(1500));
+    assert!(child.try_wait().unwrap().is_none(), "daemon stopped");
+
+    unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGTERM) };
+    assert!(child.wait().unwrap().success());
+}
+
+#[test]
+#[cfg(unix)]
+fn test_daemon_repeats_until_terminated() {
+    use std::process::Stdio;
+    use std::time::{Duration, Instant};
+
+    let temp = TempDir::new().unwrap();
+    let policy = write_policy(temp.path(), "interval = \"1s\"\njitter = \"0s\"");
+
+    let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["housekeep", "daemon", "--config"])
+        .arg(&policy)
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while read_heartbeat(temp.path()).map_or(true, |h| h.passes < 2) {
+        assert!(
+            Instant::now() < deadline,
+            "daemon did not repeat its passes"
+        );
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGTERM) };
+    let status = child.wait().unwrap();
+    assert!(status.success(), "{status:?}");
+}
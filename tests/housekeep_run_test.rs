@@ -0,0 +1,96 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn backups(dir: &Path, stamps: &[&str]) {
+    fs::create_dir_all(dir).unwrap();
+    for stamp in stamps {
+        fs::write(dir.join(format!("data.txt.{stamp}.mutx.backup")), stamp).unwrap();
+    }
+}
+
+const STAMPS: [&str; 3] = ["20200101_000000", "20210101_000000", "20220101_000000"];
+
+fn policy(temp: &Path) -> String {
+    format!(
+        r#"
+[[directory]]
+path = {:?}
+backups = {{ keep_newest = 1 }}
+
+[[directory]]
+path = {:?}
+recursive = true
+backups = {{ keep_newest = 2 }}
+"#,
+        temp.join("app"),
+        temp.join("reports")
+    )
+}
+
+#[test]
+fn test_run_applies_each_directory_its_own_rules() {
+    let temp = TempDir::new().unwrap();
+    backups(&temp.path().join("app"), &STAMPS);
+    backups(&temp.path().join("reports/2024"), &STAMPS);
+    let config = temp.path().join("housekeep.toml");
+    fs::write(&config, policy(temp.path())).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["housekeep", "run", "--config"])
+        .arg(&config)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Cleaned 3 backup file(s)"));
+
+    let left = |dir: &str| fs::read_dir(temp.path().join(dir)).unwrap().count();
+    assert_eq!(left("app"), 1);
+    assert_eq!(left("reports/2024"), 2);
+}
+
+#[test]
+fn test_run_defaults_to_policy_next_to_config_file() {
+    let temp = TempDir::new().unwrap();
+    backups(&temp.path().join("app"), &STAMPS);
+    fs::create_dir(temp.path().join("reports")).unwrap();
+    fs::write(temp.path().join("housekeep.toml"), policy(temp.path())).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .env("MUTX_CONFIG", temp.path().join("config.toml"))
+        .args(["housekeep", "run", "--dry-run", "-v"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would clean 2 backup file(s)"))
+        .stdout(predicate::str::contains("20200101_000000"));
+    assert_eq!(fs::read_dir(temp.path().join("app")).unwrap().count(), 3);
+}
+
+#[test]
+fn test_run_without_policy_fails() {
+    let temp = TempDir::new().unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .env("MUTX_CONFIG", temp.path().join("config.toml"))
+        .args(["housekeep", "run"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("housekeep.toml"));
+}
+
+#[test]
+fn test_run_rejects_directory_without_rules() {
+    let temp = TempDir::new().unwrap();
+    let config = temp.path().join("housekeep.toml");
+    fs::write(&config, "[[directory]]\npath = \"/srv\"\n").unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["housekeep", "run", "--config"])
+        .arg(&config)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "has no locks, backups or temps rules",
+        ));
+}
@@ -0,0 +1,159 @@
+use assert_cmd::Command;
+use filetime::{set_file_mtime, FileTime};
+use mutx::housekeep::{clean_temps, CleanTempConfig};
+use mutx::utils::{is_mutx_temp, temp_path_for};
+use predicates::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn stale(path: &Path) -> PathBuf {
+    fs::write(path, "partial").unwrap();
+    set_file_mtime(path, FileTime::from_unix_time(1_000_000, 0)).unwrap();
+    path.to_path_buf()
+}
+
+fn clean(dir: &Path, older_than: Option<Duration>) -> Vec<PathBuf> {
+    let mut cleaned = clean_temps(&CleanTempConfig {
+        dir: dir.to_path_buf(),
+        recursive: false,
+        older_than,
+        dry_run: false,
+    })
+    .unwrap();
+    cleaned.sort();
+    cleaned
+}
+
+#[test]
+fn test_backup_stage_uses_temp_suffix() {
+    let stage = temp_path_for(Path::new("file.txt.mutx.backup"));
+    assert!(is_mutx_temp(&stage));
+}
+
+#[test]
+fn test_cleans_stale_mutx_temps_only() {
+    let temp = TempDir::new().unwrap();
+    fs::write(temp.path().join("file.txt"), "data").unwrap();
+
+    let backup_stage = stale(&temp.path().join("file.txt.mutx.backup.mutx.tmp"));
+    let legacy_stage = stale(&temp.path().join("file.txt.mutx.tmp"));
+    let legacy_write = stale(&temp.path().join(".file.txt.a1B2c3"));
+    let unrelated = stale(&temp.path().join("notes.tmp"));
+    let dotfile = stale(&temp.path().join(".bashrc.backup"));
+
+    let cleaned = clean(temp.path(), Some(Duration::from_secs(3600)));
+
+    let mut expected = vec![backup_stage, legacy_stage, legacy_write];
+    expected.sort();
+    assert_eq!(cleaned, expected);
+    assert!(unrelated.exists());
+    assert!(dotfile.exists());
+    assert!(temp.path().join("file.txt").exists());
+}
+
+#[test]
+fn test_recent_temps_are_kept() {
+    let temp = TempDir::new().unwrap();
+    let in_progress = temp.path().join("file.txt.mutx.tmp");
+    fs::write(&in_progress, "partial").unwrap();
+
+    assert!(clean(temp.path(), Some(Duration::from_secs(3600))).is_empty());
+    assert!(in_progress.exists());
+
+    assert_eq!(clean(temp.path(), None), vec![in_progress]);
+}
+
+#[test]
+fn test_cli_housekeep_temps() {
+    let temp = TempDir::new().unwrap();
+    let stale_temp = stale(&temp.path().join("file.txt.mutx.backup.mutx.tmp"));
+    let fresh_temp = temp.path().join("other.txt.mutx.tmp");
+    fs::write(&fresh_temp, "partial").unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["housekeep", "temps", "--dry-run"])
+        .arg(temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would clean 1 temp file(s)"));
+    assert!(stale_temp.exists());
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["housekeep", "temps"])
+        .arg(temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Cleaned 1 temp file(s)"));
+    assert!(!stale_temp.exists());
+    assert!(fresh_temp.exists());
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["housekeep", "temps", "--older-than", "0s"])
+        .arg(temp.path())
+        .assert()
+        .success();
+    assert!(!fresh_temp.exists());
+}
+
+#[test]
+fn test_write_temps_use_recognizable_names() {
+    use mutx::{AtomicWriter, WriteMode};
+
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("file.txt");
+
+    let mut writer = AtomicWriter::new(&output, WriteMode::Streaming).unwrap();
+    writer.write_all(b"partial").unwrap();
+
+    // The in-flight temp is hidden, tagged with our PID and the mutx suffix
+    let temps: Vec<PathBuf> = fs::read_dir(temp.path())
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .collect();
+    assert_eq!(temps.len(), 1);
+    assert!(is_mutx_temp(&temps[0]));
+    assert_eq!(
+        mutx::utils::temp_owner_pid(&temps[0]),
+        Some(std::process::id())
+    );
+
+    // Our own PID is alive, so housekeep leaves the temp alone
+    assert!(clean(temp.path(), None).is_empty());
+
+    drop(writer);
+    assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 0);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cleans_temps_of_dead_writers() {
+    let temp = TempDir::new().unwrap();
+    // PIDs this large are never handed out
+    let orphan = stale(&temp.path().join(".file.txt.4294967-deadbeef.mutx.tmp"));
+
+    assert_eq!(clean(temp.path(), None), vec![orphan]);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cleans_scratch_dirs_of_dead_writers() {
+    let temp = TempDir::new().unwrap();
+    let orphan = temp.path().join(".file.txt.4294967-deadbeef.mutx.scratch");
+    fs::create_dir(&orphan).unwrap();
+    stale(&orphan.join("intermediate.json"));
+    let ours = mutx::utils::unique_scratch_path(&temp.path().join("file.txt"));
+    fs::create_dir(&ours).unwrap();
+
+    let cleaned = clean_temps(&CleanTempConfig {
+        dir: temp.path().to_path_buf(),
+        recursive: true,
+        older_than: None,
+        dry_run: false,
+    })
+    .unwrap();
+    assert_eq!(cleaned, vec![orphan.clone()]);
+    assert!(!orphan.exists());
+    assert!(ours.exists());
+}
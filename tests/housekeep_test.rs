@@ -96,7 +96,7 @@ fn test_older_than_filter() {
     assert!(recent_lock.exists(), "Recent lock should not be cleaned");
 }
 
-use mutx::housekeep::{clean_backups, CleanBackupConfig};
+use mutx::housekeep::{clean_backups, AgeSource, CleanBackupConfig};
 
 #[test]
 fn test_ignores_user_backup_files() {
@@ -124,7 +124,9 @@ fn test_ignores_user_backup_files() {
         older_than: Some(Duration::from_secs(0)), // Clean all
         keep_newest: None,
         dry_run: false,
-        suffix: ".mutx.backup".to_string(),
+        suffix: ".mutx.backup".parse().unwrap(),
+        age_source: AgeSource::default(),
+        max_total_size: None,
     };
 
     let cleaned = clean_backups(&config).unwrap();
@@ -146,6 +148,7 @@ fn test_cleans_custom_suffix_backups() {
     // Create backups with custom suffix
     fs::write(dir.path().join("file.txt.bak"), "backup1").unwrap();
     fs::write(dir.path().join("file.txt.20260126_120000.bak"), "backup2").unwrap();
+    fs::write(dir.path().join("file.txt.20260125_120000.bak"), "backup3").unwrap();
 
     // Should not touch .mutx.backup files
     fs::write(dir.path().join("other.txt.mutx.backup"), "keep").unwrap();
@@ -156,14 +159,48 @@ fn test_cleans_custom_suffix_backups() {
         older_than: None,
         keep_newest: Some(1),
         dry_run: false,
-        suffix: ".bak".to_string(),
+        suffix: ".bak".parse().unwrap(),
+        age_source: AgeSource::default(),
+        max_total_size: None,
     };
 
     let cleaned = clean_backups(&config).unwrap();
 
-    // Should clean one .bak file (keeping newest)
-    assert_eq!(cleaned.len(), 1);
+    // Should clean one .bak file (keeping the newest timestamped one and the
+    // suffix-only latest slot)
+    assert_eq!(
+        cleaned,
+        vec![dir.path().join("file.txt.20260125_120000.bak")]
+    );
+    assert!(dir.path().join("file.txt.bak").exists());
 
     // .mutx.backup file should still exist
     assert!(dir.path().join("other.txt.mutx.backup").exists());
 }
+
+#[test]
+fn test_clean_idle_lock_queue() {
+    let dir = TempDir::new().unwrap();
+    let lock = dir.path().join("queued.lock");
+    let queue = dir.path().join("queued.lock.queue");
+    let _held = mutx::FileLock::acquire(&lock, mutx::LockStrategy::Wait).unwrap();
+    fs::create_dir(&queue).unwrap();
+    File::create(queue.join("lock")).unwrap();
+    fs::write(queue.join("seq"), "7").unwrap();
+    // Ticket of a waiter that was killed
+    File::create(queue.join(format!("{:020}.1", 7))).unwrap();
+
+    let mut config = CleanLockConfig {
+        dir: dir.path().to_path_buf(),
+        recursive: false,
+        older_than: None,
+        dry_run: true,
+    };
+    assert_eq!(clean_locks(&config).unwrap(), vec![queue.clone()]);
+    assert!(queue.join("seq").exists());
+
+    config.dry_run = false;
+    assert_eq!(clean_locks(&config).unwrap(), vec![queue.clone()]);
+    assert!(!queue.exists());
+    assert!(lock.exists());
+}
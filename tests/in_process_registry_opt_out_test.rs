@@ -0,0 +1,21 @@
+//! In a binary of its own, since the registry is switched off process-wide
+
+use mutx::lock::{in_process_registry_enabled, set_in_process_registry};
+use mutx::{FileLock, LockStrategy, MutxError};
+use tempfile::TempDir;
+
+#[test]
+fn test_opt_out_skips_registry() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("data.lock");
+    assert!(in_process_registry_enabled());
+
+    set_in_process_registry(false);
+    assert!(!in_process_registry_enabled());
+    let _held = FileLock::acquire(&lock_path, LockStrategy::NoWait.reentrant()).unwrap();
+
+    // Without the registry there is nothing to nest in: the second
+    // acquisition meets the lock file, held through another descriptor
+    let result = FileLock::acquire(&lock_path, LockStrategy::NoWait.reentrant());
+    assert!(matches!(result, Err(MutxError::LockWouldBlock { .. })));
+}
@@ -0,0 +1,121 @@
+use mutx::{FileLock, LockStrategy, MutxError, TimeoutConfig};
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+/// Whether another thread could take the lock right now
+fn free_elsewhere(lock_path: &Path) -> bool {
+    let lock_path = lock_path.to_path_buf();
+    thread::spawn(move || FileLock::acquire(&lock_path, LockStrategy::NoWait).is_ok())
+        .join()
+        .unwrap()
+}
+
+#[test]
+fn test_threads_exclude_each_other() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("data.lock");
+
+    let held = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+    let other = lock_path.clone();
+    let result = thread::spawn(move || FileLock::acquire(&other, LockStrategy::NoWait))
+        .join()
+        .unwrap();
+    assert!(matches!(result, Err(MutxError::LockWouldBlock { .. })));
+
+    drop(held);
+    assert!(free_elsewhere(&lock_path));
+}
+
+#[test]
+fn test_waiting_thread_gets_lock_on_release() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("data.lock");
+    let held = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+
+    let (acquired, on_acquired) = mpsc::channel();
+    let other = lock_path.clone();
+    let waiter = thread::spawn(move || {
+        let strategy = LockStrategy::Timeout(TimeoutConfig::new(Duration::from_secs(10)));
+        let _lock = FileLock::acquire(&other, strategy).unwrap();
+        acquired.send(Instant::now()).unwrap();
+    });
+
+    thread::sleep(Duration::from_millis(300));
+    let released = Instant::now();
+    drop(held);
+    assert!(on_acquired.recv().unwrap() >= released);
+    waiter.join().unwrap();
+}
+
+#[test]
+fn test_same_thread_waits_for_itself() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("data.lock");
+    let _held = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+
+    let result = FileLock::acquire(&lock_path, LockStrategy::NoWait);
+    assert!(matches!(result, Err(MutxError::LockWouldBlock { .. })));
+}
+
+#[test]
+fn test_reentrant_nests_until_last_handle() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("data.lock");
+
+    let outer = FileLock::acquire(&lock_path, LockStrategy::NoWait.reentrant()).unwrap();
+    let inner = FileLock::acquire(&lock_path, LockStrategy::NoWait.reentrant()).unwrap();
+    assert!(inner.holder().is_none());
+
+    // The outermost handle going first leaves the lock held
+    drop(outer);
+    assert!(FileLock::status(&lock_path).unwrap().is_held());
+    assert!(!free_elsewhere(&lock_path));
+
+    drop(inner);
+    assert!(!FileLock::status(&lock_path).unwrap().is_held());
+    assert!(free_elsewhere(&lock_path));
+}
+
+#[test]
+fn test_reentrant_only_nests_in_own_thread() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("data.lock");
+    let _held = FileLock::acquire(&lock_path, LockStrategy::NoWait.reentrant()).unwrap();
+
+    let other = lock_path.clone();
+    let result = thread::spawn(move || FileLock::acquire(&other, LockStrategy::NoWait.reentrant()))
+        .join()
+        .unwrap();
+    assert!(matches!(result, Err(MutxError::LockWouldBlock { .. })));
+}
+
+#[test]
+fn test_shared_claims_coexist() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("data.lock");
+    let _reader = FileLock::acquire(&lock_path, LockStrategy::NoWait.shared()).unwrap();
+
+    let other = lock_path.clone();
+    let second =
+        thread::spawn(move || FileLock::acquire(&other, LockStrategy::NoWait.shared()).map(|_| ()))
+            .join()
+            .unwrap();
+    assert!(second.is_ok());
+    assert!(!free_elsewhere(&lock_path));
+}
+
+#[test]
+fn test_spellings_of_one_lock_meet() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("data.lock");
+    let _held = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+
+    let dotted = temp.path().join(".").join("data.lock");
+    let result = thread::spawn(move || FileLock::acquire(&dotted, LockStrategy::NoWait))
+        .join()
+        .unwrap();
+    assert!(matches!(result, Err(MutxError::LockWouldBlock { .. })));
+}
@@ -0,0 +1,89 @@
+#![cfg(unix)]
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use std::os::unix::net::UnixListener;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn write_from(input: &Path, output: &Path, extra: &[&str]) -> assert_cmd::assert::Assert {
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(extra)
+        .arg("--input")
+        .arg(input)
+        .arg(output)
+        .assert()
+}
+
+#[test]
+fn test_directory_input_is_rejected() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+
+    write_from(temp.path(), &output, &[])
+        .code(5)
+        .stderr(predicate::str::contains(
+            "Input is a directory, not a regular file",
+        ))
+        .stderr(predicate::str::contains("Name a file inside it"));
+    assert!(!output.exists());
+}
+
+#[test]
+fn test_socket_input_is_rejected_even_when_allowed() {
+    let temp = TempDir::new().unwrap();
+    let socket = temp.path().join("sock");
+    let _listener = UnixListener::bind(&socket).unwrap();
+    let output = temp.path().join("out.txt");
+
+    for extra in [&[][..], &["--input-allow-special"][..]] {
+        write_from(&socket, &output, extra)
+            .code(5)
+            .stderr(predicate::str::contains("Input is a socket"));
+    }
+    assert!(!output.exists());
+}
+
+#[test]
+fn test_character_device_needs_allow_special() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    fs::write(&output, "old").unwrap();
+
+    write_from(Path::new("/dev/null"), &output, &[])
+        .code(5)
+        .stderr(predicate::str::contains("Input is a character device"))
+        .stderr(predicate::str::contains("--input-allow-special"));
+    assert_eq!(fs::read_to_string(&output).unwrap(), "old");
+
+    write_from(Path::new("/dev/null"), &output, &["--input-allow-special"]).success();
+    assert_eq!(fs::read_to_string(&output).unwrap(), "");
+}
+
+#[test]
+fn test_endless_device_stops_at_max_size() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.bin");
+
+    write_from(
+        Path::new("/dev/urandom"),
+        &output,
+        &["--input-allow-special", "--max-size", "64K"],
+    )
+    .code(1)
+    .stderr(predicate::str::contains("exceeds the size limit"));
+    assert!(!output.exists());
+}
+
+#[test]
+fn test_allow_special_requires_input() {
+    let temp = TempDir::new().unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg("--input-allow-special")
+        .arg(temp.path().join("out.txt"))
+        .write_stdin("data")
+        .assert()
+        .failure();
+}
@@ -10,7 +10,7 @@ fn test_lock_and_write_integration() {
 
     let _lock = FileLock::acquire(&lock_path, LockStrategy::Wait).unwrap();
 
-    let mut writer = AtomicWriter::new(&target, WriteMode::Simple).unwrap();
+    let mut writer = AtomicWriter::new(&target, WriteMode::Auto).unwrap();
     writer.write_all(b"locked write").unwrap();
     writer.commit().unwrap();
 
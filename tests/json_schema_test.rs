@@ -0,0 +1,310 @@
+use assert_cmd::Command;
+use mutx::janitor::Heartbeat;
+use mutx::schema::{self, ErrorReport, HousekeepReport, SCHEMAS};
+use mutx::utils::xattr::{set_xattr, BACKUP_SHA256_XATTR};
+use mutx::{FileLock, LockStrategy};
+use serde_json::Value;
+use std::fs;
+use tempfile::TempDir;
+
+fn mutx(temp: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("mutx").unwrap();
+    cmd.env("XDG_CACHE_HOME", temp.path().join("cache"))
+        .env("MUTX_CONFIG", temp.path().join("config.toml"))
+        .env_remove("MUTX_LOCK_DIR");
+    cmd
+}
+
+/// Check `value` against the parts of JSON Schema the mutx schemas use.
+/// Properties a schema does not declare are rejected too, so every field
+/// mutx prints must be documented.
+fn validate(value: &Value, schema: &Value, at: &str) {
+    if let Some(expected) = schema.get("const") {
+        assert_eq!(value, expected, "{}", at);
+    }
+    if let Some(types) = schema.get("type") {
+        let types: Vec<&str> = match types {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().map(|t| t.as_str().unwrap()).collect(),
+            _ => panic!("bad type in schema at {}", at),
+        };
+        let actual = match value {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        };
+        assert!(
+            types.contains(&actual),
+            "{}: {} is not {:?}",
+            at,
+            actual,
+            types
+        );
+    }
+    if let (Some(required), Value::Object(object)) = (schema.get("required"), value) {
+        for key in required.as_array().unwrap() {
+            let key = key.as_str().unwrap();
+            assert!(object.contains_key(key), "{}: missing {}", at, key);
+        }
+    }
+    if let (Some(properties), Value::Object(object)) = (schema.get("properties"), value) {
+        for (key, field) in object {
+            let property = properties
+                .get(key)
+                .unwrap_or_else(|| panic!("{}: undocumented field {}", at, key));
+            validate(field, property, &format!("{}.{}", at, key));
+        }
+    }
+    if let (Some(items), Value::Array(array)) = (schema.get("items"), value) {
+        for (i, item) in array.iter().enumerate() {
+            validate(item, items, &format!("{}[{}]", at, i));
+        }
+    }
+}
+
+fn validate_document(json: &str, name: &str) -> Value {
+    let value: Value = serde_json::from_str(json.trim()).unwrap();
+    let schema: Value = serde_json::from_str(schema::schema(name).unwrap()).unwrap();
+    validate(&value, &schema, name);
+    value
+}
+
+#[test]
+fn test_write_report_matches_schema() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+
+    let out = mutx(&temp)
+        .args(["write", "--json", "--emit-digest", "sha256"])
+        .arg(&output)
+        .write_stdin("data")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let report = validate_document(&String::from_utf8(out).unwrap(), schema::WRITE);
+    assert_eq!(report["bytes_written"], 4);
+}
+
+#[test]
+fn test_write_report_backup_matches_schema() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    fs::write(&output, "old").unwrap();
+
+    let out = mutx(&temp)
+        .args(["write", "--json", "--backup"])
+        .arg(&output)
+        .write_stdin("data")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let report = validate_document(&String::from_utf8(out).unwrap(), schema::WRITE);
+    assert_eq!(report["backup"]["status"], "created");
+    assert!(report["backup"]["path"].is_string());
+}
+
+#[test]
+fn test_lock_status_matches_schema() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("data.txt");
+    let lock_path = temp.path().join("data.lock");
+
+    let status = || {
+        let out = mutx(&temp)
+            .args(["status", "--json", "--lock-file"])
+            .arg(&lock_path)
+            .arg(&file)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        validate_document(&String::from_utf8(out).unwrap(), schema::LOCK_STATUS)
+    };
+
+    assert_eq!(status()["state"], "free");
+    let _lock = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+    let report = status();
+    assert_eq!(report["state"], "held");
+    assert_eq!(report["pid"], std::process::id());
+}
+
+#[test]
+fn test_check_report_matches_schema() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("missing").join("out.txt");
+
+    let out = mutx(&temp)
+        .args(["check", "--json"])
+        .arg(&file)
+        .assert()
+        .failure()
+        .get_output()
+        .stdout
+        .clone();
+    let report = validate_document(&String::from_utf8(out).unwrap(), schema::CHECK);
+    assert_eq!(report["passed"], false);
+    assert!(report["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|check| check["status"] == "fail"));
+}
+
+#[test]
+fn test_backup_check_report_matches_schema() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("out.txt");
+    fs::write(temp.path().join("out.txt.mutx.backup"), "old").unwrap();
+    let broken = temp.path().join("out.txt.20240101_000000.mutx.backup");
+    fs::write(&broken, "older").unwrap();
+    // Recorded for content other than what it holds now
+    set_xattr(
+        &fs::File::open(&broken).unwrap(),
+        BACKUP_SHA256_XATTR,
+        b"0000",
+    )
+    .unwrap();
+
+    let out = mutx(&temp)
+        .args(["backups", "check", "--json"])
+        .arg(&file)
+        .assert()
+        .failure()
+        .get_output()
+        .stdout
+        .clone();
+    let report = validate_document(&String::from_utf8(out).unwrap(), schema::BACKUP_CHECK);
+    assert_eq!(report["passed"], false);
+    assert_eq!(report["backups"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn test_housekeep_report_round_trips() {
+    let temp = TempDir::new().unwrap();
+    let dir = temp.path().join("backups");
+    fs::create_dir_all(&dir).unwrap();
+    for stamp in ["20200101_000000", "20210101_000000"] {
+        fs::write(dir.join(format!("data.txt.{stamp}.mutx.backup")), stamp).unwrap();
+    }
+
+    let out = mutx(&temp)
+        .args([
+            "housekeep",
+            "backups",
+            "--keep-newest",
+            "1",
+            "--dry-run",
+            "--json",
+        ])
+        .arg(&dir)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let out = String::from_utf8(out).unwrap();
+    validate_document(&out, schema::HOUSEKEEP);
+
+    let report: HousekeepReport = serde_json::from_str(&out).unwrap();
+    assert!(report.dry_run);
+    assert_eq!(report.backups.len(), 1);
+    assert!(report.backups[0].ends_with("data.txt.20200101_000000.mutx.backup"));
+    assert!(report.locks.is_empty() && report.temps.is_empty());
+}
+
+#[test]
+fn test_heartbeat_matches_status_schema() {
+    let temp = TempDir::new().unwrap();
+    let policy = temp.path().join("policy.toml");
+    let heartbeat = temp.path().join("heartbeat.json");
+    fs::write(&policy, format!("heartbeat = {:?}\n", heartbeat)).unwrap();
+
+    mutx(&temp)
+        .args(["housekeep", "daemon", "--once", "--config"])
+        .arg(&policy)
+        .assert()
+        .success();
+
+    let contents = fs::read_to_string(&heartbeat).unwrap();
+    validate_document(&contents, schema::STATUS);
+    let heartbeat: Heartbeat = serde_json::from_str(&contents).unwrap();
+    assert_eq!(heartbeat.passes, 1);
+}
+
+#[test]
+fn test_json_error_on_stdout() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    let _held = FileLock::acquire(&temp.path().join("out.txt.lock"), LockStrategy::NoWait).unwrap();
+
+    let out = mutx(&temp)
+        .args(["write", "--json", "--no-wait", "--lock-beside"])
+        .arg(&output)
+        .write_stdin("data")
+        .assert()
+        .code(2)
+        .get_output()
+        .stdout
+        .clone();
+    let out = String::from_utf8(out).unwrap();
+    validate_document(&out, schema::ERROR);
+
+    let error: ErrorReport = serde_json::from_str(&out).unwrap();
+    assert_eq!(error.kind, "lock_would_block");
+    assert_eq!(error.exit_code, 2);
+}
+
+#[test]
+fn test_error_without_json_keeps_stdout_empty() {
+    let temp = TempDir::new().unwrap();
+
+    mutx(&temp)
+        .arg(temp.path().join("missing").join("out.txt"))
+        .write_stdin("data")
+        .assert()
+        .failure()
+        .stdout("");
+}
+
+#[test]
+fn test_schema_dump() {
+    let temp = TempDir::new().unwrap();
+
+    let out = mutx(&temp)
+        .args(["schema", "dump"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let all: Value = serde_json::from_slice(&out).unwrap();
+    for (name, _) in SCHEMAS {
+        assert_eq!(all[name]["$id"], *name);
+    }
+
+    let out = mutx(&temp)
+        .args(["schema", "dump", "mutx.error.v1"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        schema::schema(schema::ERROR).unwrap()
+    );
+
+    mutx(&temp)
+        .args(["schema", "dump", "mutx.status.v0"])
+        .assert()
+        .failure();
+}
@@ -0,0 +1,65 @@
+use assert_cmd::Command;
+use mutx::lock::{canonical_output_path, derive_lock_path_unchecked, lock_shard, LockScheme};
+use std::path::Path;
+use tempfile::TempDir;
+
+#[test]
+fn test_unchecked_derivation_needs_no_files() {
+    // Neither the output nor its directory has to exist
+    let canonical = Path::new("/nonexistent/mutx-test/config.json");
+    let lock = derive_lock_path_unchecked(canonical, &LockScheme::default()).unwrap();
+
+    let shard = lock.parent().unwrap();
+    assert_eq!(
+        shard.file_name().unwrap().to_str().unwrap(),
+        lock_shard(canonical)
+    );
+    assert!(lock
+        .file_name()
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .starts_with("n.mutx-test.config.json."));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_custom_lock_file_does_not_create_cache_dir() {
+    let temp = TempDir::new().unwrap();
+    let cache_home = temp.path().join("cache");
+    let output = temp.path().join("output.txt");
+    let lock = temp.path().join("output.lock");
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .env("XDG_CACHE_HOME", &cache_home)
+        .arg(&output)
+        .arg("--lock-file")
+        .arg(&lock)
+        .write_stdin("data")
+        .assert()
+        .success();
+
+    assert!(!cache_home.exists());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_lock_path_command_does_not_create_cache_dir() {
+    let temp = TempDir::new().unwrap();
+    let cache_home = temp.path().join("cache");
+    let output = temp.path().join("output.txt");
+
+    let out = Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .env("XDG_CACHE_HOME", &cache_home)
+        .args(["lock", "path"])
+        .arg(&output)
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+
+    let printed = String::from_utf8(out.stdout).unwrap();
+    let canonical = canonical_output_path(&output).unwrap();
+    assert!(printed.trim().starts_with(cache_home.to_str().unwrap()));
+    assert!(printed.contains(&lock_shard(&canonical)));
+    assert!(!cache_home.exists());
+}
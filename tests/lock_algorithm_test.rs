@@ -0,0 +1,62 @@
+use assert_cmd::Command;
+use mutx::lock::{canonical_output_path, lock_filename, LockScheme};
+use predicates::prelude::*;
+use tempfile::TempDir;
+
+#[test]
+fn test_pure_filename_matches_derived_lock_path() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("output.txt");
+
+    let canonical = canonical_output_path(&output).unwrap();
+    let name = lock_filename(&canonical, &LockScheme::default()).unwrap();
+    let derived = mutx::derive_lock_path(&output, false).unwrap();
+
+    assert_eq!(derived.file_name().unwrap().to_str().unwrap(), name);
+}
+
+#[test]
+fn test_pure_filename_needs_no_filesystem() {
+    // Path does not exist anywhere; the pure function must still work
+    let canonical = std::env::temp_dir()
+        .join("mutx-nonexistent-dir")
+        .join("nested")
+        .join("file.txt");
+
+    let name = lock_filename(&canonical, &LockScheme::default()).unwrap();
+    assert!(name.contains("nested.file.txt."));
+    assert!(!canonical.parent().unwrap().exists());
+}
+
+#[test]
+fn test_cli_lock_path_prints_derived_path() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("output.txt");
+    let expected = mutx::derive_lock_path(&output, false).unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
+    cmd.arg("lock")
+        .arg("path")
+        .arg(&output)
+        .assert()
+        .success()
+        .stdout(format!("{}\n", expected.display()));
+}
+
+#[test]
+fn test_cli_lock_path_print_algorithm() {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
+    cmd.arg("lock")
+        .arg("path")
+        .arg("--print-algorithm")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("SHA-256"))
+        .stdout(predicate::str::contains("Lock cache directory:"));
+}
+
+#[test]
+fn test_cli_lock_path_requires_file() {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
+    cmd.arg("lock").arg("path").assert().failure();
+}
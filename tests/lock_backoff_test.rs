@@ -1,4 +1,4 @@
-use mutx::lock::{FileLock, LockStrategy, TimeoutConfig};
+use mutx::lock::{FileLock, LockBackend, LockStrategy, TimeoutConfig};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
@@ -70,3 +70,81 @@ fn test_max_poll_interval_respected() {
     assert!(elapsed >= Duration::from_millis(1800));
     assert!(elapsed <= Duration::from_millis(3000));
 }
+
+/// The sleeps between attempts on a held lock with `config`
+fn observed_sleeps(config: TimeoutConfig) -> Vec<Duration> {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("test.lock");
+    let _holder = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+
+    let mut sleeps = Vec::new();
+    let result = FileLock::acquire_observed(
+        &lock_path,
+        LockStrategy::Timeout(config),
+        LockBackend::Flock,
+        |retry| sleeps.push(retry.sleep),
+    );
+    assert!(result.is_err());
+    sleeps
+}
+
+#[test]
+fn test_tuned_backoff_without_jitter() {
+    let config = TimeoutConfig::new(Duration::from_millis(300))
+        .with_initial_interval(Duration::from_millis(5))
+        .with_multiplier(2.0)
+        .with_max_interval(Duration::from_millis(40))
+        .with_jitter(Duration::ZERO..Duration::ZERO);
+
+    let sleeps = observed_sleeps(config);
+    let expected: Vec<Duration> = [5, 10, 20, 40, 40]
+        .into_iter()
+        .map(Duration::from_millis)
+        .collect();
+    assert_eq!(sleeps[..5], expected[..]);
+    assert!(sleeps
+        .iter()
+        .all(|sleep| *sleep <= Duration::from_millis(40)));
+}
+
+#[test]
+fn test_jitter_stays_in_range() {
+    let config = TimeoutConfig::new(Duration::from_millis(200))
+        .with_initial_interval(Duration::from_millis(10))
+        .with_multiplier(1.0)
+        .with_jitter(Duration::from_millis(5)..Duration::from_millis(10));
+
+    let sleeps = observed_sleeps(config);
+    assert!(sleeps.len() > 3);
+    for sleep in sleeps {
+        assert!(sleep >= Duration::from_millis(15) && sleep < Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn test_multiplier_below_one_does_not_shrink() {
+    let config = TimeoutConfig::new(Duration::from_millis(100))
+        .with_initial_interval(Duration::from_millis(10))
+        .with_multiplier(0.1)
+        .with_jitter(Duration::ZERO..Duration::ZERO);
+
+    let sleeps = observed_sleeps(config);
+    assert!(sleeps
+        .iter()
+        .all(|sleep| *sleep == Duration::from_millis(10)));
+}
+
+#[test]
+fn test_zero_initial_interval_still_backs_off() {
+    let config = TimeoutConfig::new(Duration::from_millis(100))
+        .with_initial_interval(Duration::ZERO)
+        .with_multiplier(2.0)
+        .with_jitter(Duration::ZERO..Duration::ZERO);
+
+    let sleeps = observed_sleeps(config);
+    let expected: Vec<Duration> = [1, 2, 4, 8]
+        .into_iter()
+        .map(Duration::from_millis)
+        .collect();
+    assert_eq!(sleeps[..4], expected[..]);
+}
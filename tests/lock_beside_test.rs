@@ -0,0 +1,93 @@
+use assert_cmd::Command;
+use mutx::{FileLock, LockStrategy};
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn mutx(temp: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("mutx").unwrap();
+    cmd.env("XDG_CACHE_HOME", temp.path().join("cache"))
+        .env_remove("MUTX_LOCK_DIR");
+    cmd
+}
+
+#[test]
+fn test_lock_beside_locks_next_to_output() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+
+    mutx(&temp)
+        .arg(&output)
+        .arg("--lock-beside")
+        .write_stdin("data")
+        .assert()
+        .success();
+
+    assert_eq!(fs::read_to_string(&output).unwrap(), "data");
+    assert!(temp.path().join("out.txt.lock").exists());
+    assert!(!temp.path().join("cache").exists());
+}
+
+#[test]
+fn test_lock_beside_contends_with_sidecar_holder() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    let _held = FileLock::acquire(&temp.path().join("out.txt.lock"), LockStrategy::NoWait).unwrap();
+
+    mutx(&temp)
+        .arg(&output)
+        .args(["--lock-beside", "--no-wait"])
+        .write_stdin("data")
+        .assert()
+        .failure();
+    assert!(!output.exists());
+
+    mutx(&temp)
+        .args(["read", "--lock-beside", "--no-wait"])
+        .arg(&output)
+        .assert()
+        .failure();
+
+    // Without --lock-beside the cache lock is free
+    mutx(&temp)
+        .arg(&output)
+        .arg("--no-wait")
+        .write_stdin("data")
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_lock_path_lock_beside() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+
+    let out = mutx(&temp)
+        .args(["lock", "path", "--lock-beside"])
+        .arg(&output)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let expected = temp.path().canonicalize().unwrap().join("out.txt.lock");
+    assert_eq!(
+        String::from_utf8(out).unwrap().trim(),
+        expected.to_str().unwrap()
+    );
+}
+
+#[test]
+fn test_lock_beside_conflicts_with_lock_file() {
+    let temp = TempDir::new().unwrap();
+
+    mutx(&temp)
+        .arg(temp.path().join("out.txt"))
+        .arg("--lock-beside")
+        .arg("--lock-file")
+        .arg(temp.path().join("custom.lock"))
+        .write_stdin("data")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
@@ -0,0 +1,63 @@
+use assert_cmd::Command;
+use filetime::{set_file_mtime, FileTime};
+use mutx::{lock_cache_info, FileLock, LockStrategy};
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_cache_info_counts_held_orphaned_and_unmigrated() {
+    let temp = TempDir::new().unwrap();
+    let shard = temp.path().join("ab");
+    fs::create_dir(&shard).unwrap();
+
+    let held_path = shard.join("held.lock");
+    let _held = FileLock::acquire(&held_path, LockStrategy::NoWait).unwrap();
+    fs::write(shard.join("orphan.lock"), b"12345").unwrap();
+    let flat = temp.path().join("flat.lock");
+    fs::write(&flat, b"").unwrap();
+    set_file_mtime(&flat, FileTime::from_unix_time(1_000_000_000, 0)).unwrap();
+    fs::write(shard.join("notes.txt"), b"ignored").unwrap();
+
+    let info = lock_cache_info(temp.path()).unwrap();
+    assert_eq!(info.files, 3);
+    assert_eq!(info.held, 1);
+    assert_eq!(info.orphaned, 2);
+    assert_eq!(info.unmigrated, 1);
+    assert_eq!(info.unreadable, 0);
+    assert!(info.total_bytes >= 5);
+    assert_eq!(info.oldest.unwrap().0, flat);
+
+    // Inspecting never removes anything
+    assert!(shard.join("orphan.lock").exists());
+}
+
+#[test]
+fn test_cache_info_missing_directory_is_empty() {
+    let temp = TempDir::new().unwrap();
+    let info = lock_cache_info(&temp.path().join("missing")).unwrap();
+    assert_eq!(info.files, 0);
+    assert!(info.oldest.is_none());
+}
+
+#[test]
+fn test_cache_info_command() {
+    let temp = TempDir::new().unwrap();
+    fs::create_dir(temp.path().join("0f")).unwrap();
+    fs::write(temp.path().join("0f").join("a.lock"), b"").unwrap();
+
+    Command::cargo_bin("mutx")
+        .unwrap()
+        .args(["lock", "cache-info"])
+        .arg(temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Cache directory: {}",
+            temp.path().display()
+        )))
+        .stdout(predicate::str::contains("Scheme: v2\n"))
+        .stdout(predicate::str::contains("Lock files: 1"))
+        .stdout(predicate::str::contains("Held: 0"))
+        .stdout(predicate::str::contains("Orphaned: 1"));
+}
@@ -0,0 +1,74 @@
+use mutx::{CancelToken, FileLock, LockBackend, LockStrategy, MutxError};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+fn cancel_after(token: &CancelToken, delay: Duration) -> thread::JoinHandle<()> {
+    let token = token.clone();
+    thread::spawn(move || {
+        thread::sleep(delay);
+        token.cancel();
+    })
+}
+
+#[test]
+fn test_cancel_interrupts_wait() {
+    for backend in [LockBackend::Flock, LockBackend::Dotlock] {
+        let temp = TempDir::new().unwrap();
+        let lock_path = temp.path().join("out.lock");
+        let _held =
+            FileLock::acquire_with_backend(&lock_path, LockStrategy::NoWait, backend).unwrap();
+
+        let token = CancelToken::new();
+        let canceller = cancel_after(&token, Duration::from_millis(100));
+        let start = Instant::now();
+        let err = FileLock::acquire_cancellable(&lock_path, LockStrategy::Wait, backend, &token)
+            .unwrap_err();
+        canceller.join().unwrap();
+
+        assert!(matches!(err, MutxError::Interrupted), "{backend}: {err:?}");
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+}
+
+#[test]
+fn test_cancelled_token_fails_before_trying() {
+    let temp = TempDir::new().unwrap();
+    let token = CancelToken::new();
+    token.cancel();
+
+    let err = FileLock::acquire_cancellable(
+        &temp.path().join("out.lock"),
+        LockStrategy::NoWait,
+        LockBackend::Flock,
+        &token,
+    )
+    .unwrap_err();
+    assert!(matches!(err, MutxError::Interrupted));
+}
+
+#[test]
+fn test_uncancelled_token_acquires_once_released() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+    let held = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+    let release = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        drop(held);
+    });
+
+    let token = CancelToken::new();
+    FileLock::acquire_cancellable(&lock_path, LockStrategy::Wait, LockBackend::Flock, &token)
+        .unwrap();
+    release.join().unwrap();
+    assert!(!token.is_cancelled());
+}
+
+#[test]
+fn test_token_from_shared_flag() {
+    let flag = Arc::new(AtomicBool::new(true));
+    let token = CancelToken::from(flag);
+    assert!(token.is_cancelled());
+}
@@ -0,0 +1,136 @@
+#![cfg(unix)]
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::path::Path;
+use std::time::Duration;
+use tempfile::TempDir;
+
+const MUTX: &str = env!("CARGO_BIN_EXE_mutx");
+
+fn mutx() -> Command {
+    let mut cmd = Command::new(MUTX);
+    // A missed cycle would hang
+    cmd.timeout(Duration::from_secs(20));
+    cmd.env_remove("MUTX_HELD_LOCKS");
+    cmd
+}
+
+fn exec_args<'a>(file: &'a Path, extra: &[&'a str]) -> Vec<&'a str> {
+    let mut args = vec!["exec", "--lock-beside"];
+    args.extend_from_slice(extra);
+    args.extend([file.to_str().unwrap(), "--"]);
+    args
+}
+
+#[test]
+fn test_nested_exec_on_same_file_fails_fast() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("data.json");
+
+    let mut args = exec_args(&file, &[]);
+    args.push(MUTX);
+    args.extend(exec_args(&file, &[]));
+    args.push("true");
+    mutx()
+        .args(&args)
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("would deadlock"))
+        .stderr(predicate::str::contains(format!(
+            "for {} and waits for",
+            file.display()
+        )));
+}
+
+#[test]
+fn test_write_under_exec_fails_fast() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("data.json");
+
+    let mut args = exec_args(&file, &[]);
+    args.extend([MUTX, "--lock-beside", file.to_str().unwrap()]);
+    mutx()
+        .args(&args)
+        .write_stdin("{}")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("would deadlock"));
+    assert!(!file.exists());
+}
+
+#[test]
+fn test_cycle_report_names_every_holder() {
+    let temp = TempDir::new().unwrap();
+    let first = temp.path().join("first.json");
+    let second = temp.path().join("second.json");
+
+    let mut args = exec_args(&first, &[]);
+    args.push(MUTX);
+    args.extend(exec_args(&second, &[]));
+    args.push(MUTX);
+    args.extend(exec_args(&first, &[]));
+    args.push("true");
+    mutx()
+        .args(&args)
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(format!(
+            "for {} and waits for",
+            first.display()
+        )))
+        .stderr(predicate::str::contains(format!(
+            "for {} and waits for",
+            second.display()
+        )));
+}
+
+#[test]
+fn test_unrelated_and_shared_locks_nest() {
+    let temp = TempDir::new().unwrap();
+    let first = temp.path().join("first.json");
+    let second = temp.path().join("second.json");
+
+    let mut args = exec_args(&first, &[]);
+    args.push(MUTX);
+    args.extend(exec_args(&second, &[]));
+    args.push("true");
+    mutx().args(&args).assert().success();
+
+    let mut args = exec_args(&first, &["--shared"]);
+    args.push(MUTX);
+    args.extend(exec_args(&first, &["--shared"]));
+    args.push("true");
+    mutx().args(&args).assert().success();
+}
+
+#[test]
+fn test_entries_of_exited_holders_are_ignored() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("data.json");
+    let lock = temp.path().canonicalize().unwrap().join("data.json.lock");
+
+    let mut exited = std::process::Command::new("true").spawn().unwrap();
+    let pid = exited.id();
+    exited.wait().unwrap();
+    let held = serde_json::json!([{
+        "pid": pid,
+        "lock": lock,
+        "shared": false,
+        "target": file,
+        "command": "true",
+    }]);
+
+    mutx()
+        .args(exec_args(&file, &[]))
+        .arg("true")
+        .env("MUTX_HELD_LOCKS", held.to_string())
+        .assert()
+        .success();
+    mutx()
+        .args(exec_args(&file, &[]))
+        .arg("true")
+        .env("MUTX_HELD_LOCKS", "not json")
+        .assert()
+        .success();
+}
@@ -0,0 +1,173 @@
+#![cfg(all(feature = "cluster", unix))]
+
+use assert_cmd::Command;
+use mutx::lock::cluster::daemon::{DaemonClient, LockDaemon};
+use mutx::{FileLock, LockBackend, LockStrategy, MutxError, TimeoutConfig};
+use std::fs;
+use std::os::unix::net::UnixListener;
+use std::path::Path;
+use std::process::{Child, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+fn start_daemon(dir: &Path) -> DaemonClient {
+    let socket = dir.join("run").join("daemon.sock");
+    let daemon = LockDaemon::bind(&socket).unwrap();
+    thread::spawn(move || daemon.serve());
+    DaemonClient::new(socket)
+}
+
+/// `mutx daemon` run as `daemon`, once it listens on `socket`
+fn spawn_daemon(mut daemon: std::process::Command, socket: &Path) -> Child {
+    let child = daemon.stderr(Stdio::null()).spawn().unwrap();
+    let start = Instant::now();
+    while !socket.exists() {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "daemon never listened"
+        );
+        thread::sleep(Duration::from_millis(20));
+    }
+    child
+}
+
+fn mutx_daemon() -> std::process::Command {
+    let mut daemon = std::process::Command::new(env!("CARGO_BIN_EXE_mutx"));
+    daemon.arg("daemon");
+    daemon
+}
+
+#[test]
+fn test_daemon_excludes_second_client() {
+    let temp = TempDir::new().unwrap();
+    let client = start_daemon(temp.path());
+
+    let held = FileLock::acquire_remote(&client, "/srv/app.conf", LockStrategy::NoWait).unwrap();
+    assert_eq!(held.backend(), LockBackend::Remote);
+    let result = FileLock::acquire_remote(&client, "/srv/app.conf", LockStrategy::NoWait);
+    assert!(matches!(result, Err(MutxError::LockWouldBlock { .. })));
+    assert!(FileLock::acquire_remote(&client, "/srv/other.conf", LockStrategy::NoWait).is_ok());
+
+    drop(held);
+    assert!(FileLock::acquire_remote(&client, "/srv/app.conf", LockStrategy::NoWait).is_ok());
+}
+
+#[test]
+fn test_daemon_socket_dir_is_private() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = TempDir::new().unwrap();
+    start_daemon(temp.path());
+
+    let mode = fs::metadata(temp.path().join("run"))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o700);
+}
+
+#[test]
+fn test_waiter_is_handed_key_on_release() {
+    let temp = TempDir::new().unwrap();
+    let client = start_daemon(temp.path());
+    let held = FileLock::acquire_remote(&client, "key", LockStrategy::NoWait).unwrap();
+
+    let waiter = {
+        let client = client.clone();
+        thread::spawn(move || {
+            let start = Instant::now();
+            let lock = FileLock::acquire_remote(&client, "key", LockStrategy::Wait).unwrap();
+            (start.elapsed(), lock.stats().retries)
+        })
+    };
+    thread::sleep(Duration::from_millis(300));
+    drop(held);
+
+    let (waited, retries) = waiter.join().unwrap();
+    assert!(waited >= Duration::from_millis(300));
+    assert_eq!(retries, 0);
+}
+
+#[test]
+fn test_wait_times_out() {
+    let temp = TempDir::new().unwrap();
+    let client = start_daemon(temp.path());
+    let _held = FileLock::acquire_remote(&client, "key", LockStrategy::NoWait).unwrap();
+
+    let strategy = LockStrategy::Timeout(TimeoutConfig::new(Duration::from_millis(200)));
+    let start = Instant::now();
+    let result = FileLock::acquire_remote(&client, "key", strategy);
+    assert!(matches!(result, Err(MutxError::LockTimeout { .. })));
+    assert!(start.elapsed() >= Duration::from_millis(200));
+}
+
+#[test]
+fn test_stale_socket_is_replaced_but_live_one_is_not() {
+    let temp = TempDir::new().unwrap();
+    let socket = temp.path().join("daemon.sock");
+
+    // Left behind by a daemon that is gone
+    drop(UnixListener::bind(&socket).unwrap());
+    let daemon = LockDaemon::bind(&socket).unwrap();
+
+    assert!(LockDaemon::bind(&socket).is_err());
+    drop(daemon);
+    assert!(!socket.exists());
+}
+
+#[test]
+fn test_cli_writes_through_daemon() {
+    let temp = TempDir::new().unwrap();
+    let socket = temp.path().join("daemon.sock");
+    let mut command = mutx_daemon();
+    command.arg("--socket").arg(&socket);
+    let mut daemon = spawn_daemon(command, &socket);
+    let output = temp.path().join("output.txt");
+    let server = format!("unix:{}", socket.display());
+
+    let client = DaemonClient::new(&socket);
+    let held = FileLock::acquire_remote(&client, "shared-key", LockStrategy::NoWait).unwrap();
+    let write = |content: &str| {
+        Command::new(env!("CARGO_BIN_EXE_mutx"))
+            .arg(&output)
+            .args(["--lock-backend", "remote", "--lock-server", &server])
+            .args(["--lock-key", "shared-key", "--no-wait"])
+            .write_stdin(content.to_string())
+            .assert()
+    };
+
+    write("blocked").code(2);
+    assert!(!output.exists());
+    drop(held);
+    write("data").success();
+    assert_eq!(fs::read_to_string(&output).unwrap(), "data");
+
+    daemon.kill().unwrap();
+    daemon.wait().unwrap();
+}
+
+#[test]
+fn test_cli_daemon_default_socket() {
+    let temp = TempDir::new().unwrap();
+    let runtime = temp.path().join("runtime");
+    fs::create_dir(&runtime).unwrap();
+    let socket = runtime.join("mutx").join("daemon.sock");
+
+    let mut command = mutx_daemon();
+    command.env("XDG_RUNTIME_DIR", &runtime);
+    let mut daemon = spawn_daemon(command, &socket);
+
+    let output = temp.path().join("output.txt");
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--lock-backend", "remote", "--lock-server", "unix:"])
+        .env("XDG_RUNTIME_DIR", &runtime)
+        .write_stdin("data")
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&output).unwrap(), "data");
+
+    daemon.kill().unwrap();
+    daemon.wait().unwrap();
+}
@@ -0,0 +1,75 @@
+use mutx::{FileLock, LockSet, LockStrategy, MutxError};
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+#[test]
+fn test_deadline_times_out_on_held_lock() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+    let _held = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+
+    let started = Instant::now();
+    let deadline = started + Duration::from_millis(200);
+    let result = FileLock::acquire(&lock_path, LockStrategy::Deadline(deadline));
+    assert!(matches!(result, Err(MutxError::LockTimeout { .. })));
+    assert!(Instant::now() >= deadline);
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
+
+#[test]
+fn test_past_deadline_still_tries_once() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+
+    let deadline = Instant::now();
+    thread::sleep(Duration::from_millis(10));
+    assert!(FileLock::acquire(&lock_path, LockStrategy::Deadline(deadline)).is_ok());
+}
+
+#[test]
+fn test_deadline_is_shared_across_acquisitions() {
+    let temp = TempDir::new().unwrap();
+    let free = temp.path().join("free.lock");
+    let held = temp.path().join("held.lock");
+    let _held = FileLock::acquire(&held, LockStrategy::NoWait).unwrap();
+
+    let deadline = Instant::now() + Duration::from_millis(400);
+    let _first = FileLock::acquire(&free, LockStrategy::Deadline(deadline)).unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    // Only what is left of the budget is waited, not another 400ms
+    let waited = Instant::now();
+    let result = FileLock::acquire(&held, LockStrategy::Deadline(deadline));
+    assert!(matches!(result, Err(MutxError::LockTimeout { .. })));
+    assert!(waited.elapsed() < Duration::from_millis(350));
+}
+
+#[test]
+fn test_lock_released_before_deadline_is_acquired() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+    let held = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+    let release = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        drop(held);
+    });
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let lock = FileLock::acquire(&lock_path, LockStrategy::Deadline(deadline).shared());
+    release.join().unwrap();
+    assert!(lock.unwrap().is_shared());
+}
+
+#[test]
+fn test_lock_set_with_deadline() {
+    let temp = TempDir::new().unwrap();
+    let paths = [temp.path().join("a.lock"), temp.path().join("b.lock")];
+    let _held = FileLock::acquire(&paths[1], LockStrategy::NoWait).unwrap();
+
+    let deadline = Instant::now() + Duration::from_millis(150);
+    let result = LockSet::acquire(&paths, LockStrategy::Deadline(deadline));
+    assert!(matches!(result, Err(MutxError::LockTimeout { .. })));
+    // Released with the failed set
+    assert!(FileLock::acquire(&paths[0], LockStrategy::NoWait).is_ok());
+}
@@ -0,0 +1,108 @@
+use assert_cmd::Command;
+use mutx::dirs::LOCK_DIR_ENV;
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// mutx reading `config` (which need not exist) and no MUTX_LOCK_DIR
+fn mutx_with_config(config: &Path) -> Command {
+    let mut cmd = Command::cargo_bin("mutx").unwrap();
+    cmd.env("MUTX_CONFIG", config).env_remove(LOCK_DIR_ENV);
+    cmd
+}
+
+fn lock_path(cmd: &mut Command, output: &Path) -> String {
+    let out = cmd
+        .args(["lock", "path"])
+        .arg(output)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    String::from_utf8(out).unwrap().trim().to_string()
+}
+
+#[test]
+fn test_config_lock_dir_moves_lock_cache() {
+    let temp = TempDir::new().unwrap();
+    let locks = temp.path().join("run-lock");
+    let config = temp.path().join("config.toml");
+    fs::write(
+        &config,
+        format!("lock_dir = {:?}\n", locks.to_str().unwrap()),
+    )
+    .unwrap();
+
+    let output = temp.path().join("out.txt");
+    let path = lock_path(&mut mutx_with_config(&config), &output);
+    assert!(path.starts_with(locks.to_str().unwrap()), "{}", path);
+
+    mutx_with_config(&config)
+        .arg(&output)
+        .write_stdin("data")
+        .assert()
+        .success();
+    assert!(Path::new(&path).exists());
+}
+
+#[test]
+fn test_env_lock_dir_beats_config() {
+    let temp = TempDir::new().unwrap();
+    let from_config = temp.path().join("from-config");
+    let from_env = temp.path().join("from-env");
+    let config = temp.path().join("config.toml");
+    fs::write(
+        &config,
+        format!("lock_dir = {:?}\n", from_config.to_str().unwrap()),
+    )
+    .unwrap();
+
+    let mut cmd = mutx_with_config(&config);
+    cmd.env(LOCK_DIR_ENV, &from_env);
+    let path = lock_path(&mut cmd, &temp.path().join("out.txt"));
+    assert!(path.starts_with(from_env.to_str().unwrap()), "{}", path);
+}
+
+#[test]
+fn test_relative_env_lock_dir_is_rejected() {
+    let temp = TempDir::new().unwrap();
+    let config = temp.path().join("config.toml");
+
+    mutx_with_config(&config)
+        .env(LOCK_DIR_ENV, "locks")
+        .current_dir(temp.path())
+        .args(["lock", "path"])
+        .arg(temp.path().join("out.txt"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("MUTX_LOCK_DIR must be absolute"));
+    assert!(!temp.path().join("locks").exists());
+}
+
+#[test]
+fn test_library_ignores_config_lock_dir() {
+    let temp = TempDir::new().unwrap();
+    let config = temp.path().join("config.toml");
+    fs::write(&config, "lock_dir = [not toml\n").unwrap();
+    std::env::set_var("MUTX_CONFIG", &config);
+    std::env::remove_var(LOCK_DIR_ENV);
+
+    // A broken configuration file is the CLI's concern alone
+    assert!(mutx::derive_lock_path(&temp.path().join("out.txt"), false).is_ok());
+}
+
+#[test]
+fn test_relative_config_lock_dir_is_rejected() {
+    let temp = TempDir::new().unwrap();
+    let config = temp.path().join("config.toml");
+    fs::write(&config, "lock_dir = \"locks\"\n").unwrap();
+
+    mutx_with_config(&config)
+        .args(["lock", "path"])
+        .arg(temp.path().join("out.txt"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("lock_dir must be absolute"));
+}
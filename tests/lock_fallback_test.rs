@@ -0,0 +1,136 @@
+use assert_cmd::Command;
+use mutx::dirs::LOCK_DIR_ENV;
+use mutx::lock::{LockFallback, LOCK_FALLBACK_ENV};
+use predicates::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// mutx with a cache directory that cannot be created (its parent is a file)
+/// and the system temp directory inside `temp`
+fn mutx_without_cache(temp: &TempDir) -> Command {
+    let blocker = temp.path().join("not-a-dir");
+    fs::write(&blocker, b"").unwrap();
+    let tmp = temp.path().join("tmp");
+    fs::create_dir_all(&tmp).unwrap();
+
+    let mut cmd = Command::cargo_bin("mutx").unwrap();
+    cmd.env("XDG_CACHE_HOME", &blocker)
+        .env("TMPDIR", &tmp)
+        .env_remove(LOCK_DIR_ENV)
+        .env_remove(LOCK_FALLBACK_ENV);
+    cmd
+}
+
+#[cfg(unix)]
+fn user_temp(temp: &TempDir) -> PathBuf {
+    let uid = unsafe { libc::geteuid() };
+    temp.path().join("tmp").join(format!("mutx-{}", uid))
+}
+
+fn lock_path(cmd: &mut Command, output: &Path) -> String {
+    let out = cmd
+        .args(["lock", "path"])
+        .arg(output)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    String::from_utf8(out).unwrap().trim().to_string()
+}
+
+#[test]
+fn test_parse_lock_fallback() {
+    assert_eq!("tmp".parse::<LockFallback>().unwrap(), LockFallback::Temp);
+    assert_eq!(
+        "adjacent".parse::<LockFallback>().unwrap(),
+        LockFallback::Adjacent
+    );
+    assert_eq!("none".parse::<LockFallback>().unwrap(), LockFallback::None);
+    assert!("home".parse::<LockFallback>().is_err());
+    assert_eq!(LockFallback::default().to_string(), "tmp");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_default_falls_back_to_private_temp_dir() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    let private = user_temp(&temp);
+
+    let lock = lock_path(&mut mutx_without_cache(&temp), &output);
+    assert!(Path::new(&lock).starts_with(private.join("locks")));
+
+    mutx_without_cache(&temp)
+        .arg(&output)
+        .write_stdin("data")
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&output).unwrap(), "data");
+    let mode = fs::metadata(&private).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o700);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_shared_temp_dir_is_not_trusted() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    fs::create_dir_all(user_temp(&temp)).unwrap();
+    fs::set_permissions(user_temp(&temp), fs::Permissions::from_mode(0o777)).unwrap();
+
+    let lock = lock_path(&mut mutx_without_cache(&temp), &output);
+    assert_eq!(PathBuf::from(lock), temp.path().join("out.txt.lock"));
+}
+
+#[test]
+fn test_adjacent_fallback_locks_beside_output() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+
+    let mut cmd = mutx_without_cache(&temp);
+    cmd.args(["--lock-fallback", "adjacent"]);
+    assert_eq!(
+        PathBuf::from(lock_path(&mut cmd, &output)),
+        temp.path().join("out.txt.lock")
+    );
+
+    mutx_without_cache(&temp)
+        .env(LOCK_FALLBACK_ENV, "adjacent")
+        .arg(&output)
+        .write_stdin("data")
+        .assert()
+        .success();
+    assert!(temp.path().join("out.txt.lock").exists());
+}
+
+#[test]
+fn test_no_fallback_fails() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+
+    mutx_without_cache(&temp)
+        .args(["--lock-fallback", "none"])
+        .arg(&output)
+        .write_stdin("data")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not-a-dir"));
+    assert!(!output.exists());
+}
+
+#[test]
+fn test_lock_dir_override_comes_first() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    let locks = temp.path().join("locks");
+
+    let mut cmd = mutx_without_cache(&temp);
+    cmd.env(LOCK_DIR_ENV, &locks);
+    assert!(Path::new(&lock_path(&mut cmd, &output)).starts_with(&locks));
+}
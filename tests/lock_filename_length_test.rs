@@ -0,0 +1,61 @@
+use mutx::lock::{derive_lock_path, MAX_LOCK_FILENAME_BYTES};
+use mutx::{FileLock, LockStrategy};
+use std::fs;
+use tempfile::TempDir;
+
+fn lock_name_len(lock_path: &std::path::Path) -> usize {
+    lock_path.file_name().unwrap().len()
+}
+
+#[test]
+fn test_lock_name_for_255_byte_filename_is_clamped() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("x".repeat(255));
+
+    let lock_path = derive_lock_path(&output, false).unwrap();
+
+    assert!(lock_name_len(&lock_path) <= MAX_LOCK_FILENAME_BYTES);
+    assert!(lock_path.to_str().unwrap().ends_with(".lock"));
+    // Lock must be creatable on disk
+    let _lock = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+}
+
+#[test]
+fn test_clamped_names_stay_unique() {
+    let temp = TempDir::new().unwrap();
+    let base = "y".repeat(250);
+    let output1 = temp.path().join(format!("{}1", base));
+    let output2 = temp.path().join(format!("{}2", base));
+
+    let lock1 = derive_lock_path(&output1, false).unwrap();
+    let lock2 = derive_lock_path(&output2, false).unwrap();
+
+    assert_ne!(lock1, lock2);
+}
+
+#[test]
+fn test_lock_name_for_deep_path_with_long_components() {
+    let temp = TempDir::new().unwrap();
+    let mut dir = temp.path().to_path_buf();
+    for i in 0..6 {
+        dir = dir.join(format!("{}{}", "d".repeat(100), i));
+    }
+    fs::create_dir_all(&dir).unwrap();
+    let output = dir.join(format!("{}.txt", "f".repeat(200)));
+
+    let lock_path = derive_lock_path(&output, false).unwrap();
+
+    assert!(lock_name_len(&lock_path) <= MAX_LOCK_FILENAME_BYTES);
+}
+
+#[test]
+fn test_multibyte_filename_clamped_on_char_boundary() {
+    let temp = TempDir::new().unwrap();
+    // 84 three-byte characters = 252 bytes
+    let output = temp.path().join("\u{65e5}".repeat(84));
+
+    let lock_path = derive_lock_path(&output, false).unwrap();
+
+    assert!(lock_path.file_name().unwrap().to_str().is_some());
+    assert!(lock_name_len(&lock_path) <= MAX_LOCK_FILENAME_BYTES);
+}
@@ -0,0 +1,77 @@
+use assert_cmd::Command;
+use mutx::lock::{derive_lock_path_with_scheme, LockScheme, FULL_HASH_LEN};
+use tempfile::TempDir;
+
+fn hash_of(lock_path: &std::path::Path) -> String {
+    let name = lock_path.file_name().unwrap().to_str().unwrap();
+    let without_lock = name.strip_suffix(".lock").unwrap();
+    without_lock.rsplit('.').next().unwrap().to_string()
+}
+
+#[test]
+fn test_default_scheme_uses_eight_char_hash() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("output.txt");
+
+    let lock_path = derive_lock_path_with_scheme(&output, &LockScheme::default()).unwrap();
+
+    assert_eq!(hash_of(&lock_path).len(), 8);
+    assert_eq!(lock_path, mutx::derive_lock_path(&output, false).unwrap());
+}
+
+#[test]
+fn test_scheme_hash_len_is_respected() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("output.txt");
+
+    let short = derive_lock_path_with_scheme(&output, &LockScheme::default()).unwrap();
+    let long = derive_lock_path_with_scheme(&output, &LockScheme::new().with_hash_len(16)).unwrap();
+    let full = derive_lock_path_with_scheme(&output, &LockScheme::new().with_full_hash()).unwrap();
+
+    assert_eq!(hash_of(&long).len(), 16);
+    assert_eq!(hash_of(&full).len(), FULL_HASH_LEN);
+    // Longer hashes extend the same digest
+    assert!(hash_of(&full).starts_with(&hash_of(&long)));
+    assert!(hash_of(&long).starts_with(&hash_of(&short)));
+}
+
+#[test]
+fn test_invalid_hash_len_rejected() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("output.txt");
+
+    assert!(derive_lock_path_with_scheme(&output, &LockScheme::new().with_hash_len(2)).is_err());
+    assert!(derive_lock_path_with_scheme(&output, &LockScheme::new().with_hash_len(65)).is_err());
+}
+
+#[test]
+fn test_cli_lock_hash_len_flag() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("output.txt");
+    let expected =
+        derive_lock_path_with_scheme(&output, &LockScheme::new().with_hash_len(16)).unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
+    cmd.arg(&output)
+        .arg("--lock-hash-len")
+        .arg("16")
+        .arg("-v")
+        .write_stdin("data")
+        .assert()
+        .success()
+        .stderr(predicates::str::contains(expected.to_str().unwrap()));
+}
+
+#[test]
+fn test_cli_rejects_invalid_lock_hash_len() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("output.txt");
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
+    cmd.arg(&output)
+        .arg("--lock-hash-len")
+        .arg("3")
+        .write_stdin("data")
+        .assert()
+        .failure();
+}
@@ -0,0 +1,169 @@
+use assert_cmd::Command;
+#[cfg(unix)]
+use mutx::LockHolder;
+use mutx::{FileLock, LockStrategy};
+use predicates::prelude::*;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn mutx() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+}
+
+fn write(file: &Path, content: &str) -> assert_cmd::assert::Assert {
+    mutx()
+        .arg(file)
+        .args(["--lock-beside", "--no-wait"])
+        .write_stdin(content.to_string())
+        .assert()
+}
+
+#[cfg(unix)]
+#[test]
+fn test_lock_holds_until_unlock() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("config.json");
+
+    let output = mutx()
+        .args(["lock", "--background", "--lock-beside"])
+        .arg(&file)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let pid: u32 = String::from_utf8(output.stdout)
+        .unwrap()
+        .trim()
+        .parse()
+        .unwrap();
+    assert_ne!(pid, std::process::id());
+
+    // Writers are kept out, and the holder names itself
+    write(&file, "blocked").code(2);
+    mutx()
+        .args(["lock", "holder", "--lock-beside"])
+        .arg(&file)
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(format!("PID {} ", pid)));
+
+    mutx()
+        .args(["unlock", "--lock-beside"])
+        .arg(&file)
+        .assert()
+        .success();
+    write(&file, "written").success();
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "written");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_background_lock_reports_failure() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("config.json");
+    let _held =
+        FileLock::acquire(&temp.path().join("config.json.lock"), LockStrategy::NoWait).unwrap();
+
+    mutx()
+        .args(["lock", "--background", "--lock-beside", "--no-wait"])
+        .arg(&file)
+        .assert()
+        .code(2)
+        .stdout("");
+}
+
+#[test]
+fn test_unlock_refuses_other_holders() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("config.json");
+    let lock_path = temp.path().join("config.json.lock");
+    let _held = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+
+    mutx()
+        .args(["unlock", "--lock-beside"])
+        .arg(&file)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not held by `mutx lock`"));
+    assert!(FileLock::status(&lock_path).unwrap().is_held());
+}
+
+/// A `mutx lock` payload naming `pid`, as a holder that has gone leaves it
+#[cfg(unix)]
+fn hold_payload(pid: u32, started_at: Option<u64>) -> Vec<u8> {
+    let holder = LockHolder {
+        pid,
+        command: Some("mutx lock".to_string()),
+        started_at,
+        ..LockHolder::current()
+    };
+    serde_json::to_vec(&holder).unwrap()
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_unlock_never_signals_a_reused_pid() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("config.json");
+    let lock_path = temp.path().join("config.json.lock");
+    let mut bystander = std::process::Command::new("sleep")
+        .arg("30")
+        .spawn()
+        .unwrap();
+    let _held = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+    // The PID now names a process started after the recorded one
+    std::fs::write(&lock_path, hold_payload(bystander.id(), Some(0))).unwrap();
+
+    mutx()
+        .args(["unlock", "--lock-beside"])
+        .arg(&file)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no longer running"));
+    assert!(bystander.try_wait().unwrap().is_none());
+    bystander.kill().unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_unlock_never_signals_for_a_shared_lock() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("config.json");
+    let lock_path = temp.path().join("config.json.lock");
+    let mut bystander = std::process::Command::new("sleep")
+        .arg("30")
+        .spawn()
+        .unwrap();
+    std::fs::write(&lock_path, hold_payload(bystander.id(), None)).unwrap();
+    let _reader = FileLock::acquire(&lock_path, LockStrategy::NoWait.shared()).unwrap();
+
+    mutx()
+        .args(["unlock", "--lock-beside"])
+        .arg(&file)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("held shared"));
+    assert!(bystander.try_wait().unwrap().is_none());
+    bystander.kill().unwrap();
+}
+
+#[test]
+fn test_unlock_of_free_lock_succeeds() {
+    let temp = TempDir::new().unwrap();
+
+    mutx()
+        .args(["unlock", "--lock-beside"])
+        .arg(temp.path().join("config.json"))
+        .assert()
+        .success()
+        .stderr(predicate::str::starts_with("Not locked"));
+}
+
+#[test]
+fn test_lock_subcommands_still_parse() {
+    mutx()
+        .args(["lock", "path", "--lock-beside", "/srv/config.json"])
+        .assert()
+        .success()
+        .stdout("/srv/config.json.lock\n");
+    mutx().arg("lock").assert().failure();
+}
@@ -0,0 +1,114 @@
+use assert_cmd::Command;
+use mutx::{FileLock, LockHolder, LockStrategy, MutxError, TimeoutConfig};
+use predicates::prelude::*;
+use std::fs;
+use std::time::Duration;
+use tempfile::TempDir;
+
+#[cfg(unix)]
+#[test]
+fn test_holder_recorded_while_held_and_cleared_on_release() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+    let target = temp.path().join("out.txt");
+
+    let mut lock = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+    lock.record_target(&target);
+
+    let holder = LockHolder::read(&lock_path).unwrap().unwrap();
+    assert_eq!(holder.pid, std::process::id());
+    assert_eq!(holder.target.as_deref(), Some(target.as_path()));
+    assert_eq!(lock.holder(), Some(&holder));
+
+    drop(lock);
+    assert_eq!(LockHolder::read(&lock_path).unwrap(), None);
+    assert_eq!(fs::read(&lock_path).unwrap(), b"");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_failed_acquire_keeps_holder_payload() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+
+    let _held = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+    let before = fs::read(&lock_path).unwrap();
+
+    let err = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap_err();
+    assert!(matches!(err, MutxError::LockWouldBlock { .. }));
+    assert_eq!(fs::read(&lock_path).unwrap(), before);
+}
+
+#[test]
+fn test_shared_locks_record_no_holder() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+
+    let lock = FileLock::acquire(&lock_path, LockStrategy::NoWait.shared()).unwrap();
+    assert!(lock.holder().is_none());
+    assert_eq!(LockHolder::read(&lock_path).unwrap(), None);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_lock_holder_command() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+    let target = temp.path().join("out.txt");
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["lock", "holder"])
+        .arg(&target)
+        .arg("--lock-file")
+        .arg(&lock_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No holder recorded"));
+
+    let mut lock = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+    lock.record_target(&target);
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["lock", "holder"])
+        .arg(&target)
+        .arg("--lock-file")
+        .arg(&lock_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "PID {} on ",
+            std::process::id()
+        )))
+        .stdout(predicate::str::contains(format!(
+            "writing {}",
+            target.display()
+        )));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_contention_error_names_holder() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+    let _held = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+
+    match FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap_err() {
+        MutxError::LockWouldBlock { holder, .. } => {
+            assert_eq!(holder.unwrap().pid, std::process::id())
+        }
+        other => panic!("unexpected error: {}", other),
+    }
+
+    let timeout = LockStrategy::Timeout(TimeoutConfig::new(Duration::from_millis(50)));
+    let err = FileLock::acquire(&lock_path, timeout).unwrap_err();
+    assert!(matches!(
+        err,
+        MutxError::LockTimeout {
+            holder: Some(_),
+            ..
+        }
+    ));
+    assert!(err
+        .to_string()
+        .contains(&format!("(held by PID {} on ", std::process::id())));
+}
@@ -0,0 +1,128 @@
+#![cfg(unix)]
+
+use assert_cmd::Command;
+use filetime::{set_file_mtime, FileTime};
+use mutx::lock::MIN_LEASE;
+use mutx::utils::process::hostname;
+use mutx::{FileLock, LockHolder, LockStrategy, MutxError};
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use std::thread::sleep;
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+
+const LEASE: Duration = Duration::from_millis(300);
+
+fn age(lock_path: &Path, by: Duration) {
+    let mtime = SystemTime::now() - by;
+    set_file_mtime(lock_path, FileTime::from_system_time(mtime)).unwrap();
+}
+
+/// Hold the lock while it names a live holder whose lease was last
+/// refreshed an hour ago, as a hung holder on a network mount would
+fn hold_expired(lock_path: &Path) -> FileLock {
+    let lock = FileLock::acquire(lock_path, LockStrategy::NoWait).unwrap();
+    let holder = LockHolder {
+        pid: std::process::id(),
+        hostname: hostname().unwrap(),
+        acquired_at: 0,
+        target: None,
+        command: None,
+        lease_ms: Some(1000),
+        fingerprint: None,
+        started_at: None,
+    };
+    fs::write(lock_path, serde_json::to_vec(&holder).unwrap()).unwrap();
+    age(lock_path, Duration::from_secs(3600));
+    lock
+}
+
+#[test]
+fn test_lease_is_recorded_and_refreshed() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+
+    let lock = FileLock::acquire(&lock_path, LockStrategy::NoWait.lease(LEASE)).unwrap();
+    let holder = LockHolder::read(&lock_path).unwrap().unwrap();
+    assert_eq!(holder.lease(), Some(LEASE));
+    assert_eq!(lock.holder(), Some(&holder));
+
+    age(&lock_path, Duration::from_secs(3600));
+    assert!(holder.lease_expired(&lock_path));
+    sleep(LEASE);
+    assert!(!holder.lease_expired(&lock_path));
+
+    drop(lock);
+    assert_eq!(LockHolder::read(&lock_path).unwrap(), None);
+}
+
+#[test]
+fn test_expired_lease_is_broken() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+    let _hung = hold_expired(&lock_path);
+
+    let err = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap_err();
+    assert!(matches!(err, MutxError::LockWouldBlock { .. }));
+
+    let lock = FileLock::acquire(&lock_path, LockStrategy::NoWait.lease(LEASE)).unwrap();
+    assert_eq!(lock.holder().unwrap().lease(), Some(LEASE));
+    assert!(!lock.holder().unwrap().lease_expired(&lock_path));
+}
+
+#[test]
+fn test_refreshed_lease_is_not_broken() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+    let _held = FileLock::acquire(&lock_path, LockStrategy::NoWait.lease(LEASE)).unwrap();
+
+    sleep(LEASE * 2);
+    let err = FileLock::acquire(&lock_path, LockStrategy::NoWait.break_stale()).unwrap_err();
+    assert!(matches!(err, MutxError::LockWouldBlock { .. }));
+}
+
+#[test]
+fn test_zero_lease_is_rejected() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+
+    for ttl in [
+        Duration::ZERO,
+        Duration::from_micros(1),
+        Duration::from_millis(9),
+    ] {
+        let strategy = LockStrategy::NoWait.lease(ttl);
+        assert!(FileLock::acquire(&lock_path, strategy).is_err());
+    }
+    assert!(FileLock::acquire(&lock_path, LockStrategy::NoWait.lease(MIN_LEASE)).is_ok());
+}
+
+#[test]
+fn test_cli_lock_lease() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    let lock_path = temp.path().join("out.lock");
+    let hung = hold_expired(&lock_path);
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["lock", "holder"])
+        .arg(&output)
+        .arg("--lock-file")
+        .arg(&lock_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(lease expired)"));
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .arg("--lock-file")
+        .arg(&lock_path)
+        .args(["--no-wait", "--lock-lease", "30s"])
+        .write_stdin("new")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("lease expired"));
+    assert_eq!(fs::read_to_string(&output).unwrap(), "new");
+    drop(hung);
+}
@@ -0,0 +1,107 @@
+use mutx::{CancelToken, FileLock, HoldOverrun, LockStrategy};
+use std::sync::mpsc;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+#[test]
+fn test_overrun_calls_handler_once() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+    let mut lock = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+
+    let (sender, overruns) = mpsc::channel();
+    let started = Instant::now();
+    lock.set_max_hold_with(Duration::from_millis(100), move |overrun| {
+        sender.send(overrun.clone()).unwrap();
+    });
+
+    let overrun = overruns.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(90));
+    assert_eq!(
+        overrun,
+        HoldOverrun {
+            lock_path: lock_path.clone(),
+            max_hold: Duration::from_millis(100),
+        }
+    );
+    assert!(overruns.recv_timeout(Duration::from_millis(300)).is_err());
+
+    // The lock is only reported, not released
+    assert!(FileLock::acquire(&lock_path, LockStrategy::NoWait).is_err());
+}
+
+#[test]
+fn test_release_before_max_hold_is_quiet() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+    let mut lock = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+
+    let (sender, overruns) = mpsc::channel::<()>();
+    lock.set_max_hold_with(Duration::from_millis(200), move |_| {
+        sender.send(()).unwrap();
+    });
+    drop(lock);
+
+    // The handler was dropped with the watch, unsent
+    assert_eq!(
+        overruns.recv_timeout(Duration::from_millis(500)),
+        Err(mpsc::RecvTimeoutError::Disconnected)
+    );
+}
+
+#[test]
+fn test_max_hold_counts_from_acquisition() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+    let mut lock = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+    sleep(Duration::from_millis(100));
+    assert!(lock.held_for() >= Duration::from_millis(100));
+
+    // Already exceeded: reported straight away
+    let (sender, overruns) = mpsc::channel();
+    lock.set_max_hold_with(Duration::from_millis(50), move |overrun| {
+        sender.send(overrun.max_hold).unwrap();
+    });
+    assert_eq!(
+        overruns.recv_timeout(Duration::from_millis(500)),
+        Ok(Duration::from_millis(50))
+    );
+}
+
+#[test]
+fn test_handler_can_cancel_the_holder() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+    let mut lock = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+
+    let token = CancelToken::new();
+    let cancel = token.clone();
+    lock.set_max_hold_with(Duration::from_millis(50), move |_| cancel.cancel());
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !token.is_cancelled() {
+        assert!(Instant::now() < deadline, "holder was never cancelled");
+        sleep(Duration::from_millis(10));
+    }
+    drop(lock);
+    assert!(FileLock::acquire(&lock_path, LockStrategy::NoWait).is_ok());
+}
+
+#[test]
+fn test_replacing_max_hold_stops_the_earlier_one() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+    let mut lock = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+
+    let (sender, overruns) = mpsc::channel();
+    let first = sender.clone();
+    lock.set_max_hold_with(Duration::from_millis(100), move |_| {
+        first.send("first").unwrap();
+    });
+    lock.set_max_hold_with(Duration::from_secs(60), move |_| {
+        sender.send("second").unwrap();
+    });
+
+    assert!(overruns.recv_timeout(Duration::from_millis(300)).is_err());
+}
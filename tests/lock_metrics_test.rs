@@ -0,0 +1,86 @@
+use assert_cmd::Command;
+use mutx::{FileLock, LockBackend, LockStrategy, TimeoutConfig};
+use predicates::prelude::*;
+use std::process::Stdio;
+use std::thread;
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn timeout(ms: u64) -> LockStrategy {
+    LockStrategy::Timeout(TimeoutConfig::new(Duration::from_millis(ms)))
+}
+
+#[test]
+fn test_uncontended_lock_has_no_retries() {
+    let temp = TempDir::new().unwrap();
+    let lock = FileLock::acquire(&temp.path().join("out.lock"), timeout(1000)).unwrap();
+
+    let stats = lock.stats();
+    assert_eq!(stats.retries, 0);
+    assert_eq!(stats.last_interval, None);
+}
+
+#[test]
+fn test_retries_are_observed_and_counted() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+    let held = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+    let release = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(300));
+        drop(held);
+    });
+
+    let mut observed = Vec::new();
+    let lock = FileLock::acquire_observed(&lock_path, timeout(5000), LockBackend::Flock, |retry| {
+        observed.push(*retry)
+    })
+    .unwrap();
+    release.join().unwrap();
+
+    let stats = lock.stats();
+    assert!(stats.retries > 0);
+    assert_eq!(stats.retries as usize, observed.len());
+    assert_eq!(stats.last_interval, observed.last().map(|r| r.sleep));
+    assert!(stats.elapsed >= Duration::from_millis(300));
+    assert!(observed
+        .iter()
+        .enumerate()
+        .all(|(i, r)| r.attempt == i as u32 + 1));
+}
+
+#[test]
+fn test_cli_reports_lock_metrics() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    let lock_path = temp.path().join("out.lock");
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .arg("--lock-file")
+        .arg(&lock_path)
+        .arg("-vv")
+        .write_stdin("one")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("without retrying"))
+        .stderr(predicate::str::contains("Lock busy").not());
+
+    let held = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+    let child = std::process::Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .arg("--lock-file")
+        .arg(&lock_path)
+        .args(["-vvv", "--timeout", "5000"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    thread::sleep(Duration::from_millis(300));
+    drop(held);
+
+    let out = child.wait_with_output().unwrap();
+    assert!(out.status.success());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("Lock busy: "), "{}", stderr);
+    assert!(stderr.contains("retries (last backoff "), "{}", stderr);
+}
@@ -0,0 +1,101 @@
+#![cfg(unix)]
+
+use assert_cmd::Command;
+use mutx::{FileLock, FileMode, LockStrategy};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn mutx(temp: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("mutx").unwrap();
+    cmd.env("XDG_CACHE_HOME", temp.path().join("cache"))
+        .env("MUTX_CONFIG", temp.path().join("config.toml"))
+        .env_remove("MUTX_LOCK_DIR");
+    cmd
+}
+
+fn lock_path(temp: &TempDir, output: &Path) -> PathBuf {
+    let out = mutx(temp)
+        .args(["lock", "path"])
+        .arg(output)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    PathBuf::from(String::from_utf8(out).unwrap().trim())
+}
+
+fn mode(path: &Path) -> u32 {
+    fs::metadata(path).unwrap().permissions().mode() & 0o7777
+}
+
+#[test]
+fn test_lock_mode_flag() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+
+    mutx(&temp)
+        .arg(&output)
+        .args(["--lock-mode", "0666"])
+        .write_stdin("data")
+        .assert()
+        .success();
+    assert_eq!(mode(&lock_path(&temp, &output)), 0o666);
+
+    mutx(&temp)
+        .arg(&output)
+        .args(["--lock-mode", "0600"])
+        .write_stdin("data")
+        .assert()
+        .success();
+    assert_eq!(mode(&lock_path(&temp, &output)), 0o600);
+}
+
+#[test]
+fn test_lock_mode_from_config() {
+    let temp = TempDir::new().unwrap();
+    fs::write(temp.path().join("config.toml"), "lock_mode = \"0660\"\n").unwrap();
+    let output = temp.path().join("out.txt");
+    fs::write(&output, "data").unwrap();
+
+    mutx(&temp).arg("read").arg(&output).assert().success();
+    assert_eq!(mode(&lock_path(&temp, &output)), 0o660);
+}
+
+#[test]
+fn test_lock_mode_sidecar() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+
+    mutx(&temp)
+        .arg(&output)
+        .args(["--lock-beside", "--lock-mode", "0666"])
+        .write_stdin("data")
+        .assert()
+        .success();
+    assert_eq!(mode(&temp.path().join("out.txt.lock")), 0o666);
+}
+
+#[test]
+fn test_invalid_lock_mode_rejected() {
+    let temp = TempDir::new().unwrap();
+
+    mutx(&temp)
+        .arg(temp.path().join("out.txt"))
+        .args(["--lock-mode", "rw-rw-rw-"])
+        .write_stdin("data")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_set_mode_on_held_lock() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+    let lock = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+
+    lock.set_mode(FileMode::new(0o666).unwrap()).unwrap();
+    assert_eq!(mode(&lock_path), 0o666);
+}
@@ -0,0 +1,77 @@
+use mutx::lock::{canonical_output_path, CacheDir, FlatHash, LockPathStrategy, Sidecar};
+use mutx::{
+    derive_lock_path, AtomicWriter, AtomicWriterPool, FileLock, LockStrategy, MutxError, WriteMode,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+#[test]
+fn test_cache_dir_matches_derive_lock_path() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    let canonical = canonical_output_path(&output).unwrap();
+
+    assert_eq!(
+        CacheDir::new().lock_path(&canonical).unwrap(),
+        derive_lock_path(&output, false).unwrap()
+    );
+}
+
+#[test]
+fn test_acquire_for_uses_strategy() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+
+    let held = FileLock::acquire_for(&output, &Sidecar, LockStrategy::NoWait).unwrap();
+    assert_eq!(
+        held.path(),
+        canonical_output_path(&output)
+            .unwrap()
+            .with_file_name("out.txt.lock")
+    );
+    let err = FileLock::acquire_for(&output, &Sidecar, LockStrategy::NoWait).unwrap_err();
+    assert!(matches!(err, MutxError::LockWouldBlock { .. }));
+}
+
+#[test]
+fn test_flat_hash_and_custom_strategies() {
+    let temp = TempDir::new().unwrap();
+    let locks = temp.path().join("locks");
+    fs::create_dir(&locks).unwrap();
+    let output = temp.path().join("out.txt");
+
+    let lock =
+        FileLock::acquire_for(&output, &FlatHash::new(&locks), LockStrategy::NoWait).unwrap();
+    assert_eq!(lock.path().parent().unwrap(), locks);
+    drop(lock);
+
+    let custom_dir = locks.clone();
+    let custom = move |canonical: &Path| -> mutx::Result<PathBuf> {
+        Ok(custom_dir.join(format!(
+            "custom-{}",
+            canonical.file_name().unwrap().to_string_lossy()
+        )))
+    };
+    let lock = FileLock::acquire_for(&output, &custom, LockStrategy::NoWait).unwrap();
+    assert_eq!(lock.path(), locks.join("custom-out.txt"));
+}
+
+#[test]
+fn test_writer_and_pool_accept_strategy() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+
+    let mut writer = AtomicWriter::new(&output, WriteMode::InMemory).unwrap();
+    let lock = writer.lock(&Sidecar, LockStrategy::NoWait).unwrap();
+    writer.write_all(b"data").unwrap();
+    writer.commit().unwrap();
+    assert!(lock.path().ends_with("out.txt.lock"));
+    drop(lock);
+
+    let mut pool = AtomicWriterPool::new(WriteMode::InMemory).with_lock_paths(Sidecar);
+    assert!(pool
+        .lock_path_for(&output)
+        .unwrap()
+        .ends_with("out.txt.lock"));
+}
@@ -0,0 +1,101 @@
+use assert_cmd::Command;
+use mutx::lock::get_lock_cache_dir;
+use mutx::lock::scope::{lock_scope_warning, sidecar_lock_path, ScopePolicy};
+use predicates::prelude::*;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// A scratch directory on a different device than the lock cache, if available
+fn cross_device_dir() -> Option<TempDir> {
+    let shm = Path::new("/dev/shm");
+    if !shm.is_dir() {
+        return None;
+    }
+    let dir = TempDir::new_in(shm).ok()?;
+    let cache = get_lock_cache_dir().ok()?;
+    lock_scope_warning(&cache, &dir.path().join("probe"), true)?;
+    Some(dir)
+}
+
+#[test]
+fn test_no_warning_outside_container() {
+    let temp = TempDir::new().unwrap();
+    let cache = get_lock_cache_dir().unwrap();
+
+    assert!(lock_scope_warning(&cache, &temp.path().join("out.txt"), false).is_none());
+}
+
+#[test]
+fn test_no_warning_on_same_device() {
+    let temp = TempDir::new().unwrap();
+    let lock_dir = temp.path().join("locks");
+    std::fs::create_dir(&lock_dir).unwrap();
+
+    assert!(lock_scope_warning(&lock_dir, &temp.path().join("out.txt"), true).is_none());
+}
+
+#[test]
+fn test_warning_for_cross_device_in_container() {
+    let Some(dir) = cross_device_dir() else {
+        return;
+    };
+    let cache = get_lock_cache_dir().unwrap();
+
+    let warning = lock_scope_warning(&cache, &dir.path().join("out.txt"), true).unwrap();
+    assert!(warning.contains("different filesystems"));
+}
+
+#[test]
+fn test_scope_policy_parsing() {
+    assert_eq!("warn".parse::<ScopePolicy>().unwrap(), ScopePolicy::Warn);
+    assert_eq!(
+        "adjacent".parse::<ScopePolicy>().unwrap(),
+        ScopePolicy::Adjacent
+    );
+    assert_eq!(
+        "ignore".parse::<ScopePolicy>().unwrap(),
+        ScopePolicy::Ignore
+    );
+    assert!("bogus".parse::<ScopePolicy>().is_err());
+}
+
+#[test]
+fn test_cli_adjacent_policy_locks_beside_output() {
+    let Some(dir) = cross_device_dir() else {
+        return;
+    };
+    let output = dir.path().join("shared.txt");
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
+    cmd.arg(&output)
+        .arg("--lock-scope")
+        .arg("adjacent")
+        .arg("-v")
+        .env("container", "test")
+        .write_stdin("data")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            sidecar_lock_path(&output).to_str().unwrap(),
+        ));
+
+    assert!(sidecar_lock_path(&output).exists());
+}
+
+#[test]
+fn test_cli_warn_policy_keeps_cache_lock() {
+    let Some(dir) = cross_device_dir() else {
+        return;
+    };
+    let output = dir.path().join("shared.txt");
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
+    cmd.arg(&output)
+        .env("container", "test")
+        .write_stdin("data")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("different filesystems"));
+
+    assert!(!sidecar_lock_path(&output).exists());
+}
@@ -0,0 +1,105 @@
+use mutx::{FileLock, LockSet, LockStrategy, MutxError, TimeoutConfig};
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+fn timeout(ms: u64) -> LockStrategy {
+    LockStrategy::Timeout(TimeoutConfig::new(Duration::from_millis(ms)))
+}
+
+#[test]
+fn test_locks_are_sorted_and_deduplicated() {
+    let temp = TempDir::new().unwrap();
+    let (a, b, c) = (
+        temp.path().join("a.lock"),
+        temp.path().join("b.lock"),
+        temp.path().join("c.lock"),
+    );
+
+    let set = LockSet::acquire([&c, &a, &b, &a], LockStrategy::NoWait).unwrap();
+    assert_eq!(set.len(), 3);
+    assert_eq!(set.paths().collect::<Vec<_>>(), vec![&a, &b, &c]);
+
+    // All three are held
+    for path in [&a, &b, &c] {
+        assert!(FileLock::acquire(path, LockStrategy::NoWait).is_err());
+    }
+    drop(set);
+    for path in [&a, &b, &c] {
+        FileLock::acquire(path, LockStrategy::NoWait).unwrap();
+    }
+}
+
+#[test]
+fn test_failure_releases_locks_already_taken() {
+    let temp = TempDir::new().unwrap();
+    let (a, b, c) = (
+        temp.path().join("a.lock"),
+        temp.path().join("b.lock"),
+        temp.path().join("c.lock"),
+    );
+    let held = FileLock::acquire(&c, LockStrategy::NoWait).unwrap();
+
+    let err = LockSet::acquire([&a, &b, &c], LockStrategy::NoWait).unwrap_err();
+    assert!(matches!(err, MutxError::LockWouldBlock { .. }), "{err}");
+
+    // a and b were rolled back
+    FileLock::acquire(&a, LockStrategy::NoWait).unwrap();
+    FileLock::acquire(&b, LockStrategy::NoWait).unwrap();
+    drop(held);
+}
+
+#[test]
+fn test_timeout_covers_the_whole_set() {
+    let temp = TempDir::new().unwrap();
+    let paths: Vec<_> = (0..3)
+        .map(|i| temp.path().join(format!("{i}.lock")))
+        .collect();
+    let _held: Vec<_> = paths
+        .iter()
+        .map(|p| FileLock::acquire(p, LockStrategy::NoWait).unwrap())
+        .collect();
+
+    let start = Instant::now();
+    let err = LockSet::acquire(&paths, timeout(300)).unwrap_err();
+    assert!(matches!(err, MutxError::LockTimeout { .. }), "{err}");
+    // The first lock uses up the budget; later ones are not given their own
+    assert!(start.elapsed() < Duration::from_millis(800));
+}
+
+#[test]
+fn test_opposite_orders_do_not_deadlock() {
+    let temp = TempDir::new().unwrap();
+    let (a, b) = (temp.path().join("a.lock"), temp.path().join("b.lock"));
+    let barrier = Arc::new(Barrier::new(2));
+
+    let workers: Vec<_> = [vec![a.clone(), b.clone()], vec![b, a]]
+        .into_iter()
+        .map(|paths| {
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                barrier.wait();
+                for _ in 0..20 {
+                    let _set = LockSet::acquire(&paths, timeout(10_000)).unwrap();
+                    thread::sleep(Duration::from_millis(2));
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+}
+
+#[test]
+fn test_for_outputs_uses_derived_lock_paths() {
+    let temp = TempDir::new().unwrap();
+    let outputs = [temp.path().join("one.txt"), temp.path().join("two.txt")];
+
+    let set = LockSet::for_outputs(&outputs, LockStrategy::NoWait).unwrap();
+    for output in &outputs {
+        let derived = mutx::derive_lock_path(output, false).unwrap();
+        assert!(set.paths().any(|p| p == derived));
+    }
+}
@@ -0,0 +1,96 @@
+use mutx::housekeep::{clean_locks, CleanLockConfig};
+use mutx::lock::{
+    canonical_output_path, derive_lock_path, get_lock_cache_dir, is_lock_shard, lock_filename,
+    lock_shard, LockScheme,
+};
+use mutx::{FileLock, LockStrategy};
+use std::fs::{self, File};
+use tempfile::TempDir;
+
+#[test]
+fn test_lock_path_is_in_hash_shard() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("output.txt");
+
+    let lock = derive_lock_path(&output, false).unwrap();
+    let canonical = canonical_output_path(&output).unwrap();
+    let shard = lock.parent().unwrap();
+
+    assert_eq!(
+        shard.file_name().unwrap().to_str().unwrap(),
+        lock_shard(&canonical)
+    );
+    assert_eq!(shard.parent().unwrap(), get_lock_cache_dir().unwrap());
+
+    let _lock = FileLock::acquire(&lock, LockStrategy::NoWait).unwrap();
+    assert!(shard.is_dir());
+}
+
+#[test]
+fn test_flat_lock_file_is_migrated_into_shard() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("migrate-me.txt");
+    let canonical = canonical_output_path(&output).unwrap();
+    let name = lock_filename(&canonical, &LockScheme::default()).unwrap();
+    let flat = get_lock_cache_dir().unwrap().join(&name);
+
+    // A lock held through the old flat path keeps excluding the new one
+    File::create(&flat).unwrap();
+    let held = FileLock::acquire(&flat, LockStrategy::NoWait).unwrap();
+
+    let lock = derive_lock_path(&output, false).unwrap();
+    assert!(
+        flat.exists(),
+        "deriving the path alone must not touch files"
+    );
+
+    assert!(FileLock::acquire(&lock, LockStrategy::NoWait).is_err());
+    assert!(lock.exists());
+
+    drop(held);
+    let new = FileLock::acquire(&lock, LockStrategy::NoWait).unwrap();
+    // An older release still locking the flat path is excluded too
+    assert!(flat.exists());
+    assert!(FileLock::acquire(&flat, LockStrategy::NoWait).is_err());
+    drop(new);
+
+    let config = CleanLockConfig {
+        dir: get_lock_cache_dir().unwrap(),
+        recursive: false,
+        older_than: None,
+        dry_run: true,
+    };
+    let cleaned = clean_locks(&config).unwrap();
+    assert!(!cleaned.contains(&flat) && !cleaned.contains(&lock));
+}
+
+#[test]
+fn test_housekeep_cleans_sharded_locks_without_recursion() {
+    let cache = TempDir::new().unwrap();
+    let shard = cache.path().join("a3");
+    let other = cache.path().join("not-a-shard");
+    fs::create_dir(&shard).unwrap();
+    fs::create_dir(&other).unwrap();
+    assert!(is_lock_shard("a3"));
+
+    let sharded = shard.join("x.output.txt.0123abcd.lock");
+    let flat = cache.path().join("y.output.txt.4567cdef.lock");
+    let nested = other.join("z.output.txt.89ab0123.lock");
+    for path in [&sharded, &flat, &nested] {
+        File::create(path).unwrap();
+    }
+
+    let config = CleanLockConfig {
+        dir: cache.path().to_path_buf(),
+        recursive: false,
+        older_than: None,
+        dry_run: false,
+    };
+    let mut cleaned = clean_locks(&config).unwrap();
+    cleaned.sort();
+
+    let mut expected = vec![sharded, flat];
+    expected.sort();
+    assert_eq!(cleaned, expected);
+    assert!(nested.exists());
+}
@@ -0,0 +1,87 @@
+use assert_cmd::Command;
+use mutx::lock::LockPlacement;
+use mutx::{FileLock, LockStatus, LockStrategy};
+use predicates::prelude::*;
+use tempfile::TempDir;
+
+#[test]
+fn test_status_of_missing_lock_is_free() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+
+    assert_eq!(FileLock::status(&lock_path).unwrap(), LockStatus::Free);
+    // Probing creates nothing
+    assert!(!lock_path.exists());
+}
+
+#[test]
+fn test_status_reports_holder_until_released() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+
+    let lock = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+    let holder = lock.holder().unwrap().clone();
+    match FileLock::status(&lock_path).unwrap() {
+        LockStatus::Held {
+            shared,
+            pid,
+            host,
+            since,
+        } => {
+            assert!(!shared);
+            assert_eq!(pid, Some(holder.pid));
+            assert_eq!(host, Some(holder.hostname));
+            assert_eq!(since, Some(holder.acquired_at));
+        }
+        LockStatus::Free => panic!("lock reported free while held"),
+    }
+
+    drop(lock);
+    assert_eq!(FileLock::status(&lock_path).unwrap(), LockStatus::Free);
+}
+
+#[test]
+fn test_status_of_shared_lock() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+
+    let reader = FileLock::acquire(&lock_path, LockStrategy::NoWait.shared()).unwrap();
+    let status = FileLock::status(&lock_path).unwrap();
+    assert!(status.is_held());
+    assert_eq!(
+        status,
+        LockStatus::Held {
+            shared: true,
+            pid: None,
+            host: None,
+            since: None,
+        }
+    );
+    // The probe did not keep a lock of its own
+    drop(reader);
+    assert!(FileLock::acquire(&lock_path, LockStrategy::NoWait).is_ok());
+}
+
+#[test]
+fn test_cli_status() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("data.txt");
+    let status = || {
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
+        cmd.args(["status", "--lock-beside"]).arg(&file);
+        cmd.assert().success()
+    };
+
+    status().stdout(predicate::str::starts_with("Not locked"));
+
+    let lock_path = LockPlacement::Sidecar.derive(&file).unwrap();
+    let lock = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+    status().stdout(predicate::str::starts_with(format!(
+        "Locked by PID {}",
+        std::process::id()
+    )));
+    drop(lock);
+
+    let _reader = FileLock::acquire(&lock_path, LockStrategy::NoWait.shared()).unwrap();
+    status().stdout(predicate::str::starts_with("Locked by readers"));
+}
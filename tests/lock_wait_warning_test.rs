@@ -0,0 +1,165 @@
+use assert_cmd::Command;
+use mutx::{FileLock, LockStrategy};
+use predicates::prelude::*;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+/// Hold `lock_path` for `hold`, from another thread
+fn hold_for(lock_path: &Path, hold: Duration) -> thread::JoinHandle<()> {
+    let lock = FileLock::acquire(lock_path, LockStrategy::NoWait).unwrap();
+    thread::spawn(move || {
+        thread::sleep(hold);
+        drop(lock);
+    })
+}
+
+/// Accept one request on a local listener and return its body
+fn receive_one_post(listener: TcpListener) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line == "\r\n" {
+                break;
+            }
+            if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                length = value.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        reader
+            .get_mut()
+            .write_all(b"HTTP/1.1 204 No Content\r\n\r\n")
+            .unwrap();
+        String::from_utf8(body).unwrap()
+    })
+}
+
+#[test]
+fn test_warns_and_keeps_waiting() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    let holder = hold_for(
+        &temp.path().join("out.txt.lock"),
+        Duration::from_millis(2500),
+    );
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--lock-beside", "--warn-after", "1s", "--no-spinner"])
+        .write_stdin("content")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Still waiting for lock after 1."));
+    assert_eq!(std::fs::read_to_string(&output).unwrap(), "content");
+    holder.join().unwrap();
+}
+
+#[test]
+fn test_posts_warning_to_webhook() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hooks/mutx", listener.local_addr().unwrap());
+    let received = receive_one_post(listener);
+    let holder = hold_for(
+        &temp.path().join("out.txt.lock"),
+        Duration::from_millis(2500),
+    );
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--lock-beside", "--warn-after", "1s", "--warn-webhook"])
+        .arg(&url)
+        .write_stdin("content")
+        .assert()
+        .success();
+
+    let body: serde_json::Value = serde_json::from_str(&received.join().unwrap()).unwrap();
+    assert_eq!(body["schema"], "mutx.lock_wait.v1");
+    assert_eq!(body["threshold_ms"], 1000);
+    assert!(body["waited_ms"].as_u64().unwrap() >= 1000);
+    assert_eq!(body["holder_pid"], std::process::id());
+    holder.join().unwrap();
+}
+
+#[test]
+fn test_slow_webhook_does_not_hold_up_the_write() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hooks/mutx", listener.local_addr().unwrap());
+    // Takes the request but does not answer for a long while
+    let webhook = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        thread::sleep(Duration::from_secs(6));
+        drop(stream);
+    });
+    let holder = hold_for(
+        &temp.path().join("out.txt.lock"),
+        Duration::from_millis(1500),
+    );
+
+    let started = Instant::now();
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--lock-beside", "--warn-after", "1s", "--warn-webhook"])
+        .arg(&url)
+        .write_stdin("content")
+        .assert()
+        .success();
+    assert!(
+        started.elapsed() < Duration::from_secs(4),
+        "{:?}",
+        started.elapsed()
+    );
+    holder.join().unwrap();
+    webhook.join().unwrap();
+}
+
+#[test]
+fn test_quiet_when_lock_is_free() {
+    let temp = TempDir::new().unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(temp.path().join("out.txt"))
+        .args(["--warn-after", "1s"])
+        .write_stdin("content")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Still waiting").not());
+}
+
+#[test]
+fn test_rejects_invalid_combinations() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+
+    for args in [
+        &["--warn-after", "10s", "--no-wait"][..],
+        &["--warn-after", "10s", "--no-lock"],
+        &["--warn-webhook", "http://127.0.0.1/hook"],
+        &[
+            "--warn-after",
+            "10s",
+            "--warn-webhook",
+            "https://example.com/hook",
+        ],
+    ] {
+        Command::new(env!("CARGO_BIN_EXE_mutx"))
+            .arg(&output)
+            .args(args)
+            .write_stdin("content")
+            .assert()
+            .failure();
+    }
+    assert!(!output.exists());
+}
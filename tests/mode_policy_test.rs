@@ -0,0 +1,122 @@
+#![cfg(unix)]
+
+use assert_cmd::Command;
+use mutx::{AtomicWriter, FileMode, ModePolicy, WriteMode};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn mode_of(path: &Path) -> u32 {
+    fs::metadata(path).unwrap().permissions().mode() & 0o7777
+}
+
+/// Mode a file created in place in `dir` gets under the current umask
+fn umask_default(dir: &Path) -> u32 {
+    let probe = dir.join("probe");
+    fs::write(&probe, "").unwrap();
+    let mode = mode_of(&probe);
+    fs::remove_file(&probe).unwrap();
+    mode
+}
+
+fn write(target: &Path, policy: ModePolicy) -> u32 {
+    let mut writer = AtomicWriter::new(target, WriteMode::InMemory)
+        .unwrap()
+        .default_mode_policy(policy);
+    writer.write_all(b"data").unwrap();
+    writer.commit().unwrap().mode
+}
+
+#[test]
+fn test_preserve_existing_keeps_mode() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("secret.conf");
+    fs::write(&target, "old").unwrap();
+    fs::set_permissions(&target, fs::Permissions::from_mode(0o600)).unwrap();
+
+    assert_eq!(write(&target, ModePolicy::PreserveExisting), 0o600);
+    assert_eq!(mode_of(&target), 0o600);
+}
+
+#[test]
+fn test_preserve_existing_keeps_setuid_and_setgid() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("tool");
+    // The file's group is one the user belongs to, so anyone may set setgid
+    fs::write(&target, "old").unwrap();
+    fs::set_permissions(&target, fs::Permissions::from_mode(0o6755)).unwrap();
+    assert_eq!(mode_of(&target), 0o6755);
+
+    assert_eq!(write(&target, ModePolicy::PreserveExisting), 0o6755);
+    assert_eq!(mode_of(&target), 0o6755);
+}
+
+#[test]
+fn test_new_file_gets_umask_default() {
+    let temp = TempDir::new().unwrap();
+    let expected = umask_default(temp.path());
+    let target = temp.path().join("new.conf");
+
+    assert_eq!(write(&target, ModePolicy::PreserveExisting), expected);
+    assert_eq!(mode_of(&target), expected);
+}
+
+#[test]
+fn test_umask_policy_ignores_existing_mode() {
+    let temp = TempDir::new().unwrap();
+    let expected = umask_default(temp.path());
+    let target = temp.path().join("app.conf");
+    fs::write(&target, "old").unwrap();
+    fs::set_permissions(&target, fs::Permissions::from_mode(0o600)).unwrap();
+
+    assert_eq!(write(&target, ModePolicy::Umask), expected);
+    assert_eq!(mode_of(&target), expected);
+}
+
+#[test]
+fn test_explicit_mode() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("app.conf");
+    fs::write(&target, "old").unwrap();
+
+    assert_eq!(
+        write(&target, ModePolicy::Explicit(FileMode::new(0o640).unwrap())),
+        0o640
+    );
+    assert_eq!(mode_of(&target), 0o640);
+}
+
+#[test]
+fn test_cli_mode_flags_and_json_report() {
+    let temp = TempDir::new().unwrap();
+    let expected = umask_default(temp.path());
+    let output = temp.path().join("app.conf");
+    fs::write(&output, "old").unwrap();
+    fs::set_permissions(&output, fs::Permissions::from_mode(0o600)).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .arg("--no-preserve-mode")
+        .write_stdin("new")
+        .assert()
+        .success();
+    assert_eq!(mode_of(&output), expected);
+
+    let assert = Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--mode", "0640", "--json"])
+        .write_stdin("new")
+        .assert()
+        .success();
+    let report: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+    assert_eq!(report["mode"], "0640");
+    assert_eq!(mode_of(&output), 0o640);
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--mode", "0640", "--no-preserve-mode"])
+        .write_stdin("new")
+        .assert()
+        .failure();
+}
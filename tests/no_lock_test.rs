@@ -0,0 +1,90 @@
+use assert_cmd::Command;
+use mutx::{FileLock, LockBackend, LockStrategy};
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn mutx(temp: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("mutx").unwrap();
+    cmd.env("XDG_CACHE_HOME", temp.path().join("cache"))
+        .env_remove("MUTX_LOCK_DIR");
+    cmd
+}
+
+#[test]
+fn test_no_lock_writes_atomically_without_touching_cache() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+
+    mutx(&temp)
+        .arg(&output)
+        .arg("--no-lock")
+        .write_stdin("data")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("not locking"));
+
+    assert_eq!(fs::read_to_string(&output).unwrap(), "data");
+    assert!(!temp.path().join("cache").exists());
+}
+
+#[test]
+fn test_no_lock_reports_not_exclusive() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+
+    let out = mutx(&temp)
+        .args(["write", "--no-lock", "--json"])
+        .arg(&output)
+        .write_stdin("data")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let report: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(report["guarantees"]["exclusive"], false);
+    assert_eq!(report["guarantees"]["atomic"], true);
+
+    mutx(&temp)
+        .arg(&output)
+        .args(["--no-lock", "--require", "exclusive"])
+        .write_stdin("other")
+        .assert()
+        .failure();
+    assert_eq!(fs::read_to_string(&output).unwrap(), "data");
+}
+
+#[test]
+fn test_no_lock_ignores_held_lock() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    let _held = FileLock::acquire_with_backend(
+        &temp.path().join("out.txt.lock"),
+        LockStrategy::NoWait,
+        LockBackend::Dotlock,
+    )
+    .unwrap();
+
+    mutx(&temp)
+        .arg(&output)
+        .args(["--no-lock", "--lock-backend", "dotlock"])
+        .write_stdin("data")
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&output).unwrap(), "data");
+}
+
+#[test]
+fn test_no_lock_conflicts_with_lock_options() {
+    let temp = TempDir::new().unwrap();
+
+    mutx(&temp)
+        .arg(temp.path().join("out.txt"))
+        .args(["--no-lock", "--lock-file"])
+        .arg(temp.path().join("custom.lock"))
+        .write_stdin("data")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
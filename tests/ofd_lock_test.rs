@@ -0,0 +1,122 @@
+#![cfg(target_os = "linux")]
+
+use assert_cmd::Command;
+use mutx::{FileLock, LockBackend, LockHolder, LockStrategy, MutxError, TimeoutConfig};
+use std::fs;
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+fn ofd(lock_path: &std::path::Path, strategy: LockStrategy) -> mutx::Result<FileLock> {
+    FileLock::acquire_with_backend(lock_path, strategy, LockBackend::Ofd)
+}
+
+#[test]
+fn test_ofd_backend_parses() {
+    assert_eq!("ofd".parse::<LockBackend>().unwrap(), LockBackend::Ofd);
+    assert_eq!(LockBackend::Ofd.to_string(), "ofd");
+    assert!(LockBackend::Ofd.is_file_lock());
+    assert!(!LockBackend::Dotlock.is_file_lock());
+}
+
+#[test]
+fn test_ofd_lock_is_an_ofd_lock() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+    let _lock = ofd(&lock_path, LockStrategy::NoWait).unwrap();
+
+    // The kernel lists OFD locks as OFDLCK, flocks as FLOCK
+    let locks = fs::read_to_string("/proc/locks").unwrap();
+    assert!(locks.lines().any(|l| l.contains("OFDLCK")), "{locks}");
+}
+
+#[test]
+fn test_ofd_excludes_within_one_process() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+
+    let held = ofd(&lock_path, LockStrategy::NoWait).unwrap();
+    assert!(matches!(
+        ofd(&lock_path, LockStrategy::NoWait),
+        Err(MutxError::LockWouldBlock { .. })
+    ));
+    drop(held);
+    ofd(&lock_path, LockStrategy::NoWait).unwrap();
+}
+
+#[test]
+fn test_ofd_threads_take_turns() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = Arc::new(temp.path().join("out.lock"));
+    let held = ofd(&lock_path, LockStrategy::NoWait).unwrap();
+
+    let start = Arc::new(Barrier::new(3));
+    let waiters: Vec<_> = (0..2)
+        .map(|_| {
+            let (lock_path, start) = (Arc::clone(&lock_path), Arc::clone(&start));
+            thread::spawn(move || {
+                start.wait();
+                let lock = ofd(&lock_path, LockStrategy::Wait).unwrap();
+                let acquired = Instant::now();
+                thread::sleep(Duration::from_millis(100));
+                drop(lock);
+                acquired
+            })
+        })
+        .collect();
+    start.wait();
+    thread::sleep(Duration::from_millis(100));
+    drop(held);
+
+    let mut acquired: Vec<Instant> = waiters.into_iter().map(|w| w.join().unwrap()).collect();
+    acquired.sort();
+    // The second waiter only got the lock once the first released it
+    assert!(acquired[1] - acquired[0] >= Duration::from_millis(100));
+}
+
+#[test]
+fn test_ofd_shared_locks_and_holder() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+
+    let first = ofd(&lock_path, LockStrategy::NoWait.shared()).unwrap();
+    let second = ofd(&lock_path, LockStrategy::NoWait.shared()).unwrap();
+    let timeout = LockStrategy::Timeout(TimeoutConfig::new(Duration::from_millis(100)));
+    assert!(matches!(
+        ofd(&lock_path, timeout),
+        Err(MutxError::LockTimeout { .. })
+    ));
+    drop((first, second));
+
+    let _lock = ofd(&lock_path, LockStrategy::NoWait).unwrap();
+    let holder = LockHolder::read(&lock_path).unwrap().unwrap();
+    assert_eq!(holder.pid, std::process::id());
+}
+
+#[test]
+fn test_cli_ofd_backend() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    let lock_path = temp.path().join("out.lock");
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .arg("--lock-file")
+        .arg(&lock_path)
+        .args(["--lock-backend", "ofd"])
+        .write_stdin("content")
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&output).unwrap(), "content");
+
+    let _held = ofd(&lock_path, LockStrategy::NoWait).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .arg("--lock-file")
+        .arg(&lock_path)
+        .args(["--lock-backend", "ofd", "--no-wait"])
+        .write_stdin("other")
+        .assert()
+        .code(2);
+}
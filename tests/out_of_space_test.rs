@@ -0,0 +1,201 @@
+use assert_cmd::Command;
+use mutx::{create_backup, prune_backups, reclaim_backups, BackupConfig, BackupSuffix};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn suffix() -> BackupSuffix {
+    ".mutx.backup".parse().unwrap()
+}
+
+fn write_backup(dir: &Path, name: &str, size: usize) {
+    fs::write(dir.join(name), vec![b'b'; size]).unwrap();
+}
+
+#[test]
+fn test_reclaim_keeps_newest_and_slot_backups() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("data.bin");
+    write_backup(temp.path(), "data.bin.20240101_000000.mutx.backup", 10);
+    write_backup(temp.path(), "data.bin.20240102_000000.mutx.backup", 20);
+    write_backup(temp.path(), "data.bin.20240103_000000.mutx.backup", 30);
+    write_backup(temp.path(), "data.bin.mutx.backup", 40);
+    write_backup(temp.path(), "other.bin.20240101_000000.mutx.backup", 50);
+
+    let freed = reclaim_backups(&target, &suffix(), None, u64::MAX).unwrap();
+
+    assert_eq!(freed, 30);
+    let mut left: Vec<_> = fs::read_dir(temp.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .collect();
+    left.sort();
+    assert_eq!(
+        left,
+        [
+            "data.bin.20240103_000000.mutx.backup",
+            "data.bin.mutx.backup",
+            "other.bin.20240101_000000.mutx.backup",
+        ]
+    );
+}
+
+#[test]
+fn test_reclaim_removes_only_what_is_needed() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("data.bin");
+    write_backup(temp.path(), "data.bin.20240101_000000.mutx.backup", 10);
+    write_backup(temp.path(), "data.bin.20240102_000000.mutx.backup", 20);
+    write_backup(temp.path(), "data.bin.20240103_000000.mutx.backup", 30);
+    write_backup(temp.path(), "data.bin.20240104_000000.mutx.backup", 40);
+
+    assert_eq!(reclaim_backups(&target, &suffix(), None, 25).unwrap(), 30);
+    assert!(!temp
+        .path()
+        .join("data.bin.20240102_000000.mutx.backup")
+        .exists());
+    assert!(temp
+        .path()
+        .join("data.bin.20240103_000000.mutx.backup")
+        .exists());
+}
+
+#[test]
+fn test_reclaim_without_backups_frees_nothing() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("data.bin");
+    write_backup(temp.path(), "data.bin.20240103_000000.mutx.backup", 30);
+
+    assert_eq!(
+        reclaim_backups(&target, &suffix(), None, u64::MAX).unwrap(),
+        0
+    );
+    assert!(temp
+        .path()
+        .join("data.bin.20240103_000000.mutx.backup")
+        .exists());
+}
+
+#[test]
+fn test_prune_keeps_requested_number_of_backups() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("data.bin");
+    write_backup(temp.path(), "data.bin.20240101_000000.mutx.backup", 10);
+    write_backup(temp.path(), "data.bin.20240102_000000.mutx.backup", 20);
+    write_backup(temp.path(), "data.bin.20240103_000000.mutx.backup", 30);
+    write_backup(temp.path(), "data.bin.mutx.backup", 40);
+
+    assert_eq!(prune_backups(&target, ".mutx.backup", None, 2).unwrap(), 10);
+    assert!(!temp
+        .path()
+        .join("data.bin.20240101_000000.mutx.backup")
+        .exists());
+    assert!(temp
+        .path()
+        .join("data.bin.20240102_000000.mutx.backup")
+        .exists());
+    assert!(temp.path().join("data.bin.mutx.backup").exists());
+}
+
+/// A tiny tmpfs to fill up; needs root, so the test is skipped otherwise
+#[cfg(target_os = "linux")]
+struct SmallFs(TempDir);
+
+#[cfg(target_os = "linux")]
+impl SmallFs {
+    fn mount(size: &str) -> Option<Self> {
+        let dir = TempDir::new().unwrap();
+        let status = std::process::Command::new("mount")
+            .args(["-t", "tmpfs", "-o", &format!("size={}", size), "tmpfs"])
+            .arg(dir.path())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .ok()?;
+        status.success().then_some(SmallFs(dir))
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for SmallFs {
+    fn drop(&mut self) {
+        let _ = std::process::Command::new("umount")
+            .arg(self.0.path())
+            .status();
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_out_of_space_cleans_up_and_reclaims() {
+    let Some(small) = SmallFs::mount("256k") else {
+        return;
+    };
+    let dir = small.0.path();
+    let output = dir.join("data.bin");
+    for day in 1..=3 {
+        write_backup(
+            dir,
+            &format!("data.bin.2024010{}_000000.mutx.backup", day),
+            64 * 1024,
+        );
+    }
+    let input = vec![b'x'; 128 * 1024];
+
+    for stream in [false, true] {
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
+        cmd.arg(&output);
+        if stream {
+            cmd.arg("--stream");
+        }
+        cmd.write_stdin(input.clone())
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains("Out of space"));
+        // Only the backups remain; the partial temp file is gone
+        assert_eq!(fs::read_dir(dir).unwrap().count(), 3);
+    }
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .arg("--reclaim-backups-on-enospc")
+        .write_stdin(input.clone())
+        .assert()
+        .success();
+
+    assert_eq!(fs::read(&output).unwrap(), input);
+    assert!(dir.join("data.bin.20240103_000000.mutx.backup").exists());
+    assert!(!dir.join("data.bin.20240101_000000.mutx.backup").exists());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_backup_out_of_space_prunes_and_retries() {
+    let Some(small) = SmallFs::mount("256k") else {
+        return;
+    };
+    let dir = small.0.path();
+    let source = dir.join("data.bin");
+    for day in 1..=2 {
+        write_backup(
+            dir,
+            &format!("data.bin.2024010{}_000000.mutx.backup", day),
+            64 * 1024,
+        );
+    }
+    fs::write(&source, vec![b'x'; 96 * 1024]).unwrap();
+
+    let config = |prune_on_enospc| BackupConfig {
+        source: source.clone(),
+        suffix: ".mutx.backup".parse().unwrap(),
+        directory: None,
+        timestamp: false,
+        prune_on_enospc,
+        delta: false,
+    };
+    assert!(create_backup(&config(None)).is_err());
+
+    let created = create_backup(&config(Some(1))).unwrap();
+    assert_eq!(fs::read(&created).unwrap(), fs::read(&source).unwrap());
+    assert!(dir.join("data.bin.20240102_000000.mutx.backup").exists());
+    assert!(!dir.join("data.bin.20240101_000000.mutx.backup").exists());
+}
@@ -0,0 +1,36 @@
+use mutx::parse::{parse_backup_suffix, parse_duration, parse_mode, parse_size};
+use mutx::MutxError;
+use std::time::Duration;
+
+#[test]
+fn test_parsers_accept_cli_forms() {
+    assert_eq!(
+        parse_duration("7d").unwrap(),
+        Duration::from_secs(7 * 86400)
+    );
+    assert_eq!(parse_size("10M").unwrap(), 10 * 1024 * 1024);
+    assert_eq!(parse_mode("0640").unwrap().bits(), 0o640);
+    assert_eq!(parse_backup_suffix(".bak").unwrap().as_str(), ".bak");
+}
+
+#[test]
+fn test_parse_errors_echo_input() {
+    let errors = [
+        parse_duration("10x").unwrap_err(),
+        parse_size("10x").unwrap_err(),
+        parse_mode("10x").unwrap_err(),
+    ];
+    for err in &errors {
+        assert!(err.to_string().contains("'10x'"), "{}", err);
+    }
+    assert!(matches!(errors[1], MutxError::InvalidSize { .. }));
+    assert!(parse_backup_suffix("").is_err());
+}
+
+#[test]
+fn test_utils_parse_duration_still_available() {
+    assert_eq!(
+        mutx::utils::parse_duration("5m").unwrap(),
+        mutx::parse::parse_duration("5m").unwrap()
+    );
+}
@@ -0,0 +1,59 @@
+use mutx::utils::ensure_within;
+use mutx::{create_backup, BackupConfig, MutxError};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_ensure_within_accepts_nested_path() {
+    let temp = TempDir::new().unwrap();
+    let path = temp.path().join("sub").join("file.txt");
+
+    assert!(ensure_within(&path, temp.path()).is_ok());
+}
+
+#[test]
+fn test_ensure_within_rejects_parent_traversal() {
+    let temp = TempDir::new().unwrap();
+    let base = temp.path().join("backups");
+    fs::create_dir(&base).unwrap();
+    let path = base.join("..").join("escaped.txt");
+
+    let result = ensure_within(&path, &base);
+    assert!(matches!(result, Err(MutxError::PathEscapes { .. })));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_ensure_within_rejects_symlinked_escape() {
+    let temp = TempDir::new().unwrap();
+    let base = temp.path().join("backups");
+    let outside = temp.path().join("outside");
+    fs::create_dir(&base).unwrap();
+    fs::create_dir(&outside).unwrap();
+    std::os::unix::fs::symlink(&outside, base.join("link")).unwrap();
+
+    let result = ensure_within(&base.join("link").join("file.txt"), &base);
+    assert!(matches!(result, Err(MutxError::PathEscapes { .. })));
+}
+
+#[test]
+fn test_backup_suffix_cannot_escape_backup_dir() {
+    let temp = TempDir::new().unwrap();
+    let source = temp.path().join("data.txt");
+    let backup_dir = temp.path().join("backups");
+    fs::create_dir(&backup_dir).unwrap();
+    fs::write(&source, b"content").unwrap();
+
+    let config = BackupConfig {
+        source,
+        suffix: "/../../escaped.backup".parse().unwrap(),
+        directory: Some(backup_dir),
+        timestamp: false,
+        prune_on_enospc: None,
+        delta: false,
+    };
+
+    let result = create_backup(&config);
+    assert!(matches!(result, Err(MutxError::PathEscapes { .. })));
+    assert!(!temp.path().join("escaped.backup").exists());
+}
@@ -0,0 +1,124 @@
+use assert_cmd::Command;
+use mutx::utils::normalize_path;
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn mutx(dir: &Path) -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
+    cmd.current_dir(dir);
+    cmd
+}
+
+#[test]
+fn test_dot_segments_name_the_same_file() {
+    let temp = TempDir::new().unwrap();
+    fs::create_dir(temp.path().join("dir")).unwrap();
+    fs::write(temp.path().join("dir/file.txt"), "zero").unwrap();
+
+    for (spelling, content) in [
+        ("dir/./file.txt", "one"),
+        ("dir/../dir/file.txt", "two"),
+        ("./dir//file.txt", "three"),
+    ] {
+        mutx(temp.path())
+            .arg(spelling)
+            .args(["--backup", "-vv"])
+            .write_stdin(content)
+            .assert()
+            .success()
+            .stderr(predicate::str::contains("Write completed: dir/file.txt"));
+    }
+
+    assert_eq!(
+        fs::read_to_string(temp.path().join("dir/file.txt")).unwrap(),
+        "three"
+    );
+    assert_eq!(
+        fs::read_to_string(temp.path().join("dir/file.txt.mutx.backup")).unwrap(),
+        "two"
+    );
+}
+
+#[test]
+fn test_trailing_slash_on_file_is_refused() {
+    let temp = TempDir::new().unwrap();
+    fs::write(temp.path().join("file.txt"), "keep").unwrap();
+
+    for spelling in ["file.txt/", "missing.txt/", "file.txt/."] {
+        mutx(temp.path())
+            .arg(spelling)
+            .write_stdin("new")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("not a directory"));
+    }
+    assert_eq!(
+        fs::read_to_string(temp.path().join("file.txt")).unwrap(),
+        "keep"
+    );
+    assert!(!temp.path().join("missing.txt").exists());
+}
+
+#[test]
+fn test_directory_spelling_reports_normalized_directory() {
+    let temp = TempDir::new().unwrap();
+    fs::create_dir(temp.path().join("dir")).unwrap();
+
+    mutx(temp.path())
+        .arg("dir/sub/..")
+        .write_stdin("new")
+        .assert()
+        .failure();
+    mutx(temp.path())
+        .arg("dir/./")
+        .write_stdin("new")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Target is a directory: dir\n"));
+}
+
+#[test]
+fn test_trailing_slash_allowed_with_into_dir() {
+    let temp = TempDir::new().unwrap();
+    fs::create_dir(temp.path().join("dir")).unwrap();
+    fs::write(temp.path().join("input.txt"), "copied").unwrap();
+
+    mutx(temp.path())
+        .args(["dir/./", "--into-dir", "--input", "input.txt"])
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(temp.path().join("dir/input.txt")).unwrap(),
+        "copied"
+    );
+}
+
+#[test]
+#[cfg(unix)]
+fn test_dotdot_after_symlinked_directory_is_not_folded() {
+    let temp = TempDir::new().unwrap();
+    fs::create_dir_all(temp.path().join("real/inner")).unwrap();
+    std::os::unix::fs::symlink("real/inner", temp.path().join("link")).unwrap();
+
+    // link/.. is real/, not the directory holding the link
+    mutx(temp.path())
+        .arg("link/../file.txt")
+        .write_stdin("physical")
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(temp.path().join("real/file.txt")).unwrap(),
+        "physical"
+    );
+    assert!(!temp.path().join("file.txt").exists());
+}
+
+#[test]
+fn test_normalize_path_is_lexical() {
+    assert_eq!(
+        normalize_path(Path::new("dir/../dir/./file.txt")),
+        Path::new("dir/file.txt")
+    );
+}
@@ -0,0 +1,73 @@
+use mutx::{FileLock, LockStrategy, MutxError, TimeoutConfig};
+use std::fs;
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// Size of one record in the fixed-record file used below
+const RECORD: u64 = 128;
+
+#[test]
+fn test_disjoint_ranges_lock_independently() {
+    let temp = TempDir::new().unwrap();
+    let data = temp.path().join("records.dat");
+    fs::write(&data, vec![0u8; 4 * RECORD as usize]).unwrap();
+
+    let first = FileLock::acquire_range(&data, 0, RECORD, LockStrategy::NoWait).unwrap();
+    let second = FileLock::acquire_range(&data, RECORD, RECORD, LockStrategy::NoWait).unwrap();
+    assert_eq!(first.range(), Some((0, RECORD)));
+    assert_eq!(second.range(), Some((RECORD, RECORD)));
+    assert!(first.holder().is_none());
+
+    // Locking leaves the data alone
+    drop((first, second));
+    assert_eq!(fs::read(&data).unwrap(), vec![0u8; 4 * RECORD as usize]);
+}
+
+// Other Unix systems use process-owned fcntl locks, which never conflict
+// within one process
+#[cfg(any(target_os = "linux", windows))]
+#[test]
+fn test_overlapping_ranges_conflict() {
+    let temp = TempDir::new().unwrap();
+    let data = temp.path().join("records.dat");
+    fs::write(&data, vec![0u8; 4 * RECORD as usize]).unwrap();
+
+    let held = FileLock::acquire_range(&data, RECORD, RECORD, LockStrategy::NoWait).unwrap();
+    assert!(matches!(
+        FileLock::acquire_range(&data, RECORD + 10, 10, LockStrategy::NoWait),
+        Err(MutxError::LockWouldBlock { .. })
+    ));
+    let timeout = LockStrategy::Timeout(TimeoutConfig::new(Duration::from_millis(50)));
+    assert!(matches!(
+        FileLock::acquire_range(&data, 0, 2 * RECORD, timeout),
+        Err(MutxError::LockTimeout { .. })
+    ));
+
+    drop(held);
+    assert!(FileLock::acquire_range(&data, 0, 2 * RECORD, LockStrategy::NoWait).is_ok());
+}
+
+#[cfg(any(target_os = "linux", windows))]
+#[test]
+fn test_shared_ranges_exclude_writers_only() {
+    let temp = TempDir::new().unwrap();
+    let data = temp.path().join("records.dat");
+    fs::write(&data, vec![0u8; RECORD as usize]).unwrap();
+
+    let shared = LockStrategy::NoWait.shared();
+    let first = FileLock::acquire_range(&data, 0, RECORD, shared.clone()).unwrap();
+    let second = FileLock::acquire_range(&data, 0, RECORD, shared).unwrap();
+    assert!(first.is_shared() && second.is_shared());
+    assert!(FileLock::acquire_range(&data, 0, 1, LockStrategy::NoWait).is_err());
+}
+
+#[test]
+fn test_invalid_ranges_and_strategies_are_refused() {
+    let temp = TempDir::new().unwrap();
+    let data = temp.path().join("records.dat");
+
+    assert!(FileLock::acquire_range(&data, 0, 0, LockStrategy::NoWait).is_err());
+    assert!(FileLock::acquire_range(&data, u64::MAX, 1, LockStrategy::NoWait).is_err());
+    assert!(FileLock::acquire_range(&data, 0, 1, LockStrategy::NoWait.break_stale()).is_err());
+    assert!(FileLock::acquire_range(&data, 0, 1, LockStrategy::NoWait.fair()).is_err());
+}
@@ -0,0 +1,273 @@
+#![cfg(feature = "redis")]
+
+use assert_cmd::Command;
+use mutx::lock::cluster::redis::RedisLockService;
+use mutx::lock::cluster::LockService;
+use mutx::{FileLock, LockStrategy, MutxError};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+/// Just enough of a Redis server for the lock scripts: keys with expiry,
+/// and EVAL recognizing each script by the command it guards
+#[derive(Default)]
+struct FakeRedis {
+    values: HashMap<String, (String, Option<Instant>)>,
+}
+
+impl FakeRedis {
+    fn get(&mut self, key: &str) -> Option<String> {
+        match self.values.get(key) {
+            Some((_, Some(expiry))) if *expiry <= Instant::now() => {
+                self.values.remove(key);
+                None
+            }
+            entry => entry.map(|(value, _)| value.clone()),
+        }
+    }
+
+    fn eval(&mut self, script: &str, keys: &[String], args: &[String]) -> i64 {
+        let ttl = |ms: &String| Some(Instant::now() + Duration::from_millis(ms.parse().unwrap()));
+        if script.contains("'NX'") {
+            if self.get(&keys[0]).is_some() {
+                return 0;
+            }
+            self.values
+                .insert(keys[0].clone(), (args[0].clone(), ttl(&args[1])));
+            let fence = self.get(&keys[1]).map_or(0, |n| n.parse().unwrap()) + 1;
+            self.values
+                .insert(keys[1].clone(), (fence.to_string(), None));
+            return fence;
+        }
+        if self.get(&keys[0]).as_ref() != Some(&args[0]) {
+            return 0;
+        }
+        if script.contains("PEXPIRE") {
+            self.values.get_mut(&keys[0]).unwrap().1 = ttl(&args[1]);
+        } else {
+            self.values.remove(&keys[0]);
+        }
+        1
+    }
+}
+
+fn read_command(reader: &mut impl BufRead) -> Option<Vec<String>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).ok()? == 0 {
+        return None;
+    }
+    let count: usize = line.trim_end().strip_prefix('*')?.parse().ok()?;
+    (0..count)
+        .map(|_| {
+            let mut line = String::new();
+            reader.read_line(&mut line).ok()?;
+            let len: usize = line.trim_end().strip_prefix('$')?.parse().ok()?;
+            let mut data = vec![0; len + 2];
+            reader.read_exact(&mut data).ok()?;
+            data.truncate(len);
+            String::from_utf8(data).ok()
+        })
+        .collect()
+}
+
+fn serve(stream: TcpStream, state: Arc<Mutex<FakeRedis>>, password: Option<&str>) {
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+    let mut authenticated = password.is_none();
+    while let Some(command) = read_command(&mut reader) {
+        let reply = match command[0].as_str() {
+            "AUTH" if Some(command.last().unwrap().as_str()) == password => {
+                authenticated = true;
+                "+OK\r\n".to_string()
+            }
+            "AUTH" => "-WRONGPASS invalid password\r\n".to_string(),
+            _ if !authenticated => "-NOAUTH Authentication required.\r\n".to_string(),
+            "SELECT" => "+OK\r\n".to_string(),
+            "EVAL" => {
+                let numkeys: usize = command[2].parse().unwrap();
+                let (keys, args) = command[3..].split_at(numkeys);
+                let result = state.lock().unwrap().eval(&command[1], keys, args);
+                format!(":{}\r\n", result)
+            }
+            other => format!("-ERR unknown command '{}'\r\n", other),
+        };
+        if writer.write_all(reply.as_bytes()).is_err() {
+            return;
+        }
+    }
+}
+
+/// Start a fake server, returning its address and its keys
+fn start_server(password: Option<&'static str>) -> (String, Arc<Mutex<FakeRedis>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let state = Arc::new(Mutex::new(FakeRedis::default()));
+    let shared = Arc::clone(&state);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let state = Arc::clone(&shared);
+            thread::spawn(move || serve(stream, state, password));
+        }
+    });
+    (addr, state)
+}
+
+#[test]
+fn test_redis_lock_excludes_until_dropped() {
+    let (addr, state) = start_server(None);
+    let service = RedisLockService::new(addr.as_str());
+
+    let held = FileLock::acquire_remote(&service, "jobs/report", LockStrategy::NoWait).unwrap();
+    assert!(state.lock().unwrap().get("mutx:lock:jobs/report").is_some());
+    let result = FileLock::acquire_remote(&service, "jobs/report", LockStrategy::NoWait);
+    assert!(matches!(result, Err(MutxError::LockWouldBlock { .. })));
+    assert!(FileLock::acquire_remote(&service, "jobs/other", LockStrategy::NoWait).is_ok());
+
+    drop(held);
+    assert!(state.lock().unwrap().get("mutx:lock:jobs/report").is_none());
+    assert!(FileLock::acquire_remote(&service, "jobs/report", LockStrategy::NoWait).is_ok());
+}
+
+#[test]
+fn test_redis_tokens_increase() {
+    let (addr, _) = start_server(None);
+    let service = RedisLockService::new(addr.as_str()).with_prefix("app:");
+
+    let first = service.try_acquire("key").unwrap().unwrap().token();
+    let second = service.try_acquire("key").unwrap().unwrap().token();
+    assert!(second > first);
+}
+
+#[test]
+fn test_redis_lease_refreshed_while_held() {
+    let (addr, _) = start_server(None);
+    let service = RedisLockService::new(addr.as_str()).with_ttl(Duration::from_millis(300));
+
+    let _held = FileLock::acquire_remote(&service, "key", LockStrategy::NoWait).unwrap();
+    thread::sleep(Duration::from_millis(1000));
+    let result = FileLock::acquire_remote(&service, "key", LockStrategy::NoWait);
+    assert!(matches!(result, Err(MutxError::LockWouldBlock { .. })));
+}
+
+#[test]
+fn test_write_under_lost_lease_is_not_committed() {
+    let (addr, state) = start_server(None);
+    let service = RedisLockService::new(addr.as_str()).with_ttl(Duration::from_millis(300));
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("output.txt");
+
+    let lock = FileLock::acquire_remote(&service, "key", LockStrategy::NoWait).unwrap();
+    let loss = lock.lease_loss().unwrap();
+    let mut writer = mutx::AtomicWriter::new(&output, mutx::WriteMode::InMemory)
+        .unwrap()
+        .with_lease_loss(lock.lease_loss());
+    writer.write_all(b"stale").unwrap();
+
+    // The key expired during a pause and another holder took it
+    state
+        .lock()
+        .unwrap()
+        .values
+        .insert("mutx:lock:key".to_string(), ("successor".to_string(), None));
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !loss.is_lost() && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(20));
+    }
+    assert!(loss.is_lost());
+
+    let err = writer.commit().unwrap_err();
+    assert!(matches!(err, MutxError::LeaseLost { .. }), "{}", err);
+    assert!(!output.exists());
+}
+
+#[test]
+fn test_redis_abandoned_lease_expires() {
+    let (addr, state) = start_server(None);
+    let service = RedisLockService::new(addr.as_str());
+    // A holder that crashed 200ms before its lease ran out
+    state.lock().unwrap().values.insert(
+        "mutx:lock:key".to_string(),
+        (
+            "crashed".to_string(),
+            Some(Instant::now() + Duration::from_millis(200)),
+        ),
+    );
+
+    let strategy = LockStrategy::Timeout(mutx::TimeoutConfig::new(Duration::from_secs(5)));
+    let lock = FileLock::acquire_remote(&service, "key", strategy).unwrap();
+    assert!(lock.stats().retries > 0);
+}
+
+#[test]
+fn test_redis_url_authenticates() {
+    let (addr, _) = start_server(Some("s3cret"));
+
+    let service = RedisLockService::from_url(&format!("redis://:s3cret@{}/1", addr)).unwrap();
+    assert!(service.try_acquire("key").unwrap().is_some());
+
+    let service = RedisLockService::new(addr.as_str());
+    assert!(service.try_acquire("key").is_err());
+}
+
+#[test]
+fn test_cli_takes_redis_password_from_environment() {
+    let (addr, _) = start_server(Some("s3cret"));
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("output.txt");
+    let write = |server: String| {
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
+        cmd.arg(&output)
+            .args(["--lock-backend", "remote", "--lock-server", &server])
+            .write_stdin("written");
+        cmd
+    };
+
+    write(format!("redis://{}", addr))
+        .env("REDISCLI_AUTH", "s3cret")
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&output).unwrap(), "written");
+
+    let assert = write(format!("redis://:wrong@{}/x", addr))
+        .env_remove("REDISCLI_AUTH")
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(stderr.contains("Invalid Redis URL"), "{}", stderr);
+    assert!(!stderr.contains("wrong"), "{}", stderr);
+}
+
+#[test]
+fn test_cli_write_with_redis_server() {
+    let (addr, _) = start_server(None);
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("output.txt");
+    let server = format!("redis://{}", addr);
+
+    let held = FileLock::acquire_remote(
+        &RedisLockService::new(addr.as_str()),
+        "shared-key",
+        LockStrategy::NoWait,
+    )
+    .unwrap();
+    let write = |content: &str| {
+        Command::new(env!("CARGO_BIN_EXE_mutx"))
+            .arg(&output)
+            .args(["--lock-backend", "remote", "--lock-server", &server])
+            .args(["--lock-key", "shared-key", "--no-wait"])
+            .write_stdin(content)
+            .assert()
+    };
+
+    write("blocked").code(2);
+    assert!(!output.exists());
+
+    drop(held);
+    write("written").success();
+    assert_eq!(fs::read_to_string(&output).unwrap(), "written");
+}
@@ -0,0 +1,83 @@
+use assert_cmd::Command;
+use mutx::{AtomicWriter, MutxError, WriteMode};
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn make_readonly(path: &Path) {
+    let mut permissions = fs::metadata(path).unwrap().permissions();
+    permissions.set_readonly(true);
+    fs::set_permissions(path, permissions).unwrap();
+}
+
+#[test]
+fn test_readonly_target_is_replaced_by_default() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("locked.conf");
+    fs::write(&output, "old").unwrap();
+    make_readonly(&output);
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .arg("-v")
+        .write_stdin("new")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Target is read-only"));
+
+    assert_eq!(fs::read_to_string(&output).unwrap(), "new");
+}
+
+#[test]
+fn test_respect_readonly_refuses() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("locked.conf");
+    fs::write(&output, "old").unwrap();
+    make_readonly(&output);
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .arg("--respect-readonly")
+        .write_stdin("new")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("read-only"));
+
+    assert_eq!(fs::read_to_string(&output).unwrap(), "old");
+}
+
+#[test]
+fn test_respect_readonly_allows_writable_and_new_files() {
+    let temp = TempDir::new().unwrap();
+    let existing = temp.path().join("existing.conf");
+    fs::write(&existing, "old").unwrap();
+
+    for output in [existing, temp.path().join("new.conf")] {
+        Command::new(env!("CARGO_BIN_EXE_mutx"))
+            .arg(&output)
+            .arg("--respect-readonly")
+            .write_stdin("new")
+            .assert()
+            .success();
+        assert_eq!(fs::read_to_string(&output).unwrap(), "new");
+    }
+}
+
+#[test]
+fn test_writer_checks_at_commit() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("locked.conf");
+    fs::write(&target, "old").unwrap();
+
+    let mut writer = AtomicWriter::new(&target, WriteMode::InMemory)
+        .unwrap()
+        .with_respect_readonly(true);
+    writer.write_all(b"new").unwrap();
+    // Made read-only after the writer was set up
+    make_readonly(&target);
+
+    assert!(matches!(writer.commit(), Err(MutxError::TargetReadOnly(_))));
+    assert_eq!(fs::read_to_string(&target).unwrap(), "old");
+}
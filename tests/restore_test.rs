@@ -0,0 +1,157 @@
+use assert_cmd::Command;
+use mutx::{find_latest_backup, restore_backup, RestoreConfig};
+use std::fs;
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+
+fn config(target: &std::path::Path, backup: &std::path::Path) -> RestoreConfig {
+    RestoreConfig {
+        target: target.to_path_buf(),
+        backup: backup.to_path_buf(),
+        backup_current: true,
+        suffix: ".mutx.backup".parse().unwrap(),
+        directory: None,
+        timestamp: true,
+    }
+}
+
+#[test]
+fn test_restore_captures_current_state() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("config.txt");
+    let backup = temp.path().join("config.txt.mutx.backup");
+    fs::write(&target, "current").unwrap();
+    fs::write(&backup, "old").unwrap();
+
+    let report = restore_backup(&config(&target, &backup)).unwrap();
+
+    assert_eq!(fs::read_to_string(&target).unwrap(), "old");
+    let previous = report.previous.expect("current state should be captured");
+    assert_eq!(fs::read_to_string(&previous).unwrap(), "current");
+    assert_eq!(fs::read_to_string(&backup).unwrap(), "old");
+}
+
+#[test]
+fn test_restore_without_backup_current() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("config.txt");
+    let backup = temp.path().join("config.txt.mutx.backup");
+    fs::write(&target, "current").unwrap();
+    fs::write(&backup, "old").unwrap();
+
+    let mut config = config(&target, &backup);
+    config.backup_current = false;
+    let report = restore_backup(&config).unwrap();
+
+    assert_eq!(report.previous, None);
+    assert_eq!(fs::read_to_string(&target).unwrap(), "old");
+    assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 2);
+}
+
+#[test]
+fn test_restore_into_missing_target() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("config.txt");
+    let backup = temp.path().join("config.txt.mutx.backup");
+    fs::write(&backup, "old").unwrap();
+
+    let report = restore_backup(&config(&target, &backup)).unwrap();
+    assert_eq!(report.previous, None);
+    assert_eq!(fs::read_to_string(&target).unwrap(), "old");
+}
+
+#[test]
+fn test_restore_same_name_capture_keeps_both_versions() {
+    // Capturing with the restored backup's own name must not lose its contents
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("config.txt");
+    let backup = temp.path().join("config.txt.mutx.backup");
+    fs::write(&target, "current").unwrap();
+    fs::write(&backup, "old").unwrap();
+
+    let mut config = config(&target, &backup);
+    config.timestamp = false;
+    restore_backup(&config).unwrap();
+
+    assert_eq!(fs::read_to_string(&target).unwrap(), "old");
+    assert_eq!(fs::read_to_string(&backup).unwrap(), "current");
+}
+
+#[test]
+fn test_restore_missing_backup_leaves_target() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("config.txt");
+    fs::write(&target, "current").unwrap();
+
+    let result = restore_backup(&config(&target, &temp.path().join("nope.mutx.backup")));
+    assert!(result.is_err());
+    assert_eq!(fs::read_to_string(&target).unwrap(), "current");
+    assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn test_find_latest_backup() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("config.txt");
+    let older = temp.path().join("config.txt.20240101_000000.mutx.backup");
+    let newer = temp.path().join("config.txt.20240102_000000.mutx.backup");
+    fs::write(&older, "1").unwrap();
+    fs::write(&newer, "2").unwrap();
+    fs::write(temp.path().join("other.txt.mutx.backup"), "x").unwrap();
+
+    let now = SystemTime::now();
+    filetime::set_file_mtime(&older, (now - Duration::from_secs(60)).into()).unwrap();
+    filetime::set_file_mtime(&newer, now.into()).unwrap();
+
+    assert_eq!(
+        find_latest_backup(&target, ".mutx.backup", None).unwrap(),
+        Some(newer)
+    );
+    assert_eq!(
+        find_latest_backup(&temp.path().join("missing.txt"), ".mutx.backup", None).unwrap(),
+        None
+    );
+}
+
+#[test]
+fn test_cli_restore_newest_backup() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("config.txt");
+    fs::write(&target, "v1").unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&target)
+        .arg("--backup")
+        .write_stdin("v2")
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&target).unwrap(), "v2");
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg("restore")
+        .arg(&target)
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&target).unwrap(), "v1");
+
+    // The replaced state was captured alongside the original backup
+    let captured: Vec<String> = fs::read_dir(temp.path())
+        .unwrap()
+        .map(|e| fs::read_to_string(e.unwrap().path()).unwrap())
+        .collect();
+    assert!(captured.iter().any(|c| c == "v2"));
+}
+
+#[test]
+fn test_cli_restore_without_backups_fails() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("config.txt");
+    fs::write(&target, "v1").unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg("restore")
+        .arg(&target)
+        .assert()
+        .failure();
+    assert_eq!(fs::read_to_string(&target).unwrap(), "v1");
+}
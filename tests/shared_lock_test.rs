@@ -0,0 +1,89 @@
+use assert_cmd::Command;
+use mutx::lock::derive_lock_path;
+use mutx::{FileLock, LockBackend, LockStrategy, MutxError};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_shared_locks_coexist() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("config.lock");
+
+    let first = FileLock::acquire(&lock_path, LockStrategy::NoWait.shared()).unwrap();
+    let second = FileLock::acquire(&lock_path, LockStrategy::NoWait.shared()).unwrap();
+    assert!(first.is_shared() && second.is_shared());
+}
+
+#[test]
+fn test_shared_and_exclusive_exclude_each_other() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("config.lock");
+
+    let reader = FileLock::acquire(&lock_path, LockStrategy::NoWait.shared()).unwrap();
+    assert!(matches!(
+        FileLock::acquire(&lock_path, LockStrategy::NoWait),
+        Err(MutxError::LockWouldBlock { .. })
+    ));
+    drop(reader);
+
+    let writer = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+    assert!(!writer.is_shared());
+    assert!(matches!(
+        FileLock::acquire(&lock_path, LockStrategy::NoWait.shared()),
+        Err(MutxError::LockWouldBlock { .. })
+    ));
+}
+
+#[test]
+fn test_shared_requires_flock_backend() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("config.lock");
+
+    let err = FileLock::acquire_with_backend(
+        &lock_path,
+        LockStrategy::Wait.shared(),
+        LockBackend::Dotlock,
+    )
+    .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("not supported by the dotlock backend"));
+    assert!(!lock_path.exists());
+}
+
+#[test]
+fn test_cli_read_under_shared_lock() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("app.conf");
+    fs::write(&file, "port = 80\n").unwrap();
+    let lock_path = derive_lock_path(&file, false).unwrap();
+
+    // Another reader does not block
+    let _reader = FileLock::acquire(&lock_path, LockStrategy::NoWait.shared()).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg("read")
+        .arg(&file)
+        .arg("--no-wait")
+        .assert()
+        .success()
+        .stdout("port = 80\n");
+}
+
+#[test]
+fn test_cli_read_waits_for_writer() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("app.conf");
+    fs::write(&file, "port = 80\n").unwrap();
+    let lock_path = temp.path().join("app.conf.lock");
+
+    let _writer = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg("read")
+        .arg(&file)
+        .arg("--lock-file")
+        .arg(&lock_path)
+        .arg("--no-wait")
+        .assert()
+        .failure()
+        .code(2);
+}
@@ -0,0 +1,105 @@
+#![cfg(unix)]
+
+use mutx::{FileLock, LockStrategy};
+use std::fs;
+use std::io::Write;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+fn signal(child: &Child, signal: libc::c_int) {
+    assert_eq!(unsafe { libc::kill(child.id() as libc::pid_t, signal) }, 0);
+}
+
+/// Wait for `child` to exit, failing if it takes more than a few seconds
+fn exit_code(mut child: Child) -> i32 {
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            return status.code().expect("exited normally");
+        }
+        if start.elapsed() > Duration::from_secs(5) {
+            child.kill().unwrap();
+            panic!("mutx did not exit after the signal");
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn test_sigint_aborts_lock_wait() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    let lock_path = temp.path().join("out.lock");
+    let _held = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+
+    let child = Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .arg("--lock-file")
+        .arg(&lock_path)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    thread::sleep(Duration::from_millis(300));
+    signal(&child, libc::SIGINT);
+
+    assert_eq!(exit_code(child), 3);
+    assert!(!output.exists());
+}
+
+#[test]
+fn test_lock_wait_blocks_in_the_kernel() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    let lock_path = temp.path().join("out.lock");
+    let held = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+
+    let child = Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .arg("--lock-file")
+        .arg(&lock_path)
+        .arg("-vv")
+        .stdin(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    thread::sleep(Duration::from_millis(300));
+    drop(held);
+
+    // Handed the lock on release, not found by polling for it
+    let result = child.wait_with_output().unwrap();
+    assert!(result.status.success());
+    let stderr = String::from_utf8(result.stderr).unwrap();
+    assert!(stderr.contains("without retrying"), "{}", stderr);
+}
+
+#[test]
+fn test_sigterm_mid_write_discards_temp_file() {
+    let temp = TempDir::new().unwrap();
+    let locks = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    fs::write(&output, "original").unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .arg("--lock-file")
+        .arg(locks.path().join("out.lock"))
+        .arg("--stream")
+        .stdin(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    // Keep stdin open so mutx is blocked reading when the signal arrives
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(b"partial").unwrap();
+    thread::sleep(Duration::from_millis(300));
+    signal(&child, libc::SIGTERM);
+
+    assert_eq!(exit_code(child), 3);
+    drop(stdin);
+    assert_eq!(fs::read_to_string(&output).unwrap(), "original");
+    let entries: Vec<_> = fs::read_dir(temp.path()).unwrap().collect();
+    assert_eq!(entries.len(), 1, "temp file left behind: {:?}", entries);
+}
@@ -0,0 +1,175 @@
+use assert_cmd::Command;
+#[cfg(feature = "signing")]
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use std::process::Command as StdCommand;
+use tempfile::TempDir;
+
+fn mutx() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+}
+
+#[cfg(feature = "signing")]
+const PASSWORD: &str = "correct horse";
+
+/// Write a minisign key pair, encrypted with `PASSWORD`, into `dir`
+#[cfg(feature = "signing")]
+fn minisign_keys(dir: &Path) -> (std::path::PathBuf, std::path::PathBuf) {
+    let keys = minisign::KeyPair::generate_encrypted_keypair(Some(PASSWORD.to_string())).unwrap();
+    let secret = dir.join("mutx.key");
+    let public = dir.join("mutx.pub");
+    fs::write(&secret, keys.sk.to_box(None).unwrap().into_string()).unwrap();
+    fs::write(&public, keys.pk.to_box().unwrap().into_string()).unwrap();
+    (secret, public)
+}
+
+/// Generate an ed25519 SSH key in `dir`, if ssh-keygen is installed
+fn ssh_key(dir: &Path) -> Option<std::path::PathBuf> {
+    let key = dir.join("id_ed25519");
+    let status = StdCommand::new("ssh-keygen")
+        .args(["-q", "-t", "ed25519", "-N", "", "-C", "test", "-f"])
+        .arg(&key)
+        .status()
+        .ok()?;
+    status.success().then_some(key)
+}
+
+#[cfg(feature = "signing")]
+#[test]
+fn test_minisign_sign_and_verify() {
+    let temp = TempDir::new().unwrap();
+    let (secret, public) = minisign_keys(temp.path());
+    let output = temp.path().join("app.conf");
+
+    mutx()
+        .arg(&output)
+        .arg("--sign")
+        .arg(&secret)
+        .env("MUTX_SIGNING_PASSWORD", PASSWORD)
+        .write_stdin("port = 80\n")
+        .assert()
+        .success();
+
+    let signature = fs::read_to_string(temp.path().join("app.conf.minisig")).unwrap();
+    assert!(signature.contains("file:app.conf"));
+
+    mutx()
+        .arg("verify-signature")
+        .arg(&output)
+        .arg("--key")
+        .arg(&public)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("OK (minisign signature)"));
+
+    // Tampering is detected
+    fs::write(&output, "port = 8080\n").unwrap();
+    mutx()
+        .arg("verify-signature")
+        .arg(&output)
+        .arg("--key")
+        .arg(&public)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Signature verification failed"));
+}
+
+#[cfg(feature = "signing")]
+#[test]
+fn test_signature_from_other_key_is_rejected() {
+    let temp = TempDir::new().unwrap();
+    let (secret, _) = minisign_keys(temp.path());
+    let other = TempDir::new().unwrap();
+    let (_, other_public) = minisign_keys(other.path());
+    let output = temp.path().join("app.conf");
+
+    mutx()
+        .arg(&output)
+        .arg("--sign")
+        .arg(&secret)
+        .env("MUTX_SIGNING_PASSWORD", PASSWORD)
+        .write_stdin("data")
+        .assert()
+        .success();
+
+    let err = mutx::verify_signature(&output, &other_public, None).unwrap_err();
+    assert!(matches!(err, mutx::MutxError::SignatureInvalid { .. }));
+}
+
+#[cfg(feature = "signing")]
+#[test]
+fn test_wrong_password_fails_before_writing() {
+    let temp = TempDir::new().unwrap();
+    let (secret, _) = minisign_keys(temp.path());
+    let output = temp.path().join("app.conf");
+
+    mutx()
+        .arg(&output)
+        .arg("--sign")
+        .arg(&secret)
+        .env("MUTX_SIGNING_PASSWORD", "wrong")
+        .write_stdin("data")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to sign"));
+    assert!(!output.exists());
+
+    mutx()
+        .arg(&output)
+        .arg("--sign")
+        .arg(&secret)
+        .env("MUTX_SIGNING_PASSWORD", PASSWORD)
+        .write_stdin("data")
+        .assert()
+        .success();
+    assert!(temp.path().join("app.conf.minisig").exists());
+}
+
+#[test]
+fn test_ssh_sign_and_verify() {
+    let temp = TempDir::new().unwrap();
+    let Some(key) = ssh_key(temp.path()) else {
+        return;
+    };
+    let output = temp.path().join("app.conf");
+
+    mutx()
+        .arg(&output)
+        .arg("--sign")
+        .arg(&key)
+        .write_stdin("port = 80\n")
+        .assert()
+        .success();
+
+    let signature = fs::read_to_string(temp.path().join("app.conf.sig")).unwrap();
+    assert!(signature.starts_with("-----BEGIN SSH SIGNATURE-----"));
+
+    let public = temp.path().join("id_ed25519.pub");
+    assert_eq!(
+        mutx::verify_signature(&output, &public, None).unwrap(),
+        mutx::SignatureKind::Ssh
+    );
+
+    fs::write(&output, "port = 8080\n").unwrap();
+    mutx()
+        .arg("verify-signature")
+        .arg(&output)
+        .arg("--key")
+        .arg(&public)
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_missing_signature_fails() {
+    let temp = TempDir::new().unwrap();
+    let Some(key) = ssh_key(temp.path()) else {
+        return;
+    };
+    let output = temp.path().join("app.conf");
+    fs::write(&output, "unsigned").unwrap();
+
+    let err = mutx::verify_signature(&output, &key.with_extension("pub"), None).unwrap_err();
+    assert!(err.to_string().contains("not found"));
+}
@@ -0,0 +1,164 @@
+use mutx::sink::Sink;
+use mutx::{
+    AtomicWriter, CriticalSection, FileLock, Guarantee, LockStrategy, MutxError, Result, WriteMode,
+};
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+
+/// Publishes into shared memory, or fails without touching it
+#[derive(Clone, Default)]
+struct MemorySink {
+    published: Arc<Mutex<Option<Vec<u8>>>>,
+    fail: bool,
+}
+
+impl Sink for MemorySink {
+    fn destination(&self) -> String {
+        "memory".to_string()
+    }
+
+    fn publish(&mut self, content: &mut dyn Read, len: u64) -> Result<()> {
+        if self.fail {
+            return Err(MutxError::Other("memory sink is full".to_string()));
+        }
+        let mut data = Vec::new();
+        content.read_to_end(&mut data)?;
+        assert_eq!(data.len() as u64, len);
+        *self.published.lock().unwrap() = Some(data);
+        Ok(())
+    }
+
+    fn durable(&self) -> bool {
+        false
+    }
+}
+
+#[test]
+fn test_sink_receives_content_instead_of_target() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("remote.json");
+    let sink = MemorySink::default();
+
+    let mut writer = AtomicWriter::new(&target, WriteMode::Streaming)
+        .unwrap()
+        .with_sink(Box::new(sink.clone()))
+        .with_digest(mutx::DigestAlgorithm::Sha256);
+    assert!(writer.guarantees().provides(Guarantee::Atomic));
+    assert!(!writer.guarantees().provides(Guarantee::Durable));
+    writer.write_all(b"{\"v\":").unwrap();
+    writer.write_all(b"2}").unwrap();
+    let report = writer.commit().unwrap();
+
+    assert_eq!(
+        sink.published.lock().unwrap().as_deref(),
+        Some(&b"{\"v\":2}"[..])
+    );
+    assert_eq!(report.path, target);
+    assert_eq!(report.bytes_written, 7);
+    assert!(report.digest.is_some());
+    // Nothing lands on the local filesystem
+    assert!(!target.exists());
+    assert_eq!(std::fs::read_dir(temp.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_sink_writes_are_locked_and_limited() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("remote.json");
+    let lock_path = temp.path().join("remote.json.lock");
+    let sink = MemorySink::default();
+
+    let _held = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+    let mut writer = AtomicWriter::new(&target, WriteMode::Auto)
+        .unwrap()
+        .with_sink(Box::new(sink.clone()))
+        .with_lock(
+            mutx::lock::LockPlacement::Sidecar,
+            LockStrategy::NoWait,
+            CriticalSection::Full,
+        );
+    assert!(matches!(
+        writer.write_all(b"data"),
+        Err(MutxError::LockWouldBlock { .. })
+    ));
+
+    let mut writer = AtomicWriter::new(&target, WriteMode::Auto)
+        .unwrap()
+        .with_sink(Box::new(sink.clone()))
+        .with_max_size(3);
+    assert!(matches!(
+        writer.write_all(b"data"),
+        Err(MutxError::SizeLimitExceeded { .. })
+    ));
+    assert!(sink.published.lock().unwrap().is_none());
+}
+
+#[test]
+fn test_failed_publish_fails_commit() {
+    let temp = TempDir::new().unwrap();
+    let sink = MemorySink {
+        fail: true,
+        ..MemorySink::default()
+    };
+
+    let mut writer = AtomicWriter::new(&temp.path().join("remote.json"), WriteMode::Auto)
+        .unwrap()
+        .with_sink(Box::new(sink));
+    writer.write_all(b"data").unwrap();
+    assert!(writer.commit().is_err());
+}
+
+#[cfg(feature = "fd-sink")]
+#[test]
+fn test_fd_sink_frames_content() {
+    use mutx::sink::FdSink;
+
+    let mut out = Vec::new();
+    let mut sink = FdSink::new(&mut out, "buffer");
+    sink.publish(&mut &b"hello\n"[..], 6).unwrap();
+    sink.publish(&mut &b"bye"[..], 3).unwrap();
+    assert_eq!(sink.destination(), "buffer");
+
+    let expected = format!(
+        "mutx-publish 1 6\nhello\nmutx-commit sha256:{}\n\
+         mutx-publish 1 3\nbyemutx-commit sha256:{}\n",
+        "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03",
+        "b49f425a7e1f9cff3856329ada223f2f9d368f15a00cf48df16ca95986137fe8"
+    );
+    assert_eq!(String::from_utf8(out).unwrap(), expected);
+}
+
+#[cfg(feature = "fd-sink")]
+#[test]
+fn test_fd_sink_never_commits_short_content() {
+    use mutx::sink::FdSink;
+
+    let mut out = Vec::new();
+    let mut sink = FdSink::new(&mut out, "buffer");
+    assert!(sink.publish(&mut &b"hel"[..], 6).is_err());
+    assert!(!String::from_utf8(out).unwrap().contains("mutx-commit"));
+}
+
+#[cfg(all(feature = "fd-sink", unix))]
+#[test]
+fn test_cli_sink_fd() {
+    use std::process::Command;
+
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("remote.json");
+    let received = temp.path().join("received");
+
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg("printf data | \"$0\" \"$1\" --lock-beside --sink-fd 3 3>\"$2\"")
+        .arg(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .arg(&received)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let frame = std::fs::read_to_string(&received).unwrap();
+    assert!(frame.starts_with("mutx-publish 1 4\ndatamutx-commit sha256:"));
+    assert!(!output.exists());
+}
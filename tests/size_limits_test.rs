@@ -0,0 +1,157 @@
+use assert_cmd::Command;
+use mutx::housekeep::{clean_backups, AgeSource, CleanBackupConfig};
+use mutx::utils::{format_size, parse_size};
+use mutx::{AtomicWriter, MutxError, WriteMode};
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_parse_size_forms() {
+    assert_eq!(parse_size("500M").unwrap(), 500 << 20);
+    assert_eq!(parse_size("500MiB").unwrap(), 500 << 20);
+    assert_eq!(format_size(parse_size("2G").unwrap()), "2G");
+}
+
+#[test]
+fn test_max_size_stops_write() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    fs::write(&output, "old").unwrap();
+
+    let mut writer = AtomicWriter::new(&output, WriteMode::Streaming)
+        .unwrap()
+        .with_max_size(4);
+    writer.write_all(b"1234").unwrap();
+    assert!(matches!(
+        writer.write_all(b"5"),
+        Err(MutxError::SizeLimitExceeded { limit: 4, .. })
+    ));
+    drop(writer);
+
+    assert_eq!(fs::read_to_string(&output).unwrap(), "old");
+    assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn test_max_size_cli() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--max-size", "1K"])
+        .write_stdin(vec![b'x'; 2048])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("exceeds the size limit of 1K"));
+    assert!(!output.exists());
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--max-size", "2KiB"])
+        .write_stdin(vec![b'x'; 2048])
+        .assert()
+        .success();
+    assert_eq!(fs::metadata(&output).unwrap().len(), 2048);
+}
+
+#[test]
+fn test_invalid_size_rejected_by_cli() {
+    let temp = TempDir::new().unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(temp.path().join("out.txt"))
+        .args(["--max-size", "10Q"])
+        .write_stdin("data")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid size '10Q'"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_min_free_refuses_commit() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    fs::write(&output, "old").unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--min-free", "1000000T"])
+        .write_stdin("new")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("below the minimum of 1000000T"));
+    assert_eq!(fs::read_to_string(&output).unwrap(), "old");
+    assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 1);
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--min-free", "1K"])
+        .write_stdin("new")
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&output).unwrap(), "new");
+}
+
+#[test]
+fn test_max_total_size_removes_oldest_backups() {
+    let temp = TempDir::new().unwrap();
+    let dir = temp.path();
+    for day in 1..=4 {
+        fs::write(
+            dir.join(format!("app.conf.2024010{}_120000.mutx.backup", day)),
+            vec![b'x'; 1024],
+        )
+        .unwrap();
+    }
+    // The latest slot counts toward the total but is never removed
+    fs::write(dir.join("app.conf.mutx.backup"), vec![b'x'; 1024]).unwrap();
+
+    let config = CleanBackupConfig {
+        dir: dir.to_path_buf(),
+        recursive: false,
+        older_than: None,
+        keep_newest: None,
+        dry_run: false,
+        suffix: Default::default(),
+        age_source: AgeSource::Name,
+        max_total_size: Some(3 * 1024),
+    };
+    let mut cleaned = clean_backups(&config).unwrap();
+    cleaned.sort();
+
+    assert_eq!(
+        cleaned,
+        vec![
+            dir.join("app.conf.20240101_120000.mutx.backup"),
+            dir.join("app.conf.20240102_120000.mutx.backup"),
+        ]
+    );
+    assert!(dir.join("app.conf.mutx.backup").exists());
+}
+
+#[test]
+fn test_max_total_size_cli() {
+    let temp = TempDir::new().unwrap();
+    for day in 1..=3 {
+        fs::write(
+            temp.path()
+                .join(format!("app.conf.2024010{}_120000.mutx.backup", day)),
+            vec![b'x'; 1024],
+        )
+        .unwrap();
+    }
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["housekeep", "backups", "--max-total-size", "2K"])
+        .arg(temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Cleaned 1 backup file(s)"));
+    assert!(!temp
+        .path()
+        .join("app.conf.20240101_120000.mutx.backup")
+        .exists());
+}
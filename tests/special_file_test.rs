@@ -0,0 +1,162 @@
+#![cfg(unix)]
+
+use assert_cmd::Command;
+use mutx::write::is_special_file;
+use mutx::{AtomicWriter, Guarantee, MutxError, WriteMode};
+use predicates::prelude::*;
+use std::ffi::CString;
+use std::fs;
+use std::io::Read;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::thread;
+use tempfile::TempDir;
+
+fn mkfifo(dir: &Path) -> PathBuf {
+    let path = dir.join("pipe");
+    let c_path = CString::new(path.as_os_str().as_bytes()).unwrap();
+    assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o644) }, 0);
+    path
+}
+
+/// Read everything written to the FIFO at `path` on another thread
+fn drain(path: &Path) -> thread::JoinHandle<String> {
+    let path = path.to_path_buf();
+    thread::spawn(move || {
+        let mut content = String::new();
+        fs::File::open(path)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        content
+    })
+}
+
+#[test]
+fn test_detects_special_files() {
+    let temp = TempDir::new().unwrap();
+    let regular = temp.path().join("file.txt");
+    fs::write(&regular, "x").unwrap();
+
+    assert!(is_special_file(&mkfifo(temp.path())));
+    assert!(is_special_file(Path::new("/dev/null")));
+    assert!(!is_special_file(&regular));
+    assert!(!is_special_file(temp.path()));
+    assert!(!is_special_file(&temp.path().join("missing")));
+}
+
+#[test]
+fn test_writer_refuses_to_replace_special_file() {
+    let temp = TempDir::new().unwrap();
+    let fifo = mkfifo(temp.path());
+
+    let mut writer = AtomicWriter::new(&fifo, WriteMode::InMemory).unwrap();
+    writer.write_all(b"data").unwrap();
+    assert!(matches!(writer.commit(), Err(MutxError::SpecialFile(_))));
+
+    let metadata = fs::metadata(&fifo).unwrap();
+    assert!(!metadata.is_file());
+}
+
+#[test]
+fn test_writer_passes_through_to_fifo() {
+    let temp = TempDir::new().unwrap();
+    let fifo = mkfifo(temp.path());
+    let reader = drain(&fifo);
+
+    let mut writer = AtomicWriter::new(&fifo, WriteMode::Streaming)
+        .unwrap()
+        .with_passthrough_special(true);
+    let guarantees = writer.guarantees();
+    assert!(!guarantees.provides(Guarantee::Atomic));
+    assert!(!guarantees.provides(Guarantee::Durable));
+    writer.write_all(b"hello ").unwrap();
+    writer.write_all(b"fifo").unwrap();
+    let report = writer.commit().unwrap();
+
+    assert_eq!(report.bytes_written, 10);
+    assert!(!report.guarantees.atomic);
+    assert_eq!(reader.join().unwrap(), "hello fifo");
+    assert!(is_special_file(&fifo));
+}
+
+#[test]
+fn test_passthrough_leaves_regular_files_atomic() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("out.txt");
+
+    let writer = AtomicWriter::new(&target, WriteMode::Auto)
+        .unwrap()
+        .with_passthrough_special(true);
+    assert!(writer.guarantees().provides(Guarantee::Atomic));
+    writer.commit().unwrap();
+    assert!(target.is_file());
+}
+
+#[test]
+fn test_cli_rejects_special_file_early() {
+    let temp = TempDir::new().unwrap();
+    let fifo = mkfifo(temp.path());
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&fifo)
+        .arg("--lock-file")
+        .arg(temp.path().join("out.lock"))
+        .write_stdin("data")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--passthrough-special"));
+    assert!(is_special_file(&fifo));
+}
+
+#[test]
+fn test_cli_passthrough_special() {
+    let temp = TempDir::new().unwrap();
+    let fifo = mkfifo(temp.path());
+    let reader = drain(&fifo);
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&fifo)
+        .arg("--lock-file")
+        .arg(temp.path().join("out.lock"))
+        .args(["--passthrough-special", "--json"])
+        .write_stdin("through the pipe")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""atomic":false"#));
+    assert_eq!(reader.join().unwrap(), "through the pipe");
+}
+
+#[test]
+fn test_cli_passthrough_to_dev_stdout() {
+    let temp = TempDir::new().unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg("/dev/stdout")
+        .arg("--lock-file")
+        .arg(temp.path().join("out.lock"))
+        .arg("--passthrough-special")
+        .write_stdin("to stdout")
+        .assert()
+        .success()
+        .stdout("to stdout");
+}
+
+#[test]
+fn test_cli_passthrough_special_refuses_backup_and_atomic() {
+    let temp = TempDir::new().unwrap();
+    let fifo = mkfifo(temp.path());
+
+    for extra in [["--backup", ""], ["--require", "atomic"]] {
+        Command::new(env!("CARGO_BIN_EXE_mutx"))
+            .arg(&fifo)
+            .arg("--lock-file")
+            .arg(temp.path().join("out.lock"))
+            .arg("--passthrough-special")
+            .args(extra.iter().filter(|a| !a.is_empty()))
+            .write_stdin("data")
+            .assert()
+            .failure();
+    }
+    assert!(is_special_file(&fifo));
+}
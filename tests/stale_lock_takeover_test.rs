@@ -0,0 +1,175 @@
+#![cfg(unix)]
+
+use assert_cmd::Command;
+use mutx::lock::invoker_fingerprint;
+use mutx::utils::process::hostname;
+use mutx::{FileLock, LockHolder, LockStrategy, MutxError, TimeoutConfig};
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use std::process::Command as StdCommand;
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// PID of a process that has already exited
+fn dead_pid() -> u32 {
+    let mut child = StdCommand::new("true").spawn().unwrap();
+    let pid = child.id();
+    child.wait().unwrap();
+    pid
+}
+
+/// Hold the lock at `lock_path` while it names `pid` on this host as holder,
+/// as a lock inherited by a surviving child or left on a network mount does
+fn hold_as(lock_path: &Path, pid: u32) -> FileLock {
+    hold_invoked_as(lock_path, pid, None)
+}
+
+/// [`hold_as`], recording `fingerprint` as the holder's invoker
+fn hold_invoked_as(lock_path: &Path, pid: u32, fingerprint: Option<String>) -> FileLock {
+    let lock = FileLock::acquire(lock_path, LockStrategy::NoWait).unwrap();
+    let holder = LockHolder {
+        pid,
+        hostname: hostname().unwrap(),
+        acquired_at: 0,
+        target: None,
+        command: None,
+        lease_ms: None,
+        fingerprint,
+        started_at: None,
+    };
+    fs::write(lock_path, serde_json::to_vec(&holder).unwrap()).unwrap();
+    lock
+}
+
+#[test]
+fn test_dead_holder_is_broken_only_when_asked() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+    let _stale = hold_as(&lock_path, dead_pid());
+
+    let err = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap_err();
+    assert!(matches!(err, MutxError::LockWouldBlock { .. }));
+
+    let lock = FileLock::acquire(&lock_path, LockStrategy::NoWait.break_stale()).unwrap();
+    let holder = LockHolder::read(&lock_path).unwrap().unwrap();
+    assert_eq!(holder.pid, std::process::id());
+    assert_eq!(lock.holder(), Some(&holder));
+
+    // The replacement lock is a real one
+    let err = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap_err();
+    assert!(matches!(err, MutxError::LockWouldBlock { .. }));
+    drop(lock);
+    FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+
+    let leftovers: Vec<_> = fs::read_dir(temp.path()).unwrap().collect();
+    assert_eq!(leftovers.len(), 1, "{:?}", leftovers);
+}
+
+#[test]
+fn test_dead_writer_payload_is_ignored_while_readers_hold_the_lock() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+    let holder = LockHolder {
+        pid: dead_pid(),
+        hostname: hostname().unwrap(),
+        acquired_at: 0,
+        target: None,
+        command: None,
+        lease_ms: None,
+        fingerprint: None,
+        started_at: None,
+    };
+    // Left by a writer that crashed; readers record no payload of their own
+    fs::write(&lock_path, serde_json::to_vec(&holder).unwrap()).unwrap();
+    let _reader = FileLock::acquire(&lock_path, LockStrategy::NoWait.shared()).unwrap();
+
+    let err = FileLock::acquire(&lock_path, LockStrategy::NoWait.break_stale()).unwrap_err();
+    assert!(matches!(
+        err,
+        MutxError::LockWouldBlock { holder: None, .. }
+    ));
+    let timeout = LockStrategy::Timeout(TimeoutConfig::new(Duration::from_millis(50)));
+    let err = FileLock::acquire(&lock_path, timeout).unwrap_err();
+    assert!(matches!(err, MutxError::LockTimeout { holder: None, .. }));
+}
+
+#[test]
+fn test_live_holder_is_not_broken() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+    let _held = hold_as(&lock_path, std::process::id());
+
+    let err = FileLock::acquire(&lock_path, LockStrategy::NoWait.break_stale()).unwrap_err();
+    assert!(matches!(
+        err,
+        MutxError::LockWouldBlock {
+            holder: Some(_),
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_reclaim_own_takes_over_only_earlier_runs() {
+    let temp = TempDir::new().unwrap();
+    let lock_path = temp.path().join("out.lock");
+    let strategy = LockStrategy::NoWait.reclaim_own();
+
+    // A dead holder run from elsewhere is left alone
+    let stale = hold_invoked_as(&lock_path, dead_pid(), Some("0123456789abcdef".into()));
+    let err = FileLock::acquire(&lock_path, strategy.clone()).unwrap_err();
+    assert!(matches!(err, MutxError::LockWouldBlock { .. }));
+    drop(stale);
+
+    // So is a live one invoked the same way
+    let live = hold_invoked_as(&lock_path, std::process::id(), invoker_fingerprint());
+    assert!(FileLock::acquire(&lock_path, strategy.clone()).is_err());
+    drop(live);
+
+    let _stale = hold_invoked_as(&lock_path, dead_pid(), invoker_fingerprint());
+    let lock = FileLock::acquire(&lock_path, strategy).unwrap();
+    assert_eq!(lock.holder().unwrap().pid, std::process::id());
+    assert_eq!(lock.holder().unwrap().fingerprint, invoker_fingerprint());
+}
+
+#[test]
+fn test_break_stale_strategy_nesting() {
+    let strategy = LockStrategy::Wait.shared().break_stale();
+    assert!(strategy.is_shared());
+    assert!(strategy.breaks_stale());
+    assert!(!LockStrategy::NoWait.breaks_stale());
+
+    let strategy = LockStrategy::Wait.reclaim_own().fair();
+    assert!(strategy.reclaims_own());
+    assert!(!strategy.breaks_stale());
+}
+
+#[test]
+fn test_cli_break_stale_locks() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    let lock_path = temp.path().join("out.lock");
+    let _stale = hold_as(&lock_path, dead_pid());
+
+    let write = |extra: &[&str]| {
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
+        cmd.arg(&output)
+            .arg("--lock-file")
+            .arg(&lock_path)
+            .arg("--no-wait")
+            .args(extra)
+            .write_stdin("new");
+        cmd.assert()
+    };
+
+    write(&[]).code(2);
+    // The holder recorded no fingerprint, so it is no earlier run of ours
+    write(&["--reclaim-own"]).code(2);
+    assert!(!output.exists());
+
+    write(&["--break-stale-locks"])
+        .success()
+        .stderr(predicate::str::contains("Broke stale lock"));
+    assert_eq!(fs::read_to_string(&output).unwrap(), "new");
+}
@@ -0,0 +1,152 @@
+#![cfg(unix)]
+
+use assert_cmd::Command;
+use mutx::{FileLock, LockStrategy};
+use predicates::prelude::*;
+use std::fs;
+use std::os::unix::fs::symlink;
+use tempfile::TempDir;
+
+fn mutx() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+}
+
+#[test]
+fn test_follow_symlinks_writes_through_relative_link() {
+    let temp = TempDir::new().unwrap();
+    fs::create_dir(temp.path().join("data")).unwrap();
+    let real = temp.path().join("data/real.txt");
+    fs::write(&real, "old").unwrap();
+    let link = temp.path().join("link.txt");
+    symlink("data/real.txt", &link).unwrap();
+
+    mutx()
+        .arg(&link)
+        .arg("--follow-symlinks")
+        .write_stdin("new")
+        .assert()
+        .success();
+
+    assert!(link.symlink_metadata().unwrap().file_type().is_symlink());
+    assert_eq!(fs::read_to_string(&real).unwrap(), "new");
+}
+
+#[test]
+fn test_write_through_creates_dangling_target() {
+    let temp = TempDir::new().unwrap();
+    let link = temp.path().join("link.txt");
+    symlink("later.txt", &link).unwrap();
+
+    mutx()
+        .arg(&link)
+        .arg("--write-through-symlink")
+        .write_stdin("created")
+        .assert()
+        .success();
+
+    assert!(link.symlink_metadata().unwrap().file_type().is_symlink());
+    assert_eq!(
+        fs::read_to_string(temp.path().join("later.txt")).unwrap(),
+        "created"
+    );
+}
+
+#[test]
+fn test_replace_symlink_replaces_link() {
+    let temp = TempDir::new().unwrap();
+    let real = temp.path().join("real.txt");
+    fs::write(&real, "old").unwrap();
+    let link = temp.path().join("link.txt");
+    symlink("real.txt", &link).unwrap();
+
+    mutx()
+        .arg(&link)
+        .arg("--replace-symlink")
+        .write_stdin("new")
+        .assert()
+        .success();
+
+    assert!(link.symlink_metadata().unwrap().file_type().is_file());
+    assert_eq!(fs::read_to_string(&link).unwrap(), "new");
+    assert_eq!(fs::read_to_string(&real).unwrap(), "old");
+}
+
+#[test]
+fn test_policies_conflict() {
+    let temp = TempDir::new().unwrap();
+
+    mutx()
+        .arg(temp.path().join("out.txt"))
+        .args(["--replace-symlink", "--write-through-symlink"])
+        .write_stdin("x")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_symlink_loop_is_reported() {
+    let temp = TempDir::new().unwrap();
+    symlink("b", temp.path().join("a")).unwrap();
+    symlink("a", temp.path().join("b")).unwrap();
+
+    mutx()
+        .arg(temp.path().join("a"))
+        .arg("--follow-symlinks")
+        .write_stdin("x")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Too many levels of symbolic links",
+        ));
+}
+
+#[test]
+fn test_two_links_to_one_file_share_a_dotlock() {
+    let temp = TempDir::new().unwrap();
+    let real = temp.path().join("real.txt");
+    fs::write(&real, "old").unwrap();
+    let (first, second) = (temp.path().join("a.txt"), temp.path().join("b.txt"));
+    symlink("real.txt", &first).unwrap();
+    symlink(&real, &second).unwrap();
+
+    let expected = format!(
+        "Lock acquired: {}",
+        temp.path().join("real.txt.lock").display()
+    );
+    for (link, policy) in [
+        (&first, "--follow-symlinks"),
+        (&second, "--replace-symlink"),
+    ] {
+        mutx()
+            .arg(link)
+            .args([policy, "--lock-backend", "dotlock", "-vv"])
+            .write_stdin("x")
+            .assert()
+            .success()
+            .stderr(predicate::str::contains(expected.as_str()));
+    }
+}
+
+#[test]
+fn test_two_links_to_one_file_share_a_derived_lock() {
+    let temp = TempDir::new().unwrap();
+    let real = temp.path().join("real.txt");
+    let (first, second) = (temp.path().join("a.txt"), temp.path().join("b.txt"));
+    symlink("real.txt", &first).unwrap();
+    symlink("real.txt", &second).unwrap();
+
+    let expected = mutx::derive_lock_path(&real, false).unwrap();
+    mutx::lock::ensure_lock_dir(&expected).unwrap();
+    let held = FileLock::acquire(&expected, LockStrategy::NoWait).unwrap();
+    for link in [&first, &second] {
+        mutx()
+            .arg(link)
+            .args(["--follow-symlinks", "--no-wait"])
+            .write_stdin("y")
+            .assert()
+            .failure();
+    }
+    drop(held);
+    assert!(!real.exists());
+}
@@ -93,3 +93,57 @@ fn test_lock_symlink_allowed_with_flag() {
     let result = check_lock_symlink(&symlink, true);
     assert!(result.is_ok());
 }
+
+#[test]
+#[cfg(unix)]
+fn test_backup_temp_does_not_follow_planted_symlink() {
+    use mutx::{create_backup, BackupConfig};
+    use std::os::unix::fs as unix_fs;
+
+    let temp = TempDir::new().unwrap();
+    let source = temp.path().join("data.txt");
+    let victim = temp.path().join("victim.txt");
+    fs::write(&source, b"new data").unwrap();
+    fs::write(&victim, b"untouched").unwrap();
+
+    // Plant a symlink where the backup stage file will be created
+    let backup_path = temp.path().join("data.txt.mutx.backup");
+    unix_fs::symlink(&victim, mutx::utils::temp_path_for(&backup_path)).unwrap();
+
+    let config = BackupConfig {
+        source,
+        suffix: ".mutx.backup".parse().unwrap(),
+        directory: None,
+        timestamp: false,
+        prune_on_enospc: None,
+        delta: false,
+    };
+    let created = create_backup(&config).unwrap();
+
+    assert_eq!(fs::read(&created).unwrap(), b"new data");
+    assert_eq!(fs::read(&victim).unwrap(), b"untouched");
+}
+
+#[test]
+#[cfg(unix)]
+fn test_writer_refuses_target_swapped_for_symlink() {
+    use mutx::{AtomicWriter, WriteMode};
+    use std::os::unix::fs as unix_fs;
+
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("output.txt");
+    let elsewhere = temp.path().join("elsewhere.txt");
+    fs::write(&elsewhere, b"original").unwrap();
+
+    let mut writer = AtomicWriter::new(&target, WriteMode::Auto)
+        .unwrap()
+        .with_follow_symlinks(false);
+    writer.write_all(b"data").unwrap();
+
+    // Target becomes a symlink between validation and commit
+    unix_fs::symlink(&elsewhere, &target).unwrap();
+
+    let result = writer.commit();
+    assert!(matches!(result, Err(MutxError::SymlinkNotAllowed { .. })));
+    assert_eq!(fs::read(&elsewhere).unwrap(), b"original");
+}
@@ -0,0 +1,71 @@
+#![cfg(unix)]
+
+use assert_cmd::Command;
+use mutx::systemd::Notifier;
+use std::os::unix::net::UnixDatagram;
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn recv_message(socket: &UnixDatagram) -> String {
+    let mut buf = [0u8; 1024];
+    let n = socket.recv(&mut buf).unwrap();
+    String::from_utf8_lossy(&buf[..n]).to_string()
+}
+
+#[test]
+fn test_cli_notify_systemd_sends_keepalive() {
+    let temp = TempDir::new().unwrap();
+    let socket_path = temp.path().join("notify.sock");
+    let socket = UnixDatagram::bind(&socket_path).unwrap();
+    socket
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    let output = temp.path().join("output.txt");
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
+    cmd.arg(&output)
+        .arg("--notify-systemd")
+        .env("NOTIFY_SOCKET", &socket_path)
+        .env_remove("WATCHDOG_USEC")
+        .write_stdin("data")
+        .assert()
+        .success();
+
+    let message = recv_message(&socket);
+    assert!(message.contains("EXTEND_TIMEOUT_USEC="));
+    assert!(message.contains("STATUS=mutx writing"));
+    assert!(!message.contains("WATCHDOG=1"));
+}
+
+#[test]
+fn test_cli_notify_systemd_without_socket_is_noop() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("output.txt");
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_mutx"));
+    cmd.arg(&output)
+        .arg("--notify-systemd")
+        .env_remove("NOTIFY_SOCKET")
+        .write_stdin("data")
+        .assert()
+        .success();
+
+    assert_eq!(std::fs::read_to_string(&output).unwrap(), "data");
+}
+
+#[test]
+fn test_keepalive_repeats_until_dropped() {
+    let temp = TempDir::new().unwrap();
+    let socket_path = temp.path().join("notify.sock");
+    let socket = UnixDatagram::bind(&socket_path).unwrap();
+    socket
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    let notifier = Notifier::new(socket_path.to_str().unwrap());
+    let keepalive = notifier.keepalive(Duration::from_millis(20), "testing");
+
+    assert!(recv_message(&socket).contains("STATUS=testing"));
+    assert!(recv_message(&socket).contains("EXTEND_TIMEOUT_USEC=60000"));
+    drop(keepalive);
+}
@@ -0,0 +1,128 @@
+use assert_cmd::Command;
+use mutx::{AtomicWriter, WriteMode};
+use std::fs;
+use std::time::Duration;
+use tempfile::TempDir;
+
+#[test]
+fn test_rename_retry_option_is_accepted() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["--rename-retry", "0"])
+        .arg(&output)
+        .write_stdin("data")
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&output).unwrap(), "data");
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["--rename-retry", "soon"])
+        .arg(&output)
+        .write_stdin("data")
+        .assert()
+        .failure();
+}
+
+// Only Windows refuses to replace files others have open
+#[cfg(not(windows))]
+#[test]
+fn test_open_target_is_replaced_without_retrying() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("file.txt");
+    fs::write(&target, "old").unwrap();
+    let _reader = fs::File::open(&target).unwrap();
+
+    let mut writer = AtomicWriter::new(&target, WriteMode::InMemory)
+        .unwrap()
+        .with_rename_retry(Duration::ZERO);
+    writer.write_all(b"new").unwrap();
+    writer.commit().unwrap();
+    assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+}
+
+#[cfg(windows)]
+mod windows {
+    use super::*;
+    use mutx::MutxError;
+    use predicates::prelude::*;
+    use std::fs::File;
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::path::Path;
+    use std::thread;
+
+    const FILE_SHARE_READ: u32 = 0x1;
+    const FILE_SHARE_WRITE: u32 = 0x2;
+
+    /// Open `path` as most Windows programs do, without FILE_SHARE_DELETE
+    fn open_without_delete_sharing(path: &Path) -> File {
+        fs::OpenOptions::new()
+            .read(true)
+            .share_mode(FILE_SHARE_READ | FILE_SHARE_WRITE)
+            .open(path)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_target_in_use_is_reported() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("file.txt");
+        fs::write(&target, "old").unwrap();
+        let holder = open_without_delete_sharing(&target);
+
+        let mut writer = AtomicWriter::new(&target, WriteMode::InMemory)
+            .unwrap()
+            .with_rename_retry(Duration::from_millis(200));
+        writer.write_all(b"new").unwrap();
+        let err = writer.commit().unwrap_err();
+
+        match err {
+            MutxError::TargetInUse { waited, .. } => {
+                assert!(waited >= Duration::from_millis(200))
+            }
+            other => panic!("expected TargetInUse, got {:?}", other),
+        }
+        drop(holder);
+        assert_eq!(fs::read_to_string(&target).unwrap(), "old");
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_target_released_during_retry_is_replaced() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("file.txt");
+        fs::write(&target, "old").unwrap();
+        let holder = open_without_delete_sharing(&target);
+        let release = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            drop(holder);
+        });
+
+        let mut writer = AtomicWriter::new(&target, WriteMode::InMemory)
+            .unwrap()
+            .with_rename_retry(Duration::from_secs(10));
+        writer.write_all(b"new").unwrap();
+        writer.commit().unwrap();
+        release.join().unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+    }
+
+    #[test]
+    fn test_cli_reports_target_in_use() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("file.txt");
+        fs::write(&target, "old").unwrap();
+        let _holder = open_without_delete_sharing(&target);
+
+        Command::new(env!("CARGO_BIN_EXE_mutx"))
+            .args(["--rename-retry", "0"])
+            .arg(&target)
+            .write_stdin("new")
+            .assert()
+            .code(1)
+            .stderr(predicate::str::contains(
+                "Target is in use by another process",
+            ));
+    }
+}
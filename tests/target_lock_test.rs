@@ -0,0 +1,126 @@
+use assert_cmd::Command;
+use fs2::FileExt;
+use mutx::{FileLock, LockBackend, LockStrategy, MutxError};
+use std::fs::{self, File};
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn acquire(target: &Path, strategy: LockStrategy) -> mutx::Result<FileLock> {
+    FileLock::acquire_with_backend(target, strategy, LockBackend::Target)
+}
+
+#[test]
+fn test_backend_parses_and_locks_target_itself() {
+    let backend: LockBackend = "target".parse().unwrap();
+    assert_eq!(backend, LockBackend::Target);
+    assert_eq!(backend.to_string(), "target");
+
+    let target = Path::new("/srv/data/state.json");
+    assert_eq!(
+        backend.default_lock_path(target),
+        Some(target.to_path_buf())
+    );
+}
+
+#[test]
+fn test_lock_leaves_content_alone() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("state.json");
+    fs::write(&target, "{\"n\":1}\n").unwrap();
+
+    let lock = acquire(&target, LockStrategy::NoWait).unwrap();
+    assert!(lock.holder().is_none());
+    drop(lock);
+    assert_eq!(fs::read_to_string(&target).unwrap(), "{\"n\":1}\n");
+}
+
+#[test]
+fn test_excludes_flock_on_data_file() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("state.json");
+    fs::write(&target, "old").unwrap();
+
+    // What flock(1) does to the data file
+    let other = File::open(&target).unwrap();
+    other.lock_exclusive().unwrap();
+    assert!(matches!(
+        acquire(&target, LockStrategy::NoWait),
+        Err(MutxError::LockWouldBlock { .. })
+    ));
+    FileExt::unlock(&other).unwrap();
+
+    assert!(acquire(&target, LockStrategy::NoWait).is_ok());
+}
+
+#[test]
+fn test_shared_locks_allowed_but_not_breaking_stale() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("state.json");
+    fs::write(&target, "data").unwrap();
+
+    let first = acquire(&target, LockStrategy::NoWait.shared()).unwrap();
+    let second = acquire(&target, LockStrategy::NoWait.shared()).unwrap();
+    drop((first, second));
+
+    assert!(acquire(&target, LockStrategy::NoWait.break_stale()).is_err());
+}
+
+#[test]
+fn test_waiter_relocks_replaced_target() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("state.json");
+    fs::write(&target, "old").unwrap();
+
+    let held = acquire(&target, LockStrategy::NoWait).unwrap();
+    let (tx, rx) = mpsc::channel();
+    let waiter = {
+        let target = target.clone();
+        thread::spawn(move || {
+            let lock = acquire(&target, LockStrategy::Wait).unwrap();
+            tx.send(()).unwrap();
+            thread::sleep(Duration::from_millis(300));
+            drop(lock);
+        })
+    };
+    thread::sleep(Duration::from_millis(100));
+
+    // Replace the target as a write does, then release the old file
+    let staged = temp.path().join("state.json.tmp");
+    fs::write(&staged, "new").unwrap();
+    fs::rename(&staged, &target).unwrap();
+    drop(held);
+
+    rx.recv().unwrap();
+    // The waiter holds the file now at the path, not the replaced one
+    assert!(acquire(&target, LockStrategy::NoWait).is_err());
+    waiter.join().unwrap();
+}
+
+#[test]
+fn test_cli_target_backend() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("state.json");
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--lock-backend", "target"])
+        .write_stdin("first")
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&output).unwrap(), "first");
+    // Nothing besides the output itself
+    assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 1);
+
+    let other = File::open(&output).unwrap();
+    other.lock_exclusive().unwrap();
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--lock-backend", "target", "--no-wait"])
+        .write_stdin("second")
+        .assert()
+        .code(2);
+    assert_eq!(fs::read_to_string(&output).unwrap(), "first");
+}
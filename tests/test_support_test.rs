@@ -0,0 +1,126 @@
+#![cfg(feature = "test-util")]
+
+use assert_cmd::Command;
+use mutx::test_support::{
+    assert_contents_one_of, assert_no_temp_files, hold_lock, hold_lock_for, orphaned_lock,
+    AtomicityWatcher, BackupTree,
+};
+use mutx::{find_latest_backup, AtomicWriter, FileLock, LockHolder, LockStrategy, WriteMode};
+use std::fs;
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+
+#[test]
+fn test_hold_lock_contends_with_writers() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    let lock = hold_lock(&output).unwrap();
+
+    Command::cargo_bin("mutx")
+        .unwrap()
+        .arg(&output)
+        .arg("--no-wait")
+        .write_stdin("data")
+        .assert()
+        .failure();
+    assert!(hold_lock(&output).is_err());
+
+    drop(lock);
+    assert!(hold_lock(&output).is_ok());
+}
+
+#[test]
+fn test_hold_lock_for_releases() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    let release = hold_lock_for(&output, Duration::from_millis(100)).unwrap();
+
+    assert!(hold_lock(&output).is_err());
+    release.join().unwrap();
+    assert!(hold_lock(&output).is_ok());
+}
+
+#[test]
+fn test_orphaned_lock_looks_abandoned() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    let lock_path = orphaned_lock(&output, Duration::from_secs(3600)).unwrap();
+
+    let holder = LockHolder::read(&lock_path).unwrap().unwrap();
+    assert_eq!(holder.is_alive(), Some(false));
+    let age = SystemTime::now()
+        .duration_since(fs::metadata(&lock_path).unwrap().modified().unwrap())
+        .unwrap();
+    assert!(age >= Duration::from_secs(3590));
+    assert!(FileLock::acquire(&lock_path, LockStrategy::NoWait).is_ok());
+}
+
+#[test]
+fn test_backup_tree_ages() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("config.json");
+    let tree = BackupTree::new(&target);
+    let backups = tree
+        .add_all(&[
+            Duration::from_secs(3 * 86400),
+            Duration::from_secs(60),
+            Duration::from_secs(86400),
+        ])
+        .unwrap();
+
+    assert_eq!(backups.len(), 3);
+    assert_eq!(
+        find_latest_backup(&target, ".mutx.backup", None).unwrap(),
+        Some(backups[1].clone())
+    );
+    assert_eq!(fs::read_to_string(&backups[2]).unwrap(), "backup 2");
+
+    let central = temp.path().join("backups");
+    let moved = BackupTree::new(&target)
+        .with_directory(&central)
+        .add(Duration::from_secs(10), b"old")
+        .unwrap();
+    assert_eq!(moved.parent().unwrap(), central);
+}
+
+#[test]
+fn test_atomicity_watcher_accepts_whole_writes() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    let old = vec![b'a'; 64 * 1024];
+    let new = vec![b'b'; 64 * 1024];
+
+    let watcher = AtomicityWatcher::start(&output, vec![old.clone(), new.clone()]);
+    for i in 0..20 {
+        let mut writer = AtomicWriter::new(&output, WriteMode::Auto).unwrap();
+        writer
+            .write_all(if i % 2 == 0 { &old } else { &new })
+            .unwrap();
+        writer.commit().unwrap();
+    }
+    assert!(watcher.finish() > 0);
+
+    assert_contents_one_of(&output, &[&old, &new]);
+    assert_no_temp_files(temp.path());
+}
+
+#[test]
+#[should_panic(expected = "write was not atomic")]
+fn test_atomicity_watcher_catches_torn_writes() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    fs::write(&output, "old").unwrap();
+
+    let watcher = AtomicityWatcher::start(&output, vec![b"old".to_vec(), b"new".to_vec()]);
+    fs::write(&output, "torn").unwrap();
+    std::thread::sleep(Duration::from_millis(100));
+    watcher.finish();
+}
+
+#[test]
+#[should_panic(expected = "temporary files left")]
+fn test_assert_no_temp_files_names_leftovers() {
+    let temp = TempDir::new().unwrap();
+    fs::write(temp.path().join(".out.txt.1-abc.mutx.tmp"), "").unwrap();
+    assert_no_temp_files(temp.path());
+}
@@ -0,0 +1,98 @@
+use assert_cmd::Command;
+use mutx::{AtomicWriter, MutxError, Result, Transform, TransformRegistry, WriteMode};
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+/// Uppercases ASCII, standing in for a downstream crate's transform
+struct Upper;
+
+impl Transform for Upper {
+    fn transform(&mut self, chunk: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        out.extend(chunk.iter().map(u8::to_ascii_uppercase));
+        Ok(())
+    }
+}
+
+/// Rejects any input containing a NUL byte
+struct NoNul;
+
+impl Transform for NoNul {
+    fn transform(&mut self, chunk: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        if chunk.contains(&0) {
+            return Err(MutxError::Other("NUL byte in input".to_string()));
+        }
+        out.extend_from_slice(chunk);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_custom_transform_via_registry() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("out.txt");
+
+    let mut registry = TransformRegistry::default();
+    registry.register("upper", || Box::new(Upper));
+
+    for mode in [WriteMode::Auto, WriteMode::Streaming] {
+        let mut writer = AtomicWriter::new(&target, mode)
+            .unwrap()
+            .with_transform(registry.create("upper").unwrap())
+            .with_transform(registry.create("trailing-newline").unwrap());
+        writer.write_all(b"hello ").unwrap();
+        writer.write_all(b"world").unwrap();
+        let report = writer.commit().unwrap();
+
+        assert_eq!(fs::read_to_string(&target).unwrap(), "HELLO WORLD\n");
+        assert_eq!(report.bytes_written, 12);
+    }
+}
+
+#[test]
+fn test_failing_transform_leaves_target_untouched() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("out.txt");
+    fs::write(&target, "original").unwrap();
+
+    let mut writer = AtomicWriter::new(&target, WriteMode::Streaming)
+        .unwrap()
+        .with_transform(Box::new(NoNul));
+    writer.write_all(b"fine").unwrap();
+    assert!(writer.write_all(b"bad\0").is_err());
+    drop(writer);
+
+    assert_eq!(fs::read_to_string(&target).unwrap(), "original");
+    assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn test_cli_transforms_chain_in_order() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--transform", "lf,trailing-newline"])
+        .write_stdin("a\r\nb")
+        .assert()
+        .success();
+
+    assert_eq!(fs::read_to_string(&output).unwrap(), "a\nb\n");
+}
+
+#[test]
+fn test_cli_unknown_transform_fails_before_writing() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--transform", "gzip"])
+        .write_stdin("data")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown transform 'gzip'"));
+
+    assert!(!output.exists());
+}
@@ -0,0 +1,38 @@
+use assert_cmd::Command;
+use mutx::{BackupSuffix, FileMode, ModePolicy, MutxError};
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_invalid_values_rejected_at_construction() {
+    assert!(BackupSuffix::new("").is_err());
+    assert!(matches!(
+        FileMode::new(0o10000),
+        Err(MutxError::InvalidPermissions { .. })
+    ));
+    assert!(matches!(
+        "0o999".parse::<ModePolicy>(),
+        Err(MutxError::InvalidPermissions { .. })
+    ));
+}
+
+#[test]
+fn test_invalid_config_suffix_fails_before_writing() {
+    let temp = TempDir::new().unwrap();
+    let config = temp.path().join("config.toml");
+    fs::write(&config, "backup_suffix = \"\"\n").unwrap();
+    let output = temp.path().join("out.txt");
+    fs::write(&output, "old").unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .env("MUTX_CONFIG", &config)
+        .arg(&output)
+        .arg("--backup")
+        .write_stdin("new")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Backup suffix cannot be empty"));
+
+    assert_eq!(fs::read_to_string(&output).unwrap(), "old");
+}
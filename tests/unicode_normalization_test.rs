@@ -0,0 +1,71 @@
+use filetime::{set_file_mtime, FileTime};
+use mutx::housekeep::{clean_backups, AgeSource, CleanBackupConfig};
+use mutx::{create_backup, derive_lock_path, BackupConfig};
+use std::fs;
+use tempfile::TempDir;
+
+const NFC_NAME: &str = "caf\u{e9}.txt";
+const NFD_NAME: &str = "cafe\u{301}.txt";
+
+#[test]
+fn test_lock_path_identical_for_nfc_and_nfd_names() {
+    let temp = TempDir::new().unwrap();
+
+    let nfc_lock = derive_lock_path(&temp.path().join(NFC_NAME), false).unwrap();
+    let nfd_lock = derive_lock_path(&temp.path().join(NFD_NAME), false).unwrap();
+
+    assert_eq!(nfc_lock, nfd_lock);
+    assert!(nfc_lock.to_str().unwrap().contains(NFC_NAME));
+}
+
+#[test]
+fn test_backup_name_is_nfc_for_decomposed_source() {
+    let temp = TempDir::new().unwrap();
+    let source = temp.path().join(NFD_NAME);
+    fs::write(&source, b"data").unwrap();
+
+    let config = BackupConfig {
+        source,
+        suffix: ".mutx.backup".parse().unwrap(),
+        directory: None,
+        timestamp: false,
+        prune_on_enospc: None,
+        delta: false,
+    };
+    let backup = create_backup(&config).unwrap();
+
+    assert_eq!(
+        backup.file_name().unwrap().to_str().unwrap(),
+        format!("{}.mutx.backup", NFC_NAME)
+    );
+}
+
+#[test]
+fn test_retention_groups_nfc_and_nfd_backups_together() {
+    let temp = TempDir::new().unwrap();
+    let older = temp
+        .path()
+        .join(format!("{}.20260101_000000.mutx.backup", NFD_NAME));
+    let newer = temp
+        .path()
+        .join(format!("{}.20260102_000000.mutx.backup", NFC_NAME));
+    fs::write(&older, b"old").unwrap();
+    fs::write(&newer, b"new").unwrap();
+    set_file_mtime(&older, FileTime::from_unix_time(1_000_000, 0)).unwrap();
+    set_file_mtime(&newer, FileTime::from_unix_time(2_000_000, 0)).unwrap();
+
+    let config = CleanBackupConfig {
+        dir: temp.path().to_path_buf(),
+        recursive: false,
+        older_than: None,
+        keep_newest: Some(1),
+        dry_run: false,
+        suffix: ".mutx.backup".parse().unwrap(),
+        age_source: AgeSource::default(),
+        max_total_size: None,
+    };
+    let cleaned = clean_backups(&config).unwrap();
+
+    assert_eq!(cleaned, vec![older.clone()]);
+    assert!(newer.exists());
+}
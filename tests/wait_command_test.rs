@@ -0,0 +1,70 @@
+use assert_cmd::Command;
+use mutx::{FileLock, LockStrategy};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+fn wait(file: &Path, extra: &[&str]) -> assert_cmd::assert::Assert {
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .args(["wait", "--lock-beside"])
+        .args(extra)
+        .arg(file)
+        .assert()
+}
+
+/// Hold `lock_path` for `hold`, from another thread
+fn hold_for(lock_path: &Path, hold: Duration) -> thread::JoinHandle<()> {
+    let lock = FileLock::acquire(lock_path, LockStrategy::NoWait).unwrap();
+    thread::spawn(move || {
+        thread::sleep(hold);
+        drop(lock);
+    })
+}
+
+#[test]
+fn test_free_lock_returns_at_once_and_creates_nothing() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("data.json");
+
+    wait(&file, &[]).success();
+    assert_eq!(std::fs::read_dir(temp.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_waits_for_release() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("data.json");
+    let holder = hold_for(
+        &temp.path().join("data.json.lock"),
+        Duration::from_millis(500),
+    );
+
+    let start = Instant::now();
+    wait(&file, &[]).success();
+    assert!(start.elapsed() >= Duration::from_millis(400));
+    holder.join().unwrap();
+}
+
+#[test]
+fn test_timeout_exits_2() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("data.json");
+    let _held =
+        FileLock::acquire(&temp.path().join("data.json.lock"), LockStrategy::NoWait).unwrap();
+
+    wait(&file, &["--timeout", "1s"]).code(2);
+    wait(&file, &["--timeout", "1s", "--acquire"]).code(2);
+}
+
+#[test]
+fn test_acquire_takes_and_releases_lock() {
+    let temp = TempDir::new().unwrap();
+    let file = temp.path().join("data.json");
+    let lock_path = temp.path().join("data.json.lock");
+    let holder = hold_for(&lock_path, Duration::from_millis(300));
+
+    wait(&file, &["--acquire"]).success();
+    holder.join().unwrap();
+    assert!(!FileLock::status(&lock_path).unwrap().is_held());
+}
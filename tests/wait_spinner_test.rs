@@ -0,0 +1,63 @@
+use assert_cmd::Command;
+use mutx::{FileLock, LockHolder, LockStrategy};
+use predicates::prelude::*;
+use std::thread;
+use std::time::Duration;
+use tempfile::TempDir;
+
+#[test]
+fn test_holder_records_command() {
+    let command = LockHolder::current().command.unwrap();
+    assert!(command.starts_with("wait_spinner_test"), "{command}");
+}
+
+#[test]
+fn test_no_spinner_when_stderr_is_not_a_terminal() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+    let lock_path = temp.path().join("out.lock");
+
+    let held = FileLock::acquire(&lock_path, LockStrategy::NoWait).unwrap();
+    let release = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(1500));
+        drop(held);
+    });
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .arg("--lock-file")
+        .arg(&lock_path)
+        .args(["--timeout", "10000"])
+        .write_stdin("content")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Waiting").not());
+    release.join().unwrap();
+}
+
+#[test]
+fn test_cli_accepts_no_spinner() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.txt");
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .arg("--no-spinner")
+        .write_stdin("content")
+        .assert()
+        .success();
+
+    for subcommand in ["read", "restore"] {
+        Command::new(env!("CARGO_BIN_EXE_mutx"))
+            .args([subcommand, "--no-spinner", "--help"])
+            .assert()
+            .success();
+    }
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg("read")
+        .arg(&output)
+        .arg("--no-spinner")
+        .assert()
+        .success()
+        .stdout("content");
+}
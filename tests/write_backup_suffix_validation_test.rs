@@ -1,4 +1,4 @@
-use mutx::backup::{create_backup, validate_backup_suffix, BackupConfig};
+use mutx::backup::{create_backup, validate_backup_suffix, BackupConfig, BackupSuffix};
 use mutx::MutxError;
 use std::fs;
 use tempfile::TempDir;
@@ -31,42 +31,16 @@ fn test_validate_backup_suffix_accepts_valid() {
 }
 
 #[test]
-fn test_create_backup_rejects_empty_suffix() {
-    let dir = TempDir::new().unwrap();
-    let target = dir.path().join("test.txt");
-    fs::write(&target, "original content").unwrap();
-
-    let config = BackupConfig {
-        source: target.clone(),
-        suffix: String::new(), // empty suffix
-        directory: None,
-        timestamp: false,
-    };
-
-    let result = create_backup(&config);
-    assert!(result.is_err());
-    match result.unwrap_err() {
+fn test_backup_suffix_rejects_empty() {
+    match BackupSuffix::new(String::new()).unwrap_err() {
         MutxError::Other(msg) => assert!(msg.contains("Backup suffix cannot be empty")),
         _ => panic!("Expected Other error for empty suffix"),
     }
 }
 
 #[test]
-fn test_create_backup_rejects_single_dot_suffix() {
-    let dir = TempDir::new().unwrap();
-    let target = dir.path().join("test.txt");
-    fs::write(&target, "original content").unwrap();
-
-    let config = BackupConfig {
-        source: target.clone(),
-        suffix: ".".to_string(), // single dot
-        directory: None,
-        timestamp: false,
-    };
-
-    let result = create_backup(&config);
-    assert!(result.is_err());
-    match result.unwrap_err() {
+fn test_backup_suffix_rejects_single_dot() {
+    match ".".parse::<BackupSuffix>().unwrap_err() {
         MutxError::Other(msg) => assert!(msg.contains("Backup suffix cannot be a single dot")),
         _ => panic!("Expected Other error for single dot suffix"),
     }
@@ -80,9 +54,11 @@ fn test_create_backup_accepts_valid_suffix() {
 
     let config = BackupConfig {
         source: target.clone(),
-        suffix: ".bak".to_string(),
+        suffix: ".bak".parse().unwrap(),
         directory: None,
         timestamp: false,
+        prune_on_enospc: None,
+        delta: false,
     };
 
     let result = create_backup(&config);
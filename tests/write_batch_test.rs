@@ -0,0 +1,66 @@
+use mutx::{AtomicWriter, FsyncPolicy, WriteBatch, WriteMode};
+use std::fs;
+use tempfile::TempDir;
+
+fn write_fragments(batch: &mut WriteBatch, dir: &std::path::Path, count: usize) -> Vec<bool> {
+    (0..count)
+        .map(|i| {
+            let target = dir.join(format!("fragment-{:03}.conf", i));
+            let mut writer = AtomicWriter::new(&target, WriteMode::Auto).unwrap();
+            writer
+                .write_all(format!("value = {}\n", i).as_bytes())
+                .unwrap();
+            batch.commit(writer).unwrap().guarantees.durable
+        })
+        .collect()
+}
+
+#[test]
+fn test_every_policy_writes_all_files() {
+    for policy in [FsyncPolicy::PerFile, FsyncPolicy::PerDir, FsyncPolicy::End] {
+        let temp = TempDir::new().unwrap();
+        let mut batch = WriteBatch::new(policy);
+
+        write_fragments(&mut batch, temp.path(), 50);
+        batch.finish().unwrap();
+
+        let entries: Vec<_> = fs::read_dir(temp.path()).unwrap().collect();
+        assert_eq!(entries.len(), 50, "policy {}", policy);
+        assert_eq!(
+            fs::read_to_string(temp.path().join("fragment-049.conf")).unwrap(),
+            "value = 49\n"
+        );
+    }
+}
+
+#[test]
+fn test_deferred_commits_report_not_durable() {
+    let temp = TempDir::new().unwrap();
+
+    let mut batch = WriteBatch::new(FsyncPolicy::End);
+    let durable = write_fragments(&mut batch, temp.path(), 3);
+    batch.finish().unwrap();
+    assert_eq!(durable, vec![false; 3]);
+
+    let mut batch = WriteBatch::new(FsyncPolicy::PerFile);
+    let durable = write_fragments(&mut batch, temp.path(), 3);
+    assert_eq!(durable, vec![cfg!(unix); 3]);
+}
+
+#[test]
+fn test_per_dir_policy_spans_directories() {
+    let temp = TempDir::new().unwrap();
+    let a = temp.path().join("a");
+    let b = temp.path().join("b");
+    fs::create_dir(&a).unwrap();
+    fs::create_dir(&b).unwrap();
+
+    let mut batch = WriteBatch::new(FsyncPolicy::PerDir);
+    write_fragments(&mut batch, &a, 5);
+    write_fragments(&mut batch, &b, 5);
+    write_fragments(&mut batch, &a, 2);
+    batch.finish().unwrap();
+
+    assert_eq!(fs::read_dir(&a).unwrap().count(), 5);
+    assert_eq!(fs::read_dir(&b).unwrap().count(), 5);
+}
@@ -0,0 +1,105 @@
+use mutx::write::engine::{TempStrategy, WriteStep};
+use mutx::{AtomicWriter, MutxError, WriteMode};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_unnamed_strategy_writes_target() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("output.txt");
+
+    let mut writer = AtomicWriter::new(&target, WriteMode::Streaming)
+        .unwrap()
+        .with_temp_strategy(TempStrategy::Unnamed);
+    writer.write_all(b"streamed ").unwrap();
+    writer.write_all(b"content").unwrap();
+    writer.commit().unwrap();
+
+    assert_eq!(fs::read_to_string(&target).unwrap(), "streamed content");
+    assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 1);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_unnamed_strategy_leaves_no_temp_before_commit() {
+    use mutx::write::engine::StagedFile;
+
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("output.txt");
+
+    let mut staged = StagedFile::create(&target, TempStrategy::Unnamed).unwrap();
+    staged.write_all(b"pending").unwrap();
+
+    // Filesystems without O_TMPFILE fall back to a named temp
+    if staged.temp_path().is_none() {
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 0);
+    }
+    drop(staged);
+    assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 0);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_replacement_keeps_target_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("script.sh");
+    fs::write(&target, "old").unwrap();
+    fs::set_permissions(&target, fs::Permissions::from_mode(0o750)).unwrap();
+
+    for strategy in [TempStrategy::Named, TempStrategy::Unnamed] {
+        let mut writer = AtomicWriter::new(&target, WriteMode::Auto)
+            .unwrap()
+            .with_temp_strategy(strategy);
+        writer.write_all(b"new").unwrap();
+        writer.commit().unwrap();
+
+        let mode = fs::metadata(&target).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o750);
+    }
+}
+
+#[test]
+fn test_missing_directory_reports_failed_step() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("missing").join("output.txt");
+
+    let mut writer = AtomicWriter::new(&target, WriteMode::Auto).unwrap();
+    writer.write_all(b"data").unwrap();
+    let err = writer.commit().unwrap_err();
+
+    assert!(matches!(
+        err,
+        MutxError::WriteStepFailed {
+            step: WriteStep::CreateTemp,
+            ..
+        }
+    ));
+    assert!(err.to_string().contains("creating temp file"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_commit_follows_directory_handle_not_path() {
+    let temp = TempDir::new().unwrap();
+    let dir = temp.path().join("data");
+    fs::create_dir(&dir).unwrap();
+    let target = dir.join("output.txt");
+
+    let mut writer = AtomicWriter::new(&target, WriteMode::Streaming).unwrap();
+    writer.write_all(b"staged").unwrap();
+
+    // Swap a different directory in at the original path mid-write
+    let moved = temp.path().join("moved");
+    fs::rename(&dir, &moved).unwrap();
+    fs::create_dir(&dir).unwrap();
+
+    writer.commit().unwrap();
+
+    assert_eq!(
+        fs::read_to_string(moved.join("output.txt")).unwrap(),
+        "staged"
+    );
+    assert!(!target.exists());
+}
@@ -0,0 +1,119 @@
+use assert_cmd::Command;
+use mutx::{AtomicWriter, Guarantee, MutxError, WriteMode};
+use predicates::prelude::*;
+use std::path::Path;
+use tempfile::TempDir;
+
+#[test]
+fn test_guarantee_parsing() {
+    assert_eq!("atomic".parse::<Guarantee>().unwrap(), Guarantee::Atomic);
+    assert_eq!(
+        " Exclusive".parse::<Guarantee>().unwrap(),
+        Guarantee::Exclusive
+    );
+    assert!("fast".parse::<Guarantee>().is_err());
+}
+
+#[test]
+fn test_require_reports_missing_guarantees() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("output.txt");
+
+    let writer = AtomicWriter::new(&output, WriteMode::Auto)
+        .unwrap()
+        .with_exclusive(false);
+    let guarantees = writer.guarantees();
+    assert!(guarantees.atomic);
+    assert!(!guarantees.exclusive);
+
+    assert!(guarantees.require(&[Guarantee::Atomic], &output).is_ok());
+    let err = guarantees
+        .require(&[Guarantee::Atomic, Guarantee::Exclusive], &output)
+        .unwrap_err();
+    assert!(
+        matches!(err, MutxError::GuaranteeUnavailable { ref missing, .. } if missing == "exclusive")
+    );
+}
+
+#[test]
+fn test_report_includes_guarantees() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("output.txt");
+
+    let mut writer = AtomicWriter::new(&output, WriteMode::Auto).unwrap();
+    writer.write_all(b"data").unwrap();
+    let report = writer.commit().unwrap();
+
+    assert!(report.guarantees.atomic);
+    assert!(report.guarantees.exclusive);
+    assert_eq!(report.guarantees.durable, cfg!(unix));
+}
+
+#[test]
+fn test_cli_json_report() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("output.txt");
+
+    let assert = Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .arg("--json")
+        .write_stdin("hello")
+        .assert()
+        .success();
+
+    let report: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+    assert_eq!(report["bytes_written"], 5);
+    assert_eq!(report["guarantees"]["atomic"], true);
+    assert!(report["guarantees"]["exclusive"].is_boolean());
+    assert!(report["fencing_token"].is_null());
+}
+
+#[test]
+fn test_cli_require_atomic_succeeds() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("output.txt");
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--require", "atomic"])
+        .write_stdin("data")
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_cli_require_exclusive_fails_when_degraded() {
+    let shm = Path::new("/dev/shm");
+    if !shm.is_dir() {
+        return;
+    }
+    let dir = TempDir::new_in(shm).unwrap();
+    let output = dir.path().join("shared.txt");
+    let cache = mutx::lock::get_lock_cache_dir().unwrap();
+    if mutx::lock::scope::lock_scope_warning(&cache, &output, true).is_none() {
+        return;
+    }
+
+    // Without --require the write degrades and says so
+    let assert = Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .arg("--json")
+        .env("container", "test")
+        .write_stdin("data")
+        .assert()
+        .success();
+    let report: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+    assert_eq!(report["guarantees"]["exclusive"], false);
+    std::fs::remove_file(&output).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .args(["--require", "atomic,exclusive"])
+        .env("container", "test")
+        .write_stdin("data")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("exclusive unavailable"));
+
+    assert!(!output.exists());
+}
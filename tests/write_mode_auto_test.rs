@@ -0,0 +1,98 @@
+use assert_cmd::Command;
+use mutx::{AtomicWriter, WriteMode};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_auto_is_default_mode() {
+    assert_eq!(WriteMode::default(), WriteMode::Auto);
+}
+
+#[test]
+fn test_auto_keeps_small_writes_in_memory() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("small.txt");
+
+    let mut writer = AtomicWriter::new(&target, WriteMode::Auto).unwrap();
+    writer.write_all(b"small").unwrap();
+
+    assert!(!writer.is_spilled());
+    assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 0);
+    writer.commit().unwrap();
+    assert_eq!(fs::read_to_string(&target).unwrap(), "small");
+}
+
+#[test]
+fn test_auto_spills_past_threshold() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("large.txt");
+
+    let mut writer = AtomicWriter::new(&target, WriteMode::Auto)
+        .unwrap()
+        .with_spill_threshold(16);
+    writer.write_all(b"0123456789").unwrap();
+    assert!(!writer.is_spilled());
+    writer.write_all(b"0123456789").unwrap();
+    assert!(writer.is_spilled());
+    writer.write_all(b"tail").unwrap();
+
+    let report = writer.commit().unwrap();
+    assert_eq!(report.bytes_written, 24);
+    assert_eq!(
+        fs::read_to_string(&target).unwrap(),
+        "01234567890123456789tail"
+    );
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_simple_behaves_like_auto() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("legacy.txt");
+
+    let mut writer = AtomicWriter::new(&target, WriteMode::Simple)
+        .unwrap()
+        .with_spill_threshold(4);
+    writer.write_all(b"more than four").unwrap();
+    assert!(writer.is_spilled());
+    writer.commit().unwrap();
+
+    assert_eq!(fs::read_to_string(&target).unwrap(), "more than four");
+}
+
+#[test]
+fn test_in_memory_never_spills() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("memory.txt");
+
+    let mut writer = AtomicWriter::new(&target, WriteMode::InMemory)
+        .unwrap()
+        .with_spill_threshold(4);
+    writer.write_all(b"more than four").unwrap();
+    assert!(!writer.is_spilled());
+    writer.commit().unwrap();
+
+    assert_eq!(fs::read_to_string(&target).unwrap(), "more than four");
+}
+
+#[test]
+fn test_cli_in_memory_and_stream_conflict() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("output.txt");
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .arg("--in-memory")
+        .write_stdin("data")
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&output).unwrap(), "data");
+
+    Command::new(env!("CARGO_BIN_EXE_mutx"))
+        .arg(&output)
+        .arg("--in-memory")
+        .arg("--stream")
+        .write_stdin("data")
+        .assert()
+        .failure();
+}
@@ -0,0 +1,104 @@
+use mutx::{AtomicWriter, MutxError, Result, Transform, WriteMode};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// Keeps every input chunk in a file of the scratch directory, as a
+/// transform feeding an external tool would
+struct Spool {
+    path: PathBuf,
+}
+
+impl Transform for Spool {
+    fn transform(&mut self, chunk: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        let mut spooled = fs::read(&self.path).unwrap_or_default();
+        spooled.extend_from_slice(chunk);
+        fs::write(&self.path, &spooled)?;
+        out.extend_from_slice(chunk);
+        Ok(())
+    }
+}
+
+fn entries(dir: &Path) -> usize {
+    fs::read_dir(dir).unwrap().count()
+}
+
+#[test]
+fn test_scratch_dir_removed_on_commit() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.json");
+
+    let mut writer = AtomicWriter::new(&output, WriteMode::InMemory).unwrap();
+    let scratch = writer.scratch_dir().unwrap().to_path_buf();
+    assert!(scratch.is_dir());
+    assert_eq!(scratch.parent(), Some(temp.path()));
+    assert_eq!(writer.scratch_dir().unwrap(), scratch);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&scratch).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+    }
+
+    let mut writer = writer.with_transform(Box::new(Spool {
+        path: scratch.join("input"),
+    }));
+    writer.write_all(b"{}").unwrap();
+    assert_eq!(fs::read(scratch.join("input")).unwrap(), b"{}");
+    writer.commit().unwrap();
+
+    assert!(!scratch.exists());
+    assert_eq!(fs::read(&output).unwrap(), b"{}");
+    assert_eq!(entries(temp.path()), 1);
+}
+
+#[test]
+fn test_scratch_dir_removed_on_abort() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.json");
+
+    let mut writer = AtomicWriter::new(&output, WriteMode::Streaming).unwrap();
+    let scratch = writer.scratch_dir().unwrap().to_path_buf();
+    fs::create_dir(scratch.join("nested")).unwrap();
+    fs::write(scratch.join("nested/aux"), "aux").unwrap();
+    writer.write_all(b"partial").unwrap();
+    drop(writer);
+
+    assert!(!scratch.exists());
+    assert_eq!(entries(temp.path()), 0);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_scratch_dir_removed_when_commit_fails() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.json");
+    fs::write(&output, "original").unwrap();
+    fs::set_permissions(&output, fs::Permissions::from_mode(0o444)).unwrap();
+
+    let mut writer = AtomicWriter::new(&output, WriteMode::InMemory)
+        .unwrap()
+        .with_respect_readonly(true);
+    let scratch = writer.scratch_dir().unwrap().to_path_buf();
+    writer.write_all(b"new").unwrap();
+    let result = writer.commit();
+
+    assert!(matches!(result, Err(MutxError::TargetReadOnly(_))));
+    assert!(!scratch.exists());
+    assert_eq!(fs::read(&output).unwrap(), b"original");
+}
+
+#[test]
+fn test_no_scratch_dir_unless_asked() {
+    let temp = TempDir::new().unwrap();
+    let output = temp.path().join("out.json");
+
+    let mut writer = AtomicWriter::new(&output, WriteMode::Streaming).unwrap();
+    writer.write_all(b"{}").unwrap();
+    assert!(!fs::read_dir(temp.path())
+        .unwrap()
+        .any(|e| mutx::utils::is_mutx_scratch(&e.unwrap().path())));
+    writer.commit().unwrap();
+}
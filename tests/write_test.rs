@@ -7,7 +7,7 @@ fn test_simple_write_creates_file() {
     let dir = TempDir::new().unwrap();
     let target = dir.path().join("test.txt");
 
-    let mut writer = AtomicWriter::new(&target, WriteMode::Simple).unwrap();
+    let mut writer = AtomicWriter::new(&target, WriteMode::Auto).unwrap();
     writer.write_all(b"hello world").unwrap();
     writer.commit().unwrap();
 
@@ -25,7 +25,7 @@ fn test_simple_write_atomic_on_error() {
 
     // Start write but don't commit
     {
-        let mut writer = AtomicWriter::new(&target, WriteMode::Simple).unwrap();
+        let mut writer = AtomicWriter::new(&target, WriteMode::Auto).unwrap();
         writer.write_all(b"new content").unwrap();
         // Drop without commit
     }
@@ -40,7 +40,7 @@ fn test_empty_input_creates_empty_file() {
     let dir = TempDir::new().unwrap();
     let target = dir.path().join("empty.txt");
 
-    let writer = AtomicWriter::new(&target, WriteMode::Simple).unwrap();
+    let writer = AtomicWriter::new(&target, WriteMode::Auto).unwrap();
     writer.commit().unwrap();
 
     assert!(target.exists());
@@ -0,0 +1,57 @@
+use mutx::{AtomicWriterPool, LockStrategy, WriteMode};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_repeated_writes_to_same_target() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("status.txt");
+    let mut pool = AtomicWriterPool::new(WriteMode::Auto);
+
+    for i in 0..20 {
+        let _lock = pool.lock_for(&target, LockStrategy::NoWait).unwrap();
+        let mut writer = pool.writer_for(&target).unwrap();
+        writer.write_all(format!("tick {}", i).as_bytes()).unwrap();
+        let report = writer.commit().unwrap();
+        assert_eq!(report.path, target.canonicalize().unwrap());
+    }
+
+    assert_eq!(fs::read_to_string(&target).unwrap(), "tick 19");
+    assert_eq!(pool.len(), 1);
+    // No temp files left behind
+    assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn test_pool_serves_several_targets() {
+    let temp = TempDir::new().unwrap();
+    let a = temp.path().join("a.txt");
+    let b = temp.path().join("b.txt");
+    let mut pool = AtomicWriterPool::new(WriteMode::Streaming);
+
+    for (path, content) in [(&a, "alpha"), (&b, "beta"), (&a, "alpha 2")] {
+        let mut writer = pool.writer_for(path).unwrap();
+        writer.write_all(content.as_bytes()).unwrap();
+        writer.commit().unwrap();
+    }
+
+    assert_eq!(fs::read_to_string(&a).unwrap(), "alpha 2");
+    assert_eq!(fs::read_to_string(&b).unwrap(), "beta");
+    assert_ne!(
+        pool.lock_path_for(&a).unwrap(),
+        pool.lock_path_for(&b).unwrap()
+    );
+    assert_eq!(pool.len(), 2);
+}
+
+#[test]
+fn test_lock_is_held_until_dropped() {
+    let temp = TempDir::new().unwrap();
+    let target = temp.path().join("status.txt");
+    let mut pool = AtomicWriterPool::new(WriteMode::Auto);
+
+    let lock = pool.lock_for(&target, LockStrategy::NoWait).unwrap();
+    assert!(pool.lock_for(&target, LockStrategy::NoWait).is_err());
+    drop(lock);
+    assert!(pool.lock_for(&target, LockStrategy::NoWait).is_ok());
+}